@@ -0,0 +1,130 @@
+#![allow(non_snake_case)]
+
+//Internal
+use std::time::Duration;
+use dualZoneCli::broker::message::{Message, Output};
+use dualZoneCli::output::backend::TestBackend;
+use dualZoneCli::output::renderer::Renderer;
+use dualZoneCli::user_input::structs::{Console, PriorityStatus};
+
+//External
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tokio::runtime::Runtime;
+use tokio::time::Instant;
+
+/**
+ * The screen the frames are drawn on, the size of a default terminal
+ */
+const WIDTH: u16 = 80;
+const HEIGHT: u16 = 24;
+
+/**
+ * A console the way dualZoneCli starts it without a config file
+ */
+fn console() -> Console {
+    Console::builder().status_bar(true).build()
+}
+
+fn renderer(console: &Console) -> Renderer<TestBackend> {
+    Renderer::new(TestBackend::new(WIDTH, HEIGHT), true).with_theme(console.screen_theme())
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_current_thread().enable_all().build().expect("a tokio runtime")
+}
+
+/**
+ * A fresh console for every batch, the sender runs in its own task like a module would
+ */
+fn broker(c: &mut Criterion) {
+    let runtime = runtime();
+    let mut group = c.benchmark_group("broker");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("msgs", |b| b.iter_custom(|messages| runtime.block_on(async {
+        let mut console = console();
+        let handle = console.new_sender("bench".to_string());
+        let start = Instant::now();
+        let sender = tokio::spawn(async move {
+            for index in 0..messages {
+                if handle.send(format!("throughput message {}", index)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let mut received = 0;
+        while received < messages {
+            match console.next_output().await {
+                Some(Output::Line(_)) => received += 1,
+                Some(_) => {},
+                None => panic!("the console shut down"),
+            }
+        }
+        let elapsed = start.elapsed();
+        sender.await.unwrap();
+        elapsed
+    })));
+    group.finish();
+}
+
+fn latency(c: &mut Criterion) {
+    let runtime = runtime();
+    let mut console = console();
+    let mut zones = console.zone_set();
+    let mut renderer = renderer(&console);
+    let handle = console.new_sender("latency".to_string());
+    c.bench_function("send to frame", |b| b.iter_custom(|samples| runtime.block_on(async {
+        let mut total = Duration::ZERO;
+        for index in 0..samples {
+            let start = Instant::now();
+            handle.send_with_priority(format!("latency message {}", index), PriorityStatus::Normal).await.unwrap();
+            loop {
+                match console.next_output().await {
+                    Some(Output::Line(message)) => {
+                        zones.push(message);
+                        break;
+                    },
+                    Some(_) => {},
+                    None => panic!("the console shut down"),
+                }
+            }
+            renderer.draw_zones(&zones).unwrap();
+            total += start.elapsed();
+        }
+        total
+    })));
+}
+
+/**
+ * Only draw_zones is timed, the message pushed before every frame is not
+ */
+fn frames(c: &mut Criterion, name: &str, full: bool) {
+    let console = console();
+    let mut zones = console.zone_set();
+    let mut renderer = renderer(&console);
+    c.bench_function(name, |b| b.iter_custom(|frames| {
+        let mut total = Duration::ZERO;
+        for index in 0..frames {
+            zones.push(Message::new("bench".to_string(), PriorityStatus::Normal, format!("frame {}", index)));
+            if full {
+                renderer.invalidate();
+            }
+            let start = Instant::now();
+            renderer.draw_zones(&zones).unwrap();
+            total += start.elapsed();
+        }
+        total
+    }));
+}
+
+fn frame(c: &mut Criterion) {
+    frames(c, "full frame", true);
+    frames(c, "diff frame", false);
+}
+
+//the measurements of dualZoneCli --bench (see output::bench) under criterion, so a run is compared with the last one.
+//  ~ broker is messages per second from a sender task to the output side of the broker, nothing drawn.
+//  ~ send to frame is from send until the frame with that message in it is drawn.
+//  ~ full frame redraws every cell, diff frame only what one new message changed.
+//cargo bench --bench console, everything renders into a TestBackend so no terminal is needed.
+criterion_group!(benches, broker, latency, frame);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+fn main() {
+    //the gRPC service is generated from its protobuf definition, only when it is served
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/dualzone.proto").expect("proto/dualzone.proto compiles");
+}
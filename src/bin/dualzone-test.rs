@@ -0,0 +1,69 @@
+#![allow(non_snake_case)]
+
+//Internal
+use std::path::{Path, PathBuf};
+use dualZoneCli::output::snapshot;
+use dualZoneCli::user_input::structs::Console;
+
+/**
+ * Snapshot test harness: feeds scripted message sequences into a Console and compares the rendered screen
+ * with the golden files checked in next to the scripts, see output::snapshot::run_script for the script steps.
+ *  ~ dualzone-test <script>... [--update] [--config <file>], --update writes the golden files instead of checking them.
+ *  ~ Every script gets a fresh Console (from the config file when given) and a paused clock, so they can't affect each other.
+ *  ~ A directory runs every .dzs script in it, cargo run --features snapshot --bin dualzone-test tests/snapshots checks them all.
+ * Exits with 1 when a snapshot differs or a script fails.
+ */
+pub fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let update = args.iter().any(|arg| arg == "--update");
+    let config = args.iter().position(|arg| arg == "--config").and_then(|index| args.get(index + 1)).map(PathBuf::from);
+    let mut scripts = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--update" => {},
+            "--config" => { rest.next(); },
+            path => match snapshot::scripts(Path::new(path)) {
+                Ok(found) => scripts.extend(found),
+                Err(e) => return fail(&format!("{}: {}", path, e)),
+            },
+        }
+    }
+    if scripts.is_empty() {
+        return println!("usage: dualzone-test <script or directory>... [--update] [--config <file>]");
+    }
+    let mut failed = 0;
+    for script in scripts {
+        let console = match console(config.as_deref()) {
+            Ok(console) => console,
+            Err(e) => return fail(&e),
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().start_paused(true).build().expect("a tokio runtime");
+        match runtime.block_on(snapshot::run_script(console, &script, update)) {
+            Ok(checked) => println!("ok {} ({} snapshots)", script.display(), checked),
+            Err(e) => {
+                failed += 1;
+                println!("FAILED {}: {}", script.display(), e);
+            },
+        }
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/**
+ * The console a script runs against, with the status bar like the real one
+ */
+fn console(config: Option<&Path>) -> Result<Console, String> {
+    let builder = Console::builder().status_bar(true);
+    match config {
+        Some(path) => builder.config_file(path).map(|builder| builder.build()).map_err(|e| format!("{}: {}", path.display(), e)),
+        None => Ok(builder.build()),
+    }
+}
+
+fn fail(reason: &str) {
+    println!("{}", reason);
+    std::process::exit(1);
+}
@@ -0,0 +1,165 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use crate::broker::filter::FilterMatch;
+use crate::broker::message::Message;
+
+/**
+ * What an alert rule does when a message matches it
+ *  ~ Bell rings the terminal bell, Flash draws the status bar in the error style for a moment, Desktop shows a desktop notification.
+ *  ~ Pin pins the message to the top of its zone.
+ *  ~ Script calls on_alert(sender, priority, text) of the named script, see broker::scripts::Scripts::on_alert.
+ *  ~ Webhook POSTs the message as JSON to the URL, see broker::webhook::payload. Needs the webhook feature.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertAction {
+    Bell,
+    Flash,
+    Desktop,
+    Pin,
+    Script(String),
+    Webhook(String),
+}
+
+impl AlertAction {
+    pub fn parse(word: &str) -> Result<AlertAction, String> {
+        let word = word.trim();
+        if let Some(script) = word.strip_prefix("script:") {
+            return Ok(AlertAction::Script(script.to_string()));
+        }
+        if let Some(url) = word.strip_prefix("webhook:") {
+            return match url.starts_with("http://") || url.starts_with("https://") {
+                true => Ok(AlertAction::Webhook(url.to_string())),
+                false => Err(format!("a webhook is an http:// or https:// URL, not {}", url)),
+            };
+        }
+        match word.to_ascii_lowercase().as_str() {
+            "bell" => Ok(AlertAction::Bell),
+            "flash" => Ok(AlertAction::Flash),
+            "desktop" => Ok(AlertAction::Desktop),
+            "pin" => Ok(AlertAction::Pin),
+            other => Err(format!("unknown action: {}, bell, flash, desktop, pin, script:<name> or webhook:<url>", other)),
+        }
+    }
+}
+
+impl fmt::Display for AlertAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertAction::Bell => write!(f, "bell"),
+            AlertAction::Flash => write!(f, "flash"),
+            AlertAction::Desktop => write!(f, "desktop"),
+            AlertAction::Pin => write!(f, "pin"),
+            AlertAction::Script(script) => write!(f, "script:{}", script),
+            AlertAction::Webhook(url) => write!(f, "webhook:{}", url),
+        }
+    }
+}
+
+/**
+ * Conditions joined by "and", each one written like in a /filter rule without its include or exclude, eg. sender db* and priority Warning+
+ */
+pub fn parse_conditions(text: &str) -> Result<Vec<FilterMatch>, String> {
+    text.split(" and ").map(|condition| {
+        let (kind, value) = condition.trim().split_once(char::is_whitespace).unwrap_or((condition.trim(), ""));
+        FilterMatch::parse(kind, value)
+    }).collect()
+}
+
+/**
+ * One alert rule, written the same way in /alert add and in the config file: the actions, "when" and what the message
+ * has to match, the conditions of /filter joined by "and", eg.
+ *
+ *     bell,flash when priority Warning+
+ *     pin,webhook:https://hooks.example.com/ops when sender db* and payload (?i)timeout
+ *
+ * Every condition has to match. hits counts the messages the rule fired for.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub actions: Vec<AlertAction>,
+    pub conditions: Vec<FilterMatch>,
+    pub hits: u64,
+}
+
+impl AlertRule {
+    pub fn parse(rule: &str) -> Result<AlertRule, String> {
+        let (actions, conditions) = rule.trim().split_once(" when ").ok_or("a rule is its actions, when and its conditions, eg. bell when priority Warning+")?;
+        let actions = actions.split(',').filter(|word| !word.trim().is_empty()).map(AlertAction::parse).collect::<Result<Vec<AlertAction>, String>>()?;
+        if actions.is_empty() {
+            return Err("a rule needs an action".to_string());
+        }
+        Ok(AlertRule { actions, conditions: parse_conditions(conditions)?, hits: 0 })
+    }
+
+    pub fn matches(&self, sender_name: &str, message: &Message) -> bool {
+        self.conditions.iter().all(|condition| condition.matches(sender_name, message))
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actions: Vec<String> = self.actions.iter().map(|action| action.to_string()).collect();
+        let conditions: Vec<String> = self.conditions.iter().map(|condition| condition.to_string()).collect();
+        write!(f, "{} when {}", actions.join(","), conditions.join(" and "))
+    }
+}
+
+/**
+ * The alert rules of the Console, checked for every module message once it gets through the filters, see Console::add_alert_rule.
+ *  ~ Every rule that matches fires, an action two of them share is done once.
+ *  ~ Unlike the [notify] alerts they fire whether the user is watching or not.
+ *  ~ Console and user lines never fire them.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct AlertRules {
+    rules: Vec<AlertRule>,
+}
+
+impl AlertRules {
+    pub fn add(&mut self, rule: AlertRule) {
+        self.rules.push(rule);
+    }
+
+    /**
+     * Remove the rule at index (0 based), returns it if there was one
+     */
+    pub fn remove(&mut self, index: usize) -> Option<AlertRule> {
+        if index < self.rules.len() {
+            Some(self.rules.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /**
+     * The actions of every rule the message matches, counting the hits
+     */
+    pub fn fire(&mut self, sender_name: &str, message: &Message) -> Vec<AlertAction> {
+        let mut actions: Vec<AlertAction> = Vec::new();
+        for rule in self.rules.iter_mut().filter(|rule| rule.matches(sender_name, message)) {
+            rule.hits += 1;
+            for action in &rule.actions {
+                if !actions.contains(action) {
+                    actions.push(action.clone());
+                }
+            }
+        }
+        actions
+    }
+}
@@ -0,0 +1,168 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//External
+use serde::{Serialize, Deserialize};
+
+/**
+ * Default number of audit entries kept in memory, the file (if any) keeps everything
+ */
+pub const DEFAULT_AUDIT_CAPACITY: usize = 1000;
+
+/**
+ * The administrative actions the broker records
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AuditAction {
+    Authorized,
+    Blacklisted,
+    Unblacklisted,
+    Reauthorized,
+    Revoked,
+    CapabilitiesChanged,
+    FilterAdded,
+    FilterRemoved,
+    FiltersCleared,
+    AlertAdded,
+    AlertRemoved,
+    AlertsCleared,
+    Command,
+    Authenticated,
+    AuthFailed,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditAction::Authorized => "authorized",
+            AuditAction::Blacklisted => "blacklisted",
+            AuditAction::Unblacklisted => "unblacklisted",
+            AuditAction::Reauthorized => "reauthorized",
+            AuditAction::Revoked => "revoked",
+            AuditAction::CapabilitiesChanged => "capabilities",
+            AuditAction::FilterAdded => "filter added",
+            AuditAction::FilterRemoved => "filter removed",
+            AuditAction::FiltersCleared => "filters cleared",
+            AuditAction::AlertAdded => "alert added",
+            AuditAction::AlertRemoved => "alert removed",
+            AuditAction::AlertsCleared => "alerts cleared",
+            AuditAction::Command => "command",
+            AuditAction::Authenticated => "authenticated",
+            AuditAction::AuthFailed => "auth failed",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * One recorded action, at is ms since unix epoch.
+ * actor is who did it: "console" for the program owning the Console, "user" for a command typed into the input zone,
+ * or the plaintext name of the module that ran the command. subject is what it was done to, eg. the sender name or the rule.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub at: u64,
+    pub actor: String,
+    pub action: AuditAction,
+    pub subject: String,
+}
+
+/**
+ * Append-only record of the broker's administrative actions. The newest entries are kept in a ring,
+ * and every entry is also appended to the audit file as one JSON object per line when one is set.
+ * Nothing can be removed from it, clearing the ring would defeat the point.
+ */
+#[derive(Debug)]
+pub struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+    capacity: usize,
+    total: u64,
+    file: Option<LineWriter<File>>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        AuditLog::new(DEFAULT_AUDIT_CAPACITY)
+    }
+}
+
+impl AuditLog {
+    pub fn new(capacity: usize) -> Self {
+        AuditLog { entries: VecDeque::new(), capacity, total: 0, file: None }
+    }
+
+    /**
+     * Append every entry from now on to this file too, None stops writing. The file is opened for appending, never truncated
+     */
+    pub fn set_file(&mut self, path: Option<&Path>) -> std::io::Result<()> {
+        self.file = match path {
+            Some(path) => Some(LineWriter::new(OpenOptions::new().create(true).append(true).open(path)?)),
+            None => None,
+        };
+        Ok(())
+    }
+
+    pub fn record(&mut self, actor: &str, action: AuditAction, subject: String) {
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let entry = AuditEntry { at, actor: actor.to_string(), action, subject };
+        //a failing audit file is not a reason to stop the broker, the entry is still in the ring
+        if let Some(file) = self.file.as_mut() {
+            if let Ok(line) = serde_json::to_string(&entry) {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+        self.total += 1;
+        self.entries.push_back(entry);
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /**
+     * Change how many entries the ring keeps, the oldest are dropped (they are still in the file)
+     */
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /**
+     * Number of entries ever recorded, including those no longer in the ring
+     */
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /**
+     * Copy of every entry in the ring, oldest first
+     */
+    pub fn snapshot(&self) -> Vec<AuditEntry> {
+        self.entries.iter().cloned().collect()
+    }
+
+    /**
+     * Copy of the n most recent entries, newest first
+     */
+    pub fn recent(&self, n: usize) -> Vec<AuditEntry> {
+        self.entries.iter().rev().take(n).cloned().collect()
+    }
+}
@@ -0,0 +1,94 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+
+//External
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/**
+ * Number of random bytes in a challenge
+ */
+const CHALLENGE_BYTES: usize = 32;
+
+/**
+ * Who may attach to a session, see broker::session.
+ *  ~ Token is one pre-shared secret for every client, whatever name it attaches under.
+ *  ~ Users is a password per user name, a client attaches under the name it authenticated as.
+ * The secret never goes over the socket: the session sends a random challenge and the client answers with
+ * HMAC-SHA256(secret, challenge) as lowercase hex, see proof.
+ */
+#[derive(Clone, PartialEq)]
+pub enum SessionAuth {
+    Token(String),
+    Users(HashMap<String, String>),
+}
+
+impl SessionAuth {
+    /**
+     * The secret a user answers the challenge with, None for a user that isn't known
+     */
+    fn secret(&self, user: &str) -> Option<&str> {
+        match self {
+            SessionAuth::Token(token) => Some(token),
+            SessionAuth::Users(users) => users.get(user).map(|password| password.as_str()),
+        }
+    }
+
+    /**
+     * Whether the answer to a challenge proves the user knows its secret, compared in constant time
+     */
+    pub fn verify(&self, user: &str, challenge: &str, answer: &str) -> bool {
+        let (secret, answer) = match (self.secret(user), from_hex(answer)) {
+            (Some(secret), Some(answer)) => (secret, answer),
+            _ => return false,
+        };
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(challenge.as_bytes());
+        mac.verify_slice(&answer).is_ok()
+    }
+}
+
+/**
+ * Never print the secrets, even in debug output
+ */
+impl fmt::Debug for SessionAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionAuth::Token(_) => f.write_str("SessionAuth::Token(<hidden>)"),
+            SessionAuth::Users(users) => write!(f, "SessionAuth::Users({} users, passwords hidden)", users.len()),
+        }
+    }
+}
+
+/**
+ * A fresh random challenge, as hex
+ */
+pub fn challenge() -> String {
+    let mut bytes = [0u8; CHALLENGE_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/**
+ * The answer to a challenge, what a client sends back
+ */
+pub fn proof(secret: &str, challenge: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(challenge.as_bytes());
+    mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok()).collect()
+}
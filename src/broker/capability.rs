@@ -0,0 +1,171 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use crate::user_input::structs::Console;
+use crate::broker::audit::AuditAction;
+
+//External
+use serde::{Serialize, Deserialize};
+
+/**
+ * One thing a sender may be allowed to do
+ *  ~ Print is putting anything in the output zones: messages, tables, pinned lines, (un)pinning, clipboard copies and input requests.
+ *  ~ Broadcast is routing a message to every module inbox at once (SenderHandle::broadcast).
+ *  ~ ExecuteCommands is running /commands as if the user typed them (SenderHandle::run_command), but for the ones that reach
+ *    into the host, which only the terminal runs (see Command::is_host_only).
+ *  ~ SpawnSubConsole is creating a SubConsole of its own.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Capability {
+    Print,
+    Broadcast,
+    ExecuteCommands,
+    SpawnSubConsole,
+}
+
+impl Capability {
+    pub fn parse(name: &str) -> Option<Capability> {
+        match name.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "print" | "can_print" => Some(Capability::Print),
+            "broadcast" | "can_broadcast" => Some(Capability::Broadcast),
+            "execute_commands" | "commands" | "can_execute_commands" => Some(Capability::ExecuteCommands),
+            "spawn_subconsole" | "subconsole" | "can_spawn_subconsole" => Some(Capability::SpawnSubConsole),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::Print => "print",
+            Capability::Broadcast => "broadcast",
+            Capability::ExecuteCommands => "execute_commands",
+            Capability::SpawnSubConsole => "spawn_subconsole",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * What an Authorized sender may do, on top of being Authorized. Kept in the handle state so a change applies
+ * to handles already given out, and checked both by the handle and by the broker (a handle is only a convenience,
+ * the broker is what an untrusted module cannot get around).
+ * Senders get Capabilities::default() unless told otherwise, which is printing and broadcasting but not commands or SubConsoles.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub can_print: bool,
+    pub can_broadcast: bool,
+    pub can_execute_commands: bool,
+    pub can_spawn_subconsole: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities { can_print: true, can_broadcast: true, can_execute_commands: false, can_spawn_subconsole: false }
+    }
+}
+
+impl Capabilities {
+    pub fn all() -> Self {
+        Capabilities { can_print: true, can_broadcast: true, can_execute_commands: true, can_spawn_subconsole: true }
+    }
+
+    pub fn none() -> Self {
+        Capabilities { can_print: false, can_broadcast: false, can_execute_commands: false, can_spawn_subconsole: false }
+    }
+
+    /**
+     * A list of capability names joined by commas or spaces, eg. "print,broadcast", "all" or "none"
+     */
+    pub fn parse(list: &str) -> Result<Capabilities, String> {
+        match list.trim().to_ascii_lowercase().as_str() {
+            "all" => return Ok(Capabilities::all()),
+            "none" | "" => return Ok(Capabilities::none()),
+            _ => {},
+        }
+        let mut capabilities = Capabilities::none();
+        for name in list.split([',', ' ']).filter(|name| !name.is_empty()) {
+            let capability = Capability::parse(name).ok_or(format!("unknown capability: {}", name))?;
+            capabilities = capabilities.with(capability, true);
+        }
+        Ok(capabilities)
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Print => self.can_print,
+            Capability::Broadcast => self.can_broadcast,
+            Capability::ExecuteCommands => self.can_execute_commands,
+            Capability::SpawnSubConsole => self.can_spawn_subconsole,
+        }
+    }
+
+    pub fn with(mut self, capability: Capability, allowed: bool) -> Self {
+        match capability {
+            Capability::Print => self.can_print = allowed,
+            Capability::Broadcast => self.can_broadcast = allowed,
+            Capability::ExecuteCommands => self.can_execute_commands = allowed,
+            Capability::SpawnSubConsole => self.can_spawn_subconsole = allowed,
+        }
+        self
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = [Capability::Print, Capability::Broadcast, Capability::ExecuteCommands, Capability::SpawnSubConsole]
+            .iter()
+            .filter(|capability| self.allows(**capability))
+            .map(|capability| capability.to_string())
+            .collect();
+        match names.is_empty() {
+            true => write!(f, "none"),
+            false => write!(f, "{}", names.join(",")),
+        }
+    }
+}
+
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Change what a sender may do, applies to handles already given out
+     */
+    pub fn set_capabilities(&mut self, identifier: String, capabilities: Capabilities) {
+        let id = self.generate_id(identifier.clone());
+        match self.handles.get(&id) {
+            Some(handle_state) => {
+                handle_state.lock().unwrap().capabilities = capabilities;
+                self.record_audit(AuditAction::CapabilitiesChanged, format!("{}: {}", identifier, capabilities));
+            },
+            None => {
+                self.emit_event(format!("{} has no handle", identifier));
+            }
+        }
+    }
+
+    /**
+     * Capabilities given to senders created after this call, Capabilities::default() unless changed
+     */
+    pub fn set_default_capabilities(&mut self, capabilities: Capabilities) {
+        self.default_capabilities = capabilities;
+    }
+
+    /**
+     * What a sender may do, by plaintext name, None when there is no such sender
+     */
+    pub fn capabilities_of(&self, identifier: String) -> Option<Capabilities> {
+        let id = self.generate_id(identifier);
+        self.handles.get(&id).map(|handle_state| handle_state.lock().unwrap().capabilities)
+    }
+
+    /**
+     * The broker side check, by signed id. A sender without a handle has no capabilities
+     */
+    pub(crate) fn permits(&self, id: &str, capability: Capability) -> bool {
+        self.handles.get(id).is_some_and(|handle_state| handle_state.lock().unwrap().capabilities.allows(capability))
+    }
+}
@@ -0,0 +1,131 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+
+/**
+ * The longest nick /nick takes
+ */
+pub const MAX_NICK_LEN: usize = 32;
+
+/**
+ * Names no one may take as a nick, they are the broker's own
+ */
+pub const RESERVED_NICKS: [&str; 2] = ["console", "*"];
+
+/**
+ * What /nick, /me and /msg do
+ *  ~ Nick changes the name the user chats under, or says it with none.
+ *  ~ Me says what the user does, drawn as "* alice waves".
+ *  ~ Msg says something to one user only, a chat user or a module in the phonebook, see Console::set_chat_mode.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatCommand {
+    Nick(Option<String>),
+    Me(String),
+    Msg { to: Option<String>, text: String },
+}
+
+impl ChatCommand {
+    /**
+     * /nick, /me or /msg with what came after the command's name
+     */
+    pub fn parse(name: &str, rest: &str) -> Option<ChatCommand> {
+        let rest = rest.trim();
+        match name {
+            "nick" => Some(ChatCommand::Nick(rest.split_whitespace().next().map(|nick| nick.to_string()))),
+            "me" => Some(ChatCommand::Me(rest.to_string())),
+            "msg" => {
+                let (to, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                Some(ChatCommand::Msg { to: Some(to.to_string()).filter(|to| !to.is_empty()), text: text.trim().to_string() })
+            },
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Whether a nick may be taken: up to MAX_NICK_LEN letters, digits and -_[]{}|^` without spaces, not one of RESERVED_NICKS
+ */
+pub fn valid_nick(nick: &str) -> Result<(), String> {
+    if nick.is_empty() || nick.chars().count() > MAX_NICK_LEN {
+        return Err(format!("a nick has 1 to {} characters", MAX_NICK_LEN));
+    }
+    if let Some(c) = nick.chars().find(|c| !c.is_alphanumeric() && !"-_[]{}|^`".contains(*c)) {
+        return Err(format!("a nick can't have {:?} in it", c));
+    }
+    if RESERVED_NICKS.iter().any(|reserved| reserved.eq_ignore_ascii_case(nick)) {
+        return Err(format!("{} is the console's own", nick));
+    }
+    Ok(())
+}
+
+/**
+ * The chat "personality" of the Console, for a terminal chat server of the clients attached to its session, see Console::set_chat_mode.
+ *  ~ enabled draws what the users say as "<nick> text" instead of "[client] > text". /me, /msg and /nick work either way.
+ *  ~ nicks is the name every user that said something goes by, by client (the session names the clients, "user" is the terminal).
+ *    A user without one chats under its client name.
+ * Nicks are not kept across restarts, a client takes its own again with /nick.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Chat {
+    pub enabled: bool,
+    nicks: HashMap<String, String>,
+}
+
+impl Chat {
+    /**
+     * The nick of a client, its own name until it takes one
+     */
+    pub fn nick<'a>(&'a self, client: &'a str) -> &'a str {
+        self.nicks.get(client).map_or(client, |nick| nick.as_str())
+    }
+
+    /**
+     * A client said something, so it can be sent to by its nick from now on
+     */
+    pub fn join(&mut self, client: &str) {
+        if !self.nicks.contains_key(client) {
+            self.nicks.insert(client.to_string(), client.to_string());
+        }
+    }
+
+    /**
+     * Give a client a new nick, returns the one it had. A nick another user has is refused, whatever its case
+     */
+    pub fn set_nick(&mut self, client: &str, nick: &str) -> Result<String, String> {
+        valid_nick(nick)?;
+        if self.nicks.iter().any(|(other, taken)| other != client && taken.eq_ignore_ascii_case(nick)) {
+            return Err(format!("{} is taken", nick));
+        }
+        let old = self.nick(client).to_string();
+        self.nicks.insert(client.to_string(), nick.to_string());
+        Ok(old)
+    }
+
+    /**
+     * The client that goes by a nick, if it said something yet
+     */
+    pub fn client_of(&self, nick: &str) -> Option<&str> {
+        self.nicks.iter().find(|(_, taken)| taken.eq_ignore_ascii_case(nick)).map(|(client, _)| client.as_str())
+    }
+
+    /**
+     * What a user says as it is drawn, "<nick> text" in chat mode and "[client] > text" out of it
+     */
+    pub fn say(&self, client: &str, text: &str) -> String {
+        match self.enabled {
+            true => format!("<{}> {}", self.nick(client), text),
+            false => format!("[{}] > {}", client, text),
+        }
+    }
+
+    /**
+     * What /me draws
+     */
+    pub fn action(&self, client: &str, text: &str) -> String {
+        format!("* {} {}", self.nick(client), text)
+    }
+}
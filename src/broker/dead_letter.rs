@@ -0,0 +1,185 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::broker::message::Message;
+use crate::broker::memory::{self, BufferUsage, MemoryLimit};
+use crate::user_input::structs::SenderStatus;
+use crate::broker::capability::Capability;
+
+//External
+use serde::{Serialize, Deserialize};
+
+/**
+ * Default number of rejected messages kept before the oldest are discarded
+ */
+pub const DEFAULT_DEAD_LETTER_CAPACITY: usize = 1000;
+
+/**
+ * Why a message ended up in the dead-letter queue
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RejectReason {
+    NotAuthorized(SenderStatus),
+    NotPermitted(Capability),
+    RateLimited,
+    DroppedOldest,
+    DroppedNewest,
+    Undelivered(String),
+}
+
+/**
+ * A rejected message with the reason and the time it was rejected (ms since unix epoch)
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub message: Message,
+    pub reason: RejectReason,
+    pub rejected_at: u64,
+}
+
+/**
+ * Ring buffer of rejected messages, shared between the broker and the sender handles since both can reject.
+ * How many it holds is a MemoryLimit, by count (the capacity) and optionally by bytes, evicting per its policy.
+ */
+#[derive(Debug)]
+pub struct DeadLetterQueue {
+    letters: VecDeque<DeadLetter>,
+    limit: MemoryLimit,
+    bytes: usize,
+    evicted: u64,
+    total: u64,
+}
+
+impl Default for DeadLetterQueue {
+    fn default() -> Self {
+        DeadLetterQueue::new(DEFAULT_DEAD_LETTER_CAPACITY)
+    }
+}
+
+impl DeadLetterQueue {
+    pub fn new(capacity: usize) -> Self {
+        DeadLetterQueue::with_limit(MemoryLimit::entries(capacity))
+    }
+
+    pub fn with_limit(limit: MemoryLimit) -> Self {
+        DeadLetterQueue { letters: VecDeque::new(), limit, bytes: 0, evicted: 0, total: 0 }
+    }
+
+    /**
+     * Record a rejected message, evicting per the limit once full
+     */
+    pub fn push(&mut self, message: Message, reason: RejectReason) {
+        let rejected_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        self.total += 1;
+        if self.limit.max_entries == Some(0) {
+            return;
+        }
+        self.bytes += memory::message_size(&message);
+        self.letters.push_back(DeadLetter { message, reason, rejected_at });
+        self.evict();
+    }
+
+    /**
+     * Change how many rejects are kept, trims per the eviction policy if needed
+     */
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.limit.max_entries = Some(capacity);
+        self.evict();
+    }
+
+    pub fn set_limit(&mut self, limit: MemoryLimit) {
+        self.limit = limit;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.limit.exceeded(self.letters.len(), self.bytes) {
+            let index = match self.limit.eviction.victim(self.letters.iter().map(|letter| letter.message.priority.severity())) {
+                Some(index) => index,
+                None => break,
+            };
+            if let Some(letter) = self.letters.remove(index) {
+                self.bytes -= memory::message_size(&letter.message);
+                self.evicted += 1;
+            }
+        }
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        BufferUsage { entries: self.letters.len(), bytes: self.bytes, evicted: self.evicted }
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    /**
+     * Number of rejects ever recorded, including evicted ones
+     */
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /**
+     * Copy of every letter currently held, oldest first
+     */
+    pub fn snapshot(&self) -> Vec<DeadLetter> {
+        self.letters.iter().cloned().collect()
+    }
+
+    /**
+     * Copy of the n most recent letters, newest first
+     */
+    pub fn recent(&self, n: usize) -> Vec<DeadLetter> {
+        self.letters.iter().rev().take(n).cloned().collect()
+    }
+
+    /**
+     * Remove and return the oldest letter
+     */
+    pub fn pop(&mut self) -> Option<DeadLetter> {
+        let letter = self.letters.pop_front()?;
+        self.bytes -= memory::message_size(&letter.message);
+        Some(letter)
+    }
+
+    /**
+     * Put a letter taken with pop back in front, where it was. It is not counted as a new reject
+     */
+    pub fn requeue(&mut self, letter: DeadLetter) {
+        self.bytes += memory::message_size(&letter.message);
+        self.letters.push_front(letter);
+        self.evict();
+    }
+
+    /**
+     * Remove and return every letter, oldest first
+     */
+    pub fn drain(&mut self) -> Vec<DeadLetter> {
+        self.bytes = 0;
+        self.letters.drain(..).collect()
+    }
+
+    /**
+     * Write every letter as one JSON object per line, returns how many were written
+     */
+    pub fn export(&self, path: &str) -> std::io::Result<usize> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for letter in self.letters.iter() {
+            let line = serde_json::to_string(letter).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()?;
+        Ok(self.letters.len())
+    }
+}
@@ -0,0 +1,76 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::broker::message::{self, Message};
+use crate::user_input::structs::PriorityStatus;
+
+/**
+ * The newest line of one zone, as it was first sent, and how many times in a row it came
+ */
+#[derive(Debug, Clone)]
+struct Repeat {
+    sender: String,
+    priority: PriorityStatus,
+    topic: Option<String>,
+    payload: Arc<str>,
+    slot: u64,
+    count: u64,
+}
+
+impl Repeat {
+    fn same(&self, message: &Message) -> bool {
+        self.sender == message.sender && self.priority == message.priority && self.topic == message.topic && self.payload == message.payload
+    }
+}
+
+/**
+ * Collapses a message that repeats the line before it in the same zone into that line, which then reads "text (×N)" like syslog's
+ * "last message repeated". A line repeats when sender, priority, topic and text are all the same.
+ *  ~ It works through slots: the first line of a possible run gets a slot of its own and every repeat replaces it,
+ *    so the scrollback keeps one entry too.
+ *  ~ Pinned messages, widgets, updates and anything that already has a slot are never collapsed and end a run.
+ */
+#[derive(Debug, Default)]
+pub struct Repeats {
+    last: HashMap<String, Repeat>,
+}
+
+impl Repeats {
+    /**
+     * Called for every message once its zone is known, enabled is whether its sender's repeats are collapsed
+     */
+    pub fn collapse(&mut self, message: &mut Message, enabled: bool) {
+        let zone = message.zone.clone().unwrap_or_default();
+        if !enabled || message.pinned || message.widget.is_some() || message.slot.is_some() || message.replaces.is_some() {
+            self.last.remove(&zone);
+            return;
+        }
+        if let Some(repeat) = self.last.get_mut(&zone).filter(|repeat| repeat.same(message)) {
+            repeat.count += 1;
+            message.slot = Some(repeat.slot);
+            message.payload = format!("{} (×{})", repeat.payload, repeat.count).into();
+            return;
+        }
+        let slot = message::next_id();
+        message.slot = Some(slot);
+        self.last.insert(zone, Repeat {
+            sender: message.sender.clone(),
+            priority: message.priority.clone(),
+            topic: message.topic.clone(),
+            payload: message.payload.clone(),
+            slot,
+            count: 1,
+        });
+    }
+
+    /**
+     * Forget every run, the next message starts a new line whatever it is
+     */
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+}
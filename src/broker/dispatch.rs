@@ -0,0 +1,1588 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use crate::broker::message::{self, AppEvent, BrokerEvent, ConsoleEvent, Message, Origin, Output, RenderedLine, CONSOLE_SENDER, USER_SENDER};
+use crate::broker::rate_limit::{TokenBucket, Verdict};
+use crate::broker::dead_letter::RejectReason;
+use crate::broker::routing::{self, DeliveryError, DeliveryReport, BROADCAST_TARGET};
+use crate::broker::capability::Capability;
+use crate::broker::audit::AuditAction;
+use crate::broker::hooks::Flow;
+use crate::broker::filter::FilterRule;
+use crate::broker::split::Split;
+use crate::user_input::commands::{Command, MacroCommand, ProcessCommand, RuleCommand, ScheduleCommand, SplitCommand};
+use crate::output::highlight::HighlightRule;
+use crate::user_input::structs::{Console, PriorityStatus, SenderStatus};
+use crate::output::widgets::Widget;
+use crate::output::ansi;
+use crate::output::search::SearchCommand;
+use crate::output::clipboard::CopyTarget;
+use crate::output::tabs::TabCommand;
+use crate::output::export::ExportFormat;
+use crate::user_input::keymap::ACTION_TOPIC;
+use crate::user_input::vi::EditMode;
+use crate::user_input::input::InputRequest;
+use crate::broker::distribute::BridgedMessage;
+use crate::broker::remote::{self, RemoteReply, RemoteRequest, SenderSummary};
+use crate::broker::process::ProcessInput;
+use crate::broker::shell::{self, ShellVerdict};
+use crate::broker::pager::Pager;
+use crate::broker::threads::ThreadCommand;
+use crate::broker::chat::ChatCommand;
+use crate::broker::alerts::{AlertAction, AlertRule};
+use crate::broker::webhook::{self, WebhookFormat};
+use crate::output::notify::Alert;
+use crate::user_input::dialog::PageStep;
+use crate::output::layout::Layout;
+
+//External
+use chrono::TimeZone;
+use tokio::sync::oneshot;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::Instant;
+
+/**
+ * What one broker step came up with, output for the zones or an application event queued for next_event()
+ */
+enum Step {
+    Output(Box<Output>),
+    Event,
+}
+
+impl From<Output> for Step {
+    fn from(output: Output) -> Self {
+        Step::Output(Box::new(output))
+    }
+}
+
+/**
+ * The broker half of the Console, everything received on the channel passes through here before it is rendered.
+ */
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Wait for the next thing the output zone has to do, usually a message to draw.
+     * Returns None once the channel is closed or a Shutdown event is received.
+     * Application events are dropped here, an application that has them takes them with next_event() instead.
+     */
+    pub async fn next_output(&mut self) -> Option<Output> {
+        loop {
+            match self.next_step().await? {
+                Step::Output(output) => return Some(*output),
+                Step::Event => self.app_events.clear(),
+            }
+        }
+    }
+
+    /**
+     * Run the broker without drawing anything, for an input only console (see Console::enable_input_only).
+     * Every output goes to redirect_output's receiver, or nowhere once it is dropped or when there is none. Returns on shutdown
+     */
+    pub async fn relay_output(&mut self) {
+        while let Some(output) = self.next_output().await {
+            if self.output_redirect.as_ref().is_some_and(|redirect| redirect.send(output).is_err()) {
+                self.output_redirect = None;
+            }
+        }
+    }
+
+    /**
+     * next_output() for an application with its own event type: the built-in output wrapped in BrokerEvent::Console,
+     * or an event a module emitted for the application (SenderHandle::emit) in BrokerEvent::App.
+     */
+    pub async fn next_event(&mut self) -> Option<BrokerEvent<T>> {
+        loop {
+            if let Some(event) = self.app_events.pop_front() {
+                return Some(BrokerEvent::App(event));
+            }
+            match self.next_step().await? {
+                Step::Output(output) => return Some(BrokerEvent::Console(*output)),
+                Step::Event => {},
+            }
+        }
+    }
+
+    /**
+     * One broker step, with the output finished for the zones
+     */
+    async fn next_step(&mut self) -> Option<Step> {
+        loop {
+            if let Some(copy) = self.split_copies.pop_front() {
+                return Some(Output::Line(copy).into());
+            }
+            let output = match self.next_admitted().await? {
+                Step::Output(output) => *output,
+                Step::Event => return Some(Step::Event),
+            };
+            let output = match output {
+                //a thread drawn again expanded or collapsed, its messages went through all of this when they came
+                Output::Line(message) if self.redrawn.remove(&message.id) => Output::Line(message),
+                Output::Line(mut message) => {
+                    if self.hooks.run_message(&mut message) == Flow::Veto {
+                        continue;
+                    }
+                    message.zone = Some(self.zone_for(&message));
+                    let module = message.sender != CONSOLE_SENDER && message.sender != USER_SENDER;
+                    if self.tabs && module {
+                        message.tab = Some(message.topic.clone().unwrap_or_else(|| self.display_name(&message.sender)));
+                    }
+                    let mut alert = Alert::NONE;
+                    if module {
+                        let sender_name = self.display_name(&message.sender);
+                        let replies = self.scripts.on_message(&sender_name, &message);
+                        self.ready.extend(replies.into_iter().map(Output::Line));
+                        //before the threads and repeats, a pinned message is never grouped
+                        alert = self.run_alerts(&sender_name, &mut message);
+                    }
+                    let collapse = module && *self.dedup.get(&message.sender).unwrap_or(&self.default_dedup);
+                    let sent = message.payload.clone();
+                    self.threads.group(&mut message);
+                    self.threads.fold(&mut message, module && self.fold);
+                    //what leaves the console is what was sent, not the thread's line or the folded first line
+                    let unthreaded = (message.payload != sent).then_some(sent);
+                    self.repeats.collapse(&mut message, collapse);
+                    if message.pinned {
+                        self.pinned_by.insert(message.id, message.sender.clone());
+                    }
+                    if message.sender != USER_SENDER {
+                        self.unread.fetch_add(1, Ordering::Relaxed);
+                    }
+                    if self.is_away() {
+                        let away = self.notify.alert(&message.priority);
+                        alert = Alert { bell: alert.bell || away.bell, desktop: alert.desktop || away.desktop, flash: alert.flash || away.flash };
+                    }
+                    if !alert.is_none() {
+                        //right behind the line, before anything else that is ready
+                        self.ready.push_front(Output::Notify { alert, message: message.clone() });
+                    }
+                    if self.split.is_some() {
+                        let sender_name = self.display_name(&message.sender);
+                        let copies = self.split.as_mut().map(|split| split.copies(&sender_name, &message)).unwrap_or_default();
+                        self.split_copies.extend(copies);
+                    }
+                    self.forward_to_sinks(&message, unthreaded);
+                    Output::Line(message)
+                },
+                Output::Dismiss(id) => {
+                    match id {
+                        Some(id) => { self.pinned_by.remove(&id); },
+                        None => self.pinned_by.clear(),
+                    }
+                    Output::Dismiss(id)
+                },
+                Output::Pause(paused) => {
+                    self.paused = paused;
+                    Output::Pause(paused)
+                },
+                Output::Retract { sender, id } => {
+                    if self.pinned_by.get(&id) == Some(&sender) {
+                        self.pinned_by.remove(&id);
+                    }
+                    Output::Retract { sender, id }
+                },
+                other => other,
+            };
+            return Some(output.into());
+        }
+    }
+
+    /**
+     * Hand a line of one of this console's SubConsoles to the bridge, when they are distributed
+     */
+    fn bridge_out(&mut self, message: &Message) {
+        let (origin, tx) = match self.bridge.as_ref() {
+            Some(bridge) if self.subconsoles.contains_key(&message.sender) => bridge,
+            _ => return,
+        };
+        let bridged = BridgedMessage {
+            origin: origin.clone(),
+            subconsole: self.display_name(&message.sender),
+            priority: message.priority.clone(),
+            text: message.payload.clone(),
+            topic: message.topic.clone(),
+        };
+        let _ = tx.send(bridged);
+    }
+
+    /**
+     * Hand a rendered line to the sinks, the ones that went away are forgotten. unthreaded is the text as it was sent
+     * when a thread or a fold changed it for the zones
+     */
+    fn forward_to_sinks(&mut self, message: &Message, unthreaded: Option<Arc<str>>) {
+        if self.line_sinks.is_empty() {
+            return;
+        }
+        let mut line = RenderedLine::new(self.display_name(&message.sender), message);
+        if let Some(text) = unthreaded {
+            line.text = text;
+        }
+        self.line_sinks.retain(|sink| sink.send(line.clone()).is_ok());
+    }
+
+    /**
+     * A line from a SubConsole of another console, from a sender of its own.
+     * The sender is made the first time one of its lines comes, after that it is whatever it became, eg. blacklisted
+     */
+    fn bridged_in(&mut self, bridged: BridgedMessage) -> Message {
+        let name = bridged.sender_name();
+        let mut id = self.generate_id(name.clone());
+        if !self.phonebook.contains_key(&id) {
+            id = self.new_sender(name).id().to_string();
+        }
+        let message = Message::new(id, bridged.priority, bridged.text);
+        match bridged.topic {
+            Some(topic) => message.with_topic(topic),
+            None => message,
+        }
+    }
+
+    /**
+     * Carry out a request of a service outside the process, the answer and the line to show when it sent one.
+     * A line is only sent as the sender the caller proved it is, one the phonebook already has, see remote::caller_identity
+     */
+    fn remote(&mut self, client: &str, request: RemoteRequest) -> (RemoteReply, Option<Message>) {
+        let refused = |lines: Vec<Message>| match lines.first() {
+            Some(note) => RemoteReply::Refused(note.payload.to_string()),
+            None => RemoteReply::Done,
+        };
+        match request {
+            RemoteRequest::Send { sender, text, priority, topic } => {
+                let identity = match remote::caller_identity(client) {
+                    Some(identity) => identity,
+                    None => return (RemoteReply::Refused(format!("{} has no sender to send as, see x-dualzone-client and x-dualzone-token", client)), None),
+                };
+                if sender != identity {
+                    return (RemoteReply::Refused(format!("{} may only send as {}", client, identity)), None);
+                }
+                let id = self.generate_id(sender.clone());
+                if !self.phonebook.contains_key(&id) {
+                    return (RemoteReply::Refused(format!("{} is not a known sender", sender)), None);
+                }
+                if let Some(note) = self.seen(&id, true) {
+                    self.ready.push_back(Output::Line(note));
+                }
+                let mut message = Message::new(id, priority, text);
+                message.topic = topic;
+                self.counters.record_received(&message.sender);
+                match self.admit(message) {
+                    Some(message) => (RemoteReply::Sent(message.id), Some(message)),
+                    None => (RemoteReply::Refused(format!("{} is not admitted, see /status {}", sender, sender)), None),
+                }
+            },
+            RemoteRequest::ListSenders => {
+                let senders = self.phonebook_entries().into_iter().map(|entry| SenderSummary {
+                    capabilities: self.capabilities_of(entry.name.clone()).map(|capabilities| capabilities.to_string()),
+                    id: self.generate_id(entry.name.clone()),
+                    name: entry.name,
+                    status: entry.status,
+                    messages: entry.messages,
+                }).collect();
+                (RemoteReply::Senders(senders), None)
+            },
+            RemoteRequest::Ban(name) => (refused(self.ban(&name)), None),
+            RemoteRequest::Unban(name) => (refused(self.unban(&name)), None),
+        }
+    }
+
+    /**
+     * Pick the output zone for a message, the first zone bound to its topic or sender wins,
+     * otherwise the first SubConsole window bound to it, otherwise the first catch-all zone, otherwise the first zone.
+     */
+    fn zone_for(&self, message: &Message) -> String {
+        let sender_name = self.display_name(&message.sender);
+        let topic = message.topic.as_deref();
+        let zone = self.zones.iter().find(|zone| zone.matches(topic, &sender_name))
+            .or_else(|| self.windows.iter().find(|window| window.matches(topic, &sender_name)))
+            .or_else(|| self.zones.iter().find(|zone| zone.is_catch_all()))
+            .or_else(|| self.zones.first());
+        match zone {
+            Some(zone) => zone.name.clone(),
+            None => crate::output::zone::MAIN_ZONE.to_string(),
+        }
+    }
+
+    /**
+     * The broker step proper, pulls events until one produces something to render
+     */
+    async fn next_admitted(&mut self) -> Option<Step> {
+        loop {
+            if let Some(output) = self.ready.pop_front() {
+                return Some(output.into());
+            }
+            if let Some(message) = self.release_delayed(Instant::now()) {
+                return Some(Output::Line(message).into());
+            }
+            //scheduled messages go through the broker when they fire, like anything a module sends
+            let fired = self.release_scheduled(Instant::now());
+            if !fired.is_empty() {
+                for message in fired {
+                    self.counters.record_received(&message.sender);
+                    if let Some(message) = self.admit(message) {
+                        self.ready.push_back(Output::Line(message));
+                    }
+                }
+                continue;
+            }
+
+            let now = Instant::now();
+            let notes = self.check_liveness(now);
+            if !notes.is_empty() {
+                self.ready.extend(notes.into_iter().map(Output::Line));
+                continue;
+            }
+
+            //wake up for the next delayed message, scheduled message or module to miss its heartbeat, whichever comes first
+            let wake = self.delayed.front().map(|(due, _)| *due).into_iter().chain(self.timers.next_due()).chain(self.next_stale_at()).min();
+            let event = match wake {
+                Some(due) => {
+                    tokio::select! {
+                        event = self.rx.recv() => event,
+                        _ = tokio::time::sleep_until(due) => continue,
+                    }
+                },
+                None => self.rx.recv().await,
+            };
+
+            self.publish_metrics();
+            //anything the user does at the keyboard or with the mouse says they are watching
+            if let Some(ConsoleEvent::UserInput(_) | ConsoleEvent::Search(_) | ConsoleEvent::Pause(_) | ConsoleEvent::Scroll(_)
+                | ConsoleEvent::Focus(_) | ConsoleEvent::Select(_)) = &event {
+                self.last_input = Instant::now();
+            }
+            let back = match &event {
+                Some(ConsoleEvent::Message(message)) | Some(ConsoleEvent::Routed { message, .. }) => self.seen(&message.sender.clone(), true),
+                Some(ConsoleEvent::Dismiss { sender, .. }) | Some(ConsoleEvent::Retract { sender, .. }) | Some(ConsoleEvent::Copy { sender, .. })
+                | Some(ConsoleEvent::Input { sender, .. }) | Some(ConsoleEvent::Command { sender, .. })
+                | Some(ConsoleEvent::Heartbeat(sender)) | Some(ConsoleEvent::Unschedule { sender, .. })
+                | Some(ConsoleEvent::SubConsole { sender, .. }) | Some(ConsoleEvent::App { sender, .. }) => self.seen(&sender.clone(), false),
+                Some(ConsoleEvent::Schedule(scheduled)) => self.seen(&scheduled.message.sender.clone(), false),
+                _ => None,
+            };
+            if let Some(note) = back {
+                self.ready.push_back(Output::Line(note));
+            }
+            match event {
+                None | Some(ConsoleEvent::Shutdown) => return None,
+                Some(ConsoleEvent::UserInput(mut line)) => {
+                    //the user is looking at the console again
+                    self.unread.store(0, Ordering::Relaxed);
+                    if self.hooks.run_input(&mut line) == Flow::Veto {
+                        continue;
+                    }
+                    if !matches!(Command::parse(&line), Some(Command::Macro(_))) {
+                        self.macros.record(&line);
+                    }
+                    let replies = self.handle_input(line.clone());
+                    let replies = self.page_replies(&line, replies);
+                    self.ready.extend(replies.into_iter().map(Output::Line));
+                },
+                Some(ConsoleEvent::SessionInput { client, mut line }) => {
+                    if self.hooks.run_input(&mut line) == Flow::Veto {
+                        continue;
+                    }
+                    //everyone attached sees who typed what, commands run as the client so the audit log names it too.
+                    //a chat command draws its own line, a /msg is for the one it is sent to alone
+                    self.chat.join(&client);
+                    let command = Command::parse(&line);
+                    if !matches!(command, Some(Command::Chat(_))) {
+                        let attributed = self.chat.say(&client, &line);
+                        self.ready.push_back(Output::Line(Message::new(USER_SENDER.to_string(), PriorityStatus::Normal, ansi::apply(&attributed, &self.default_ansi_policy))));
+                    }
+                    if let Some(command) = command {
+                        let replies = self.run_command_as(client, Origin::SessionClient, &line, command);
+                        self.ready.extend(replies.into_iter().map(Output::Line));
+                    }
+                },
+                Some(ConsoleEvent::Message(message)) => {
+                    self.counters.record_received(&message.sender);
+                    if let Some(message) = self.admit(message) {
+                        self.bridge_out(&message);
+                        return Some(Output::Line(message).into());
+                    }
+                },
+                Some(ConsoleEvent::Bridged(bridged)) => {
+                    let message = self.bridged_in(bridged);
+                    self.counters.record_received(&message.sender);
+                    if let Some(message) = self.admit(message) {
+                        return Some(Output::Line(message).into());
+                    }
+                },
+                Some(ConsoleEvent::Routed { message, target, ack }) => {
+                    self.counters.record_received(&message.sender);
+                    self.route(message, target, ack);
+                },
+                Some(ConsoleEvent::Dismiss { sender, id }) => {
+                    //a module may only dismiss what it pinned itself
+                    if self.pinned_by.get(&id) == Some(&sender) && self.permits(&sender, Capability::Print) {
+                        return Some(Output::Dismiss(Some(id)).into());
+                    }
+                },
+                //the zones only take back the sender's own lines, the broker only checks it may still print
+                Some(ConsoleEvent::Retract { sender, id }) => {
+                    if let (SenderStatus::Authorized, true) = (self.get_sender_status(sender.clone()), self.permits(&sender, Capability::Print)) {
+                        return Some(Output::Retract { sender, id }.into());
+                    }
+                },
+                Some(ConsoleEvent::Remote { client, request, reply }) => {
+                    self.acting = Some(client.clone());
+                    self.origin = Some(Origin::Remote);
+                    let (answer, line) = self.remote(&client, request);
+                    self.acting = None;
+                    self.origin = None;
+                    let _ = reply.send(answer);
+                    if let Some(message) = line {
+                        return Some(Output::Line(message).into());
+                    }
+                },
+                Some(ConsoleEvent::SessionAudit { client, action, subject }) => {
+                    self.acting = Some(client);
+                    self.origin = Some(Origin::SessionClient);
+                    self.record_audit(action, subject);
+                    self.acting = None;
+                    self.origin = None;
+                },
+                Some(ConsoleEvent::Page { id, step }) => {
+                    let replies = self.turn_page(id, step);
+                    self.ready.extend(replies.into_iter().map(Output::Line));
+                },
+                Some(ConsoleEvent::Shell(line)) => {
+                    let replies = self.run_shell(&line);
+                    self.ready.extend(replies.into_iter().map(Output::Line));
+                },
+                Some(ConsoleEvent::Thread(command)) => {
+                    let replies = self.run_thread_command(command);
+                    self.ready.extend(replies.into_iter().map(Output::Line));
+                },
+                Some(ConsoleEvent::Detach) => {
+                    let replies = self.detach_terminal();
+                    self.ready.extend(replies.into_iter().map(Output::Line));
+                },
+                Some(ConsoleEvent::Search(command)) => return Some(Output::Search(command).into()),
+                Some(ConsoleEvent::Pause(paused)) => return Some(Output::Pause(paused).into()),
+                Some(ConsoleEvent::Redraw) => return Some(Output::Redraw.into()),
+                Some(ConsoleEvent::Scroll(command)) => return Some(Output::Scroll(command).into()),
+                Some(ConsoleEvent::Focus(command)) => return Some(Output::Focus(command).into()),
+                Some(ConsoleEvent::Select(command)) => return Some(Output::Select(command).into()),
+                //the panes of a split stay put
+                Some(ConsoleEvent::Tab(command)) => if self.split.is_none() {
+                    return Some(Output::Tab(command).into());
+                },
+                Some(ConsoleEvent::Copy { sender, target }) => {
+                    if let (SenderStatus::Authorized, true) = (self.get_sender_status(sender.clone()), self.permits(&sender, Capability::Print)) {
+                        return Some(Output::Copy(target).into());
+                    }
+                },
+                Some(ConsoleEvent::Action(name)) => self.broadcast_action(name),
+                //dropping the request of a sender that may not ask closes its reply channel
+                Some(ConsoleEvent::Input { sender, request }) => {
+                    if let (SenderStatus::Authorized, true) = (self.get_sender_status(sender.clone()), self.permits(&sender, Capability::Print)) {
+                        let _ = self.input_requests.send(request);
+                    }
+                },
+                Some(ConsoleEvent::Command { sender, line }) => {
+                    let replies = self.run_module_command(sender, line);
+                    self.ready.extend(replies.into_iter().map(Output::Line));
+                },
+                //last_seen was updated above, nothing else to do
+                Some(ConsoleEvent::Heartbeat(_)) => {},
+                Some(ConsoleEvent::Schedule(scheduled)) => self.schedule_for(scheduled),
+                Some(ConsoleEvent::Unschedule { sender, id }) => { self.unschedule_for(&sender, id); },
+                //dropping the reply of a sender that may not spawn one closes it
+                Some(ConsoleEvent::SubConsole { sender, name, window, reply }) => {
+                    if let (SenderStatus::Authorized, true) = (self.get_sender_status(sender.clone()), self.permits(&sender, Capability::SpawnSubConsole)) {
+                        match self.new_subconsole(name, window) {
+                            Ok(subconsole) => { let _ = reply.send(subconsole); },
+                            Err(e) => self.ready.push_back(Output::Line(Message::from_console(format!("[console] no SubConsole: {}", e)))),
+                        }
+                    }
+                },
+                Some(ConsoleEvent::App { sender, target, payload }) => {
+                    if self.deliver_event(sender, target, payload) {
+                        return Some(Step::Event);
+                    }
+                },
+            }
+        }
+    }
+
+    /**
+     * Let go of the console's own terminal, the input zone stops and the zones keep going for the clients of the session.
+     * Only with a session to come back to, see Console::serve_session
+     */
+    fn detach_terminal(&mut self) -> Vec<Message> {
+        let path = match self.session.as_ref() {
+            Some(path) => path.display().to_string(),
+            None => return vec![Message::from_console("[session] there is no session to come back to, start the console with --session".to_string())],
+        };
+        if self.detached {
+            return vec![Message::from_console("[session] the terminal is already detached".to_string())];
+        }
+        self.detached = true;
+        let _ = self.input_requests.send(InputRequest::Detach);
+        self.ready.push_back(Output::Detach);
+        vec![Message::from_console(format!("[session] detached, dualZoneCli attach (on {}) comes back", path))]
+    }
+
+    /**
+     * Pop the first delayed message whose time has come
+     */
+    fn release_delayed(&mut self, now: Instant) -> Option<Message> {
+        match self.delayed.front() {
+            Some((due, _)) if *due <= now => self.delayed.pop_front().map(|(_, message)| message),
+            _ => None,
+        }
+    }
+
+    /**
+     * Check a module message against the phonebook and its rate limit.
+     * Returns the message to render now, or None if it was dropped or delayed.
+     */
+    fn admit(&mut self, mut message: Message) -> Option<Message> {
+        //escape sequences are dealt with before anything else so nothing downstream sees a cursor move
+        let policy = self.ansi_policies.get(&message.sender).unwrap_or(&self.default_ansi_policy);
+        ansi::apply_to_message(&mut message, policy);
+
+        if message.sender == CONSOLE_SENDER || message.sender == USER_SENDER {
+            return Some(message);
+        }
+        match self.get_sender_status(message.sender.clone()) {
+            SenderStatus::Authorized => {},
+            status => {
+                self.dead_letters.lock().unwrap().push(message, RejectReason::NotAuthorized(status));
+                return None;
+            }
+        }
+        if !self.permits(&message.sender, Capability::Print) {
+            self.dead_letters.lock().unwrap().push(message, RejectReason::NotPermitted(Capability::Print));
+            return None;
+        }
+
+        //below the filter level, not an error so it does not go to the dead-letter queue
+        if message.priority.severity() < self.filter_level.severity() {
+            return None;
+        }
+        if !self.filter_rules.is_empty() {
+            let sender_name = self.display_name(&message.sender);
+            if !self.filter_rules.admits(&sender_name, &message) {
+                return None;
+            }
+        }
+
+        if !self.rate_limits.contains_key(&message.sender) {
+            match self.default_rate_limit.clone() {
+                Some(limit) => {
+                    self.rate_limits.insert(message.sender.clone(), TokenBucket::new(limit));
+                },
+                None => return Some(message),
+            }
+        }
+
+        let now = Instant::now();
+        let verdict = match self.rate_limits.get_mut(&message.sender) {
+            Some(bucket) => bucket.check(now),
+            None => Verdict::Pass,
+        };
+        if verdict != Verdict::Pass {
+            *self.rate_limited.entry(message.sender.clone()).or_insert(0) += 1;
+        }
+        match verdict {
+            Verdict::Pass => Some(message),
+            Verdict::Drop => {
+                self.dead_letters.lock().unwrap().push(message, RejectReason::RateLimited);
+                None
+            },
+            Verdict::Downgrade => {
+                message.priority = PriorityStatus::Verbose;
+                Some(message)
+            },
+            Verdict::Delay(wait) => {
+                let due = now + wait;
+                //keep the queue ordered by due time so the front is always the next to release
+                let position = self.delayed.iter().position(|(d, _)| *d > due).unwrap_or(self.delayed.len());
+                self.delayed.insert(position, (due, message));
+                None
+            },
+        }
+    }
+
+    /**
+     * Deliver a module to module message to the target's inbox.
+     * Messages asking for an ack are handed to their own task so retries never hold up the broker.
+     */
+    fn route(&mut self, message: Message, target: String, ack: Option<oneshot::Sender<DeliveryReport>>) {
+        let failure = match self.get_sender_status(message.sender.clone()) {
+            SenderStatus::Authorized => None,
+            _ => Some(DeliveryError::NotAuthorized),
+        };
+        if target == BROADCAST_TARGET && failure.is_none() {
+            self.broadcast(message);
+            return;
+        }
+        let target_id = self.generate_id(target);
+        let failure = failure.or_else(|| match self.inboxes.get(&target_id) {
+            Some(_) => None,
+            None => Some(DeliveryError::UnknownTarget),
+        });
+        if let Some(error) = failure {
+            self.dead_letters.lock().unwrap().push(message, RejectReason::Undelivered(error.to_string()));
+            if let Some(ack) = ack {
+                let _ = ack.send(Err(error));
+            }
+            return;
+        }
+
+        let inbox = self.inboxes.get(&target_id).unwrap().clone();
+        match ack {
+            Some(report) => {
+                tokio::spawn(routing::deliver_with_ack(inbox, message, self.retry_policy.clone(), self.dead_letters.clone(), report));
+            },
+            None => {
+                let _ = routing::deliver_once(&inbox, message, &self.dead_letters);
+            }
+        }
+    }
+
+    /**
+     * Deliver a module message to every inbox but the sender's own, best effort
+     */
+    fn broadcast(&mut self, message: Message) {
+        if !self.permits(&message.sender, Capability::Broadcast) {
+            let error = DeliveryError::NotPermitted;
+            self.dead_letters.lock().unwrap().push(message, RejectReason::Undelivered(error.to_string()));
+            return;
+        }
+        for (id, inbox) in self.inboxes.iter().filter(|(id, _)| **id != message.sender) {
+            let _ = routing::deliver_once(inbox, message.clone(), &self.dead_letters);
+        }
+    }
+
+    /**
+     * Hand an emitted event on, true when it is waiting for next_event().
+     * An event its sender or target can't take is dropped with a console note, it has no Message to put in the dead-letter queue.
+     */
+    fn deliver_event(&mut self, sender: String, target: Option<String>, payload: T) -> bool {
+        self.counters.record_received(&sender);
+        let name = self.display_name(&sender);
+        if !matches!(self.get_sender_status(sender.clone()), SenderStatus::Authorized) {
+            self.ready.push_back(Output::Line(Message::from_console(format!("[console] dropped an event from {}: {}", name, DeliveryError::NotAuthorized))));
+            return false;
+        }
+        let inbox = match target.as_ref() {
+            None => {
+                self.app_events.push_back(AppEvent { sender, target, payload });
+                return true;
+            },
+            Some(target) => self.event_inboxes.get(&self.generate_id(target.clone())).cloned(),
+        };
+        let error = match inbox.map(|inbox| inbox.try_send(AppEvent { sender, target: target.clone(), payload })) {
+            Some(Ok(())) => return false,
+            Some(Err(TrySendError::Full(_))) => DeliveryError::TargetFull,
+            Some(Err(TrySendError::Closed(_))) => DeliveryError::TargetClosed,
+            None => DeliveryError::UnknownTarget,
+        };
+        let note = format!("[console] dropped an event from {} to {}: {}", name, target.unwrap_or_default(), error);
+        self.ready.push_back(Output::Line(Message::from_console(note)));
+        false
+    }
+
+    /**
+     * A /command from a module, run only if the sender may execute commands. Anything else is rejected with a console note
+     */
+    fn run_module_command(&mut self, sender: String, line: String) -> Vec<Message> {
+        let name = self.display_name(&sender);
+        let allowed = matches!(self.get_sender_status(sender.clone()), SenderStatus::Authorized) && self.permits(&sender, Capability::ExecuteCommands);
+        if !allowed {
+            let message = Message::new(sender, PriorityStatus::Normal, line);
+            self.dead_letters.lock().unwrap().push(message, RejectReason::NotPermitted(Capability::ExecuteCommands));
+            return vec![Message::from_console(format!("[console] {} may not run commands", name))];
+        }
+        match Command::parse(&line) {
+            Some(command) => self.run_command_as(name, Origin::Module, &line, command),
+            None => vec![Message::from_console(format!("[console] {} sent a command that is not one: {}", name, line))],
+        }
+    }
+
+    /**
+     * Hand a custom keybind action to every module with an inbox, modules that don't care ignore the topic
+     */
+    fn broadcast_action(&mut self, name: String) {
+        for inbox in self.inboxes.values() {
+            let message = Message::from_console(name.clone()).with_topic(ACTION_TOPIC.to_string());
+            let _ = routing::deliver_once(inbox, message, &self.dead_letters);
+        }
+    }
+
+    /**
+     * A line from the input zone is either a command for the broker or echoed to the output zone
+     */
+    fn handle_input(&mut self, line: String) -> Vec<Message> {
+        if let Some(command) = Command::parse(&line) {
+            return self.run_command_as(USER_SENDER.to_string(), Origin::Terminal, &line, command);
+        }
+        //with a process in focus the line is its input, shown as going to it
+        if let Some(name) = self.process_focus.clone() {
+            if self.send_to_process(&name, ProcessInput::Line(line.clone())) {
+                let echo = format!("{} < {}", name, line);
+                return vec![Message::new(USER_SENDER.to_string(), PriorityStatus::Normal, ansi::apply(&echo, &self.default_ansi_policy))];
+            }
+            self.process_focus = None;
+            return vec![Message::from_console(format!("[proc] {} is no longer running, lines are your own again", name))];
+        }
+        let line = if self.chat.enabled { self.chat.say(USER_SENDER, &line) } else { line };
+        vec![Message::new(USER_SENDER.to_string(), PriorityStatus::Normal, ansi::apply(&line, &self.default_ansi_policy))]
+    }
+
+    /**
+     * The result of a line the user typed as it is shown: a /command's that is taller than the output zone a page at a time,
+     * the rest waits in the pager for the user to ask for it (or behind the one already being paged)
+     */
+    fn page_replies(&mut self, line: &str, replies: Vec<Message>) -> Vec<Message> {
+        if self.display_only || self.input_only || Command::parse(line).is_none() {
+            return replies;
+        }
+        let command = line.trim().trim_start_matches('/').split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+        if !self.pager_policy.pages(&command) {
+            return replies;
+        }
+        if let Some(pager) = self.paging.as_mut() {
+            pager.extend(replies);
+            return Vec::new();
+        }
+        let rects = Layout::from_terminal(self.status_bar).split_output(self.zones.len(), &self.zone_arrangement);
+        let page = rects.first().map_or(1, |rect| rect.height as usize);
+        let (shown, pager) = Pager::start(message::next_id(), command, replies, page);
+        if let Some(pager) = pager {
+            self.ask_page(pager);
+        }
+        shown
+    }
+
+    /**
+     * Hand the input zone the pager's prompt, the step the user takes comes back as ConsoleEvent::Page
+     */
+    fn ask_page(&mut self, pager: Pager) {
+        let (reply, answer) = oneshot::channel();
+        let _ = self.input_requests.send(InputRequest::Page { prompt: pager.prompt(), reply });
+        let (id, console) = (pager.id, self.tx.clone());
+        self.paging = Some(pager);
+        tokio::spawn(async move {
+            //a prompt that is dropped unanswered, eg. by /detach, stops the pager
+            let step = answer.await.unwrap_or(PageStep::Quit);
+            let _ = console.send(ConsoleEvent::Page { id, step }).await;
+        });
+    }
+
+    fn turn_page(&mut self, id: u64, step: PageStep) -> Vec<Message> {
+        let mut pager = match self.paging.take() {
+            Some(pager) if pager.id == id => pager,
+            other => {
+                self.paging = other;
+                return Vec::new();
+            },
+        };
+        let lines = pager.step(step);
+        if !pager.is_done() {
+            self.ask_page(pager);
+        }
+        lines
+    }
+
+    /**
+     * /sh, a line for the system's shell as the [shell] policy allows it. A line it wants confirmed is asked about
+     * in the input zone and comes back as ConsoleEvent::Shell once the user says yes
+     */
+    fn run_shell_command(&mut self, line: String) -> Vec<Message> {
+        let reply = |text: String| vec![Message::from_console(format!("[sh] {}", text))];
+        if line.is_empty() {
+            return reply("usage: /sh <command>".to_string());
+        }
+        match self.shell.check(&line) {
+            ShellVerdict::Run => self.run_shell(&line),
+            ShellVerdict::Refused(part) => reply(format!("{} is not allowed, see the [shell] allow list", part)),
+            ShellVerdict::Confirm => {
+                let answer = self.confirm(format!("Run {}? [y/N]", line));
+                let console = self.tx.clone();
+                tokio::spawn(async move {
+                    let event = match answer.await {
+                        Ok(true) => ConsoleEvent::Shell(line),
+                        _ => ConsoleEvent::Message(Message::from_console(format!("[sh] did not run {}", line))),
+                    };
+                    let _ = console.send(event).await;
+                });
+                Vec::new()
+            },
+        }
+    }
+
+    fn run_shell(&mut self, line: &str) -> Vec<Message> {
+        match self.spawn_words("sh".to_string(), line, &shell::shell_words(line)) {
+            Ok(name) => vec![Message::from_console(format!("[sh] $ {} (as {}, /proc kill {} ends it)", line, name, name))],
+            Err(e) => vec![Message::from_console(format!("[sh] could not run {}: {}", line, e))],
+        }
+    }
+
+    /**
+     * /expand and /collapse, and the toggle-thread keybind. The thread is drawn again in its line, only a thread that isn't there says so
+     */
+    fn run_thread_command(&mut self, command: ThreadCommand) -> Vec<Message> {
+        match self.threads.apply(command) {
+            Ok(line) => {
+                self.redrawn.insert(line.id);
+                vec![line]
+            },
+            Err(reason) => vec![Message::from_console(format!("[thread] {}", reason))],
+        }
+    }
+
+    /**
+     * /nick, /me and /msg, as whoever typed them: the terminal or a client of the session. What only that user needs
+     * to see (its nick, a usage line, an error) is told to it alone
+     */
+    fn run_chat_command(&mut self, command: ChatCommand) -> Vec<Message> {
+        let actor = self.acting.clone().unwrap_or_else(|| USER_SENDER.to_string());
+        self.chat.join(&actor);
+        match command {
+            ChatCommand::Nick(None) => self.whisper(&actor, Message::from_console(format!("[chat] you are {}", self.chat.nick(&actor)))),
+            ChatCommand::Nick(Some(nick)) => match self.chat.set_nick(&actor, &nick) {
+                Ok(old) => return vec![Message::from_console(format!("[chat] {} is now known as {}", old, nick))],
+                Err(e) => self.whisper(&actor, Message::from_console(format!("[chat] {}", e))),
+            },
+            ChatCommand::Me(text) if text.is_empty() => self.whisper(&actor, Message::from_console("[chat] usage: /me <action>".to_string())),
+            ChatCommand::Me(text) => {
+                let action = self.chat.action(&actor, &text);
+                return vec![Message::new(USER_SENDER.to_string(), PriorityStatus::Normal, ansi::apply(&action, &self.default_ansi_policy))];
+            },
+            ChatCommand::Msg { to: Some(to), text } if !text.is_empty() => self.private_message(&actor, &to, text),
+            ChatCommand::Msg { .. } => self.whisper(&actor, Message::from_console("[chat] usage: /msg <user> <text>".to_string())),
+        }
+        Vec::new()
+    }
+
+    /**
+     * A line for one user alone, see Output::Private
+     */
+    fn whisper(&mut self, to: &str, message: Message) {
+        self.ready.push_back(Output::Private { to: to.to_string(), message });
+    }
+
+    /**
+     * /msg: to a chat user by nick, drawn as "*alice* text" for it and "-> *bob* text" for whoever sent it,
+     * or else to the inbox of the module the phonebook knows by that name (see Console::open_inbox) with the nick as a field
+     */
+    fn private_message(&mut self, actor: &str, to: &str, text: String) {
+        let from = self.chat.nick(actor).to_string();
+        let said = |text: String| Message::new(USER_SENDER.to_string(), PriorityStatus::Normal, text);
+        if let Some(client) = self.chat.client_of(to).map(|client| client.to_string()) {
+            let nick = self.chat.nick(&client).to_string();
+            let policy = self.default_ansi_policy.clone();
+            self.whisper(&client, said(ansi::apply(&format!("*{}* {}", from, text), &policy)));
+            if client != actor {
+                self.whisper(actor, said(ansi::apply(&format!("-> *{}* {}", nick, text), &policy)));
+            }
+            return;
+        }
+        let sent = match self.resolve_target(to) {
+            Err(DeliveryError::UnknownTarget) if self.phonebook_entry(to).is_none() => Err(format!("no one is called {}", to)),
+            Err(e) => Err(format!("{} didn't get it: {}", to, e)),
+            Ok(id) => match self.inboxes.get(&id).cloned() {
+                None => Err(format!("{} didn't get it: {}", to, DeliveryError::UnknownTarget)),
+                Some(inbox) => routing::deliver_once(&inbox, said(text.clone()).with_field("nick", from), &self.dead_letters)
+                    .map_err(|e| format!("{} didn't get it: {}", to, e)),
+            },
+        };
+        let reply = match sent {
+            Ok(()) => said(ansi::apply(&format!("-> *{}* {}", to, text), &self.default_ansi_policy)),
+            Err(e) => Message::from_console(format!("[chat] {}", e)),
+        };
+        self.whisper(actor, reply);
+    }
+
+    /**
+     * /proc, the processes started with Console::spawn_process
+     */
+    fn run_process_command(&mut self, command: ProcessCommand) -> Vec<Message> {
+        let reply = |text: String| vec![Message::from_console(format!("[proc] {}", text))];
+        let hand = |console: &Self, name: Option<String>, input: ProcessInput, done: &str| match name {
+            None => reply("which process? /proc lists them".to_string()),
+            Some(name) if console.send_to_process(&name, input) => reply(format!("{} {}", name, done)),
+            Some(name) => reply(format!("no process named {} is running", name)),
+        };
+        match command {
+            ProcessCommand::List => {
+                let processes = self.running_processes();
+                let mut lines = reply(format!("{} running", processes.len()));
+                for process in processes {
+                    let pid = process.pid.map_or("?".to_string(), |pid| pid.to_string());
+                    let focus = if self.process_focus.as_deref() == Some(process.name.as_str()) { ", in focus" } else { "" };
+                    lines.extend(reply(format!("{} (pid {}{}): {}", process.name, pid, focus, process.command)));
+                }
+                lines
+            },
+            ProcessCommand::Send { name, line } => hand(self, name, ProcessInput::Line(line), "got the line"),
+            ProcessCommand::Close(name) => hand(self, name, ProcessInput::Close, "has its input closed"),
+            ProcessCommand::Kill(name) => hand(self, name, ProcessInput::Kill, "is being killed"),
+            ProcessCommand::Focus(None) => match self.process_focus.take() {
+                Some(name) => reply(format!("{} is out of focus, lines are your own again", name)),
+                None => reply("no process is in focus".to_string()),
+            },
+            ProcessCommand::Focus(Some(name)) => match self.running_processes().iter().any(|process| process.name == name) {
+                true => {
+                    let text = format!("lines go to {} now, /proc focus on its own to stop", name);
+                    self.process_focus = Some(name);
+                    reply(text)
+                },
+                false => reply(format!("no process named {} is running", name)),
+            },
+            ProcessCommand::Unknown(name) => reply(format!("unknown subcommand {}, use list, send, focus, close or kill", name)),
+        }
+    }
+
+    /**
+     * Execute a command for an actor come in from origin, recording it in the audit log along with whatever it changes.
+     * A command that reaches into the host (Command::is_host_only) is only run for the terminal, by origin since a name can be anyone's
+     */
+    fn run_command_as(&mut self, actor: String, origin: Origin, line: &str, command: Command) -> Vec<Message> {
+        self.acting = Some(actor);
+        self.origin = Some(origin);
+        self.record_audit(AuditAction::Command, line.to_string());
+        let replies = match command.is_host_only() && origin != Origin::Terminal {
+            true => {
+                let name = line.trim().trim_start_matches('/').split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+                vec![Message::from_console(format!("[console] only the user at the terminal may run /{}", name))]
+            },
+            false => self.run_command(command),
+        };
+        self.acting = None;
+        self.origin = None;
+        replies
+    }
+
+    /**
+     * Execute a command and return the reply lines to render
+     */
+    fn run_command(&mut self, command: Command) -> Vec<Message> {
+        match command {
+            Command::Stats => {
+                let table = self.metrics().table();
+                vec![Message::from_console(self.metrics().status_line()).with_widget(message::next_id(), Widget::Table(table))]
+            },
+            Command::Who => vec![self.who()],
+            Command::Senders => self.list_senders(),
+            Command::Macro(command) => self.run_macro_command(command),
+            Command::Schedule(command) => self.run_schedule_command(command),
+            Command::Scripts(None) => {
+                let names = self.scripts.names();
+                let mut lines = vec![Message::from_console(format!("[scripts] {} loaded", names.len()))];
+                for name in names {
+                    let commands: Vec<String> = self.scripts.commands(&name).iter().map(|command| format!("/{}", command)).collect();
+                    lines.push(Message::from_console(format!("[scripts] {} {}", name, commands.join(" "))));
+                }
+                lines
+            },
+            Command::Scripts(Some(subcommand)) if subcommand == "reload" => match self.reload_scripts() {
+                Ok(count) => vec![Message::from_console(format!("[scripts] reloaded {} scripts", count))],
+                Err(e) => vec![Message::from_console(format!("[scripts] reload failed, keeping the old scripts: {}", e))],
+            },
+            Command::Scripts(Some(subcommand)) => vec![Message::from_console(format!("[scripts] unknown subcommand {}, use reload", subcommand))],
+            Command::Plugins => {
+                let names = self.plugins.names();
+                let mut lines = vec![Message::from_console(format!("[plugins] {} loaded", names.len()))];
+                for name in names {
+                    let commands: Vec<String> = self.plugins.commands(&name).iter().map(|command| format!("/{}", command)).collect();
+                    lines.push(Message::from_console(format!("[plugins] {} {}", name, commands.join(" "))));
+                }
+                lines
+            },
+            Command::SenderStatus(Some(name)) => self.show_sender(&name),
+            Command::Ban(Some(name)) => self.ban(&name),
+            Command::Unban(Some(name)) => self.unban(&name),
+            Command::SenderStatus(None) => vec![Message::from_console("[senders] usage: /status <name>".to_string())],
+            Command::Ban(None) => vec![Message::from_console("[senders] usage: /ban <name>".to_string())],
+            Command::Unban(None) => vec![Message::from_console("[senders] usage: /unban <name>".to_string())],
+            Command::Audit(count) => {
+                let recent = self.audit_log.recent(count);
+                let mut lines = vec![Message::from_console(format!("[audit] showing {} of {} entries", recent.len(), self.audit_log.total()))];
+                for entry in recent.iter().rev() {
+                    let at = chrono::Local.timestamp_millis_opt(entry.at as i64).single().map(|t| t.format("%H:%M:%S").to_string()).unwrap_or_default();
+                    lines.push(Message::from_console(format!("[audit] {} {} {}: {}", at, entry.actor, entry.action, entry.subject)));
+                }
+                lines
+            },
+            Command::DeadLetters(count) => {
+                let (recent, total) = {
+                    let queue = self.dead_letters.lock().unwrap();
+                    (queue.recent(count), queue.total())
+                };
+                let mut lines = vec![Message::from_console(format!("[dlq] showing {} of {} rejected messages", recent.len(), total))];
+                for letter in recent {
+                    lines.push(Message::from_console(format!(
+                        "[dlq] {} ({:?}): {}",
+                        self.get_plaintext_name(letter.message.sender.clone()), letter.reason, letter.message.payload
+                    )));
+                }
+                lines
+            },
+            Command::Level(level) => {
+                match level.as_deref().map(PriorityStatus::parse) {
+                    None => vec![Message::from_console(format!("[console] filter level is {:?}", self.filter_level))],
+                    Some(Some(level)) => {
+                        self.set_filter_level(level);
+                        vec![Message::from_console(format!("[console] filter level set to {:?}", self.filter_level))]
+                    },
+                    Some(None) => vec![Message::from_console("[console] unknown priority level".to_string())],
+                }
+            },
+            Command::Filter(command) => self.run_filter_command(command),
+            Command::Highlight(command) => self.run_highlight_command(command),
+            Command::Alert(command) => self.run_alert_command(command),
+            Command::Process(command) => self.run_process_command(command),
+            Command::Shell(line) => self.run_shell_command(line),
+            Command::Thread(command) => self.run_thread_command(command),
+            Command::Chat(command) => self.run_chat_command(command),
+            Command::Fields(setting) => {
+                let shown = match setting.as_deref() {
+                    None => self.highlights.fields.shown,
+                    Some("on") => true,
+                    Some("off") => false,
+                    Some(other) => return vec![Message::from_console(format!("[fields] usage: /fields on|off, not {}", other))],
+                };
+                if setting.is_some() {
+                    self.show_fields(shown);
+                }
+                vec![Message::from_console(format!("[fields] fields are {}", if shown { "shown" } else { "hidden" }))]
+            },
+            Command::Unpin(target) => {
+                match target.as_deref() {
+                    None | Some("all") => {
+                        self.ready.push_back(Output::Dismiss(None));
+                        vec![Message::from_console("[console] dismissed all pinned messages".to_string())]
+                    },
+                    Some(id) => match id.trim_start_matches('#').parse::<u64>() {
+                        Ok(id) if self.pinned_by.contains_key(&id) => {
+                            self.ready.push_back(Output::Dismiss(Some(id)));
+                            vec![Message::from_console(format!("[console] dismissed pinned message #{}", id))]
+                        },
+                        _ => vec![Message::from_console(format!("[console] no pinned message {}", id))],
+                    },
+                }
+            },
+            //no reply line, it would land in the scrollback and match its own term, the status bar shows the result
+            Command::Search(term) => {
+                let command = match term {
+                    Some(term) => SearchCommand::Query(term),
+                    None => SearchCommand::Clear,
+                };
+                self.ready.push_back(Output::Search(command));
+                Vec::new()
+            },
+            Command::Pause(paused) => {
+                self.ready.push_back(Output::Pause(paused));
+                Vec::new()
+            },
+            Command::Detach => self.detach_terminal(),
+            //the zones know the tabs, they say when there is no such tab
+            Command::Tab(_) | Command::View(_) if self.split.is_some() => {
+                vec![Message::from_console("[console] the output area is split, /split off first".to_string())]
+            },
+            Command::Tab(name) => {
+                self.ready.push_back(Output::Tab(TabCommand::Show(name)));
+                Vec::new()
+            },
+            Command::View(name) => {
+                let known = name.as_ref().is_none_or(|name| self.zones.iter().chain(self.windows.iter()).any(|zone| &zone.name == name));
+                if !known {
+                    return vec![Message::from_console(format!("[console] no window named {}, /view <module> shows the window of a SubConsole", name.unwrap_or_default()))];
+                }
+                self.ready.push_back(Output::View(name));
+                Vec::new()
+            },
+            Command::Split(command) => self.run_split_command(command),
+            Command::Copy(count) => {
+                let target = match count {
+                    Some(count) => CopyTarget::Last(count),
+                    None => CopyTarget::Selection,
+                };
+                self.ready.push_back(Output::Copy(target));
+                Vec::new()
+            },
+            Command::Export { path: None, .. } => {
+                vec![Message::from_console("[console] usage: /export <path> [text|json|html]".to_string())]
+            },
+            Command::Export { path: Some(path), format } => {
+                let path = std::path::PathBuf::from(path);
+                let format = match format.as_deref().map(ExportFormat::parse) {
+                    None => ExportFormat::from_path(&path),
+                    Some(Some(format)) => format,
+                    Some(None) => return vec![Message::from_console("[console] unknown export format, use text, json or html".to_string())],
+                };
+                self.export_scrollback(path, format);
+                Vec::new()
+            },
+            Command::EditMode(mode) => {
+                match mode.as_deref().map(EditMode::parse) {
+                    None => vec![Message::from_console(format!("[console] editing mode is {:?}", self.edit_mode))],
+                    Some(Some(mode)) => {
+                        self.set_edit_mode(mode);
+                        vec![Message::from_console(format!("[console] editing mode set to {:?}", self.edit_mode))]
+                    },
+                    Some(None) => vec![Message::from_console("[console] unknown editing mode, use vi or emacs".to_string())],
+                }
+            },
+            //a plugin or a script may have defined the name
+            Command::Unknown(line) if self.plugins.run_command(&line) => Vec::new(),
+            Command::Unknown(line) => match self.scripts.run_command(&line) {
+                Some(lines) => lines,
+                None => vec![Message::from_console(format!("[console] unknown command: {}", line))],
+            },
+        }
+    }
+
+    fn run_filter_command(&mut self, command: RuleCommand) -> Vec<Message> {
+        match command {
+            RuleCommand::List => {
+                let rules = self.filter_rules.rules();
+                let mut lines = vec![Message::from_console(format!(
+                    "[filter] {} rules, {} messages filtered out", rules.len(), self.filter_rules.filtered()
+                ))];
+                for (index, rule) in rules.iter().enumerate() {
+                    lines.push(Message::from_console(format!("[filter] {}. {} ({} hits)", index + 1, rule, rule.hits)));
+                }
+                lines
+            },
+            RuleCommand::Add(rule) => match FilterRule::parse(&rule) {
+                Ok(rule) => {
+                    let reply = format!("[filter] added {}. {}", self.filter_rules.rules().len() + 1, rule);
+                    self.add_filter(rule);
+                    vec![Message::from_console(reply)]
+                },
+                Err(e) => vec![Message::from_console(format!("[filter] {}, eg. /filter add exclude sender heartbeat*", e))],
+            },
+            RuleCommand::Remove(Some(number)) => match self.remove_filter(number - 1) {
+                Some(rule) => vec![Message::from_console(format!("[filter] removed {}", rule))],
+                None => vec![Message::from_console(format!("[filter] no rule {}", number))],
+            },
+            RuleCommand::Remove(None) => vec![Message::from_console("[filter] usage: /filter remove <number>".to_string())],
+            RuleCommand::Clear => {
+                self.clear_filters();
+                vec![Message::from_console("[filter] removed every rule".to_string())]
+            },
+            RuleCommand::Unknown(name) => {
+                vec![Message::from_console(format!("[filter] unknown subcommand {}, use list, add, remove or clear", name))]
+            },
+        }
+    }
+
+    fn run_alert_command(&mut self, command: RuleCommand) -> Vec<Message> {
+        match command {
+            RuleCommand::List => {
+                let rules = self.alert_rules.rules();
+                let mut lines = vec![Message::from_console(format!("[alert] {} rules", rules.len()))];
+                for (index, rule) in rules.iter().enumerate() {
+                    lines.push(Message::from_console(format!("[alert] {}. {} ({} hits)", index + 1, rule, rule.hits)));
+                }
+                lines
+            },
+            RuleCommand::Add(rule) => match AlertRule::parse(&rule) {
+                Ok(rule) => {
+                    let reply = format!("[alert] added {}. {}", self.alert_rules.rules().len() + 1, rule);
+                    self.add_alert_rule(rule);
+                    vec![Message::from_console(reply)]
+                },
+                Err(e) => vec![Message::from_console(format!("[alert] {}, eg. /alert add bell,flash when priority Warning+", e))],
+            },
+            RuleCommand::Remove(Some(number)) => match self.remove_alert_rule(number - 1) {
+                Some(rule) => vec![Message::from_console(format!("[alert] removed {}", rule))],
+                None => vec![Message::from_console(format!("[alert] no rule {}", number))],
+            },
+            RuleCommand::Remove(None) => vec![Message::from_console("[alert] usage: /alert remove <number>".to_string())],
+            RuleCommand::Clear => {
+                self.clear_alert_rules();
+                vec![Message::from_console("[alert] removed every rule".to_string())]
+            },
+            RuleCommand::Unknown(name) => {
+                vec![Message::from_console(format!("[alert] unknown subcommand {}, use list, add, remove or clear", name))]
+            },
+        }
+    }
+
+    /**
+     * Do what the alert rules a module message matched say. Pin changes the message before it is drawn,
+     * the bell, flash and desktop notification it returns go right behind it, scripts and webhooks on the side
+     */
+    fn run_alerts(&mut self, sender_name: &str, message: &mut Message) -> Alert {
+        let actions = self.alert_rules.fire(sender_name, message);
+        let mut alert = Alert::NONE;
+        for action in actions {
+            match action {
+                AlertAction::Bell => alert.bell = true,
+                AlertAction::Flash => alert.flash = true,
+                AlertAction::Desktop => alert.desktop = true,
+                AlertAction::Pin => message.pinned = true,
+                AlertAction::Script(script) => {
+                    let replies = self.scripts.on_alert(&script, sender_name, message);
+                    self.ready.extend(replies.into_iter().map(Output::Line));
+                },
+                AlertAction::Webhook(url) => {
+                    let line = RenderedLine::new(sender_name.to_string(), message);
+                    let (body, console) = (webhook::payload(&line, &WebhookFormat::Json), self.tx.clone());
+                    tokio::spawn(async move {
+                        if let Err(e) = webhook::post(&url, &body).await {
+                            let note = Message::from_console(format!("[alert] webhook {}: {}", url, e));
+                            let _ = console.send(ConsoleEvent::Message(note)).await;
+                        }
+                    });
+                },
+            }
+        }
+        alert
+    }
+
+    fn run_split_command(&mut self, command: SplitCommand) -> Vec<Message> {
+        let reply = |text: String| vec![Message::from_console(format!("[split] {}", text))];
+        match command {
+            SplitCommand::Start(first, second) => {
+                let split = Split::new(&first, &second);
+                self.ready.push_back(Output::Split(Some(split.zones())));
+                self.split = Some(split);
+                reply(format!("following {} over {}, /split 1 or /split 2 add <rule> filters a pane", first, second))
+            },
+            SplitCommand::Off => match self.split.take() {
+                Some(_) => {
+                    self.split_copies.clear();
+                    self.ready.push_back(Output::Split(None));
+                    reply("back to the zones".to_string())
+                },
+                None => reply("nothing is split".to_string()),
+            },
+            SplitCommand::Status => match self.split.as_ref() {
+                Some(split) => {
+                    let mut lines = Vec::new();
+                    for (index, pane) in split.panes.iter().enumerate() {
+                        lines.extend(reply(format!("{}. {}, {} rules, {} messages filtered out", index + 1, pane.source, pane.filter.rules().len(), pane.filter.filtered())));
+                    }
+                    lines
+                },
+                None => reply("nothing is split, /split <source> [<source>] follows two at once, eg. /split errors all".to_string()),
+            },
+            SplitCommand::Filter { pane, command } => {
+                let filter = match self.split.as_mut() {
+                    Some(split) => &mut split.panes[pane].filter,
+                    None => return reply("nothing is split".to_string()),
+                };
+                let number = pane + 1;
+                match command {
+                    RuleCommand::List => {
+                        let mut lines = reply(format!("pane {}: {} rules", number, filter.rules().len()));
+                        for (index, rule) in filter.rules().iter().enumerate() {
+                            lines.extend(reply(format!("pane {}: {}. {} ({} hits)", number, index + 1, rule, rule.hits)));
+                        }
+                        lines
+                    },
+                    RuleCommand::Add(rule) => match FilterRule::parse(&rule) {
+                        Ok(rule) => {
+                            let text = format!("pane {}: added {}. {}", number, filter.rules().len() + 1, rule);
+                            filter.add(rule);
+                            reply(text)
+                        },
+                        Err(e) => reply(format!("{}, eg. /split {} add include priority Warning+", e, number)),
+                    },
+                    RuleCommand::Remove(Some(index)) => match filter.remove(index - 1) {
+                        Some(rule) => reply(format!("pane {}: removed {}", number, rule)),
+                        None => reply(format!("pane {}: no rule {}", number, index)),
+                    },
+                    RuleCommand::Remove(None) => reply(format!("usage: /split {} remove <number>", number)),
+                    RuleCommand::Clear => {
+                        filter.clear();
+                        reply(format!("pane {}: removed every rule", number))
+                    },
+                    RuleCommand::Unknown(name) => reply(format!("unknown subcommand {}, use list, add, remove or clear", name)),
+                }
+            },
+        }
+    }
+
+    /**
+     * /senders, every known sender with its status and capabilities
+     */
+    fn list_senders(&self) -> Vec<Message> {
+        let entries = self.phonebook_entries();
+        let mut lines = vec![Message::from_console(format!("[senders] {} known senders", entries.len()))];
+        for entry in entries {
+            let capabilities = self.capabilities_of(entry.name.clone()).map_or("no handle".to_string(), |capabilities| capabilities.to_string());
+            lines.push(Message::from_console(format!("[senders] {} {:?} ({})", entry.name, entry.status, capabilities)));
+        }
+        lines
+    }
+
+    /**
+     * /status <name>, everything the phonebook knows about one sender
+     */
+    fn show_sender(&self, name: &str) -> Vec<Message> {
+        let entry = match self.phonebook_entry(name) {
+            Some(entry) => entry,
+            None => return vec![Message::from_console(format!("[senders] no sender named {}", name))],
+        };
+        let capabilities = self.capabilities_of(name.to_string()).map_or("no handle".to_string(), |capabilities| capabilities.to_string());
+        let heartbeat = entry.heartbeat.map_or("none".to_string(), |interval| format!("every {}s", interval.as_secs_f64()));
+        vec![Message::from_console(format!(
+            "[senders] {} is {:?}, {} messages, last seen {}s ago, heartbeat {}, may {}",
+            entry.name, entry.status, entry.messages, entry.last_seen.elapsed().as_secs(), heartbeat, capabilities
+        ))]
+    }
+
+    /**
+     * /ban <name>, the blacklist note comes from add_to_blacklist itself
+     */
+    fn ban(&mut self, name: &str) -> Vec<Message> {
+        match self.phonebook_entry(name).map(|entry| entry.status) {
+            None => vec![Message::from_console(format!("[senders] no sender named {}", name))],
+            Some(SenderStatus::BlackListed) => vec![Message::from_console(format!("[senders] {} is already banned", name))],
+            Some(SenderStatus::Authorized) | Some(SenderStatus::Stale) => {
+                self.add_to_blacklist(name.to_string());
+                Vec::new()
+            },
+            //not on the Authorized list, blacklisting would not find it, so it is put back first
+            Some(_) => {
+                self.reauthorize(name.to_string());
+                self.add_to_blacklist(name.to_string());
+                Vec::new()
+            },
+        }
+    }
+
+    /**
+     * /unban <name>, takes the sender off the blacklist and authorizes it again
+     */
+    fn unban(&mut self, name: &str) -> Vec<Message> {
+        match self.phonebook_entry(name).map(|entry| entry.status) {
+            None => vec![Message::from_console(format!("[senders] no sender named {}", name))],
+            Some(SenderStatus::BlackListed) => {
+                self.remove_from_blacklist(name.to_string());
+                self.reauthorize(name.to_string());
+                Vec::new()
+            },
+            Some(status) => vec![Message::from_console(format!("[senders] {} is not banned ({:?})", name, status))],
+        }
+    }
+
+    fn run_macro_command(&mut self, command: MacroCommand) -> Vec<Message> {
+        let reply = match command {
+            MacroCommand::List => {
+                let macros = self.macros.list();
+                let mut lines = vec![Message::from_console(format!("[macro] {} macros", macros.len()))];
+                for (name, count) in macros {
+                    lines.push(Message::from_console(format!("[macro] {} ({} lines)", name, count)));
+                }
+                return lines;
+            },
+            MacroCommand::Record(Some(name)) => match self.macros.recording() {
+                Some(current) => format!("[macro] already recording {}, /macro stop first", current),
+                None => {
+                    let reply = format!("[macro] recording {}, /macro stop when done", name);
+                    self.macros.start(name);
+                    reply
+                },
+            },
+            MacroCommand::Record(None) => "[macro] usage: /macro record <name>".to_string(),
+            MacroCommand::Stop => match self.macros.stop() {
+                Some(Ok((name, count))) => format!("[macro] saved {} ({} lines)", name, count),
+                Some(Err(e)) => format!("[macro] kept for this session but could not save: {}", e),
+                None => "[macro] not recording".to_string(),
+            },
+            MacroCommand::Play { name: Some(name), timed } => match self.macros.get(&name).cloned() {
+                Some(steps) => {
+                    let tx = self.tx.clone();
+                    //sent back through the channel so every line goes through the hooks and commands like a typed one
+                    tokio::spawn(async move {
+                        for step in steps {
+                            if timed && step.delay_ms > 0 {
+                                tokio::time::sleep(tokio::time::Duration::from_millis(step.delay_ms)).await;
+                            }
+                            if tx.send(ConsoleEvent::UserInput(step.line)).await.is_err() {
+                                break;
+                            }
+                        }
+                    });
+                    format!("[macro] playing {}", name)
+                },
+                None => format!("[macro] no macro named {}", name),
+            },
+            MacroCommand::Play { name: None, .. } => "[macro] usage: /macro play <name> [timed]".to_string(),
+            MacroCommand::Delete(Some(name)) => match self.macros.remove(&name) {
+                Ok(true) => format!("[macro] deleted {}", name),
+                Ok(false) => format!("[macro] no macro named {}", name),
+                Err(e) => format!("[macro] deleted {} but could not save: {}", name, e),
+            },
+            MacroCommand::Delete(None) => "[macro] usage: /macro delete <name>".to_string(),
+            MacroCommand::Unknown(name) => format!("[macro] unknown subcommand {}, use record, stop, play, list or delete", name),
+        };
+        vec![Message::from_console(reply)]
+    }
+
+    fn run_schedule_command(&mut self, command: ScheduleCommand) -> Vec<Message> {
+        let reply = match command {
+            ScheduleCommand::List => {
+                let now = Instant::now();
+                let timers = self.scheduled();
+                let mut lines = vec![Message::from_console(format!("[schedule] {} scheduled", timers.len()))];
+                for timer in timers {
+                    let when = match timer.every {
+                        Some(every) => format!("every {}s, next in {}s", every.as_secs(), timer.due.saturating_duration_since(now).as_secs()),
+                        None => format!("in {}s", timer.due.saturating_duration_since(now).as_secs()),
+                    };
+                    lines.push(Message::from_console(format!(
+                        "[schedule] {}. {} from {}: {}", timer.id, when, self.display_name(&timer.message.sender), timer.message.payload
+                    )));
+                }
+                return lines;
+            },
+            ScheduleCommand::Add { text, delay, repeat } => {
+                let message = Message::from_console(text);
+                match repeat {
+                    true => format!("[schedule] {} every {}s", self.send_every(message, delay), delay.as_secs()),
+                    false => format!("[schedule] {} in {}s", self.send_after(message, delay), delay.as_secs()),
+                }
+            },
+            ScheduleCommand::Cancel(Some(id)) => match self.cancel_scheduled(id) {
+                true => format!("[schedule] cancelled {}", id),
+                false => format!("[schedule] nothing scheduled with id {}", id),
+            },
+            ScheduleCommand::Cancel(None) => "[schedule] usage: /schedule cancel <id>".to_string(),
+            ScheduleCommand::Invalid(reason) => format!("[schedule] {}", reason),
+        };
+        vec![Message::from_console(reply)]
+    }
+
+    fn run_highlight_command(&mut self, command: RuleCommand) -> Vec<Message> {
+        match command {
+            RuleCommand::List => {
+                let rules = self.highlights.rules();
+                let mut lines = vec![Message::from_console(format!("[highlight] {} rules", rules.len()))];
+                for (index, rule) in rules.iter().enumerate() {
+                    lines.push(Message::from_console(format!("[highlight] {}. {}", index + 1, rule)));
+                }
+                lines
+            },
+            RuleCommand::Add(rule) => {
+                let parsed = match rule.split_once(char::is_whitespace) {
+                    Some((style, pattern)) => HighlightRule::new(pattern.trim(), style),
+                    None => Err("a rule is a style and a pattern".to_string()),
+                };
+                match parsed {
+                    Ok(rule) => {
+                        let reply = format!("[highlight] added {}. {}", self.highlights.rules().len() + 1, rule);
+                        self.add_highlight(rule);
+                        vec![Message::from_console(reply)]
+                    },
+                    Err(e) => vec![Message::from_console(format!("[highlight] {}, eg. /highlight add red,bold ERR-\\d+", e))],
+                }
+            },
+            RuleCommand::Remove(Some(number)) => match self.remove_highlight(number - 1) {
+                Some(rule) => vec![Message::from_console(format!("[highlight] removed {}", rule))],
+                None => vec![Message::from_console(format!("[highlight] no rule {}", number))],
+            },
+            RuleCommand::Remove(None) => vec![Message::from_console("[highlight] usage: /highlight remove <number>".to_string())],
+            RuleCommand::Clear => {
+                self.clear_highlights();
+                vec![Message::from_console("[highlight] removed every rule".to_string())]
+            },
+            RuleCommand::Unknown(name) => {
+                vec![Message::from_console(format!("[highlight] unknown subcommand {}, use list, add, remove or clear", name))]
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::capability::Capabilities;
+    use crate::broker::shell::ShellPolicy;
+    use crate::user_input::macros::MacroStep;
+
+    fn shell_console(allow: &[&str]) -> Console {
+        let mut console: Console = Console::default();
+        console.set_shell_policy(ShellPolicy { allow: allow.iter().map(|pattern| pattern.to_string()).collect(), confirm: Vec::new() });
+        console
+    }
+
+    /**
+     * The first line the broker comes up with that starts with prefix, past the attributed echo and any other note
+     */
+    async fn reply(console: &mut Console, prefix: &str) -> String {
+        loop {
+            if let Some(Output::Line(message)) = console.next_output().await {
+                if message.payload.starts_with(prefix) {
+                    return message.payload.to_string();
+                }
+            }
+        }
+    }
+
+    async fn session_input(console: &mut Console, client: &str, line: &str) {
+        let event = ConsoleEvent::SessionInput { client: client.to_string(), line: line.to_string() };
+        console.tx.send(event).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_session_client_called_user_may_not_run_sh() {
+        let mut console = shell_console(&["*"]);
+        session_input(&mut console, USER_SENDER, "/sh echo hi").await;
+        assert_eq!(reply(&mut console, "[console]").await, "[console] only the user at the terminal may run /sh");
+    }
+
+    #[tokio::test]
+    async fn a_module_called_user_may_not_run_sh() {
+        let mut console = shell_console(&["*"]);
+        console.set_default_capabilities(Capabilities::all());
+        let handle = console.new_sender(USER_SENDER.to_string());
+        handle.run_command("/sh echo hi".to_string()).await.unwrap();
+        assert_eq!(reply(&mut console, "[console]").await, "[console] only the user at the terminal may run /sh");
+    }
+
+    #[tokio::test]
+    async fn the_terminal_gets_past_the_origin_to_the_policy() {
+        let mut console = shell_console(&[]);
+        console.tx.send(ConsoleEvent::UserInput("/sh echo hi".to_string())).await.unwrap();
+        assert_eq!(reply(&mut console, "[sh]").await, "[sh] echo hi is not allowed, see the [shell] allow list");
+    }
+
+    #[tokio::test]
+    async fn a_session_client_may_not_reach_into_the_host() {
+        let mut console = shell_console(&["*"]);
+        console.new_sender("db".to_string());
+        let path = std::env::temp_dir().join("dualzone-session-export.txt");
+        let export = format!("/export {}", path.display());
+        for line in [export.as_str(), "/proc send server reload", "/proc kill server", "/scripts reload", "/ban db", "/unban db", "/copy 3"] {
+            session_input(&mut console, "alice", line).await;
+            let name = line[1..].split_whitespace().next().unwrap();
+            assert_eq!(reply(&mut console, "[console]").await, format!("[console] only the user at the terminal may run /{}", name));
+        }
+        assert!(!path.exists());
+        assert!(matches!(console.get_sender_status(console.generate_id("db".to_string())), SenderStatus::Authorized));
+    }
+
+    #[tokio::test]
+    async fn a_session_client_may_not_play_a_macro_as_the_terminal() {
+        let mut console = shell_console(&["*"]);
+        console.macros.insert("deploy".to_string(), vec![MacroStep { line: "/sh echo hi".to_string(), delay_ms: 0 }]).unwrap();
+        session_input(&mut console, "alice", "/macro play deploy").await;
+        assert_eq!(reply(&mut console, "[console]").await, "[console] only the user at the terminal may run /macro");
+        //nothing was handed back to the channel to be played as typed at the terminal
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(console.tx.stats().depth, 0);
+        assert!(console.ready.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_session_client_still_runs_what_stays_in_the_console() {
+        let mut console = shell_console(&[]);
+        session_input(&mut console, "alice", "/level").await;
+        assert_eq!(reply(&mut console, "[console]").await, "[console] filter level is Ignore");
+    }
+}
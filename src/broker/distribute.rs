@@ -0,0 +1,139 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::future::Future;
+use std::sync::Arc;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::{ConsoleEvent, Message};
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+
+/**
+ * Reasons a bridge could not carry a message
+ */
+#[derive(Debug, Clone)]
+pub enum BridgeError {
+    Connect(String),
+    Send(String),
+    Closed,
+}
+
+impl std::fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BridgeError::Connect(e) => write!(f, "could not connect: {}", e),
+            BridgeError::Send(e) => write!(f, "could not send: {}", e),
+            BridgeError::Closed => write!(f, "the bridge is closed"),
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+/**
+ * A line of a SubConsole as it goes between consoles, JSON on the wire.
+ * origin is the console it was sent on (see Console::distribute_subconsoles), subconsole the SubConsole's name.
+ * text is shared with the message it was made from, it is only copied when it is written to the wire
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgedMessage {
+    pub origin: String,
+    pub subconsole: String,
+    pub priority: PriorityStatus,
+    pub text: Arc<str>,
+    pub topic: Option<String>,
+}
+
+impl BridgedMessage {
+    /**
+     * The name the SubConsole goes by in the consoles it is bridged to, eg. builder@ci-1
+     */
+    pub fn sender_name(&self) -> String {
+        format!("{}@{}", self.subconsole, self.origin)
+    }
+}
+
+/**
+ * Carries the output of SubConsoles between consoles in different processes or on different machines, over a message bus.
+ *  ~ publish hands a line of one of this console's SubConsoles to the others.
+ *  ~ receive waits for the next line of a SubConsole of another console, None once the bridge is closed for good.
+ *    A bus that also delivers the console's own lines back may, run leaves out the ones from its own origin.
+ * The NATS bridge is one (broker::nats::NatsBridge, the nats feature), a RabbitMQ one would implement the same.
+ */
+pub trait SubConsoleBridge: Send + 'static {
+    fn publish(&mut self, message: BridgedMessage) -> impl Future<Output = Result<(), BridgeError>> + Send;
+    fn receive(&mut self) -> impl Future<Output = Option<BridgedMessage>> + Send;
+}
+
+/**
+ * The NATS server the binary distributes its SubConsoles over, see broker::nats::NatsBridge.
+ * Lines go out on <subject>.<subconsole>, origin is the console's name on the bus (default_origin() when None)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct NatsTarget {
+    pub url: String,
+    pub subject: String,
+    pub origin: Option<String>,
+}
+
+/**
+ * The console's end of a bridge, from Console::distribute_subconsoles, that run takes over
+ */
+#[derive(Debug)]
+pub struct SubConsoleLink<T = ()> {
+    pub origin: String,
+    pub(crate) outgoing: mpsc::UnboundedReceiver<BridgedMessage>,
+    pub(crate) console: MailboxSender<T>,
+}
+
+/**
+ * A name for this console on the bus when it isn't given one, the host name and process id
+ */
+pub fn default_origin() -> String {
+    let host = std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME")).unwrap_or_else(|_| "local".to_string());
+    format!("{}:{}", host, std::process::id())
+}
+
+/**
+ * Move lines between the console and the bridge until either goes away.
+ * A line that can't be published is said in the output zone, the bridge keeps going
+ */
+pub async fn run<B: SubConsoleBridge, T>(mut bridge: B, mut link: SubConsoleLink<T>) {
+    loop {
+        tokio::select! {
+            outgoing = link.outgoing.recv() => {
+                let message = match outgoing {
+                    Some(message) => message,
+                    None => return,
+                };
+                if let Err(e) = bridge.publish(message).await {
+                    let note = Message::from_console(format!("[bridge] {}", e));
+                    if link.console.send(ConsoleEvent::Message(note)).await.is_err() {
+                        return;
+                    }
+                }
+            },
+            incoming = bridge.receive() => {
+                let message = match incoming {
+                    Some(message) => message,
+                    None => {
+                        let note = Message::from_console("[bridge] closed, SubConsoles of other consoles no longer show".to_string());
+                        let _ = link.console.send(ConsoleEvent::Message(note)).await;
+                        return;
+                    },
+                };
+                if message.origin == link.origin {
+                    continue;
+                }
+                if link.console.send(ConsoleEvent::Bridged(message)).await.is_err() {
+                    return;
+                }
+            },
+        }
+    }
+}
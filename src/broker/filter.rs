@@ -0,0 +1,208 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use crate::broker::message::{Message, RenderedLine};
+use crate::broker::pattern::{self, Pattern};
+use crate::user_input::structs::PriorityStatus;
+
+/**
+ * What a rule does with the messages it matches
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterAction {
+    Include,
+    Exclude,
+}
+
+/**
+ * What a rule looks at
+ *  ~ Sender and Topic are wildcard patterns (* and ?) over the sender's plain name and the topic, a message without a topic never matches Topic.
+ *  ~ Priority matches that priority, or that priority and above when at_least is set (written Warning+).
+ *  ~ Payload is a regular expression over the text, see pattern::Pattern.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterMatch {
+    Sender(String),
+    Topic(String),
+    Priority { priority: PriorityStatus, at_least: bool },
+    Payload(Pattern),
+}
+
+/**
+ * One filter rule, written the same way in /filter add and in the config file, eg.
+ *
+ *     exclude sender heartbeat*
+ *     include priority Warning+
+ *     exclude payload (?i)^debug:
+ *
+ * hits counts the messages the rule decided on.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterRule {
+    pub action: FilterAction,
+    pub matcher: FilterMatch,
+    pub hits: u64,
+}
+
+impl FilterRule {
+    pub fn new(action: FilterAction, matcher: FilterMatch) -> Self {
+        FilterRule { action, matcher, hits: 0 }
+    }
+
+    pub fn parse(rule: &str) -> Result<FilterRule, String> {
+        let mut parts = rule.trim().splitn(3, ' ');
+        let action = match parts.next().map(|action| action.to_ascii_lowercase()).as_deref() {
+            Some("include") => FilterAction::Include,
+            Some("exclude") => FilterAction::Exclude,
+            _ => return Err("a rule starts with include or exclude".to_string()),
+        };
+        let matcher = FilterMatch::parse(parts.next().unwrap_or(""), parts.next().unwrap_or(""))?;
+        Ok(FilterRule::new(action, matcher))
+    }
+
+    pub fn matches(&self, sender_name: &str, message: &Message) -> bool {
+        self.matcher.matches(sender_name, message)
+    }
+}
+
+impl FilterMatch {
+    /**
+     * What a rule looks at, eg. kind "sender" and value "heartbeat*". Alert rules are written with the same ones
+     */
+    pub fn parse(kind: &str, value: &str) -> Result<FilterMatch, String> {
+        let kind = kind.trim().to_ascii_lowercase();
+        if kind.is_empty() {
+            return Err("include or exclude what, sender, topic, priority or payload".to_string());
+        }
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(format!("{} needs a pattern", kind));
+        }
+        let matcher = match kind.as_str() {
+            "sender" => FilterMatch::Sender(value.to_string()),
+            "topic" => FilterMatch::Topic(value.to_string()),
+            "priority" => {
+                let (name, at_least) = match value.strip_suffix('+') {
+                    Some(name) => (name, true),
+                    None => (value, false),
+                };
+                let priority = PriorityStatus::parse(name).ok_or(format!("unknown priority: {}", name))?;
+                FilterMatch::Priority { priority, at_least }
+            },
+            "payload" => FilterMatch::Payload(Pattern::new(value).map_err(|e| format!("bad pattern: {}", e))?),
+            other => return Err(format!("unknown rule kind: {}", other)),
+        };
+        Ok(matcher)
+    }
+
+    pub fn matches(&self, sender_name: &str, message: &Message) -> bool {
+        self.matches_parts(sender_name, message.topic.as_deref(), &message.priority, &message.payload)
+    }
+
+    /**
+     * The same for a line that already left the broker, eg. in a webhook sink
+     */
+    pub fn matches_line(&self, line: &RenderedLine) -> bool {
+        self.matches_parts(&line.sender, line.topic.as_deref(), &line.priority, &line.text)
+    }
+
+    fn matches_parts(&self, sender_name: &str, topic: Option<&str>, priority: &PriorityStatus, text: &str) -> bool {
+        match self {
+            FilterMatch::Sender(pattern) => pattern::glob_match(pattern, sender_name),
+            FilterMatch::Topic(pattern) => topic.is_some_and(|topic| pattern::glob_match(pattern, topic)),
+            FilterMatch::Priority { priority: wanted, at_least: false } => priority == wanted,
+            FilterMatch::Priority { priority: wanted, at_least: true } => priority.severity() >= wanted.severity(),
+            FilterMatch::Payload(pattern) => pattern.is_match(text),
+        }
+    }
+}
+
+impl fmt::Display for FilterMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterMatch::Sender(pattern) => write!(f, "sender {}", pattern),
+            FilterMatch::Topic(pattern) => write!(f, "topic {}", pattern),
+            FilterMatch::Priority { priority, at_least } => write!(f, "priority {:?}{}", priority, if *at_least { "+" } else { "" }),
+            FilterMatch::Payload(pattern) => write!(f, "payload {}", pattern),
+        }
+    }
+}
+
+impl fmt::Display for FilterRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            FilterAction::Include => "include",
+            FilterAction::Exclude => "exclude",
+        };
+        write!(f, "{} {}", action, self.matcher)
+    }
+}
+
+/**
+ * The filter rules of the Console, checked in the broker after the filter level and before the rate limits.
+ *  ~ Rules are tried in order and the first one that matches decides.
+ *  ~ A message no rule matches is shown, unless there is an include rule, then only what some rule includes is shown.
+ *  ~ Filtered out messages are dropped quietly like those under the filter level, they don't go to the dead-letter queue.
+ *  ~ Console and user lines are never filtered.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct FilterRules {
+    rules: Vec<FilterRule>,
+    filtered: u64,
+}
+
+impl FilterRules {
+    pub fn add(&mut self, rule: FilterRule) {
+        self.rules.push(rule);
+    }
+
+    /**
+     * Remove the rule at index (0 based), returns it if there was one
+     */
+    pub fn remove(&mut self, index: usize) -> Option<FilterRule> {
+        if index < self.rules.len() {
+            Some(self.rules.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn rules(&self) -> &[FilterRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /**
+     * How many messages were filtered out so far
+     */
+    pub fn filtered(&self) -> u64 {
+        self.filtered
+    }
+
+    /**
+     * Whether a message gets through, counting the hit on the rule that decided
+     */
+    pub fn admits(&mut self, sender_name: &str, message: &Message) -> bool {
+        let admitted = match self.rules.iter_mut().find(|rule| rule.matches(sender_name, message)) {
+            Some(rule) => {
+                rule.hits += 1;
+                rule.action == FilterAction::Include
+            },
+            None => !self.rules.iter().any(|rule| rule.action == FilterAction::Include),
+        };
+        if !admitted {
+            self.filtered += 1;
+        }
+        admitted
+    }
+}
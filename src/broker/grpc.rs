@@ -0,0 +1,177 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::ConsoleEvent;
+use crate::broker::remote::{RemoteListen, RemoteReply, RemoteRequest};
+use crate::broker::session::{ServerFrame, SessionHub};
+use crate::output::recorder::Recorded;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+/**
+ * The messages and service generated from proto/dualzone.proto
+ */
+pub mod proto {
+    tonic::include_proto!("dualzone");
+}
+
+use proto::broker_server::{Broker, BrokerServer};
+use proto::output_event::Event;
+
+/**
+ * The name a call is done as, grpc:<sender> when its x-dualzone-client and x-dualzone-token metadata prove it is that sender
+ * (see RemoteListen::identify), otherwise grpc
+ */
+fn client_of<T>(listen: &RemoteListen, request: &Request<T>) -> String {
+    let metadata = |key: &str| request.metadata().get(key).and_then(|value| value.to_str().ok());
+    match listen.identify(metadata("x-dualzone-client"), metadata("x-dualzone-token")) {
+        Some(name) => format!("grpc:{}", name),
+        None => "grpc".to_string(),
+    }
+}
+
+fn to_event(recorded: Recorded) -> proto::OutputEvent {
+    let event = match recorded {
+        //prost's strings are owned, the shared text is copied once here on its way to the wire
+        Recorded::Line(message) => Event::Line(proto::Line {
+            id: message.id,
+            sender: message.sender,
+            priority: format!("{:?}", message.priority),
+            text: message.payload.to_string(),
+            topic: message.topic,
+            zone: message.zone,
+        }),
+        Recorded::Dismiss(id) => Event::Dismissed(proto::Dismissed { id }),
+        Recorded::Retract { sender, id } => Event::Retracted(proto::Retracted { sender, id }),
+    };
+    proto::OutputEvent { event: Some(event) }
+}
+
+struct BrokerService {
+    console: MailboxSender,
+    hub: SessionHub,
+    listen: RemoteListen,
+}
+
+impl BrokerService {
+    /**
+     * Hand a request to the broker and wait for its answer, a Refused one is FAILED_PRECONDITION
+     */
+    async fn ask(&self, client: String, request: RemoteRequest) -> Result<RemoteReply, Status> {
+        let gone = || Status::unavailable("the console has shut down");
+        let (reply, answer) = oneshot::channel();
+        self.console.send(ConsoleEvent::Remote { client, request, reply }).await.map_err(|_| gone())?;
+        match answer.await.map_err(|_| gone())? {
+            RemoteReply::Refused(reason) => Err(Status::failed_precondition(reason)),
+            answer => Ok(answer),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Broker for BrokerService {
+    async fn send_message(&self, request: Request<proto::SendMessageRequest>) -> Result<Response<proto::SendMessageReply>, Status> {
+        let client = client_of(&self.listen, &request);
+        let request = request.into_inner();
+        if request.sender.trim().is_empty() {
+            return Err(Status::invalid_argument("a message needs a sender"));
+        }
+        let priority = match request.priority.as_str() {
+            "" => PriorityStatus::Normal,
+            name => PriorityStatus::parse(name).ok_or_else(|| Status::invalid_argument(format!("unknown priority {}", name)))?,
+        };
+        let remote = RemoteRequest::Send { sender: request.sender, text: request.text, priority, topic: request.topic };
+        match self.ask(client, remote).await? {
+            RemoteReply::Sent(id) => Ok(Response::new(proto::SendMessageReply { id })),
+            _ => Err(Status::internal("unexpected answer from the broker")),
+        }
+    }
+
+    type StreamOutputStream = ReceiverStream<Result<proto::OutputEvent, Status>>;
+
+    async fn stream_output(&self, request: Request<proto::StreamOutputRequest>) -> Result<Response<Self::StreamOutputStream>, Status> {
+        let (backlog, mut live) = self.hub.subscribe();
+        let backlog = if request.into_inner().backlog { backlog } else { Vec::new() };
+        let (tx, rx) = mpsc::channel(256);
+        tokio::spawn(async move {
+            for recorded in backlog {
+                if tx.send(Ok(to_event(recorded))).await.is_err() {
+                    return;
+                }
+            }
+            loop {
+                let event = match live.recv().await {
+                    Ok(ServerFrame::Event(recorded)) => Ok(to_event(recorded)),
+                    Ok(ServerFrame::Bye(reason)) => Err(Status::unavailable(reason)),
+                    Ok(ServerFrame::Welcome { .. }) | Ok(ServerFrame::Private { .. }) => continue,
+                    //a caller this far behind has missed lines, better it knows than gets a gap
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => Err(Status::data_loss(format!("fell {} changes behind", skipped))),
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                let last = event.is_err();
+                if tx.send(event).await.is_err() || last {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn list_senders(&self, request: Request<proto::ListSendersRequest>) -> Result<Response<proto::ListSendersReply>, Status> {
+        let senders = match self.ask(client_of(&self.listen, &request), RemoteRequest::ListSenders).await? {
+            RemoteReply::Senders(senders) => senders,
+            _ => return Err(Status::internal("unexpected answer from the broker")),
+        };
+        let senders = senders.into_iter().map(|sender| proto::Sender {
+            name: sender.name,
+            id: sender.id,
+            status: format!("{:?}", sender.status),
+            messages: sender.messages,
+            capabilities: sender.capabilities,
+        }).collect();
+        Ok(Response::new(proto::ListSendersReply { senders }))
+    }
+
+    async fn ban(&self, request: Request<proto::SenderName>) -> Result<Response<proto::BanReply>, Status> {
+        let client = client_of(&self.listen, &request);
+        self.ask(client, RemoteRequest::Ban(request.into_inner().name)).await?;
+        Ok(Response::new(proto::BanReply {}))
+    }
+
+    async fn unban(&self, request: Request<proto::SenderName>) -> Result<Response<proto::BanReply>, Status> {
+        let client = client_of(&self.listen, &request);
+        self.ask(client, RemoteRequest::Unban(request.into_inner().name)).await?;
+        Ok(Response::new(proto::BanReply {}))
+    }
+}
+
+/**
+ * Serve the broker over gRPC until the server fails, see proto/dualzone.proto for the service.
+ *  ~ A call with the x-dualzone-client and x-dualzone-token metadata of one of listen's senders is done as it (grpc:<name>)
+ *    in the audit log, like a command typed at a session client, any other as grpc. Send only sends as that sender,
+ *    see remote::caller_identity.
+ *  ~ StreamOutput is what the session's clients see, from the same hub.
+ * Refuses to serve an address other than loopback without a token, see RemoteListen::check_exposure
+ */
+pub async fn serve(listen: RemoteListen, hub: SessionHub, console: MailboxSender) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    listen.check_exposure()?;
+    let addr = listen.addr;
+    let service = BrokerService { console, hub, listen: listen.clone() };
+    let check = move |request: Request<()>| -> Result<Request<()>, Status> {
+        let header = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+        match listen.authorizes(header) {
+            true => Ok(request),
+            false => Err(Status::unauthenticated("a valid token is needed")),
+        }
+    };
+    let service = BrokerServer::with_interceptor(service, check);
+    tonic::transport::Server::builder().add_service(service).serve(addr).await?;
+    Ok(())
+}
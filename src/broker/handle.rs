@@ -0,0 +1,541 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::user_input::structs::{SenderStatus, PriorityStatus, SubConsole, SubWindow};
+use crate::broker::message::{self, ConsoleEvent, Message};
+use crate::broker::mailbox::{MailboxSender, OverflowPolicy, SendOutcome, TrySendError};
+use crate::broker::dead_letter::{DeadLetterQueue, RejectReason};
+use crate::broker::routing::{DeliveryReport, BROADCAST_TARGET};
+use crate::broker::capability::{Capabilities, Capability};
+use crate::broker::schedule::Scheduled;
+use crate::output::progress::Progress;
+use crate::output::spinner::Spinner;
+use crate::output::table::Table;
+use crate::output::widgets::Widget;
+use crate::user_input::input::InputRequest;
+use crate::user_input::dialog;
+use crate::user_input::form::{Form, FormAnswers, FormDialog};
+use crate::output::clipboard::CopyTarget;
+
+//External
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tokio::time::{Duration, Instant};
+
+/**
+ * Handle given to a module by Console::new_sender().
+ *  ~ The status is shared with the Console, so blacklisting or revoking a sender takes effect on every clone of its handle immediately.
+ *  ~ Only an Authorized handle can send, anything else is rejected before the message reaches the channel.
+ *  ~ Once revoked a handle can never be reactivated, the module has to ask for a new sender.
+ *  ~ The overflow policy is shared the same way, so Console::set_overflow_policy() applies to handles already given out.
+ *  ~ So are the capabilities (Console::set_capabilities), a handle without Print can still route to inboxes but not show anything.
+ *  ~ T is the event type of the Console that gave it out, what emit() takes, () for a plain Console.
+ */
+#[derive(Debug)]
+pub struct SenderHandle<T = ()> {
+    id: String,
+    name: String,
+    tx: MailboxSender<T>,
+    state: Arc<Mutex<HandleState>>,
+    dead_letters: Arc<Mutex<DeadLetterQueue>>,
+}
+
+//not derived, that would want T: Clone
+impl<T> Clone for SenderHandle<T> {
+    fn clone(&self) -> Self {
+        SenderHandle { id: self.id.clone(), name: self.name.clone(), tx: self.tx.clone(), state: self.state.clone(), dead_letters: self.dead_letters.clone() }
+    }
+}
+
+/**
+ * The part of a handle the Console can change after the handle was given out
+ */
+#[derive(Debug, Clone)]
+pub struct HandleState {
+    pub status: SenderStatus,
+    pub overflow: OverflowPolicy,
+    pub capabilities: Capabilities,
+}
+
+/**
+ * Reasons a handle could not send a message
+ */
+#[derive(Debug, Clone)]
+pub enum HandleError {
+    NotAuthorized(SenderStatus),
+    NotPermitted(Capability),
+    Revoked,
+    ChannelFull,
+    ChannelClosed,
+}
+
+impl std::fmt::Display for HandleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandleError::NotAuthorized(status) => write!(f, "sender is not authorized ({:?})", status),
+            HandleError::NotPermitted(capability) => write!(f, "sender may not {}", capability),
+            HandleError::Revoked => write!(f, "sender handle was revoked"),
+            HandleError::ChannelFull => write!(f, "console channel is full"),
+            HandleError::ChannelClosed => write!(f, "console channel is closed"),
+        }
+    }
+}
+
+impl std::error::Error for HandleError {}
+
+impl<T> SenderHandle<T> {
+    /**
+     * Only called by the Console, which keeps the other end of the shared status
+     */
+    pub(crate) fn new(id: String, name: String, tx: MailboxSender<T>, state: Arc<Mutex<HandleState>>, dead_letters: Arc<Mutex<DeadLetterQueue>>) -> Self {
+        SenderHandle { id, name, tx, state, dead_letters }
+    }
+
+    /**
+     * The signed id of this sender
+     */
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /**
+     * The plaintext name this sender was registered with
+     */
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Current status of this sender as seen by the Console
+     */
+    pub fn status(&self) -> SenderStatus {
+        self.state.lock().unwrap().status.clone()
+    }
+
+    /**
+     * Current overflow policy of this sender
+     */
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.state.lock().unwrap().overflow.clone()
+    }
+
+    /**
+     * Current capabilities of this sender
+     */
+    pub fn capabilities(&self) -> Capabilities {
+        self.state.lock().unwrap().capabilities
+    }
+
+    /**
+     * Check the shared status, and the capability the action needs if any
+     */
+    fn allowed(&self, capability: Option<Capability>) -> Result<(), HandleError> {
+        let state = self.state.lock().unwrap();
+        match state.status {
+            SenderStatus::Authorized => {},
+            SenderStatus::NotInPhonebook => return Err(HandleError::Revoked),
+            ref other => return Err(HandleError::NotAuthorized(other.clone())),
+        }
+        match capability {
+            Some(capability) if !state.capabilities.allows(capability) => Err(HandleError::NotPermitted(capability)),
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Check the shared status before anything is put on the channel, a rejected message goes to the dead-letter queue
+     */
+    fn check_status(&self, payload: &str, priority: &PriorityStatus, capability: Option<Capability>) -> Result<(), HandleError> {
+        let error = match self.allowed(capability) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+        let reason = match &error {
+            HandleError::NotPermitted(capability) => RejectReason::NotPermitted(*capability),
+            _ => RejectReason::NotAuthorized(self.status()),
+        };
+        let message = Message::new(self.id.clone(), priority.clone(), payload.to_string());
+        self.dead_letters.lock().unwrap().push(message, reason);
+        Err(error)
+    }
+
+    /**
+     * Record whatever the overflow policy dropped
+     */
+    fn record_outcome(&self, outcome: &SendOutcome<T>) {
+        let (event, reason) = match outcome {
+            SendOutcome::DroppedNewest(event) => (event, RejectReason::DroppedNewest),
+            SendOutcome::DroppedOldest(event) => (event, RejectReason::DroppedOldest),
+            _ => return,
+        };
+        match event {
+            ConsoleEvent::Message(message) | ConsoleEvent::Routed { message, .. } => {
+                self.dead_letters.lock().unwrap().push(message.clone(), reason);
+            },
+            _ => {}
+        }
+    }
+
+    /**
+     * Wrap the payload in an envelope signed with this handle's id
+     */
+    fn envelope(&self, payload: String, priority: PriorityStatus) -> ConsoleEvent<T> {
+        ConsoleEvent::Message(Message::new(self.id.clone(), priority, payload))
+    }
+
+    /**
+     * Send a message to the console, a full channel is handled by this sender's overflow policy
+     */
+    pub async fn send(&self, message: String) -> Result<SendOutcome<T>, HandleError> {
+        self.send_with_priority(message, PriorityStatus::Normal).await
+    }
+
+    /**
+     * Send a message with an explicit priority, a full channel is handled by this sender's overflow policy
+     */
+    pub async fn send_with_priority(&self, message: String, priority: PriorityStatus) -> Result<SendOutcome<T>, HandleError> {
+        self.check_status(&message, &priority, Some(Capability::Print))?;
+        let policy = self.overflow_policy();
+        let outcome = self.tx.send_with_policy(self.envelope(message, priority), &policy).await.map_err(|_| HandleError::ChannelClosed)?;
+        self.record_outcome(&outcome);
+        Ok(outcome)
+    }
+
+    /**
+     * Send a prepared message, it must carry this handle's id as sender or it is rejected by the broker
+     */
+    pub(crate) async fn send_message(&self, message: Message) -> Result<SendOutcome<T>, HandleError> {
+        self.check_status(&message.payload, &message.priority, Some(Capability::Print))?;
+        let policy = self.overflow_policy();
+        let outcome = self.tx.send_with_policy(ConsoleEvent::Message(message), &policy).await.map_err(|_| HandleError::ChannelClosed)?;
+        self.record_outcome(&outcome);
+        Ok(outcome)
+    }
+
+    /**
+     * Send a prepared message without waiting
+     */
+    pub(crate) fn try_send_message(&self, message: Message) -> Result<(), HandleError> {
+        self.check_status(&message.payload, &message.priority, Some(Capability::Print))?;
+        match self.tx.try_send(ConsoleEvent::Message(message)) {
+            Ok(_) => Ok(()),
+            Err(TrySendError::Full(event)) => {
+                self.record_outcome(&SendOutcome::DroppedNewest(*event));
+                Err(HandleError::ChannelFull)
+            },
+            Err(TrySendError::Closed(_)) => Err(HandleError::ChannelClosed),
+        }
+    }
+
+    /**
+     * Start a progress bar line in the output zone, total is the amount of work that makes 100%
+     */
+    pub fn progress(&self, label: String, total: u64) -> Progress<T> {
+        Progress::new(self.clone(), label, total)
+    }
+
+    /**
+     * Start a spinner line in the output zone, eg. handle.spinner("indexing…".to_string())
+     */
+    pub fn spinner(&self, label: String) -> Spinner<T> {
+        Spinner::new(self.clone(), label)
+    }
+
+    /**
+     * Send a table, the output zone draws it with box-drawing characters fitted to the zone width
+     */
+    pub async fn send_table(&self, table: Table, priority: PriorityStatus) -> Result<SendOutcome<T>, HandleError> {
+        let message = Message::new(self.id.clone(), priority, table.plain_text()).with_widget(message::next_id(), Widget::Table(table));
+        self.send_message(message).await
+    }
+
+    /**
+     * Ask the user for a value without echoing it, see Console::read_secret. The receiver errors if the user cancels.
+     */
+    pub async fn read_secret(&self, prompt: String) -> Result<oneshot::Receiver<String>, HandleError> {
+        self.allowed(Some(Capability::Print))?;
+        let (reply, answer) = oneshot::channel();
+        let request = InputRequest::Secret { prompt, mask: Some('*'), reply };
+        self.tx.send(ConsoleEvent::Input { sender: self.id.clone(), request }).await.map_err(|_| HandleError::ChannelClosed)?;
+        Ok(answer)
+    }
+
+    /**
+     * Ask the user a yes or no question, see Console::confirm. The receiver errors if the console shuts down first
+     */
+    pub async fn confirm(&self, prompt: impl Into<String>) -> Result<oneshot::Receiver<bool>, HandleError> {
+        self.allowed(Some(Capability::Print))?;
+        let prompt = prompt.into();
+        let (reply, answer) = oneshot::channel();
+        let request = InputRequest::Confirm { default: dialog::default_answer(&prompt), prompt, reply };
+        self.tx.send(ConsoleEvent::Input { sender: self.id.clone(), request }).await.map_err(|_| HandleError::ChannelClosed)?;
+        Ok(answer)
+    }
+
+    /**
+     * Let the user pick one of items, see Console::select. Waits for the choice, Ok(None) if the user cancels
+     */
+    pub async fn select<I: std::fmt::Display + Send>(&self, title: impl Into<String>, items: Vec<I>) -> Result<Option<I>, HandleError> {
+        self.allowed(Some(Capability::Print))?;
+        let (reply, answer) = oneshot::channel();
+        let request = InputRequest::Select { title: title.into(), items: items.iter().map(|item| item.to_string()).collect(), reply };
+        self.tx.send(ConsoleEvent::Input { sender: self.id.clone(), request }).await.map_err(|_| HandleError::ChannelClosed)?;
+        Ok(answer.await.ok().and_then(|index| items.into_iter().nth(index)))
+    }
+
+    /**
+     * Ask the user the fields of a form, see Console::form. The receiver errors if the user cancels
+     */
+    pub async fn form(&self, form: Form) -> Result<oneshot::Receiver<FormAnswers>, HandleError> {
+        self.allowed(Some(Capability::Print))?;
+        let (reply, answer) = oneshot::channel();
+        let request = InputRequest::Form(Box::new(FormDialog::new(form, reply)));
+        self.tx.send(ConsoleEvent::Input { sender: self.id.clone(), request }).await.map_err(|_| HandleError::ChannelClosed)?;
+        Ok(answer)
+    }
+
+    /**
+     * Send a message pinned to the top of its output zone, returns the message id needed to dismiss it
+     */
+    pub async fn send_pinned(&self, message: String, priority: PriorityStatus) -> Result<u64, HandleError> {
+        self.check_status(&message, &priority, Some(Capability::Print))?;
+        let message = Message::new(self.id.clone(), priority, message).pinned();
+        let id = message.id;
+        let policy = self.overflow_policy();
+        let outcome = self.tx.send_with_policy(ConsoleEvent::Message(message), &policy).await.map_err(|_| HandleError::ChannelClosed)?;
+        self.record_outcome(&outcome);
+        Ok(id)
+    }
+
+    /**
+     * Send a message and get its id back, to update or retract it later
+     */
+    pub async fn send_tracked(&self, message: String, priority: PriorityStatus) -> Result<u64, HandleError> {
+        self.send_built(Message::new(self.id.clone(), priority, message)).await
+    }
+
+    /**
+     * Send a message that leaves its output zone after ttl, whatever its priority, returns its id
+     */
+    pub async fn send_with_ttl(&self, message: String, priority: PriorityStatus, ttl: Duration) -> Result<u64, HandleError> {
+        self.send_built(Message::new(self.id.clone(), priority, message).with_ttl(ttl)).await
+    }
+
+    /**
+     * Replace a message this handle sent, the line is drawn again in place, eg. "connecting…" becoming "connected".
+     * The id stays the one of the first message, updates of a line that already expired show as a new line.
+     */
+    pub async fn update(&self, id: u64, message: String, priority: PriorityStatus) -> Result<SendOutcome<T>, HandleError> {
+        self.send_message(Message::new(self.id.clone(), priority, message).replacing(id)).await
+    }
+
+    /**
+     * Take a message this handle sent out of the output zones and their scrollback
+     */
+    pub async fn retract(&self, id: u64) -> Result<(), HandleError> {
+        self.allowed(Some(Capability::Print))?;
+        self.tx.send(ConsoleEvent::Retract { sender: self.id.clone(), id }).await.map_err(|_| HandleError::ChannelClosed)
+    }
+
+    async fn send_built(&self, message: Message) -> Result<u64, HandleError> {
+        let id = message.id;
+        self.send_message(message).await?;
+        Ok(id)
+    }
+
+    /**
+     * Dismiss a message this handle pinned
+     */
+    pub async fn dismiss(&self, id: u64) -> Result<(), HandleError> {
+        self.allowed(Some(Capability::Print))?;
+        self.tx.send(ConsoleEvent::Dismiss { sender: self.id.clone(), id }).await.map_err(|_| HandleError::ChannelClosed)
+    }
+
+    /**
+     * Tell the Console this module is still alive, needed when it was asked for a heartbeat (Console::require_heartbeat)
+     * and has nothing to send for a while. Any message counts as a heartbeat too.
+     */
+    pub async fn heartbeat(&self) -> Result<(), HandleError> {
+        self.allowed(None)?;
+        self.tx.send(ConsoleEvent::Heartbeat(self.id.clone())).await.map_err(|_| HandleError::ChannelClosed)
+    }
+
+    /**
+     * Send a message once delay has passed, returns the id to cancel it with (SenderHandle::cancel_scheduled).
+     * The message is checked again when it fires, a handle blacklisted in between has it dropped.
+     */
+    pub async fn send_after(&self, message: String, priority: PriorityStatus, delay: Duration) -> Result<u64, HandleError> {
+        self.schedule(message, priority, delay, None).await
+    }
+
+    /**
+     * Send a message every interval until cancelled, the first time one interval from now
+     */
+    pub async fn send_every(&self, message: String, priority: PriorityStatus, interval: Duration) -> Result<u64, HandleError> {
+        self.schedule(message, priority, interval, Some(interval)).await
+    }
+
+    async fn schedule(&self, message: String, priority: PriorityStatus, delay: Duration, every: Option<Duration>) -> Result<u64, HandleError> {
+        self.check_status(&message, &priority, Some(Capability::Print))?;
+        let id = message::next_id();
+        let scheduled = Scheduled { id, due: Instant::now() + delay, every, message: Message::new(self.id.clone(), priority, message) };
+        self.tx.send(ConsoleEvent::Schedule(scheduled)).await.map_err(|_| HandleError::ChannelClosed)?;
+        Ok(id)
+    }
+
+    /**
+     * Cancel a message this handle scheduled, ids of other senders are ignored
+     */
+    pub async fn cancel_scheduled(&self, id: u64) -> Result<(), HandleError> {
+        self.allowed(None)?;
+        self.tx.send(ConsoleEvent::Unschedule { sender: self.id.clone(), id }).await.map_err(|_| HandleError::ChannelClosed)
+    }
+
+    /**
+     * Put output on the user's clipboard, eg. the last lines of a report the user asked for
+     */
+    pub async fn copy_output(&self, target: CopyTarget) -> Result<(), HandleError> {
+        self.allowed(Some(Capability::Print))?;
+        self.tx.send(ConsoleEvent::Copy { sender: self.id.clone(), target }).await.map_err(|_| HandleError::ChannelClosed)
+    }
+
+    /**
+     * Send a message tagged with a topic, output zones bound to the topic will show it
+     */
+    pub async fn send_to_topic(&self, topic: String, message: String, priority: PriorityStatus) -> Result<SendOutcome<T>, HandleError> {
+        self.check_status(&message, &priority, Some(Capability::Print))?;
+        let policy = self.overflow_policy();
+        let event = ConsoleEvent::Message(Message::new(self.id.clone(), priority, message).with_topic(topic));
+        let outcome = self.tx.send_with_policy(event, &policy).await.map_err(|_| HandleError::ChannelClosed)?;
+        self.record_outcome(&outcome);
+        Ok(outcome)
+    }
+
+    /**
+     * Send a message threaded with the others of the same correlation id, eg. a request id.
+     * They show as one line, the first and how many more, until the thread is expanded with /expand or Alt+T
+     */
+    pub async fn send_in_thread(&self, correlation: String, message: String, priority: PriorityStatus) -> Result<SendOutcome<T>, HandleError> {
+        self.check_status(&message, &priority, Some(Capability::Print))?;
+        let policy = self.overflow_policy();
+        let event = ConsoleEvent::Message(Message::new(self.id.clone(), priority, message).with_correlation(correlation));
+        let outcome = self.tx.send_with_policy(event, &policy).await.map_err(|_| HandleError::ChannelClosed)?;
+        self.record_outcome(&outcome);
+        Ok(outcome)
+    }
+
+    /**
+     * Send a message with structured key=value fields, eg. a request's method and status, drawn lined up after the payload
+     */
+    pub async fn send_with_fields(&self, message: String, fields: HashMap<String, Value>, priority: PriorityStatus) -> Result<SendOutcome<T>, HandleError> {
+        self.check_status(&message, &priority, Some(Capability::Print))?;
+        let policy = self.overflow_policy();
+        let event = ConsoleEvent::Message(Message::new(self.id.clone(), priority, message).with_fields(fields));
+        let outcome = self.tx.send_with_policy(event, &policy).await.map_err(|_| HandleError::ChannelClosed)?;
+        self.record_outcome(&outcome);
+        Ok(outcome)
+    }
+
+    /**
+     * Send a message to the console without waiting
+     */
+    pub fn try_send(&self, message: String) -> Result<(), HandleError> {
+        self.check_status(&message, &PriorityStatus::Normal, Some(Capability::Print))?;
+        match self.tx.try_send(self.envelope(message, PriorityStatus::Normal)) {
+            Ok(_) => Ok(()),
+            Err(TrySendError::Full(event)) => {
+                self.record_outcome(&SendOutcome::DroppedNewest(*event));
+                Err(HandleError::ChannelFull)
+            },
+            Err(TrySendError::Closed(_)) => Err(HandleError::ChannelClosed),
+        }
+    }
+
+    /**
+     * Send a message to another module's inbox, best effort, anything the target cannot take goes to the dead-letter queue
+     */
+    pub async fn route(&self, target: String, message: String) -> Result<SendOutcome<T>, HandleError> {
+        self.check_status(&message, &PriorityStatus::Normal, None)?;
+        let event = ConsoleEvent::Routed {
+            message: Message::new(self.id.clone(), PriorityStatus::Normal, message),
+            target,
+            ack: None,
+        };
+        let policy = self.overflow_policy();
+        let outcome = self.tx.send_with_policy(event, &policy).await.map_err(|_| HandleError::ChannelClosed)?;
+        self.record_outcome(&outcome);
+        Ok(outcome)
+    }
+
+    /**
+     * Send a message to every other module's inbox, best effort like route(). Needs the Broadcast capability
+     */
+    pub async fn broadcast(&self, message: String) -> Result<SendOutcome<T>, HandleError> {
+        self.check_status(&message, &PriorityStatus::Normal, Some(Capability::Broadcast))?;
+        self.route(BROADCAST_TARGET.to_string(), message).await
+    }
+
+    /**
+     * Run a /command as if the user typed it, eg. handle.run_command("/level Warning".to_string()).
+     * Needs the ExecuteCommands capability, the replies show in the output zones like the user's would.
+     */
+    pub async fn run_command(&self, line: String) -> Result<(), HandleError> {
+        self.allowed(Some(Capability::ExecuteCommands))?;
+        self.tx.send(ConsoleEvent::Command { sender: self.id.clone(), line }).await.map_err(|_| HandleError::ChannelClosed)
+    }
+
+    /**
+     * Send a message to another module's inbox with at-least-once delivery.
+     * The returned receiver resolves to the number of attempts it took, or the reason it ended up in the dead-letter queue.
+     * Always waits for room in the Console channel, the overflow policy does not apply to acked messages.
+     */
+    pub async fn route_with_ack(&self, target: String, message: String) -> Result<oneshot::Receiver<DeliveryReport>, HandleError> {
+        self.check_status(&message, &PriorityStatus::Normal, None)?;
+        let (report_tx, report_rx) = oneshot::channel();
+        let event = ConsoleEvent::Routed {
+            message: Message::new(self.id.clone(), PriorityStatus::Normal, message),
+            target,
+            ack: Some(report_tx),
+        };
+        self.tx.send(event).await.map_err(|_| HandleError::ChannelClosed)?;
+        Ok(report_rx)
+    }
+
+    /**
+     * Ask for a SubConsole of this module's own, eg. for a worker whose output should be readable apart, see Console::new_subconsole.
+     * Needs the SpawnSubConsole capability, the receiver gets the SubConsole once the broker made it.
+     */
+    pub async fn spawn_subconsole(&self, name: String, window: SubWindow) -> Result<oneshot::Receiver<SubConsole<T>>, HandleError> {
+        self.allowed(Some(Capability::SpawnSubConsole))?;
+        let (reply, subconsole) = oneshot::channel();
+        let event = ConsoleEvent::SubConsole { sender: self.id.clone(), name, window, reply };
+        self.tx.send(event).await.map_err(|_| HandleError::ChannelClosed)?;
+        Ok(subconsole)
+    }
+
+    /**
+     * Send an application event to the application as it is, no String in between, it comes out of Console::next_event.
+     * The overflow policy applies like for route(), a dropped event is not kept in the dead-letter queue.
+     */
+    pub async fn emit(&self, event: T) -> Result<SendOutcome<T>, HandleError> {
+        self.emit_event(None, event).await
+    }
+
+    /**
+     * Send an application event to the event inbox a module opened with Console::open_event_inbox, see emit()
+     */
+    pub async fn emit_to(&self, target: String, event: T) -> Result<SendOutcome<T>, HandleError> {
+        self.emit_event(Some(target), event).await
+    }
+
+    async fn emit_event(&self, target: Option<String>, event: T) -> Result<SendOutcome<T>, HandleError> {
+        self.allowed(None)?;
+        let event = ConsoleEvent::App { sender: self.id.clone(), target, payload: event };
+        let policy = self.overflow_policy();
+        self.tx.send_with_policy(event, &policy).await.map_err(|_| HandleError::ChannelClosed)
+    }
+}
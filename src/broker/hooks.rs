@@ -0,0 +1,85 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use crate::broker::message::Message;
+
+/**
+ * What a hook wants done with what it was given, after any change it made to it
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Continue,
+    Veto,
+}
+
+pub type MessageHook = Box<dyn FnMut(&mut Message) -> Flow + Send>;
+pub type InputHook = Box<dyn FnMut(&mut String) -> Flow + Send>;
+
+/**
+ * The middleware chains of the broker, see Console::on_message and Console::on_input.
+ *  ~ Hooks run in the order they were added, each sees the message or line as the hooks before it left it.
+ *  ~ The first Veto stops the chain and the message or line is dropped, quietly like a filtered one.
+ *  ~ Message hooks see every line about to be rendered, console replies and the user's own lines included,
+ *    after the phonebook, filters and rate limits let it through.
+ *  ~ Input hooks see every line typed into the input zone before it is parsed as a command or echoed.
+ */
+#[derive(Default)]
+pub struct Hooks {
+    message: Vec<MessageHook>,
+    input: Vec<InputHook>,
+    vetoed: u64,
+}
+
+impl Hooks {
+    pub fn add_message_hook(&mut self, hook: MessageHook) {
+        self.message.push(hook);
+    }
+
+    pub fn add_input_hook(&mut self, hook: InputHook) {
+        self.input.push(hook);
+    }
+
+    pub fn clear(&mut self) {
+        self.message.clear();
+        self.input.clear();
+    }
+
+    /**
+     * How many messages and lines the hooks vetoed so far
+     */
+    pub fn vetoed(&self) -> u64 {
+        self.vetoed
+    }
+
+    pub fn run_message(&mut self, message: &mut Message) -> Flow {
+        for hook in self.message.iter_mut() {
+            if hook(message) == Flow::Veto {
+                self.vetoed += 1;
+                return Flow::Veto;
+            }
+        }
+        Flow::Continue
+    }
+
+    pub fn run_input(&mut self, line: &mut String) -> Flow {
+        for hook in self.input.iter_mut() {
+            if hook(line) == Flow::Veto {
+                self.vetoed += 1;
+                return Flow::Veto;
+            }
+        }
+        Flow::Continue
+    }
+}
+
+/**
+ * Closures have no Debug, the counts are what is useful anyway
+ */
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hooks {{ message: {}, input: {}, vetoed: {} }}", self.message.len(), self.input.len(), self.vetoed)
+    }
+}
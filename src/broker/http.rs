@@ -0,0 +1,199 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::convert::Infallible;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::ConsoleEvent;
+use crate::broker::remote::{RemoteListen, RemoteReply, RemoteRequest};
+use crate::broker::session::{ServerFrame, SessionHub};
+use crate::output::recorder::Recorded;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+
+type Failure = (StatusCode, String);
+
+#[derive(Clone)]
+struct AppState {
+    console: MailboxSender,
+    hub: SessionHub,
+    listen: RemoteListen,
+}
+
+/**
+ * The body of POST /messages, priority is a PriorityStatus by name (Normal when left out)
+ */
+#[derive(Debug, Deserialize)]
+struct PostMessage {
+    sender: String,
+    text: String,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    topic: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StreamQuery {
+    #[serde(default)]
+    backlog: bool,
+}
+
+impl AppState {
+    /**
+     * Who a request is done as, http:<sender> when its x-dualzone-client and x-dualzone-token headers prove it is that sender
+     * (see RemoteListen::identify), otherwise http. Fails without the token
+     */
+    fn client(&self, headers: &HeaderMap) -> Result<String, Failure> {
+        let header = |key: &str| headers.get(key).and_then(|value| value.to_str().ok());
+        if !self.listen.authorizes(header("authorization")) {
+            return Err((StatusCode::UNAUTHORIZED, "a valid token is needed".to_string()));
+        }
+        match self.listen.identify(header("x-dualzone-client"), header("x-dualzone-token")) {
+            Some(name) => Ok(format!("http:{}", name)),
+            None => Ok("http".to_string()),
+        }
+    }
+
+    /**
+     * Hand a request to the broker and wait for its answer, a Refused one is 409
+     */
+    async fn ask(&self, client: String, request: RemoteRequest) -> Result<RemoteReply, Failure> {
+        let gone = || (StatusCode::SERVICE_UNAVAILABLE, "the console has shut down".to_string());
+        let (reply, answer) = oneshot::channel();
+        self.console.send(ConsoleEvent::Remote { client, request, reply }).await.map_err(|_| gone())?;
+        match answer.await.map_err(|_| gone())? {
+            RemoteReply::Refused(reason) => Err((StatusCode::CONFLICT, reason)),
+            answer => Ok(answer),
+        }
+    }
+}
+
+/**
+ * The data of a line event, borrowed from the line so its text goes straight into the JSON without a copy in between
+ */
+#[derive(Debug, Serialize)]
+struct LineData<'a> {
+    id: u64,
+    sender: &'a str,
+    priority: String,
+    text: &'a str,
+    topic: Option<&'a str>,
+    zone: Option<&'a str>,
+}
+
+/**
+ * A zone change as an SSE event, named line, dismissed or retracted with its JSON as the data
+ */
+fn to_event(recorded: &Recorded) -> Result<Event, axum::Error> {
+    match recorded {
+        Recorded::Line(message) => Event::default().event("line").json_data(LineData {
+            id: message.id,
+            sender: &message.sender,
+            priority: format!("{:?}", message.priority),
+            text: &message.payload,
+            topic: message.topic.as_deref(),
+            zone: message.zone.as_deref(),
+        }),
+        Recorded::Dismiss(id) => Event::default().event("dismissed").json_data(json!({ "id": id })),
+        Recorded::Retract { sender, id } => Event::default().event("retracted").json_data(json!({ "sender": sender, "id": id })),
+    }
+}
+
+async fn post_message(State(state): State<AppState>, headers: HeaderMap, Json(body): Json<PostMessage>) -> Result<Json<serde_json::Value>, Failure> {
+    let client = state.client(&headers)?;
+    if body.sender.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "a message needs a sender".to_string()));
+    }
+    let priority = match body.priority.as_deref() {
+        None | Some("") => PriorityStatus::Normal,
+        Some(name) => PriorityStatus::parse(name).ok_or_else(|| (StatusCode::BAD_REQUEST, format!("unknown priority {}", name)))?,
+    };
+    let request = RemoteRequest::Send { sender: body.sender, text: body.text, priority, topic: body.topic };
+    match state.ask(client, request).await? {
+        RemoteReply::Sent(id) => Ok(Json(json!({ "id": id }))),
+        _ => Err((StatusCode::INTERNAL_SERVER_ERROR, "unexpected answer from the broker".to_string())),
+    }
+}
+
+async fn get_senders(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<serde_json::Value>, Failure> {
+    let client = state.client(&headers)?;
+    let senders = match state.ask(client, RemoteRequest::ListSenders).await? {
+        RemoteReply::Senders(senders) => senders,
+        _ => return Err((StatusCode::INTERNAL_SERVER_ERROR, "unexpected answer from the broker".to_string())),
+    };
+    let senders: Vec<serde_json::Value> = senders.into_iter().map(|sender| json!({
+        "name": sender.name,
+        "id": sender.id,
+        "status": format!("{:?}", sender.status),
+        "messages": sender.messages,
+        "capabilities": sender.capabilities,
+    })).collect();
+    Ok(Json(json!(senders)))
+}
+
+async fn get_stream(State(state): State<AppState>, headers: HeaderMap, Query(query): Query<StreamQuery>)
+    -> Result<Sse<ReceiverStream<Result<Event, axum::Error>>>, Failure> {
+    state.client(&headers)?;
+    let (backlog, mut live) = state.hub.subscribe();
+    let backlog = if query.backlog { backlog } else { Vec::new() };
+    let (tx, rx) = mpsc::channel(256);
+    tokio::spawn(async move {
+        for recorded in backlog.iter() {
+            if tx.send(to_event(recorded)).await.is_err() {
+                return;
+            }
+        }
+        loop {
+            let event = match live.recv().await {
+                Ok(ServerFrame::Event(recorded)) => to_event(&recorded),
+                Ok(ServerFrame::Bye(reason)) => {
+                    let _ = tx.send(Ok(Event::default().event("bye").data(reason))).await;
+                    return;
+                },
+                Ok(ServerFrame::Welcome { .. }) | Ok(ServerFrame::Private { .. }) => continue,
+                //a dashboard this far behind has missed lines, it is told so and can fetch the backlog again
+                Err(broadcast::error::RecvError::Lagged(skipped)) => Ok(Event::default().event("lagged").data(skipped.to_string())),
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default()))
+}
+
+/**
+ * Serve the broker over HTTP until the server fails, the easiest way in for a web dashboard.
+ *  ~ POST /messages takes {"sender", "text", "priority", "topic"} (the last two optional) and answers {"id"}.
+ *  ~ GET /stream is the output as Server-Sent Events, line, dismissed and retracted with JSON data, ?backlog=true
+ *    starts with the scrollback. It is what the session's clients see, from the same hub.
+ *  ~ GET /senders is /senders as JSON, the names of the signed ids the lines carry.
+ *  ~ A request sends the token as "authorization: Bearer <token>" when there is one. One with the x-dualzone-client and
+ *    x-dualzone-token headers of one of listen's senders is done as it (http:<name>) in the audit log, any other as http.
+ *    POST /messages only sends as that sender.
+ * Refuses to serve an address other than loopback without a token, see RemoteListen::check_exposure
+ */
+pub async fn serve(listen: RemoteListen, hub: SessionHub, console: MailboxSender) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    listen.check_exposure()?;
+    let addr = listen.addr;
+    let app = Router::new()
+        .route("/messages", post(post_message))
+        .route("/stream", get(get_stream))
+        .route("/senders", get(get_senders))
+        .with_state(AppState { console, hub, listen });
+    axum::Server::bind(&addr).serve(app.into_make_service()).await?;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//External
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/**
+ * Version tag prepended to every generated identifier, bump this if the id scheme ever changes.
+ */
+pub const ID_PREFIX: &str = "dz1:";
+
+/**
+ * Number of bytes of the HMAC output kept in the identifier.
+ */
+const ID_BYTES: usize = 16;
+
+/**
+ * Signs sender names into identifiers using HMAC-SHA256 keyed at Console creation.
+ *  ~ Id format (stable): "dz1:" followed by the first 16 bytes of HMAC-SHA256(key, name) as 32 lowercase hex chars.
+ *  ~ The same name always maps to the same id for a given key, so lookups by plaintext name still work.
+ *  ~ The key never leaves this struct, a module holding only its Sender cannot derive the id of another module.
+ */
+pub struct IdSigner {
+    key: [u8; 32],
+}
+
+impl Default for IdSigner {
+    fn default() -> Self {
+        IdSigner::new()
+    }
+}
+
+impl IdSigner {
+    /**
+     * Create a signer with a fresh random key
+     */
+    pub fn new() -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        IdSigner { key }
+    }
+
+    /**
+     * Create a signer from an existing key, used when an id scheme has to survive a restart
+     */
+    pub fn from_key(key: [u8; 32]) -> Self {
+        IdSigner { key }
+    }
+
+    /**
+     * Sign the identifier and return it in the documented id format
+     */
+    pub fn sign(&self, identifier: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(identifier.as_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        let mut signed_identifier = String::with_capacity(ID_PREFIX.len() + ID_BYTES * 2);
+        signed_identifier.push_str(ID_PREFIX);
+        for byte in digest.iter().take(ID_BYTES) {
+            signed_identifier.push_str(&format!("{:02x}", byte));
+        }
+        signed_identifier
+    }
+
+    /**
+     * Check that a string is shaped like an id produced by this scheme
+     */
+    pub fn is_valid_format(id: &str) -> bool {
+        match id.strip_prefix(ID_PREFIX) {
+            Some(hex) => hex.len() == ID_BYTES * 2 && hex.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()),
+            None => false,
+        }
+    }
+}
+
+/**
+ * Never print the key, even in debug output
+ */
+impl std::fmt::Debug for IdSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IdSigner { key: <hidden> }")
+    }
+}
@@ -0,0 +1,40 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::handle::{HandleError, SenderHandle};
+
+//External
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/**
+ * The sender the piped lines come from, see Console::ingest_stdin
+ */
+pub const INGEST_SENDER: &str = "stdin";
+
+/**
+ * Show the lines piped into stdin until it ends, other-program | dualZoneCli --ingest.
+ * Bytes that aren't UTF-8 are shown replaced, the end of the pipe is the last line and the console keeps running
+ */
+pub async fn run(output: SenderHandle) {
+    let mut reader = BufReader::new(tokio::io::stdin());
+    let mut line = Vec::new();
+    let mut count: u64 = 0;
+    let end = loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) => break format!("[ingest] stdin closed after {} lines", count),
+            Ok(_) => {
+                count += 1;
+                let text = String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string();
+                if let Err(HandleError::ChannelClosed) = output.send(text).await {
+                    return;
+                }
+            },
+            Err(e) => break format!("[ingest] could not read stdin: {}", e),
+        }
+    };
+    //Normal like the lines, a higher priority would be taken from the queue before the last of them
+    let _ = output.send(end).await;
+}
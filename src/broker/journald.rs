@@ -0,0 +1,102 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::os::linux::net::SocketAddrExt;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::{ConsoleEvent, Message, RenderedLine};
+use crate::broker::syslog;
+
+//External
+use tokio::net::UnixDatagram;
+use tokio::sync::mpsc;
+
+/**
+ * Where journald takes entries in its native protocol
+ */
+pub const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/**
+ * The SYSLOG_IDENTIFIER of every entry, what journalctl -t picks them by
+ */
+pub const IDENTIFIER: &str = "dualzone";
+
+/**
+ * One field of an entry, a value with a newline in it goes as its length and the bytes as the protocol wants
+ */
+fn append_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    match value.contains('\n') {
+        true => {
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        },
+        false => entry.push(b'='),
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+/**
+ * A rendered line as a journal entry: MESSAGE, PRIORITY (the syslog severity, see broker::syslog::severity_of),
+ * SENDER and TOPIC when it has one, eg. journalctl -t dualzone SENDER=builder
+ */
+pub fn entry(line: &RenderedLine) -> Vec<u8> {
+    let mut entry = Vec::new();
+    append_field(&mut entry, "MESSAGE", &line.text);
+    append_field(&mut entry, "PRIORITY", &syslog::severity_of(&line.priority).to_string());
+    append_field(&mut entry, "SYSLOG_IDENTIFIER", IDENTIFIER);
+    append_field(&mut entry, "SENDER", &line.sender);
+    if let Some(topic) = &line.topic {
+        append_field(&mut entry, "TOPIC", topic);
+    }
+    entry
+}
+
+/**
+ * Forward the rendered lines to journald until the console goes away.
+ * A journal that can't be reached is said once in the output zone, the lines meanwhile are not kept
+ */
+pub async fn forward(mut lines: mpsc::UnboundedReceiver<RenderedLine>, console: MailboxSender) {
+    let socket = match UnixDatagram::unbound() {
+        Ok(socket) => socket,
+        Err(e) => {
+            let note = Message::from_console(format!("[journal] not forwarding: {}", e));
+            let _ = console.send(ConsoleEvent::Message(note)).await;
+            return;
+        },
+    };
+    let mut failing = false;
+    while let Some(line) = lines.recv().await {
+        match socket.send_to(&entry(&line), JOURNAL_SOCKET).await {
+            Ok(_) => failing = false,
+            Err(e) if !failing => {
+                failing = true;
+                let note = Message::from_console(format!("[journal] could not forward to {}: {}", JOURNAL_SOCKET, e));
+                if console.send(ConsoleEvent::Message(note)).await.is_err() {
+                    return;
+                }
+            },
+            Err(_) => {},
+        }
+    }
+}
+
+/**
+ * Tell systemd how the service is doing (sd_notify), eg. READY=1 once the broker runs, for Type=notify units.
+ * Returns false when the process isn't run by systemd, there is no NOTIFY_SOCKET then
+ */
+pub fn notify(state: &str) -> std::io::Result<bool> {
+    let path = match std::env::var("NOTIFY_SOCKET") {
+        Ok(path) if !path.is_empty() => path,
+        _ => return Ok(false),
+    };
+    let socket = std::os::unix::net::UnixDatagram::unbound()?;
+    //a name starting with @ is in the abstract namespace, not on the file system
+    match path.strip_prefix('@') {
+        Some(name) => socket.send_to_addr(state.as_bytes(), &std::os::unix::net::SocketAddr::from_abstract_name(name)?)?,
+        None => socket.send_to(state.as_bytes(), &path)?,
+    };
+    Ok(true)
+}
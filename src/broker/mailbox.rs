@@ -0,0 +1,474 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use crate::broker::message::ConsoleEvent;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use tokio::sync::Notify;
+
+/**
+ * What a sender does when the Console channel is full
+ *  ~ Block waits for room, this is the old behaviour.
+ *  ~ DropOldest evicts the oldest message this sender still has queued, if it has none queued the new message is dropped instead.
+ *    An application event (SenderHandle::emit) only ever evicts an application event of the same sender, never one of its messages.
+ *  ~ DropNewest drops the message being sent.
+ *  ~ Spill puts the message on an unbounded overflow queue which is drained in order as the main queue empties.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum OverflowPolicy {
+    #[default]
+    Block,
+    DropOldest,
+    DropNewest,
+    Spill,
+}
+
+/**
+ * The order the Console takes queued events in
+ *  ~ Priority takes the highest PriorityStatus first, every severity has its own lane and each lane is first in first out,
+ *    so an Urgent alert doesn't sit behind a backlog of Verbose chatter.
+ *  ~ Fifo takes them in the order they were sent, the old single queue.
+ * Either way events that aren't module messages (input, redraw, scrolling, shutdown...) keep their place: everything
+ * sent before one comes out before it, so a /command sees the messages sent ahead of it.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Scheduling {
+    #[default]
+    Priority,
+    Fifo,
+}
+
+impl Scheduling {
+    pub fn parse(name: &str) -> Option<Scheduling> {
+        match name.to_ascii_lowercase().as_str() {
+            "priority" => Some(Scheduling::Priority),
+            "fifo" => Some(Scheduling::Fifo),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * One lane per severity, see PriorityStatus::severity
+ */
+const LANES: usize = 10;
+
+/**
+ * After this many messages in a row were taken ahead of the oldest one, the oldest one goes next.
+ * A steady stream of high priority messages slows the lower lanes down but can't stop them.
+ */
+const FAIRNESS: u32 = 16;
+
+/**
+ * What happened to a message handed to send_with_policy()
+ */
+#[derive(Debug)]
+pub enum SendOutcome<T = ()> {
+    Queued,
+    Spilled,
+    DroppedNewest(ConsoleEvent<T>),
+    DroppedOldest(ConsoleEvent<T>),
+}
+
+/**
+ * Counters for the overflow behaviour, read with MailboxSender::stats()
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MailboxStats {
+    pub depth: usize,
+    pub overflow_depth: usize,
+    pub capacity: usize,
+    pub dropped_oldest: u64,
+    pub dropped_newest: u64,
+    pub spilled: u64,
+}
+
+#[derive(Debug)]
+pub enum TrySendError<T = ()> {
+    Full(Box<ConsoleEvent<T>>),
+    Closed(Box<ConsoleEvent<T>>),
+}
+
+#[derive(Debug)]
+pub struct SendError<T = ()>(pub ConsoleEvent<T>);
+
+/**
+ * A queued event and when it was queued, seq only ever grows
+ */
+#[derive(Debug)]
+struct Queued<T> {
+    seq: u64,
+    event: ConsoleEvent<T>,
+}
+
+/**
+ * The queue proper: module messages in their severity lane, everything else in control
+ */
+#[derive(Debug)]
+struct MailboxState<T> {
+    lanes: Vec<VecDeque<Queued<T>>>,
+    control: VecDeque<Queued<T>>,
+    next_seq: u64,
+    //messages taken ahead of the oldest one since the oldest one was last taken
+    skipped: u32,
+    scheduling: Scheduling,
+    overflow: VecDeque<ConsoleEvent<T>>,
+    closed: bool,
+    stats: MailboxStats,
+}
+
+impl<T> MailboxState<T> {
+    fn new(capacity: usize) -> Self {
+        MailboxState {
+            lanes: (0..LANES).map(|_| VecDeque::new()).collect(),
+            control: VecDeque::new(),
+            next_seq: 0,
+            skipped: 0,
+            scheduling: Scheduling::default(),
+            overflow: VecDeque::new(),
+            closed: false,
+            stats: MailboxStats { capacity, ..MailboxStats::default() },
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.lanes.iter().map(|lane| lane.len()).sum::<usize>() + self.control.len()
+    }
+
+    fn push(&mut self, event: ConsoleEvent<T>) {
+        let queued = Queued { seq: self.next_seq, event };
+        self.next_seq += 1;
+        match priority_of(&queued.event) {
+            Some(priority) => self.lanes[(priority.severity() as usize).min(LANES - 1)].push_back(queued),
+            None => self.control.push_back(queued),
+        }
+    }
+
+    fn pop(&mut self) -> Option<ConsoleEvent<T>> {
+        //no message may pass the oldest control event
+        let barrier = self.control.front().map_or(u64::MAX, |queued| queued.seq);
+        let waiting = |lane: &VecDeque<Queued<T>>| lane.front().is_some_and(|queued| queued.seq < barrier);
+        let oldest = (0..LANES).filter(|&lane| waiting(&self.lanes[lane])).min_by_key(|&lane| self.lanes[lane][0].seq);
+        let lane = match self.scheduling {
+            Scheduling::Priority if self.skipped < FAIRNESS => self.lanes.iter().rposition(waiting),
+            _ => oldest,
+        };
+        match lane {
+            Some(lane) => {
+                self.skipped = if Some(lane) == oldest { 0 } else { self.skipped + 1 };
+                self.lanes[lane].pop_front().map(|queued| queued.event)
+            },
+            None => self.control.pop_front().map(|queued| queued.event),
+        }
+    }
+
+    /**
+     * Take out the oldest event of the same kind the sender of event still has queued: a message in the lanes for a message,
+     * an application event in control for an application event
+     */
+    fn remove_oldest_like(&mut self, event: &ConsoleEvent<T>) -> Option<ConsoleEvent<T>> {
+        let sender = sender_of(event)?;
+        if let ConsoleEvent::App { .. } = event {
+            let index = self.control.iter().position(|queued| matches!(&queued.event, ConsoleEvent::App { .. }) && sender_of(&queued.event) == Some(sender))?;
+            return self.control.remove(index).map(|queued| queued.event);
+        }
+        let found = self.lanes.iter().enumerate()
+            .filter_map(|(lane, queued)| queued.iter().position(|queued| sender_of(&queued.event) == Some(sender)).map(|index| (lane, index)))
+            .min_by_key(|&(lane, index)| self.lanes[lane][index].seq);
+        let (lane, index) = found?;
+        self.lanes[lane].remove(index).map(|queued| queued.event)
+    }
+}
+
+/**
+ * Bounded queue shared by every sender and the single Console receiver, taken from in the order its Scheduling says.
+ * The lock is only held to push or pop, never across an await.
+ */
+#[derive(Debug)]
+struct Mailbox<T> {
+    state: Mutex<MailboxState<T>>,
+    capacity: usize,
+    readable: Notify,
+    writable: Notify,
+}
+
+#[derive(Debug)]
+pub struct MailboxSender<T = ()> {
+    mailbox: Arc<Mailbox<T>>,
+}
+
+//not derived, that would want T: Clone
+impl<T> Clone for MailboxSender<T> {
+    fn clone(&self) -> Self {
+        MailboxSender { mailbox: self.mailbox.clone() }
+    }
+}
+
+#[derive(Debug)]
+pub struct MailboxReceiver<T = ()> {
+    mailbox: Arc<Mailbox<T>>,
+}
+
+/**
+ * Create a mailbox with room for capacity events, mirrors tokio::sync::mpsc::channel()
+ */
+pub fn channel<T>(capacity: usize) -> (MailboxSender<T>, MailboxReceiver<T>) {
+    let mailbox = Arc::new(Mailbox {
+        state: Mutex::new(MailboxState::new(capacity)),
+        capacity,
+        readable: Notify::new(),
+        writable: Notify::new(),
+    });
+    (MailboxSender { mailbox: mailbox.clone() }, MailboxReceiver { mailbox })
+}
+
+/**
+ * The sender id of an event, only module messages and application events have one
+ */
+fn sender_of<T>(event: &ConsoleEvent<T>) -> Option<&str> {
+    match event {
+        ConsoleEvent::Message(message) => Some(message.sender.as_str()),
+        ConsoleEvent::Routed { message, .. } => Some(message.sender.as_str()),
+        ConsoleEvent::App { sender, .. } => Some(sender.as_str()),
+        _ => None,
+    }
+}
+
+/**
+ * The priority an event is scheduled by, only module messages have one
+ */
+fn priority_of<T>(event: &ConsoleEvent<T>) -> Option<&PriorityStatus> {
+    match event {
+        ConsoleEvent::Message(message) => Some(&message.priority),
+        ConsoleEvent::Routed { message, .. } => Some(&message.priority),
+        _ => None,
+    }
+}
+
+impl<T> MailboxSender<T> {
+    /**
+     * Send an event, waiting for room in the queue. Fails once the mailbox is closed, the receiver going away closes it
+     */
+    pub async fn send(&self, event: ConsoleEvent<T>) -> Result<(), SendError<T>> {
+        let mut event = event;
+        loop {
+            //made before trying so a close in between still wakes it
+            let writable = self.mailbox.writable.notified();
+            match self.try_send(event) {
+                Ok(_) => return Ok(()),
+                Err(TrySendError::Closed(e)) => return Err(SendError(*e)),
+                Err(TrySendError::Full(e)) => {
+                    event = *e;
+                    writable.await;
+                }
+            }
+        }
+    }
+
+    /**
+     * Send an event without waiting
+     */
+    pub fn try_send(&self, event: ConsoleEvent<T>) -> Result<(), TrySendError<T>> {
+        let mut state = self.mailbox.state.lock().unwrap();
+        if state.closed {
+            return Err(TrySendError::Closed(Box::new(event)));
+        }
+        //spilled messages are older than anything sent now, so nothing may jump ahead of them
+        if state.len() >= self.mailbox.capacity || !state.overflow.is_empty() {
+            return Err(TrySendError::Full(Box::new(event)));
+        }
+        state.push(event);
+        drop(state);
+        self.mailbox.readable.notify_one();
+        Ok(())
+    }
+
+    /**
+     * Send an event applying the given overflow policy when the queue is full
+     */
+    pub async fn send_with_policy(&self, event: ConsoleEvent<T>, policy: &OverflowPolicy) -> Result<SendOutcome<T>, SendError<T>> {
+        if *policy == OverflowPolicy::Block {
+            return self.send(event).await.map(|_| SendOutcome::Queued);
+        }
+        let event = match self.try_send(event) {
+            Ok(_) => return Ok(SendOutcome::Queued),
+            Err(TrySendError::Closed(e)) => return Err(SendError(*e)),
+            Err(TrySendError::Full(e)) => *e,
+        };
+
+        let mut state = self.mailbox.state.lock().unwrap();
+        let outcome = match policy {
+            OverflowPolicy::DropNewest => {
+                state.stats.dropped_newest += 1;
+                SendOutcome::DroppedNewest(event)
+            },
+            OverflowPolicy::DropOldest => {
+                let oldest = state.remove_oldest_like(&event);
+                match oldest {
+                    Some(oldest) => {
+                        state.push(event);
+                        state.stats.dropped_oldest += 1;
+                        SendOutcome::DroppedOldest(oldest)
+                    },
+                    None => {
+                        state.stats.dropped_newest += 1;
+                        SendOutcome::DroppedNewest(event)
+                    }
+                }
+            },
+            _ => {
+                state.overflow.push_back(event);
+                state.stats.spilled += 1;
+                SendOutcome::Spilled
+            }
+        };
+        drop(state);
+        self.mailbox.readable.notify_one();
+        Ok(outcome)
+    }
+
+    /**
+     * Snapshot of the queue depth and overflow counters
+     */
+    pub fn stats(&self) -> MailboxStats {
+        let state = self.mailbox.state.lock().unwrap();
+        let mut stats = state.stats.clone();
+        stats.depth = state.len();
+        stats.overflow_depth = state.overflow.len();
+        stats
+    }
+
+    /**
+     * Change the order queued events are taken in, it applies to what is already queued too
+     */
+    pub fn set_scheduling(&self, scheduling: Scheduling) {
+        self.mailbox.state.lock().unwrap().scheduling = scheduling;
+    }
+
+    /**
+     * Close the mailbox, pending events can still be received but nothing new is accepted
+     */
+    pub fn close(&self) {
+        self.mailbox.state.lock().unwrap().closed = true;
+        self.mailbox.readable.notify_one();
+        self.mailbox.writable.notify_waiters();
+    }
+}
+
+impl<T> MailboxReceiver<T> {
+    /**
+     * Wait for the next event, returns None once the mailbox is closed and empty
+     */
+    pub async fn recv(&mut self) -> Option<ConsoleEvent<T>> {
+        loop {
+            {
+                let mut state = self.mailbox.state.lock().unwrap();
+                if let Some(event) = state.pop() {
+                    //pull the next spilled message into the freed slot so ordering is kept
+                    if let Some(spilled) = state.overflow.pop_front() {
+                        state.push(spilled);
+                    }
+                    drop(state);
+                    self.mailbox.writable.notify_one();
+                    return Some(event);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.mailbox.readable.notified().await;
+        }
+    }
+
+    /**
+     * Snapshot of the queue depth and overflow counters
+     */
+    pub fn stats(&self) -> MailboxStats {
+        MailboxSender { mailbox: self.mailbox.clone() }.stats()
+    }
+}
+
+/**
+ * Nothing will take from the mailbox any more, close it so senders get Closed instead of waiting for room for ever
+ */
+impl<T> Drop for MailboxReceiver<T> {
+    fn drop(&mut self) {
+        MailboxSender { mailbox: self.mailbox.clone() }.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::message::Message;
+
+    fn message(sender: &str, text: &str) -> ConsoleEvent<u32> {
+        ConsoleEvent::Message(Message::new(sender.to_string(), PriorityStatus::Normal, text))
+    }
+
+    fn app(sender: &str, payload: u32) -> ConsoleEvent<u32> {
+        ConsoleEvent::App { sender: sender.to_string(), target: None, payload }
+    }
+
+    #[tokio::test]
+    async fn an_application_event_evicts_the_oldest_application_event() {
+        let (tx, mut rx) = channel::<u32>(2);
+        tx.try_send(message("a", "kept")).unwrap();
+        tx.try_send(app("a", 1)).unwrap();
+        match tx.send_with_policy(app("a", 2), &OverflowPolicy::DropOldest).await.unwrap() {
+            SendOutcome::DroppedOldest(ConsoleEvent::App { payload, .. }) => assert_eq!(payload, 1),
+            outcome => panic!("expected the first event evicted, got {:?}", outcome),
+        }
+        assert!(matches!(rx.recv().await, Some(ConsoleEvent::Message(message)) if &*message.payload == "kept"));
+        assert!(matches!(rx.recv().await, Some(ConsoleEvent::App { payload: 2, .. })));
+        assert_eq!(tx.stats().dropped_oldest, 1);
+    }
+
+    #[tokio::test]
+    async fn an_application_event_never_evicts_a_message() {
+        let (tx, mut rx) = channel::<u32>(1);
+        tx.try_send(message("a", "kept")).unwrap();
+        match tx.send_with_policy(app("a", 1), &OverflowPolicy::DropOldest).await.unwrap() {
+            SendOutcome::DroppedNewest(ConsoleEvent::App { payload, .. }) => assert_eq!(payload, 1),
+            outcome => panic!("expected the new event dropped, got {:?}", outcome),
+        }
+        let stats = tx.stats();
+        assert_eq!((stats.dropped_oldest, stats.dropped_newest, stats.depth), (0, 1, 1));
+        assert!(matches!(rx.recv().await, Some(ConsoleEvent::Message(message)) if &*message.payload == "kept"));
+    }
+
+    #[tokio::test]
+    async fn a_send_after_the_receiver_is_dropped_is_closed() {
+        let (tx, rx) = channel::<u32>(1);
+        drop(rx);
+        assert!(matches!(tx.try_send(message("a", "late")), Err(TrySendError::Closed(_))));
+        assert!(tx.send(message("a", "late")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn a_blocked_send_wakes_when_the_receiver_is_dropped() {
+        let (tx, rx) = channel::<u32>(1);
+        tx.try_send(message("a", "first")).unwrap();
+        let blocked = tokio::spawn({
+            let tx = tx.clone();
+            async move { tx.send(message("a", "second")).await.is_err() }
+        });
+        tokio::task::yield_now().await;
+        drop(rx);
+        let closed = tokio::time::timeout(tokio::time::Duration::from_secs(1), blocked).await.expect("the send is still blocked");
+        assert!(closed.unwrap());
+    }
+
+    #[tokio::test]
+    async fn a_message_never_evicts_an_application_event() {
+        let (tx, _rx) = channel::<u32>(1);
+        tx.try_send(app("a", 1)).unwrap();
+        assert!(matches!(tx.send_with_policy(message("a", "new"), &OverflowPolicy::DropOldest).await.unwrap(), SendOutcome::DroppedNewest(_)));
+        assert_eq!(tx.stats().depth, 1);
+    }
+}
@@ -0,0 +1,201 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use crate::broker::message::Message;
+use crate::broker::dead_letter::DEFAULT_DEAD_LETTER_CAPACITY;
+use crate::output::zone::DEFAULT_SCROLLBACK;
+use crate::user_input::editor::MAX_HISTORY;
+
+//External
+use serde::{Serialize, Deserialize};
+
+/**
+ * Which entry goes first when a buffer is over its limit
+ *  ~ OldestFirst is a plain ring buffer.
+ *  ~ LowestPriorityFirst drops the oldest entry of the lowest priority held, so a burst of Verbose chatter can't push
+ *    the last Critical out of the scrollback. Entries without a priority (input history) go oldest first either way.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+    #[default]
+    OldestFirst,
+    LowestPriorityFirst,
+}
+
+impl EvictionPolicy {
+    pub fn parse(name: &str) -> Option<EvictionPolicy> {
+        match name.to_ascii_lowercase().as_str() {
+            "oldest" | "oldest-first" | "fifo" => Some(EvictionPolicy::OldestFirst),
+            "lowest-priority" | "lowest-priority-first" | "priority" => Some(EvictionPolicy::LowestPriorityFirst),
+            _ => None,
+        }
+    }
+
+    /**
+     * Index of the entry to evict given the severity of every entry oldest first, None when there are none
+     */
+    pub fn victim(&self, mut severities: impl Iterator<Item = u8>) -> Option<usize> {
+        match self {
+            EvictionPolicy::OldestFirst => severities.next().map(|_| 0),
+            //min_by_key keeps the first of equal keys, which is the oldest
+            EvictionPolicy::LowestPriorityFirst => severities.enumerate().min_by_key(|(_, severity)| *severity).map(|(index, _)| index),
+        }
+    }
+}
+
+/**
+ * The most a buffer may hold, by number of entries and by estimated bytes. None is no limit of that kind,
+ * a buffer over either limit evicts until it is under both.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryLimit {
+    pub max_entries: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub eviction: EvictionPolicy,
+}
+
+impl MemoryLimit {
+    pub fn entries(max_entries: usize) -> Self {
+        MemoryLimit { max_entries: Some(max_entries), max_bytes: None, eviction: EvictionPolicy::OldestFirst }
+    }
+
+    pub fn unlimited() -> Self {
+        MemoryLimit { max_entries: None, max_bytes: None, eviction: EvictionPolicy::OldestFirst }
+    }
+
+    pub fn with_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn with_eviction(mut self, eviction: EvictionPolicy) -> Self {
+        self.eviction = eviction;
+        self
+    }
+
+    pub fn exceeded(&self, entries: usize, bytes: usize) -> bool {
+        self.max_entries.is_some_and(|max| entries > max) || self.max_bytes.is_some_and(|max| bytes > max)
+    }
+}
+
+/**
+ * The limits of every buffer that grows with the session
+ *  ~ scrollback is per output zone, DEFAULT_SCROLLBACK messages each.
+ *  ~ history is the submitted lines of the input zone, MAX_HISTORY of them.
+ *  ~ dead_letters is the dead-letter queue, DEFAULT_DEAD_LETTER_CAPACITY rejects.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryLimits {
+    pub scrollback: MemoryLimit,
+    pub history: MemoryLimit,
+    pub dead_letters: MemoryLimit,
+}
+
+impl Default for MemoryLimits {
+    fn default() -> Self {
+        MemoryLimits {
+            scrollback: MemoryLimit::entries(DEFAULT_SCROLLBACK),
+            history: MemoryLimit::entries(MAX_HISTORY),
+            dead_letters: MemoryLimit::entries(DEFAULT_DEAD_LETTER_CAPACITY),
+        }
+    }
+}
+
+/**
+ * What one buffer holds right now and how many entries it had to let go of so far
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BufferUsage {
+    pub entries: usize,
+    pub bytes: usize,
+    pub evicted: u64,
+}
+
+/**
+ * Console::memory_usage, scrollback summed over every zone. bytes are estimates: the heap text plus the fixed size of an entry
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub scrollback: BufferUsage,
+    pub history: BufferUsage,
+    pub dead_letters: BufferUsage,
+}
+
+impl MemoryUsage {
+    pub fn total_bytes(&self) -> usize {
+        self.scrollback.bytes + self.history.bytes + self.dead_letters.bytes
+    }
+}
+
+/**
+ * Running usage of a buffer that lives in another task, the owner reports every change and the Console reads it
+ */
+#[derive(Debug, Default)]
+pub struct BufferMeter {
+    entries: AtomicUsize,
+    bytes: AtomicUsize,
+    evicted: AtomicU64,
+}
+
+impl BufferMeter {
+    pub fn added(&self, bytes: usize) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn removed(&self, bytes: usize) {
+        self.entries.fetch_sub(1, Ordering::Relaxed);
+        self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    /**
+     * An entry replaced in place, eg. a widget update
+     */
+    pub fn resized(&self, old: usize, new: usize) {
+        self.bytes.fetch_add(new, Ordering::Relaxed);
+        self.bytes.fetch_sub(old, Ordering::Relaxed);
+    }
+
+    pub fn evicted(&self, bytes: usize) {
+        self.removed(bytes);
+        self.evicted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        BufferUsage {
+            entries: self.entries.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/**
+ * Meters of the buffers that live outside the broker, shared with the output and input tasks
+ */
+#[derive(Debug, Default)]
+pub struct MemoryMeter {
+    pub scrollback: BufferMeter,
+    pub history: BufferMeter,
+}
+
+/**
+ * Estimated memory a message takes, what the byte limits count
+ */
+pub fn message_size(message: &Message) -> usize {
+    std::mem::size_of::<Message>()
+        + message.payload.len()
+        + message.sender.len()
+        + message.topic.as_ref().map_or(0, |topic| topic.len())
+        + message.zone.as_ref().map_or(0, |zone| zone.len())
+}
+
+/**
+ * Estimated memory a line of text takes, for the input history
+ */
+pub fn text_size(text: &str) -> usize {
+    std::mem::size_of::<String>() + text.len()
+}
@@ -0,0 +1,422 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::user_input::structs::{PriorityStatus, SubConsole, SubWindow};
+use crate::broker::routing::DeliveryReport;
+use crate::output::widgets::Widget;
+use crate::output::search::SearchCommand;
+use crate::output::highlight::Highlights;
+use crate::output::zone::{ScrollCommand, ZoneSpec};
+use crate::output::selection::SelectCommand;
+use crate::output::clipboard::CopyTarget;
+use crate::output::tabs::TabCommand;
+use crate::output::export::ExportFormat;
+use crate::output::notify::Alert;
+use crate::user_input::input::InputRequest;
+use crate::user_input::dialog::PageStep;
+use crate::broker::threads::ThreadCommand;
+use crate::broker::schedule::Scheduled;
+use crate::broker::audit::AuditAction;
+use crate::broker::distribute::BridgedMessage;
+use crate::broker::remote::{RemoteReply, RemoteRequest};
+
+//External
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+/**
+ * Sender name used for everything the Console itself emits (membership events, command replies).
+ */
+pub const CONSOLE_SENDER: &str = "console";
+
+/**
+ * Sender name used for lines typed into the input zone.
+ */
+pub const USER_SENDER: &str = "user";
+
+/**
+ * Where a /command came into the broker from. A name can be picked by anyone, this is what /sh goes by instead.
+ *  ~ Terminal is the input zone of the console's own terminal (ConsoleEvent::UserInput).
+ *  ~ Module is a SenderHandle running one (ConsoleEvent::Command).
+ *  ~ SessionClient is a client attached to the session or a chat relay's channel (ConsoleEvent::SessionInput and SessionAudit).
+ *  ~ Remote is a service outside the process (ConsoleEvent::Remote).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Terminal,
+    Module,
+    SessionClient,
+    Remote,
+}
+
+/**
+ * Source of message ids, unique for the life of the process
+ */
+static NEXT_MESSAGE_ID: AtomicU64 = AtomicU64::new(1);
+
+/**
+ * Take the next message id, also used for widget slots so they never collide with a message id
+ */
+pub fn next_id() -> u64 {
+    NEXT_MESSAGE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/**
+ * The envelope every module message travels in.
+ *  ~ sender is the signed id of the SenderHandle, or one of the built in sender names above.
+ *  ~ priority defaults to Normal and may be changed by the broker (eg. downgraded by a rate limit).
+ *  ~ topic is optional and set by the sender, zones can be bound to topics.
+ *  ~ zone is filled in by the broker with the name of the output zone the message is routed to.
+ *  ~ tab is filled in by the broker with the tab the message also shows in when tabs are on, its topic or module.
+ *  ~ id is assigned when the message is created, it is how a pinned message is dismissed.
+ *  ~ pinned messages stay at the top of their zone and never expire until dismissed.
+ *  ~ slot makes the message replace the line already showing the same slot instead of adding a new line.
+ *  ~ replaces is the id of an earlier message of the same sender this one takes the place of, in place (SenderHandle::update).
+ *  ~ ttl overrides how long the zone's expiry policy would keep the line for its priority.
+ *  ~ widget is structured content (eg. a progress bar) the output zone draws to fit, payload is its plain text version.
+ *  ~ payload is shared, not copied: cloning a message for the scrollback, a topic inbox or a recording reuses the same text.
+ *  ~ sent_at is when the message was made, where its span starts in the OpenTelemetry export. It is not recorded.
+ *  ~ correlation groups the messages sharing it into one collapsible thread (see broker::threads::Threads).
+ *  ~ fields are structured key=value data drawn after the payload, see output::fields::FieldView.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    #[serde(default)]
+    pub id: u64,
+    pub sender: String,
+    pub priority: PriorityStatus,
+    pub payload: Arc<str>,
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(default)]
+    pub tab: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub slot: Option<u64>,
+    #[serde(default)]
+    pub replaces: Option<u64>,
+    #[serde(default)]
+    pub ttl: Option<std::time::Duration>,
+    #[serde(default)]
+    pub widget: Option<Widget>,
+    #[serde(skip)]
+    pub sent_at: Option<std::time::SystemTime>,
+    #[serde(default)]
+    pub correlation: Option<String>,
+    #[serde(default)]
+    pub fields: HashMap<String, Value>,
+}
+
+impl Message {
+    pub fn new(sender: String, priority: PriorityStatus, payload: impl Into<Arc<str>>) -> Self {
+        Message { id: next_id(), sender, priority, payload: payload.into(), topic: None, zone: None, tab: None, pinned: false, slot: None, replaces: None, ttl: None, widget: None, sent_at: Some(std::time::SystemTime::now()), correlation: None, fields: HashMap::new() }
+    }
+
+    /**
+     * Attach a widget drawn in the given slot
+     */
+    pub fn with_widget(mut self, slot: u64, widget: Widget) -> Self {
+        self.slot = Some(slot);
+        self.widget = Some(widget);
+        self
+    }
+
+    /**
+     * Show the message in place of an earlier one of the same sender
+     */
+    pub fn replacing(mut self, id: u64) -> Self {
+        self.replaces = Some(id);
+        self
+    }
+
+    /**
+     * Keep the line for ttl, whatever its priority
+     */
+    pub fn with_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /**
+     * Whether this is the message with the given id, or what replaced it, from the sender
+     */
+    pub fn is_or_replaces(&self, id: u64, sender: &str) -> bool {
+        (self.id == id || self.replaces == Some(id)) && self.sender == sender
+    }
+
+    /**
+     * Pin the message to the top of its zone
+     */
+    pub fn pinned(mut self) -> Self {
+        self.pinned = true;
+        self
+    }
+
+    /**
+     * Tag the message with a topic
+     */
+    pub fn with_topic(mut self, topic: String) -> Self {
+        self.topic = Some(topic);
+        self
+    }
+
+    /**
+     * Thread the message with the others of the same correlation id
+     */
+    pub fn with_correlation(mut self, correlation: String) -> Self {
+        self.correlation = Some(correlation);
+        self
+    }
+
+    /**
+     * Add a key=value field, drawn after the payload
+     */
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.fields.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_fields(mut self, fields: HashMap<String, Value>) -> Self {
+        self.fields.extend(fields);
+        self
+    }
+
+    /**
+     * A message emitted by the Console itself
+     */
+    pub fn from_console(payload: String) -> Self {
+        Message::new(CONSOLE_SENDER.to_string(), PriorityStatus::Informational, payload)
+    }
+}
+
+/**
+ * What the broker hands to the output zone
+ *  ~ Line is a message to draw.
+ *  ~ Dismiss unpins the pinned message with the given id, or every pinned message when None.
+ *  ~ Retract takes a message of the sender (and whatever replaced it) out of the zones and their scrollback.
+ *  ~ Search starts, moves or ends a scrollback search in the focused zone.
+ *  ~ Pause freezes (true) or resumes (false) drawing of the output zones, messages keep arriving underneath.
+ *  ~ Redraw draws every zone again, eg. after the terminal or the input zone changed size.
+ *  ~ Scroll moves the focused zone through its scrollback and Focus gives the keyboard focus to an output zone, or the input zone with None.
+ *  ~ Select changes the mouse selection and Copy puts the selection or the newest messages on the clipboard.
+ *  ~ Export saves the scrollback of every zone to a file.
+ *  ~ Highlights replaces the highlight rules the zones are drawn with.
+ *  ~ Notify calls the user back for a message that was just drawn, with the bell and/or a desktop notification.
+ *  ~ Window adds the output window of a SubConsole, a tab kept off screen or a zone of its own, see SubWindow.
+ *  ~ Tab switches tabs when the tab bar is on (Alt+Left/Alt+Right and /tab).
+ *  ~ View shows a SubConsole's window (or another zone) in the first zone's place, None puts it back (/view).
+ *  ~ Split splits the output area between two new zones with these names, None ends the split (/split), see broker::split::Split.
+ *  ~ Detach lets go of the console's own terminal, the zones go on for the clients attached to the session (/detach), see broker::session.
+ *  ~ Private is a line only the client to sees, a /msg: the console's own terminal when it is USER_SENDER and the client of the
+ *    session with that name otherwise. It is kept out of the recording and the session backlog.
+ */
+#[derive(Debug, Clone)]
+pub enum Output {
+    Line(Message),
+    Dismiss(Option<u64>),
+    Retract {
+        sender: String,
+        id: u64,
+    },
+    Search(SearchCommand),
+    Pause(bool),
+    Redraw,
+    Scroll(ScrollCommand),
+    Focus(Option<usize>),
+    Select(SelectCommand),
+    Copy(CopyTarget),
+    Export {
+        path: std::path::PathBuf,
+        format: ExportFormat,
+    },
+    Highlights(Highlights),
+    Notify {
+        alert: Alert,
+        message: Message,
+    },
+    Window {
+        spec: ZoneSpec,
+        tab: bool,
+    },
+    View(Option<String>),
+    Tab(TabCommand),
+    Split(Option<[String; 2]>),
+    Detach,
+    Private {
+        to: String,
+        message: Message,
+    },
+}
+
+/**
+ * Everything that can travel on the Console channel.
+ *  ~ Message is output from a module.
+ *  ~ UserInput is a line typed into the input zone, which may be a /command.
+ *  ~ Routed is a module to module message, delivered to the target's inbox instead of the output zone.
+ *  ~ Dismiss unpins a message, only the sender that pinned it may dismiss it this way.
+ *  ~ Search comes from the search keybinds in the input zone.
+ *  ~ Pause comes from Ctrl+S (true) and Ctrl+Q (false) in the input zone.
+ *  ~ Redraw asks the output zones to draw again because the screen layout changed.
+ *  ~ Scroll and Focus come from the scroll and focus keybinds in the input zone, or the mouse.
+ *  ~ Select comes from clicking and dragging in an output zone.
+ *  ~ Tab comes from the tab keybinds in the input zone.
+ *  ~ Copy is a module asking for output to be put on the clipboard (SenderHandle::copy_output).
+ *  ~ Action is a custom keybind action, the broker delivers it to every module inbox.
+ *  ~ Input is a module asking the user for something through the input zone (eg. SenderHandle::read_secret).
+ *  ~ Command is a module running a /command as if the user typed it (SenderHandle::run_command), needs ExecuteCommands.
+ *  ~ Heartbeat is a module saying it is still alive without sending anything to show (SenderHandle::heartbeat).
+ *  ~ Retract is a module taking back a message it sent (SenderHandle::retract).
+ *  ~ Schedule is a module handing over a message to deliver later (SenderHandle::send_after), Unschedule cancels one of its own.
+ *  ~ SubConsole is a module asking for a SubConsole of its own (SenderHandle::spawn_subconsole), needs SpawnSubConsole.
+ *  ~ App is an application event of the Console's own type (SenderHandle::emit), for the application or a module's event inbox.
+ *  ~ Bridged is a line of a SubConsole of another console, come over a SubConsoleBridge (see broker::distribute).
+ *  ~ SessionInput is a line typed at a client attached to the session (see broker::session), shown as "[client] > line"
+ *    ("<nick> line" in chat mode, see broker::chat::Chat) and run as that client.
+ *  ~ Remote is a service outside the process asking something of the broker (see broker::remote), answered on reply and done as client.
+ *  ~ SessionAudit is the session socket recording a client authenticating or failing to, in the audit log as that client.
+ *  ~ Shell is a /sh line the user confirmed, run without asking again.
+ *  ~ Page is the step the user took the pager of a long command result to, see broker::pager::Pager.
+ *  ~ Thread expands or collapses a thread of correlated messages, from the toggle-thread keybind.
+ *  ~ Detach comes from dualZoneCli detach through the session socket, the console's own terminal lets go of it like /detach.
+ *  ~ Shutdown replaces the old "USER_BREAK_$0uU" sentinel string.
+ */
+#[derive(Debug)]
+pub enum ConsoleEvent<T = ()> {
+    Message(Message),
+    UserInput(String),
+    Routed {
+        message: Message,
+        target: String,
+        ack: Option<oneshot::Sender<DeliveryReport>>,
+    },
+    Dismiss {
+        sender: String,
+        id: u64,
+    },
+    Search(SearchCommand),
+    Pause(bool),
+    Redraw,
+    Scroll(ScrollCommand),
+    Focus(Option<usize>),
+    Select(SelectCommand),
+    Tab(TabCommand),
+    Copy {
+        sender: String,
+        target: CopyTarget,
+    },
+    Action(String),
+    Input {
+        sender: String,
+        request: InputRequest,
+    },
+    Command {
+        sender: String,
+        line: String,
+    },
+    Heartbeat(String),
+    Retract {
+        sender: String,
+        id: u64,
+    },
+    Schedule(Scheduled),
+    Unschedule {
+        sender: String,
+        id: u64,
+    },
+    SubConsole {
+        sender: String,
+        name: String,
+        window: SubWindow,
+        reply: oneshot::Sender<SubConsole<T>>,
+    },
+    App {
+        sender: String,
+        target: Option<String>,
+        payload: T,
+    },
+    Bridged(BridgedMessage),
+    SessionInput {
+        client: String,
+        line: String,
+    },
+    Remote {
+        client: String,
+        request: RemoteRequest,
+        reply: oneshot::Sender<RemoteReply>,
+    },
+    SessionAudit {
+        client: String,
+        action: AuditAction,
+        subject: String,
+    },
+    Shell(String),
+    Page {
+        id: u64,
+        step: PageStep,
+    },
+    Thread(ThreadCommand),
+    Detach,
+    Shutdown,
+}
+
+/**
+ * An application event as the application or a module's event inbox receives it, see Console::next_event
+ *  ~ sender is the signed id of the module that emitted it, display_name() gives its name.
+ *  ~ target is the event inbox it was emitted to, None when it was for the application.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppEvent<T> {
+    pub sender: String,
+    pub target: Option<String>,
+    pub payload: T,
+}
+
+/**
+ * A line as the zones got it, for what takes the output out of the console (syslog, the journal, ...), see Console::rendered_lines.
+ * sender is the name of the sender, not its signed id, sent_at when the message was made (see Message).
+ * text is the message's payload shared, not copied, like every clone of the message
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedLine {
+    pub id: u64,
+    pub sent_at: Option<std::time::SystemTime>,
+    pub sender: String,
+    pub priority: PriorityStatus,
+    pub text: Arc<str>,
+    pub topic: Option<String>,
+    pub fields: HashMap<String, Value>,
+}
+
+impl RenderedLine {
+    pub fn new(sender: String, message: &Message) -> Self {
+        RenderedLine {
+            id: message.id,
+            sent_at: message.sent_at,
+            sender,
+            priority: message.priority.clone(),
+            text: message.payload.clone(),
+            topic: message.topic.clone(),
+            fields: message.fields.clone(),
+        }
+    }
+}
+
+/**
+ * What Console::next_event hands back: the built-in output the zones draw, or an application event
+ */
+//Output is by far the common case, it is not boxed to make room for App
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum BrokerEvent<T> {
+    Console(Output),
+    App(AppEvent<T>),
+}
@@ -0,0 +1,120 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+
+use crate::output::table::{Align, Table};
+
+//External
+use serde::{Serialize, Deserialize};
+use tokio::time::Duration;
+
+/**
+ * Running counters kept by the broker, turned into a Metrics snapshot on request
+ */
+#[derive(Debug, Default)]
+pub struct BrokerCounters {
+    pub received: u64,
+    pub rendered: u64,
+    pub per_sender: HashMap<String, u64>,
+    pub render_total: Duration,
+    pub render_max: Duration,
+    pub render_last: Duration,
+}
+
+impl BrokerCounters {
+    pub fn record_received(&mut self, sender: &str) {
+        self.received += 1;
+        *self.per_sender.entry(sender.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn record_render(&mut self, took: Duration) {
+        self.rendered += 1;
+        self.render_total += took;
+        self.render_last = took;
+        if took > self.render_max {
+            self.render_max = took;
+        }
+    }
+}
+
+/**
+ * Snapshot of the broker state for library users, see Console::metrics()
+ *  ~ Sender maps are keyed by plaintext name.
+ *  ~ dropped counts every message that went to the dead-letter queue, whatever the reason.
+ *  ~ Render times are in microseconds.
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metrics {
+    pub received: u64,
+    pub rendered: u64,
+    pub dropped: u64,
+    pub per_sender: HashMap<String, u64>,
+    pub rate_limited: HashMap<String, u64>,
+    pub delayed: usize,
+    pub channel_depth: usize,
+    pub channel_capacity: usize,
+    pub overflow_depth: usize,
+    pub dropped_oldest: u64,
+    pub dropped_newest: u64,
+    pub spilled: u64,
+    pub render_avg_us: u64,
+    pub render_max_us: u64,
+    pub render_last_us: u64,
+}
+
+/**
+ * name=count pairs sorted by name, or "none"
+ */
+fn format_counts(counts: &HashMap<String, u64>) -> String {
+    let mut pairs: Vec<String> = counts.iter().map(|(name, count)| format!("{}={}", name, count)).collect();
+    if pairs.is_empty() {
+        return "none".to_string();
+    }
+    pairs.sort();
+    pairs.join(", ")
+}
+
+impl Metrics {
+    /**
+     * The /stats table, one metric per row
+     */
+    pub fn table(&self) -> Table {
+        let rows = vec![
+            ("received", self.received.to_string()),
+            ("rendered", self.rendered.to_string()),
+            ("dropped", self.dropped.to_string()),
+            ("queue", format!("{}/{}", self.channel_depth, self.channel_capacity)),
+            ("overflow", self.overflow_depth.to_string()),
+            ("delayed", self.delayed.to_string()),
+            ("dropped oldest", self.dropped_oldest.to_string()),
+            ("dropped newest", self.dropped_newest.to_string()),
+            ("spilled", self.spilled.to_string()),
+            ("render avg", format!("{}us", self.render_avg_us)),
+            ("render max", format!("{}us", self.render_max_us)),
+            ("senders", format_counts(&self.per_sender)),
+            ("rate limited", format_counts(&self.rate_limited)),
+        ];
+        let mut table = Table::new().column("stat".to_string(), Align::Left).column("value".to_string(), Align::Right);
+        for (name, value) in rows {
+            table.push_row(vec![name.to_string(), value]);
+        }
+        table
+    }
+
+    /**
+     * The one line /stats summary
+     */
+    pub fn status_line(&self) -> String {
+        format!(
+            "[stats] received: {} rendered: {} dropped: {} | queue: {}/{} overflow: {} delayed: {} | dropped oldest: {} newest: {} spilled: {} | render avg: {}us max: {}us | senders: {} | rate limited: {}",
+            self.received, self.rendered, self.dropped,
+            self.channel_depth, self.channel_capacity, self.overflow_depth, self.delayed,
+            self.dropped_oldest, self.dropped_newest, self.spilled,
+            self.render_avg_us, self.render_max_us,
+            format_counts(&self.per_sender), format_counts(&self.rate_limited)
+        )
+    }
+}
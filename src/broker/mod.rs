@@ -0,0 +1,52 @@
+pub mod ids;
+pub mod handle;
+pub mod message;
+pub mod mailbox;
+pub mod rate_limit;
+pub mod dead_letter;
+pub mod memory;
+pub mod pattern;
+pub mod filter;
+pub mod split;
+pub mod dedup;
+pub mod phonebook;
+pub mod capability;
+pub mod audit;
+pub mod persist;
+pub mod hooks;
+pub mod plugin;
+pub mod scripts;
+pub mod schedule;
+pub mod routing;
+pub mod metrics;
+pub mod dispatch;
+pub mod auth;
+pub mod session;
+pub mod mqtt;
+pub mod distribute;
+pub mod remote;
+pub mod syslog;
+pub mod process;
+pub mod shell;
+pub mod tail;
+pub mod ingest;
+pub mod pager;
+pub mod threads;
+pub mod alerts;
+pub mod webhook;
+pub mod relay;
+pub mod chat;
+#[cfg(all(feature = "systemd", target_os = "linux"))]
+pub mod journald;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "otel")]
+pub mod otel;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
\ No newline at end of file
@@ -0,0 +1,164 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use crate::broker::handle::SenderHandle;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use serde::Deserialize;
+use tokio::time::Duration;
+
+/**
+ * How long the bridge waits before connecting again after the broker went away
+ */
+pub const MQTT_RETRY: Duration = Duration::from_secs(5);
+
+/**
+ * One subscription of the bridge: the messages published to topics matching filter come from sender.
+ * filter is an MQTT topic filter, + stands for one level and # (last) for the rest, eg. home/+/temperature or alerts/#
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttSubscription {
+    pub filter: String,
+    pub sender: String,
+}
+
+/**
+ * An MQTT client that brings what is published on its topics into the output zone, see Console::set_mqtt_bridge.
+ *  ~ Every sender of the subscriptions is a sender of the console like new_sender() gives out, so it shows in /senders
+ *    and can be blacklisted like any module.
+ *  ~ A message keeps the topic it was published to as its topic, so it gets a tab of its own when tabs are on.
+ *  ~ A payload that is a JSON object with a text (and optionally a priority) is shown as that, any other as it is, Normal.
+ *  ~ Losing the broker is said in the output zone, the bridge connects again every MQTT_RETRY until it is back.
+ * Needs the mqtt feature.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttBridge {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub subscriptions: Vec<MqttSubscription>,
+}
+
+impl MqttBridge {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        MqttBridge { host: host.into(), port, client_id: "dualzone".to_string(), username: None, password: None, subscriptions: Vec::new() }
+    }
+
+    pub fn subscribe(mut self, filter: impl Into<String>, sender: impl Into<String>) -> Self {
+        self.subscriptions.push(MqttSubscription { filter: filter.into(), sender: sender.into() });
+        self
+    }
+
+    /**
+     * The sender of the first subscription a topic matches
+     */
+    pub fn sender_of(&self, topic: &str) -> Option<&str> {
+        self.subscriptions.iter().find(|subscription| topic_matches(&subscription.filter, topic)).map(|subscription| subscription.sender.as_str())
+    }
+
+    /**
+     * Every sender of the subscriptions once
+     */
+    pub fn senders(&self) -> Vec<String> {
+        let mut senders: Vec<String> = Vec::new();
+        for subscription in self.subscriptions.iter() {
+            if !senders.contains(&subscription.sender) {
+                senders.push(subscription.sender.clone());
+            }
+        }
+        senders
+    }
+}
+
+/**
+ * Whether a topic matches an MQTT topic filter
+ */
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut levels = topic.split('/');
+    for part in filter.split('/') {
+        match (part, levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {},
+            (part, Some(level)) if part == level => {},
+            _ => return false,
+        }
+    }
+    levels.next().is_none()
+}
+
+#[derive(Deserialize)]
+struct JsonPayload {
+    text: String,
+    priority: Option<String>,
+}
+
+/**
+ * What a payload shows as, the text and priority of a JSON payload or the payload itself
+ */
+pub fn payload_message(payload: &[u8]) -> (String, PriorityStatus) {
+    let text = String::from_utf8_lossy(payload);
+    match serde_json::from_str::<JsonPayload>(&text) {
+        Ok(json) => {
+            let priority = json.priority.as_deref().and_then(PriorityStatus::parse).unwrap_or(PriorityStatus::Normal);
+            (json.text, priority)
+        },
+        Err(_) => (text.trim_end().to_string(), PriorityStatus::Normal),
+    }
+}
+
+/**
+ * Run the bridge until the console goes away. senders are the handles of the subscriptions' senders by name,
+ * status is the bridge's own, for the lines about the connection
+ */
+#[cfg(feature = "mqtt")]
+pub async fn run(bridge: MqttBridge, senders: HashMap<String, SenderHandle>, status: SenderHandle) {
+    use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+
+    let mut options = MqttOptions::new(bridge.client_id.clone(), bridge.host.clone(), bridge.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let (Some(username), Some(password)) = (bridge.username.clone(), bridge.password.clone()) {
+        options.set_credentials(username, password);
+    }
+    let (client, mut events) = AsyncClient::new(options, 64);
+    loop {
+        match events.poll().await {
+            //subscriptions don't outlive the connection, every new one asks again
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                for subscription in bridge.subscriptions.iter() {
+                    if let Err(e) = client.subscribe(subscription.filter.clone(), QoS::AtLeastOnce).await {
+                        let _ = status.send_with_priority(format!("[mqtt] could not subscribe to {}: {}", subscription.filter, e), PriorityStatus::Warning).await;
+                    }
+                }
+                let _ = status.send(format!("[mqtt] connected to {}:{}, {} topics", bridge.host, bridge.port, bridge.subscriptions.len())).await;
+            },
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let handle = match bridge.sender_of(&publish.topic).and_then(|sender| senders.get(sender)) {
+                    Some(handle) => handle,
+                    None => continue,
+                };
+                let (text, priority) = payload_message(&publish.payload);
+                if handle.send_to_topic(publish.topic.clone(), text, priority).await.is_err() {
+                    return;
+                }
+            },
+            Ok(_) => {},
+            Err(e) => {
+                if status.send_with_priority(format!("[mqtt] {}:{} lost: {}, trying again", bridge.host, bridge.port, e), PriorityStatus::Warning).await.is_err() {
+                    return;
+                }
+                tokio::time::sleep(MQTT_RETRY).await;
+            },
+        }
+    }
+}
+
+#[cfg(not(feature = "mqtt"))]
+pub async fn run(bridge: MqttBridge, senders: HashMap<String, SenderHandle>, status: SenderHandle) {
+    let _ = status.send_with_priority("[mqtt] not bridging, built without the mqtt feature".to_string(), PriorityStatus::Warning).await;
+}
@@ -0,0 +1,60 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::distribute::{BridgeError, BridgedMessage, SubConsoleBridge};
+
+//External
+use futures::StreamExt;
+
+/**
+ * SubConsoles distributed over NATS, a lighter bus to run than AMQP.
+ *  ~ A line of the SubConsole builder goes out on <subject>.builder, every console on the subject gets it (core NATS, nothing is kept).
+ *  ~ The subscription is <subject>.>, so the bridge also gets its own lines back, broker::distribute::run leaves those out.
+ *  ~ The client reconnects on its own when the server goes away, lines published meanwhile are buffered by it.
+ */
+pub struct NatsBridge {
+    client: async_nats::Client,
+    subject: String,
+    subscriber: async_nats::Subscriber,
+}
+
+impl NatsBridge {
+    pub async fn connect(url: &str, subject: &str) -> Result<NatsBridge, BridgeError> {
+        let client = async_nats::connect(url).await.map_err(|e| BridgeError::Connect(e.to_string()))?;
+        let subscriber = client.subscribe(format!("{}.>", subject)).await.map_err(|e| BridgeError::Connect(e.to_string()))?;
+        Ok(NatsBridge { client, subject: subject.to_string(), subscriber })
+    }
+}
+
+/**
+ * A SubConsole name as one subject token, the characters NATS gives a meaning replaced
+ */
+fn token(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+impl SubConsoleBridge for NatsBridge {
+    async fn publish(&mut self, message: BridgedMessage) -> Result<(), BridgeError> {
+        let payload = serde_json::to_vec(&message).map_err(|e| BridgeError::Send(e.to_string()))?;
+        let subject = format!("{}.{}", self.subject, token(&message.subconsole));
+        self.client.publish(subject, payload.into()).await.map_err(|e| BridgeError::Send(e.to_string()))
+    }
+
+    async fn receive(&mut self) -> Option<BridgedMessage> {
+        loop {
+            let received = self.subscriber.next().await?;
+            //whatever else is published on the subject isn't ours to show
+            if let Ok(message) = serde_json::from_slice::<BridgedMessage>(&received.payload) {
+                return Some(message);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for NatsBridge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NatsBridge {{ subject: {} }}", self.subject)
+    }
+}
@@ -0,0 +1,102 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::time::SystemTime;
+use crate::broker::mailbox::{MailboxSender, MailboxStats};
+use crate::broker::message::RenderedLine;
+use crate::broker::metrics::Metrics;
+
+//External
+use opentelemetry::metrics::{Meter, MeterProvider as _};
+use opentelemetry::trace::{Span, SpanKind, Tracer};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, Resource};
+use tokio::sync::{mpsc, watch};
+use tokio::time::Duration;
+
+/**
+ * How often the counters go out, the spans are batched by the exporter on its own
+ */
+pub const METRICS_PERIOD: Duration = Duration::from_secs(10);
+
+fn resource() -> Resource {
+    Resource::new(vec![KeyValue::new("service.name", "dualzone")])
+}
+
+/**
+ * A counter read from the broker's own snapshot every period, like the Prometheus exporter does
+ */
+fn observe(meter: &Meter, name: &'static str, description: &'static str, metrics: &watch::Receiver<Metrics>, read: fn(&Metrics) -> u64) {
+    let metrics = metrics.clone();
+    meter.u64_observable_counter(name)
+        .with_description(description)
+        .with_callback(move |observer| observer.observe(read(&metrics.borrow()), &[]))
+        .init();
+}
+
+/**
+ * A counter read from the channel as it is right now, a stalled broker still shows in them
+ */
+fn observe_live(meter: &Meter, name: &'static str, description: &'static str, channel: &MailboxSender, read: fn(&MailboxStats) -> u64) {
+    let channel = channel.clone();
+    meter.u64_observable_counter(name)
+        .with_description(description)
+        .with_callback(move |observer| observer.observe(read(&channel.stats()), &[]))
+        .init();
+}
+
+/**
+ * Export the broker's telemetry over OTLP (gRPC) to endpoint, eg. http://localhost:4317, until the console goes away.
+ *  ~ A span per rendered line, from the moment the message was made (Message::sent_at) to the broker handing it to the zones,
+ *    with the sender, priority and topic as attributes. A slow sender, a full queue or a rate limit shows as a long span.
+ *  ~ Counters of what the broker received, rendered and dropped (dead letters, drop-oldest, drop-newest, rate limits),
+ *    sent every METRICS_PERIOD.
+ * metrics comes from Console::watch_metrics(), channel is a clone of Console.tx and lines from Console::rendered_lines()
+ */
+pub async fn export(endpoint: String, metrics: watch::Receiver<Metrics>, channel: MailboxSender, mut lines: mpsc::UnboundedReceiver<RenderedLine>)
+    -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint.clone()))
+        .with_trace_config(opentelemetry_sdk::trace::config().with_resource(resource()))
+        .install_batch(runtime::Tokio)?;
+    let meters = opentelemetry_otlp::new_pipeline()
+        .metrics(runtime::Tokio)
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+        .with_resource(resource())
+        .with_period(METRICS_PERIOD)
+        .build()?;
+
+    let meter = meters.meter("dualzone");
+    observe(&meter, "dualzone.messages.received", "Messages received by the broker.", &metrics, |metrics| metrics.received);
+    observe(&meter, "dualzone.messages.rendered", "Messages drawn in the output zone.", &metrics, |metrics| metrics.rendered);
+    observe(&meter, "dualzone.messages.dropped", "Messages sent to the dead-letter queue.", &metrics, |metrics| metrics.dropped);
+    observe(&meter, "dualzone.messages.rate_limited", "Messages over a sender's rate limit.", &metrics, |metrics| metrics.rate_limited.values().sum());
+    observe_live(&meter, "dualzone.messages.dropped_oldest", "Messages evicted by the drop-oldest overflow policy.", &channel, |live| live.dropped_oldest);
+    observe_live(&meter, "dualzone.messages.dropped_newest", "Messages refused by the drop-newest overflow policy.", &channel, |live| live.dropped_newest);
+    observe_live(&meter, "dualzone.messages.spilled", "Messages moved to the overflow queue.", &channel, |live| live.spilled);
+
+    while let Some(line) = lines.recv().await {
+        let mut attributes = vec![
+            KeyValue::new("dualzone.sender", line.sender),
+            KeyValue::new("dualzone.priority", format!("{:?}", line.priority)),
+            KeyValue::new("dualzone.message_id", line.id as i64),
+        ];
+        if let Some(topic) = line.topic {
+            attributes.push(KeyValue::new("dualzone.topic", topic));
+        }
+        let mut span = tracer.span_builder("message")
+            .with_kind(SpanKind::Internal)
+            .with_start_time(line.sent_at.unwrap_or_else(SystemTime::now))
+            .with_attributes(attributes)
+            .start(&tracer);
+        span.end_with_timestamp(SystemTime::now());
+    }
+    //what is still batched goes out before the exporter is let go of
+    opentelemetry::global::shutdown_tracer_provider();
+    meters.shutdown()?;
+    Ok(())
+}
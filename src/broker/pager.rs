@@ -0,0 +1,112 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::{HashMap, VecDeque};
+use crate::broker::message::Message;
+use crate::user_input::dialog::PageStep;
+
+/**
+ * Which /commands page a result that is taller than the output zone, see Console::set_pager_policy.
+ * enabled is every command's unless commands says otherwise for it, by name without the slash (eg. "audit")
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PagerPolicy {
+    pub enabled: bool,
+    pub commands: HashMap<String, bool>,
+}
+
+impl Default for PagerPolicy {
+    fn default() -> Self {
+        PagerPolicy { enabled: true, commands: HashMap::new() }
+    }
+}
+
+impl PagerPolicy {
+    pub fn pages(&self, command: &str) -> bool {
+        self.commands.get(&command.to_ascii_lowercase()).copied().unwrap_or(self.enabled)
+    }
+}
+
+/**
+ * The rows a line takes in the zone, a line of several lines of text takes one for each
+ */
+fn rows(message: &Message) -> usize {
+    message.payload.lines().count().max(1)
+}
+
+/**
+ * The rest of a long command result, shown a page at a time as the user asks for it instead of scrolled past at once.
+ * id tells its answers apart from those of one that is done with, page is the height of the output zone in rows
+ */
+#[derive(Debug)]
+pub struct Pager {
+    pub id: u64,
+    pub command: String,
+    lines: VecDeque<Message>,
+    page: usize,
+}
+
+impl Pager {
+    /**
+     * Split a result into its first page and a pager with the rest, None when it fits the zone as it is
+     */
+    pub fn start(id: u64, command: String, lines: Vec<Message>, page: usize) -> (Vec<Message>, Option<Pager>) {
+        let page = page.max(1);
+        if lines.iter().map(rows).sum::<usize>() <= page {
+            return (lines, None);
+        }
+        let mut pager = Pager { id, command, lines: lines.into(), page };
+        let first = pager.take(page);
+        (first, Some(pager))
+    }
+
+    /**
+     * The lines that make up rows rows, at least one line however tall it is
+     */
+    fn take(&mut self, rows_wanted: usize) -> Vec<Message> {
+        let mut taken = Vec::new();
+        let mut used = 0;
+        while let Some(line) = self.lines.front() {
+            if !taken.is_empty() && used + rows(line) > rows_wanted {
+                break;
+            }
+            used += rows(line);
+            taken.extend(self.lines.pop_front());
+        }
+        taken
+    }
+
+    /**
+     * A result that comes while this one is paged waits behind it
+     */
+    pub fn extend(&mut self, lines: Vec<Message>) {
+        self.lines.extend(lines);
+    }
+
+    /**
+     * The lines the step shows, Quit drops the rest
+     */
+    pub fn step(&mut self, step: PageStep) -> Vec<Message> {
+        match step {
+            PageStep::Page => self.take(self.page),
+            PageStep::Line => self.take(1),
+            PageStep::Quit => {
+                self.lines.clear();
+                Vec::new()
+            },
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /**
+     * What the input zone shows while the pager waits
+     */
+    pub fn prompt(&self) -> String {
+        format!("-- /{}: {} more lines, space for a page, enter for a line, q to stop --", self.command, self.lines.len())
+    }
+}
@@ -0,0 +1,404 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+
+/**
+ * Why a pattern doesn't parse, position is the character it gave up at
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternError {
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at character {}", self.reason, self.position)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Char(char),
+    Any,
+    Class { ranges: Vec<(char, char)>, negated: bool },
+    Start,
+    End,
+    //the alternatives of a group, each one a sequence
+    Group(Vec<Vec<Node>>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize>, lazy: bool },
+}
+
+/**
+ * A regular expression, the common subset: literals, ., [a-z] and [^...] classes, \d \w \s and their negations,
+ * ^ and $, groups with |, and the * + ? {n} {n,} {n,m} quantifiers (lazy with a trailing ?).
+ * A leading (?i) makes it case-insensitive. There are no captures or backreferences.
+ *  ~ It is a backtracking matcher, meant for the short patterns of filters and highlights, not for untrusted input.
+ *  ~ Match offsets are in bytes so they can slice the text they were found in.
+ */
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    nodes: Vec<Node>,
+    case_insensitive: bool,
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Pattern, PatternError> {
+        let (case_insensitive, body) = match source.strip_prefix("(?i)") {
+            Some(body) => (true, body),
+            None => (false, source),
+        };
+        let mut parser = Parser { chars: body.chars().collect(), position: 0 };
+        let alternatives = parser.alternatives()?;
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unmatched )"));
+        }
+        Ok(Pattern { source: source.to_string(), nodes: vec![Node::Group(alternatives)], case_insensitive })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    /**
+     * The first match as (start, end) byte offsets
+     */
+    pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+        self.find_at(text, 0)
+    }
+
+    /**
+     * Every match, left to right and not overlapping, as (start, end) byte offsets. Empty matches are skipped
+     */
+    pub fn find_all(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        let mut from = 0;
+        while let Some((start, end)) = self.find_at(text, from) {
+            if end > start {
+                found.push((start, end));
+                from = end;
+            } else {
+                //step over the character an empty match sat in front of
+                match text[start..].chars().next() {
+                    Some(c) => from = start + c.len_utf8(),
+                    None => break,
+                }
+            }
+        }
+        found
+    }
+
+    fn find_at(&self, text: &str, from: usize) -> Option<(usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        //byte offset of every character and of the end
+        let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).chain(std::iter::once(text.len())).collect();
+        let first = offsets.iter().position(|offset| *offset >= from)?;
+        let matcher = Matcher { text: &chars, case_insensitive: self.case_insensitive };
+        for start in first..=chars.len() {
+            let mut end = None;
+            if matcher.sequence(&self.nodes, start, &mut |position| { end = Some(position); true }) {
+                return end.map(|end| (offsets[start], offsets[end]));
+            }
+        }
+        None
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, reason: &'static str) -> PatternError {
+        PatternError { position: self.position, reason }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, PatternError> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.position += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, PatternError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, PatternError> {
+        match self.next() {
+            Some('(') => {
+                if self.chars[self.position..].starts_with(&['?', ':']) {
+                    self.position += 2;
+                }
+                let alternatives = self.alternatives()?;
+                match self.next() {
+                    Some(')') => Ok(Node::Group(alternatives)),
+                    _ => Err(self.error("unclosed (")),
+                }
+            },
+            Some('[') => self.class(),
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('\\') => self.escape(),
+            Some('*') | Some('+') | Some('?') => Err(self.error("nothing to repeat")),
+            Some(c) => Ok(Node::Char(c)),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, PatternError> {
+        let c = self.next().ok_or_else(|| self.error("trailing \\"))?;
+        Ok(match class_escape(c) {
+            Some((ranges, negated)) => Node::Class { ranges, negated },
+            None => Node::Char(literal_escape(c)),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, PatternError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.position += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.next().ok_or_else(|| self.error("unclosed ["))?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = if c == '\\' {
+                let escaped = self.next().ok_or_else(|| self.error("unclosed ["))?;
+                if let Some((class, false)) = class_escape(escaped) {
+                    ranges.extend(class);
+                    continue;
+                }
+                literal_escape(escaped)
+            } else {
+                c
+            };
+            //a - at the end of the class is a literal
+            if self.peek() == Some('-') && self.chars.get(self.position + 1).is_some_and(|next| *next != ']') {
+                self.position += 1;
+                let high = match self.next() {
+                    Some('\\') => literal_escape(self.next().ok_or_else(|| self.error("unclosed ["))?),
+                    Some(high) => high,
+                    None => return Err(self.error("unclosed [")),
+                };
+                if high < low {
+                    return Err(self.error("range out of order"));
+                }
+                ranges.push((low, high));
+            } else {
+                ranges.push((low, low));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, PatternError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.bounds() {
+                Some(bounds) => bounds,
+                //not a quantifier after all, a literal {
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End) {
+            return Err(self.error("nothing to repeat"));
+        }
+        //past the quantifier, or the closing brace bounds() stopped on
+        self.position += 1;
+        let lazy = self.peek() == Some('?');
+        if lazy {
+            self.position += 1;
+        }
+        Ok(Node::Repeat { node: Box::new(atom), min, max, lazy })
+    }
+
+    /**
+     * {n}, {n,} or {n,m} with the position left on the closing brace, None leaves the position alone
+     */
+    fn bounds(&mut self) -> Option<(usize, Option<usize>)> {
+        let close = self.chars[self.position..].iter().position(|c| *c == '}')? + self.position;
+        let inside: String = self.chars[self.position + 1..close].iter().collect();
+        let bounds = match inside.split_once(',') {
+            None => {
+                let count = inside.parse().ok()?;
+                (count, Some(count))
+            },
+            Some((min, "")) => (min.parse().ok()?, None),
+            Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?)),
+        };
+        if bounds.1.is_some_and(|max| max < bounds.0) {
+            return None;
+        }
+        self.position = close;
+        Some(bounds)
+    }
+}
+
+fn class_escape(c: char) -> Option<(Vec<(char, char)>, bool)> {
+    let digits = vec![('0', '9')];
+    let word = vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+    let space = vec![(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r'), ('\x0b', '\x0c')];
+    match c {
+        'd' => Some((digits, false)),
+        'D' => Some((digits, true)),
+        'w' => Some((word, false)),
+        'W' => Some((word, true)),
+        's' => Some((space, false)),
+        'S' => Some((space, true)),
+        _ => None,
+    }
+}
+
+fn literal_escape(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        c => c,
+    }
+}
+
+struct Matcher<'a> {
+    text: &'a [char],
+    case_insensitive: bool,
+}
+
+impl<'a> Matcher<'a> {
+    /**
+     * Match nodes at position, then hand where the match ends to rest, which says whether the whole match succeeded
+     */
+    fn sequence(&self, nodes: &[Node], position: usize, rest: &mut dyn FnMut(usize) -> bool) -> bool {
+        let (node, tail) = match nodes.split_first() {
+            Some(split) => split,
+            None => return rest(position),
+        };
+        match node {
+            Node::Start => position == 0 && self.sequence(tail, position, rest),
+            Node::End => position == self.text.len() && self.sequence(tail, position, rest),
+            Node::Group(alternatives) => alternatives.iter()
+                .any(|alternative| self.sequence(alternative, position, &mut |end| self.sequence(tail, end, rest))),
+            Node::Repeat { node, min, max, lazy } => self.repeat(node, *min, *max, *lazy, 0, tail, position, rest),
+            single => position < self.text.len() && self.single(single, self.text[position]) && self.sequence(tail, position + 1, rest),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(&self, node: &Node, min: usize, max: Option<usize>, lazy: bool, count: usize, tail: &[Node], position: usize, rest: &mut dyn FnMut(usize) -> bool) -> bool {
+        let can_stop = count >= min;
+        let can_go_on = max.is_none_or(|max| count < max);
+        if lazy && can_stop && self.sequence(tail, position, rest) {
+            return true;
+        }
+        //a repetition that matched nothing would go round forever
+        if can_go_on && self.sequence(std::slice::from_ref(node), position, &mut |end| {
+            end != position && self.repeat(node, min, max, lazy, count + 1, tail, end, rest)
+        }) {
+            return true;
+        }
+        !lazy && can_stop && self.sequence(tail, position, rest)
+    }
+
+    fn single(&self, node: &Node, c: char) -> bool {
+        match node {
+            Node::Char(expected) => *expected == c || (self.case_insensitive && same_letter(*expected, c)),
+            Node::Any => c != '\n',
+            Node::Class { ranges, negated } => {
+                let within = |c: char| ranges.iter().any(|(low, high)| *low <= c && c <= *high);
+                let found = within(c) || (self.case_insensitive && (within(lower(c)) || within(upper(c))));
+                found != *negated
+            },
+            _ => false,
+        }
+    }
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn upper(c: char) -> char {
+    c.to_uppercase().next().unwrap_or(c)
+}
+
+fn same_letter(a: char, b: char) -> bool {
+    lower(a) == lower(b)
+}
+
+/**
+ * Shell style wildcards for names, * is any run of characters and ? any one, ASCII case-insensitive
+ */
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    //the last * seen and where in the text it was tried from, to backtrack to
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p].eq_ignore_ascii_case(&text[t])) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
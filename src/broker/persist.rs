@@ -0,0 +1,175 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::broker::ids::ID_PREFIX;
+use crate::broker::phonebook::PhonebookEntry;
+use crate::broker::message::{ConsoleEvent, Message};
+use crate::user_input::structs::{Console, SenderStatus};
+
+//External
+use serde::{Serialize, Deserialize};
+use tokio::time::Duration;
+
+/**
+ * Version of the phonebook file layout, bump this when a field changes meaning
+ */
+pub const PHONEBOOK_FILE_VERSION: u32 = 1;
+
+/**
+ * A saved sender, by plaintext name. Ids are never saved: they are signed with a key made fresh for every Console
+ * (see IdSigner), so they are signed again from the names when the file is loaded.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSender {
+    pub name: String,
+    pub status: SenderStatus,
+    #[serde(default)]
+    pub heartbeat_secs: Option<f64>,
+    #[serde(default)]
+    pub messages: u64,
+}
+
+/**
+ * The phonebook file, JSON.
+ *  ~ id_scheme is the ID_PREFIX of the Console that wrote it. Since only names are saved any scheme can be read,
+ *    the field is there so a later scheme that saves more can tell old files apart and migrate them.
+ *  ~ A file with a newer version than this build knows is refused instead of half read.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhonebookFile {
+    pub version: u32,
+    pub id_scheme: String,
+    pub senders: Vec<SavedSender>,
+}
+
+/**
+ * Reasons the phonebook could not be saved or loaded
+ */
+#[derive(Debug)]
+pub enum PersistError {
+    Io(std::io::Error),
+    Parse(String),
+    Version(u32),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Io(e) => write!(f, "could not access the phonebook file: {}", e),
+            PersistError::Parse(e) => write!(f, "could not parse the phonebook file: {}", e),
+            PersistError::Version(version) => write!(f, "phonebook file version {} is newer than this build reads ({})", version, PHONEBOOK_FILE_VERSION),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl From<std::io::Error> for PersistError {
+    fn from(e: std::io::Error) -> Self {
+        PersistError::Io(e)
+    }
+}
+
+impl PhonebookFile {
+    pub fn load(path: &Path) -> Result<PhonebookFile, PersistError> {
+        let text = fs::read_to_string(path)?;
+        let file: PhonebookFile = serde_json::from_str(&text).map_err(|e| PersistError::Parse(e.to_string()))?;
+        if file.version > PHONEBOOK_FILE_VERSION {
+            return Err(PersistError::Version(file.version));
+        }
+        Ok(file)
+    }
+
+    /**
+     * Written next to the target and renamed over it, a crash mid-write never leaves a truncated phonebook
+     */
+    pub fn save(&self, path: &Path) -> Result<(), PersistError> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| PersistError::Parse(e.to_string()))?;
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        fs::write(&temporary, text)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/**
+ * Phonebook persistence, so a restarted Console knows the modules it authorized before and keeps its bans.
+ *  ~ A saved module is in the phonebook before it asks for a sender again, with its status, heartbeat and message count.
+ *  ~ A blacklisted name stays blacklisted, new_sender() hands it a blacklisted handle as it would have before the restart.
+ */
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Load the phonebook file if it exists and save to it after every change to the senders from now on, None stops saving
+     */
+    pub fn set_phonebook_file(&mut self, path: Option<PathBuf>) -> Result<usize, PersistError> {
+        let loaded = match path.as_deref() {
+            Some(path) if path.exists() => self.load_phonebook(path)?,
+            _ => 0,
+        };
+        self.phonebook_file = path;
+        Ok(loaded)
+    }
+
+    /**
+     * Save every phonebook entry, returns how many were written
+     */
+    pub fn save_phonebook(&self, path: &Path) -> Result<usize, PersistError> {
+        let senders: Vec<SavedSender> = self.phonebook_entries().into_iter().map(|entry| SavedSender {
+            name: entry.name,
+            status: entry.status,
+            heartbeat_secs: entry.heartbeat.map(|interval| interval.as_secs_f64()),
+            messages: entry.messages,
+        }).collect();
+        let count = senders.len();
+        PhonebookFile { version: PHONEBOOK_FILE_VERSION, id_scheme: ID_PREFIX.to_string(), senders }.save(path)?;
+        Ok(count)
+    }
+
+    /**
+     * Add the senders of a phonebook file, signed with this Console's key. Senders already registered keep their entry
+     */
+    pub fn load_phonebook(&mut self, path: &Path) -> Result<usize, PersistError> {
+        let file = PhonebookFile::load(path)?;
+        let mut loaded = 0;
+        for saved in file.senders {
+            let id = self.generate_id(saved.name.clone());
+            if self.phonebook.contains_key(&id) {
+                continue;
+            }
+            //Stale was about the last run, the module gets its full interval again
+            let status = match saved.status {
+                SenderStatus::Stale => SenderStatus::Authorized,
+                status => status,
+            };
+            match status {
+                SenderStatus::Authorized => { self.Authorized.insert(id.clone(), self.tx.clone()); },
+                SenderStatus::BlackListed => { self.BlackListed.insert(id.clone(), self.tx.clone()); },
+                _ => {},
+            }
+            let mut entry = PhonebookEntry::new(saved.name, status);
+            entry.heartbeat = saved.heartbeat_secs.map(Duration::from_secs_f64);
+            entry.messages = saved.messages;
+            self.phonebook.insert(id, entry);
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /**
+     * Save to the phonebook file if there is one, called after every change to the senders
+     */
+    pub(crate) fn persist_phonebook(&self) {
+        if let Some(path) = self.phonebook_file.as_deref() {
+            if let Err(e) = self.save_phonebook(path) {
+                let _ = self.tx.try_send(ConsoleEvent::Message(Message::from_console(format!("[console] {}", e))));
+            }
+        }
+    }
+}
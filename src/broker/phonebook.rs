@@ -0,0 +1,157 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::message::Message;
+use crate::output::table::{Align, Table};
+use crate::output::widgets::Widget;
+use crate::broker::message;
+use crate::user_input::structs::{Console, SenderStatus};
+
+//External
+use tokio::time::{Duration, Instant};
+
+/**
+ * What the Console knows about one registered module, keyed by its signed id in the phonebook
+ *  ~ last_seen is when anything last came from it (a message, a heartbeat, a request), or when it registered.
+ *  ~ messages counts the messages it sent, routed ones included.
+ *  ~ heartbeat is how often it promised to show signs of life, a module silent for longer turns Stale.
+ */
+#[derive(Debug, Clone)]
+pub struct PhonebookEntry {
+    pub name: String,
+    pub status: SenderStatus,
+    pub last_seen: Instant,
+    pub messages: u64,
+    pub heartbeat: Option<Duration>,
+}
+
+impl PhonebookEntry {
+    pub fn new(name: String, status: SenderStatus) -> Self {
+        PhonebookEntry { name, status, last_seen: Instant::now(), messages: 0, heartbeat: None }
+    }
+
+    /**
+     * When the entry turns Stale if nothing comes from it before then
+     */
+    pub fn stale_at(&self) -> Option<Instant> {
+        match (&self.status, self.heartbeat) {
+            (SenderStatus::Authorized, Some(interval)) => Some(self.last_seen + interval),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Liveness tracking, the part of the broker that keeps last_seen up to date and marks silent modules Stale.
+ * Stale only lives in the phonebook, the module's handle keeps working and its next sign of life makes it Authorized again.
+ */
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Ask a module to show signs of life at least every interval, with SenderHandle::heartbeat() or any message.
+     * None drops the requirement, and a Stale module is Authorized again.
+     */
+    pub fn require_heartbeat(&mut self, identifier: String, interval: Option<Duration>) {
+        let id = self.generate_id(identifier);
+        if let Some(entry) = self.phonebook.get_mut(&id) {
+            entry.heartbeat = interval;
+            if interval.is_none() && matches!(entry.status, SenderStatus::Stale) {
+                entry.status = SenderStatus::Authorized;
+            }
+        }
+        self.persist_phonebook();
+    }
+
+    /**
+     * Something came from id, counted as a message when it was one. Returns a note when that brought a Stale module back
+     */
+    pub(crate) fn seen(&mut self, id: &str, message: bool) -> Option<Message> {
+        let entry = self.phonebook.get_mut(id)?;
+        entry.last_seen = Instant::now();
+        if message {
+            entry.messages += 1;
+        }
+        if !matches!(entry.status, SenderStatus::Stale) {
+            return None;
+        }
+        entry.status = SenderStatus::Authorized;
+        Some(Message::from_console(format!("[console] {} is back", entry.name)))
+    }
+
+    /**
+     * Mark every module that missed its heartbeat Stale, with a note for each one
+     */
+    pub(crate) fn check_liveness(&mut self, now: Instant) -> Vec<Message> {
+        let mut notes = Vec::new();
+        for entry in self.phonebook.values_mut() {
+            if entry.stale_at().is_some_and(|at| at <= now) {
+                entry.status = SenderStatus::Stale;
+                notes.push(Message::from_console(format!(
+                    "[console] {} missed its heartbeat, last seen {} ago", entry.name, since(now, entry.last_seen)
+                )));
+            }
+        }
+        notes
+    }
+
+    /**
+     * The soonest time a module turns Stale, the broker wakes up then to notice
+     */
+    pub(crate) fn next_stale_at(&self) -> Option<Instant> {
+        self.phonebook.values().filter_map(|entry| entry.stale_at()).min()
+    }
+
+    /**
+     * Copy of every phonebook entry, with plaintext names and statuses, sorted by name
+     */
+    pub fn phonebook_entries(&self) -> Vec<PhonebookEntry> {
+        let mut entries: Vec<PhonebookEntry> = self.phonebook.values().cloned().collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+
+    /**
+     * The entry of a module by its plaintext name, the reverse of what the signed ids hide
+     */
+    pub fn phonebook_entry(&self, name: &str) -> Option<PhonebookEntry> {
+        self.phonebook.get(&self.generate_id(name.to_string())).cloned()
+    }
+
+    /**
+     * The /who reply, one row per registered module sorted by name
+     */
+    pub(crate) fn who(&self) -> Message {
+        let now = Instant::now();
+        let entries = self.phonebook_entries();
+        let mut table = Table::new()
+            .column("module".to_string(), Align::Left)
+            .column("status".to_string(), Align::Left)
+            .column("messages".to_string(), Align::Right)
+            .column("last seen".to_string(), Align::Right)
+            .column("heartbeat".to_string(), Align::Right);
+        for entry in &entries {
+            table.push_row(vec![
+                entry.name.clone(),
+                format!("{:?}", entry.status),
+                entry.messages.to_string(),
+                format!("{} ago", since(now, entry.last_seen)),
+                entry.heartbeat.map_or("-".to_string(), |interval| format!("{}s", interval.as_secs_f64())),
+            ]);
+        }
+        let stale = entries.iter().filter(|entry| matches!(entry.status, SenderStatus::Stale)).count();
+        Message::from_console(format!("[who] {} modules, {} stale", entries.len(), stale)).with_widget(message::next_id(), Widget::Table(table))
+    }
+}
+
+/**
+ * How long ago then was, in the largest whole unit
+ */
+fn since(now: Instant, then: Instant) -> String {
+    let secs = now.saturating_duration_since(then).as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
@@ -0,0 +1,252 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::ffi::{c_char, c_void, CStr};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use crate::broker::handle::SenderHandle;
+use crate::broker::message::Message;
+use crate::user_input::structs::{Console, PriorityStatus};
+
+/**
+ * Version of the plugin interface below, a plugin built for another version is refused by its entry point (it sees abi_version)
+ */
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/**
+ * The symbols a plugin library exports, only the entry point is required
+ *  ~ dz_plugin_entry(host: *const PluginHost) -> i32, called once when loaded, 0 means ready.
+ *  ~ dz_plugin_command(line: *const c_char), called with the whole line when the user types a command the plugin registered.
+ *  ~ dz_plugin_unload(), called before the library is closed, the plugin must stop calling the host before returning.
+ */
+pub const PLUGIN_ENTRY: &[u8] = b"dz_plugin_entry\0";
+pub const PLUGIN_COMMAND: &[u8] = b"dz_plugin_command\0";
+pub const PLUGIN_UNLOAD: &[u8] = b"dz_plugin_unload\0";
+
+/**
+ * What the host gives a plugin, a C struct so plugins can be built with any compiler (or in another language).
+ *  ~ The struct itself is only valid during dz_plugin_entry, copy it. context stays valid until dz_plugin_unload returns.
+ *  ~ send puts a line in the output zone as the plugin's own sender, priority is the index of the PriorityStatus
+ *    variant in declaration order (0 Urgent ... 7 Normal ... 9 Informational). It may be called from any thread.
+ *  ~ register_command claims a /command name (without the slash), typed lines starting with it go to dz_plugin_command.
+ *  ~ Both return 0 on success and a negative number otherwise: -1 bad argument, -2 rejected by the Console.
+ */
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PluginHost {
+    pub abi_version: u32,
+    pub context: *mut c_void,
+    pub send: extern "C" fn(context: *mut c_void, priority: u8, text: *const c_char) -> i32,
+    pub register_command: extern "C" fn(context: *mut c_void, name: *const c_char) -> i32,
+}
+
+/**
+ * Reasons a plugin could not be loaded
+ */
+#[derive(Debug)]
+pub enum PluginError {
+    Unsupported,
+    Load(String),
+    Init(i32),
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::Unsupported => write!(f, "plugin support is not compiled in, build with --features plugins"),
+            PluginError::Load(e) => write!(f, "could not load plugin: {}", e),
+            PluginError::Init(status) => write!(f, "plugin failed to start ({})", status),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+/**
+ * What the host callbacks reach through the context pointer, boxed so its address stays put
+ */
+struct PluginContext<T> {
+    handle: SenderHandle<T>,
+    commands: Mutex<Vec<String>>,
+}
+
+const PRIORITIES: [PriorityStatus; 10] = [
+    PriorityStatus::Urgent,
+    PriorityStatus::Critical,
+    PriorityStatus::Notice,
+    PriorityStatus::Warning,
+    PriorityStatus::Exception,
+    PriorityStatus::Delay,
+    PriorityStatus::Verbose,
+    PriorityStatus::Normal,
+    PriorityStatus::Ignore,
+    PriorityStatus::Informational,
+];
+
+/**
+ * The text a plugin handed over, None for a null pointer or text that is not UTF-8
+ */
+unsafe fn plugin_str<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+extern "C" fn host_send<T>(context: *mut c_void, priority: u8, text: *const c_char) -> i32 {
+    //the context is the PluginContext boxed in LoadedPlugin, alive until dz_plugin_unload returned
+    let context = unsafe { &*(context as *const PluginContext<T>) };
+    let (priority, text) = match (PRIORITIES.get(priority as usize), unsafe { plugin_str(text) }) {
+        (Some(priority), Some(text)) => (priority.clone(), text),
+        _ => return -1,
+    };
+    let message = Message::new(context.handle.id().to_string(), priority, text);
+    match context.handle.try_send_message(message) {
+        Ok(()) => 0,
+        Err(_) => -2,
+    }
+}
+
+extern "C" fn host_register_command<T>(context: *mut c_void, name: *const c_char) -> i32 {
+    let context = unsafe { &*(context as *const PluginContext<T>) };
+    match unsafe { plugin_str(name) } {
+        Some(name) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+            context.commands.lock().unwrap().push(name.trim_start_matches('/').to_ascii_lowercase());
+            0
+        },
+        _ => -1,
+    }
+}
+
+/**
+ * A loaded library, unloaded when dropped. The library is the last field so it is closed after everything that points into it
+ */
+struct LoadedPlugin<T> {
+    name: String,
+    context: Box<PluginContext<T>>,
+    command: Option<extern "C" fn(line: *const c_char)>,
+    unload: Option<extern "C" fn()>,
+    #[cfg(feature = "plugins")]
+    library: libloading::Library,
+}
+
+impl<T> Drop for LoadedPlugin<T> {
+    fn drop(&mut self) {
+        if let Some(unload) = self.unload {
+            unload();
+        }
+    }
+}
+
+/**
+ * The plugins of a Console. Each one is a module like any other, with a sender of its own named after the library file
+ * (libhello.so is "hello"), so the phonebook, capabilities, filters and rate limits apply to it as usual.
+ * Loading needs the plugins feature, without it Console::load_plugin always fails with PluginError::Unsupported.
+ */
+pub struct Plugins<T = ()> {
+    loaded: Vec<LoadedPlugin<T>>,
+}
+
+//not derived, that would want T: Default
+impl<T> Default for Plugins<T> {
+    fn default() -> Self {
+        Plugins { loaded: Vec::new() }
+    }
+}
+
+impl<T> Plugins<T> {
+    pub fn names(&self) -> Vec<String> {
+        self.loaded.iter().map(|plugin| plugin.name.clone()).collect()
+    }
+
+    /**
+     * The commands a plugin registered so far
+     */
+    pub fn commands(&self, name: &str) -> Vec<String> {
+        self.loaded.iter().find(|plugin| plugin.name == name).map_or(Vec::new(), |plugin| plugin.context.commands.lock().unwrap().clone())
+    }
+
+    /**
+     * Hand a typed command to the plugin that registered its name, false if none did
+     */
+    pub fn run_command(&self, line: &str) -> bool {
+        let name = line.trim_start_matches('/').split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+        let plugin = self.loaded.iter().find(|plugin| plugin.context.commands.lock().unwrap().contains(&name));
+        match plugin.and_then(|plugin| plugin.command) {
+            Some(command) => match std::ffi::CString::new(line) {
+                Ok(line) => {
+                    command(line.as_ptr());
+                    true
+                },
+                Err(_) => false,
+            },
+            None => false,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Plugins<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Plugins {:?}", self.names())
+    }
+}
+
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Load a plugin library and start it, returns the name its sender was registered with.
+     * Loading native code runs it with the host's rights, only load plugins you trust that far. Capabilities only
+     * limit what it can do through the Console.
+     */
+    #[cfg(feature = "plugins")]
+    pub fn load_plugin(&mut self, path: &Path) -> Result<String, PluginError> {
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).ok_or_else(|| PluginError::Load(format!("bad file name: {}", path.display())))?;
+        let name = stem.strip_prefix("lib").unwrap_or(stem).to_string();
+        //the library runs its initialisers when opened, that is the trust boundary the doc comment above is about
+        let library = unsafe { libloading::Library::new(path) }.map_err(|e| PluginError::Load(e.to_string()))?;
+        let (entry, command, unload) = unsafe {
+            let entry = *library.get::<extern "C" fn(*const PluginHost) -> i32>(PLUGIN_ENTRY).map_err(|e| PluginError::Load(e.to_string()))?;
+            let command = library.get::<extern "C" fn(*const c_char)>(PLUGIN_COMMAND).ok().map(|symbol| *symbol);
+            let unload = library.get::<extern "C" fn()>(PLUGIN_UNLOAD).ok().map(|symbol| *symbol);
+            (entry, command, unload)
+        };
+
+        let handle = self.new_sender(name.clone());
+        let context = Box::new(PluginContext { handle, commands: Mutex::new(Vec::new()) });
+        let host = PluginHost {
+            abi_version: PLUGIN_ABI_VERSION,
+            context: &*context as *const PluginContext<T> as *mut c_void,
+            send: host_send::<T>,
+            register_command: host_register_command::<T>,
+        };
+        let status = entry(&host);
+        if status != 0 {
+            self.revoke_sender(name);
+            return Err(PluginError::Init(status));
+        }
+        self.plugins.loaded.push(LoadedPlugin { name: name.clone(), context, command, unload, library });
+        self.emit_event(format!("plugin {} loaded", name));
+        Ok(name)
+    }
+
+    #[cfg(not(feature = "plugins"))]
+    pub fn load_plugin(&mut self, path: &Path) -> Result<String, PluginError> {
+        Err(PluginError::Unsupported)
+    }
+
+    /**
+     * Stop a plugin and close its library, its sender is revoked
+     */
+    pub fn unload_plugin(&mut self, name: &str) -> bool {
+        match self.plugins.loaded.iter().position(|plugin| plugin.name == name) {
+            Some(index) => {
+                self.plugins.loaded.remove(index);
+                self.revoke_sender(name.to_string());
+                true
+            },
+            None => false,
+        }
+    }
+}
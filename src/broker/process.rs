@@ -0,0 +1,150 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::process::Stdio;
+use crate::broker::handle::SenderHandle;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+
+/**
+ * What the console hands a running process
+ *  ~ Line is written to its stdin, with a newline.
+ *  ~ Close closes its stdin, for a program that reads until the end of its input.
+ *  ~ Kill ends it.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessInput {
+    Line(String),
+    Close,
+    Kill,
+}
+
+/**
+ * A process started with Console::spawn_process, name is the sender its output comes from
+ */
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub name: String,
+    pub command: String,
+    pub pid: Option<u32>,
+    pub(crate) input: mpsc::UnboundedSender<ProcessInput>,
+}
+
+impl Process {
+    /**
+     * Whether it still runs, its task lets go of the input once it has exited
+     */
+    pub fn is_running(&self) -> bool {
+        !self.input.is_closed()
+    }
+}
+
+/**
+ * A command line as a program and its arguments, split at whitespace outside of single or double quotes.
+ * A backslash outside single quotes takes the next character as it is
+ */
+pub fn split_command(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.get_or_insert_with(String::new).push(next);
+                }
+            },
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            },
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/**
+ * The name a process goes by when it isn't given one, the file name of its program
+ */
+pub fn program_name(program: &str) -> String {
+    let name = std::path::Path::new(program).file_stem().and_then(|stem| stem.to_str()).unwrap_or(program);
+    if name.is_empty() { "process".to_string() } else { name.to_string() }
+}
+
+/**
+ * Start a program with its output piped, killed when the console lets go of it
+ */
+pub fn start(words: &[String]) -> std::io::Result<Child> {
+    let (program, args) = words.split_first().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no program to run"))?;
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+}
+
+/**
+ * Run a started process until it exits: its stdout lines go to the output zone as Normal messages of output,
+ * its stderr lines as Warning, and what comes on input goes to it. The exit status is the last line, Warning when it failed
+ */
+pub async fn supervise<T>(mut child: Child, output: SenderHandle<T>, mut input: mpsc::UnboundedReceiver<ProcessInput>) {
+    let mut stdin = child.stdin.take();
+    let mut stdout = child.stdout.take().map(|stdout| BufReader::new(stdout).lines());
+    let mut stderr = child.stderr.take().map(|stderr| BufReader::new(stderr).lines());
+    let mut listening = true;
+    let status = loop {
+        tokio::select! {
+            line = async { stdout.as_mut().unwrap().next_line().await }, if stdout.is_some() => match line {
+                Ok(Some(line)) => { let _ = output.send(line).await; },
+                _ => stdout = None,
+            },
+            line = async { stderr.as_mut().unwrap().next_line().await }, if stderr.is_some() => match line {
+                Ok(Some(line)) => { let _ = output.send_with_priority(line, PriorityStatus::Warning).await; },
+                _ => stderr = None,
+            },
+            request = input.recv(), if listening => match request {
+                Some(ProcessInput::Line(line)) => {
+                    let written = match stdin.as_mut() {
+                        Some(pipe) => pipe.write_all(format!("{}\n", line).as_bytes()).await,
+                        None => Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "its input is closed")),
+                    };
+                    if let Err(e) = written {
+                        let _ = output.send_with_priority(format!("[process] could not write to it: {}", e), PriorityStatus::Warning).await;
+                    }
+                },
+                Some(ProcessInput::Close) => stdin = None,
+                Some(ProcessInput::Kill) => {
+                    let _ = child.start_kill();
+                },
+                //the console going away ends what it started
+                None => {
+                    listening = false;
+                    let _ = child.start_kill();
+                },
+            },
+            //the rest of the output is read once the process is gone, no line is lost to the race
+            status = child.wait(), if stdout.is_none() && stderr.is_none() => break status,
+        }
+    };
+    //nothing more can be handed to it, the console sees it as no longer running from here on
+    input.close();
+    let (text, priority) = match status {
+        Ok(status) if status.success() => ("[process] exited".to_string(), PriorityStatus::Normal),
+        Ok(status) => (format!("[process] exited with {}", status), PriorityStatus::Warning),
+        Err(e) => (format!("[process] lost: {}", e), PriorityStatus::Warning),
+    };
+    let _ = output.send_with_priority(text, priority).await;
+}
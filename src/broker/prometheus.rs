@@ -0,0 +1,96 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::metrics::Metrics;
+
+//External
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use tokio::sync::watch;
+
+/**
+ * Escape a label value as required by the Prometheus text format
+ */
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: String) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn write_per_sender(out: &mut String, name: &str, help: &str, counts: &HashMap<String, u64>) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let mut senders: Vec<(&String, &u64)> = counts.iter().collect();
+    senders.sort();
+    for (sender, count) in senders {
+        let _ = writeln!(out, "{}{{sender=\"{}\"}} {}", name, escape_label(sender), count);
+    }
+}
+
+/**
+ * Render a Metrics snapshot in the Prometheus text exposition format
+ */
+pub fn render_prometheus(metrics: &Metrics) -> String {
+    let mut out = String::new();
+    write_metric(&mut out, "dualzone_messages_received_total", "counter", "Messages received by the broker.", metrics.received.to_string());
+    write_metric(&mut out, "dualzone_messages_rendered_total", "counter", "Messages drawn in the output zone.", metrics.rendered.to_string());
+    write_metric(&mut out, "dualzone_messages_dropped_total", "counter", "Messages sent to the dead-letter queue.", metrics.dropped.to_string());
+    write_metric(&mut out, "dualzone_messages_dropped_oldest_total", "counter", "Messages evicted by the drop-oldest overflow policy.", metrics.dropped_oldest.to_string());
+    write_metric(&mut out, "dualzone_messages_dropped_newest_total", "counter", "Messages refused by the drop-newest overflow policy.", metrics.dropped_newest.to_string());
+    write_metric(&mut out, "dualzone_messages_spilled_total", "counter", "Messages moved to the overflow queue.", metrics.spilled.to_string());
+    write_metric(&mut out, "dualzone_queue_depth", "gauge", "Events waiting in the broker channel.", metrics.channel_depth.to_string());
+    write_metric(&mut out, "dualzone_queue_capacity", "gauge", "Capacity of the broker channel.", metrics.channel_capacity.to_string());
+    write_metric(&mut out, "dualzone_overflow_depth", "gauge", "Events waiting in the overflow queue.", metrics.overflow_depth.to_string());
+    write_metric(&mut out, "dualzone_delayed_messages", "gauge", "Messages held back by a rate limit.", metrics.delayed.to_string());
+    write_metric(&mut out, "dualzone_render_seconds_avg", "gauge", "Average time to draw a message.", (metrics.render_avg_us as f64 / 1_000_000.0).to_string());
+    write_metric(&mut out, "dualzone_render_seconds_max", "gauge", "Slowest draw of a message.", (metrics.render_max_us as f64 / 1_000_000.0).to_string());
+    write_per_sender(&mut out, "dualzone_sender_messages_total", "Messages received per sender.", &metrics.per_sender);
+    write_per_sender(&mut out, "dualzone_sender_rate_limited_total", "Messages over the rate limit per sender.", &metrics.rate_limited);
+    out
+}
+
+async fn handle(request: Request<Body>, metrics: watch::Receiver<Metrics>, channel: MailboxSender) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET || request.uri().path() != "/metrics" {
+        let mut not_found = Response::new(Body::from("not found"));
+        *not_found.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(not_found);
+    }
+    //the broker publishes its counters as it works, the queue gauges are read live so a stalled broker still shows up
+    let mut snapshot = metrics.borrow().clone();
+    let live = channel.stats();
+    snapshot.channel_depth = live.depth;
+    snapshot.overflow_depth = live.overflow_depth;
+    snapshot.dropped_oldest = live.dropped_oldest;
+    snapshot.dropped_newest = live.dropped_newest;
+    snapshot.spilled = live.spilled;
+
+    let mut response = Response::new(Body::from(render_prometheus(&snapshot)));
+    response.headers_mut().insert("content-type", "text/plain; version=0.0.4".parse().unwrap());
+    Ok(response)
+}
+
+/**
+ * Serve GET /metrics on addr until the server fails.
+ * metrics comes from Console::watch_metrics() and channel is a clone of Console.tx.
+ */
+pub async fn serve_metrics(addr: SocketAddr, metrics: watch::Receiver<Metrics>, channel: MailboxSender) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        let channel = channel.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| handle(request, metrics.clone(), channel.clone())))
+        }
+    });
+    Server::bind(&addr).serve(make_service).await
+}
@@ -0,0 +1,99 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//External
+use tokio::time::{Duration, Instant};
+
+/**
+ * What the broker does with a message that arrives when the sender's bucket is empty
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverLimitAction {
+    Drop,
+    Delay,
+    Downgrade,
+}
+
+/**
+ * Token bucket settings for a sender.
+ *  ~ burst is the number of messages that can be sent back to back.
+ *  ~ per_second is how fast the bucket refills.
+ */
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: f64,
+    pub action: OverLimitAction,
+}
+
+impl RateLimit {
+    pub fn new(burst: u32, per_second: f64, action: OverLimitAction) -> Self {
+        RateLimit { burst, per_second, action }
+    }
+}
+
+/**
+ * Outcome of checking a message against a bucket
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Drop,
+    Delay(Duration),
+    Downgrade,
+}
+
+/**
+ * Per sender token bucket, tokens may go negative while messages are being delayed so delayed messages keep their order.
+ */
+#[derive(Debug)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        TokenBucket {
+            tokens: limit.burst as f64,
+            limit,
+            last_refill: Instant::now(),
+        }
+    }
+
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.last_refill = now;
+    }
+
+    /**
+     * Take a token for one message and decide what happens to it
+     */
+    pub fn check(&mut self, now: Instant) -> Verdict {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Verdict::Pass;
+        }
+        match self.limit.action {
+            OverLimitAction::Drop => Verdict::Drop,
+            OverLimitAction::Downgrade => Verdict::Downgrade,
+            OverLimitAction::Delay => {
+                //never queue more than one extra burst worth of delayed messages
+                if self.limit.per_second <= 0.0 || self.tokens <= -(self.limit.burst as f64) {
+                    return Verdict::Drop;
+                }
+                self.tokens -= 1.0;
+                let wait = (1.0 - (self.tokens + 1.0)) / self.limit.per_second;
+                Verdict::Delay(Duration::from_secs_f64(wait.max(0.0)))
+            }
+        }
+    }
+}
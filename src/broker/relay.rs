@@ -0,0 +1,421 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+use crate::broker::handle::SenderHandle;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::{ConsoleEvent, RenderedLine};
+use crate::broker::pattern::glob_match;
+use crate::user_input::commands::Command;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+/**
+ * How often the channel is asked for what was said in it, unless the relay says
+ */
+pub const RELAY_POLL: Duration = Duration::from_secs(2);
+
+/**
+ * How long the relay waits after the service failed it before it asks again
+ */
+pub const RELAY_RETRY: Duration = Duration::from_secs(30);
+
+/**
+ * The longest line Discord takes, longer ones are cut
+ */
+pub const DISCORD_MAX_CONTENT: usize = 2000;
+
+/**
+ * The chat service a relay talks to through its bot API
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelayService {
+    Discord,
+    Slack,
+}
+
+impl RelayService {
+    pub fn parse(name: &str) -> Option<RelayService> {
+        match name.to_ascii_lowercase().as_str() {
+            "discord" => Some(RelayService::Discord),
+            "slack" => Some(RelayService::Slack),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RelayService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayService::Discord => write!(f, "discord"),
+            RelayService::Slack => write!(f, "slack"),
+        }
+    }
+}
+
+/**
+ * Why the service could not be talked to
+ */
+#[derive(Debug)]
+pub enum RelayError {
+    Request(String),
+    Status(u16),
+    Api(String),
+}
+
+impl fmt::Display for RelayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayError::Request(e) => write!(f, "request failed: {}", e),
+            RelayError::Status(status) => write!(f, "answered {}", status),
+            RelayError::Api(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RelayError {}
+
+/**
+ * A bot in a Discord or Slack channel that the console talks through, eg. for the admins of a game server, see Console::set_chat_relay.
+ *  ~ The rendered lines whose topic matches one of topics (globs, see broker::pattern::glob_match) are posted to channel
+ *    as "[topic] sender: text". Lines without a topic are never posted, so what the relay brings in doesn't go back out.
+ *  ~ What people say in the channel comes in as input of "<user>@discord" (or @slack), shown as "[alice@discord] > text"
+ *    like a line typed at a session client, see ConsoleEvent::SessionInput. What bots say is left out, the relay's own lines too.
+ *  ~ A /command from the channel only runs, as that user, when commands is on. It is off unless set, anyone in the channel could type one.
+ *  ~ The channel is asked every poll for what is new, what was said before the relay started is not brought in.
+ * channel is the Discord channel id or the Slack conversation id, token the bot token. Needs the relay feature.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatRelay {
+    pub service: RelayService,
+    pub channel: String,
+    pub token: String,
+    pub topics: Vec<String>,
+    pub commands: bool,
+    pub poll: Duration,
+}
+
+impl ChatRelay {
+    pub fn new(service: RelayService, channel: impl Into<String>, token: impl Into<String>) -> Self {
+        ChatRelay { service, channel: channel.into(), token: token.into(), topics: Vec::new(), commands: false, poll: RELAY_POLL }
+    }
+
+    pub fn mirror(mut self, topic: impl Into<String>) -> Self {
+        self.topics.push(topic.into());
+        self
+    }
+
+    pub fn allow_commands(mut self, commands: bool) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    pub fn poll(mut self, poll: Duration) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    pub fn mirrors(&self, line: &RenderedLine) -> bool {
+        match line.topic.as_deref() {
+            Some(topic) => self.topics.iter().any(|pattern| glob_match(pattern, topic)),
+            None => false,
+        }
+    }
+
+    /**
+     * What a mirrored line is posted as
+     */
+    pub fn outgoing(&self, line: &RenderedLine) -> String {
+        let text = format!("[{}] {}: {}", line.topic.as_deref().unwrap_or(""), line.sender, line.text);
+        match self.service {
+            RelayService::Discord if text.chars().count() > DISCORD_MAX_CONTENT => text.chars().take(DISCORD_MAX_CONTENT - 1).chain(std::iter::once('…')).collect(),
+            _ => text,
+        }
+    }
+
+    /**
+     * The input a line said in the channel comes in as, None for a /command while commands are off
+     */
+    pub fn incoming(&self, user: &str, text: &str) -> Option<ConsoleEvent> {
+        if !self.commands && Command::parse(text).is_some() {
+            return None;
+        }
+        Some(ConsoleEvent::SessionInput { client: format!("{}@{}", user, self.service), line: text.to_string() })
+    }
+}
+
+/**
+ * A line said in the channel, by who
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatLine {
+    pub user: String,
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct DiscordAuthor {
+    username: String,
+    #[serde(default)]
+    bot: bool,
+}
+
+#[derive(Deserialize)]
+struct DiscordMessage {
+    id: String,
+    #[serde(default)]
+    content: String,
+    author: DiscordAuthor,
+}
+
+#[derive(Deserialize)]
+struct SlackMessage {
+    ts: String,
+    #[serde(default)]
+    text: String,
+    user: Option<String>,
+    bot_id: Option<String>,
+    subtype: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SlackHistory {
+    ok: bool,
+    error: Option<String>,
+    #[serde(default)]
+    messages: Vec<SlackMessage>,
+}
+
+#[derive(Deserialize)]
+struct SlackProfile {
+    #[serde(default)]
+    display_name: String,
+}
+
+#[derive(Deserialize)]
+struct SlackUser {
+    name: String,
+    profile: Option<SlackProfile>,
+}
+
+#[derive(Deserialize)]
+struct SlackUserInfo {
+    ok: bool,
+    user: Option<SlackUser>,
+}
+
+#[derive(Deserialize)]
+struct SlackAnswer {
+    ok: bool,
+    error: Option<String>,
+}
+
+/**
+ * The relay's side of the service's bot API. cursor is the newest message seen, None until the first poll
+ */
+#[cfg(feature = "relay")]
+struct Client {
+    http: reqwest::Client,
+    cursor: Option<String>,
+    names: HashMap<String, String>,
+}
+
+#[cfg(feature = "relay")]
+impl Client {
+    const DISCORD_API: &'static str = "https://discord.com/api/v10";
+    const SLACK_API: &'static str = "https://slack.com/api";
+
+    fn new() -> Result<Client, RelayError> {
+        let http = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().map_err(|e| RelayError::Request(e.to_string()))?;
+        Ok(Client { http, cursor: None, names: HashMap::new() })
+    }
+
+    fn request(&self, relay: &ChatRelay, method: reqwest::Method, url: String) -> reqwest::RequestBuilder {
+        let auth = match relay.service {
+            RelayService::Discord => format!("Bot {}", relay.token),
+            RelayService::Slack => format!("Bearer {}", relay.token),
+        };
+        self.http.request(method, url).header("Authorization", auth)
+    }
+
+    async fn answer<T: serde::de::DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, RelayError> {
+        let response = request.send().await.map_err(|e| RelayError::Request(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(RelayError::Status(response.status().as_u16()));
+        }
+        response.json::<T>().await.map_err(|e| RelayError::Request(e.to_string()))
+    }
+
+    async fn post(&self, relay: &ChatRelay, text: String) -> Result<(), RelayError> {
+        match relay.service {
+            RelayService::Discord => {
+                let url = format!("{}/channels/{}/messages", Self::DISCORD_API, relay.channel);
+                Self::answer::<serde_json::Value>(self.request(relay, reqwest::Method::POST, url).json(&serde_json::json!({ "content": text }))).await?;
+            },
+            RelayService::Slack => {
+                let url = format!("{}/chat.postMessage", Self::SLACK_API);
+                let body = serde_json::json!({ "channel": relay.channel, "text": text });
+                let answer: SlackAnswer = Self::answer(self.request(relay, reqwest::Method::POST, url).json(&body)).await?;
+                if !answer.ok {
+                    return Err(RelayError::Api(answer.error.unwrap_or_else(|| "not ok".to_string())));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /**
+     * What people said since the last poll, oldest first. The first poll only finds where the channel is
+     */
+    async fn poll(&mut self, relay: &ChatRelay) -> Result<Vec<ChatLine>, RelayError> {
+        let limit = if self.cursor.is_some() { "50" } else { "1" };
+        let lines = match relay.service {
+            RelayService::Discord => {
+                let url = format!("{}/channels/{}/messages", Self::DISCORD_API, relay.channel);
+                let mut query = vec![("limit", limit.to_string())];
+                if let Some(after) = self.cursor.clone() {
+                    query.push(("after", after));
+                }
+                let mut messages: Vec<DiscordMessage> = Self::answer(self.request(relay, reqwest::Method::GET, url).query(&query)).await?;
+                //snowflakes grow with time
+                messages.sort_by_key(|message| message.id.parse::<u64>().unwrap_or(0));
+                let first = self.cursor.is_none();
+                if let Some(newest) = messages.last() {
+                    self.cursor = Some(newest.id.clone());
+                }
+                if first {
+                    self.cursor.get_or_insert_with(|| "0".to_string());
+                    return Ok(Vec::new());
+                }
+                messages.into_iter().filter(|message| !message.author.bot && !message.content.is_empty())
+                    .map(|message| ChatLine { user: message.author.username, text: message.content }).collect()
+            },
+            RelayService::Slack => {
+                let url = format!("{}/conversations.history", Self::SLACK_API);
+                let mut query = vec![("channel", relay.channel.clone()), ("limit", limit.to_string())];
+                if let Some(oldest) = self.cursor.clone() {
+                    query.push(("oldest", oldest));
+                }
+                let history: SlackHistory = Self::answer(self.request(relay, reqwest::Method::GET, url).query(&query)).await?;
+                if !history.ok {
+                    return Err(RelayError::Api(history.error.unwrap_or_else(|| "not ok".to_string())));
+                }
+                let mut messages = history.messages;
+                messages.sort_by(|a, b| a.ts.parse::<f64>().unwrap_or(0.0).total_cmp(&b.ts.parse::<f64>().unwrap_or(0.0)));
+                let first = self.cursor.is_none();
+                if let Some(newest) = messages.last() {
+                    self.cursor = Some(newest.ts.clone());
+                }
+                if first {
+                    self.cursor.get_or_insert_with(|| "0".to_string());
+                    return Ok(Vec::new());
+                }
+                let mut lines = Vec::new();
+                for message in messages.into_iter().filter(|message| message.bot_id.is_none() && message.subtype.is_none() && !message.text.is_empty()) {
+                    let user = match message.user {
+                        Some(user) => self.slack_name(relay, user).await,
+                        None => continue,
+                    };
+                    lines.push(ChatLine { user, text: message.text });
+                }
+                lines
+            },
+        };
+        Ok(lines)
+    }
+
+    /**
+     * The name a Slack user id goes by, asked for once. The id itself when Slack won't say
+     */
+    async fn slack_name(&mut self, relay: &ChatRelay, id: String) -> String {
+        if let Some(name) = self.names.get(&id) {
+            return name.clone();
+        }
+        let url = format!("{}/users.info", Self::SLACK_API);
+        let request = self.request(relay, reqwest::Method::GET, url).query(&[("user", id.as_str())]);
+        let name = match Self::answer::<SlackUserInfo>(request).await {
+            Ok(SlackUserInfo { ok: true, user: Some(user) }) => match user.profile {
+                Some(profile) if !profile.display_name.is_empty() => profile.display_name,
+                _ => user.name,
+            },
+            _ => return id,
+        };
+        self.names.insert(id, name.clone());
+        name
+    }
+}
+
+/**
+ * Run the relay until the console goes away: lines are the rendered lines to mirror, console where the channel's input goes
+ * and status the relay's own sender, for the lines about the service. A failure is said once until the service answers again
+ */
+#[cfg(feature = "relay")]
+pub async fn run(relay: ChatRelay, mut lines: mpsc::UnboundedReceiver<RenderedLine>, console: MailboxSender, status: SenderHandle) {
+    let mut client = match Client::new() {
+        Ok(client) => client,
+        Err(e) => {
+            let _ = status.send_with_priority(format!("[relay] not relaying: {}", e), PriorityStatus::Warning).await;
+            return;
+        },
+    };
+    let mut poll = tokio::time::interval(relay.poll);
+    poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut failing = false;
+    let _ = status.send(format!("[relay] relaying {} channel {}, {} topics", relay.service, relay.channel, relay.topics.len())).await;
+    loop {
+        let result = tokio::select! {
+            line = lines.recv() => match line {
+                Some(line) if relay.mirrors(&line) => client.post(&relay, relay.outgoing(&line)).await,
+                Some(_) => continue,
+                None => return,
+            },
+            _ = poll.tick() => match client.poll(&relay).await {
+                Ok(said) => {
+                    for line in said {
+                        let event = match relay.incoming(&line.user, &line.text) {
+                            Some(event) => event,
+                            None => {
+                                let note = format!("[relay] not running {} from {}@{}, commands are off", line.text, line.user, relay.service);
+                                let _ = status.send_with_priority(note, PriorityStatus::Warning).await;
+                                continue;
+                            },
+                        };
+                        if console.send(event).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(())
+                },
+                Err(e) => Err(e),
+            },
+        };
+        match result {
+            Ok(()) if failing => {
+                failing = false;
+                let _ = status.send(format!("[relay] {} answers again", relay.service)).await;
+            },
+            Ok(()) => {},
+            Err(e) => {
+                if !failing {
+                    failing = true;
+                    if status.send_with_priority(format!("[relay] {}: {}, trying again", relay.service, e), PriorityStatus::Warning).await.is_err() {
+                        return;
+                    }
+                }
+                tokio::time::sleep(RELAY_RETRY).await;
+            },
+        }
+    }
+}
+
+#[cfg(not(feature = "relay"))]
+pub async fn run(relay: ChatRelay, lines: mpsc::UnboundedReceiver<RenderedLine>, console: MailboxSender, status: SenderHandle) {
+    let _ = status.send_with_priority("[relay] not relaying, built without the relay feature".to_string(), PriorityStatus::Warning).await;
+}
@@ -0,0 +1,164 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use crate::user_input::structs::{PriorityStatus, SenderStatus};
+
+/**
+ * What a service outside the process asks of the broker, see ConsoleEvent::Remote, broker::grpc and broker::http.
+ *  ~ Send shows a line as sender, which has to be the name the caller proved it is (see caller_identity) and a sender of the
+ *    phonebook, made with new_sender() or loaded from a saved phonebook. A line of any other sender is refused.
+ *  ~ ListSenders is /senders, Ban and Unban are /ban and /unban.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteRequest {
+    Send {
+        sender: String,
+        text: String,
+        priority: PriorityStatus,
+        topic: Option<String>,
+    },
+    ListSenders,
+    Ban(String),
+    Unban(String),
+}
+
+/**
+ * The sender a remote caller sends as, the name after its transport: grpc:db and http:db both send as db.
+ * A transport only puts a name there that the caller proved with its token (see RemoteListen::identify), None for a caller that didn't
+ */
+pub fn caller_identity(client: &str) -> Option<&str> {
+    client.split_once(':').map(|(_, name)| name).filter(|name| !name.is_empty())
+}
+
+/**
+ * One sender as /senders lists it, id is the signed id its messages carry and capabilities is None for a sender without a handle
+ */
+#[derive(Debug, Clone)]
+pub struct SenderSummary {
+    pub name: String,
+    pub id: String,
+    pub status: SenderStatus,
+    pub messages: u64,
+    pub capabilities: Option<String>,
+}
+
+/**
+ * The broker's answer to a RemoteRequest
+ *  ~ Done is the request carried out, Sent with the id the line got.
+ *  ~ Refused is why it wasn't, the note /ban or /unban would show, or the line not being admitted.
+ */
+#[derive(Debug, Clone)]
+pub enum RemoteReply {
+    Sent(u64),
+    Senders(Vec<SenderSummary>),
+    Done,
+    Refused(String),
+}
+
+/**
+ * Where the broker is served to other processes, over gRPC (broker::grpc) or HTTP (broker::http).
+ *  ~ token has to be sent by every call as the authorization header ("Bearer <token>"), an address other than
+ *    loopback is only served with one.
+ *  ~ senders is a token per sender name. A call is only done as a sender, and may only send its lines, when it names it in
+ *    x-dualzone-client and sends its token in x-dualzone-token. Anything else is done as the transport alone.
+ */
+#[derive(Clone, PartialEq)]
+pub struct RemoteListen {
+    pub addr: std::net::SocketAddr,
+    pub token: Option<String>,
+    pub senders: HashMap<String, String>,
+}
+
+/**
+ * Whether two secrets are the same, compared in constant time
+ */
+fn same(expected: &str, given: &str) -> bool {
+    expected.len() == given.len() && expected.bytes().zip(given.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl RemoteListen {
+    pub fn new(addr: std::net::SocketAddr, token: Option<String>) -> Self {
+        RemoteListen { addr, token, senders: HashMap::new() }
+    }
+
+    /**
+     * Let a caller holding token send as name
+     */
+    pub fn with_sender(mut self, name: impl Into<String>, token: impl Into<String>) -> Self {
+        self.senders.insert(name.into(), token.into());
+        self
+    }
+
+    /**
+     * The sender a caller is, the name it gave when the token it sent is that sender's. None for anyone else
+     */
+    pub fn identify(&self, name: Option<&str>, token: Option<&str>) -> Option<String> {
+        let name = name.map(str::trim).filter(|name| !name.is_empty())?;
+        let expected = self.senders.get(name)?;
+        same(expected, token.unwrap_or("").trim()).then(|| name.to_string())
+    }
+
+    /**
+     * Whether the address may be served as it is, whoever reaches one other than loopback could ban every sender
+     */
+    pub fn check_exposure(&self) -> Result<(), String> {
+        match self.token.is_none() && !self.addr.ip().is_loopback() {
+            true => Err(format!("{} is not loopback, it needs a token to be served", self.addr)),
+            false => Ok(()),
+        }
+    }
+
+    /**
+     * Whether a call with that authorization header may go through, compared in constant time
+     */
+    pub fn authorizes(&self, header: Option<&str>) -> bool {
+        let token = match &self.token {
+            Some(token) => token,
+            None => return true,
+        };
+        same(&format!("Bearer {}", token), header.unwrap_or(""))
+    }
+}
+
+/**
+ * Never print the token, even in debug output
+ */
+impl std::fmt::Debug for RemoteListen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let token = if self.token.is_some() { "<hidden>" } else { "none" };
+        write!(f, "RemoteListen {{ addr: {}, token: {}, senders: {} with tokens hidden }}", self.addr, token, self.senders.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listen() -> RemoteListen {
+        RemoteListen::new("127.0.0.1:50051".parse().unwrap(), Some("shared".to_string())).with_sender("db", "db-token")
+    }
+
+    #[test]
+    fn a_caller_is_the_sender_whose_token_it_sends() {
+        assert_eq!(listen().identify(Some("db"), Some("db-token")), Some("db".to_string()));
+    }
+
+    #[test]
+    fn a_name_alone_is_no_one() {
+        assert_eq!(listen().identify(Some("db"), None), None);
+        assert_eq!(listen().identify(Some("db"), Some("shared")), None);
+        assert_eq!(listen().identify(Some("web"), Some("db-token")), None);
+    }
+
+    #[test]
+    fn only_a_proven_name_is_sent_as() {
+        let client = match listen().identify(Some("db"), Some("wrong")) {
+            Some(name) => format!("grpc:{}", name),
+            None => "grpc".to_string(),
+        };
+        assert_eq!(caller_identity(&client), None);
+    }
+}
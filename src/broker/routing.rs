@@ -0,0 +1,174 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::sync::{Arc, Mutex};
+use crate::broker::message::Message;
+use crate::broker::dead_letter::{DeadLetterQueue, RejectReason};
+
+//External
+use tokio::sync::mpsc::{Sender, error::TrySendError};
+use tokio::sync::oneshot;
+use tokio::time::{sleep, timeout, Duration};
+
+/**
+ * Default capacity of a module inbox opened with Console::open_inbox()
+ */
+pub const DEFAULT_INBOX_CAPACITY: usize = 32;
+
+/**
+ * Route target meaning every module inbox but the sender's own, see SenderHandle::broadcast()
+ */
+pub const BROADCAST_TARGET: &str = "*";
+
+/**
+ * A routed message as it arrives in the target module's inbox.
+ * When the sender asked for an ack the target must call ack() once it has handled the message, otherwise it is redelivered.
+ */
+#[derive(Debug)]
+pub struct Delivery {
+    pub message: Message,
+    pub attempt: u32,
+    ack: Option<oneshot::Sender<()>>,
+}
+
+impl Delivery {
+    pub(crate) fn new(message: Message, attempt: u32, ack: Option<oneshot::Sender<()>>) -> Self {
+        Delivery { message, attempt, ack }
+    }
+
+    /**
+     * True if the sender is waiting for this delivery to be acknowledged
+     */
+    pub fn wants_ack(&self) -> bool {
+        self.ack.is_some()
+    }
+
+    /**
+     * Acknowledge the delivery, a no-op for messages sent without an ack request
+     */
+    pub fn ack(mut self) {
+        if let Some(ack) = self.ack.take() {
+            let _ = ack.send(());
+        }
+    }
+}
+
+/**
+ * Why a routed message could not be delivered
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryError {
+    UnknownTarget,
+    TargetStale,
+    TargetBlacklisted,
+    NotAuthorized,
+    NotPermitted,
+    TargetClosed,
+    TargetFull,
+    Unacknowledged { attempts: u32 },
+}
+
+impl std::fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeliveryError::UnknownTarget => write!(f, "target has no inbox"),
+            DeliveryError::TargetStale => write!(f, "target missed its heartbeat"),
+            DeliveryError::TargetBlacklisted => write!(f, "target is blacklisted"),
+            DeliveryError::NotAuthorized => write!(f, "sender is not authorized"),
+            DeliveryError::NotPermitted => write!(f, "sender may not broadcast"),
+            DeliveryError::TargetClosed => write!(f, "target inbox is closed"),
+            DeliveryError::TargetFull => write!(f, "target inbox is full"),
+            DeliveryError::Unacknowledged { attempts } => write!(f, "not acknowledged after {} attempts", attempts),
+        }
+    }
+}
+
+impl std::error::Error for DeliveryError {}
+
+/**
+ * Final outcome reported back to a sender that requested an ack
+ */
+pub type DeliveryReport = Result<u32, DeliveryError>;
+
+/**
+ * How hard the broker tries to deliver a message that asked for an ack
+ *  ~ Delays start at initial_backoff and double each attempt up to max_backoff.
+ *  ~ ack_timeout is how long the target has to ack before the message is sent again.
+ */
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub ack_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            ack_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+/**
+ * Best effort delivery, one try and anything that does not fit goes to the dead-letter queue
+ */
+pub(crate) fn deliver_once(inbox: &Sender<Delivery>, message: Message, dead_letters: &Arc<Mutex<DeadLetterQueue>>) -> Result<(), DeliveryError> {
+    match inbox.try_send(Delivery::new(message, 1, None)) {
+        Ok(_) => Ok(()),
+        Err(TrySendError::Full(delivery)) => {
+            dead_letters.lock().unwrap().push(delivery.message, RejectReason::Undelivered(DeliveryError::TargetFull.to_string()));
+            Err(DeliveryError::TargetFull)
+        },
+        Err(TrySendError::Closed(delivery)) => {
+            dead_letters.lock().unwrap().push(delivery.message, RejectReason::Undelivered(DeliveryError::TargetClosed.to_string()));
+            Err(DeliveryError::TargetClosed)
+        },
+    }
+}
+
+/**
+ * At-least-once delivery, runs on its own task so the broker never waits on a slow target.
+ * The message is redelivered until it is acked or the attempts run out, then it goes to the dead-letter queue.
+ */
+pub(crate) async fn deliver_with_ack(
+    inbox: Sender<Delivery>,
+    message: Message,
+    policy: RetryPolicy,
+    dead_letters: Arc<Mutex<DeadLetterQueue>>,
+    report: oneshot::Sender<DeliveryReport>,
+) {
+    let mut backoff = policy.initial_backoff;
+    let mut error = DeliveryError::Unacknowledged { attempts: policy.max_attempts };
+
+    for attempt in 1..=policy.max_attempts {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        match inbox.try_send(Delivery::new(message.clone(), attempt, Some(ack_tx))) {
+            Ok(_) => {
+                if let Ok(Ok(())) = timeout(policy.ack_timeout, ack_rx).await {
+                    let _ = report.send(Ok(attempt));
+                    return;
+                }
+            },
+            //the target is busy, back off and try again
+            Err(TrySendError::Full(_)) => {},
+            Err(TrySendError::Closed(_)) => {
+                error = DeliveryError::TargetClosed;
+                break;
+            },
+        }
+        if attempt < policy.max_attempts {
+            sleep(backoff).await;
+            backoff = (backoff * 2).min(policy.max_backoff);
+        }
+    }
+
+    dead_letters.lock().unwrap().push(message, RejectReason::Undelivered(error.to_string()));
+    let _ = report.send(Err(error));
+}
@@ -0,0 +1,238 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::message::{self, Message};
+use crate::broker::capability::Capability;
+use crate::user_input::structs::{Console, SenderStatus};
+
+//External
+use tokio::time::{Duration, Instant};
+
+/**
+ * Resolution of the timer wheel, a scheduled message fires at most this late
+ */
+pub const TICK: Duration = Duration::from_millis(100);
+
+/**
+ * Number of slots, one turn of the wheel covers SLOTS * TICK (25.6s), later timers wait in their slot for more turns
+ */
+const SLOTS: usize = 256;
+
+/**
+ * A message waiting for its time, and how often it comes back if it is recurring
+ */
+#[derive(Debug, Clone)]
+pub struct Scheduled {
+    pub id: u64,
+    pub due: Instant,
+    pub every: Option<Duration>,
+    pub message: Message,
+}
+
+/**
+ * Hashed timer wheel of the broker's scheduled messages.
+ *  ~ A timer goes in the slot of its due tick modulo SLOTS, advancing the wheel only looks at the slots of the ticks that passed,
+ *    timers in them that are due a later turn stay.
+ *  ~ Ids are unique for the Console (message::next_id) so a module can only cancel what it knows the id of.
+ */
+#[derive(Debug)]
+pub struct TimerWheel {
+    slots: Vec<Vec<Scheduled>>,
+    origin: Instant,
+    //the last tick advance() went through
+    tick: u64,
+    len: usize,
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        TimerWheel { slots: vec![Vec::new(); SLOTS], origin: Instant::now(), tick: 0, len: 0 }
+    }
+}
+
+impl TimerWheel {
+    fn tick_of(&self, at: Instant) -> u64 {
+        (at.saturating_duration_since(self.origin).as_nanos() / TICK.as_nanos()) as u64
+    }
+
+    pub fn insert(&mut self, timer: Scheduled) {
+        //a tick already passed would never be looked at again, such a timer goes in the next one
+        let tick = self.tick_of(timer.due).max(self.tick + 1);
+        self.slots[(tick % SLOTS as u64) as usize].push(timer);
+        self.len += 1;
+    }
+
+    /**
+     * Take every timer due by now, soonest first. Recurring ones are put back for their next time
+     */
+    pub fn advance(&mut self, now: Instant) -> Vec<Scheduled> {
+        let target = self.tick_of(now);
+        if target <= self.tick {
+            return Vec::new();
+        }
+        //going round more than once would visit the same slots again
+        let passed = (target - self.tick).min(SLOTS as u64);
+        let mut fired = Vec::new();
+        for step in 1..=passed {
+            let slot = ((self.tick + step) % SLOTS as u64) as usize;
+            let (due, waiting): (Vec<Scheduled>, Vec<Scheduled>) = self.slots[slot].drain(..).partition(|timer| timer.due <= now);
+            self.slots[slot] = waiting;
+            fired.extend(due);
+        }
+        self.tick = target;
+        self.len -= fired.len();
+        fired.sort_by_key(|timer| timer.due);
+        for timer in &fired {
+            if let Some(every) = timer.every {
+                let mut next = timer.clone();
+                next.due = timer.due + every;
+                //a broker that was busy for several intervals sends one, not a burst
+                if next.due <= now {
+                    next.due = now + every;
+                }
+                self.insert(next);
+            }
+        }
+        fired
+    }
+
+    pub fn cancel(&mut self, id: u64) -> Option<Scheduled> {
+        for slot in self.slots.iter_mut() {
+            if let Some(index) = slot.iter().position(|timer| timer.id == id) {
+                self.len -= 1;
+                return Some(slot.remove(index));
+            }
+        }
+        None
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Scheduled> {
+        self.slots.iter().flatten().find(|timer| timer.id == id)
+    }
+
+    /**
+     * When the broker has to wake up for the next timer
+     */
+    pub fn next_due(&self) -> Option<Instant> {
+        self.slots.iter().flatten().map(|timer| timer.due).min()
+    }
+
+    /**
+     * Copy of every waiting timer, soonest first
+     */
+    pub fn list(&self) -> Vec<Scheduled> {
+        let mut timers: Vec<Scheduled> = self.slots.iter().flatten().cloned().collect();
+        timers.sort_by_key(|timer| timer.due);
+        timers
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/**
+ * A duration written as numbers with units, eg. "90s", "10m", "1h30m" or "500ms". A plain number is seconds
+ */
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim().to_ascii_lowercase();
+    if let Ok(secs) = text.parse::<f64>() {
+        return (secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let value: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let seconds = match &rest[..unit] {
+            "ms" => 0.001,
+            "s" | "sec" | "secs" => 1.0,
+            "m" | "min" | "mins" => 60.0,
+            "h" | "hr" | "hrs" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            _ => return None,
+        };
+        total += Duration::from_secs_f64(value * seconds);
+        rest = &rest[unit..];
+    }
+    Some(total)
+}
+
+/**
+ * Scheduled delivery. A scheduled message goes through the broker when it fires, not when it was scheduled,
+ * so a sender blacklisted in between has its schedules dropped like its messages.
+ */
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Deliver a message once delay has passed, returns the id to cancel it with
+     */
+    pub fn send_after(&mut self, message: Message, delay: Duration) -> u64 {
+        self.schedule(message, Instant::now() + delay, None)
+    }
+
+    /**
+     * Deliver a message every interval, the first time one interval from now
+     */
+    pub fn send_every(&mut self, message: Message, interval: Duration) -> u64 {
+        self.schedule(message, Instant::now() + interval, Some(interval))
+    }
+
+    fn schedule(&mut self, message: Message, due: Instant, every: Option<Duration>) -> u64 {
+        let id = message::next_id();
+        self.timers.insert(Scheduled { id, due, every, message });
+        id
+    }
+
+    /**
+     * A schedule handed over by a module, only taken while the module may print
+     */
+    pub(crate) fn schedule_for(&mut self, scheduled: Scheduled) {
+        let sender = scheduled.message.sender.clone();
+        if matches!(self.get_sender_status(sender.clone()), SenderStatus::Authorized) && self.permits(&sender, Capability::Print) {
+            self.timers.insert(scheduled);
+        }
+    }
+
+    /**
+     * Cancel a schedule for a module, only its own
+     */
+    pub(crate) fn unschedule_for(&mut self, sender: &str, id: u64) -> bool {
+        match self.timers.get(id) {
+            Some(timer) if timer.message.sender == sender => self.cancel_scheduled(id),
+            _ => false,
+        }
+    }
+
+    /**
+     * Cancel a scheduled message, false if there is none with that id (or it already fired, for one off messages)
+     */
+    pub fn cancel_scheduled(&mut self, id: u64) -> bool {
+        self.timers.cancel(id).is_some()
+    }
+
+    /**
+     * Copy of every scheduled message, soonest first
+     */
+    pub fn scheduled(&self) -> Vec<Scheduled> {
+        self.timers.list()
+    }
+
+    /**
+     * The messages whose time has come, each with a fresh id since a recurring one is rendered more than once
+     */
+    pub(crate) fn release_scheduled(&mut self, now: Instant) -> Vec<Message> {
+        self.timers.advance(now).into_iter().map(|timer| {
+            let mut message = timer.message;
+            message.id = message::next_id();
+            message
+        }).collect()
+    }
+}
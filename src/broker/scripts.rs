@@ -0,0 +1,214 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::broker::message::Message;
+use crate::user_input::structs::Console;
+
+/**
+ * Prefix of the script functions that define a slash command, fn cmd_deploy(args) is /deploy
+ */
+pub const COMMAND_PREFIX: &str = "cmd_";
+
+/**
+ * Reasons the scripts could not be loaded
+ */
+#[derive(Debug)]
+pub enum ScriptError {
+    Unsupported,
+    NoDirectory,
+    Io(std::io::Error),
+    Compile { file: String, reason: String },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Unsupported => write!(f, "scripting is not compiled in, build with --features scripting"),
+            ScriptError::NoDirectory => write!(f, "no scripts directory is set"),
+            ScriptError::Io(e) => write!(f, "could not read the scripts directory: {}", e),
+            ScriptError::Compile { file, reason } => write!(f, "{}: {}", file, reason),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/**
+ * One compiled script and what it defines
+ */
+#[cfg(feature = "scripting")]
+struct Script {
+    name: String,
+    ast: rhai::AST,
+    on_message: bool,
+    commands: Vec<String>,
+}
+
+/**
+ * The Rhai scripts of a Console, every *.rhai file of the scripts directory. A script may define
+ *
+ *     fn on_message(sender, priority, text) { if text.contains("FAILED") { "build is red, paging" } }
+ *     fn cmd_deploy(args) { "deploying " + args }
+ *
+ *  ~ on_message is called for every module message as it is rendered, whatever it returns (other than nothing)
+ *    is shown as a line from the script, eg. for auto-replies and alerts.
+ *  ~ Every cmd_<name>(args) defines /<name>, args is the rest of the line. Built in commands win over script ones.
+ *  ~ A script that fails at runtime gets a console note, the others carry on.
+ *  ~ /scripts lists them, /scripts reload compiles the directory again, keeping the old scripts if any file fails.
+ *
+ * Needs the scripting feature, without it loading fails with ScriptError::Unsupported.
+ */
+#[derive(Default)]
+pub struct Scripts {
+    directory: Option<PathBuf>,
+    #[cfg(feature = "scripting")]
+    engine: Option<rhai::Engine>,
+    #[cfg(feature = "scripting")]
+    scripts: Vec<Script>,
+}
+
+impl fmt::Debug for Scripts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Scripts {{ directory: {:?}, loaded: {:?} }}", self.directory, self.names())
+    }
+}
+
+#[cfg(feature = "scripting")]
+impl Scripts {
+    /**
+     * Compile every script of the directory, all or nothing. Returns how many were loaded
+     */
+    pub fn reload(&mut self) -> Result<usize, ScriptError> {
+        let directory = self.directory.clone().ok_or(ScriptError::NoDirectory)?;
+        let engine = self.engine.get_or_insert_with(rhai::Engine::new);
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&directory).map_err(ScriptError::Io)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "rhai"))
+            .collect();
+        paths.sort();
+
+        let mut scripts = Vec::new();
+        for path in paths {
+            let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+            let ast = engine.compile_file(path.clone()).map_err(|e| ScriptError::Compile { file: name.clone(), reason: e.to_string() })?;
+            let on_message = ast.iter_functions().any(|function| function.name == "on_message");
+            let commands = ast.iter_functions()
+                .filter_map(|function| function.name.strip_prefix(COMMAND_PREFIX).map(|command| command.to_ascii_lowercase()))
+                .collect();
+            scripts.push(Script { name, ast, on_message, commands });
+        }
+        self.scripts = scripts;
+        Ok(self.scripts.len())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.scripts.iter().map(|script| script.name.clone()).collect()
+    }
+
+    pub fn commands(&self, name: &str) -> Vec<String> {
+        self.scripts.iter().find(|script| script.name == name).map_or(Vec::new(), |script| script.commands.clone())
+    }
+
+    /**
+     * Let every script react to a rendered module message, returns the lines they want shown
+     */
+    pub fn on_message(&self, sender_name: &str, message: &Message) -> Vec<Message> {
+        let engine = match self.engine.as_ref() {
+            Some(engine) => engine,
+            None => return Vec::new(),
+        };
+        let mut lines = Vec::new();
+        for script in self.scripts.iter().filter(|script| script.on_message) {
+            let args = (sender_name.to_string(), format!("{:?}", message.priority), message.payload.to_string());
+            let result = engine.call_fn::<rhai::Dynamic>(&mut rhai::Scope::new(), &script.ast, "on_message", args);
+            lines.extend(script_reply(&script.name, result));
+        }
+        lines
+    }
+
+    /**
+     * Call on_alert(sender, priority, text) of the named script for a message an alert rule fired for, returns the lines it wants shown
+     */
+    pub fn on_alert(&self, script: &str, sender_name: &str, message: &Message) -> Vec<Message> {
+        let (engine, found) = match (self.engine.as_ref(), self.scripts.iter().find(|loaded| loaded.name == script)) {
+            (Some(engine), Some(found)) => (engine, found),
+            _ => return vec![Message::from_console(format!("[scripts] no script named {} for an alert", script))],
+        };
+        let args = (sender_name.to_string(), format!("{:?}", message.priority), message.payload.to_string());
+        let result = engine.call_fn::<rhai::Dynamic>(&mut rhai::Scope::new(), &found.ast, "on_alert", args);
+        script_reply(&found.name, result).into_iter().collect()
+    }
+
+    /**
+     * Run a typed command if a script defines it, None when none does
+     */
+    pub fn run_command(&self, line: &str) -> Option<Vec<Message>> {
+        let engine = self.engine.as_ref()?;
+        let mut words = line.trim_start_matches('/').splitn(2, char::is_whitespace);
+        let name = words.next().unwrap_or("").to_ascii_lowercase();
+        let args = words.next().unwrap_or("").trim().to_string();
+        let script = self.scripts.iter().find(|script| script.commands.contains(&name))?;
+        let result = engine.call_fn::<rhai::Dynamic>(&mut rhai::Scope::new(), &script.ast, format!("{}{}", COMMAND_PREFIX, name), (args,));
+        Some(script_reply(&script.name, result).into_iter().collect())
+    }
+}
+
+/**
+ * What a script call returned as a line to show, nothing for (), a console note for an error
+ */
+#[cfg(feature = "scripting")]
+fn script_reply(script: &str, result: Result<rhai::Dynamic, Box<rhai::EvalAltResult>>) -> Option<Message> {
+    match result {
+        Ok(value) if value.is_unit() => None,
+        Ok(value) => Some(Message::from_console(format!("[{}] {}", script, value))),
+        Err(e) => Some(Message::from_console(format!("[scripts] {} failed: {}", script, e))),
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+impl Scripts {
+    pub fn reload(&mut self) -> Result<usize, ScriptError> {
+        Err(ScriptError::Unsupported)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    pub fn commands(&self, name: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    pub fn on_message(&self, sender_name: &str, message: &Message) -> Vec<Message> {
+        Vec::new()
+    }
+
+    pub fn run_command(&self, line: &str) -> Option<Vec<Message>> {
+        None
+    }
+
+    pub fn on_alert(&self, script: &str, sender_name: &str, message: &Message) -> Vec<Message> {
+        vec![Message::from_console(format!("[scripts] {}", ScriptError::Unsupported))]
+    }
+}
+
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Load the scripts of a directory, see Scripts. Returns how many were loaded
+     */
+    pub fn set_scripts_dir(&mut self, directory: PathBuf) -> Result<usize, ScriptError> {
+        self.scripts.directory = Some(directory);
+        self.scripts.reload()
+    }
+
+    /**
+     * Compile the scripts directory again, the running scripts stay if it fails
+     */
+    pub fn reload_scripts(&mut self) -> Result<usize, ScriptError> {
+        self.scripts.reload()
+    }
+}
@@ -0,0 +1,483 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use crate::broker::audit::AuditAction;
+use crate::broker::auth::{self, SessionAuth};
+use crate::broker::filter::{FilterRule, FilterRules};
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::{ConsoleEvent, Message, Output, CONSOLE_SENDER, USER_SENDER};
+use crate::output::recorder::Recorded;
+use crate::user_input::commands::RuleCommand;
+
+//External
+use serde::{Serialize, Deserialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::broadcast;
+
+/**
+ * Where the session socket is when DUALZONE_SOCKET doesn't say, dualzone-<user>.sock in XDG_RUNTIME_DIR or the temp directory
+ */
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("DUALZONE_SOCKET") {
+        return PathBuf::from(path);
+    }
+    let dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(std::env::temp_dir);
+    dir.join(format!("dualzone-{}.sock", user_name()))
+}
+
+/**
+ * The name a client goes by when dualZoneCli attach isn't given one, the user's login name
+ */
+pub fn user_name() -> String {
+    std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "user".to_string())
+}
+
+/**
+ * Where dualZoneCli attach finds the console
+ *  ~ Socket is the session socket on this machine, see socket_path.
+ *  ~ Tls is host:port of a console serving its session on the network, see broker::tls. Needs the tls feature.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionTarget {
+    Socket(PathBuf),
+    Tls(String),
+}
+
+impl std::fmt::Display for SessionTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionTarget::Socket(path) => write!(f, "{}", path.display()),
+            SessionTarget::Tls(addr) => write!(f, "tls://{}", addr),
+        }
+    }
+}
+
+/**
+ * The session served on the network as well as the socket, over TLS with the certificate and key in PEM files.
+ * A session on the network has to have auth, see Console::set_session_auth
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsListen {
+    pub addr: std::net::SocketAddr,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+pub type SessionReader = Box<dyn AsyncRead + Unpin + Send>;
+pub type SessionWriter = Box<dyn AsyncWrite + Unpin + Send>;
+
+/**
+ * Open a connection to a session, the two halves of it
+ */
+pub async fn connect(target: &SessionTarget) -> std::io::Result<(SessionReader, SessionWriter)> {
+    match target {
+        #[cfg(unix)]
+        SessionTarget::Socket(path) => {
+            let (reader, writer) = tokio::net::UnixStream::connect(path).await?.into_split();
+            Ok((Box::new(reader), Box::new(writer)))
+        },
+        #[cfg(feature = "tls")]
+        SessionTarget::Tls(addr) => {
+            let (reader, writer) = tokio::io::split(crate::broker::tls::connect(addr).await?);
+            Ok((Box::new(reader), Box::new(writer)))
+        },
+        #[allow(unreachable_patterns)]
+        _ => Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "this build can't reach it, TLS needs the tls feature and the socket Unix")),
+    }
+}
+
+/**
+ * How many of the newest zone changes an attaching client is sent first, the scrollback it comes back to
+ */
+pub const SESSION_BACKLOG: usize = 5000;
+
+/**
+ * What a client sends the session, one JSON object per line
+ *  ~ Auth answers the Welcome's challenge as user, see broker::auth::proof. It comes first when the session asks for it.
+ *  ~ Hello names the client. A name another client has is numbered, eg. alice-2.
+ *    A session with users (see SessionAuth::Users) names the client after the user it authenticated as instead.
+ *  ~ Input is a line typed at the client, shown attributed to it ("[alice] > restart") and run as it, see ConsoleEvent::SessionInput.
+ *  ~ Detach asks the console's own terminal to let go of it, see dualZoneCli detach.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientFrame {
+    Auth {
+        user: String,
+        proof: String,
+    },
+    Hello(String),
+    Input(String),
+    Detach,
+}
+
+/**
+ * What the session sends a client, one JSON object per line
+ *  ~ Welcome is the first frame, with a challenge when the client has to authenticate before anything else.
+ *  ~ Event changes the zones, the backlog first and then everything as it happens.
+ *  ~ Private is a line for the client named to alone, see Output::Private. The session hands it on as an Event to that client
+ *    and leaves it out for the rest.
+ *  ~ Bye is the last frame, with the reason, eg. the console shut down.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerFrame {
+    Welcome {
+        challenge: Option<String>,
+    },
+    Event(Recorded),
+    Private {
+        to: String,
+        event: Recorded,
+    },
+    Bye(String),
+}
+
+#[derive(Debug)]
+struct HubState {
+    backlog: VecDeque<Recorded>,
+    limit: usize,
+    clients: Vec<String>,
+}
+
+/**
+ * The zone changes of a console shared with the clients attached to it.
+ *  ~ The output task publishes every output, the lines, dismissals and retractions (what a recording keeps) go to the clients.
+ *  ~ It keeps the newest of them so a client attaching later starts with the scrollback instead of an empty screen.
+ *  ~ Any number of clients attach at once, each sees the same zone changes and types into the same console under its own name.
+ */
+#[derive(Debug, Clone)]
+pub struct SessionHub {
+    state: Arc<Mutex<HubState>>,
+    live: broadcast::Sender<ServerFrame>,
+}
+
+impl SessionHub {
+    pub fn new(limit: usize) -> Self {
+        let (live, _) = broadcast::channel(1024);
+        SessionHub { state: Arc::new(Mutex::new(HubState { backlog: VecDeque::new(), limit, clients: Vec::new() })), live }
+    }
+
+    pub fn publish(&self, output: &Output) {
+        let event = match Recorded::from_output(output) {
+            Some(event) => event,
+            None => return,
+        };
+        let mut state = self.state.lock().unwrap();
+        if state.backlog.len() >= state.limit {
+            state.backlog.pop_front();
+        }
+        state.backlog.push_back(event.clone());
+        //nobody attached is not an error
+        let _ = self.live.send(ServerFrame::Event(event));
+    }
+
+    /**
+     * A line for one client only, kept out of the backlog
+     */
+    pub fn publish_private(&self, to: &str, message: Message) {
+        let _ = self.live.send(ServerFrame::Private { to: to.to_string(), event: Recorded::Line(Box::new(message)) });
+    }
+
+    /**
+     * Tell every client the session is over
+     */
+    pub fn close(&self, reason: &str) {
+        let _ = self.live.send(ServerFrame::Bye(reason.to_string()));
+    }
+
+    /**
+     * A client attached under a name, numbered when the name is taken. Returns the name it goes by.
+     * user and console are always taken, they are the terminal and the console itself (USER_SENDER and CONSOLE_SENDER)
+     */
+    pub fn join(&self, name: &str) -> String {
+        let mut state = self.state.lock().unwrap();
+        let name = if name.trim().is_empty() { "client" } else { name.trim() };
+        let reserved = |name: &str| name.eq_ignore_ascii_case(USER_SENDER) || name.eq_ignore_ascii_case(CONSOLE_SENDER);
+        let mut unique = name.to_string();
+        let mut number = 1;
+        while state.clients.contains(&unique) || reserved(&unique) {
+            number += 1;
+            unique = format!("{}-{}", name, number);
+        }
+        state.clients.push(unique.clone());
+        unique
+    }
+
+    pub fn leave(&self, name: &str) {
+        self.state.lock().unwrap().clients.retain(|client| client != name);
+    }
+
+    /**
+     * The names of the clients attached right now, in the order they came
+     */
+    pub fn clients(&self) -> Vec<String> {
+        self.state.lock().unwrap().clients.clone()
+    }
+
+    /**
+     * The backlog and what comes after it, taken together so nothing falls in between
+     */
+    pub fn subscribe(&self) -> (Vec<Recorded>, broadcast::Receiver<ServerFrame>) {
+        let state = self.state.lock().unwrap();
+        (state.backlog.iter().cloned().collect(), self.live.subscribe())
+    }
+}
+
+/**
+ * A frame as it goes over the socket, JSON and a newline
+ */
+pub fn to_line<F: Serialize>(frame: &F) -> std::io::Result<String> {
+    serde_json::to_string(frame).map(|json| json + "\n").map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+pub fn parse_frame<F: for<'de> Deserialize<'de>>(line: &str) -> std::io::Result<F> {
+    serde_json::from_str(line).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/**
+ * Serve the session on a Unix domain socket until the console goes away, every connection is a client.
+ *  ~ A socket file left behind by a console that is gone is replaced, one that still answers means it is taken.
+ *  ~ The socket is only open to the user running the console, with auth a client also has to prove it knows a secret.
+ *  ~ The terminal closing (SIGHUP) no longer ends the process, a detached console has to outlive it.
+ */
+#[cfg(unix)]
+pub async fn serve(path: PathBuf, hub: SessionHub, console: MailboxSender, auth: Option<SessionAuth>) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::{UnixListener, UnixStream};
+
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            return Err(std::io::Error::new(std::io::ErrorKind::AddrInUse, format!("another console serves {}", path.display())));
+        }
+        std::fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+    tokio::spawn(async move { while hangup.recv().await.is_some() {} });
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (hub, console, auth) = (hub.clone(), console.clone(), auth.clone());
+        tokio::spawn(async move {
+            let _ = serve_client(stream, hub, console, auth).await;
+        });
+    }
+}
+
+/**
+ * One attached client, over the socket or TLS: the challenge first when there is auth, then the backlog and the live zone changes out, its lines in.
+ * A client that fails to authenticate is told so after a pause, the attempt goes in the audit log and the connection is closed
+ */
+pub(crate) async fn serve_client<S>(stream: S, hub: SessionHub, console: MailboxSender, auth: Option<SessionAuth>) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    let challenge = auth.as_ref().map(|_| auth::challenge());
+    writer.write_all(to_line(&ServerFrame::Welcome { challenge: challenge.clone() })?.as_bytes()).await?;
+    let mut authenticated = None;
+    if let (Some(auth), Some(challenge)) = (auth.as_ref(), challenge) {
+        let frame = lines.next_line().await?.map(|line| parse_frame::<ClientFrame>(&line));
+        let (user, verdict) = match frame {
+            Some(Ok(ClientFrame::Auth { user, proof })) => {
+                let verified = auth.verify(&user, &challenge, &proof);
+                (user, if verified { None } else { Some("wrong secret") })
+            },
+            Some(_) => (String::new(), Some("no credentials")),
+            None => return Ok(()),
+        };
+        let action = if verdict.is_none() { AuditAction::Authenticated } else { AuditAction::AuthFailed };
+        let subject = match verdict {
+            Some(reason) => format!("session {}", reason),
+            None => "session".to_string(),
+        };
+        let _ = console.send(ConsoleEvent::SessionAudit { client: user.clone(), action, subject }).await;
+        if verdict.is_some() {
+            //guessing one secret after another gets slow
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            writer.write_all(to_line(&ServerFrame::Bye("authentication failed".to_string()))?.as_bytes()).await?;
+            return Ok(());
+        }
+        if matches!(auth, SessionAuth::Users(_)) {
+            authenticated = Some(user);
+        }
+    }
+    let (backlog, mut live) = hub.subscribe();
+    //the name the client goes by once it said hello, for the lines sent to it alone
+    let own_name = Arc::new(Mutex::new(None::<String>));
+    let own = own_name.clone();
+    let sending = tokio::spawn(async move {
+        for event in backlog {
+            writer.write_all(to_line(&ServerFrame::Event(event))?.as_bytes()).await?;
+        }
+        loop {
+            let frame = match live.recv().await {
+                Ok(frame) => frame,
+                //the client is too slow to keep up, it misses what was dropped but stays attached
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => ServerFrame::Bye("the console shut down".to_string()),
+            };
+            let frame = match frame {
+                ServerFrame::Private { to, event } if own.lock().unwrap().as_deref() == Some(to.as_str()) => ServerFrame::Event(event),
+                ServerFrame::Private { .. } => continue,
+                frame => frame,
+            };
+            writer.write_all(to_line(&frame)?.as_bytes()).await?;
+            if matches!(frame, ServerFrame::Bye(_)) {
+                return Ok::<(), std::io::Error>(());
+            }
+        }
+    });
+    let mut name = None;
+    let result = async {
+        while let Some(line) = lines.next_line().await? {
+            let event = match parse_frame::<ClientFrame>(&line) {
+                Ok(ClientFrame::Hello(requested)) if name.is_none() => {
+                    let joined = hub.join(authenticated.as_deref().unwrap_or(&requested));
+                    let note = format!("[session] {} attached, {} clients", joined, hub.clients().len());
+                    *own_name.lock().unwrap() = Some(joined.clone());
+                    name = Some(joined);
+                    ConsoleEvent::Message(Message::from_console(note))
+                },
+                Ok(ClientFrame::Hello(_)) | Ok(ClientFrame::Auth { .. }) => continue,
+                Ok(ClientFrame::Input(line)) => {
+                    let client = name.get_or_insert_with(|| hub.join("client")).clone();
+                    own_name.lock().unwrap().get_or_insert_with(|| client.clone());
+                    ConsoleEvent::SessionInput { client, line }
+                },
+                Ok(ClientFrame::Detach) => ConsoleEvent::Detach,
+                Err(_) => continue,
+            };
+            if console.send(event).await.is_err() {
+                break;
+            }
+        }
+        Ok::<(), std::io::Error>(())
+    }.await;
+    sending.abort();
+    if let Some(name) = name {
+        hub.leave(&name);
+        let note = format!("[session] {} left", name);
+        let _ = console.send(ConsoleEvent::Message(Message::from_console(note))).await;
+    }
+    result
+}
+
+/**
+ * Whether a client shows a message through its own filter rules, the console's own lines always get through
+ */
+pub fn client_admits(filter: &mut FilterRules, message: &Message) -> bool {
+    message.sender == CONSOLE_SENDER || filter.admits(&message.sender, message)
+}
+
+/**
+ * /filter typed at an attached client, it changes the client's own rules and leaves what every other client and the console show alone
+ */
+pub fn run_client_filter(filter: &mut FilterRules, command: RuleCommand) -> Vec<Message> {
+    let reply = |text: String| vec![Message::from_console(format!("[filter] this client: {}", text))];
+    match command {
+        RuleCommand::List => {
+            let mut lines = reply(format!("{} rules, {} messages filtered out", filter.rules().len(), filter.filtered()));
+            for (index, rule) in filter.rules().iter().enumerate() {
+                lines.extend(reply(format!("{}. {} ({} hits)", index + 1, rule, rule.hits)));
+            }
+            lines
+        },
+        RuleCommand::Add(rule) => match FilterRule::parse(&rule) {
+            Ok(rule) => {
+                let text = format!("added {}. {}", filter.rules().len() + 1, rule);
+                filter.add(rule);
+                reply(text)
+            },
+            Err(e) => reply(format!("{}, eg. /filter add exclude sender heartbeat*", e)),
+        },
+        RuleCommand::Remove(Some(number)) => match filter.remove(number - 1) {
+            Some(rule) => reply(format!("removed {}", rule)),
+            None => reply(format!("no rule {}", number)),
+        },
+        RuleCommand::Remove(None) => reply("usage: /filter remove <number>".to_string()),
+        RuleCommand::Clear => {
+            filter.clear();
+            reply("removed every rule".to_string())
+        },
+        RuleCommand::Unknown(name) => reply(format!("unknown subcommand {}, use list, add, remove or clear", name)),
+    }
+}
+
+/**
+ * The secret a client answers a challenge with, DUALZONE_TOKEN for a session with a token or DUALZONE_PASSWORD for one with users
+ */
+pub fn client_secret() -> Option<String> {
+    std::env::var("DUALZONE_TOKEN").or_else(|_| std::env::var("DUALZONE_PASSWORD")).ok()
+}
+
+/**
+ * Read the Welcome and answer its challenge, if it has one, as user with the secret secret() gives. True when it had one.
+ * secret is only asked for when there is a challenge, so a session without auth never asks for a password
+ */
+pub async fn client_handshake<R, W>(lines: &mut tokio::io::Lines<R>, writer: &mut W, user: &str, secret: impl FnOnce() -> Option<String>) -> std::io::Result<bool>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let line = lines.next_line().await?.ok_or_else(|| std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the console closed the session"))?;
+    let challenge = match parse_frame::<ServerFrame>(&line)? {
+        ServerFrame::Welcome { challenge } => challenge,
+        _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not a dualZoneCli session")),
+    };
+    let challenged = challenge.is_some();
+    if let Some(challenge) = challenge {
+        let secret = secret().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::PermissionDenied, "the session needs a secret, set DUALZONE_TOKEN or DUALZONE_PASSWORD"))?;
+        let frame = ClientFrame::Auth { user: user.to_string(), proof: auth::proof(&secret, &challenge) };
+        writer.write_all(to_line(&frame)?.as_bytes()).await?;
+    }
+    Ok(challenged)
+}
+
+/**
+ * Ask the console serving the socket to detach its own terminal, for dualZoneCli detach
+ */
+#[cfg(unix)]
+pub async fn request_detach(path: &Path) -> std::io::Result<()> {
+    let stream = tokio::net::UnixStream::connect(path).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let challenged = client_handshake(&mut lines, &mut writer, &user_name(), client_secret).await?;
+    writer.write_all(to_line(&ClientFrame::Detach)?.as_bytes()).await?;
+    //a wrong secret is only told after a pause and the detach is ignored then, a right one gets the backlog
+    if challenged {
+        if let Ok(Ok(Some(line))) = tokio::time::timeout(std::time::Duration::from_secs(2), lines.next_line()).await {
+            if let Ok(ServerFrame::Bye(reason)) = parse_frame::<ServerFrame>(&line) {
+                return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, reason));
+            }
+        }
+    }
+    writer.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_taken_name_is_numbered() {
+        let hub = SessionHub::new(10);
+        assert_eq!(hub.join("alice"), "alice");
+        assert_eq!(hub.join("alice"), "alice-2");
+        assert_eq!(hub.join(" "), "client");
+    }
+
+    #[test]
+    fn the_terminal_and_the_console_are_never_a_client() {
+        let hub = SessionHub::new(10);
+        assert_eq!(hub.join(USER_SENDER), "user-2");
+        assert_eq!(hub.join(CONSOLE_SENDER), "console-2");
+        assert_eq!(hub.join("User"), "User-2");
+        assert_eq!(hub.clients(), ["user-2", "console-2", "User-2"]);
+    }
+}
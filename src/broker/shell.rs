@@ -0,0 +1,139 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::pattern::glob_match;
+
+/**
+ * The commands asked about before /sh runs them unless the [shell] table has a confirm list of its own
+ */
+pub const DEFAULT_CONFIRM: [&str; 24] = [
+    "rm", "rmdir", "mv", "dd", "mkfs*", "shred", "truncate", "chmod", "chown", "kill", "killall", "pkill",
+    "shutdown", "reboot", "halt", "poweroff", "systemctl stop", "systemctl restart", "systemctl disable",
+    "git push", "git reset", "git clean", "docker rm*", "kubectl delete",
+];
+
+/**
+ * What /sh may run, see Console::set_shell_policy
+ *  ~ allow is what it runs at all, nothing until it is set. ["*"] allows anything.
+ *  ~ confirm is what the user is asked about first, DEFAULT_CONFIRM unless set.
+ * A pattern is a command with any arguments after it, eg. "git status" allows git status -s, and * and ? are wildcards
+ * (see broker::pattern::glob_match). Every part of a line with ;, &&, ||, | or backticks in it has to be allowed on its own,
+ * and a line is asked about when one of its parts is. A line that redirects (>, >> or <) is always asked about, it writes
+ * or reads a file the allow list knows nothing of, and one expanding $VAR, ${VAR} or $( ) is refused, it could run anything
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellPolicy {
+    pub allow: Vec<String>,
+    pub confirm: Vec<String>,
+}
+
+impl Default for ShellPolicy {
+    fn default() -> Self {
+        ShellPolicy { allow: Vec::new(), confirm: DEFAULT_CONFIRM.iter().map(|pattern| pattern.to_string()).collect() }
+    }
+}
+
+/**
+ * What the policy says about a line
+ *  ~ Run runs it, Confirm once the user says yes.
+ *  ~ Refused is the part of it that isn't allowed.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellVerdict {
+    Run,
+    Confirm,
+    Refused(String),
+}
+
+fn covers(pattern: &str, part: &str) -> bool {
+    glob_match(pattern, part) || glob_match(&format!("{} *", pattern), part)
+}
+
+/**
+ * The commands a shell line runs, split at the shell's separators, pipes and substitutions.
+ * Quotes are not looked into, a separator inside them splits as well, which only ever asks for more
+ */
+pub fn command_parts(line: &str) -> Vec<String> {
+    line.split([';', '&', '|', '\n', '(', ')', '`', '{', '}'])
+        .map(|part| part.trim().trim_start_matches('$').trim())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .collect()
+}
+
+/**
+ * The first word of a line the shell expands, $HOME, ${HOME} or $(date). Quotes are not looked into here either
+ */
+pub fn expansion(line: &str) -> Option<String> {
+    line.split_whitespace().find(|word| word.contains('$')).map(|word| word.to_string())
+}
+
+/**
+ * Whether a line redirects a command's output to a file (> or >>) or its input from one (<)
+ */
+pub fn redirects(line: &str) -> bool {
+    line.contains(['>', '<'])
+}
+
+impl ShellPolicy {
+    pub fn check(&self, line: &str) -> ShellVerdict {
+        let parts = command_parts(line);
+        if parts.is_empty() {
+            return ShellVerdict::Refused(line.trim().to_string());
+        }
+        if let Some(word) = expansion(line) {
+            return ShellVerdict::Refused(word);
+        }
+        if let Some(part) = parts.iter().find(|part| !self.allow.iter().any(|pattern| covers(pattern, part))) {
+            return ShellVerdict::Refused(part.clone());
+        }
+        match redirects(line) || parts.iter().any(|part| self.confirm.iter().any(|pattern| covers(pattern, part))) {
+            true => ShellVerdict::Confirm,
+            false => ShellVerdict::Run,
+        }
+    }
+}
+
+/**
+ * The program and arguments that hand a line to the system's shell, sh -c or cmd /C on Windows
+ */
+pub fn shell_words(line: &str) -> Vec<String> {
+    match cfg!(windows) {
+        true => vec!["cmd".to_string(), "/C".to_string(), line.to_string()],
+        false => vec!["sh".to_string(), "-c".to_string(), line.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowing(allow: &[&str]) -> ShellPolicy {
+        ShellPolicy { allow: allow.iter().map(|pattern| pattern.to_string()).collect(), ..ShellPolicy::default() }
+    }
+
+    #[test]
+    fn an_allowed_line_runs_without_asking() {
+        assert_eq!(allowing(&["echo", "cat"]).check("echo x | cat"), ShellVerdict::Run);
+    }
+
+    #[test]
+    fn a_redirection_is_asked_about() {
+        let policy = allowing(&["echo", "cat"]);
+        assert_eq!(policy.check("echo x > ~/.bashrc"), ShellVerdict::Confirm);
+        assert_eq!(policy.check("echo x >> ~/.bashrc"), ShellVerdict::Confirm);
+        assert_eq!(policy.check("cat secret > /tmp/out"), ShellVerdict::Confirm);
+        assert_eq!(policy.check("cat < secret"), ShellVerdict::Confirm);
+    }
+
+    #[test]
+    fn an_expansion_is_refused() {
+        let policy = allowing(&["echo", "date"]);
+        assert_eq!(policy.check("echo $HOME"), ShellVerdict::Refused("$HOME".to_string()));
+        assert_eq!(policy.check("echo ${HOME}"), ShellVerdict::Refused("${HOME}".to_string()));
+        assert_eq!(policy.check("echo $(date)"), ShellVerdict::Refused("$(date)".to_string()));
+        assert_eq!(allowing(&["*"]).check("$CMD"), ShellVerdict::Refused("$CMD".to_string()));
+    }
+}
@@ -0,0 +1,88 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::filter::FilterRules;
+use crate::broker::message::{Message, CONSOLE_SENDER};
+
+/**
+ * The source a pane with every message follows
+ */
+pub const ALL_SOURCE: &str = "all";
+
+/**
+ * One pane of a split, following a source through its own filter rules.
+ *  ~ source is a zone, a tab or SubConsole window, a topic or a module name, or all for every message.
+ *  ~ filter works like the Console's /filter rules but only decides what the pane shows, the rest of the console still gets everything.
+ */
+#[derive(Debug, Clone)]
+pub struct SplitPane {
+    pub source: String,
+    pub filter: FilterRules,
+}
+
+impl SplitPane {
+    pub fn new(source: &str) -> Self {
+        SplitPane { source: source.to_string(), filter: FilterRules::default() }
+    }
+
+    /**
+     * Whether a message routed to its zone (and tab) comes from the pane's source, before the filter
+     */
+    pub fn follows(&self, sender_name: &str, message: &Message) -> bool {
+        let source = Some(self.source.as_str());
+        self.source.eq_ignore_ascii_case(ALL_SOURCE)
+            || message.zone.as_deref() == source
+            || message.tab.as_deref() == source
+            || message.topic.as_deref() == source
+            || sender_name == self.source
+    }
+}
+
+/**
+ * Two sources followed at once in a split output area (/split errors all), one pane over the other.
+ *  ~ Each pane is an output zone of its own named after its number and source, eg. "1: errors",
+ *    so it has its own scrollback, search and scroll position.
+ *  ~ Messages are copied into the panes as they arrive, a pane starts out empty.
+ *  ~ The zones that were on screen go back when the split ends (/split off), they kept getting their messages meanwhile.
+ */
+#[derive(Debug, Clone)]
+pub struct Split {
+    pub panes: [SplitPane; 2],
+}
+
+impl Split {
+    pub fn new(first: &str, second: &str) -> Self {
+        Split { panes: [SplitPane::new(first), SplitPane::new(second)] }
+    }
+
+    /**
+     * The names of the panes' zones, top first
+     */
+    pub fn zones(&self) -> [String; 2] {
+        [Split::zone_name(0, &self.panes[0].source), Split::zone_name(1, &self.panes[1].source)]
+    }
+
+    pub fn zone_name(index: usize, source: &str) -> String {
+        format!("{}: {}", index + 1, source)
+    }
+
+    /**
+     * The copies of a message for the panes that follow its source and whose filter lets it through.
+     * The console's own lines go in both panes, the replies to /split would be out of sight otherwise
+     */
+    pub fn copies(&mut self, sender_name: &str, message: &Message) -> Vec<Message> {
+        let console = message.sender == CONSOLE_SENDER;
+        let mut copies = Vec::new();
+        for (index, pane) in self.panes.iter_mut().enumerate() {
+            if console || (pane.follows(sender_name, message) && pane.filter.admits(sender_name, message)) {
+                let mut copy = message.clone();
+                copy.zone = Some(Split::zone_name(index, &pane.source));
+                copy.tab = None;
+                copies.push(copy);
+            }
+        }
+        copies
+    }
+}
@@ -0,0 +1,270 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::net::SocketAddr;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::{ConsoleEvent, Message, RenderedLine};
+use crate::broker::remote::RemoteRequest;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use chrono::SecondsFormat;
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, oneshot};
+
+/**
+ * The longest datagram sent or read, what RFC 5424 says every receiver should take. A longer line is cut
+ */
+pub const MAX_DATAGRAM: usize = 2048;
+
+/**
+ * The SD-ID of the structured data a forwarded line carries, 32473 is the enterprise number RFC 5612 keeps for examples
+ */
+const SD_ID: &str = "dualzone@32473";
+
+/**
+ * Where the rendered lines are forwarded to, a syslog daemon's UDP port (eg. 127.0.0.1:514), see Console::forward_syslog.
+ * facility is the number of the facility they go out as (local0, 16, unless set), app_name the APP-NAME of every line
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyslogTarget {
+    pub addr: String,
+    pub facility: u8,
+    pub app_name: String,
+}
+
+impl SyslogTarget {
+    pub fn new(addr: impl Into<String>) -> Self {
+        SyslogTarget { addr: addr.into(), facility: 16, app_name: "dualzone".to_string() }
+    }
+}
+
+/**
+ * A facility by its name (kern, user, daemon, auth, local0 to local7, ...) or number
+ */
+pub fn parse_facility(name: &str) -> Option<u8> {
+    const NAMES: [&str; 24] = [
+        "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv", "ftp",
+        "ntp", "security", "console", "solaris-cron", "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+    ];
+    let name = name.to_ascii_lowercase();
+    match name.parse::<u8>() {
+        Ok(number) if (number as usize) < NAMES.len() => Some(number),
+        Ok(_) => None,
+        Err(_) => NAMES.iter().position(|known| *known == name).map(|number| number as u8),
+    }
+}
+
+/**
+ * The syslog severity a priority goes out as, 0 (emergency) is never sent
+ */
+pub fn severity_of(priority: &PriorityStatus) -> u8 {
+    match priority {
+        PriorityStatus::Urgent => 1,
+        PriorityStatus::Critical => 2,
+        PriorityStatus::Exception => 3,
+        PriorityStatus::Warning => 4,
+        PriorityStatus::Notice => 5,
+        PriorityStatus::Normal | PriorityStatus::Informational | PriorityStatus::Delay => 6,
+        PriorityStatus::Verbose | PriorityStatus::Ignore => 7,
+    }
+}
+
+/**
+ * The priority a syslog severity shows as, emergency and alert are both Urgent
+ */
+pub fn priority_of(severity: u8) -> PriorityStatus {
+    match severity {
+        0 | 1 => PriorityStatus::Urgent,
+        2 => PriorityStatus::Critical,
+        3 => PriorityStatus::Exception,
+        4 => PriorityStatus::Warning,
+        5 => PriorityStatus::Notice,
+        6 => PriorityStatus::Normal,
+        _ => PriorityStatus::Verbose,
+    }
+}
+
+/**
+ * The name of this machine in the HOSTNAME field
+ */
+fn hostname() -> String {
+    std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME"))
+        .or_else(|_| std::fs::read_to_string("/etc/hostname").map(|name| name.trim().to_string()))
+        .unwrap_or_else(|_| "-".to_string())
+}
+
+/**
+ * A header field as RFC 5424 allows it, printable ASCII without spaces and at most max long, - when there is nothing left
+ */
+fn header_field(value: &str, max: usize) -> String {
+    let field: String = value.chars().filter(|c| c.is_ascii_graphic()).take(max).collect();
+    if field.is_empty() { "-".to_string() } else { field }
+}
+
+fn escape_param(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace(']', "\\]")
+}
+
+/**
+ * A rendered line as an RFC 5424 line, the sender and topic in structured data:
+ * <134>1 2024-05-01T12:00:00.000Z build-box dualzone - - [dualzone@32473 sender="builder"] compiling
+ */
+pub fn format_record(target: &SyslogTarget, host: &str, record: &RenderedLine) -> String {
+    let pri = target.facility as u16 * 8 + severity_of(&record.priority) as u16;
+    let timestamp = chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    let mut data = format!("[{} sender=\"{}\"", SD_ID, escape_param(&record.sender));
+    if let Some(topic) = &record.topic {
+        data.push_str(&format!(" topic=\"{}\"", escape_param(topic)));
+    }
+    data.push(']');
+    let mut line = format!("<{}>1 {} {} {} - - {} {}", pri, timestamp, header_field(host, 255), header_field(&target.app_name, 48), data, record.text);
+    if line.len() > MAX_DATAGRAM {
+        let mut end = MAX_DATAGRAM;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        line.truncate(end);
+    }
+    line
+}
+
+/**
+ * Forward the rendered lines to the daemon until the console goes away. A daemon that can't be reached is said once in the output zone,
+ * the lines meanwhile are lost like any syslog over UDP
+ */
+pub async fn forward(target: SyslogTarget, mut records: mpsc::UnboundedReceiver<RenderedLine>, console: MailboxSender) {
+    let socket = match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket,
+        Err(e) => {
+            let note = Message::from_console(format!("[syslog] not forwarding to {}: {}", target.addr, e));
+            let _ = console.send(ConsoleEvent::Message(note)).await;
+            return;
+        },
+    };
+    let host = hostname();
+    let mut failing = false;
+    while let Some(record) = records.recv().await {
+        let line = format_record(&target, &host, &record);
+        match socket.send_to(line.as_bytes(), target.addr.as_str()).await {
+            Ok(_) => failing = false,
+            Err(e) if !failing => {
+                failing = true;
+                let note = Message::from_console(format!("[syslog] could not forward to {}: {}", target.addr, e));
+                if console.send(ConsoleEvent::Message(note)).await.is_err() {
+                    return;
+                }
+            },
+            Err(_) => {},
+        }
+    }
+}
+
+/**
+ * A line read from the syslog port
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyslogLine {
+    pub severity: u8,
+    pub host: Option<String>,
+    pub app: Option<String>,
+    pub text: String,
+}
+
+impl SyslogLine {
+    /**
+     * The sender the line shows as, app@host (or whichever of them there is, syslog when neither)
+     */
+    pub fn sender_name(&self) -> String {
+        match (&self.app, &self.host) {
+            (Some(app), Some(host)) => format!("{}@{}", app, host),
+            (Some(name), None) | (None, Some(name)) => name.clone(),
+            (None, None) => "syslog".to_string(),
+        }
+    }
+}
+
+fn nil(field: &str) -> Option<String> {
+    if field == "-" || field.is_empty() { None } else { Some(field.to_string()) }
+}
+
+/**
+ * Skip the STRUCTURED-DATA of an RFC 5424 line, - or any number of [...] elements, quoted values may hold ] and spaces
+ */
+fn skip_structured_data(rest: &str) -> &str {
+    if let Some(after) = rest.strip_prefix('-') {
+        return after;
+    }
+    let (mut quoted, mut escaped, mut depth) = (false, false, 0);
+    for (index, c) in rest.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '[' if !quoted => depth += 1,
+            ']' if !quoted => {
+                depth -= 1;
+                if depth == 0 && !rest[index + 1..].starts_with('[') {
+                    return &rest[index + 1..];
+                }
+            },
+            _ => {},
+        }
+    }
+    ""
+}
+
+/**
+ * A datagram as a line, RFC 5424 (<PRI>1 TIMESTAMP HOST APP PROCID MSGID SD MSG) or the older
+ * RFC 3164 (<PRI>Mmm dd hh:mm:ss HOST TAG[PID]: MSG). None for anything without a <PRI>
+ */
+pub fn parse_line(datagram: &str) -> Option<SyslogLine> {
+    let rest = datagram.trim_end_matches(['\r', '\n', '\0']).strip_prefix('<')?;
+    let (pri, rest) = rest.split_once('>')?;
+    let severity = (pri.parse::<u16>().ok()? % 8) as u8;
+    if let Some(rest) = rest.strip_prefix("1 ") {
+        let mut fields = rest.splitn(6, ' ');
+        let (_timestamp, host, app, _procid, _msgid) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+        let text = skip_structured_data(fields.next().unwrap_or(""));
+        let text = text.strip_prefix(' ').unwrap_or(text).trim_start_matches('\u{feff}');
+        return Some(SyslogLine { severity, host: nil(host), app: nil(app), text: text.to_string() });
+    }
+    //Mmm dd hh:mm:ss is 15 long, a line without it is taken as just a message
+    let looks_dated = rest.len() > 16 && rest.as_bytes().get(3) == Some(&b' ') && rest.as_bytes().get(15) == Some(&b' ');
+    if !looks_dated {
+        return Some(SyslogLine { severity, host: None, app: None, text: rest.trim().to_string() });
+    }
+    let rest = &rest[16..];
+    let (host, rest) = rest.split_once(' ').unwrap_or(("", rest));
+    let (app, text) = match rest.split_once(": ") {
+        Some((tag, text)) => (tag.split('[').next().map(str::to_string), text),
+        None => (None, rest),
+    };
+    Some(SyslogLine { severity, host: nil(host), app: app.filter(|app| !app.is_empty()), text: text.to_string() })
+}
+
+/**
+ * Show what comes in on a UDP syslog port in the output zone until the console goes away.
+ * Every app@host is a sender of its own and the severity is its priority (see priority_of). Its lines are only shown once it is
+ * in the phonebook (new_sender() or a saved phonebook), anyone can send to a UDP port
+ */
+pub async fn listen(addr: SocketAddr, console: MailboxSender) -> std::io::Result<()> {
+    let socket = UdpSocket::bind(addr).await?;
+    let mut buffer = vec![0u8; 8192];
+    loop {
+        let (len, _) = socket.recv_from(&mut buffer).await?;
+        let line = match parse_line(&String::from_utf8_lossy(&buffer[..len])) {
+            Some(line) => line,
+            None => continue,
+        };
+        let client = format!("syslog:{}", line.sender_name());
+        let request = RemoteRequest::Send { sender: line.sender_name(), text: line.text, priority: priority_of(line.severity), topic: None };
+        //nobody waits for the answer, a line that isn't admitted is just not shown
+        let (reply, _) = oneshot::channel();
+        if console.send(ConsoleEvent::Remote { client, request, reply }).await.is_err() {
+            return Ok(());
+        }
+    }
+}
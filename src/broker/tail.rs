@@ -0,0 +1,220 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use crate::broker::handle::{HandleError, SenderHandle};
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::time::Duration;
+
+/**
+ * How often the followed files are looked at for new lines, a rotation or a truncation
+ */
+pub const TAIL_POLL: Duration = Duration::from_millis(250);
+
+/**
+ * Files followed like tail -F does, every line shown as a message of the file it was appended to (see sender_names).
+ *  ~ A file that doesn't exist yet is waited for, one that is rotated (moved away and made again) is read from the start of the new one,
+ *    one that is truncated from its start again.
+ *  ~ Only what is appended from now on is shown, unless from_start, then the files are read from their first line.
+ * See Console::follow_files, dualZoneCli tail <file>... follows them in a console of their own
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TailSource {
+    pub paths: Vec<PathBuf>,
+    pub from_start: bool,
+}
+
+impl TailSource {
+    pub fn new(paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        TailSource { paths: paths.into_iter().map(Into::into).collect(), from_start: false }
+    }
+
+    pub fn follow(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    pub fn read_from_start(mut self, from_start: bool) -> Self {
+        self.from_start = from_start;
+        self
+    }
+
+    /**
+     * The sender every file's lines come from, its file name, or the whole path when two files have the same name
+     */
+    pub fn sender_names(&self) -> Vec<String> {
+        let file_name = |path: &PathBuf| path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string());
+        self.paths.iter().map(|path| {
+            let name = file_name(path);
+            match self.paths.iter().filter(|other| file_name(other) == name).count() {
+                1 => name,
+                _ => path.display().to_string(),
+            }
+        }).collect()
+    }
+}
+
+/**
+ * Which file a path is, a rotated log is another file under the same path. None where there is no way to tell,
+ * only truncation is noticed there
+ */
+fn identity(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    None
+}
+
+/**
+ * One followed file, what has been read of it and the part of a line that has no newline yet
+ */
+struct Followed {
+    path: PathBuf,
+    output: SenderHandle,
+    file: Option<File>,
+    identity: Option<(u64, u64)>,
+    position: u64,
+    partial: Vec<u8>,
+    missing: bool,
+    closed: bool,
+}
+
+impl Followed {
+    async fn show(&mut self, text: String, priority: PriorityStatus) {
+        if let Err(HandleError::ChannelClosed) = self.output.send_with_priority(text, priority).await {
+            self.closed = true;
+        }
+    }
+
+    /**
+     * Normal like the lines, a rotation said with a higher priority would be taken from the queue before the last lines of the old file
+     */
+    async fn note(&mut self, text: String) {
+        self.show(format!("[tail] {}", text), PriorityStatus::Normal).await;
+    }
+
+    /**
+     * Open the file, at its end when only the lines to come are wanted
+     */
+    async fn open(&mut self, at_end: bool) {
+        let mut file = match File::open(&self.path).await {
+            Ok(file) => file,
+            Err(e) => {
+                if !self.missing {
+                    self.missing = true;
+                    self.note(format!("waiting for {}: {}", self.path.display(), e)).await;
+                }
+                return;
+            },
+        };
+        let metadata = match file.metadata().await {
+            Ok(metadata) => metadata,
+            Err(_) => return,
+        };
+        self.position = if at_end { metadata.len() } else { 0 };
+        if file.seek(SeekFrom::Start(self.position)).await.is_err() {
+            return;
+        }
+        if self.missing {
+            self.missing = false;
+            self.note(format!("following {}", self.path.display())).await;
+        }
+        self.identity = identity(&metadata);
+        self.partial.clear();
+        self.file = Some(file);
+    }
+
+    /**
+     * Show the lines appended since the last look, a line without its newline yet waits for the rest
+     */
+    async fn read_new(&mut self) {
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return,
+        };
+        let mut appended = Vec::new();
+        match file.read_to_end(&mut appended).await {
+            Ok(read) => self.position += read as u64,
+            Err(e) => {
+                self.file = None;
+                return self.note(format!("could not read {}: {}", self.path.display(), e)).await;
+            },
+        }
+        self.partial.extend_from_slice(&appended);
+        while let Some(end) = self.partial.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            let text = String::from_utf8_lossy(&line[..line.len() - 1]).trim_end_matches('\r').to_string();
+            self.show(text, PriorityStatus::Normal).await;
+        }
+    }
+
+    /**
+     * What is under the path now: the same file that may have grown, a truncated one or another one after a rotation
+     */
+    async fn poll(&mut self) {
+        if self.file.is_none() {
+            //a file that shows up after the start is read from its first line, nothing of it has been seen
+            return self.open(false).await;
+        }
+        self.read_new().await;
+        let metadata = match tokio::fs::metadata(&self.path).await {
+            Ok(metadata) => metadata,
+            //moved away and not made again yet, the old file is kept to read what is still written to it
+            Err(_) => return,
+        };
+        if identity(&metadata) != self.identity {
+            self.flush_partial().await;
+            self.note(format!("{} was rotated, following the new file", self.path.display())).await;
+            self.open(false).await;
+            self.read_new().await;
+        } else if metadata.len() < self.position {
+            self.flush_partial().await;
+            self.note(format!("{} was truncated", self.path.display())).await;
+            self.open(false).await;
+            self.read_new().await;
+        }
+    }
+
+    /**
+     * The last line of a file that is let go of, even without its newline
+     */
+    async fn flush_partial(&mut self) {
+        if !self.partial.is_empty() {
+            let text = String::from_utf8_lossy(&self.partial).to_string();
+            self.partial.clear();
+            self.show(text, PriorityStatus::Normal).await;
+        }
+    }
+}
+
+/**
+ * Follow the files until the console goes away, outputs are the senders of TailSource::sender_names in the same order
+ */
+pub async fn run(source: TailSource, outputs: Vec<SenderHandle>) {
+    let mut followed: Vec<Followed> = source.paths.iter().cloned().zip(outputs).map(|(path, output)| {
+        Followed { path, output, file: None, identity: None, position: 0, partial: Vec::new(), missing: false, closed: false }
+    }).collect();
+    for file in followed.iter_mut() {
+        file.open(!source.from_start).await;
+    }
+    let mut tick = tokio::time::interval(TAIL_POLL);
+    loop {
+        tick.tick().await;
+        for file in followed.iter_mut() {
+            file.poll().await;
+        }
+        if followed.iter().all(|file| file.closed) {
+            return;
+        }
+    }
+}
@@ -0,0 +1,209 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use crate::broker::message::{self, Message};
+use crate::user_input::structs::PriorityStatus;
+
+/**
+ * How many threads are kept, a message of one that was forgotten starts a new line
+ */
+pub const MAX_THREADS: usize = 512;
+
+/**
+ * How many lines of a thread are kept for when it is expanded, the later ones are only counted
+ */
+pub const MAX_THREAD_LINES: usize = 1000;
+
+/**
+ * What /expand and /collapse do, and the toggle-thread keybind (Alt+T). None is the newest thread or folded payload
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThreadCommand {
+    Expand(Option<String>),
+    Collapse(Option<String>),
+    Toggle(Option<String>),
+}
+
+/**
+ * The messages of one correlation id so far, drawn as a single line in the slot of the first.
+ * A folded thread is the lines of one multi-line payload instead, eg. a panic or a stack trace
+ */
+#[derive(Debug, Clone)]
+struct Thread {
+    slot: u64,
+    sender: String,
+    zone: Option<String>,
+    tab: Option<String>,
+    topic: Option<String>,
+    priority: PriorityStatus,
+    lines: Vec<Arc<str>>,
+    count: usize,
+    expanded: bool,
+    folded: bool,
+}
+
+impl Thread {
+    /**
+     * The first line and "(+12 more) [id]" while collapsed, every line kept under the first one while expanded.
+     * A folded payload's lines are drawn as they were sent
+     */
+    fn text(&self, id: &str) -> String {
+        let first = self.lines.first().map_or("", |line| line);
+        if self.count == 1 {
+            return first.to_string();
+        }
+        if !self.expanded {
+            let more = if self.folded { "lines" } else { "more" };
+            return format!("{} (+{} {}) [{}]", first, self.count - 1, more, id);
+        }
+        let indent = if self.folded { "" } else { "  " };
+        let mut text = first.to_string();
+        for line in self.lines.iter().skip(1) {
+            text.push('\n');
+            text.push_str(indent);
+            text.push_str(line);
+        }
+        if self.count > self.lines.len() {
+            text.push_str(&format!("\n{}({} more not kept)", indent, self.count - self.lines.len()));
+        }
+        text
+    }
+
+    /**
+     * The line as it is drawn now, in its slot
+     */
+    fn message(&self, id: &str) -> Message {
+        let mut message = Message::new(self.sender.clone(), self.priority.clone(), self.text(id));
+        message.slot = Some(self.slot);
+        message.zone = self.zone.clone();
+        message.tab = self.tab.clone();
+        message.topic = self.topic.clone();
+        message.correlation = Some(id.to_string());
+        message
+    }
+}
+
+/**
+ * Groups the messages that share a correlation id into one collapsible line, eg. the request and response lines of a trace
+ * or the frames of a stack trace, see SenderHandle::send_in_thread.
+ *  ~ Like Repeats it works through slots, every message of a thread replaces the line of the first, so the scrollback keeps one entry.
+ *  ~ The line has the highest priority any of its messages had.
+ *  ~ Pinned messages, widgets and updates are never grouped.
+ * It also folds a payload of several lines to its first one, expanded the same way with its id ("#" and the message id)
+ */
+#[derive(Debug, Default)]
+pub struct Threads {
+    threads: HashMap<String, Thread>,
+    order: VecDeque<String>,
+}
+
+impl Threads {
+    /**
+     * Called for every message once its zone is known, one without a correlation id is left as it is
+     */
+    pub fn group(&mut self, message: &mut Message) {
+        let id = match message.correlation.clone() {
+            Some(id) if !message.pinned && message.widget.is_none() && message.replaces.is_none() && message.slot.is_none() => id,
+            _ => return,
+        };
+        if let Some(thread) = self.threads.get_mut(&id) {
+            if thread.lines.len() < MAX_THREAD_LINES {
+                thread.lines.push(message.payload.clone());
+            }
+            thread.count += 1;
+            if message.priority.severity() > thread.priority.severity() {
+                thread.priority = message.priority.clone();
+            }
+            message.priority = thread.priority.clone();
+            message.slot = Some(thread.slot);
+            message.payload = thread.text(&id).into();
+            self.order.retain(|known| *known != id);
+            self.order.push_back(id);
+            return;
+        }
+        let first = vec![message.payload.clone()];
+        self.start(id, message, first, 1, false);
+    }
+
+    /**
+     * Called for every message like group, enabled is whether its payload folds when it has several lines.
+     * A message of a thread is left to the thread
+     */
+    pub fn fold(&mut self, message: &mut Message, enabled: bool) {
+        if !enabled || message.correlation.is_some() || message.pinned || message.widget.is_some() || message.replaces.is_some() || message.slot.is_some() {
+            return;
+        }
+        let text = message.payload.trim_end();
+        let count = text.lines().count();
+        if count < 2 {
+            return;
+        }
+        let lines: Vec<Arc<str>> = text.lines().take(MAX_THREAD_LINES).map(Arc::from).collect();
+        let id = format!("#{}", message.id);
+        let thread = self.start(id.clone(), message, lines, count, true);
+        message.payload = thread.text(&id).into();
+    }
+
+    /**
+     * A new thread drawn in a slot of its own, the oldest ones are forgotten past MAX_THREADS
+     */
+    fn start(&mut self, id: String, message: &mut Message, lines: Vec<Arc<str>>, count: usize, folded: bool) -> &Thread {
+        while self.order.len() >= MAX_THREADS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.threads.remove(&oldest);
+            }
+        }
+        let slot = message::next_id();
+        message.slot = Some(slot);
+        self.order.push_back(id.clone());
+        self.threads.entry(id).or_insert(Thread {
+            slot,
+            sender: message.sender.clone(),
+            zone: message.zone.clone(),
+            tab: message.tab.clone(),
+            topic: message.topic.clone(),
+            priority: message.priority.clone(),
+            lines,
+            count,
+            expanded: false,
+            folded,
+        })
+    }
+
+    /**
+     * The id of the thread a command is about, the newest one when it names none. A folded payload's may be given without its "#"
+     */
+    fn resolve(&self, id: Option<String>) -> Option<String> {
+        match id {
+            Some(id) if self.threads.contains_key(&id) => Some(id),
+            Some(id) => Some(format!("#{}", id)).filter(|folded| self.threads.contains_key(folded)),
+            None => self.order.back().cloned(),
+        }
+    }
+
+    /**
+     * Expand or collapse a thread, the line to draw in its slot or why there is none
+     */
+    pub fn apply(&mut self, command: ThreadCommand) -> Result<Message, String> {
+        let (id, expanded) = match command {
+            ThreadCommand::Expand(id) => (id, Some(true)),
+            ThreadCommand::Collapse(id) => (id, Some(false)),
+            ThreadCommand::Toggle(id) => (id, None),
+        };
+        let named = id.clone();
+        let id = match self.resolve(id) {
+            Some(id) => id,
+            None => return Err(match named {
+                Some(name) => format!("no thread {}", name),
+                None => "no threads yet".to_string(),
+            }),
+        };
+        let thread = self.threads.get_mut(&id).ok_or_else(|| format!("no thread {}", id))?;
+        thread.expanded = expanded.unwrap_or(!thread.expanded);
+        Ok(thread.message(&id))
+    }
+}
@@ -0,0 +1,90 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use crate::broker::auth::SessionAuth;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::session::{self, SessionHub, TlsListen};
+
+//External
+use rustls_pemfile::Item;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::{self, Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName};
+use tokio_rustls::{client, TlsAcceptor, TlsConnector};
+
+fn invalid(e: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+}
+
+/**
+ * Every certificate in a PEM file, the server's own first and then its chain
+ */
+fn load_certs(path: &Path) -> std::io::Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(path)?))?;
+    match certs.is_empty() {
+        true => Err(invalid(format!("no certificate in {}", path.display()))),
+        false => Ok(certs.into_iter().map(Certificate).collect()),
+    }
+}
+
+/**
+ * The first private key in a PEM file, PKCS#8, RSA or EC
+ */
+fn load_key(path: &Path) -> std::io::Result<PrivateKey> {
+    for item in rustls_pemfile::read_all(&mut BufReader::new(File::open(path)?))? {
+        if let Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) = item {
+            return Ok(PrivateKey(key));
+        }
+    }
+    Err(invalid(format!("no private key in {}", path.display())))
+}
+
+/**
+ * Serve the session on the network until the console goes away, every TLS connection is a client like one on the socket.
+ * Refuses to start without auth, the socket is only open to the user running the console but an address is open to anyone who reaches it
+ */
+pub async fn serve(listen: TlsListen, hub: SessionHub, console: MailboxSender, auth: Option<SessionAuth>) -> std::io::Result<()> {
+    if auth.is_none() {
+        return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "a session on the network needs a [session] token or users"));
+    }
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(load_certs(&listen.cert)?, load_key(&listen.key)?)
+        .map_err(invalid)?;
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind(listen.addr).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (acceptor, hub, console, auth) = (acceptor.clone(), hub.clone(), console.clone(), auth.clone());
+        tokio::spawn(async move {
+            //a client that fails the handshake never gets as far as the challenge
+            if let Ok(stream) = acceptor.accept(stream).await {
+                let _ = session::serve_client(stream, hub, console, auth).await;
+            }
+        });
+    }
+}
+
+/**
+ * Connect to a session served on the network at host:port. The server's certificate is checked against the ones in the
+ * PEM file DUALZONE_CA names, a console's own certificate for a self signed one
+ */
+pub async fn connect(addr: &str) -> std::io::Result<client::TlsStream<TcpStream>> {
+    let ca = std::env::var_os("DUALZONE_CA")
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "set DUALZONE_CA to the certificate the console is trusted by"))?;
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(Path::new(&ca))? {
+        roots.add(&cert).map_err(invalid)?;
+    }
+    let config = ClientConfig::builder().with_safe_defaults().with_root_certificates(roots).with_no_client_auth();
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host).trim_start_matches('[').trim_end_matches(']');
+    let name = ServerName::try_from(host).map_err(invalid)?;
+    let stream = TcpStream::connect(addr).await?;
+    TlsConnector::from(Arc::new(config)).connect(name, stream).await
+}
@@ -0,0 +1,217 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use crate::broker::alerts;
+use crate::broker::filter::FilterMatch;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::message::{ConsoleEvent, Message, RenderedLine};
+
+//External
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+/**
+ * How long a webhook has to answer
+ */
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/**
+ * How often a line is tried again before it is given up on, unless the sink says
+ */
+pub const DEFAULT_RETRIES: u32 = 5;
+
+/**
+ * The wait before the first retry, doubled for every one after it up to MAX_BACKOFF
+ */
+pub const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/**
+ * Why a webhook could not be called
+ */
+#[derive(Debug)]
+pub enum WebhookError {
+    Unsupported,
+    Request(String),
+    Status(u16),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookError::Unsupported => write!(f, "webhooks are not compiled in, build with --features webhook"),
+            WebhookError::Request(e) => write!(f, "request failed: {}", e),
+            WebhookError::Status(status) => write!(f, "answered {}", status),
+        }
+    }
+}
+
+impl std::error::Error for WebhookError {}
+
+impl WebhookError {
+    /**
+     * Whether trying again may help, a 4xx other than 408 and 429 says the request itself is wrong
+     */
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            WebhookError::Unsupported => false,
+            WebhookError::Request(_) => true,
+            WebhookError::Status(status) => *status >= 500 || *status == 408 || *status == 429,
+        }
+    }
+}
+
+/**
+ * The body a line is posted as
+ *  ~ Json is the line itself: id, sender, priority, text, topic, fields and at (RFC 3339).
+ *  ~ Slack is {"text": ...} for a Slack incoming webhook, Discord {"content": ...} for a Discord one.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum WebhookFormat {
+    #[default]
+    Json,
+    Slack,
+    Discord,
+}
+
+impl WebhookFormat {
+    pub fn parse(name: &str) -> Option<WebhookFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(WebhookFormat::Json),
+            "slack" => Some(WebhookFormat::Slack),
+            "discord" => Some(WebhookFormat::Discord),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * What is posted for a line
+ */
+pub fn payload(line: &RenderedLine, format: &WebhookFormat) -> Value {
+    let summary = format!("[{:?}] {}: {}", line.priority, line.sender, line.text);
+    match format {
+        WebhookFormat::Json => json!({
+            "id": line.id,
+            "sender": line.sender,
+            "priority": format!("{:?}", line.priority),
+            "text": line.text,
+            "topic": line.topic,
+            "fields": line.fields,
+            "at": line.sent_at.map_or_else(chrono::Local::now, chrono::DateTime::<chrono::Local>::from).to_rfc3339(),
+        }),
+        WebhookFormat::Slack => json!({ "text": summary }),
+        WebhookFormat::Discord => json!({ "content": summary }),
+    }
+}
+
+/**
+ * POST body as JSON to url, an answer outside 2xx is an error. Needs the webhook feature
+ */
+#[cfg(feature = "webhook")]
+pub async fn post(url: &str, body: &Value) -> Result<(), WebhookError> {
+    let client = reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build().map_err(|e| WebhookError::Request(e.to_string()))?;
+    let response = client.post(url).json(body).send().await.map_err(|e| WebhookError::Request(e.to_string()))?;
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        status => Err(WebhookError::Status(status.as_u16())),
+    }
+}
+
+#[cfg(not(feature = "webhook"))]
+pub async fn post(url: &str, body: &Value) -> Result<(), WebhookError> {
+    Err(WebhookError::Unsupported)
+}
+
+/**
+ * Where the rendered lines that match go, see Console::forward_to_webhook
+ *  ~ when is the conditions a line has to match, those of an alert rule (see alerts::parse_conditions). Every line when empty.
+ *  ~ A line that could not be posted is tried again retries times, FIRST_BACKOFF after the first failure and twice as long
+ *    after every one after it. The lines after it wait their turn, they are posted in order.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookSink {
+    pub url: String,
+    pub when: Vec<FilterMatch>,
+    pub format: WebhookFormat,
+    pub retries: u32,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Result<WebhookSink, String> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("a webhook is an http:// or https:// URL, not {}", url));
+        }
+        Ok(WebhookSink { url: url.to_string(), when: Vec::new(), format: WebhookFormat::Json, retries: DEFAULT_RETRIES })
+    }
+
+    /**
+     * Only post the lines that match, eg. "priority Critical+ and sender db*"
+     */
+    pub fn when(mut self, conditions: &str) -> Result<WebhookSink, String> {
+        self.when = alerts::parse_conditions(conditions)?;
+        Ok(self)
+    }
+
+    pub fn format(mut self, format: WebhookFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn matches(&self, line: &RenderedLine) -> bool {
+        self.when.iter().all(|condition| condition.matches_line(line))
+    }
+
+    /**
+     * Post one line, trying again with backoff as long as it may help. The error is the last one
+     */
+    async fn deliver(&self, line: &RenderedLine) -> Result<(), WebhookError> {
+        let body = payload(line, &self.format);
+        let mut backoff = FIRST_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            match post(&self.url, &body).await {
+                Ok(()) => return Ok(()),
+                Err(e) if !e.is_retryable() || attempt >= self.retries => return Err(e),
+                Err(_) => {
+                    attempt += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                },
+            }
+        }
+    }
+}
+
+/**
+ * Post the matching rendered lines until the console goes away. A line given up on is said in the output zone,
+ * once until one gets through again so a webhook that is down doesn't fill the zone
+ */
+pub async fn forward(sink: WebhookSink, mut lines: mpsc::UnboundedReceiver<RenderedLine>, console: MailboxSender) {
+    let mut failing = false;
+    while let Some(line) = lines.recv().await {
+        if !sink.matches(&line) {
+            continue;
+        }
+        match sink.deliver(&line).await {
+            Ok(()) => failing = false,
+            Err(e) if !failing => {
+                failing = true;
+                let note = Message::from_console(format!("[webhook] gave up posting to {}: {}", sink.url, e));
+                if console.send(ConsoleEvent::Message(note)).await.is_err() {
+                    return;
+                }
+            },
+            Err(_) => {},
+        }
+    }
+}
@@ -0,0 +1,11 @@
+#![allow(unused)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+/**
+ * The console as a library, for the dualzone-test harness (src/bin/dualzone-test.rs) and the benches.
+ * The dualZoneCli binary builds the same modules itself, it reaches into the Console past what is public here.
+ */
+pub mod user_input;
+pub mod broker;
+pub mod output;
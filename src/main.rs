@@ -5,12 +5,27 @@
 
 use std::error::Error;
 //Internal
-use std::io::{stdout, Write, ErrorKind};
+use std::io::{Write, ErrorKind};
 use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
 use user_input::structs::Console;
+use user_input::config::DEFAULT_CONFIG_FILE;
+use broker::message::{ConsoleEvent, Output};
+use output::renderer::Renderer;
+use output::status_bar::StatusBar;
+use output::clipboard::{self, CopiedTo, CopyTarget};
+use output::notify;
+use output::export;
+use output::tabs::TabCommand;
+use output::recorder::{Recorded, Recorder, Recording};
+use output::terminal::RenderStrategy;
+use output::writer::TerminalWriter;
+use broker::message::Message;
 
 //modules
 mod user_input;
+mod broker;
+mod output;
 
 //External
 use tokio::io::{AsyncBufReadExt, BufReader};
@@ -20,14 +35,116 @@ use tokio::time::Duration;
 use crossterm::{cursor, execute, queue, style, terminal, Result};
 use serde::{Serialize, Deserialize};
 
-const PROMPT: &str = "> ";
-
 /**
  * main app starting point
+ *  ~ dualZoneCli --record <file> records the session while it runs.
+ *  ~ --accessible (before any other argument) runs in accessibility mode, see Console::enable_accessibility,
+ *    and --no-color draws without colors like NO_COLOR does. --session serves the console on its session socket (see broker::session).
+ *    other-program | dualZoneCli --ingest shows the piped lines in the output zone and takes the input from the terminal,
+ *    see Console::ingest_stdin.
+ *  ~ dualZoneCli detach lets the terminal of the console serving the session go, the console keeps running,
+ *    and dualZoneCli attach [name] shows it in this terminal with its scrollback. DUALZONE_SOCKET says where the socket is.
+ *    Any number of terminals attach at once, each types under its name (the login name when not given) and has its own /filter rules.
+ *    dualZoneCli attach [name] --tls host:port attaches to a console serving its session on the network, see broker::tls.
+ *  ~ dualZoneCli tail [--from-start] <file>... shows what is appended to the files like tail -F, each file a sender
+ *    that /filter, /view and the rest work on, see broker::tail::TailSource.
+ *  ~ dualZoneCli replay <file> [--speed N] plays a recorded session back, N = 2 is twice as fast.
+ *  ~ dualZoneCli --bench [--messages N] [--frames N] [--size WxH] measures broker throughput, send to frame latency
+ *    and the cost of a frame without a terminal, see output::bench::run. Run it on a release build.
+ *    cargo bench measures the same under criterion and compares with the last run, see benches/console.rs.
  */
 pub fn main() {
 
-    let broker = init(); 
+    let mut broker = init(); 
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    while let Some(flag) = args.first().map(|arg| arg.as_str()) {
+        match flag {
+            "--accessible" => broker.enable_accessibility(true),
+            "--no-color" => broker.set_color_depth(Some(output::theme::ColorDepth::NoColor)),
+            "--session" => broker.serve_session(Some(broker::session::socket_path())),
+            "--ingest" => broker.ingest_stdin(true),
+            _ => break,
+        }
+        args.remove(0);
+    }
+    match args.first().map(|arg| arg.as_str()) {
+        Some("replay") => {
+            let speed = match args.get(2).map(|arg| arg.as_str()) {
+                Some("--speed") => args.get(3).and_then(|speed| speed.parse::<f64>().ok()).filter(|speed| *speed > 0.0),
+                None => Some(1.0),
+                _ => None,
+            };
+            match (args.get(1), speed) {
+                (Some(path), Some(speed)) => { let _ = replay(broker, PathBuf::from(path), speed); },
+                _ => println!("usage: dualZoneCli replay <file> [--speed N]"),
+            }
+            return;
+        },
+        Some("attach") => {
+            let tls = args.iter().position(|arg| arg == "--tls");
+            let target = match tls.and_then(|index| args.get(index + 1)) {
+                Some(addr) => broker::session::SessionTarget::Tls(addr.clone()),
+                None => broker::session::SessionTarget::Socket(broker::session::socket_path()),
+            };
+            let name = args.get(1).filter(|arg| *arg != "--tls").cloned().unwrap_or_else(broker::session::user_name);
+            let _ = attach(broker, target, name);
+            return;
+        },
+        Some("detach") => {
+            let path = broker::session::socket_path();
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().expect("a tokio runtime");
+            #[cfg(unix)]
+            match runtime.block_on(broker::session::request_detach(&path)) {
+                Ok(()) => println!("detached the console on {}, dualZoneCli attach comes back to it", path.display()),
+                Err(e) => println!("No console to detach on {}: {}", path.display(), e),
+            }
+            #[cfg(not(unix))]
+            println!("Sessions need Unix domain sockets, this platform has none");
+            return;
+        },
+        Some("--bench") => {
+            let mut options = output::bench::BenchOptions::default();
+            let mut rest = args.iter().skip(1);
+            while let Some(flag) = rest.next() {
+                let value = rest.next();
+                let parsed = match flag.as_str() {
+                    "--messages" => value.and_then(|value| value.parse().ok()).map(|messages| options.messages = messages),
+                    "--frames" => value.and_then(|value| value.parse().ok()).map(|frames| options.frames = frames),
+                    "--size" => value.and_then(|value| value.split_once('x')).and_then(|(width, height)| {
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    }).map(|(width, height)| { options.width = width; options.height = height; }),
+                    _ => None,
+                };
+                if parsed.is_none() {
+                    return println!("usage: dualZoneCli --bench [--messages N] [--frames N] [--size WxH]");
+                }
+            }
+            //a console without the config file, so runs on different setups compare
+            let console = Console::builder().status_bar(true).build();
+            let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().expect("a tokio runtime");
+            match runtime.block_on(output::bench::run(console, options)) {
+                Ok(report) => println!("{}", report),
+                Err(e) => {
+                    println!("bench failed: {}", e);
+                    std::process::exit(1);
+                },
+            }
+            return;
+        },
+        Some("tail") => {
+            let from_start = args.iter().any(|arg| arg == "--from-start");
+            let files: Vec<&String> = args.iter().skip(1).filter(|arg| !arg.starts_with("--")).collect();
+            if files.is_empty() {
+                return println!("usage: dualZoneCli tail [--from-start] <file>...");
+            }
+            broker.follow_files(Some(broker::tail::TailSource::new(files).read_from_start(from_start)));
+        },
+        Some("--record") => match args.get(1) {
+            Some(path) => broker.record_session(Some(PathBuf::from(path))),
+            None => return println!("usage: dualZoneCli --record <file>"),
+        },
+        _ => {},
+    }
     run(broker);
 }
 
@@ -35,8 +152,19 @@ pub fn main() {
  * Simple init func to demonstrate how to implement this cli
  */
 pub fn init() -> Console {
-    let main_broker = Console::default();
-    main_broker
+    let builder = Console::builder().status_bar(true);
+    //DUALZONE_CONFIG points at a config file, otherwise dualzone.toml is used if there is one
+    let path = std::env::var("DUALZONE_CONFIG").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from(DEFAULT_CONFIG_FILE));
+    if !path.exists() {
+        return builder.build();
+    }
+    match builder.config_file(&path) {
+        Ok(builder) => builder.build(),
+        Err(e) => {
+            println!("Ignoring {}: {}", path.display(), e);
+            Console::builder().status_bar(true).build()
+        }
+    }
 }
 
 /**
@@ -45,89 +173,454 @@ pub fn init() -> Console {
 #[tokio::main]
 async fn run(Broker: Console) -> Result<()> {
 
-    let mut main_inbox = Broker.rx;
-    let mut user_input = Broker.tx;
+    let mut main_inbox = Broker;
+    let user_input = main_inbox.tx.clone();
     let mut stop_requested = false;
+    //before anything is drawn, on Windows this also turns on virtual terminal processing
+    let strategy = output::terminal::init();
+    //the render actor owns stdout, every task draws through a writer of its own
+    let mut stdout = TerminalWriter::spawn();
+    //a terminal that can only append has no screen to switch to, an input only console draws no zones to put on one
+    let alternate_screen = main_inbox.alternate_screen && strategy.moves_cursor() && !main_inbox.input_only;
+    if alternate_screen {
+        execute!(stdout, terminal::EnterAlternateScreen)?;
+    }
+    let input_stdout = stdout.clone();
+    let output_stdout = stdout.clone();
+    let mut detach_stdout = stdout.clone();
+    let input_requests = main_inbox.take_input_requests().expect("the input zone is started once");
 
-    let input_handle = tokio::spawn(async move {
-        let mut reader = BufReader::new(tokio::io::stdin());
-        loop {
-            if stop_requested {
-                break;
-            }
+    //scrape endpoint for long running admin consoles, eg. DUALZONE_METRICS_ADDR=127.0.0.1:9464
+    #[cfg(feature = "prometheus")]
+    if let Ok(addr) = std::env::var("DUALZONE_METRICS_ADDR") {
+        match addr.parse() {
+            Ok(addr) => {
+                let metrics = main_inbox.watch_metrics();
+                let channel = main_inbox.tx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = broker::prometheus::serve_metrics(addr, metrics, channel).await {
+                        println!("Metrics endpoint stopped: {}", e);
+                    }
+                });
+            },
+            Err(_) => println!("Invalid DUALZONE_METRICS_ADDR: {}", addr),
+        }
+    }
 
-            let mut input = String::new();
-            match reader.read_line(&mut input).await {
-            //user ends input
-                Ok(0) | Ok(_) if input.trim().eq_ignore_ascii_case("exit") || input.trim().eq_ignore_ascii_case("quit") => {
-                    user_input.send("USER_BREAK_$0uU".to_string());
-                    stop_requested = true;
-                    break;
-                },
-            //message ok
-                Ok(_) => {
-                    user_input.send(input.trim().to_string()).await.unwrap();
-                },
-            //User pressed Ctrl+C
-                Err(ref e) if e.kind() == tokio::io::ErrorKind::Interrupted => {
-                    // Ctrl+C was pressed
-                    stop_requested = true;
-                    break;
-                },
-            //other errors
-                Err(e) => {
-                    println!("Error reading from stdin: {}", e);
-                    break;
-                }
+    //what is published on the bridged topics comes in as messages of their senders
+    if let Some(bridge) = main_inbox.mqtt.clone() {
+        let senders = bridge.senders().into_iter().map(|name| (name.clone(), main_inbox.new_sender(name))).collect();
+        let status = main_inbox.new_sender("mqtt".to_string());
+        tokio::spawn(broker::mqtt::run(bridge, senders, status));
+    }
+
+    //the mirrored topics go to the chat channel, what is said there comes back as input
+    if let Some(relay) = main_inbox.relay.clone() {
+        let (lines, channel) = (main_inbox.rendered_lines(), main_inbox.tx.clone());
+        let status = main_inbox.new_sender("relay".to_string());
+        tokio::spawn(broker::relay::run(relay, lines, channel, status));
+    }
+
+    //every followed file is a sender of its own
+    if let Some(source) = main_inbox.tail.clone() {
+        let outputs = source.sender_names().into_iter().map(|name| main_inbox.new_sender(name)).collect();
+        tokio::spawn(broker::tail::run(source, outputs));
+    }
+
+    //the SubConsoles are shared with the consoles on the same NATS subject
+    if let Some(target) = main_inbox.nats.clone() {
+        let link = main_inbox.distribute_subconsoles(target.origin.clone());
+        let channel = main_inbox.tx.clone();
+        tokio::spawn(async move {
+            #[cfg(feature = "nats")]
+            let reason = match broker::nats::NatsBridge::connect(&target.url, &target.subject).await {
+                Ok(bridge) => return broker::distribute::run(bridge, link).await,
+                Err(e) => e.to_string(),
+            };
+            #[cfg(not(feature = "nats"))]
+            let reason = "built without the nats feature".to_string();
+            let note = format!("[bridge] not distributing over {}: {}", target.url, reason);
+            let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+        });
+    }
+
+    //the console outlives its terminal, clients attach to it over the session socket or TLS, gRPC and HTTP stream from it too
+    let session = (main_inbox.session.is_some() || main_inbox.session_tls.is_some() || main_inbox.grpc.is_some() || main_inbox.http.is_some())
+        .then(|| broker::session::SessionHub::new(broker::session::SESSION_BACKLOG));
+    #[cfg(unix)]
+    if let (Some(path), Some(hub)) = (main_inbox.session.clone(), session.clone()) {
+        let channel = main_inbox.tx.clone();
+        let auth = main_inbox.session_auth.clone();
+        tokio::spawn(async move {
+            if let Err(e) = broker::session::serve(path.clone(), hub, channel.clone(), auth).await {
+                let note = format!("[session] not serving {}: {}", path.display(), e);
+                let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
             }
-        }
-    });
+        });
+    }
+    if let (Some(listen), Some(hub)) = (main_inbox.session_tls.clone(), session.clone()) {
+        let channel = main_inbox.tx.clone();
+        let auth = main_inbox.session_auth.clone();
+        tokio::spawn(async move {
+            #[cfg(feature = "tls")]
+            let served = broker::tls::serve(listen.clone(), hub, channel.clone(), auth).await;
+            #[cfg(not(feature = "tls"))]
+            let served: std::io::Result<()> = Err(std::io::Error::new(ErrorKind::Unsupported, "built without the tls feature"));
+            if let Err(e) = served {
+                let note = format!("[session] not serving tls://{}: {}", listen.addr, e);
+                let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+            }
+        });
+    }
 
+    //other services send, follow the output and ban senders over gRPC
+    if let (Some(listen), Some(hub)) = (main_inbox.grpc.clone(), session.clone()) {
+        let channel = main_inbox.tx.clone();
+        tokio::spawn(async move {
+            #[cfg(feature = "grpc")]
+            let reason = match broker::grpc::serve(listen.clone(), hub, channel.clone()).await {
+                Ok(()) => return,
+                Err(e) => e.to_string(),
+            };
+            #[cfg(not(feature = "grpc"))]
+            let reason = "built without the grpc feature".to_string();
+            let note = format!("[grpc] not serving {}: {}", listen.addr, reason);
+            let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+        });
+    }
 
-    let output_handle = tokio::spawn(async move {
-        let mut stdout = Arc::new(Mutex::new(stdout()));
+    //and web dashboards over HTTP
+    if let (Some(listen), Some(hub)) = (main_inbox.http.clone(), session.clone()) {
+        let channel = main_inbox.tx.clone();
+        tokio::spawn(async move {
+            #[cfg(feature = "http")]
+            let reason = match broker::http::serve(listen.clone(), hub, channel.clone()).await {
+                Ok(()) => return,
+                Err(e) => e.to_string(),
+            };
+            #[cfg(not(feature = "http"))]
+            let reason = "built without the http feature".to_string();
+            let note = format!("[http] not serving {}: {}", listen.addr, reason);
+            let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+        });
+    }
 
-        loop {
-            let input = main_inbox.recv().await;
+    //rendered lines go on to the syslog daemon, what is sent to the syslog port is shown
+    if let Some(target) = main_inbox.syslog_forward.clone() {
+        tokio::spawn(broker::syslog::forward(target, main_inbox.rendered_lines(), main_inbox.tx.clone()));
+    }
+    if let Some(addr) = main_inbox.syslog_listen {
+        let channel = main_inbox.tx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = broker::syslog::listen(addr, channel.clone()).await {
+                let note = format!("[syslog] not listening on {}: {}", addr, e);
+                let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+            }
+        });
+    }
+
+    //under systemd the rendered lines go to the journal too
+    if main_inbox.journal {
+        let lines = main_inbox.rendered_lines();
+        let channel = main_inbox.tx.clone();
+        #[cfg(all(feature = "systemd", target_os = "linux"))]
+        tokio::spawn(broker::journald::forward(lines, channel));
+        #[cfg(not(all(feature = "systemd", target_os = "linux")))]
+        tokio::spawn(async move {
+            let note = "[journal] not forwarding, built without the systemd feature or not on Linux".to_string();
+            let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+        });
+    }
+
+    //the lines the webhooks match are posted to them
+    for sink in main_inbox.webhooks.clone() {
+        let channel = main_inbox.tx.clone();
+        #[cfg(feature = "webhook")]
+        tokio::spawn(broker::webhook::forward(sink, main_inbox.rendered_lines(), channel));
+        #[cfg(not(feature = "webhook"))]
+        tokio::spawn(async move {
+            let note = format!("[webhook] not posting to {}: built without the webhook feature", sink.url);
+            let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+        });
+    }
 
-            let input = match input {
-                Some(input) => input,
-                None => { "USER_BREAK_$0uU".to_string()}
+    //spans and counters for the observability stack
+    if let Some(endpoint) = main_inbox.otel.clone() {
+        let (metrics, channel, lines) = (main_inbox.watch_metrics(), main_inbox.tx.clone(), main_inbox.rendered_lines());
+        tokio::spawn(async move {
+            #[cfg(feature = "otel")]
+            let reason = match broker::otel::export(endpoint.clone(), metrics, channel.clone(), lines).await {
+                Ok(()) => return,
+                Err(e) => e.to_string(),
             };
+            #[cfg(not(feature = "otel"))]
+            let reason = "built without the otel feature".to_string();
+            let note = format!("[otel] not exporting to {}: {}", endpoint, reason);
+            let _ = channel.send(ConsoleEvent::Message(Message::from_console(note))).await;
+        });
+    }
+
+    //the piped lines are a sender of their own, the keys come from the terminal if there is one to type on
+    if main_inbox.ingest {
+        if !(user_input::input::has_terminal() && strategy.moves_cursor()) {
+            main_inbox.enable_display_only(true);
+        }
+        let output = main_inbox.new_sender(broker::ingest::INGEST_SENDER.to_string());
+        tokio::spawn(broker::ingest::run(output));
+    }
+
+    //display only there is no input task, Ctrl+C is the way out and the zones take the input row
+    let display_only = main_inbox.display_only;
+    let input_handle = match display_only {
+        true => {
+            output::layout::remove_input_zone();
+            let _ = execute!(stdout, cursor::Hide);
+            let shutdown = main_inbox.tx.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    let _ = shutdown.send(ConsoleEvent::Shutdown).await;
+                }
+            });
+            None
+        },
+        false => {
+            let input_options = main_inbox.input_options();
+            Some(tokio::spawn(user_input::input::run(user_input, input_stdout, main_inbox.prompt_source(), input_requests, input_options)))
+        },
+    };
 
-            if input.clone().trim().eq_ignore_ascii_case("USER_BREAK_$0uU") {
-                stop_requested = true;
-                break;
+    let output_handle = tokio::spawn(async move {
+        if main_inbox.input_only {
+            return main_inbox.relay_output().await;
+        }
+        if !strategy.moves_cursor() {
+            return append_output(main_inbox, output_stdout, session).await;
+        }
+        let mut renderer = Renderer::new(output_stdout, main_inbox.status_bar).with_theme(main_inbox.screen_theme());
+        let mut zones = main_inbox.zone_set();
+        let mut recorder = None;
+        if let Some(path) = main_inbox.recording.clone() {
+            let layout = renderer.layout();
+            match Recorder::create(&path, layout.width, layout.height) {
+                Ok(created) => recorder = Some(created),
+                Err(e) => zones.push(Message::from_console(format!("[console] could not record to {}: {}", path.display(), e))),
             }
+        }
+        if strategy != RenderStrategy::Ansi {
+            zones.push(Message::from_console(format!("[console] rendering with the {} strategy for this terminal", strategy)));
+        }
+        let mut status_tick = tokio::time::interval(Duration::from_secs(1));
+        let mut animation_tick = tokio::time::interval(Duration::from_millis(output::widgets::SPINNER_FRAME_MS));
+        renderer.draw_initial(&if display_only { String::new() } else { main_inbox.prompt_source().render() }).unwrap();
 
-            queue!(
-                *stdout.lock().unwrap(),
-                cursor::SavePosition,
-                cursor::MoveToPreviousLine(1),
-                terminal::Clear(terminal::ClearType::CurrentLine),
-                style::Print(input),
-                cursor::RestorePosition,
-                cursor::MoveToNextLine(1),
-                style::Print(PROMPT),
-            )
-            .unwrap();
-            stdout.lock().unwrap().flush().unwrap();
-
-            // Wait for 5 seconds and clear the output
-            tokio::time::sleep(Duration::from_secs(2)).await;
-            queue!(
-                *stdout.lock().unwrap(),
-                terminal::Clear(terminal::ClearType::All),
-                cursor::MoveToPreviousLine(1),
-                style::Print(PROMPT),
-            )
-            .unwrap();
-            stdout.lock().unwrap().flush().unwrap();
+        loop {
+            let next_expiry = zones.next_expiry();
+            //accessibility mode keeps spinners still
+            let animated = zones.is_animated() && !main_inbox.accessible;
+            tokio::select! {
+                //the broker step applies the phonebook, rate limits and commands
+                output = main_inbox.next_output() => {
+                    let render_started = tokio::time::Instant::now();
+                    //while paused only the user's own actions redraw the zones
+                    let mut redraw = !zones.is_paused();
+                    if let (Some(active), Some(output)) = (recorder.as_mut(), output.as_ref()) {
+                        if let Err(e) = active.record(output) {
+                            recorder = None;
+                            zones.push(Message::from_console(format!("[console] recording stopped: {}", e)));
+                        }
+                    }
+                    if let Some(hub) = session.as_ref() {
+                        match output.as_ref() {
+                            Some(output) => hub.publish(output),
+                            None => hub.close("the console shut down"),
+                        }
+                    }
+                    match output {
+                        Some(Output::Line(message)) => zones.push(message),
+                        Some(Output::Dismiss(id)) => {
+                            zones.dismiss(id);
+                        },
+                        Some(Output::Retract { sender, id }) => {
+                            zones.retract(&sender, id);
+                        },
+                        Some(Output::Notify { alert, message }) => {
+                            redraw = false;
+                            if alert.bell {
+                                let _ = renderer.bell();
+                            }
+                            //until the status bar's next tick
+                            if alert.flash {
+                                let layout = renderer.layout();
+                                let _ = renderer.flash_status(&StatusBar::line(&main_inbox, &zones, layout.width));
+                            }
+                            if alert.desktop {
+                                let summary = format!("{} ({:?})", main_inbox.display_name(&message.sender), message.priority);
+                                let body = clipboard::plain(&message);
+                                //the notification daemon is a blocking D-Bus call away
+                                tokio::task::spawn_blocking(move || notify::desktop(&summary, &body));
+                            }
+                        },
+                        Some(Output::Search(command)) => {
+                            zones.search(command);
+                            redraw = true;
+                            let layout = renderer.layout();
+                            renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                        },
+                        Some(Output::Redraw) => {
+                            //asked for when the screen may have been drawn over, eg. a resize, so every cell is written
+                            renderer.invalidate();
+                            redraw = true;
+                            let layout = renderer.layout();
+                            renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                        },
+                        Some(Output::Scroll(command)) => {
+                            zones.scroll(command);
+                            redraw = true;
+                            let layout = renderer.layout();
+                            renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                        },
+                        Some(Output::Focus(focus)) => {
+                            zones.set_focus(focus);
+                            redraw = true;
+                            let layout = renderer.layout();
+                            renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                        },
+                        Some(Output::Select(command)) => {
+                            zones.select(command);
+                            redraw = true;
+                        },
+                        Some(Output::Copy(target)) => {
+                            let text = match target {
+                                CopyTarget::Selection => renderer.selection_text(&zones).or_else(|| zones.copy_text(&target)),
+                                CopyTarget::Last(_) => zones.copy_text(&target),
+                            };
+                            let note = match text {
+                                Some(text) => match renderer.copy(&text) {
+                                    Ok(CopiedTo::System) => format!("[console] copied {} characters", text.chars().count()),
+                                    Ok(CopiedTo::Terminal) => format!("[console] copied {} characters through the terminal", text.chars().count()),
+                                    Err(e) => format!("[console] copy failed: {}", e),
+                                },
+                                None => "[console] nothing to copy, select with the mouse or a search, or use /copy N".to_string(),
+                            };
+                            zones.push(Message::from_console(note));
+                            redraw = true;
+                        },
+                        Some(Output::Export { path, format }) => {
+                            let note = match export::write(&path, &format, &zones, &|id| main_inbox.display_name(id)) {
+                                Ok(count) => format!("[console] exported {} messages to {} ({})", count, path.display(), format),
+                                Err(e) => format!("[console] export to {} failed: {}", path.display(), e),
+                            };
+                            zones.push(Message::from_console(note));
+                            redraw = true;
+                        },
+                        Some(Output::Highlights(highlights)) => {
+                            zones.highlights = highlights;
+                            redraw = true;
+                        },
+                        Some(Output::Window { spec, tab }) => {
+                            //a tab is off screen, a zone changes the layout
+                            redraw = zones.add_window(&spec, tab) && !tab;
+                            if redraw {
+                                renderer.invalidate();
+                            }
+                        },
+                        Some(Output::Tab(command)) => {
+                            if let (false, TabCommand::Show(Some(name))) = (zones.tab(command.clone()), &command) {
+                                zones.push(Message::from_console(format!("[console] no tab named {}", name)));
+                            }
+                            renderer.invalidate();
+                            redraw = true;
+                        },
+                        Some(Output::Split(panes)) => {
+                            zones.split(panes);
+                            renderer.invalidate();
+                            redraw = true;
+                            let layout = renderer.layout();
+                            renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                        },
+                        Some(Output::View(name)) => {
+                            zones.view(name.as_deref());
+                            renderer.invalidate();
+                            redraw = true;
+                            let layout = renderer.layout();
+                            renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                        },
+                        Some(Output::Pause(paused)) => {
+                            zones.set_paused(paused);
+                            redraw = !paused;
+                            let layout = renderer.layout();
+                            renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                        },
+                        Some(Output::Detach) => {
+                            //the zones keep filling in for the clients, nothing more is drawn here
+                            redraw = false;
+                            if alternate_screen {
+                                let _ = execute!(detach_stdout, terminal::LeaveAlternateScreen);
+                            }
+                            let _ = execute!(detach_stdout, cursor::Show, style::Print("\r\n[session] detached, dualZoneCli attach comes back to this console\r\n"));
+                            let _ = detach_stdout.release();
+                        },
+                        //a /msg is drawn here only when it is for the terminal, the client it is for gets it alone
+                        Some(Output::Private { to, message }) if to == broker::message::USER_SENDER => zones.push(message),
+                        Some(Output::Private { to, message }) => {
+                            redraw = false;
+                            if let Some(hub) = session.as_ref() {
+                                hub.publish_private(&to, message);
+                            }
+                        },
+                        None => {
+                            stop_requested = true;
+                            break;
+                        }
+                    };
+                    if redraw {
+                        renderer.draw_zones(&zones).unwrap();
+                        main_inbox.record_render(render_started.elapsed());
+                    }
+                },
+                //clear messages once their time is up
+                _ = tokio::time::sleep_until(next_expiry.unwrap_or_else(tokio::time::Instant::now)), if next_expiry.is_some() => {
+                    if zones.expire(tokio::time::Instant::now()) && !zones.is_paused() {
+                        renderer.draw_zones(&zones).unwrap();
+                    }
+                },
+                //spinners move on their own while they run
+                _ = animation_tick.tick(), if animated && !zones.is_paused() => {
+                    renderer.draw_zones(&zones).unwrap();
+                },
+                //the status bar refreshes on its own timer, independent of message traffic
+                _ = status_tick.tick() => {
+                    let layout = renderer.layout();
+                    renderer.draw_status(&StatusBar::line(&main_inbox, &zones, layout.width)).unwrap();
+                },
+            }
         }
     });
 
+    //a Type=notify unit counts as started once the broker runs
+    #[cfg(all(feature = "systemd", target_os = "linux"))]
+    let _ = broker::journald::notify("READY=1\nSTATUS=console broker running");
+
     // Wait for both tasks to complete and handle any errors
-    match tokio::try_join!(input_handle, output_handle) {
+    let input_done = async move {
+        match input_handle {
+            Some(handle) => handle.await,
+            None => Ok(()),
+        }
+    };
+    let joined = tokio::try_join!(input_done, output_handle);
+    #[cfg(all(feature = "systemd", target_os = "linux"))]
+    let _ = broker::journald::notify("STOPPING=1");
+    if display_only {
+        let _ = execute!(stdout, cursor::Show);
+    }
+    //the shell's screen comes back as it was, before any error is printed so it stays readable
+    if alternate_screen {
+        let _ = execute!(stdout, terminal::LeaveAlternateScreen);
+    }
+    //whatever the tasks drew last is still queued, the process must not end before it is out
+    let _ = stdout.sync();
+    match joined {
         Ok(_) => {Ok(())},
         Err(e) => {
             // Handle errors from either task
@@ -137,4 +630,288 @@ async fn run(Broker: Console) -> Result<()> {
     };
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/**
+ * The output task on a terminal that can only append, see RenderStrategy::Append.
+ * Every line is printed as it comes with the prompt again after it, there are no zones to scroll, search or expire,
+ * so the outputs that work on them are dropped. The bell still rings and the session is still recorded.
+ * A display only console prints no prompt, a session (see broker::session) still gets every output.
+ */
+async fn append_output(mut main_inbox: Console, stdout: TerminalWriter, session: Option<broker::session::SessionHub>) {
+    let mut detach_stdout = stdout.clone();
+    let mut renderer = Renderer::new(stdout, false).with_theme(main_inbox.screen_theme());
+    let source = main_inbox.prompt_source();
+    let display_only = main_inbox.display_only;
+    let prompt = move || if display_only { String::new() } else { source.render() };
+    let mut recorder = None;
+    renderer.draw_initial(&prompt()).unwrap();
+    if let Some(path) = main_inbox.recording.clone() {
+        let layout = renderer.layout();
+        match Recorder::create(&path, layout.width, layout.height) {
+            Ok(created) => recorder = Some(created),
+            Err(e) => renderer.draw_appended(&Message::from_console(format!("[console] could not record to {}: {}", path.display(), e)), &prompt()).unwrap(),
+        }
+    }
+    renderer.draw_appended(&Message::from_console("[console] rendering with the append strategy for this terminal".to_string()), &prompt()).unwrap();
+    loop {
+        let output = main_inbox.next_output().await;
+        if let (Some(active), Some(output)) = (recorder.as_mut(), output.as_ref()) {
+            if let Err(e) = active.record(output) {
+                recorder = None;
+                renderer.draw_appended(&Message::from_console(format!("[console] recording stopped: {}", e)), &prompt()).unwrap();
+            }
+        }
+        if let Some(hub) = session.as_ref() {
+            match output.as_ref() {
+                Some(output) => hub.publish(output),
+                None => hub.close("the console shut down"),
+            }
+        }
+        match output {
+            Some(Output::Line(message)) => renderer.draw_appended(&message, &prompt()).unwrap(),
+            Some(Output::Private { to, message }) if to == broker::message::USER_SENDER => renderer.draw_appended(&message, &prompt()).unwrap(),
+            Some(Output::Private { to, message }) => {
+                if let Some(hub) = session.as_ref() {
+                    hub.publish_private(&to, message);
+                }
+            },
+            Some(Output::Notify { alert, .. }) if alert.bell => {
+                let _ = renderer.bell();
+            },
+            Some(Output::Detach) => {
+                let _ = execute!(detach_stdout, style::Print("\r\n[session] detached, dualZoneCli attach comes back to this console\r\n"));
+                let _ = detach_stdout.release();
+            },
+            Some(_) => {},
+            None => break,
+        }
+    }
+}
+
+/**
+ * Show a console serving its session (see broker::session) in this terminal until Ctrl+C, exit or quit, the console keeps running.
+ * The scrollback comes first, then everything as it happens. Lines typed here go to the console under the client's name,
+ * the prompt shows it. Scrolling, focus, search, selection and /filter only change what this terminal shows.
+ */
+#[tokio::main]
+async fn attach(mut console: Console, target: broker::session::SessionTarget, name: String) -> Result<()> {
+    use broker::session::{ClientFrame, ServerFrame};
+    use user_input::commands::Command;
+    use user_input::prompt::{PromptSpec, DEFAULT_PROMPT};
+    use tokio::io::AsyncWriteExt;
+
+    let (reader, mut writer) = match broker::session::connect(&target).await {
+        Ok(halves) => halves,
+        Err(e) => {
+            println!("No console to attach to on {}: {}", target, e);
+            return Ok(());
+        },
+    };
+    let mut frames = BufReader::new(reader).lines();
+    //asked for before anything is drawn, and only by a session that wants it
+    let asking = name.clone();
+    let secret = || broker::session::client_secret().or_else(|| ask_secret(&format!("password for {} on {}: ", asking, target)));
+    if let Err(e) = broker::session::client_handshake(&mut frames, &mut writer, &name, secret).await {
+        println!("Could not attach to {}: {}", target, e);
+        return Ok(());
+    }
+    writer.write_all(broker::session::to_line(&ClientFrame::Hello(name.clone()))?.as_bytes()).await?;
+    console.set_prompt(PromptSpec::new().text(format!("[{}] ", name)).bold().text(DEFAULT_PROMPT.to_string()));
+    let mut filter = broker::filter::FilterRules::default();
+
+    output::terminal::init();
+    let mut stdout = TerminalWriter::spawn();
+    if console.alternate_screen {
+        execute!(stdout, terminal::EnterAlternateScreen)?;
+    }
+    let mut renderer = Renderer::new(stdout.clone(), true).with_theme(console.screen_theme());
+    let mut zones = console.zone_set();
+    renderer.draw_initial(&console.prompt_source().render())?;
+
+    //the input zone runs as it does in the console, its events come here instead
+    let (input_tx, mut input) = broker::mailbox::channel(100);
+    let requests = console.take_input_requests().expect("the input zone is started once");
+    let input_handle = tokio::spawn(user_input::input::run(input_tx, stdout.clone(), console.prompt_source(), requests, console.input_options()));
+
+    let mut status_tick = tokio::time::interval(Duration::from_secs(1));
+    let reason = loop {
+        let next_expiry = zones.next_expiry();
+        tokio::select! {
+            frame = frames.next_line() => {
+                let line = match frame {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break "the console closed the session".to_string(),
+                    Err(e) => break format!("the session failed: {}", e),
+                };
+                match broker::session::parse_frame::<ServerFrame>(&line) {
+                    Ok(ServerFrame::Event(Recorded::Line(message))) if !broker::session::client_admits(&mut filter, &message) => continue,
+                    Ok(ServerFrame::Event(event)) => event.apply(&mut zones),
+                    Ok(ServerFrame::Bye(reason)) => break reason,
+                    Ok(ServerFrame::Welcome { .. }) | Ok(ServerFrame::Private { .. }) | Err(_) => continue,
+                }
+                if !zones.is_paused() {
+                    renderer.draw_zones(&zones)?;
+                }
+            },
+            event = input.recv() => {
+                match event {
+                    Some(ConsoleEvent::UserInput(line)) => {
+                        //the client's own filter, the console never sees it
+                        if let Some(Command::Filter(command)) = Command::parse(&line) {
+                            for reply in broker::session::run_client_filter(&mut filter, command) {
+                                zones.push(reply);
+                            }
+                            renderer.draw_zones(&zones)?;
+                            continue;
+                        }
+                        let sent = match broker::session::to_line(&ClientFrame::Input(line)) {
+                            Ok(frame) => writer.write_all(frame.as_bytes()).await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = sent {
+                            break format!("the session failed: {}", e);
+                        }
+                        continue;
+                    },
+                    Some(ConsoleEvent::Scroll(command)) => zones.scroll(command),
+                    Some(ConsoleEvent::Focus(focus)) => zones.set_focus(focus),
+                    Some(ConsoleEvent::Search(command)) => zones.search(command),
+                    Some(ConsoleEvent::Select(command)) => zones.select(command),
+                    Some(ConsoleEvent::Tab(command)) => { zones.tab(command); },
+                    Some(ConsoleEvent::Pause(paused)) => zones.set_paused(paused),
+                    Some(ConsoleEvent::Redraw) => renderer.invalidate(),
+                    Some(ConsoleEvent::Shutdown) | None => break "detached".to_string(),
+                    Some(_) => continue,
+                }
+                renderer.draw_zones(&zones)?;
+            },
+            _ = tokio::time::sleep_until(next_expiry.unwrap_or_else(tokio::time::Instant::now)), if next_expiry.is_some() => {
+                if zones.expire(tokio::time::Instant::now()) && !zones.is_paused() {
+                    renderer.draw_zones(&zones)?;
+                }
+            },
+            _ = status_tick.tick() => {
+                renderer.draw_status(&format!(" attached to {} | Ctrl+C detaches", target))?;
+            },
+        }
+    };
+    input_handle.abort();
+    let _ = terminal::disable_raw_mode();
+    if console.alternate_screen {
+        execute!(stdout, terminal::LeaveAlternateScreen)?;
+    }
+    execute!(stdout, cursor::Show, style::Print(format!("\r\n[session] {}\r\n", reason)))?;
+    stdout.sync()?;
+    Ok(())
+}
+
+/**
+ * Read a secret typed at the terminal without showing it, None when stdin is not a terminal or the user presses Esc or Ctrl+C
+ */
+fn ask_secret(prompt: &str) -> Option<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+    use crossterm::tty::IsTty;
+
+    if !std::io::stdin().is_tty() {
+        return None;
+    }
+    print!("{}", prompt);
+    let _ = std::io::stdout().flush();
+    terminal::enable_raw_mode().ok()?;
+    let mut secret = String::new();
+    let typed = loop {
+        match event::read() {
+            Ok(Event::Key(key)) => match key.code {
+                KeyCode::Enter => break Some(secret),
+                KeyCode::Esc => break None,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break None,
+                KeyCode::Backspace => { secret.pop(); },
+                KeyCode::Char(c) => secret.push(c),
+                _ => {},
+            },
+            Ok(_) => {},
+            Err(_) => break None,
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    println!();
+    typed
+}
+
+/**
+ * Play a recorded session back in the output zones, with the zones and expiry of the current config.
+ * The status bar shows how far along the replay is, q, Esc or Ctrl+C stop it and the last frame stays up until then.
+ */
+#[tokio::main]
+async fn replay(console: Console, path: PathBuf, speed: f64) -> Result<()> {
+    let recording = match Recording::load(&path) {
+        Ok(recording) => recording,
+        Err(e) => {
+            println!("Could not read {}: {}", path.display(), e);
+            return Ok(());
+        },
+    };
+    let total = recording.duration(speed);
+    output::terminal::init();
+    let mut stdout = TerminalWriter::spawn();
+    if console.alternate_screen {
+        execute!(stdout, terminal::EnterAlternateScreen)?;
+    }
+    let mut renderer = Renderer::new(stdout.clone(), true);
+    let mut zones = console.zone_set();
+    terminal::enable_raw_mode()?;
+    renderer.draw_initial(&format!("replay of {} at {}x, q quits", path.display(), speed))?;
+
+    //the key reader blocks, so it gets a thread and only says when to stop
+    let (quit_tx, mut quit) = tokio::sync::mpsc::channel::<()>(1);
+    std::thread::spawn(move || loop {
+        match crossterm::event::read() {
+            Ok(crossterm::event::Event::Key(key)) => {
+                let ctrl_c = key.code == crossterm::event::KeyCode::Char('c') && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL);
+                if ctrl_c || matches!(key.code, crossterm::event::KeyCode::Char('q') | crossterm::event::KeyCode::Esc) {
+                    let _ = quit_tx.blocking_send(());
+                    break;
+                }
+            },
+            Ok(_) => {},
+            Err(_) => break,
+        }
+    });
+
+    let started = tokio::time::Instant::now();
+    let due = |at: u64| started + Duration::from_secs_f64(at as f64 / 1000.0 / speed);
+    let mut events = recording.events.into_iter().peekable();
+    let mut status_tick = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        let next = events.peek().map(|event| due(event.at));
+        let next_expiry = zones.next_expiry();
+        tokio::select! {
+            _ = quit.recv() => break,
+            _ = tokio::time::sleep_until(next.unwrap_or_else(tokio::time::Instant::now)), if next.is_some() => {
+                let now = tokio::time::Instant::now();
+                while let Some(event) = events.next_if(|event| due(event.at) <= now) {
+                    event.event.apply(&mut zones);
+                }
+                renderer.draw_zones(&zones)?;
+            },
+            _ = tokio::time::sleep_until(next_expiry.unwrap_or_else(tokio::time::Instant::now)), if next_expiry.is_some() => {
+                if zones.expire(tokio::time::Instant::now()) {
+                    renderer.draw_zones(&zones)?;
+                }
+            },
+            _ = status_tick.tick() => {
+                let elapsed = started.elapsed().min(total);
+                let state = if events.peek().is_some() { "playing" } else { "finished" };
+                let line = format!(" replay {} | {}s / {}s | {}x", state, elapsed.as_secs(), total.as_secs(), speed);
+                renderer.draw_status(&line)?;
+            },
+        }
+    }
+    terminal::disable_raw_mode()?;
+    if console.alternate_screen {
+        execute!(stdout, terminal::LeaveAlternateScreen)?;
+    }
+    stdout.sync()?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::message::Message;
+use crate::output::widgets::{Widget, WidgetState};
+
+//External
+use serde::{Serialize, Deserialize};
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+/**
+ * Shown in place of ESC when a sequence is re-encoded, so the sequence is visible but inert
+ */
+const ESC_PICTURE: char = '␛';
+
+/**
+ * What the broker does with ANSI escape sequences in a sender's messages
+ *  ~ Passthrough leaves them untouched, only for trusted senders since they can move the cursor anywhere.
+ *  ~ Strip removes every escape sequence and control character.
+ *  ~ Sanitize keeps color and style (SGR) sequences and re-encodes everything else as visible text, the default.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum AnsiPolicy {
+    Passthrough,
+    Strip,
+    #[default]
+    Sanitize,
+}
+
+/**
+ * Length in bytes of the escape sequence text starts with, None if it doesn't start with ESC.
+ *  ~ CSI is ESC [ parameters and a final byte in @..~
+ *  ~ OSC is ESC ] up to BEL or ESC \
+ *  ~ anything else is ESC and one more character
+ * An unterminated sequence runs to the end of the text.
+ */
+pub fn escape_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    match chars.next() {
+        Some((_, ESC)) => {},
+        _ => return None,
+    }
+    match chars.next() {
+        Some((_, '[')) => {
+            for (i, c) in chars {
+                if ('@'..='~').contains(&c) {
+                    return Some(i + c.len_utf8());
+                }
+            }
+            Some(text.len())
+        },
+        Some((_, ']')) => {
+            let mut previous = ' ';
+            for (i, c) in chars {
+                if c == BEL || (previous == ESC && c == '\\') {
+                    return Some(i + c.len_utf8());
+                }
+                previous = c;
+            }
+            Some(text.len())
+        },
+        Some((i, c)) => Some(i + c.len_utf8()),
+        None => Some(text.len()),
+    }
+}
+
+/**
+ * True for a color or style sequence, the only kind that can't move the cursor
+ */
+pub fn is_sgr(sequence: &str) -> bool {
+    sequence.starts_with("\u{1b}[") && sequence.ends_with('m')
+        && sequence[2..sequence.len() - 1].chars().all(|c| c.is_ascii_digit() || c == ';' || c == ':')
+}
+
+/**
+ * Apply a policy to one piece of text, newlines are kept and tabs become a space so the zone's column math holds
+ */
+pub fn apply(text: &str, policy: &AnsiPolicy) -> String {
+    if *policy == AnsiPolicy::Passthrough {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(len) = escape_len(rest) {
+            let sequence = &rest[..len];
+            if *policy == AnsiPolicy::Sanitize {
+                if is_sgr(sequence) {
+                    out.push_str(sequence);
+                } else {
+                    out.extend(sequence.chars().map(|c| if c == ESC { ESC_PICTURE } else if c.is_control() { ' ' } else { c }));
+                }
+            }
+            rest = &rest[len..];
+            continue;
+        }
+        match c {
+            '\n' => out.push('\n'),
+            '\t' => out.push(' '),
+            c if c.is_control() => {},
+            c => out.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/**
+ * Apply a policy to a message payload and every piece of text in its widget
+ */
+pub fn apply_to_message(message: &mut Message, policy: &AnsiPolicy) {
+    if *policy == AnsiPolicy::Passthrough {
+        return;
+    }
+    //plain text comes out the same, keep sharing it rather than copying it
+    if message.payload.chars().any(|c| c.is_control() && c != '\n') {
+        message.payload = apply(&message.payload, policy).into();
+    }
+    let clean = |text: &mut String| *text = apply(text, policy);
+    match message.widget.as_mut() {
+        Some(Widget::Progress(progress)) => {
+            clean(&mut progress.label);
+            if let WidgetState::Aborted(reason) = &mut progress.state {
+                clean(reason);
+            }
+        },
+        Some(Widget::Spinner(spinner)) => {
+            clean(&mut spinner.label);
+            if let Some(status) = spinner.status.as_mut() {
+                clean(status);
+            }
+            if let WidgetState::Aborted(reason) = &mut spinner.state {
+                clean(reason);
+            }
+        },
+        Some(Widget::Table(table)) => {
+            for column in table.columns.iter_mut() {
+                clean(&mut column.title);
+            }
+            for cell in table.rows.iter_mut().flatten() {
+                clean(cell);
+            }
+        },
+        None => {},
+    }
+}
@@ -0,0 +1,361 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+use crate::output::ansi::{self, AnsiPolicy};
+use crate::output::terminal::{self as capabilities, RenderStrategy};
+use crate::output::writer::TerminalWriter;
+
+//External
+use crossterm::{cursor, queue, style, terminal, Result};
+use crossterm::style::Attribute;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/**
+ * Everything the renderer does to a terminal, so it can draw somewhere else than the real one.
+ *  ~ print takes text that may contain color and style (SGR) sequences, as message payloads do.
+ *  ~ Calls are queued and only reach the screen on flush.
+ *  ~ TerminalWriter is the real terminal through crossterm and the render actor, TestBackend is an in-memory grid of cells.
+ *    TerminalWriter follows the terminal::RenderStrategy picked at startup, see there.
+ */
+pub trait Backend: Send {
+    fn size(&self) -> (u16, u16);
+    fn move_to(&mut self, column: u16, row: u16) -> Result<()>;
+    fn print(&mut self, text: &str) -> Result<()>;
+    fn set_attribute(&mut self, attribute: Attribute) -> Result<()>;
+    fn clear_all(&mut self) -> Result<()>;
+    fn clear_line(&mut self) -> Result<()>;
+    fn save_position(&mut self) -> Result<()>;
+    fn restore_position(&mut self) -> Result<()>;
+    fn show_cursor(&mut self, visible: bool) -> Result<()>;
+    /**
+     * Escape sequences that don't draw anything, eg. OSC 52 to set the clipboard
+     */
+    fn write_raw(&mut self, sequence: &str) -> Result<()>;
+    fn flush(&mut self) -> Result<()>;
+}
+
+//where save_position left the cursor when it isn't saved with DECSC, column in the high half
+static SAVED_CURSOR: AtomicU32 = AtomicU32::new(0);
+
+impl Backend for TerminalWriter {
+    fn size(&self) -> (u16, u16) {
+        terminal::size().unwrap_or((80, 24))
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> Result<()> {
+        if !capabilities::strategy().moves_cursor() {
+            return Ok(());
+        }
+        queue!(self, cursor::MoveTo(column, row))
+    }
+
+    fn print(&mut self, text: &str) -> Result<()> {
+        if capabilities::strategy().colors() {
+            queue!(self, style::Print(text))
+        } else {
+            queue!(self, style::Print(ansi::apply(text, &AnsiPolicy::Strip)))
+        }
+    }
+
+    fn set_attribute(&mut self, attribute: Attribute) -> Result<()> {
+        if !capabilities::strategy().colors() {
+            return Ok(());
+        }
+        queue!(self, style::SetAttribute(attribute))
+    }
+
+    fn clear_all(&mut self) -> Result<()> {
+        if !capabilities::strategy().moves_cursor() {
+            return Ok(());
+        }
+        queue!(self, terminal::Clear(terminal::ClearType::All))
+    }
+
+    fn clear_line(&mut self) -> Result<()> {
+        if !capabilities::strategy().moves_cursor() {
+            return Ok(());
+        }
+        queue!(self, terminal::Clear(terminal::ClearType::CurrentLine))
+    }
+
+    fn save_position(&mut self) -> Result<()> {
+        if !capabilities::strategy().moves_cursor() {
+            return Ok(());
+        }
+        if capabilities::strategy().saves_cursor() {
+            return queue!(self, cursor::SavePosition);
+        }
+        //the position is only right once everything queued before it is out
+        self.sync()?;
+        let (column, row) = cursor::position()?;
+        SAVED_CURSOR.store((column as u32) << 16 | row as u32, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn restore_position(&mut self) -> Result<()> {
+        if !capabilities::strategy().moves_cursor() {
+            return Ok(());
+        }
+        if capabilities::strategy().saves_cursor() {
+            return queue!(self, cursor::RestorePosition);
+        }
+        let saved = SAVED_CURSOR.load(Ordering::Relaxed);
+        queue!(self, cursor::MoveTo((saved >> 16) as u16, saved as u16))
+    }
+
+    fn show_cursor(&mut self, visible: bool) -> Result<()> {
+        if !capabilities::strategy().moves_cursor() {
+            return Ok(());
+        }
+        if visible {
+            queue!(self, cursor::Show)
+        } else {
+            queue!(self, cursor::Hide)
+        }
+    }
+
+    fn write_raw(&mut self, sequence: &str) -> Result<()> {
+        if capabilities::strategy() == RenderStrategy::Legacy {
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "the console doesn't take escape sequences"));
+        }
+        self.write_all(sequence.as_bytes())?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Write::flush(self)?;
+        Ok(())
+    }
+}
+
+/**
+ * The styles a TestBackend cell keeps, colors are dropped
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CellStyle {
+    pub bold: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+/**
+ * One cell of the grid. A wide character fills its first cell, the cell after it is left with an empty symbol
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: String,
+    pub style: CellStyle,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell { symbol: " ".to_string(), style: CellStyle::default() }
+    }
+}
+
+/**
+ * A virtual terminal that keeps what would be on screen in memory, for tests and for embedders that want a snapshot
+ * of the console without a terminal (see Renderer::new and Renderer::backend).
+ * Text past the right edge is cut off instead of wrapping, the renderer never relies on the terminal wrapping.
+ */
+#[derive(Debug, Clone)]
+pub struct TestBackend {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    cursor: (u16, u16),
+    saved: (u16, u16),
+    cursor_visible: bool,
+    style: CellStyle,
+    raw: Vec<String>,
+}
+
+impl TestBackend {
+    pub fn new(width: u16, height: u16) -> Self {
+        TestBackend {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+            cursor: (0, 0),
+            saved: (0, 0),
+            cursor_visible: true,
+            style: CellStyle::default(),
+            raw: Vec::new(),
+        }
+    }
+
+    /**
+     * Change the size like a terminal resize, the screen is cleared
+     */
+    pub fn resize(&mut self, width: u16, height: u16) {
+        *self = TestBackend { raw: std::mem::take(&mut self.raw), ..TestBackend::new(width, height) };
+    }
+
+    pub fn cell(&self, column: u16, row: u16) -> Option<&Cell> {
+        if column >= self.width || row >= self.height {
+            return None;
+        }
+        self.cells.get(row as usize * self.width as usize + column as usize)
+    }
+
+    /**
+     * The text of one row, trailing blanks removed
+     */
+    pub fn row_text(&self, row: u16) -> String {
+        let text: String = (0..self.width).filter_map(|column| self.cell(column, row)).map(|cell| cell.symbol.as_str()).collect();
+        text.trim_end().to_string()
+    }
+
+    /**
+     * Every row as text, top to bottom
+     */
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.height).map(|row| self.row_text(row)).collect()
+    }
+
+    pub fn cursor(&self) -> (u16, u16) {
+        self.cursor
+    }
+
+    pub fn is_cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /**
+     * Sequences written with write_raw, oldest first
+     */
+    pub fn raw(&self) -> &[String] {
+        &self.raw
+    }
+
+    fn put(&mut self, symbol: &str) {
+        let (column, row) = self.cursor;
+        let width = UnicodeWidthStr::width(symbol);
+        if width == 0 || row >= self.height || column as usize + width > self.width as usize {
+            //past the edge the cursor still moves, like a terminal with autowrap off it just draws nothing
+            self.cursor.0 = self.cursor.0.saturating_add(width as u16);
+            return;
+        }
+        let index = row as usize * self.width as usize + column as usize;
+        self.cells[index] = Cell { symbol: symbol.to_string(), style: self.style };
+        for extra in 1..width {
+            self.cells[index + extra] = Cell { symbol: String::new(), style: self.style };
+        }
+        self.cursor.0 += width as u16;
+    }
+
+    /**
+     * Apply the parameters of an SGR sequence to the current style
+     */
+    fn apply_sgr(&mut self, parameters: &str) {
+        for code in parameters.split([';', ':']) {
+            match code.parse::<u16>().unwrap_or(0) {
+                0 => self.style = CellStyle::default(),
+                1 => self.style.bold = true,
+                4 => self.style.underline = true,
+                7 => self.style.reverse = true,
+                22 => self.style.bold = false,
+                24 => self.style.underline = false,
+                27 => self.style.reverse = false,
+                _ => {},
+            }
+        }
+    }
+}
+
+impl fmt::Display for TestBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lines().join("\n"))
+    }
+}
+
+impl Backend for TestBackend {
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> Result<()> {
+        self.cursor = (column.min(self.width.saturating_sub(1)), row.min(self.height.saturating_sub(1)));
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> Result<()> {
+        let mut rest = text;
+        while !rest.is_empty() {
+            if let Some(len) = ansi::escape_len(rest) {
+                let sequence = &rest[..len];
+                if ansi::is_sgr(sequence) {
+                    self.apply_sgr(&sequence[2..sequence.len() - 1]);
+                }
+                rest = &rest[len..];
+                continue;
+            }
+            let grapheme = rest.graphemes(true).next().unwrap_or(rest);
+            if grapheme == "\n" {
+                self.cursor = (0, (self.cursor.1 + 1).min(self.height.saturating_sub(1)));
+            } else {
+                self.put(grapheme);
+            }
+            rest = &rest[grapheme.len()..];
+        }
+        Ok(())
+    }
+
+    fn set_attribute(&mut self, attribute: Attribute) -> Result<()> {
+        match attribute {
+            Attribute::Reset => self.style = CellStyle::default(),
+            Attribute::Bold => self.style.bold = true,
+            Attribute::Underlined => self.style.underline = true,
+            Attribute::Reverse => self.style.reverse = true,
+            Attribute::NormalIntensity => self.style.bold = false,
+            Attribute::NoUnderline => self.style.underline = false,
+            Attribute::NoReverse => self.style.reverse = false,
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn clear_all(&mut self) -> Result<()> {
+        self.cells.iter_mut().for_each(|cell| *cell = Cell::default());
+        Ok(())
+    }
+
+    fn clear_line(&mut self) -> Result<()> {
+        let row = self.cursor.1 as usize;
+        let width = self.width as usize;
+        if let Some(cells) = self.cells.get_mut(row * width..(row + 1) * width) {
+            cells.iter_mut().for_each(|cell| *cell = Cell::default());
+        }
+        Ok(())
+    }
+
+    fn save_position(&mut self) -> Result<()> {
+        self.saved = self.cursor;
+        Ok(())
+    }
+
+    fn restore_position(&mut self) -> Result<()> {
+        self.cursor = self.saved;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self, visible: bool) -> Result<()> {
+        self.cursor_visible = visible;
+        Ok(())
+    }
+
+    fn write_raw(&mut self, sequence: &str) -> Result<()> {
+        self.raw.push(sequence.to_string());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use crate::broker::message::{Message, Output};
+use crate::output::backend::TestBackend;
+use crate::output::renderer::Renderer;
+use crate::output::zone::ZoneSet;
+use crate::user_input::structs::{Console, PriorityStatus};
+
+//External
+use tokio::time::{Duration, Instant};
+
+/**
+ * Why a benchmark could not finish
+ */
+#[derive(Debug)]
+pub enum BenchError {
+    Io(std::io::Error),
+    Console(String),
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BenchError::Io(e) => write!(f, "{}", e),
+            BenchError::Console(e) => write!(f, "console: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BenchError {}
+
+/**
+ * How much work a benchmark run does
+ *  ~ messages is how many go through the broker for the throughput figure.
+ *  ~ samples is how many messages are timed one at a time from send to rendered frame.
+ *  ~ frames is how many frames are drawn for the per-frame cost, once in full and once as a diff.
+ */
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub messages: usize,
+    pub samples: usize,
+    pub frames: usize,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions { messages: 10_000, samples: 1_000, frames: 500, width: 80, height: 24 }
+    }
+}
+
+/**
+ * Durations of one measurement, sorted so percentiles are a lookup
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    samples: Vec<Duration>,
+}
+
+impl Timings {
+    pub fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        Timings { samples }
+    }
+
+    /**
+     * The sample below which p percent of them fall, zero when there are none
+     */
+    pub fn percentile(&self, p: f64) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        let index = ((p / 100.0) * (self.samples.len() - 1) as f64).round() as usize;
+        self.samples[index.min(self.samples.len() - 1)]
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mean {:?}  p50 {:?}  p99 {:?}  max {:?}", self.mean(), self.percentile(50.0), self.percentile(99.0), self.percentile(100.0))
+    }
+}
+
+/**
+ * What a benchmark run measured
+ *  ~ throughput is messages per second from a sender through the broker to the output side, nothing drawn.
+ *  ~ latency is from send to the frame with that message in it being drawn.
+ *  ~ full_frame redraws every cell, diff_frame only what one new message changed, the usual case.
+ */
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub options: BenchOptions,
+    pub throughput: f64,
+    pub latency: Timings,
+    pub full_frame: Timings,
+    pub diff_frame: Timings,
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "screen      {}x{}", self.options.width, self.options.height)?;
+        writeln!(f, "throughput  {:.0} msgs/sec over {} messages", self.throughput, self.options.messages)?;
+        writeln!(f, "latency     {} ({} samples)", self.latency, self.options.samples)?;
+        writeln!(f, "full frame  {} ({} frames)", self.full_frame, self.options.frames)?;
+        write!(f, "diff frame  {} ({} frames)", self.diff_frame, self.options.frames)
+    }
+}
+
+/**
+ * Run every measurement against the console passed in, rendering into a TestBackend so no terminal is needed.
+ * It is a guard against regressions rather than an absolute figure, compare runs on the same machine and build profile.
+ */
+pub async fn run(mut console: Console, options: BenchOptions) -> Result<BenchReport, BenchError> {
+    let throughput = throughput(&mut console, options.messages).await?;
+    let mut zones = console.zone_set();
+    let mut renderer = Renderer::new(TestBackend::new(options.width, options.height), console.status_bar).with_theme(console.screen_theme());
+    let latency = latency(&mut console, &mut zones, &mut renderer, options.samples).await?;
+    let full_frame = frames(&mut zones, &mut renderer, options.frames, true)?;
+    let diff_frame = frames(&mut zones, &mut renderer, options.frames, false)?;
+    Ok(BenchReport { options, throughput, latency, full_frame, diff_frame })
+}
+
+/**
+ * Messages per second from one sender task to the output side of the broker
+ */
+async fn throughput(console: &mut Console, messages: usize) -> Result<f64, BenchError> {
+    let handle = console.new_sender("bench".to_string());
+    let start = Instant::now();
+    let sender = tokio::spawn(async move {
+        for index in 0..messages {
+            if handle.send(format!("throughput message {}", index)).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut received = 0;
+    while received < messages {
+        match console.next_output().await {
+            Some(Output::Line(_)) => received += 1,
+            Some(_) => {},
+            None => return Err(BenchError::Console("console shut down".to_string())),
+        }
+    }
+    let elapsed = start.elapsed();
+    sender.await.map_err(|e| BenchError::Console(e.to_string()))?;
+    Ok(messages as f64 / elapsed.as_secs_f64().max(f64::EPSILON))
+}
+
+/**
+ * Time single messages from send until the frame showing them is drawn
+ */
+async fn latency(console: &mut Console, zones: &mut ZoneSet, renderer: &mut Renderer<TestBackend>, samples: usize) -> Result<Timings, BenchError> {
+    let handle = console.new_sender("latency".to_string());
+    let mut timings = Vec::with_capacity(samples);
+    for index in 0..samples {
+        let start = Instant::now();
+        handle.send_with_priority(format!("latency message {}", index), PriorityStatus::Normal).await
+            .map_err(|e| BenchError::Console(e.to_string()))?;
+        loop {
+            match console.next_output().await {
+                Some(Output::Line(message)) => {
+                    zones.push(message);
+                    break;
+                },
+                Some(_) => {},
+                None => return Err(BenchError::Console("console shut down".to_string())),
+            }
+        }
+        renderer.draw_zones(zones).map_err(BenchError::Io)?;
+        timings.push(start.elapsed());
+    }
+    Ok(Timings::new(timings))
+}
+
+/**
+ * Time draw_zones with a new message pushed before every frame, full forgets the last frame so every cell is written
+ */
+fn frames(zones: &mut ZoneSet, renderer: &mut Renderer<TestBackend>, count: usize, full: bool) -> Result<Timings, BenchError> {
+    let mut timings = Vec::with_capacity(count);
+    for index in 0..count {
+        zones.push(Message::new("bench".to_string(), PriorityStatus::Normal, format!("frame {}", index)));
+        if full {
+            renderer.invalidate();
+        }
+        let start = Instant::now();
+        renderer.draw_zones(zones).map_err(BenchError::Io)?;
+        timings.push(start.elapsed());
+    }
+    Ok(Timings::new(timings))
+}
@@ -0,0 +1,97 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::io::Write;
+use crate::broker::message::Message;
+use crate::output::ansi::{self, AnsiPolicy};
+use crate::output::backend::Backend;
+
+/**
+ * Why copying to the clipboard failed
+ */
+#[derive(Debug)]
+pub enum ClipboardError {
+    Unavailable(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::Unavailable(e) => write!(f, "clipboard unavailable: {}", e),
+            ClipboardError::Io(e) => write!(f, "could not write to the terminal: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+/**
+ * What /copy puts on the clipboard
+ *  ~ Selection is the mouse selection, or the current search match when nothing is selected.
+ *  ~ Last is the given number of newest messages of the focused zone, one per line.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopyTarget {
+    Selection,
+    Last(usize),
+}
+
+/**
+ * How the text got to the clipboard
+ *  ~ System went through the system clipboard (the clipboard feature, arboard).
+ *  ~ Terminal asked the terminal to set it with OSC 52, which most terminals and tmux honour, also over ssh.
+ *    There is no answer, so whether it worked can't be known.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopiedTo {
+    System,
+    Terminal,
+}
+
+/**
+ * Put text on the clipboard, through the system clipboard when built with the clipboard feature
+ * and falling back to OSC 52 through the terminal when there is none
+ */
+pub fn copy<B: Backend>(stdout: &mut B, text: &str) -> Result<CopiedTo, ClipboardError> {
+    #[cfg(feature = "clipboard")]
+    {
+        let copied = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.to_string()));
+        if copied.is_ok() {
+            return Ok(CopiedTo::System);
+        }
+    }
+    stdout.write_raw(&format!("\u{1b}]52;c;{}\u{7}", base64(text.as_bytes()))).map_err(ClipboardError::Io)?;
+    stdout.flush().map_err(ClipboardError::Io)?;
+    Ok(CopiedTo::Terminal)
+}
+
+/**
+ * A message as it is copied, its payload without colors or other escape codes
+ */
+pub fn plain(message: &Message) -> String {
+    ansi::apply(&message.payload, &AnsiPolicy::Strip)
+}
+
+/**
+ * Standard base64 with padding, all OSC 52 needs
+ */
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,53 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use crate::user_input::structs::PriorityStatus;
+use crate::output::zone::DEFAULT_EXPIRY;
+
+//External
+use tokio::time::Duration;
+
+/**
+ * How long a message stays in the output zone, by priority. None means the message never expires.
+ *  ~ Urgent and Critical never expire, Exception and Warning stay for 10 seconds, everything else for DEFAULT_EXPIRY.
+ *  ~ A message that never expires only leaves the zone when it is pushed out by newer lines, it is still in the scrollback.
+ */
+#[derive(Debug, Clone)]
+pub struct ExpiryPolicy {
+    pub default: Option<Duration>,
+    pub overrides: HashMap<PriorityStatus, Option<Duration>>,
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        let mut overrides = HashMap::new();
+        overrides.insert(PriorityStatus::Urgent, None);
+        overrides.insert(PriorityStatus::Critical, None);
+        overrides.insert(PriorityStatus::Exception, Some(Duration::from_secs(10)));
+        overrides.insert(PriorityStatus::Warning, Some(Duration::from_secs(10)));
+        ExpiryPolicy { default: Some(DEFAULT_EXPIRY), overrides }
+    }
+}
+
+impl ExpiryPolicy {
+    /**
+     * The old behaviour, every message cleared after the same time
+     */
+    pub fn uniform(ttl: Option<Duration>) -> Self {
+        ExpiryPolicy { default: ttl, overrides: HashMap::new() }
+    }
+
+    pub fn set(&mut self, priority: PriorityStatus, ttl: Option<Duration>) {
+        self.overrides.insert(priority, ttl);
+    }
+
+    pub fn ttl(&self, priority: &PriorityStatus) -> Option<Duration> {
+        match self.overrides.get(priority) {
+            Some(ttl) => *ttl,
+            None => self.default,
+        }
+    }
+}
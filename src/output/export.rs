@@ -0,0 +1,282 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use crate::broker::message::Message;
+use crate::output::ansi::{self, AnsiPolicy};
+use crate::output::clipboard;
+use crate::output::zone::{OutputZone, ZoneSet};
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use serde::{Serialize, Deserialize};
+
+/**
+ * File formats the scrollback can be saved in
+ *  ~ Text is the plain text of every message, "sender: text", colors removed.
+ *  ~ JsonLines is one JSON object per message with its sender, priority, topic and zone, for tools.
+ *  ~ Html is a standalone page that keeps the colors and styles of the messages.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportFormat {
+    Text,
+    JsonLines,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<ExportFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" | "txt" | "log" => Some(ExportFormat::Text),
+            "json" | "jsonl" | "ndjson" | "json-lines" => Some(ExportFormat::JsonLines),
+            "html" | "htm" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    /**
+     * The format a file name asks for by its extension, plain text when it has none or an unknown one
+     */
+    pub fn from_path(path: &Path) -> ExportFormat {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(ExportFormat::parse)
+            .unwrap_or(ExportFormat::Text)
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Text => write!(f, "text"),
+            ExportFormat::JsonLines => write!(f, "json-lines"),
+            ExportFormat::Html => write!(f, "html"),
+        }
+    }
+}
+
+/**
+ * One message of a JSON-lines export
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedLine {
+    pub zone: String,
+    pub id: u64,
+    pub sender: String,
+    pub priority: PriorityStatus,
+    pub topic: Option<String>,
+    pub payload: String,
+}
+
+/**
+ * Write the scrollback of every zone, oldest message first and zone by zone, returns how many messages were written.
+ * name turns a sender id into the name shown for it.
+ */
+pub fn write(path: &Path, format: &ExportFormat, zones: &ZoneSet, name: &dyn Fn(&str) -> String) -> std::io::Result<usize> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut written = 0;
+    if *format == ExportFormat::Html {
+        writeln!(writer, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>dualZoneCli scrollback</title>")?;
+        writeln!(writer, "<style>body {{ background: #1e1e1e; color: #d4d4d4; }} pre {{ font-family: monospace; }} h2 {{ font-family: sans-serif; font-size: 1em; }}</style>\n</head>\n<body>")?;
+    }
+    let headers = zones.zones.len() > 1;
+    for zone in zones.zones.iter() {
+        match format {
+            ExportFormat::Text if headers => writeln!(writer, "== {} ==", zone.name)?,
+            ExportFormat::Html => {
+                writeln!(writer, "<h2>{}</h2>", escape_html(&zone.name))?;
+                write!(writer, "<pre>")?;
+            },
+            _ => {},
+        }
+        for message in scrollback(zone) {
+            let sender = name(&message.sender);
+            match format {
+                ExportFormat::Text => writeln!(writer, "{}: {}", sender, clipboard::plain(message))?,
+                ExportFormat::JsonLines => {
+                    let line = ExportedLine {
+                        zone: zone.name.clone(),
+                        id: message.id,
+                        sender,
+                        priority: message.priority.clone(),
+                        topic: message.topic.clone(),
+                        payload: clipboard::plain(message),
+                    };
+                    let line = serde_json::to_string(&line).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    writeln!(writer, "{}", line)?;
+                },
+                ExportFormat::Html => writeln!(writer, "<b>{}:</b> {}", escape_html(&sender), to_html(&message.payload))?,
+            }
+            written += 1;
+        }
+        if *format == ExportFormat::Html {
+            writeln!(writer, "</pre>")?;
+        }
+    }
+    if *format == ExportFormat::Html {
+        writeln!(writer, "</body>\n</html>")?;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+fn scrollback(zone: &OutputZone) -> impl Iterator<Item = &Message> {
+    zone.scrollback_range().filter_map(move |at| zone.scrollback_at(at))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/**
+ * The 16 basic terminal colors, normal then bright, close to what most terminals show by default
+ */
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+    "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+/**
+ * The current graphic rendition while converting, what the SGR sequences so far have switched on
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Rendition {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    foreground: Option<String>,
+    background: Option<String>,
+}
+
+impl Rendition {
+    fn style(&self) -> String {
+        let mut style = Vec::new();
+        if self.bold {
+            style.push("font-weight: bold".to_string());
+        }
+        if self.italic {
+            style.push("font-style: italic".to_string());
+        }
+        if self.underline {
+            style.push("text-decoration: underline".to_string());
+        }
+        if let Some(color) = &self.foreground {
+            style.push(format!("color: {}", color));
+        }
+        if let Some(color) = &self.background {
+            style.push(format!("background: {}", color));
+        }
+        style.join("; ")
+    }
+
+    /**
+     * Apply the parameters of one SGR sequence, unknown ones are ignored
+     */
+    fn apply(&mut self, parameters: &str) {
+        let codes: Vec<u16> = parameters.split([';', ':']).map(|code| code.parse().unwrap_or(0)).collect();
+        let mut codes = codes.into_iter();
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Rendition::default(),
+                1 => self.bold = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => self.bold = false,
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.foreground = Some(PALETTE[(code - 30) as usize].to_string()),
+                90..=97 => self.foreground = Some(PALETTE[(code - 90 + 8) as usize].to_string()),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(PALETTE[(code - 40) as usize].to_string()),
+                100..=107 => self.background = Some(PALETTE[(code - 100 + 8) as usize].to_string()),
+                49 => self.background = None,
+                38 | 48 => {
+                    let color = match codes.next() {
+                        Some(5) => codes.next().map(color_256),
+                        Some(2) => match (codes.next(), codes.next(), codes.next()) {
+                            (Some(r), Some(g), Some(b)) => Some(format!("#{:02x}{:02x}{:02x}", r.min(255), g.min(255), b.min(255))),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if code == 38 { self.foreground = color } else { self.background = color }
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+/**
+ * A color of the 256 color palette: the 16 basic ones, a 6x6x6 cube and a gray ramp
+ */
+fn color_256(index: u16) -> String {
+    match index {
+        0..=15 => PALETTE[index as usize].to_string(),
+        16..=231 => {
+            let level = |n: u16| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = index - 16;
+            format!("#{:02x}{:02x}{:02x}", level(n / 36), level(n / 6 % 6), level(n % 6))
+        },
+        _ => {
+            let gray = 8 + (index.min(255) - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+        },
+    }
+}
+
+/**
+ * A payload as HTML, its color and style sequences become spans and anything else escaped is dropped
+ */
+fn to_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rendition = Rendition::default();
+    let mut open = false;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(len) = ansi::escape_len(rest) {
+            let sequence = &rest[..len];
+            if ansi::is_sgr(sequence) {
+                rendition.apply(&sequence[2..sequence.len() - 1]);
+                if open {
+                    out.push_str("</span>");
+                    open = false;
+                }
+                let style = rendition.style();
+                if !style.is_empty() {
+                    out.push_str(&format!("<span style=\"{}\">", style));
+                    open = true;
+                }
+            }
+            rest = &rest[len..];
+            continue;
+        }
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            c if c.is_control() && c != '\n' => {},
+            c => out.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    if open {
+        out.push_str("</span>");
+    }
+    out
+}
@@ -0,0 +1,103 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+use crate::broker::pattern::glob_match;
+use crate::output::highlight::HighlightStyle;
+use crate::output::text;
+use crate::output::theme::ColorDepth;
+
+//External
+use serde_json::Value;
+
+/**
+ * The column the fields of a short line start at, so the fields of the lines under each other line up
+ */
+pub const FIELD_COLUMN: usize = 48;
+
+/**
+ * The style the fields with a matching key are drawn in, key is a glob (eg. "http.*"), see broker::pattern::glob_match
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldColor {
+    pub key: String,
+    pub style: HighlightStyle,
+}
+
+impl FieldColor {
+    pub fn new(key: &str, style: &str) -> Result<FieldColor, String> {
+        Ok(FieldColor { key: key.to_string(), style: HighlightStyle::parse(style)? })
+    }
+}
+
+impl fmt::Display for FieldColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.style, self.key)
+    }
+}
+
+/**
+ * A field value as it is drawn, a string as it is unless it needs quotes to read as one value
+ */
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(text) if text.is_empty() || text.contains(|c: char| c.is_whitespace() || c == '=' || c == '"') => format!("{:?}", text),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/**
+ * How the key=value fields of a message (see Message::fields) are drawn after its payload, changing it redraws the scrollback too.
+ *  ~ shown is whether they are drawn at all, /fields on|off. On unless changed.
+ *  ~ The fields are drawn by key in order, at FIELD_COLUMN when the payload is shorter. A payload of several lines has them after its last line.
+ *  ~ A field takes the style of the first color rule its key matches, the rest are drawn in the payload's own.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldView {
+    pub shown: bool,
+    pub colors: Vec<FieldColor>,
+}
+
+impl Default for FieldView {
+    fn default() -> Self {
+        FieldView { shown: true, colors: Vec::new() }
+    }
+}
+
+impl FieldView {
+    /**
+     * The payload with the fields after it, None when there is nothing to add
+     */
+    pub fn render(&self, payload: &str, fields: &HashMap<String, Value>) -> Option<String> {
+        if !self.shown || fields.is_empty() {
+            return None;
+        }
+        let mut keys: Vec<&String> = fields.keys().collect();
+        keys.sort();
+        let rendered: Vec<String> = keys.into_iter().map(|key| {
+            let field = format!("{}={}", key, format_value(&fields[key]));
+            match self.colors.iter().find(|color| glob_match(&color.key, key)) {
+                Some(color) => color.style.paint(&field),
+                None => field,
+            }
+        }).collect();
+        let used = text::width(payload.rsplit('\n').next().unwrap_or(payload));
+        let gap = match payload.contains('\n') {
+            true => 2,
+            false => FIELD_COLUMN.saturating_sub(used).max(2),
+        };
+        Some(format!("{}{}{}", payload, " ".repeat(gap), rendered.join(" ")))
+    }
+
+    /**
+     * The view with its colors brought down to what the terminal shows
+     */
+    pub fn fitted(&self, depth: &ColorDepth) -> FieldView {
+        let colors = self.colors.iter().map(|color| FieldColor { key: color.key.clone(), style: color.style.fitted(depth) }).collect();
+        FieldView { shown: self.shown, colors }
+    }
+}
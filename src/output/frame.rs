@@ -0,0 +1,234 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::sync::Arc;
+use crate::output::ansi;
+use crate::output::backend::Backend;
+
+//External
+use crossterm::Result;
+use crossterm::style::Attribute;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/**
+ * Unchanged cells between two changed ones shorter than this are written again rather than jumped over,
+ * a cursor move costs about as much as a few cells
+ */
+const MERGE_GAP: usize = 4;
+
+/**
+ * One cell of a Frame. pen is the color and style sequences in effect when it was drawn, empty for the default style.
+ * A wide character fills its first cell, the cell after it is left with an empty symbol
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FrameCell {
+    symbol: String,
+    pen: Arc<str>,
+}
+
+impl Default for FrameCell {
+    fn default() -> Self {
+        FrameCell { symbol: " ".to_string(), pen: Arc::from("") }
+    }
+}
+
+/**
+ * One frame of the output zones, drawn in memory and then compared with the frame before it so only the cells
+ * that changed are written to the terminal.
+ *  ~ It is a Backend, the renderer draws into it the same way it would draw to the terminal.
+ *  ~ Colors and styles are kept per cell, other escape sequences are dropped, they can't be placed on a cell.
+ *  ~ It covers the rows from the top of the screen to the last output row, anything drawn below is ignored.
+ */
+#[derive(Debug, Clone)]
+pub struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<FrameCell>,
+    cursor: (u16, u16),
+    saved: (u16, u16),
+    pen: Arc<str>,
+}
+
+impl Frame {
+    pub fn new(width: u16, height: u16) -> Self {
+        Frame {
+            width,
+            height,
+            cells: vec![FrameCell::default(); width as usize * height as usize],
+            cursor: (0, 0),
+            saved: (0, 0),
+            pen: Arc::from(""),
+        }
+    }
+
+    fn index(&self, column: u16, row: u16) -> usize {
+        row as usize * self.width as usize + column as usize
+    }
+
+    fn put(&mut self, symbol: &str) {
+        let (column, row) = self.cursor;
+        let width = UnicodeWidthStr::width(symbol);
+        if width == 0 || row >= self.height || column as usize + width > self.width as usize {
+            self.cursor.0 = self.cursor.0.saturating_add(width as u16);
+            return;
+        }
+        let index = self.index(column, row);
+        self.cells[index] = FrameCell { symbol: symbol.to_string(), pen: self.pen.clone() };
+        for extra in 1..width {
+            self.cells[index + extra] = FrameCell { symbol: String::new(), pen: self.pen.clone() };
+        }
+        self.cursor.0 += width as u16;
+    }
+
+    /**
+     * Add an SGR sequence to the pen, a reset starts it over
+     */
+    fn apply_sgr(&mut self, sequence: &str) {
+        let parameters = &sequence[2..sequence.len() - 1];
+        let first = parameters.split([';', ':']).next().unwrap_or("");
+        if parameters.is_empty() || parameters == "0" {
+            self.pen = Arc::from("");
+        } else if first.is_empty() || first == "0" {
+            self.pen = Arc::from(sequence);
+        } else {
+            self.pen = Arc::from(format!("{}{}", self.pen, sequence));
+        }
+    }
+
+    /**
+     * Write the cells that differ from previous to out, every cell when there is no previous frame or its size differs.
+     * Returns how many cells were written.
+     */
+    pub fn draw_changes<B: Backend>(&self, previous: Option<&Frame>, out: &mut B) -> Result<usize> {
+        let previous = previous.filter(|previous| previous.width == self.width && previous.height == self.height);
+        let width = self.width as usize;
+        let mut written = 0;
+        //what the terminal's pen is, unknown until the first run resets it
+        let mut pen: Option<Arc<str>> = None;
+        for row in 0..self.height {
+            let start = self.index(0, row);
+            let cells = &self.cells[start..start + width];
+            let changed: Vec<bool> = match previous {
+                Some(previous) => cells.iter().zip(&previous.cells[start..start + width]).map(|(new, old)| new != old).collect(),
+                None => vec![true; width],
+            };
+            for (first, last) in runs(&changed) {
+                //a run can't start on the second half of a wide character
+                let first = (0..=first).rev().find(|&column| !cells[column].symbol.is_empty()).unwrap_or(first);
+                out.move_to(first as u16, row)?;
+                for cell in &cells[first..=last] {
+                    if cell.symbol.is_empty() {
+                        continue;
+                    }
+                    if pen.as_ref() != Some(&cell.pen) {
+                        out.set_attribute(Attribute::Reset)?;
+                        out.print(&cell.pen)?;
+                        pen = Some(cell.pen.clone());
+                    }
+                    out.print(&cell.symbol)?;
+                    written += 1;
+                }
+            }
+        }
+        if pen.is_some() {
+            out.set_attribute(Attribute::Reset)?;
+        }
+        Ok(written)
+    }
+}
+
+/**
+ * The runs of changed cells in a row as (first, last) columns, runs closer than MERGE_GAP are joined
+ */
+fn runs(changed: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (column, _) in changed.iter().enumerate().filter(|(_, changed)| **changed) {
+        match runs.last_mut() {
+            Some(run) if column - run.1 <= MERGE_GAP => run.1 = column,
+            _ => runs.push((column, column)),
+        }
+    }
+    runs
+}
+
+impl Backend for Frame {
+    fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> Result<()> {
+        self.cursor = (column, row);
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> Result<()> {
+        let mut rest = text;
+        while !rest.is_empty() {
+            if let Some(len) = ansi::escape_len(rest) {
+                let sequence = &rest[..len];
+                if ansi::is_sgr(sequence) {
+                    self.apply_sgr(sequence);
+                }
+                rest = &rest[len..];
+                continue;
+            }
+            let grapheme = rest.graphemes(true).next().unwrap_or(rest);
+            if grapheme == "\n" {
+                self.cursor = (0, self.cursor.1.saturating_add(1));
+            } else {
+                self.put(grapheme);
+            }
+            rest = &rest[grapheme.len()..];
+        }
+        Ok(())
+    }
+
+    fn set_attribute(&mut self, attribute: Attribute) -> Result<()> {
+        match attribute {
+            Attribute::Reset => self.pen = Arc::from(""),
+            attribute => self.pen = Arc::from(format!("{}{}", self.pen, attribute)),
+        }
+        Ok(())
+    }
+
+    fn clear_all(&mut self) -> Result<()> {
+        self.cells.iter_mut().for_each(|cell| *cell = FrameCell::default());
+        Ok(())
+    }
+
+    fn clear_line(&mut self) -> Result<()> {
+        if self.cursor.1 >= self.height {
+            return Ok(());
+        }
+        let start = self.index(0, self.cursor.1);
+        let width = self.width as usize;
+        self.cells[start..start + width].iter_mut().for_each(|cell| *cell = FrameCell::default());
+        Ok(())
+    }
+
+    fn save_position(&mut self) -> Result<()> {
+        self.saved = self.cursor;
+        Ok(())
+    }
+
+    fn restore_position(&mut self) -> Result<()> {
+        self.cursor = self.saved;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self, visible: bool) -> Result<()> {
+        Ok(())
+    }
+
+    //nothing that draws the zones writes raw sequences, there is no cell to keep them in
+    fn write_raw(&mut self, sequence: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use crate::broker::pattern::Pattern;
+use crate::output::ansi;
+use crate::output::fields::FieldView;
+use crate::output::theme::{self, ColorDepth};
+
+//External
+use crossterm::style::{Attribute, Color, SetBackgroundColor, SetForegroundColor};
+
+/**
+ * How a highlighted match is drawn, written as words joined by commas or spaces, eg. "red,bold" or "black on_yellow".
+ *  ~ A color name (crossterm's: red, dark_red, grey, dark_grey...) is the foreground, on_<color> the background.
+ *    A color can also be #rrggbb or a number of the 256 color palette, see output::theme::ColorDepth for terminals with fewer.
+ *  ~ bold, dim, italic, underline and reverse add that style.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightStyle {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub attributes: Vec<Attribute>,
+    source: String,
+}
+
+impl HighlightStyle {
+    pub fn parse(style: &str) -> Result<HighlightStyle, String> {
+        let mut parsed = HighlightStyle { foreground: None, background: None, attributes: Vec::new(), source: style.to_string() };
+        for word in style.split([',', ' ']).filter(|word| !word.is_empty()) {
+            let word = word.to_ascii_lowercase();
+            let attribute = match word.as_str() {
+                "bold" => Some(Attribute::Bold),
+                "dim" => Some(Attribute::Dim),
+                "italic" => Some(Attribute::Italic),
+                "underline" => Some(Attribute::Underlined),
+                "reverse" => Some(Attribute::Reverse),
+                _ => None,
+            };
+            match (attribute, word.strip_prefix("on_")) {
+                (Some(attribute), _) => parsed.attributes.push(attribute),
+                (None, Some(color)) => parsed.background = Some(parse_color(color).ok_or(format!("unknown color: {}", color))?),
+                (None, None) => parsed.foreground = Some(parse_color(&word).ok_or(format!("unknown color or style: {}", word))?),
+            }
+        }
+        if parsed.foreground.is_none() && parsed.background.is_none() && parsed.attributes.is_empty() {
+            return Err("a style needs a color or bold, dim, italic, underline or reverse".to_string());
+        }
+        Ok(parsed)
+    }
+
+    /**
+     * The sequences that turn the style on
+     */
+    pub(crate) fn open(&self) -> String {
+        let mut out = String::new();
+        //the named colors as the 16 color codes, which every terminal takes, crossterm writes them as palette numbers
+        match self.foreground.map(|color| (color, theme::basic_index(color))) {
+            Some((_, Some(index))) => out.push_str(&format!("\u{1b}[{}m", if index < 8 { 30 + index } else { 82 + index })),
+            Some((color, None)) => out.push_str(&SetForegroundColor(color).to_string()),
+            None => {},
+        }
+        match self.background.map(|color| (color, theme::basic_index(color))) {
+            Some((_, Some(index))) => out.push_str(&format!("\u{1b}[{}m", if index < 8 { 40 + index } else { 92 + index })),
+            Some((color, None)) => out.push_str(&SetBackgroundColor(color).to_string()),
+            None => {},
+        }
+        for attribute in &self.attributes {
+            out.push_str(&attribute.to_string());
+        }
+        out
+    }
+
+    /**
+     * The text drawn in the style, everything is reset after it
+     */
+    pub fn paint(&self, text: &str) -> String {
+        format!("{}{}\u{1b}[0m", self.open(), text)
+    }
+
+    /**
+     * The style with its colors brought down to what the terminal shows.
+     * NoColor takes the colors off, a style that had a background and no attributes left is drawn reverse so it still stands out
+     */
+    pub fn fitted(&self, depth: &ColorDepth) -> HighlightStyle {
+        if *depth == ColorDepth::NoColor {
+            let mut attributes = self.attributes.clone();
+            if attributes.is_empty() && self.background.is_some() {
+                attributes.push(Attribute::Reverse);
+            }
+            return HighlightStyle { foreground: None, background: None, attributes, ..self.clone() };
+        }
+        HighlightStyle {
+            foreground: self.foreground.map(|color| depth.fit(color)),
+            background: self.background.map(|color| depth.fit(color)),
+            ..self.clone()
+        }
+    }
+}
+
+/**
+ * A color name, #rrggbb or a palette number
+ */
+fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |at: usize| hex.get(at..at + 2).and_then(|channel| u8::from_str_radix(channel, 16).ok());
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Some(Color::Rgb { r, g, b }),
+            _ => None,
+        };
+    }
+    match name.parse::<u8>() {
+        Ok(value) => Some(Color::AnsiValue(value)),
+        Err(_) => Color::try_from(name).ok(),
+    }
+}
+
+impl fmt::Display for HighlightStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/**
+ * A pattern and the style its matches are drawn in, see pattern::Pattern for what a pattern can do
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightRule {
+    pub pattern: Pattern,
+    pub style: HighlightStyle,
+}
+
+impl HighlightRule {
+    pub fn new(pattern: &str, style: &str) -> Result<HighlightRule, String> {
+        let style = HighlightStyle::parse(style)?;
+        let pattern = Pattern::new(pattern).map_err(|e| format!("bad pattern: {}", e))?;
+        Ok(HighlightRule { pattern, style })
+    }
+}
+
+impl fmt::Display for HighlightRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.style, self.pattern)
+    }
+}
+
+/**
+ * The highlight rules of the output zones, applied to message text as it is drawn so changing them changes the scrollback too.
+ *  ~ Where the matches of two rules overlap the rule added first wins.
+ *  ~ Matching skips escape sequences already in the payload, a match never spans one, and the payload's own style
+ *    is back after a match.
+ *  ~ Widgets are drawn as they are.
+ * fields is how the key=value fields after the payload are drawn, see output::fields::FieldView.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Highlights {
+    rules: Vec<HighlightRule>,
+    pub fields: FieldView,
+}
+
+impl Highlights {
+    pub fn add(&mut self, rule: HighlightRule) {
+        self.rules.push(rule);
+    }
+
+    /**
+     * Remove the rule at index (0 based), returns it if there was one
+     */
+    pub fn remove(&mut self, index: usize) -> Option<HighlightRule> {
+        if index < self.rules.len() {
+            Some(self.rules.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn rules(&self) -> &[HighlightRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /**
+     * The rules with their colors brought down to what the terminal shows
+     */
+    pub fn fitted(&self, depth: &ColorDepth) -> Highlights {
+        let rules = self.rules.iter().map(|rule| HighlightRule { pattern: rule.pattern.clone(), style: rule.style.fitted(depth) }).collect();
+        Highlights { rules, fields: self.fields.fitted(depth) }
+    }
+
+    /**
+     * The text with every match wrapped in its rule's style
+     */
+    pub fn apply(&self, text: &str) -> String {
+        if self.rules.is_empty() {
+            return text.to_string();
+        }
+        let mut out = String::with_capacity(text.len());
+        //the payload's own colors and styles in effect, put back after every match
+        let mut active = String::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            if let Some(len) = ansi::escape_len(rest) {
+                let sequence = &rest[..len];
+                if sequence == "\u{1b}[0m" || sequence == "\u{1b}[m" {
+                    active.clear();
+                } else if ansi::is_sgr(sequence) {
+                    active.push_str(sequence);
+                }
+                out.push_str(sequence);
+                rest = &rest[len..];
+                continue;
+            }
+            let first = rest.chars().next().map_or(1, |c| c.len_utf8());
+            let plain = rest[first..].find('\u{1b}').map_or(rest.len(), |at| at + first);
+            self.apply_plain(&rest[..plain], &active, &mut out);
+            rest = &rest[plain..];
+        }
+        out
+    }
+
+    fn apply_plain(&self, text: &str, active: &str, out: &mut String) {
+        let mut spans: Vec<(usize, usize, &HighlightStyle)> = Vec::new();
+        for rule in &self.rules {
+            for (start, end) in rule.pattern.find_all(text) {
+                if spans.iter().all(|(taken_start, taken_end, _)| end <= *taken_start || start >= *taken_end) {
+                    spans.push((start, end, &rule.style));
+                }
+            }
+        }
+        spans.sort_by_key(|(start, _, _)| *start);
+        let mut last = 0;
+        for (start, end, style) in spans {
+            out.push_str(&text[last..start]);
+            out.push_str(&style.open());
+            out.push_str(&text[start..end]);
+            out.push_str("\u{1b}[0m");
+            out.push_str(active);
+            last = end;
+        }
+        out.push_str(&text[last..]);
+    }
+}
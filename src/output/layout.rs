@@ -0,0 +1,208 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::ops::Range;
+use std::sync::atomic::{AtomicU16, Ordering};
+use crate::output::zone::Arrangement;
+
+//External
+use crossterm::terminal;
+
+/**
+ * Rows the input zone currently takes, more than one while a multi-line input is being composed.
+ * Written by the input task and read by every layout, so the output zones shrink while the input grows.
+ */
+static INPUT_ROWS: AtomicU16 = AtomicU16::new(1);
+
+/**
+ * Set the input zone height, returns the previous one
+ */
+pub fn set_input_rows(rows: u16) -> u16 {
+    INPUT_ROWS.swap(rows.max(1), Ordering::Relaxed)
+}
+
+/**
+ * Leave no rows to the input zone, for a console without one (see Console::enable_display_only)
+ */
+pub fn remove_input_zone() {
+    INPUT_ROWS.store(0, Ordering::Relaxed);
+}
+
+/**
+ * A rectangle of terminal cells, x and y are the top left corner
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        column >= self.x && column < self.x + self.width && row >= self.y && row < self.y + self.height
+    }
+}
+
+/**
+ * Split length into count parts, the first parts get the remainder
+ */
+fn split_length(length: u16, count: u16) -> Vec<u16> {
+    let base = length / count;
+    let remainder = length % count;
+    (0..count).map(|i| base + if i < remainder { 1 } else { 0 }).collect()
+}
+
+/**
+ * Where each zone sits on the screen, rows counted from the top.
+ *  ~ The input zone is the last input_rows rows, usually just the last row, none when the console is display only.
+ *  ~ The status bar (when enabled) is the row right above the input zone.
+ *  ~ The output zone takes every row above that.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub width: u16,
+    pub height: u16,
+    pub status_bar: bool,
+    pub input_rows: u16,
+}
+
+impl Layout {
+    pub fn new(width: u16, height: u16, status_bar: bool) -> Self {
+        Layout { width, height, status_bar, input_rows: 1 }
+    }
+
+    /**
+     * The most rows a multi-line input may take, half the screen
+     */
+    pub fn max_input_rows(&self) -> u16 {
+        (self.height / 2).max(1)
+    }
+
+    /**
+     * First row of the input zone
+     */
+    pub fn input_top(&self) -> u16 {
+        self.height.saturating_sub(self.input_rows.min(self.max_input_rows()))
+    }
+
+    /**
+     * Build the layout from the current terminal size, falls back to 80x24 when the size can't be read
+     */
+    pub fn from_terminal(status_bar: bool) -> Self {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        Layout::from_size(width, height, status_bar)
+    }
+
+    /**
+     * The layout of a screen of the given size, with the input zone as tall as the input being edited
+     */
+    pub fn from_size(width: u16, height: u16, status_bar: bool) -> Self {
+        let mut layout = Layout::new(width, height, status_bar);
+        layout.input_rows = INPUT_ROWS.load(Ordering::Relaxed);
+        layout
+    }
+
+    pub fn input_row(&self) -> u16 {
+        self.height.saturating_sub(1)
+    }
+
+    pub fn status_row(&self) -> Option<u16> {
+        if self.status_bar && self.input_top() >= 2 {
+            Some(self.input_top() - 1)
+        } else {
+            None
+        }
+    }
+
+    pub fn output_rows(&self) -> Range<u16> {
+        let end = match self.status_row() {
+            Some(row) => row,
+            None => self.input_top(),
+        };
+        0..end
+    }
+
+    pub fn output_height(&self) -> usize {
+        self.output_rows().len()
+    }
+
+    pub fn output_rect(&self) -> Rect {
+        let rows = self.output_rows();
+        Rect { x: 0, y: rows.start, width: self.width, height: rows.end - rows.start }
+    }
+
+    /**
+     * Split the output area between count named zones.
+     * Side by side zones are separated by a one column gap the renderer draws a divider in.
+     */
+    pub fn split_output(&self, count: usize, arrangement: &Arrangement) -> Vec<Rect> {
+        let area = self.output_rect();
+        let count = count.max(1) as u16;
+        match arrangement {
+            Arrangement::Stacked => {
+                let mut y = area.y;
+                split_length(area.height, count).into_iter().map(|height| {
+                    let rect = Rect { x: area.x, y, width: area.width, height };
+                    y += height;
+                    rect
+                }).collect()
+            },
+            Arrangement::SideBySide => {
+                let usable = area.width.saturating_sub(count - 1);
+                let mut x = area.x;
+                split_length(usable, count).into_iter().map(|width| {
+                    let rect = Rect { x, y: area.y, width, height: area.height };
+                    x += width + 1;
+                    rect
+                }).collect()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_status_bar_sits_between_output_and_input() {
+        let layout = Layout::new(80, 24, true);
+        assert_eq!(layout.input_row(), 23);
+        assert_eq!(layout.status_row(), Some(22));
+        assert_eq!(layout.output_rows(), 0..22);
+
+        let layout = Layout::new(80, 24, false);
+        assert_eq!(layout.status_row(), None);
+        assert_eq!(layout.output_rows(), 0..23);
+    }
+
+    #[test]
+    fn a_taller_input_takes_rows_from_the_output() {
+        let mut layout = Layout::new(80, 10, true);
+        layout.input_rows = 3;
+        assert_eq!(layout.input_top(), 7);
+        assert_eq!(layout.output_rows(), 0..6);
+        //never more than half the screen
+        layout.input_rows = 9;
+        assert_eq!(layout.input_top(), 5);
+    }
+
+    #[test]
+    fn stacked_zones_share_the_rows() {
+        let rects = Layout::new(80, 11, false).split_output(3, &Arrangement::Stacked);
+        let heights: Vec<u16> = rects.iter().map(|rect| rect.height).collect();
+        assert_eq!(heights.iter().sum::<u16>(), 10);
+        assert!(rects.windows(2).all(|pair| pair[0].y + pair[0].height == pair[1].y));
+        assert!(rects.iter().all(|rect| rect.width == 80));
+    }
+
+    #[test]
+    fn side_by_side_zones_leave_a_gap_for_the_divider() {
+        let rects = Layout::new(21, 5, false).split_output(2, &Arrangement::SideBySide);
+        assert_eq!(rects, [Rect { x: 0, y: 0, width: 10, height: 4 }, Rect { x: 11, y: 0, width: 10, height: 4 }]);
+    }
+}
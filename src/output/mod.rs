@@ -0,0 +1,28 @@
+pub mod layout;
+pub mod zone;
+pub mod tabs;
+pub mod expiry;
+pub mod text;
+pub mod ansi;
+pub mod search;
+pub mod highlight;
+pub mod fields;
+pub mod theme;
+pub mod selection;
+pub mod clipboard;
+pub mod notify;
+pub mod export;
+pub mod recorder;
+pub mod widgets;
+pub mod table;
+pub mod progress;
+pub mod spinner;
+pub mod status_bar;
+pub mod terminal;
+pub mod writer;
+pub mod backend;
+pub mod frame;
+pub mod bench;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+pub mod renderer;
\ No newline at end of file
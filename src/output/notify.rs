@@ -0,0 +1,142 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+use crate::output::backend::Backend;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use tokio::time::Duration;
+
+/**
+ * How long without input before the user counts as away, see NotifyPolicy
+ */
+pub const DEFAULT_AWAY_AFTER: Duration = Duration::from_secs(60);
+
+/**
+ * Why a desktop notification could not be shown
+ */
+#[derive(Debug)]
+pub enum NotifyError {
+    Unsupported,
+    Failed(String),
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Unsupported => write!(f, "desktop notifications are not compiled in, build with --features desktop-notify"),
+            NotifyError::Failed(e) => write!(f, "desktop notification failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {}
+
+/**
+ * What a message of one priority calls the user back with, written "bell", "desktop", "bell,desktop" or "none".
+ * flash draws the status bar in the error style until its next refresh, for alert rules (see broker::alerts) as well
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Alert {
+    pub bell: bool,
+    pub desktop: bool,
+    pub flash: bool,
+}
+
+impl Alert {
+    pub const NONE: Alert = Alert { bell: false, desktop: false, flash: false };
+    pub const BELL: Alert = Alert { bell: true, desktop: false, flash: false };
+
+    pub fn parse(text: &str) -> Option<Alert> {
+        let mut alert = Alert::NONE;
+        for word in text.split(',').map(|word| word.trim().to_ascii_lowercase()) {
+            match word.as_str() {
+                "bell" => alert.bell = true,
+                "desktop" => alert.desktop = true,
+                "flash" => alert.flash = true,
+                "none" | "" => {},
+                _ => return None,
+            }
+        }
+        Some(alert)
+    }
+
+    pub fn is_none(&self) -> bool {
+        !self.bell && !self.desktop && !self.flash
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<&str> = [(self.bell, "bell"), (self.desktop, "desktop"), (self.flash, "flash")].iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, word)| *word)
+            .collect();
+        match words.is_empty() {
+            true => write!(f, "none"),
+            false => write!(f, "{}", words.join(",")),
+        }
+    }
+}
+
+/**
+ * When the Console calls the user back to the terminal, by priority.
+ *  ~ Only while the user is not watching: output is paused, or nothing was typed for away_after.
+ *    crossterm can't report whether the terminal window has focus, so not typing stands in for it.
+ *  ~ Urgent and Critical ring the terminal bell by default, desktop notifications are opt in (and need the desktop-notify feature).
+ */
+#[derive(Debug, Clone)]
+pub struct NotifyPolicy {
+    pub default: Alert,
+    pub overrides: HashMap<PriorityStatus, Alert>,
+    pub away_after: Duration,
+}
+
+impl Default for NotifyPolicy {
+    fn default() -> Self {
+        let mut overrides = HashMap::new();
+        overrides.insert(PriorityStatus::Urgent, Alert::BELL);
+        overrides.insert(PriorityStatus::Critical, Alert::BELL);
+        NotifyPolicy { default: Alert::NONE, overrides, away_after: DEFAULT_AWAY_AFTER }
+    }
+}
+
+impl NotifyPolicy {
+    pub fn set(&mut self, priority: PriorityStatus, alert: Alert) {
+        self.overrides.insert(priority, alert);
+    }
+
+    pub fn alert(&self, priority: &PriorityStatus) -> Alert {
+        *self.overrides.get(priority).unwrap_or(&self.default)
+    }
+}
+
+/**
+ * Ring the terminal bell, BEL draws nothing so it can go out between frames
+ */
+pub fn ring<B: Backend>(stdout: &mut B) -> crossterm::Result<()> {
+    stdout.write_raw("\u{7}")?;
+    stdout.flush()
+}
+
+/**
+ * Show a desktop notification, blocks until the notification daemon took it so call it off the async threads
+ */
+#[cfg(feature = "desktop-notify")]
+pub fn desktop(summary: &str, body: &str) -> Result<(), NotifyError> {
+    notify_rust::Notification::new()
+        .summary(summary)
+        .body(body)
+        .show()
+        .map(|_| ())
+        .map_err(|e| NotifyError::Failed(e.to_string()))
+}
+
+#[cfg(not(feature = "desktop-notify"))]
+pub fn desktop(summary: &str, body: &str) -> Result<(), NotifyError> {
+    Err(NotifyError::Unsupported)
+}
@@ -0,0 +1,119 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::handle::{HandleError, SenderHandle};
+use crate::broker::message::{self, Message};
+use crate::output::widgets::{ProgressState, Widget, WidgetState};
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use tokio::time::Instant;
+
+/**
+ * A progress bar line in the output zone, obtained with SenderHandle::progress().
+ *  ~ Every update replaces the same line in place, the renderer draws whole frames so updates can't interleave with other output.
+ *  ~ Updates that don't change the shown percentage are not sent, so a tight loop calling inc() doesn't flood the broker.
+ *  ~ finish() or abort() end the bar, dropping an unfinished bar aborts it.
+ */
+pub struct Progress<T = ()> {
+    handle: SenderHandle<T>,
+    slot: u64,
+    label: String,
+    total: u64,
+    current: u64,
+    started: Instant,
+    last_percent: Option<u64>,
+    ended: bool,
+}
+
+impl<T> Progress<T> {
+    /**
+     * Created through SenderHandle::progress(), the bar is drawn on the first update
+     */
+    pub(crate) fn new(handle: SenderHandle<T>, label: String, total: u64) -> Self {
+        let slot = message::next_id();
+        Progress { handle, slot, label, total: total.max(1), current: 0, started: Instant::now(), last_percent: None, ended: false }
+    }
+
+    fn fraction(&self) -> f64 {
+        (self.current as f64 / self.total as f64).min(1.0)
+    }
+
+    fn state(&self, state: WidgetState) -> Widget {
+        let fraction = self.fraction();
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let eta_secs = if fraction > 0.0 && fraction < 1.0 {
+            Some((elapsed * (1.0 - fraction) / fraction).round() as u64)
+        } else {
+            None
+        };
+        Widget::Progress(ProgressState { label: self.label.clone(), fraction, eta_secs, state })
+    }
+
+    fn message(&self, widget: Widget) -> Message {
+        Message::new(self.handle.id().to_string(), PriorityStatus::Normal, widget.plain_text()).with_widget(self.slot, widget)
+    }
+
+    async fn update(&mut self, force: bool) -> Result<(), HandleError> {
+        let percent = (self.fraction() * 100.0) as u64;
+        if !force && self.last_percent == Some(percent) {
+            return Ok(());
+        }
+        self.last_percent = Some(percent);
+        let message = self.message(self.state(WidgetState::Running));
+        self.handle.send_message(message).await.map(|_| ())
+    }
+
+    /**
+     * Set how much of the total is done
+     */
+    pub async fn set(&mut self, current: u64) -> Result<(), HandleError> {
+        self.current = current.min(self.total);
+        self.update(false).await
+    }
+
+    /**
+     * Add to how much of the total is done
+     */
+    pub async fn inc(&mut self, delta: u64) -> Result<(), HandleError> {
+        self.set(self.current.saturating_add(delta)).await
+    }
+
+    /**
+     * Change the label, redraws immediately
+     */
+    pub async fn set_label(&mut self, label: String) -> Result<(), HandleError> {
+        self.label = label;
+        self.update(true).await
+    }
+
+    /**
+     * Complete the bar, the finished line then expires like any other message
+     */
+    pub async fn finish(mut self) -> Result<(), HandleError> {
+        self.ended = true;
+        self.current = self.total;
+        let message = self.message(self.state(WidgetState::Done));
+        self.handle.send_message(message).await.map(|_| ())
+    }
+
+    /**
+     * Stop the bar where it is and show the reason
+     */
+    pub async fn abort(mut self, reason: String) -> Result<(), HandleError> {
+        self.ended = true;
+        let message = self.message(self.state(WidgetState::Aborted(reason)));
+        self.handle.send_message(message).await.map(|_| ())
+    }
+}
+
+impl<T> Drop for Progress<T> {
+    fn drop(&mut self) {
+        if !self.ended {
+            let message = self.message(self.state(WidgetState::Aborted("dropped".to_string())));
+            let _ = self.handle.try_send_message(message);
+        }
+    }
+}
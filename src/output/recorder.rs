@@ -0,0 +1,158 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::broker::message::{Message, Output};
+use crate::output::zone::ZoneSet;
+
+//External
+use serde::{Serialize, Deserialize};
+use tokio::time::{Duration, Instant};
+
+pub const RECORDING_VERSION: u32 = 1;
+
+/**
+ * First line of a recording, the terminal it was recorded on and when (ms since unix epoch)
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    pub started_at: u64,
+}
+
+/**
+ * What changed the output zones, the outputs that decide what a replay shows
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Recorded {
+    Line(Box<Message>),
+    Dismiss(Option<u64>),
+    Retract {
+        sender: String,
+        id: u64,
+    },
+}
+
+impl Recorded {
+    /**
+     * The change an output makes to the zones, None for everything but lines, dismissals and retractions
+     */
+    pub fn from_output(output: &Output) -> Option<Recorded> {
+        match output {
+            Output::Line(message) => Some(Recorded::Line(Box::new(message.clone()))),
+            Output::Dismiss(id) => Some(Recorded::Dismiss(*id)),
+            Output::Retract { sender, id } => Some(Recorded::Retract { sender: sender.clone(), id: *id }),
+            _ => None,
+        }
+    }
+
+    /**
+     * Make the change to the zones, for a replay or a client attached to a session
+     */
+    pub fn apply(self, zones: &mut ZoneSet) {
+        match self {
+            Recorded::Line(message) => zones.push(*message),
+            Recorded::Dismiss(id) => { zones.dismiss(id); },
+            Recorded::Retract { sender, id } => { zones.retract(&sender, id); },
+        }
+    }
+}
+
+/**
+ * One line of a recording after the header, at is ms since recording started
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub at: u64,
+    pub event: Recorded,
+}
+
+/**
+ * Writes the session to a file as it happens, like asciinema: a header line and then one JSON object per output, with its time.
+ * Every event is flushed so a session that ends in a crash is still on disk up to that point.
+ */
+#[derive(Debug)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, width: u16, height: u16) -> std::io::Result<Recorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let started_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let header = RecordingHeader { version: RECORDING_VERSION, width, height, started_at };
+        writeln!(writer, "{}", to_json(&header)?)?;
+        writer.flush()?;
+        Ok(Recorder { writer, started: Instant::now() })
+    }
+
+    /**
+     * Record an output before it is applied to the zones, everything but lines, dismissals and retractions is left out
+     */
+    pub fn record(&mut self, output: &Output) -> std::io::Result<()> {
+        let event = match Recorded::from_output(output) {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+        let event = RecordedEvent { at: self.started.elapsed().as_millis() as u64, event };
+        writeln!(self.writer, "{}", to_json(&event)?)?;
+        self.writer.flush()
+    }
+}
+
+/**
+ * A recording read back for replay
+ */
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    /**
+     * Read a recording, a truncated last line (the session was killed mid write) is skipped
+     */
+    pub fn load(path: &Path) -> std::io::Result<Recording> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header = match lines.next() {
+            Some(line) => from_json::<RecordingHeader>(&line?)?,
+            None => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "empty recording")),
+        };
+        if header.version > RECORDING_VERSION {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("recording version {} is newer than this build", header.version)));
+        }
+        let mut events = Vec::new();
+        for line in lines {
+            match from_json::<RecordedEvent>(&line?) {
+                Ok(event) => events.push(event),
+                Err(_) => break,
+            }
+        }
+        Ok(Recording { header, events })
+    }
+
+    /**
+     * How long the replay takes at the given speed
+     */
+    pub fn duration(&self, speed: f64) -> Duration {
+        let last = self.events.last().map(|event| event.at).unwrap_or(0);
+        Duration::from_secs_f64(last as f64 / 1000.0 / speed)
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> std::io::Result<String> {
+    serde_json::to_string(value).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn from_json<T: for<'de> Deserialize<'de>>(line: &str) -> std::io::Result<T> {
+    serde_json::from_str(line).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,627 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::output::layout::{Layout, Rect};
+use crate::output::zone::{Arrangement, OutputZone, ZoneSet};
+use crate::broker::message::Message;
+use crate::output::text::{self, LineMode};
+use crate::output::search::Search;
+use crate::output::highlight::{HighlightStyle, Highlights};
+use crate::output::theme::{self, Theme};
+use crate::output::selection::{self, Selection};
+use crate::output::clipboard::{self, ClipboardError, CopiedTo};
+use crate::output::notify;
+use crate::user_input::editor::{self, LineEditor};
+use crate::user_input::highlighter::{self, Highlighter};
+use crate::user_input::dialog::{SelectDialog, MAX_SELECT_ROWS};
+use crate::output::layout;
+use crate::output::backend::Backend;
+use crate::output::frame::Frame;
+use crate::output::writer::TerminalWriter;
+
+//External
+use crossterm::Result;
+use crossterm::style::Attribute;
+
+/**
+ * Draws the zones onto the terminal.
+ *  ~ Every draw saves and restores the cursor so whatever the user is typing in the input zone is left alone.
+ *  ~ The layout is re-read from the terminal on every draw so a resize is picked up on the next frame.
+ *  ~ The input task draws the input row through a TerminalWriter of its own, see draw_input.
+ *  ~ It draws through a Backend, the terminal by default or a TestBackend to see what would be on screen without one.
+ *  ~ The output zones are drawn into a Frame first, only the cells that changed since the last frame reach the backend.
+ */
+pub struct Renderer<B: Backend = TerminalWriter> {
+    stdout: B,
+    status_bar: bool,
+    previous: Option<Frame>,
+    theme: Theme,
+}
+
+impl<B: Backend> Renderer<B> {
+    pub fn new(stdout: B, status_bar: bool) -> Self {
+        Renderer { stdout, status_bar, previous: None, theme: Theme::default() }
+    }
+
+    /**
+     * Draw with theme instead of the plain one, its colors should already be fitted to the terminal (see Console::screen_theme)
+     */
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /**
+     * What the renderer draws to, eg. the TestBackend to look at the screen
+     */
+    pub fn backend(&self) -> &B {
+        &self.stdout
+    }
+
+    /**
+     * Forget the last frame so the next draw_zones writes every cell, for when something else drew over the zones
+     */
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+    }
+
+    pub fn layout(&self) -> Layout {
+        let (width, height) = self.stdout.size();
+        Layout::from_size(width, height, self.status_bar)
+    }
+
+    /**
+     * Clear the screen and put the prompt on the input row, used once at startup
+     */
+    pub fn draw_initial(&mut self, prompt: &str) -> Result<()> {
+        self.invalidate();
+        let layout = self.layout();
+        let stdout = &mut self.stdout;
+        stdout.clear_all()?;
+        stdout.move_to(0, layout.input_row())?;
+        stdout.print(prompt)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /**
+     * Print a message under the ones before it and the prompt again after it, for the Append strategy
+     * (see output::terminal::RenderStrategy) where nothing can be drawn in place. The message starts with a carriage return
+     * so it goes over the prompt it follows, whatever the user was typing stays in the terminal's line for them to finish.
+     */
+    pub fn draw_appended(&mut self, message: &Message, prompt: &str) -> Result<()> {
+        let width = self.stdout.size().0 as usize;
+        let prompt_width = text::width(prompt);
+        let rows = Self::line_rows(message, width, &LineMode::Wrap, &Highlights::default(), &self.theme);
+        let stdout = &mut self.stdout;
+        stdout.write_raw("\r")?;
+        for (index, row) in rows.iter().enumerate() {
+            match (index, text::width(row)) {
+                //long enough to cover the prompt
+                (0, row_width) if row_width < prompt_width => stdout.print(&text::pad(row, prompt_width))?,
+                _ => stdout.print(row)?,
+            }
+            stdout.write_raw("\r\n")?;
+        }
+        stdout.print(prompt)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /**
+     * Redraw every output zone, each zone bottom aligned with its newest line closest to the input zone.
+     * With more than one zone each one gets its name as a header row, a zone with the keyboard focus always gets one
+     * and it is drawn in reverse video. With tabs on the first zone's header is the tab bar, unless the output area is split.
+     */
+    pub fn draw_zones(&mut self, zones: &ZoneSet) -> Result<()> {
+        let layout = self.layout();
+        let rects = layout.split_output(zones.zones.len(), &zones.arrangement);
+        let headers = zones.zones.len() > 1;
+        let focused = zones.focused_zone();
+
+        let mut frame = Frame::new(layout.width, layout.output_rows().end);
+        for (index, (zone, rect)) in zones.zones.iter().zip(rects.iter()).enumerate() {
+            let has_focus = focused == Some(index);
+            let selection = zones.selection.as_ref().filter(|selection| selection.zone == index);
+            let header = match (index, zones.tab_bar.as_ref().filter(|_| !zones.is_split())) {
+                (0, Some(bar)) => Some(bar.line(&zone.name)),
+                _ if headers || has_focus => Some(format!("─ {} ", zone.name)),
+                _ => None,
+            };
+            Self::draw_zone(&mut frame, zone, *rect, header, has_focus, selection, &zones.highlights, &self.theme)?;
+        }
+        //dividers between side by side zones
+        if zones.arrangement == Arrangement::SideBySide {
+            for rect in rects.iter().skip(1) {
+                for row in rect.y..rect.y + rect.height {
+                    frame.move_to(rect.x - 1, row)?;
+                    frame.print("│")?;
+                }
+            }
+        }
+
+        let stdout = &mut self.stdout;
+        stdout.save_position()?;
+        frame.draw_changes(self.previous.as_ref(), stdout)?;
+        stdout.restore_position()?;
+        stdout.flush()?;
+        self.previous = Some(frame);
+        Ok(())
+    }
+
+    /**
+     * Draw one zone into its rectangle, every cell of the rectangle is overwritten so nothing stale is left behind
+     */
+    #[allow(clippy::too_many_arguments)]
+    fn draw_zone(stdout: &mut Frame, zone: &OutputZone, rect: Rect, header: Option<String>, focused: bool, selection: Option<&Selection>, highlights: &Highlights, theme: &Theme) -> Result<()> {
+        if rect.height == 0 || rect.width == 0 {
+            return Ok(());
+        }
+        let width = rect.width as usize;
+        let mut top = rect.y;
+        if let Some(title) = header {
+            if focused {
+                stdout.set_attribute(Attribute::Reverse)?;
+            }
+            stdout.move_to(rect.x, top)?;
+            stdout.print(&theme.header.paint(&text::pad_with(&title, width, '─')))?;
+            stdout.set_attribute(Attribute::Reset)?;
+            top += 1;
+        }
+        //pinned messages take the rows under the header, they can fill the zone but never push past it
+        let bottom = rect.y + rect.height;
+        for line in zone.pinned() {
+            if top >= bottom {
+                break;
+            }
+            let first = Self::line_rows(&line.message, width, &LineMode::Truncate, highlights, theme).into_iter().next().unwrap_or_default();
+            let text = format!("[pin #{}] {}", line.message.id, first);
+            stdout.move_to(rect.x, top)?;
+            stdout.print(&theme.pinned.paint(&text::pad(&text, width)))?;
+            top += 1;
+        }
+
+        for (row, line) in Self::body_rows(zone, rect, top, highlights, theme) {
+            let mut line = text::pad(&line, width);
+            if let Some(columns) = selection.and_then(|selection| selection.columns(row, rect)) {
+                line = selection::highlight(&line, columns);
+            }
+            stdout.move_to(rect.x, row)?;
+            stdout.print(&line)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * What each screen row of a zone below its header and pinned lines shows, from top down to the bottom of rect
+     */
+    fn body_rows(zone: &OutputZone, rect: Rect, top: u16, highlights: &Highlights, theme: &Theme) -> Vec<(u16, String)> {
+        let width = rect.width as usize;
+        //every message takes at least one row, so height messages are always enough to fill the zone
+        let height = (rect.y + rect.height).saturating_sub(top) as usize;
+        let rows: Vec<String> = match zone.search() {
+            Some(search) if search.position().is_some() => Self::search_rows(zone, search, width, height, highlights, theme),
+            _ if zone.scrolled().is_some() => Self::scrolled_rows(zone, width, height, highlights, theme),
+            _ => zone.visible(height).iter().flat_map(|line| Self::line_rows(&line.message, width, &zone.line_mode, highlights, theme)).collect(),
+        };
+        let rows = &rows[rows.len().saturating_sub(height)..];
+        let blank = height - rows.len();
+        (0..height).map(|offset| {
+            let line = if offset >= blank { rows[offset - blank].clone() } else { String::new() };
+            (top + offset as u16, line)
+        }).collect()
+    }
+
+    /**
+     * Put text on the clipboard, see clipboard::copy. Goes through the renderer because OSC 52 is written to the terminal
+     */
+    pub fn copy(&mut self, text: &str) -> std::result::Result<CopiedTo, ClipboardError> {
+        clipboard::copy(&mut self.stdout, text)
+    }
+
+    /**
+     * Ring the terminal bell
+     */
+    pub fn bell(&mut self) -> Result<()> {
+        notify::ring(&mut self.stdout)
+    }
+
+    /**
+     * The text under the mouse selection, one line per screen row. None when nothing is selected
+     */
+    pub fn selection_text(&self, zones: &ZoneSet) -> Option<String> {
+        let selection = zones.selection.as_ref().filter(|selection| !selection.is_empty())?;
+        let zone = zones.zones.get(selection.zone)?;
+        let rects = self.layout().split_output(zones.zones.len(), &zones.arrangement);
+        let rect = *rects.get(selection.zone)?;
+        //the same rows draw_zone skips before the body
+        let header = zones.zones.len() > 1 || zones.focused_zone() == Some(selection.zone) || (selection.zone == 0 && zones.tab_bar.is_some() && !zones.is_split());
+        let top = rect.y + header as u16 + zone.pinned().len().min(rect.height as usize) as u16;
+        let lines: Vec<String> = Self::body_rows(zone, rect, top.min(rect.y + rect.height), &zones.highlights, &self.theme).into_iter()
+            .filter_map(|(row, line)| selection.columns(row, rect).map(|columns| selection::extract(&line, columns)))
+            .collect();
+        if lines.is_empty() { None } else { Some(lines.join("\n")) }
+    }
+
+    /**
+     * The scrollback around the current match, which sits about a third of the way up from the bottom.
+     * Matches are highlighted and the rows of the current match are underlined.
+     */
+    fn search_rows(zone: &OutputZone, search: &Search, width: usize, height: usize, highlights: &Highlights, theme: &Theme) -> Vec<String> {
+        let position = match search.position() {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+        let range = zone.scrollback_range();
+        let rows_of = |at: u64| -> Vec<String> {
+            match zone.scrollback_at(at) {
+                Some(message) => Self::line_rows(message, width, &zone.line_mode, highlights, theme).iter().map(|row| search.highlight(row)).collect(),
+                None => Vec::new(),
+            }
+        };
+
+        let mut current = rows_of(position);
+        for row in current.iter_mut() {
+            *row = format!("\u{1b}[4m{}\u{1b}[24m", row);
+        }
+        let mut after: Vec<String> = Vec::new();
+        let mut next = position + 1;
+        while next < range.end {
+            let rows = rows_of(next);
+            if after.len() + rows.len() > height / 3 {
+                break;
+            }
+            after.extend(rows);
+            next += 1;
+        }
+        let mut before: Vec<Vec<String>> = Vec::new();
+        let mut filled = current.len() + after.len();
+        let mut previous = position;
+        while previous > range.start && filled < height {
+            previous -= 1;
+            let rows = rows_of(previous);
+            filled += rows.len();
+            before.push(rows);
+        }
+        before.into_iter().rev().flatten().chain(current).chain(after).collect()
+    }
+
+    /**
+     * The scrollback up to the newest message the user scrolled back to, enough of it to fill height rows
+     */
+    fn scrolled_rows(zone: &OutputZone, width: usize, height: usize, highlights: &Highlights, theme: &Theme) -> Vec<String> {
+        let range = zone.scrollback_range();
+        let mut at = match zone.scrolled() {
+            Some(at) => at + 1,
+            None => return Vec::new(),
+        };
+        let mut messages: Vec<Vec<String>> = Vec::new();
+        let mut filled = 0;
+        while at > range.start && filled < height {
+            at -= 1;
+            if let Some(message) = zone.scrollback_at(at) {
+                let rows = Self::line_rows(message, width, &zone.line_mode, highlights, theme);
+                filled += rows.len();
+                messages.push(rows);
+            }
+        }
+        messages.into_iter().rev().flatten().collect()
+    }
+
+    /**
+     * The rows one message takes fitted to width, widgets are drawn to fit and plain messages are highlighted,
+     * then wrap or are truncated per the zone. Every row of a plain message starts in the theme's color for its priority,
+     * and it starts with the priority's label when the theme labels them
+     */
+    fn line_rows(message: &Message, width: usize, mode: &LineMode, highlights: &Highlights, theme: &Theme) -> Vec<String> {
+        let labelled;
+        let payload = match theme.labels.then(|| theme::label(&message.priority)).flatten() {
+            Some(label) => {
+                labelled = format!("{} {}", label, message.payload);
+                labelled.as_str()
+            },
+            None => &message.payload,
+        };
+        let with_fields = highlights.fields.render(payload, &message.fields);
+        let payload = with_fields.as_deref().unwrap_or(payload);
+        let rows = match &message.widget {
+            Some(widget) => return widget.render_rows(width),
+            None if highlights.is_empty() => text::fit(payload, width, mode),
+            None => text::fit(&highlights.apply(payload), width, mode),
+        };
+        match theme.priority(&message.priority) {
+            Some(style) => rows.into_iter().map(|row| format!("{}{}", style.open(), row)).collect(),
+            None => rows,
+        }
+    }
+
+    /**
+     * Redraw the status bar row, does nothing when the status bar is disabled
+     */
+    pub fn draw_status(&mut self, line: &str) -> Result<()> {
+        let style = self.theme.status_bar.clone();
+        self.draw_status_in(line, &style)
+    }
+
+    /**
+     * Draw the status bar in the theme's error style to catch the eye, the next draw_status puts it back
+     */
+    pub fn flash_status(&mut self, line: &str) -> Result<()> {
+        let style = self.theme.error.clone();
+        self.draw_status_in(line, &style)
+    }
+
+    fn draw_status_in(&mut self, line: &str, style: &HighlightStyle) -> Result<()> {
+        let layout = self.layout();
+        let row = match layout.status_row() {
+            Some(row) => row,
+            None => return Ok(()),
+        };
+        let stdout = &mut self.stdout;
+        stdout.save_position()?;
+        stdout.move_to(0, row)?;
+        stdout.clear_line()?;
+        stdout.print(&style.paint(&text::pad(line, layout.width as usize)))?;
+        stdout.restore_position()?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /**
+     * Draw the prompt and the visible part of the input being edited, then leave the cursor where the editor's cursor is.
+     * A multi-line input grows the input zone upward, continuation lines are indented under the prompt.
+     * An error (a rejected line, see user_input::validate) takes one more row under the input, in red.
+     * A highlighter styles the input as it is drawn, see user_input::highlighter. The editor's suggestion is drawn dim after the cursor.
+     * The error, the suggestion and a prompt without colors of its own take the theme's styles.
+     * Returns true when the input zone changed height, the output zones have to be redrawn to make room.
+     */
+    pub fn draw_input(stdout: &mut B, prompt: &str, editor: &LineEditor, error: Option<&str>, highlighter: Option<&dyn Highlighter>, theme: &Theme) -> Result<bool> {
+        let (width, height) = stdout.size();
+        let mut layout = Layout::from_size(width, height, false);
+        let width = layout.width as usize;
+        let prompt = text::take_width(prompt, width);
+        let prompt_width = text::width(&prompt);
+        let prompt = Self::themed_prompt(prompt, theme);
+        let lines: Vec<&str> = editor.buffer().split('\n').collect();
+        let (cursor_line, cursor_offset) = editor.cursor_line();
+        let spans = highlighter.map_or(Vec::new(), |highlighter| highlighter.highlight(editor.buffer(), editor.cursor()));
+
+        let error_rows = error.is_some() as u16;
+        let rows = (lines.len() as u16 + error_rows).min(layout.max_input_rows());
+        let resized = layout::set_input_rows(rows) != rows;
+        layout.input_rows = rows;
+        //the error row is given up before the line being edited
+        let text_rows = if rows > 1 { rows - error_rows } else { rows };
+        //when the input is taller than the zone, show the lines around the cursor
+        let first = (cursor_line + 1).saturating_sub(text_rows as usize);
+        let top = layout.input_top();
+
+        let mut cursor_at = (prompt_width as u16, top);
+        for (row, index) in (first..first + text_rows as usize).enumerate() {
+            let y = top + row as u16;
+            let lead = if index == 0 { prompt.clone() } else { " ".repeat(prompt_width) };
+            let room = width - prompt_width;
+            let line = lines.get(index).copied().unwrap_or("");
+            let range = if index == cursor_line {
+                let (range, column) = editor::view_range(line, cursor_offset, room);
+                cursor_at = ((prompt_width + column) as u16, y);
+                range
+            } else {
+                0..text::take_width(line, room).len()
+            };
+            let offset: usize = lines.iter().take(index).map(|line| line.len() + 1).sum();
+            let visible = highlighter::styled(line, offset, range.start, range.end, &spans);
+            stdout.move_to(0, y)?;
+            stdout.clear_line()?;
+            stdout.print(&lead)?;
+            stdout.print(&visible)?;
+            //the suggestion's first line goes dim after the cursor, in what room the line leaves
+            if let (Some(rest), true) = (editor.suggestion(), index == cursor_line) {
+                let rest = rest.split('\n').next().unwrap_or("");
+                let room = room.saturating_sub(text::width(&line[range.clone()]) + 1);
+                if room > 0 && !rest.is_empty() {
+                    stdout.print(&theme.suggestion.paint(&text::take_width(rest, room)))?;
+                }
+            }
+        }
+        if let (Some(error), true) = (error, text_rows < rows) {
+            stdout.move_to(0, top + text_rows)?;
+            stdout.clear_line()?;
+            stdout.print(&theme.error.paint(&format!("{}✖ {}", " ".repeat(prompt_width), text::take_width(error, width.saturating_sub(prompt_width + 2)))))?;
+        }
+        stdout.move_to(cursor_at.0, cursor_at.1)?;
+        stdout.flush()?;
+        Ok(resized)
+    }
+
+    /**
+     * Draw a select() picker in the input zone: the matching items above the prompt line, the selected one in reverse video.
+     * The list scrolls to keep the selection in view and takes at most MAX_SELECT_ROWS rows (and no more than draw_input may).
+     * Returns true when the input zone changed height, like draw_input.
+     */
+    pub fn draw_select(stdout: &mut B, dialog: &SelectDialog, theme: &Theme) -> Result<bool> {
+        let (width, height) = stdout.size();
+        let mut layout = Layout::from_size(width, height, false);
+        let width = layout.width as usize;
+        let matches = dialog.matches();
+        let list_rows = matches.len().clamp(1, MAX_SELECT_ROWS).min(layout.max_input_rows().saturating_sub(1) as usize);
+        let rows = list_rows as u16 + 1;
+        let resized = layout::set_input_rows(rows) != rows;
+        layout.input_rows = rows;
+        let top = layout.input_top();
+        //the selection stays on the last list row once it goes past it
+        let first = (dialog.selected + 1).saturating_sub(list_rows);
+
+        for row in 0..list_rows {
+            stdout.move_to(0, top + row as u16)?;
+            stdout.clear_line()?;
+            match matches.get(first + row) {
+                Some(index) if first + row == dialog.selected => {
+                    let item = text::take_width(&dialog.items[*index], width.saturating_sub(2));
+                    stdout.print(&theme.selected.paint(&format!("> {}", item)))?;
+                },
+                Some(index) => stdout.print(&format!("  {}", text::take_width(&dialog.items[*index], width.saturating_sub(2))))?,
+                None if row == 0 => stdout.print(&theme.suggestion.paint("  (no match)"))?,
+                None => {},
+            }
+        }
+        let prompt = text::take_width(&dialog.prompt(), width);
+        let prompt_width = text::width(&prompt);
+        let (filter, column) = dialog.filter.view(width - prompt_width);
+        let y = top + list_rows as u16;
+        stdout.move_to(0, y)?;
+        stdout.clear_line()?;
+        stdout.print(&prompt)?;
+        stdout.print(&filter)?;
+        stdout.move_to((prompt_width + column) as u16, y)?;
+        stdout.flush()?;
+        Ok(resized)
+    }
+
+    /**
+     * Hide the cursor while an output zone has the focus, so it is clear typing doesn't go to the input line
+     */
+    pub fn show_cursor(stdout: &mut B, visible: bool) -> Result<()> {
+        stdout.show_cursor(visible)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /**
+     * Put a fresh prompt on the input row, called by the input task after every submitted line
+     */
+    pub fn draw_prompt(stdout: &mut B, prompt: &str, theme: &Theme) -> Result<()> {
+        let (width, height) = stdout.size();
+        let layout = Layout::from_size(width, height, false);
+        stdout.move_to(0, layout.input_row())?;
+        stdout.clear_line()?;
+        stdout.print(&Self::themed_prompt(prompt.to_string(), theme))?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /**
+     * The prompt in the theme's prompt style, unless the application colored it itself
+     */
+    fn themed_prompt(prompt: String, theme: &Theme) -> String {
+        match &theme.prompt {
+            Some(style) if !prompt.contains('\u{1b}') => style.paint(&prompt),
+            _ => prompt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::backend::TestBackend;
+    use crate::output::expiry::ExpiryPolicy;
+    use crate::output::zone::{ZoneSpec, MAIN_ZONE};
+    use crate::user_input::structs::PriorityStatus;
+    use tokio::time::{Duration, Instant};
+
+    fn zones(specs: &[ZoneSpec], arrangement: Arrangement) -> ZoneSet {
+        ZoneSet::from_specs(specs, arrangement, &ExpiryPolicy::default())
+    }
+
+    fn main_zone() -> ZoneSet {
+        zones(&[ZoneSpec::new(MAIN_ZONE.to_string())], Arrangement::Stacked)
+    }
+
+    fn message(priority: PriorityStatus, text: &str) -> Message {
+        Message::new("test".to_string(), priority, text)
+    }
+
+    fn draw(zones: &ZoneSet, width: u16, height: u16, status_bar: bool) -> Vec<String> {
+        let mut renderer = Renderer::new(TestBackend::new(width, height), status_bar);
+        renderer.draw_zones(zones).unwrap();
+        renderer.backend().lines()
+    }
+
+    #[test]
+    fn lines_are_bottom_aligned_above_the_input_row() {
+        let mut zones = main_zone();
+        zones.push(message(PriorityStatus::Normal, "first"));
+        zones.push(message(PriorityStatus::Normal, "second"));
+        let rows = draw(&zones, 20, 6, false);
+        assert_eq!(rows, ["", "", "", "first", "second", ""]);
+    }
+
+    #[test]
+    fn the_status_bar_takes_the_row_above_the_input() {
+        let mut zones = main_zone();
+        zones.push(message(PriorityStatus::Normal, "first"));
+        zones.push(message(PriorityStatus::Normal, "second"));
+        let rows = draw(&zones, 20, 6, true);
+        assert_eq!(rows, ["", "", "first", "second", "", ""]);
+    }
+
+    #[test]
+    fn only_the_newest_lines_that_fit_are_drawn() {
+        let mut zones = main_zone();
+        for n in 0..10 {
+            zones.push(message(PriorityStatus::Normal, &format!("line {}", n)));
+        }
+        let rows = draw(&zones, 20, 4, false);
+        assert_eq!(rows, ["line 7", "line 8", "line 9", ""]);
+    }
+
+    #[test]
+    fn side_by_side_zones_get_headers_and_a_divider() {
+        let specs = [ZoneSpec::new("left".to_string()), ZoneSpec::new("right".to_string())];
+        let mut zones = zones(&specs, Arrangement::SideBySide);
+        let mut right = message(PriorityStatus::Normal, "there");
+        right.zone = Some("right".to_string());
+        zones.push(message(PriorityStatus::Normal, "here"));
+        zones.push(right);
+        let rows = draw(&zones, 21, 4, false);
+        assert_eq!(rows, ["─ left ───│─ right ──", "          │", "here      │there", ""]);
+    }
+
+    #[test]
+    fn stacked_zones_split_the_rows() {
+        let specs = [ZoneSpec::new("top".to_string()), ZoneSpec::new("bottom".to_string())];
+        let mut zones = zones(&specs, Arrangement::Stacked);
+        let mut bottom = message(PriorityStatus::Normal, "below");
+        bottom.zone = Some("bottom".to_string());
+        zones.push(message(PriorityStatus::Normal, "above"));
+        zones.push(bottom);
+        let rows = draw(&zones, 10, 7, false);
+        assert_eq!(rows, ["─ top ────", "", "above", "─ bottom ─", "", "below", ""]);
+    }
+
+    #[test]
+    fn long_lines_wrap_with_a_continuation_indent() {
+        let mut zones = main_zone();
+        zones.push(message(PriorityStatus::Normal, "alpha beta gamma delta"));
+        let rows = draw(&zones, 12, 4, false);
+        assert_eq!(rows, ["alpha beta", "  gamma", "  delta", ""]);
+    }
+
+    #[test]
+    fn a_truncating_zone_cuts_long_lines() {
+        let spec = ZoneSpec::new(MAIN_ZONE.to_string()).with_line_mode(LineMode::Truncate);
+        let mut zones = zones(&[spec], Arrangement::Stacked);
+        zones.push(message(PriorityStatus::Normal, "alpha beta gamma delta"));
+        let rows = draw(&zones, 12, 3, false);
+        assert_eq!(rows, ["", "alpha beta …", ""]);
+    }
+
+    #[test]
+    fn expired_lines_are_gone_from_the_next_frame() {
+        let mut zones = main_zone();
+        zones.push(message(PriorityStatus::Critical, "stays"));
+        zones.push(message(PriorityStatus::Warning, "lingers"));
+        zones.push(message(PriorityStatus::Normal, "fades"));
+        assert_eq!(draw(&zones, 20, 4, false), ["stays", "lingers", "fades", ""]);
+
+        assert!(zones.expire(Instant::now() + Duration::from_secs(3)));
+        assert_eq!(draw(&zones, 20, 4, false), ["", "stays", "lingers", ""]);
+
+        assert!(zones.expire(Instant::now() + Duration::from_secs(11)));
+        assert_eq!(draw(&zones, 20, 4, false), ["", "", "stays", ""]);
+        assert!(!zones.expire(Instant::now() + Duration::from_secs(3600)));
+    }
+}
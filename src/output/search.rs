@@ -0,0 +1,132 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+/**
+ * What the user asked the scrollback search to do, from a keybind in the input zone or /search
+ *  ~ Query starts a new search (or refines the current one while typing) and jumps to the newest match.
+ *  ~ Older and Newer jump between matches.
+ *  ~ Clear ends the search and the zone goes back to following live output.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchCommand {
+    Query(String),
+    Older,
+    Newer,
+    Clear,
+}
+
+/**
+ * An active search over one zone's scrollback.
+ * Matches are absolute scrollback positions (they keep counting when old lines are evicted), oldest first.
+ */
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub query: String,
+    pub matches: Vec<u64>,
+    pub current: Option<usize>,
+}
+
+impl Search {
+    pub fn new(query: String) -> Self {
+        Search { query, matches: Vec::new(), current: None }
+    }
+
+    /**
+     * ASCII case-insensitive so byte offsets in the lowered text line up with the original
+     */
+    pub fn is_match(&self, text: &str) -> bool {
+        !self.query.is_empty() && text.to_ascii_lowercase().contains(&self.query.to_ascii_lowercase())
+    }
+
+    /**
+     * Jump to the last (newest) match
+     */
+    pub fn newest(&mut self) {
+        self.current = if self.matches.is_empty() { None } else { Some(self.matches.len() - 1) };
+    }
+
+    pub fn older(&mut self) {
+        if let Some(current) = self.current {
+            self.current = Some(current.saturating_sub(1));
+        }
+    }
+
+    pub fn newer(&mut self) {
+        if let Some(current) = self.current {
+            self.current = Some((current + 1).min(self.matches.len().saturating_sub(1)));
+        }
+    }
+
+    /**
+     * Scrollback position of the match being shown
+     */
+    pub fn position(&self) -> Option<u64> {
+        self.current.and_then(|current| self.matches.get(current).copied())
+    }
+
+    /**
+     * Drop matches that fell out of the scrollback, keeping current on the same line where it still exists
+     */
+    pub fn evicted(&mut self, first_kept: u64) {
+        let gone = self.matches.iter().take_while(|position| **position < first_kept).count();
+        if gone == 0 {
+            return;
+        }
+        self.matches.drain(..gone);
+        self.current = match self.current {
+            _ if self.matches.is_empty() => None,
+            Some(current) => Some(current.saturating_sub(gone)),
+            None => None,
+        };
+    }
+
+    /**
+     * Drop the match at a position that was evicted out of order, keeping current on the same line where it still exists
+     */
+    pub fn removed(&mut self, position: u64) {
+        let index = match self.matches.binary_search(&position) {
+            Ok(index) => index,
+            Err(_) => return,
+        };
+        self.matches.remove(index);
+        self.current = match self.current {
+            _ if self.matches.is_empty() => None,
+            Some(current) if current > index => Some(current - 1),
+            Some(current) => Some(current.min(self.matches.len() - 1)),
+            None => None,
+        };
+    }
+
+    /**
+     * "search "term" 3/12" for the status bar, counting from the newest match
+     */
+    pub fn status(&self) -> String {
+        match self.current {
+            Some(current) => format!("search \"{}\" {}/{}", self.query, self.matches.len() - current, self.matches.len()),
+            None => format!("search \"{}\" no matches", self.query),
+        }
+    }
+
+    /**
+     * Wrap every occurrence of the query in a drawn row with reverse video
+     */
+    pub fn highlight(&self, row: &str) -> String {
+        if self.query.is_empty() {
+            return row.to_string();
+        }
+        let lowered = row.to_ascii_lowercase();
+        let needle = self.query.to_ascii_lowercase();
+        let mut out = String::with_capacity(row.len());
+        let mut last = 0;
+        for (start, found) in lowered.match_indices(&needle) {
+            out.push_str(&row[last..start]);
+            out.push_str("\u{1b}[7m");
+            out.push_str(&row[start..start + found.len()]);
+            out.push_str("\u{1b}[27m");
+            last = start + found.len();
+        }
+        out.push_str(&row[last..]);
+        out
+    }
+}
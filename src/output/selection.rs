@@ -0,0 +1,90 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::ops::Range;
+use crate::output::ansi::{self, AnsiPolicy};
+use crate::output::layout::Rect;
+use crate::output::text;
+
+/**
+ * What a mouse drag does to the selection
+ *  ~ Start drops the old selection and anchors a new one at a cell of the given zone.
+ *  ~ Extend moves the other end of the selection, the drag itself.
+ *  ~ Clear drops the selection.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectCommand {
+    Start { zone: usize, column: u16, row: u16 },
+    Extend { column: u16, row: u16 },
+    Clear,
+}
+
+/**
+ * Text selected with the mouse in one output zone, in screen cells like a terminal selection.
+ * anchor is where the drag started and head where it is now, either may come first on screen.
+ * It selects what is drawn there, so a new message in a zone that is neither paused nor scrolled back drops it.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub zone: usize,
+    pub anchor: (u16, u16),
+    pub head: (u16, u16),
+}
+
+impl Selection {
+    pub fn new(zone: usize, column: u16, row: u16) -> Self {
+        Selection { zone, anchor: (column, row), head: (column, row) }
+    }
+
+    /**
+     * Start and end cell in reading order, both as (column, row)
+     */
+    fn ordered(&self) -> ((u16, u16), (u16, u16)) {
+        let (a, b) = (self.anchor, self.head);
+        if (a.1, a.0) <= (b.1, b.0) { (a, b) } else { (b, a) }
+    }
+
+    /**
+     * A click selects nothing, only a drag does
+     */
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    /**
+     * The selected columns of one screen row, relative to the zone's left edge. The end cell is included
+     */
+    pub fn columns(&self, row: u16, rect: Rect) -> Option<Range<usize>> {
+        if self.is_empty() {
+            return None;
+        }
+        let (start, end) = self.ordered();
+        if row < start.1 || row > end.1 {
+            return None;
+        }
+        let left = if row == start.1 { start.0.saturating_sub(rect.x) as usize } else { 0 };
+        let right = if row == end.1 { (end.0.saturating_sub(rect.x) as usize + 1).min(rect.width as usize) } else { rect.width as usize };
+        if left >= right { None } else { Some(left..right) }
+    }
+}
+
+/**
+ * Draw the selected columns of an already padded row in reverse video, colors inside the selection are dropped
+ */
+pub fn highlight(row: &str, columns: Range<usize>) -> String {
+    let (left, rest) = text::split_at_width(row, columns.start);
+    let (selected, right) = text::split_at_width(&rest, columns.end - columns.start);
+    format!("{}\u{1b}[7m{}\u{1b}[27m{}", left, ansi::apply(&selected, &AnsiPolicy::Strip), right)
+}
+
+/**
+ * The plain text of the selected columns of one row, trailing padding removed
+ */
+pub fn extract(row: &str, columns: Range<usize>) -> String {
+    let plain = ansi::apply(row, &AnsiPolicy::Strip);
+    let (_, rest) = text::split_at_width(&plain, columns.start);
+    let (selected, _) = text::split_at_width(&rest, columns.end - columns.start);
+    selected.trim_end().to_string()
+}
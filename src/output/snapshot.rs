@@ -0,0 +1,320 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::broker::handle::SenderHandle;
+use crate::broker::message::{ConsoleEvent, Output, USER_SENDER};
+use crate::output::backend::TestBackend;
+use crate::output::renderer::Renderer;
+use crate::output::zone::ZoneSet;
+use crate::user_input::structs::{Console, PriorityStatus};
+
+//External
+use tokio::time::Duration;
+
+/**
+ * Why a snapshot script could not run, or what it found
+ */
+#[derive(Debug)]
+pub enum HarnessError {
+    Io(std::io::Error),
+    Script { line: usize, reason: String },
+    Console(String),
+    Mismatch { name: String, diff: String },
+    Missing(PathBuf),
+}
+
+impl fmt::Display for HarnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HarnessError::Io(e) => write!(f, "{}", e),
+            HarnessError::Script { line, reason } => write!(f, "line {}: {}", line, reason),
+            HarnessError::Console(e) => write!(f, "console: {}", e),
+            HarnessError::Mismatch { name, diff } => write!(f, "snapshot {} differs:\n{}", name, diff),
+            HarnessError::Missing(path) => write!(f, "no golden snapshot at {}, run with --update to create it", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for HarnessError {}
+
+/**
+ * Drives a Console without a terminal and renders its output zones into a TestBackend.
+ *  ~ Every step waits until the broker has handled everything sent so far, so what is drawn doesn't depend on timing.
+ *  ~ The clock is tokio's, run it paused (the snapshot feature turns on tokio's test-util) and wait() moves it forward,
+ *    so expiry is exact too.
+ *  ~ The status bar row is left empty, it shows the time of day.
+ *  ~ What is drawn follows the terminal: while paused only the user's own actions (scrolling, /search, /resume, ...) redraw the zones.
+ */
+pub struct Harness {
+    console: Console,
+    zones: ZoneSet,
+    renderer: Renderer<TestBackend>,
+    senders: HashMap<String, SenderHandle>,
+    stale: bool,
+}
+
+impl Harness {
+    pub fn new(console: Console, width: u16, height: u16) -> Self {
+        let renderer = Renderer::new(TestBackend::new(width, height), console.status_bar).with_theme(console.screen_theme());
+        let zones = console.zone_set();
+        Harness { console, zones, renderer, senders: HashMap::new(), stale: true }
+    }
+
+    /**
+     * Send a message as the named module, the sender is registered the first time it is used
+     */
+    pub async fn send(&mut self, sender: &str, priority: PriorityStatus, topic: Option<String>, text: String) -> Result<(), HarnessError> {
+        let console = &mut self.console;
+        let handle = self.senders.entry(sender.to_string()).or_insert_with(|| console.new_sender(sender.to_string()));
+        let sent = match topic {
+            Some(topic) => handle.send_to_topic(topic, text, priority).await,
+            None => handle.send_with_priority(text, priority).await,
+        };
+        sent.map_err(|e| HarnessError::Console(e.to_string()))?;
+        self.settle().await
+    }
+
+    /**
+     * A line typed into the input zone, /commands included
+     */
+    pub async fn input(&mut self, line: String) -> Result<(), HarnessError> {
+        self.console.tx.send(ConsoleEvent::UserInput(line)).await.map_err(|_| HarnessError::Console("channel closed".to_string()))?;
+        self.settle().await
+    }
+
+    /**
+     * Let time pass, messages whose time is up expire
+     */
+    pub async fn wait(&mut self, duration: Duration) -> Result<(), HarnessError> {
+        tokio::time::sleep(duration).await;
+        if self.zones.expire(tokio::time::Instant::now()) {
+            self.stale |= !self.zones.is_paused();
+        }
+        self.settle().await
+    }
+
+    /**
+     * Apply everything the broker has for the output zones and draw them when that changed what is shown.
+     * A Redraw goes in behind whatever was sent, once it comes out the other end the broker is caught up.
+     */
+    pub async fn settle(&mut self) -> Result<(), HarnessError> {
+        self.console.tx.send(ConsoleEvent::Redraw).await.map_err(|_| HarnessError::Console("channel closed".to_string()))?;
+        loop {
+            //what is sent only shows while not paused, what the user does always does
+            let sent = !self.zones.is_paused();
+            let redraw = match self.console.next_output().await {
+                Some(Output::Redraw) => break,
+                Some(Output::Line(message)) => { self.zones.push(message); sent },
+                Some(Output::Private { to, message }) if to == USER_SENDER => { self.zones.push(message); sent },
+                Some(Output::Dismiss(id)) => { self.zones.dismiss(id); sent },
+                Some(Output::Retract { sender, id }) => { self.zones.retract(&sender, id); sent },
+                Some(Output::Search(command)) => { self.zones.search(command); true },
+                Some(Output::Pause(paused)) => { self.zones.set_paused(paused); !paused },
+                Some(Output::Scroll(command)) => { self.zones.scroll(command); true },
+                Some(Output::Focus(focus)) => { self.zones.set_focus(focus); true },
+                Some(Output::Select(command)) => { self.zones.select(command); true },
+                Some(Output::Highlights(highlights)) => { self.zones.highlights = highlights; true },
+                Some(Output::Window { spec, tab }) => self.zones.add_window(&spec, tab) && !tab,
+                Some(Output::View(name)) => { self.zones.view(name.as_deref()); true },
+                Some(Output::Tab(command)) => { self.zones.tab(command); true },
+                Some(Output::Split(panes)) => { self.zones.split(panes); true },
+                //nothing to draw, the clipboard, files and bell are left alone
+                Some(Output::Copy(_)) | Some(Output::Export { .. }) | Some(Output::Notify { .. }) | Some(Output::Detach) | Some(Output::Private { .. }) => false,
+                None => return Err(HarnessError::Console("console shut down".to_string())),
+            };
+            self.stale |= redraw;
+        }
+        if std::mem::take(&mut self.stale) {
+            self.renderer.draw_zones(&self.zones).map_err(HarnessError::Io)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * What is on screen, one line per row with trailing blanks removed
+     */
+    pub fn snapshot(&self) -> String {
+        self.renderer.backend().to_string()
+    }
+
+    pub fn backend(&self) -> &TestBackend {
+        self.renderer.backend()
+    }
+
+    /**
+     * Compare the screen with a golden file, or write the file when update is set
+     */
+    pub fn assert_snapshot(&self, name: &str, golden: &Path, update: bool) -> Result<(), HarnessError> {
+        let actual = self.snapshot();
+        if update {
+            std::fs::write(golden, format!("{}\n", actual)).map_err(HarnessError::Io)?;
+            return Ok(());
+        }
+        let expected = match std::fs::read_to_string(golden) {
+            Ok(expected) => expected,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(HarnessError::Missing(golden.to_path_buf())),
+            Err(e) => return Err(HarnessError::Io(e)),
+        };
+        let expected = expected.strip_suffix('\n').unwrap_or(&expected);
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(HarnessError::Mismatch { name: name.to_string(), diff: diff(expected, &actual) })
+        }
+    }
+}
+
+/**
+ * The rows that differ, "-" is the golden file and "+" what was drawn
+ */
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = Vec::new();
+    for row in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(row).copied().unwrap_or(""), actual.get(row).copied().unwrap_or(""));
+        if old != new {
+            out.push(format!("row {}:\n-|{}|\n+|{}|", row, old, new));
+        }
+    }
+    out.join("\n")
+}
+
+/**
+ * Run a snapshot script. One step per line, # starts a comment:
+ *
+ *     size 40x10
+ *     send build Normal compiling
+ *     send build Warning @ci slow test
+ *     input /pause
+ *     wait 2.5
+ *     snapshot paused
+ *
+ *  ~ send <sender> <priority> [@topic] <text> sends a message as a module.
+ *  ~ snapshot <name> checks the screen against <script>.<name>.snap next to the script.
+ *  ~ size has to come before the first other step, the default is 80x24.
+ * The console is the one passed in, so its zones and config apply. Returns how many snapshots were checked.
+ */
+pub async fn run_script(console: Console, script: &Path, update: bool) -> Result<usize, HarnessError> {
+    let text = std::fs::read_to_string(script).map_err(HarnessError::Io)?;
+    let mut console = Some(console);
+    let mut harness: Option<Harness> = None;
+    let mut size = (80, 24);
+    let mut checked = 0;
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let fail = |reason: &str| HarnessError::Script { line: line_number, reason: reason.to_string() };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (step, rest) = line.split_once(' ').map(|(step, rest)| (step, rest.trim())).unwrap_or((line, ""));
+        if step == "size" {
+            if harness.is_some() {
+                return Err(fail("size has to come first"));
+            }
+            let parsed = rest.split_once('x').and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+            size = parsed.ok_or_else(|| fail("size is WIDTHxHEIGHT"))?;
+            continue;
+        }
+        if harness.is_none() {
+            harness = Some(Harness::new(console.take().unwrap(), size.0, size.1));
+        }
+        let harness = harness.as_mut().unwrap();
+        match step {
+            "send" => {
+                let mut parts = rest.splitn(3, ' ');
+                let sender = parts.next().filter(|sender| !sender.is_empty()).ok_or_else(|| fail("send needs a sender"))?;
+                let priority = parts.next().and_then(PriorityStatus::parse).ok_or_else(|| fail("unknown priority"))?;
+                let text = parts.next().unwrap_or("");
+                let (topic, text) = match text.strip_prefix('@') {
+                    Some(tagged) => match tagged.split_once(' ') {
+                        Some((topic, text)) => (Some(topic.to_string()), text),
+                        None => (Some(tagged.to_string()), ""),
+                    },
+                    None => (None, text),
+                };
+                harness.send(sender, priority, topic, text.to_string()).await?;
+            },
+            "input" => harness.input(rest.to_string()).await?,
+            "wait" => {
+                let secs = rest.parse::<f64>().ok().filter(|secs| *secs >= 0.0).ok_or_else(|| fail("wait takes seconds"))?;
+                harness.wait(Duration::from_secs_f64(secs)).await?;
+            },
+            "snapshot" => {
+                if rest.is_empty() {
+                    return Err(fail("snapshot needs a name"));
+                }
+                harness.assert_snapshot(rest, &golden_path(script, rest), update)?;
+                checked += 1;
+            },
+            other => return Err(fail(&format!("unknown step {}", other))),
+        }
+    }
+    Ok(checked)
+}
+
+/**
+ * The file extension of a snapshot script
+ */
+pub const SCRIPT_EXTENSION: &str = "dzs";
+
+/**
+ * The scripts a path stands for, a file is one and a directory is every .dzs file in it, in name order
+ */
+pub fn scripts(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut scripts = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let script = entry?.path();
+        if script.extension().is_some_and(|extension| extension == SCRIPT_EXTENSION) {
+            scripts.push(script);
+        }
+    }
+    scripts.sort();
+    Ok(scripts)
+}
+
+/**
+ * Where the golden file of a snapshot lives, eg. tests/pause.dzs and paused give tests/pause.dzs.paused.snap
+ */
+pub fn golden_path(script: &Path, name: &str) -> PathBuf {
+    let mut file = script.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+    file.push(format!(".{}.snap", name));
+    script.with_file_name(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Every script checked in under tests/snapshots against its golden files, dualzone-test --update rewrites them
+     */
+    #[test]
+    fn golden_snapshots() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots");
+        let scripts = scripts(&dir).unwrap();
+        assert!(!scripts.is_empty(), "no scripts in {}", dir.display());
+        for script in scripts {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().start_paused(true).build().unwrap();
+            let console = Console::builder().status_bar(true).build();
+            match runtime.block_on(run_script(console, &script, false)) {
+                Ok(checked) => assert!(checked > 0, "{} checks no snapshot", script.display()),
+                Err(e) => panic!("{}: {}", script.display(), e),
+            }
+        }
+    }
+
+    #[test]
+    fn golden_files_sit_next_to_the_script() {
+        assert_eq!(golden_path(Path::new("tests/pause.dzs"), "paused"), Path::new("tests/pause.dzs.paused.snap"));
+    }
+}
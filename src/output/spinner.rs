@@ -0,0 +1,74 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::handle::{HandleError, SenderHandle};
+use crate::broker::message::{self, Message};
+use crate::output::widgets::{SpinnerState, Widget, WidgetState};
+use crate::user_input::structs::PriorityStatus;
+
+/**
+ * An animated task indicator in the output zone, obtained with SenderHandle::spinner().
+ *  ~ The animation is driven by the renderer, the owning module only sends when the status text changes.
+ *  ~ success() and fail() replace the spinner with a ✔ or ✖ line which then expires like any other message.
+ *  ~ Dropping a spinner that was never resolved marks it failed.
+ */
+pub struct Spinner<T = ()> {
+    handle: SenderHandle<T>,
+    slot: u64,
+    label: String,
+    status: Option<String>,
+    ended: bool,
+}
+
+impl<T> Spinner<T> {
+    /**
+     * Created through SenderHandle::spinner(), the first frame is sent right away
+     */
+    pub(crate) fn new(handle: SenderHandle<T>, label: String) -> Self {
+        let spinner = Spinner { handle, slot: message::next_id(), label, status: None, ended: false };
+        let _ = spinner.handle.try_send_message(spinner.message(WidgetState::Running));
+        spinner
+    }
+
+    fn message(&self, state: WidgetState) -> Message {
+        let widget = Widget::Spinner(SpinnerState { label: self.label.clone(), status: self.status.clone(), state });
+        Message::new(self.handle.id().to_string(), PriorityStatus::Normal, widget.plain_text()).with_widget(self.slot, widget)
+    }
+
+    /**
+     * Change the status text shown after the label
+     */
+    pub async fn set_status(&mut self, status: String) -> Result<(), HandleError> {
+        self.status = Some(status);
+        self.handle.send_message(self.message(WidgetState::Running)).await.map(|_| ())
+    }
+
+    /**
+     * Resolve the spinner with a success glyph and an optional final status
+     */
+    pub async fn success(mut self, status: Option<String>) -> Result<(), HandleError> {
+        self.ended = true;
+        if status.is_some() {
+            self.status = status;
+        }
+        self.handle.send_message(self.message(WidgetState::Done)).await.map(|_| ())
+    }
+
+    /**
+     * Resolve the spinner with a failure glyph and the reason
+     */
+    pub async fn fail(mut self, reason: String) -> Result<(), HandleError> {
+        self.ended = true;
+        self.handle.send_message(self.message(WidgetState::Aborted(reason))).await.map(|_| ())
+    }
+}
+
+impl<T> Drop for Spinner<T> {
+    fn drop(&mut self) {
+        if !self.ended {
+            let _ = self.handle.try_send_message(self.message(WidgetState::Aborted("dropped".to_string())));
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::user_input::structs::Console;
+use crate::output::text;
+use crate::output::zone::ZoneSet;
+
+//External
+use chrono::Local;
+
+/**
+ * The one line status bar shown between the output and input zones.
+ * Built from the live Console state every time it is drawn, so it stays current even when no messages arrive.
+ */
+pub struct StatusBar;
+
+impl StatusBar {
+    /**
+     * Render the status line for the given width, cut to fit
+     */
+    pub fn line(console: &Console, zones: &ZoneSet, width: u16) -> String {
+        let channel = console.rx.stats();
+        let pending = channel.depth + channel.overflow_depth + console.delayed.len();
+        let mut line = format!(
+            " senders: {} | pending: {} | level: {:?} | {}",
+            console.Authorized.len(),
+            pending,
+            console.filter_level,
+            Local::now().format("%H:%M:%S"),
+        );
+        if let Some(viewing) = zones.view_status() {
+            line.push_str(&format!(" | {}", viewing));
+        }
+        if let Some(paused) = zones.pause_status() {
+            line.push_str(&format!(" | {}", paused));
+        }
+        if let Some(focus) = zones.focus_status() {
+            line.push_str(&format!(" | {}", focus));
+        }
+        if let Some(scrolled) = zones.scroll_status() {
+            line.push_str(&format!(" | {}", scrolled));
+        }
+        if let Some(search) = zones.search_status() {
+            line.push_str(&format!(" | {}", search));
+        }
+        text::take_width(&line, width as usize)
+    }
+}
@@ -0,0 +1,194 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::output::text;
+
+//External
+use serde::{Serialize, Deserialize};
+
+/**
+ * Narrowest a column is squeezed to before the table stops shrinking
+ */
+const MIN_COLUMN_WIDTH: usize = 3;
+
+/**
+ * Horizontal alignment of the cells in a column
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/**
+ * What happens to a cell that doesn't fit its column once the table is squeezed to the zone width
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CellOverflow {
+    Truncate,
+    Wrap,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Column {
+    pub title: String,
+    pub align: Align,
+}
+
+/**
+ * Tabular output drawn with box-drawing characters, built up and then sent with SenderHandle::send_table().
+ *  ~ Columns are sized to their content and squeezed, widest first, when the zone is too narrow.
+ *  ~ Rows shorter than the column list are padded with empty cells, extra cells are ignored.
+ *  ~ The header row is drawn unless header(false) is set.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<String>>,
+    pub header: bool,
+    pub overflow: CellOverflow,
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table { columns: Vec::new(), rows: Vec::new(), header: true, overflow: CellOverflow::Truncate }
+    }
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Table::default()
+    }
+
+    pub fn column(mut self, title: String, align: Align) -> Self {
+        self.columns.push(Column { title, align });
+        self
+    }
+
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn overflow(mut self, overflow: CellOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn row(mut self, cells: Vec<String>) -> Self {
+        self.rows.push(cells);
+        self
+    }
+
+    pub fn push_row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    fn cell(row: &[String], column: usize) -> &str {
+        row.get(column).map(|cell| cell.as_str()).unwrap_or("")
+    }
+
+    /**
+     * One line per row with cells separated by " | ", used as the message payload
+     */
+    pub fn plain_text(&self) -> String {
+        let mut lines = Vec::new();
+        if self.header {
+            lines.push(self.columns.iter().map(|c| c.title.as_str()).collect::<Vec<&str>>().join(" | "));
+        }
+        for row in self.rows.iter() {
+            lines.push((0..self.columns.len()).map(|i| Table::cell(row, i)).collect::<Vec<&str>>().join(" | "));
+        }
+        lines.join("\n")
+    }
+
+    /**
+     * Column widths that fit in width columns including borders and padding
+     */
+    fn column_widths(&self, width: usize) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.columns.iter().enumerate().map(|(i, column)| {
+            let title = if self.header { text::width(&column.title) } else { 0 };
+            self.rows.iter().map(|row| text::width(Table::cell(row, i))).max().unwrap_or(0).max(title).max(1)
+        }).collect();
+        //every column costs its content, a space either side and one border
+        let chrome = widths.len() * 3 + 1;
+        let available = width.saturating_sub(chrome);
+        while widths.iter().sum::<usize>() > available {
+            let (widest, &current) = match widths.iter().enumerate().max_by_key(|(_, w)| **w) {
+                Some(found) => found,
+                None => break,
+            };
+            if current <= MIN_COLUMN_WIDTH {
+                break;
+            }
+            widths[widest] = current - 1;
+        }
+        widths
+    }
+
+    /**
+     * Break a cell into the lines it takes in a column of the given width
+     */
+    fn cell_lines(&self, cell: &str, width: usize) -> Vec<String> {
+        match self.overflow {
+            CellOverflow::Truncate => vec![text::truncate(cell, width)],
+            CellOverflow::Wrap => text::wrap(cell, width, 0),
+        }
+    }
+
+    fn align(cell: &str, width: usize, align: &Align) -> String {
+        let cell = text::take_width(cell, width);
+        let missing = width - text::width(&cell);
+        let (left, right) = match align {
+            Align::Left => (0, missing),
+            Align::Right => (missing, 0),
+            Align::Center => (missing / 2, missing - missing / 2),
+        };
+        format!("{}{}{}", " ".repeat(left), cell, " ".repeat(right))
+    }
+
+    fn border(widths: &[usize], left: char, middle: char, right: char) -> String {
+        let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        format!("{}{}{}", left, segments.join(&middle.to_string()), right)
+    }
+
+    /**
+     * The rows of one table row, more than one when a cell wraps
+     */
+    fn draw_row(&self, cells: &[&str], widths: &[usize]) -> Vec<String> {
+        let split: Vec<Vec<String>> = cells.iter().zip(widths.iter()).map(|(cell, w)| self.cell_lines(cell, *w)).collect();
+        let height = split.iter().map(|lines| lines.len()).max().unwrap_or(1);
+        (0..height).map(|line| {
+            let parts: Vec<String> = split.iter().enumerate().map(|(i, lines)| {
+                let text = lines.get(line).map(|s| s.as_str()).unwrap_or("");
+                format!(" {} ", Table::align(text, widths[i], &self.columns[i].align))
+            }).collect();
+            format!("│{}│", parts.join("│"))
+        }).collect()
+    }
+
+    /**
+     * Draw the table for a zone width columns wide, one string per terminal row
+     */
+    pub fn render(&self, width: usize) -> Vec<String> {
+        if self.columns.is_empty() {
+            return Vec::new();
+        }
+        let widths = self.column_widths(width);
+        let mut lines = vec![Table::border(&widths, '┌', '┬', '┐')];
+        if self.header {
+            let titles: Vec<&str> = self.columns.iter().map(|c| c.title.as_str()).collect();
+            lines.extend(self.draw_row(&titles, &widths));
+            lines.push(Table::border(&widths, '├', '┼', '┤'));
+        }
+        for row in self.rows.iter() {
+            let cells: Vec<&str> = (0..self.columns.len()).map(|i| Table::cell(row, i)).collect();
+            lines.extend(self.draw_row(&cells, &widths));
+        }
+        lines.push(Table::border(&widths, '└', '┴', '┘'));
+        lines
+    }
+}
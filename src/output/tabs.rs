@@ -0,0 +1,111 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+/**
+ * What the tab keybinds and /tab do
+ *  ~ Next and Previous move to the tab beside the one shown, wrapping around.
+ *  ~ Show goes to the named tab, None to the first one that has everything.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum TabCommand {
+    Next,
+    Previous,
+    Show(Option<String>),
+}
+
+/**
+ * One tab of the tab bar, unread counts what arrived in it since it was last shown
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tab {
+    pub name: String,
+    pub unread: u64,
+}
+
+/**
+ * The tabs above the main output zone when tabs are on, see Console::enable_tabs.
+ *  ~ The first tab is the main zone itself with every message, the others are made as topics and modules send to it,
+ *    a message with a topic goes in the topic's tab and any other in its module's.
+ *  ~ Each tab keeps its own scrollback, the shown one is swapped into the main zone's place like /view does.
+ */
+#[derive(Debug, Clone)]
+pub struct TabBar {
+    tabs: Vec<Tab>,
+}
+
+impl TabBar {
+    pub fn new(all: &str) -> Self {
+        TabBar { tabs: vec![Tab { name: all.to_string(), unread: 0 }] }
+    }
+
+    pub fn tabs(&self) -> &[Tab] {
+        &self.tabs
+    }
+
+    /**
+     * The name of the tab with every message
+     */
+    pub fn all(&self) -> &str {
+        &self.tabs[0].name
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tabs.iter().any(|tab| tab.name == name)
+    }
+
+    /**
+     * Add a tab after the others, false when it is already there
+     */
+    pub fn add(&mut self, name: &str) -> bool {
+        if self.contains(name) {
+            return false;
+        }
+        self.tabs.push(Tab { name: name.to_string(), unread: 0 });
+        true
+    }
+
+    /**
+     * A message arrived in the named tab, it is unread unless that tab is the one shown
+     */
+    pub fn arrived(&mut self, name: &str, shown: &str) {
+        if name == shown {
+            return;
+        }
+        if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.name == name) {
+            tab.unread += 1;
+        }
+    }
+
+    /**
+     * The named tab is shown, everything in it is read
+     */
+    pub fn shown(&mut self, name: &str) {
+        if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.name == name) {
+            tab.unread = 0;
+        }
+    }
+
+    /**
+     * The tab step places away from shown, wrapping around. None when shown is not a tab
+     */
+    pub fn beside(&self, shown: &str, step: isize) -> Option<&str> {
+        let index = self.tabs.iter().position(|tab| tab.name == shown)? as isize;
+        let count = self.tabs.len() as isize;
+        Some(&self.tabs[(index + step).rem_euclid(count) as usize].name)
+    }
+
+    /**
+     * The tab bar as drawn in the main zone's header, the shown tab in brackets and unread counts after the names
+     */
+    pub fn line(&self, shown: &str) -> String {
+        let labels: Vec<String> = self.tabs.iter().map(|tab| {
+            let name = if tab.name == shown { format!("[{}]", tab.name) } else { tab.name.clone() };
+            match tab.unread {
+                0 => name,
+                unread => format!("{} ({})", name, unread),
+            }
+        }).collect();
+        format!("─ {} ", labels.join(" │ "))
+    }
+}
@@ -0,0 +1,153 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/**
+ * How the real terminal is drawn to, picked once at startup by probe()
+ *  ~ Ansi is escape sequences all the way, colors kept and the cursor saved and restored with DECSC/DECRC.
+ *  ~ ExplicitCursor still uses escape sequences but the cursor is read back before a draw and moved back after it,
+ *    for Windows consoles (conhost) where the saved position and the one the console reports drift apart.
+ *  ~ Legacy is for consoles without virtual terminal processing: crossterm falls back to the console API,
+ *    colors and styles in payloads are stripped and raw sequences (OSC 52) aren't written, they would show as text.
+ *  ~ Append is for terminals that can't move the cursor (TERM=dumb, or no TERM at all): nothing is drawn in place,
+ *    messages are printed one after the other with the prompt printed again under each, and input is read a line at a time.
+ *    Colors and styles are stripped like for Legacy, only the line breaks and the bell are written raw.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStrategy {
+    Ansi,
+    ExplicitCursor,
+    Legacy,
+    Append,
+}
+
+impl RenderStrategy {
+    pub fn parse(name: &str) -> Option<RenderStrategy> {
+        match name.to_ascii_lowercase().as_str() {
+            "ansi" | "vt" => Some(RenderStrategy::Ansi),
+            "explicit-cursor" | "explicit" | "conhost" => Some(RenderStrategy::ExplicitCursor),
+            "legacy" | "winapi" => Some(RenderStrategy::Legacy),
+            "append" | "dumb" => Some(RenderStrategy::Append),
+            _ => None,
+        }
+    }
+
+    /**
+     * Whether payload colors and styles reach the terminal
+     */
+    pub fn colors(&self) -> bool {
+        !matches!(self, RenderStrategy::Legacy | RenderStrategy::Append)
+    }
+
+    /**
+     * Whether the cursor can be moved and lines cleared, so the zones can be drawn in place
+     */
+    pub fn moves_cursor(&self) -> bool {
+        *self != RenderStrategy::Append
+    }
+
+    /**
+     * Whether the cursor is saved with DECSC, otherwise its position is read back and restored with a move.
+     * Only Windows answers the position from the console API, elsewhere it is a query on the tty that would race
+     * the event reader for the answer, so DECSC is kept there whatever the strategy.
+     */
+    pub fn saves_cursor(&self) -> bool {
+        *self == RenderStrategy::Ansi || !cfg!(windows)
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            RenderStrategy::Ansi => 0,
+            RenderStrategy::ExplicitCursor => 1,
+            RenderStrategy::Legacy => 2,
+            RenderStrategy::Append => 3,
+        }
+    }
+
+    fn from_u8(value: u8) -> RenderStrategy {
+        match value {
+            1 => RenderStrategy::ExplicitCursor,
+            2 => RenderStrategy::Legacy,
+            3 => RenderStrategy::Append,
+            _ => RenderStrategy::Ansi,
+        }
+    }
+}
+
+impl fmt::Display for RenderStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderStrategy::Ansi => write!(f, "ansi"),
+            RenderStrategy::ExplicitCursor => write!(f, "explicit-cursor"),
+            RenderStrategy::Legacy => write!(f, "legacy"),
+            RenderStrategy::Append => write!(f, "append"),
+        }
+    }
+}
+
+//every TerminalWriter reads the strategy from here, the tasks each have their own
+static STRATEGY: AtomicU8 = AtomicU8::new(0);
+
+/**
+ * Look at the terminal we run in and pick a strategy, nothing is stored. DUALZONE_RENDER=ansi|explicit-cursor|legacy|append overrides it.
+ * On Windows asking crossterm whether ANSI is supported is also what turns virtual terminal processing on for the console.
+ */
+pub fn probe() -> RenderStrategy {
+    if let Some(strategy) = std::env::var("DUALZONE_RENDER").ok().and_then(|name| RenderStrategy::parse(&name)) {
+        return strategy;
+    }
+    if is_dumb() {
+        return RenderStrategy::Append;
+    }
+    platform_strategy()
+}
+
+/**
+ * Whether TERM says the terminal can't move the cursor. Outside Windows a missing TERM means the same,
+ * eg. a cron job or an editor's shell buffer, Windows consoles don't set it at all
+ */
+fn is_dumb() -> bool {
+    match std::env::var("TERM") {
+        Ok(term) => term.is_empty() || term.eq_ignore_ascii_case("dumb"),
+        Err(_) => !cfg!(windows),
+    }
+}
+
+#[cfg(windows)]
+fn platform_strategy() -> RenderStrategy {
+    if !crossterm::ansi_support::supports_ansi() {
+        return RenderStrategy::Legacy;
+    }
+    //Windows Terminal, ConEmu and the VS Code terminal are real VTs, a bare conhost is the one that needs help with the cursor
+    let vt_host = ["WT_SESSION", "ConEmuANSI", "TERM_PROGRAM"].iter().any(|name| std::env::var_os(name).is_some());
+    if vt_host { RenderStrategy::Ansi } else { RenderStrategy::ExplicitCursor }
+}
+
+#[cfg(not(windows))]
+fn platform_strategy() -> RenderStrategy {
+    RenderStrategy::Ansi
+}
+
+/**
+ * Probe the terminal and use the result from now on, called once before the first draw
+ */
+pub fn init() -> RenderStrategy {
+    let strategy = probe();
+    set_strategy(strategy);
+    strategy
+}
+
+pub fn set_strategy(strategy: RenderStrategy) {
+    STRATEGY.store(strategy.to_u8(), Ordering::Relaxed);
+}
+
+/**
+ * The strategy in use, Ansi until init() or set_strategy() says otherwise
+ */
+pub fn strategy() -> RenderStrategy {
+    RenderStrategy::from_u8(STRATEGY.load(Ordering::Relaxed))
+}
@@ -0,0 +1,228 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::output::ansi;
+
+//External
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/**
+ * Columns a wrapped continuation row is indented by
+ */
+pub const CONTINUATION_INDENT: usize = 2;
+
+/**
+ * How an output zone fits a message that is wider than the zone
+ *  ~ Wrap breaks it over several rows at word boundaries, continuation rows are indented.
+ *  ~ Truncate keeps it on one row and ends it with an ellipsis.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum LineMode {
+    #[default]
+    Wrap,
+    Truncate,
+}
+
+/**
+ * Columns the text takes on screen, wide characters (CJK, most emoji) take two, combining marks and escape sequences none
+ */
+pub fn width(text: &str) -> usize {
+    graphemes(text).iter().map(|(_, columns)| columns).sum()
+}
+
+/**
+ * The grapheme clusters of text with the columns each one takes, the unit the cursor and wrapping move by.
+ * An escape sequence is kept whole as one zero width unit so cutting a line never splits a color code.
+ */
+pub fn graphemes(text: &str) -> Vec<(&str, usize)> {
+    let mut units = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(len) = ansi::escape_len(rest) {
+            units.push((&rest[..len], 0));
+            rest = &rest[len..];
+            continue;
+        }
+        let plain = rest.find('\u{1b}').unwrap_or(rest.len());
+        units.extend(rest[..plain].graphemes(true).map(|g| (g, UnicodeWidthStr::width(g))));
+        rest = &rest[plain..];
+    }
+    units
+}
+
+/**
+ * The longest prefix of text that fits in width columns, a wide character is never split
+ */
+pub fn take_width(text: &str, width: usize) -> String {
+    let mut used = 0;
+    let mut out = String::new();
+    for (grapheme, columns) in graphemes(text) {
+        if used + columns > width {
+            break;
+        }
+        used += columns;
+        out.push_str(grapheme);
+    }
+    out
+}
+
+/**
+ * Split text after width columns, escape sequences stay on the side they were on
+ */
+pub fn split_at_width(text: &str, width: usize) -> (String, String) {
+    let mut used = 0;
+    let mut split = text.len();
+    let mut offset = 0;
+    for (grapheme, columns) in graphemes(text) {
+        if columns > 0 && used + columns > width {
+            split = offset;
+            break;
+        }
+        used += columns;
+        offset += grapheme.len();
+    }
+    (text[..split].to_string(), text[split..].to_string())
+}
+
+/**
+ * Fit text into exactly width columns, cut if too wide and padded with fill if too narrow
+ */
+pub fn pad_with(text: &str, width: usize, fill: char) -> String {
+    let text = take_width(text, width);
+    let missing = width - self::width(&text);
+    let mut out = text;
+    //end any color the text left open so it doesn't run into the padding or the next zone
+    if out.contains('\u{1b}') {
+        out.push_str("\u{1b}[0m");
+    }
+    out.extend(std::iter::repeat_n(fill, missing));
+    out
+}
+
+/**
+ * Fit text into exactly width columns, padded with spaces
+ */
+pub fn pad(text: &str, width: usize) -> String {
+    pad_with(text, width, ' ')
+}
+
+/**
+ * Cut text to width columns, ending with … when anything was cut
+ */
+pub fn truncate(text: &str, width: usize) -> String {
+    if self::width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    format!("{}…", take_width(text, width - 1))
+}
+
+/**
+ * Break text into rows of at most width columns, at spaces where possible.
+ * Every row after the first starts with indent spaces, words longer than a row are split between graphemes.
+ */
+pub fn wrap(text: &str, width: usize, indent: usize) -> Vec<String> {
+    //a wide character needs two columns, below that nothing can be placed
+    if width < 2 {
+        return vec![take_width(text, width)];
+    }
+    //narrow zones get no indent rather than no room for text
+    let indent = if indent * 2 >= width { 0 } else { indent };
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    let mut used = 0;
+    let mut start = 0;
+
+    for word in text.split(' ') {
+        let mut parts = graphemes(word);
+        loop {
+            let gap = if used > start { 1 } else { 0 };
+            let word_width: usize = parts.iter().map(|(_, w)| w).sum();
+            if used + gap + word_width <= width {
+                row.extend(std::iter::repeat_n(' ', gap));
+                row.extend(parts.iter().map(|(g, _)| *g));
+                used += gap + word_width;
+                break;
+            }
+            //a word that fits a fresh row moves there, a longer one is split across rows
+            if gap == 1 && word_width <= width - indent {
+                rows.push(std::mem::replace(&mut row, " ".repeat(indent)));
+                used = indent;
+                start = indent;
+                continue;
+            }
+            let mut room = width - used - gap;
+            let mut take = 0;
+            while take < parts.len() && parts[take].1 <= room {
+                room -= parts[take].1;
+                take += 1;
+            }
+            row.extend(std::iter::repeat_n(' ', gap));
+            row.extend(parts.drain(..take).map(|(g, _)| g));
+            rows.push(std::mem::replace(&mut row, " ".repeat(indent)));
+            used = indent;
+            start = indent;
+        }
+    }
+    if used > start || rows.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
+/**
+ * The rows a message payload takes in a zone width columns wide, embedded newlines always start a new row
+ */
+pub fn fit(text: &str, width: usize, mode: &LineMode) -> Vec<String> {
+    text.split('\n').flat_map(|line| match mode {
+        LineMode::Wrap => wrap(line, width, CONTINUATION_INDENT),
+        LineMode::Truncate => vec![truncate(line, width)],
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_at_spaces_and_indents_the_rest() {
+        assert_eq!(wrap("alpha beta gamma delta", 12, 2), ["alpha beta", "  gamma", "  delta"]);
+        assert_eq!(wrap("short", 12, 2), ["short"]);
+        assert_eq!(wrap("", 12, 2), [""]);
+    }
+
+    #[test]
+    fn wrap_splits_words_longer_than_a_row() {
+        assert_eq!(wrap("abcdefghij", 4, 0), ["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn wrap_keeps_wide_characters_whole() {
+        assert_eq!(wrap("日本語です", 5, 0), ["日本", "語で", "す"]);
+    }
+
+    #[test]
+    fn narrow_rows_get_no_indent() {
+        assert_eq!(wrap("ab cd", 3, 2), ["ab", "cd"]);
+    }
+
+    #[test]
+    fn fit_starts_a_row_at_every_newline() {
+        assert_eq!(fit("one\ntwo", 10, &LineMode::Wrap), ["one", "two"]);
+        assert_eq!(fit("a long first line\nb", 8, &LineMode::Truncate), ["a long …", "b"]);
+    }
+
+    #[test]
+    fn truncate_and_pad_fill_exactly_the_width() {
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("abc", 4), "abc");
+        assert_eq!(pad("ab", 4), "ab  ");
+        assert_eq!(pad_with("ab", 4, '─'), "ab──");
+    }
+}
@@ -0,0 +1,318 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use crate::output::highlight::HighlightStyle;
+use crate::user_input::structs::PriorityStatus;
+
+//External
+use crossterm::style::Color;
+
+/**
+ * The colors a terminal shows
+ *  ~ Basic is the 16 named colors, every terminal has those.
+ *  ~ Ansi256 adds the 256 color palette, TrueColor takes any #rrggbb.
+ *  ~ NoColor draws no colors at all, only bold, dim, italic, underline and reverse, see https://no-color.org.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    Basic,
+    Ansi256,
+    TrueColor,
+    NoColor,
+}
+
+/**
+ * The 16 named colors in palette order, with the RGB xterm draws them in
+ */
+const BASIC: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/**
+ * The levels of each channel in the 6x6x6 cube of the 256 color palette
+ */
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /**
+     * What the terminal says it takes: COLORTERM=truecolor (or 24bit) for TrueColor, a TERM with 256color in it for Ansi256.
+     * A NO_COLOR that is set and not empty comes first, it means NoColor whatever the terminal takes
+     */
+    pub fn detect() -> ColorDepth {
+        if std::env::var("NO_COLOR").is_ok_and(|value| !value.is_empty()) {
+            return ColorDepth::NoColor;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default().to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+        match std::env::var("TERM") {
+            Ok(term) if term.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Basic,
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<ColorDepth> {
+        match name.to_ascii_lowercase().as_str() {
+            "16" | "basic" => Some(ColorDepth::Basic),
+            "256" => Some(ColorDepth::Ansi256),
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "none" | "no-color" => Some(ColorDepth::NoColor),
+            _ => None,
+        }
+    }
+
+    /**
+     * The closest color the terminal shows, colors it has are left as they are.
+     * NoColor leaves them too, HighlightStyle::fitted takes them off the style
+     */
+    pub fn fit(&self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::TrueColor, color) => color,
+            (ColorDepth::Ansi256, Color::Rgb { r, g, b }) => Color::AnsiValue(palette_index(r, g, b)),
+            (ColorDepth::Basic, Color::Rgb { r, g, b }) => nearest_basic((r, g, b)),
+            (ColorDepth::Basic, Color::AnsiValue(value)) => match value {
+                0..=15 => BASIC[value as usize].0,
+                value => nearest_basic(palette_rgb(value)),
+            },
+            (_, color) => color,
+        }
+    }
+}
+
+/**
+ * The 256 color palette entry closest to an RGB color, from the cube or the grey ramp
+ */
+fn palette_index(r: u8, g: u8, b: u8) -> u8 {
+    let level = |channel: u8| CUBE.iter().enumerate().min_by_key(|(_, level)| (**level as i32 - channel as i32).abs()).map_or(0, |(index, _)| index as u8);
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let grey = 232 + ((average.saturating_sub(3)) / 10).min(23) as u8;
+    match distance(palette_rgb(grey), (r, g, b)) < distance(palette_rgb(cube), (r, g, b)) {
+        true => grey,
+        false => cube,
+    }
+}
+
+/**
+ * The RGB of a 256 color palette entry
+ */
+fn palette_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => BASIC[value as usize].1,
+        16..=231 => {
+            let index = value - 16;
+            (CUBE[(index / 36) as usize], CUBE[(index / 6 % 6) as usize], CUBE[(index % 6) as usize])
+        },
+        grey => {
+            let level = 8 + (grey - 232) * 10;
+            (level, level, level)
+        },
+    }
+}
+
+/**
+ * Where a named color is in the 16 color palette, None for the others
+ */
+pub fn basic_index(color: Color) -> Option<u8> {
+    BASIC.iter().position(|(basic, _)| *basic == color).map(|index| index as u8)
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC.iter().min_by_key(|(_, basic)| distance(*basic, rgb)).map_or(Color::White, |(color, _)| *color)
+}
+
+/**
+ * The text put before a message of a priority when the theme labels priorities, None for the ones drawn as they are
+ */
+pub fn label(priority: &PriorityStatus) -> Option<&'static str> {
+    match priority {
+        PriorityStatus::Urgent => Some("[URGENT]"),
+        PriorityStatus::Critical => Some("[CRIT]"),
+        PriorityStatus::Exception => Some("[EXC]"),
+        PriorityStatus::Warning => Some("[WARN]"),
+        PriorityStatus::Notice => Some("[NOTE]"),
+        PriorityStatus::Delay => Some("[DELAY]"),
+        PriorityStatus::Verbose => Some("[VERB]"),
+        PriorityStatus::Normal | PriorityStatus::Informational | PriorityStatus::Ignore => None,
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+fn style(style: &str) -> HighlightStyle {
+    HighlightStyle::parse(style).expect("built-in theme style")
+}
+
+/**
+ * Every color the console draws with, apart from what the modules and highlight rules put in their own text.
+ *  ~ priorities color the text of messages by priority, a priority without one is drawn as sent.
+ *  ~ prompt is the prompt's style where the application gave it no color of its own.
+ *  ~ status_bar, header (of the zone names and the tab bar) and pinned style those rows,
+ *    error the reason a line was rejected, suggestion the autosuggestion and selected the chosen item of a select() picker.
+ *  ~ labels puts a priority's label (see label) before its messages, so the priority does not show by color alone.
+ *    The Console turns it on in accessibility mode, see Console::enable_accessibility.
+ *  ~ plain is the look the console always had, dark and light color the priorities for dark and light backgrounds.
+ *  ~ A custom theme starts from a built-in one and changes some of its styles, see ConsoleConfig.
+ * Styles are written as in output::highlight::HighlightStyle, they are fitted to the terminal's ColorDepth when drawn.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub priorities: HashMap<PriorityStatus, HighlightStyle>,
+    pub prompt: Option<HighlightStyle>,
+    pub status_bar: HighlightStyle,
+    pub header: HighlightStyle,
+    pub pinned: HighlightStyle,
+    pub error: HighlightStyle,
+    pub suggestion: HighlightStyle,
+    pub selected: HighlightStyle,
+    pub labels: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::plain()
+    }
+}
+
+impl Theme {
+    pub fn plain() -> Self {
+        Theme {
+            name: "plain".to_string(),
+            priorities: HashMap::new(),
+            prompt: None,
+            status_bar: style("reverse"),
+            header: style("bold"),
+            pinned: style("reverse"),
+            error: style("dark_red"),
+            suggestion: style("dim"),
+            selected: style("reverse"),
+            labels: false,
+        }
+    }
+
+    pub fn dark() -> Self {
+        let priorities = [
+            (PriorityStatus::Urgent, "white,on_dark_red,bold"),
+            (PriorityStatus::Critical, "red,bold"),
+            (PriorityStatus::Exception, "magenta"),
+            (PriorityStatus::Warning, "yellow"),
+            (PriorityStatus::Notice, "cyan"),
+            (PriorityStatus::Delay, "blue"),
+            (PriorityStatus::Verbose, "dark_grey"),
+        ];
+        Theme {
+            name: "dark".to_string(),
+            priorities: priorities.iter().map(|(priority, look)| (priority.clone(), style(look))).collect(),
+            prompt: Some(style("cyan")),
+            status_bar: style("black,on_grey"),
+            error: style("red"),
+            ..Theme::plain()
+        }
+    }
+
+    pub fn light() -> Self {
+        let priorities = [
+            (PriorityStatus::Urgent, "white,on_dark_red,bold"),
+            (PriorityStatus::Critical, "dark_red,bold"),
+            (PriorityStatus::Exception, "dark_magenta"),
+            (PriorityStatus::Warning, "dark_yellow"),
+            (PriorityStatus::Notice, "dark_blue"),
+            (PriorityStatus::Delay, "dark_cyan"),
+            (PriorityStatus::Verbose, "dark_grey"),
+        ];
+        Theme {
+            name: "light".to_string(),
+            priorities: priorities.iter().map(|(priority, look)| (priority.clone(), style(look))).collect(),
+            prompt: Some(style("dark_blue")),
+            status_bar: style("white,on_dark_blue"),
+            ..Theme::plain()
+        }
+    }
+
+    /**
+     * A built-in theme by name, plain, dark or light
+     */
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name.to_ascii_lowercase().as_str() {
+            "plain" | "default" => Some(Theme::plain()),
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            _ => None,
+        }
+    }
+
+    /**
+     * Change one style, by its name (prompt, status_bar, header, pinned, error, suggestion, selected) or a priority's.
+     * "none" takes the color off a priority or the prompt
+     */
+    pub fn set(&mut self, key: &str, look: &str) -> Result<(), String> {
+        let none = look.trim().eq_ignore_ascii_case("none");
+        if let Some(priority) = PriorityStatus::parse(key) {
+            match none {
+                true => { self.priorities.remove(&priority); },
+                false => { self.priorities.insert(priority, HighlightStyle::parse(look)?); },
+            }
+            return Ok(());
+        }
+        if key == "prompt" {
+            self.prompt = if none { None } else { Some(HighlightStyle::parse(look)?) };
+            return Ok(());
+        }
+        let slot = match key {
+            "status_bar" => &mut self.status_bar,
+            "header" => &mut self.header,
+            "pinned" => &mut self.pinned,
+            "error" => &mut self.error,
+            "suggestion" => &mut self.suggestion,
+            "selected" => &mut self.selected,
+            other => return Err(format!("unknown theme style: {}", other)),
+        };
+        *slot = HighlightStyle::parse(look)?;
+        Ok(())
+    }
+
+    pub fn priority(&self, priority: &PriorityStatus) -> Option<&HighlightStyle> {
+        self.priorities.get(priority)
+    }
+
+    /**
+     * The theme with every color brought down to what the terminal shows
+     */
+    pub fn fitted(&self, depth: &ColorDepth) -> Theme {
+        Theme {
+            name: self.name.clone(),
+            priorities: self.priorities.iter().map(|(priority, style)| (priority.clone(), style.fitted(depth))).collect(),
+            prompt: self.prompt.as_ref().map(|style| style.fitted(depth)),
+            status_bar: self.status_bar.fitted(depth),
+            header: self.header.fitted(depth),
+            pinned: self.pinned.fitted(depth),
+            error: self.error.fitted(depth),
+            suggestion: self.suggestion.fitted(depth),
+            selected: self.selected.fitted(depth),
+            labels: self.labels,
+        }
+    }
+}
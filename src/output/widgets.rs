@@ -0,0 +1,172 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::output::table::Table;
+use crate::output::text;
+
+//External
+use serde::{Serialize, Deserialize};
+
+/**
+ * Spinner animation frames, one frame per SPINNER_FRAME_MS
+ */
+pub const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+pub const SPINNER_FRAME_MS: u64 = 100;
+const SUCCESS_GLYPH: char = '✔';
+const FAILURE_GLYPH: char = '✖';
+
+/**
+ * Lifecycle of a live widget
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WidgetState {
+    Running,
+    Done,
+    Aborted(String),
+}
+
+/**
+ * Everything needed to draw a progress bar line
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressState {
+    pub label: String,
+    pub fraction: f64,
+    pub eta_secs: Option<u64>,
+    pub state: WidgetState,
+}
+
+/**
+ * Everything needed to draw a spinner line, Done is success and Aborted is failure
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpinnerState {
+    pub label: String,
+    pub status: Option<String>,
+    pub state: WidgetState,
+}
+
+/**
+ * Structured content the output zone draws itself, sized to the zone width at draw time.
+ * The message payload still carries a plain text version for anything that can't draw widgets (exports, the dead-letter queue).
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Widget {
+    Progress(ProgressState),
+    Spinner(SpinnerState),
+    Table(Table),
+}
+
+/**
+ * mm:ss, or h:mm:ss once it gets long
+ */
+pub(crate) fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+impl Widget {
+    /**
+     * A running widget keeps its line in the zone, it only starts expiring once it is done or aborted
+     */
+    pub fn is_live(&self) -> bool {
+        match self {
+            Widget::Progress(progress) => progress.state == WidgetState::Running,
+            Widget::Spinner(spinner) => spinner.state == WidgetState::Running,
+            Widget::Table(_) => false,
+        }
+    }
+
+    /**
+     * True if the widget changes on its own and needs redrawing on the animation timer
+     */
+    pub fn is_animated(&self) -> bool {
+        match self {
+            Widget::Spinner(spinner) => spinner.state == WidgetState::Running,
+            _ => false,
+        }
+    }
+
+    /**
+     * Plain text version of the widget without any width fitting
+     */
+    pub fn plain_text(&self) -> String {
+        match self {
+            Widget::Progress(progress) => {
+                let percent = (progress.fraction.clamp(0.0, 1.0) * 100.0).round() as u64;
+                match &progress.state {
+                    WidgetState::Running => format!("{} {}%", progress.label, percent),
+                    WidgetState::Done => format!("{} done", progress.label),
+                    WidgetState::Aborted(reason) => format!("{} aborted at {}%: {}", progress.label, percent, reason),
+                }
+            },
+            Widget::Spinner(spinner) => {
+                let status = spinner.status.as_deref().map(|s| format!(" - {}", s)).unwrap_or_default();
+                match &spinner.state {
+                    WidgetState::Running => format!("{}{}", spinner.label, status),
+                    WidgetState::Done => format!("{} {}{}", SUCCESS_GLYPH, spinner.label, status),
+                    WidgetState::Aborted(reason) => format!("{} {} - {}", FAILURE_GLYPH, spinner.label, reason),
+                }
+            },
+            Widget::Table(table) => table.plain_text(),
+        }
+    }
+
+    /**
+     * Draw the widget into rows exactly width columns wide, only tables take more than one row
+     */
+    pub fn render_rows(&self, width: usize) -> Vec<String> {
+        match self {
+            Widget::Table(table) => table.render(width).iter().map(|row| text::pad(row, width)).collect(),
+            _ => vec![self.render(width)],
+        }
+    }
+
+    /**
+     * Draw the widget into exactly width columns
+     */
+    pub fn render(&self, width: usize) -> String {
+        let line = match self {
+            Widget::Progress(progress) => Widget::render_progress(progress, width),
+            Widget::Spinner(spinner) => match spinner.state {
+                //the frame comes from the clock so every redraw moves the animation along
+                WidgetState::Running => {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+                    let frame = SPINNER_FRAMES[((now / SPINNER_FRAME_MS) % SPINNER_FRAMES.len() as u64) as usize];
+                    format!("{} {}", frame, self.plain_text())
+                },
+                _ => self.plain_text(),
+            },
+            Widget::Table(table) => table.render(width).into_iter().next().unwrap_or_default(),
+        };
+        text::pad(&line, width)
+    }
+
+    fn render_progress(progress: &ProgressState, width: usize) -> String {
+        let fraction = progress.fraction.clamp(0.0, 1.0);
+        let percent = (fraction * 100.0).round() as u64;
+        let tail = match &progress.state {
+            WidgetState::Running => match progress.eta_secs {
+                Some(eta) => format!(" {:>3}% ETA {}", percent, format_duration(eta)),
+                None => format!(" {:>3}%", percent),
+            },
+            WidgetState::Done => " done".to_string(),
+            WidgetState::Aborted(reason) => format!(" aborted: {}", reason),
+        };
+        //label, space, [bar], tail, the bar gets whatever is left
+        let fixed = text::width(&progress.label) + 3 + text::width(&tail);
+        if width <= fixed + 1 {
+            return format!("{}{}", progress.label, tail);
+        }
+        let bar_width = width - fixed;
+        let filled = ((bar_width as f64) * fraction).round() as usize;
+        let bar: String = std::iter::repeat_n('#', filled).chain(std::iter::repeat_n('-', bar_width - filled)).collect();
+        format!("{} [{}]{}", progress.label, bar, tail)
+    }
+}
@@ -0,0 +1,108 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::io::{stdout, Write};
+use std::sync::mpsc;
+
+/**
+ * What a TerminalWriter sends to the render actor
+ *  ~ Write is one flushed batch, written out in one go so batches of different writers never interleave.
+ *  ~ Sync is answered once everything sent before it is on the terminal.
+ *  ~ Release drops every batch after it, the terminal is no longer the console's.
+ */
+enum WriteCommand {
+    Write(Vec<u8>),
+    Sync(mpsc::Sender<()>),
+    Release,
+}
+
+/**
+ * A handle on the terminal for one task. Drawing only fills its buffer, flush hands the batch to the render actor,
+ * a thread that owns stdout and does the blocking writes, so no task blocks on the terminal or holds a lock on it.
+ *  ~ Every task gets its own clone, the output zones and the input zone draw through separate writers.
+ *  ~ Batches reach the terminal in the order they were flushed, whichever writer they come from.
+ *  ~ The actor stops once every writer is dropped, call sync() before exiting so nothing queued is lost.
+ */
+#[derive(Debug)]
+pub struct TerminalWriter {
+    buffer: Vec<u8>,
+    tx: mpsc::Sender<WriteCommand>,
+}
+
+impl TerminalWriter {
+    /**
+     * Start the render actor and return the first writer for it
+     */
+    pub fn spawn() -> TerminalWriter {
+        let (tx, rx) = mpsc::channel::<WriteCommand>();
+        std::thread::spawn(move || {
+            let stdout = stdout();
+            let mut released = false;
+            for command in rx {
+                match command {
+                    WriteCommand::Write(_) if released => {},
+                    WriteCommand::Write(bytes) => {
+                        let mut stdout = stdout.lock();
+                        //nobody is left to tell when the terminal is gone, the next batch just tries again
+                        let _ = stdout.write_all(&bytes).and_then(|_| stdout.flush());
+                    },
+                    WriteCommand::Sync(done) => {
+                        let _ = done.send(());
+                    },
+                    WriteCommand::Release => released = true,
+                }
+            }
+        });
+        TerminalWriter { buffer: Vec::new(), tx }
+    }
+
+    /**
+     * Flush and wait until the actor has written everything sent so far, from every writer.
+     * It blocks the thread, it is for the few places that need the terminal up to date: reading the cursor back and exiting.
+     */
+    pub fn sync(&mut self) -> std::io::Result<()> {
+        self.flush()?;
+        let (done, wait) = mpsc::channel();
+        self.tx.send(WriteCommand::Sync(done)).map_err(|_| closed())?;
+        wait.recv().map_err(|_| closed())
+    }
+
+    /**
+     * Let go of the terminal, for every writer: what was flushed before still gets out, nothing after it does.
+     * A console whose terminal detached keeps drawing into the void and the terminal is free to close, see broker::session
+     */
+    pub fn release(&mut self) -> std::io::Result<()> {
+        self.flush()?;
+        self.tx.send(WriteCommand::Release).map_err(|_| closed())
+    }
+}
+
+impl Clone for TerminalWriter {
+    /**
+     * A new writer for the same actor, with an empty buffer of its own
+     */
+    fn clone(&self) -> Self {
+        TerminalWriter { buffer: Vec::new(), tx: self.tx.clone() }
+    }
+}
+
+impl Write for TerminalWriter {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::take(&mut self.buffer);
+        self.tx.send(WriteCommand::Write(batch)).map_err(|_| closed())
+    }
+}
+
+fn closed() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the render actor stopped")
+}
@@ -0,0 +1,972 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+use crate::broker::message::Message;
+use crate::broker::memory::{self, BufferMeter, MemoryLimit, MemoryMeter};
+use crate::output::text::LineMode;
+use crate::output::search::{Search, SearchCommand};
+use crate::output::highlight::Highlights;
+use crate::output::expiry::ExpiryPolicy;
+use crate::output::selection::{SelectCommand, Selection};
+use crate::output::clipboard::{self, CopyTarget};
+use crate::output::tabs::{TabBar, TabCommand};
+
+//External
+use tokio::time::{Duration, Instant};
+
+/**
+ * How long a message stays in the output zone before it is cleared, this was the old global clear timer.
+ * It is now the default of the ExpiryPolicy, which sets the time per priority.
+ */
+pub const DEFAULT_EXPIRY: Duration = Duration::from_secs(2);
+
+/**
+ * Name of the zone every Console starts with, it catches everything no other zone is bound to.
+ */
+pub const MAIN_ZONE: &str = "main";
+
+/**
+ * How many messages a zone keeps in scrollback after they have expired from view, see MemoryLimits for other limits
+ */
+pub const DEFAULT_SCROLLBACK: usize = 5000;
+
+/**
+ * How many messages one scroll step moves through the scrollback
+ */
+pub const SCROLL_STEP: u64 = 5;
+
+/**
+ * Moving the focused zone through its scrollback, Bottom goes back to following live output.
+ * Up and Down move SCROLL_STEP messages, LineUp and LineDown one.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScrollCommand {
+    Up,
+    Down,
+    LineUp,
+    LineDown,
+    Top,
+    Bottom,
+}
+
+/**
+ * Moving the focus between the input zone and the output zones.
+ * The order is the input zone, then the output zones in display order, then around again.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum FocusCommand {
+    Next,
+    Previous,
+    Input,
+}
+
+impl FocusCommand {
+    /**
+     * Where the focus goes from current, None is the input zone
+     */
+    pub fn apply(&self, current: Option<usize>, zone_count: usize) -> Option<usize> {
+        match (self, current) {
+            (FocusCommand::Input, _) => None,
+            (_, _) if zone_count == 0 => None,
+            (FocusCommand::Next, None) => Some(0),
+            (FocusCommand::Next, Some(index)) if index + 1 < zone_count => Some(index + 1),
+            (FocusCommand::Next, Some(_)) => None,
+            (FocusCommand::Previous, None) => Some(zone_count - 1),
+            (FocusCommand::Previous, Some(0)) => None,
+            (FocusCommand::Previous, Some(index)) => Some(index.min(zone_count) - 1),
+        }
+    }
+}
+
+/**
+ * Definition of a named output zone and what gets routed to it.
+ *  ~ A zone bound to nothing is a catch-all and gets every message no other zone claimed.
+ *  ~ senders are plaintext sender names, topics match Message::topic exactly.
+ *  ~ line_mode decides whether messages wider than the zone wrap or are truncated, wrap by default.
+ */
+#[derive(Debug, Clone)]
+pub struct ZoneSpec {
+    pub name: String,
+    pub topics: Vec<String>,
+    pub senders: Vec<String>,
+    pub line_mode: LineMode,
+}
+
+impl ZoneSpec {
+    pub fn new(name: String) -> Self {
+        ZoneSpec { name, topics: Vec::new(), senders: Vec::new(), line_mode: LineMode::default() }
+    }
+
+    pub fn with_line_mode(mut self, line_mode: LineMode) -> Self {
+        self.line_mode = line_mode;
+        self
+    }
+
+    pub fn with_topic(mut self, topic: String) -> Self {
+        self.topics.push(topic);
+        self
+    }
+
+    pub fn with_sender(mut self, sender: String) -> Self {
+        self.senders.push(sender);
+        self
+    }
+
+    pub fn is_catch_all(&self) -> bool {
+        self.topics.is_empty() && self.senders.is_empty()
+    }
+
+    /**
+     * True if the message is bound to this zone by topic or sender name
+     */
+    pub fn matches(&self, topic: Option<&str>, sender_name: &str) -> bool {
+        let by_topic = match topic {
+            Some(topic) => self.topics.iter().any(|t| t == topic),
+            None => false,
+        };
+        by_topic || self.senders.iter().any(|s| s == sender_name)
+    }
+}
+
+/**
+ * How several output zones share the output area
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arrangement {
+    Stacked,
+    SideBySide,
+}
+
+/**
+ * A message currently visible in the output zone
+ */
+#[derive(Debug, Clone)]
+pub struct VisibleLine {
+    pub message: Message,
+    pub expires_at: Option<Instant>,
+}
+
+/**
+ * The lines currently shown in one output zone, newest last.
+ * Pinned messages are kept apart, they are drawn at the top and are never expired or evicted.
+ * Every other message is also kept in the scrollback, which outlives expiry and is what a search looks through.
+ * Scrollback entries keep the absolute position they were recorded at, so positions stay valid as messages are evicted,
+ * from the front or (evicting the lowest priority first) from anywhere. next_position is the one the next message gets.
+ * scrolled is the position of the newest message shown while the user has scrolled back, None while following live output.
+ */
+#[derive(Debug)]
+pub struct OutputZone {
+    pub name: String,
+    lines: VecDeque<VisibleLine>,
+    pinned: Vec<VisibleLine>,
+    expiry: ExpiryPolicy,
+    max_lines: usize,
+    pub line_mode: LineMode,
+    scrollback: VecDeque<(u64, Message)>,
+    next_position: u64,
+    scrollback_bytes: usize,
+    limit: MemoryLimit,
+    meter: Arc<MemoryMeter>,
+    search: Option<Search>,
+    scrolled: Option<u64>,
+}
+
+impl Default for OutputZone {
+    fn default() -> Self {
+        OutputZone::new(MAIN_ZONE.to_string(), ExpiryPolicy::default())
+    }
+}
+
+impl OutputZone {
+    pub fn new(name: String, expiry: ExpiryPolicy) -> Self {
+        OutputZone {
+            name,
+            lines: VecDeque::new(),
+            pinned: Vec::new(),
+            expiry,
+            max_lines: 1000,
+            line_mode: LineMode::default(),
+            scrollback: VecDeque::new(),
+            next_position: 0,
+            scrollback_bytes: 0,
+            limit: MemoryLimit::entries(DEFAULT_SCROLLBACK),
+            meter: Arc::new(MemoryMeter::default()),
+            search: None,
+            scrolled: None,
+        }
+    }
+
+    /**
+     * Limit the scrollback and report its usage to meter, messages over the new limit are evicted right away
+     */
+    pub fn set_memory_limit(&mut self, limit: MemoryLimit, meter: Arc<MemoryMeter>) {
+        for (_, message) in self.scrollback.iter() {
+            self.meter.scrollback.removed(memory::message_size(message));
+            meter.scrollback.added(memory::message_size(message));
+        }
+        self.limit = limit;
+        self.meter = meter;
+        self.evict();
+    }
+
+    /**
+     * Keep a message in the scrollback, a widget update replaces the entry for its slot and an update the message it replaces
+     */
+    fn record(&mut self, message: &Message) {
+        let size = memory::message_size(message);
+        let replaced = |entry: &Message| match (message.slot, message.replaces) {
+            (_, Some(id)) => entry.is_or_replaces(id, &message.sender),
+            (Some(slot), None) => entry.slot == Some(slot),
+            (None, None) => false,
+        };
+        if message.slot.is_some() || message.replaces.is_some() {
+            if let Some((_, entry)) = self.scrollback.iter_mut().rev().find(|(_, entry)| replaced(entry)) {
+                let old = memory::message_size(entry);
+                self.scrollback_bytes = self.scrollback_bytes + size - old;
+                self.meter.scrollback.resized(old, size);
+                *entry = message.clone();
+                self.evict();
+                return;
+            }
+        }
+        let position = self.next_position;
+        self.next_position += 1;
+        if let Some(search) = self.search.as_mut() {
+            if search.is_match(&message.payload) {
+                search.matches.push(position);
+            }
+        }
+        self.scrollback.push_back((position, message.clone()));
+        self.scrollback_bytes += size;
+        self.meter.scrollback.added(size);
+        self.evict();
+    }
+
+    /**
+     * Drop messages until the scrollback is within its limit, the one to go is the eviction policy's pick
+     */
+    fn evict(&mut self) {
+        while self.limit.exceeded(self.scrollback.len(), self.scrollback_bytes) {
+            let index = match self.limit.eviction.victim(self.scrollback.iter().map(|(_, message)| message.priority.severity())) {
+                Some(index) => index,
+                None => break,
+            };
+            let (position, message) = match self.scrollback.remove(index) {
+                Some(entry) => entry,
+                None => break,
+            };
+            let size = memory::message_size(&message);
+            self.scrollback_bytes -= size;
+            self.meter.scrollback.evicted(size);
+            if let Some(search) = self.search.as_mut() {
+                search.removed(position);
+            }
+        }
+        let start = self.scrollback_range().start;
+        if let Some(search) = self.search.as_mut() {
+            search.evicted(start);
+        }
+        if let Some(scrolled) = self.scrolled.as_mut() {
+            *scrolled = (*scrolled).max(start);
+        }
+    }
+
+    /**
+     * Estimated bytes the scrollback takes, see memory::message_size
+     */
+    pub fn scrollback_bytes(&self) -> usize {
+        self.scrollback_bytes
+    }
+
+    /**
+     * Add a message, it expires after the time the zone's expiry policy sets for its priority
+     */
+    pub fn push(&mut self, mut message: Message) {
+        //an update of a pinned message stays pinned, and out of the scrollback like it
+        if let Some(id) = message.replaces {
+            if let Some(line) = self.pinned.iter_mut().find(|line| line.message.is_or_replaces(id, &message.sender)) {
+                message.pinned = true;
+                line.message = message;
+                return;
+            }
+        }
+        if !message.pinned {
+            self.record(&message);
+        }
+        //a running widget holds its line until it is done
+        let expires_at = match &message.widget {
+            Some(widget) if widget.is_live() => None,
+            _ => message.ttl.or_else(|| self.expiry.ttl(&message.priority)).map(|ttl| Instant::now() + ttl),
+        };
+        //an update of a line that already expired is shown as a new line
+        if let Some(id) = message.replaces {
+            if let Some(line) = self.lines.iter_mut().find(|line| line.message.is_or_replaces(id, &message.sender)) {
+                line.message = message;
+                line.expires_at = expires_at;
+                return;
+            }
+        }
+        if let Some(slot) = message.slot {
+            let existing = self.pinned.iter_mut().chain(self.lines.iter_mut()).find(|line| line.message.slot == Some(slot));
+            if let Some(line) = existing {
+                let expires_at = if line.message.pinned { None } else { expires_at };
+                let pinned = line.message.pinned;
+                line.message = message;
+                line.message.pinned = pinned;
+                line.expires_at = expires_at;
+                return;
+            }
+        }
+        if message.pinned {
+            self.pinned.push(VisibleLine { message, expires_at: None });
+            return;
+        }
+        while self.lines.len() >= self.max_lines {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(VisibleLine { message, expires_at });
+    }
+
+    /**
+     * Remove every line that has expired, returns true if anything was removed
+     */
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(|line| match line.expires_at {
+            Some(at) => at > now,
+            None => true,
+        });
+        before != self.lines.len()
+    }
+
+    /**
+     * The soonest time any visible line expires
+     */
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.lines.iter().filter_map(|line| line.expires_at).min()
+    }
+
+    /**
+     * True if any visible line is an animated widget
+     */
+    pub fn is_animated(&self) -> bool {
+        self.pinned.iter().chain(self.lines.iter()).any(|line| match &line.message.widget {
+            Some(widget) => widget.is_animated(),
+            None => false,
+        })
+    }
+
+    /**
+     * Unpin a message by id, or every pinned message when None. Returns true if anything was removed
+     */
+    pub fn dismiss(&mut self, id: Option<u64>) -> bool {
+        let before = self.pinned.len();
+        match id {
+            Some(id) => self.pinned.retain(|line| line.message.id != id),
+            None => self.pinned.clear(),
+        }
+        before != self.pinned.len()
+    }
+
+    /**
+     * Take a message of the sender out of the zone, visible or not, along with what replaced it.
+     * Returns true if it was showing
+     */
+    pub fn retract(&mut self, sender: &str, id: u64) -> bool {
+        let before = self.lines.len() + self.pinned.len();
+        self.lines.retain(|line| !line.message.is_or_replaces(id, sender));
+        self.pinned.retain(|line| !line.message.is_or_replaces(id, sender));
+        let changed = before != self.lines.len() + self.pinned.len();
+
+        while let Some(index) = self.scrollback.iter().position(|(_, message)| message.is_or_replaces(id, sender)) {
+            if let Some((position, message)) = self.scrollback.remove(index) {
+                let size = memory::message_size(&message);
+                self.scrollback_bytes -= size;
+                self.meter.scrollback.removed(size);
+                if let Some(search) = self.search.as_mut() {
+                    search.removed(position);
+                }
+            }
+        }
+        changed
+    }
+
+    /**
+     * Pinned messages in the order they were pinned
+     */
+    pub fn pinned(&self) -> &[VisibleLine] {
+        &self.pinned
+    }
+
+    /**
+     * The newest lines that fit in the given number of rows, oldest first
+     */
+    pub fn visible(&self, rows: usize) -> Vec<&VisibleLine> {
+        let skip = self.lines.len().saturating_sub(rows);
+        self.lines.iter().skip(skip).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /**
+     * Absolute positions currently held in the scrollback
+     */
+    pub fn scrollback_range(&self) -> Range<u64> {
+        let start = self.scrollback.front().map(|(position, _)| *position).unwrap_or(self.next_position);
+        start..self.next_position
+    }
+
+    /**
+     * The message recorded at position, None when it was evicted
+     */
+    pub fn scrollback_at(&self, position: u64) -> Option<&Message> {
+        self.scrollback.binary_search_by_key(&position, |(at, _)| *at).ok().map(|index| &self.scrollback[index].1)
+    }
+
+    pub fn search(&self) -> Option<&Search> {
+        self.search.as_ref()
+    }
+
+    /**
+     * The newest count messages of the scrollback as plain text, oldest first
+     */
+    pub fn last_text(&self, count: usize) -> Vec<String> {
+        let range = self.scrollback_range();
+        (range.end.saturating_sub(count as u64).max(range.start)..range.end)
+            .filter_map(|at| self.scrollback_at(at))
+            .map(clipboard::plain)
+            .collect()
+    }
+
+    /**
+     * The message the search is on as plain text
+     */
+    pub fn search_text(&self) -> Option<String> {
+        let position = self.search.as_ref()?.position()?;
+        self.scrollback_at(position).map(clipboard::plain)
+    }
+
+    pub fn scrolled(&self) -> Option<u64> {
+        self.scrolled
+    }
+
+    /**
+     * Scroll back through the scrollback or return to live output, scrolling down past the newest message follows live output again
+     */
+    pub fn scroll(&mut self, command: ScrollCommand) {
+        let range = self.scrollback_range();
+        if range.is_empty() {
+            self.scrolled = None;
+            return;
+        }
+        let newest = range.end - 1;
+        let at = self.scrolled.unwrap_or(newest);
+        self.scrolled = match command {
+            ScrollCommand::Up => Some(at.saturating_sub(SCROLL_STEP).max(range.start)),
+            ScrollCommand::LineUp => Some(at.saturating_sub(1).max(range.start)),
+            ScrollCommand::Down if at + SCROLL_STEP < newest => Some(at + SCROLL_STEP),
+            ScrollCommand::LineDown if at + 1 < newest => Some(at + 1),
+            ScrollCommand::Down | ScrollCommand::LineDown => None,
+            ScrollCommand::Top => Some(range.start),
+            ScrollCommand::Bottom => None,
+        };
+    }
+
+    /**
+     * How many messages are newer than the last one shown while scrolled back
+     */
+    pub fn scrolled_back(&self) -> Option<u64> {
+        self.scrolled.map(|at| self.scrollback_range().end.saturating_sub(at + 1))
+    }
+
+    /**
+     * Start, move or end a search of the scrollback. While a search has a match the zone shows the scrollback around it
+     */
+    pub fn apply_search(&mut self, command: SearchCommand) {
+        match command {
+            SearchCommand::Query(query) if query.is_empty() => self.search = None,
+            SearchCommand::Query(query) => {
+                let mut search = Search::new(query);
+                search.matches = self.scrollback.iter()
+                    .filter(|(_, message)| search.is_match(&message.payload))
+                    .map(|(position, _)| *position)
+                    .collect();
+                search.newest();
+                self.search = Some(search);
+            },
+            SearchCommand::Older => if let Some(search) = self.search.as_mut() { search.older() },
+            SearchCommand::Newer => if let Some(search) = self.search.as_mut() { search.newer() },
+            SearchCommand::Clear => self.search = None,
+        }
+    }
+}
+
+/**
+ * Every output zone the renderer knows about, in display order.
+ * focused is the zone searches and scrolling apply to, output_focused is true while it has the keyboard focus
+ * instead of the input zone. The input task decides where the focus is and tells the zones with set_focus.
+ * selection is the text selected with the mouse, there is at most one across all zones.
+ * highlights are the highlight rules every zone is drawn with.
+ * windows are the output windows of SubConsoles kept off screen, /view swaps one of them (or a zone) into the first zone's place.
+ * Each keeps its name, so messages still find their window wherever it is.
+ * tab_bar is set when tabs are on, the tabs other than the first are windows too.
+ * split is set while two panes are followed at once (/split), the zones it took off screen wait among the windows.
+ */
+#[derive(Debug)]
+pub struct ZoneSet {
+    pub zones: Vec<OutputZone>,
+    pub arrangement: Arrangement,
+    pub focused: usize,
+    output_focused: bool,
+    paused: Option<u64>,
+    pub selection: Option<Selection>,
+    pub highlights: Highlights,
+    pub windows: Vec<OutputZone>,
+    viewing: Option<Viewed>,
+    pub tab_bar: Option<TabBar>,
+    split: Option<Stashed>,
+}
+
+/**
+ * What a split took off screen, to put back when it ends
+ */
+#[derive(Debug)]
+struct Stashed {
+    zones: Vec<String>,
+    arrangement: Arrangement,
+    focused: usize,
+}
+
+/**
+ * Where the first zone went while another window is viewed in its place
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Viewed {
+    Zone(usize),
+    Window(usize),
+}
+
+impl ZoneSet {
+    /**
+     * One OutputZone per spec, in the order the specs were defined
+     */
+    pub fn from_specs(specs: &[ZoneSpec], arrangement: Arrangement, expiry: &ExpiryPolicy) -> Self {
+        let zones = specs.iter().map(|spec| {
+            let mut zone = OutputZone::new(spec.name.clone(), expiry.clone());
+            zone.line_mode = spec.line_mode.clone();
+            zone
+        }).collect();
+        ZoneSet {
+            zones,
+            arrangement,
+            focused: 0,
+            output_focused: false,
+            paused: None,
+            selection: None,
+            highlights: Highlights::default(),
+            windows: Vec::new(),
+            viewing: None,
+            tab_bar: None,
+            split: None,
+        }
+    }
+
+    /**
+     * Add the output window of a SubConsole, off screen for a tab or laid out after the other zones.
+     * It takes the expiry and scrollback limit of the first zone. False when a zone or window already has the name
+     */
+    pub fn add_window(&mut self, spec: &ZoneSpec, tab: bool) -> bool {
+        if self.zones.iter().chain(self.windows.iter()).any(|zone| zone.name == spec.name) {
+            return false;
+        }
+        let window = self.new_window(spec);
+        match (tab, self.split.as_mut()) {
+            (true, _) => self.windows.push(window),
+            //laid out with the others once the split ends
+            (false, Some(stashed)) => {
+                stashed.zones.push(spec.name.clone());
+                self.windows.push(window);
+            },
+            (false, None) => self.zones.push(window),
+        }
+        true
+    }
+
+    fn new_window(&self, spec: &ZoneSpec) -> OutputZone {
+        let template = self.zones.first();
+        let mut window = OutputZone::new(spec.name.clone(), template.map(|zone| zone.expiry.clone()).unwrap_or_default());
+        if let Some(template) = template {
+            window.set_memory_limit(template.limit.clone(), template.meter.clone());
+        }
+        window.line_mode = spec.line_mode.clone();
+        window
+    }
+
+    /**
+     * Turn the tab bar on, the first zone becomes the tab with everything in it
+     */
+    pub fn enable_tabs(&mut self) {
+        self.tab_bar = self.zones.first().map(|zone| TabBar::new(&zone.name));
+    }
+
+    /**
+     * Name of what the first zone shows, the first zone itself or what is viewed in its place
+     */
+    pub fn shown(&self) -> &str {
+        self.zones.first().map_or("", |zone| zone.name.as_str())
+    }
+
+    /**
+     * Put a message in the zone the broker routed it to, unknown or missing zone names fall back to the first zone.
+     * With tabs on a message for the tab with everything also goes in its own tab, which is made when it is the first one
+     */
+    pub fn push(&mut self, message: Message) {
+        let shown = self.shown().to_string();
+        let bar = match self.tab_bar.as_ref() {
+            Some(bar) if message.zone.as_deref() == Some(bar.all()) => bar,
+            _ => return self.push_into(message),
+        };
+        let all = bar.all().to_string();
+        if let Some(tab) = message.tab.clone().filter(|tab| *tab != all) {
+            let known = bar.contains(&tab) || self.add_window(&ZoneSpec::new(tab.clone()), true);
+            if let (true, Some(bar)) = (known, self.tab_bar.as_mut()) {
+                bar.add(&tab);
+                bar.arrived(&tab, &shown);
+                let mut copy = message.clone();
+                copy.zone = Some(tab);
+                self.push_into(copy);
+            }
+        }
+        if let Some(bar) = self.tab_bar.as_mut() {
+            bar.arrived(&all, &shown);
+        }
+        self.push_into(message);
+    }
+
+    fn push_into(&mut self, message: Message) {
+        if let Some(window) = message.zone.as_ref().and_then(|name| self.windows.iter_mut().find(|window| &window.name == name)) {
+            window.push(message);
+            return;
+        }
+        let index = match &message.zone {
+            Some(name) => self.zones.iter().position(|zone| &zone.name == name).unwrap_or(0),
+            None => 0,
+        };
+        if self.zones.is_empty() {
+            self.zones.push(OutputZone::default());
+        }
+        if let Some(count) = self.paused.as_mut() {
+            *count += 1;
+        }
+        //the selection is of screen cells, once the zone moves on they show other text
+        let moves = self.paused.is_none() && self.zones[index].scrolled().is_none();
+        if moves && self.selection.as_ref().map(|selection| selection.zone) == Some(index) {
+            self.selection = None;
+        }
+        self.zones[index].push(message);
+    }
+
+    /**
+     * The same scrollback limit for every zone, their usage adds up in meter
+     */
+    pub fn set_memory_limit(&mut self, limit: &MemoryLimit, meter: &Arc<MemoryMeter>) {
+        for zone in self.zones.iter_mut().chain(self.windows.iter_mut()) {
+            zone.set_memory_limit(limit.clone(), meter.clone());
+        }
+    }
+
+    /**
+     * Expire the zones and the windows off screen, true when something on screen changed
+     */
+    pub fn expire(&mut self, now: Instant) -> bool {
+        for window in self.windows.iter_mut() {
+            window.expire(now);
+        }
+        let mut changed = false;
+        for zone in self.zones.iter_mut() {
+            changed |= zone.expire(now);
+        }
+        changed
+    }
+
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.zones.iter().filter_map(|zone| zone.next_expiry()).min()
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.zones.iter().any(|zone| zone.is_animated())
+    }
+
+    pub fn dismiss(&mut self, id: Option<u64>) -> bool {
+        for window in self.windows.iter_mut() {
+            window.dismiss(id);
+        }
+        let mut changed = false;
+        for zone in self.zones.iter_mut() {
+            changed |= zone.dismiss(id);
+        }
+        changed
+    }
+
+    pub fn retract(&mut self, sender: &str, id: u64) -> bool {
+        for window in self.windows.iter_mut() {
+            window.retract(sender, id);
+        }
+        let mut changed = false;
+        for zone in self.zones.iter_mut() {
+            changed |= zone.retract(sender, id);
+        }
+        changed
+    }
+
+    /**
+     * Show the named window or zone in the first zone's place, or put everything back where it was with None.
+     * Only one is viewed at a time, viewing another puts the first one back first. False for a name nothing has, or while split
+     */
+    pub fn view(&mut self, name: Option<&str>) -> bool {
+        if self.split.is_some() {
+            return false;
+        }
+        if let Some(viewed) = self.viewing.take() {
+            self.swap_first(viewed);
+        }
+        let name = match name {
+            Some(name) => name,
+            None => {
+                self.tab_shown();
+                return true;
+            },
+        };
+        let viewed = match (self.zones.iter().position(|zone| zone.name == name), self.windows.iter().position(|window| window.name == name)) {
+            (Some(0), _) => return true,
+            (Some(index), _) => Viewed::Zone(index),
+            (None, Some(index)) => Viewed::Window(index),
+            (None, None) => return false,
+        };
+        self.swap_first(viewed);
+        self.viewing = Some(viewed);
+        //the selection is of what was on screen
+        self.selection = None;
+        self.tab_shown();
+        true
+    }
+
+    /**
+     * Switch tabs, false when tabs are off or there is no such tab
+     */
+    pub fn tab(&mut self, command: TabCommand) -> bool {
+        let bar = match self.tab_bar.as_ref() {
+            Some(bar) => bar,
+            None => return false,
+        };
+        let shown = self.shown();
+        let target = match &command {
+            //from a window viewed with /view the tabs start over at the first
+            TabCommand::Next => Some(bar.beside(shown, 1).unwrap_or(bar.all())),
+            TabCommand::Previous => Some(bar.beside(shown, -1).unwrap_or(bar.all())),
+            TabCommand::Show(None) => Some(bar.all()),
+            TabCommand::Show(Some(name)) => bar.tabs().iter().find(|tab| tab.name.eq_ignore_ascii_case(name)).map(|tab| tab.name.as_str()),
+        };
+        match target.map(|target| (target == bar.all(), target.to_string())) {
+            Some((true, _)) => self.view(None),
+            Some((false, target)) => self.view(Some(&target)),
+            None => false,
+        }
+    }
+
+    /**
+     * Split the output area between the two named panes, one over the other, or end the split with None.
+     * The panes are new empty zones, the zones on screen go among the windows until the split ends and then come back
+     * as they were, anything viewed in the first zone's place included. Splitting again replaces the panes.
+     * False when there was nothing to end
+     */
+    pub fn split(&mut self, panes: Option<[String; 2]>) -> bool {
+        let ended = self.unsplit();
+        let panes = match panes {
+            Some(panes) => panes,
+            None => return ended,
+        };
+        let zones: Vec<OutputZone> = panes.iter().map(|name| self.new_window(&ZoneSpec::new(name.clone()))).collect();
+        let stashed = Stashed {
+            zones: self.zones.iter().map(|zone| zone.name.clone()).collect(),
+            arrangement: std::mem::replace(&mut self.arrangement, Arrangement::Stacked),
+            focused: self.focused,
+        };
+        self.windows.append(&mut self.zones);
+        self.zones = zones;
+        self.split = Some(stashed);
+        self.focused = 0;
+        self.selection = None;
+        true
+    }
+
+    fn unsplit(&mut self) -> bool {
+        let stashed = match self.split.take() {
+            Some(stashed) => stashed,
+            None => return false,
+        };
+        //the panes go, and their scrollback with them
+        for pane in self.zones.drain(..) {
+            pane.meter.scrollback.removed(pane.scrollback_bytes);
+        }
+        for name in stashed.zones.iter() {
+            if let Some(index) = self.windows.iter().position(|window| &window.name == name) {
+                self.zones.push(self.windows.remove(index));
+            }
+        }
+        self.arrangement = stashed.arrangement;
+        self.focused = stashed.focused.min(self.zones.len().saturating_sub(1));
+        self.selection = None;
+        true
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.split.is_some()
+    }
+
+    fn tab_shown(&mut self) {
+        let shown = self.shown().to_string();
+        if let Some(bar) = self.tab_bar.as_mut() {
+            bar.shown(&shown);
+        }
+    }
+
+    fn swap_first(&mut self, viewed: Viewed) {
+        match viewed {
+            Viewed::Zone(index) if index < self.zones.len() => self.zones.swap(0, index),
+            Viewed::Window(index) if index < self.windows.len() && !self.zones.is_empty() => std::mem::swap(&mut self.zones[0], &mut self.windows[index]),
+            _ => {},
+        }
+    }
+
+    /**
+     * Freeze or resume drawing, while paused messages still go into the zones and their scrollback.
+     * Resuming draws whatever is current, so the zones fast-forward past what arrived in the meantime.
+     */
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = match (paused, self.paused) {
+            (true, Some(count)) => Some(count),
+            (true, None) => Some(0),
+            (false, _) => None,
+        };
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /**
+     * Searches run in the focused zone
+     */
+    pub fn search(&mut self, command: SearchCommand) {
+        if let Some(zone) = self.zones.get_mut(self.focused) {
+            zone.apply_search(command);
+        }
+    }
+
+    /**
+     * Scrolling moves the focused zone
+     */
+    pub fn scroll(&mut self, command: ScrollCommand) {
+        if let Some(zone) = self.zones.get_mut(self.focused) {
+            zone.scroll(command);
+        }
+    }
+
+    /**
+     * Give the keyboard focus to an output zone, or back to the input zone with None.
+     * The last focused output zone stays the target of searches and scrolling.
+     */
+    pub fn set_focus(&mut self, focus: Option<usize>) {
+        match focus {
+            Some(index) => {
+                self.focused = index.min(self.zones.len().saturating_sub(1));
+                self.output_focused = true;
+            },
+            None => self.output_focused = false,
+        }
+    }
+
+    /**
+     * What /copy copies from the focused zone, the mouse selection is left to the renderer which knows what is on screen
+     */
+    pub fn copy_text(&self, target: &CopyTarget) -> Option<String> {
+        let zone = self.zones.get(self.focused)?;
+        match target {
+            CopyTarget::Selection => zone.search_text(),
+            CopyTarget::Last(count) => {
+                let lines = zone.last_text(*count);
+                if lines.is_empty() { None } else { Some(lines.join("\n")) }
+            },
+        }
+    }
+
+    pub fn select(&mut self, command: SelectCommand) {
+        match command {
+            SelectCommand::Start { zone, column, row } => self.selection = Some(Selection::new(zone, column, row)),
+            SelectCommand::Extend { column, row } => if let Some(selection) = self.selection.as_mut() {
+                selection.head = (column, row);
+            },
+            SelectCommand::Clear => self.selection = None,
+        }
+    }
+
+    /**
+     * The output zone that has the keyboard focus, None while the input zone has it
+     */
+    pub fn focused_zone(&self) -> Option<usize> {
+        if self.output_focused { Some(self.focused) } else { None }
+    }
+
+    /**
+     * The status bar text while an output zone has the focus
+     */
+    pub fn focus_status(&self) -> Option<String> {
+        self.focused_zone().and_then(|index| self.zones.get(index))
+            .map(|zone| format!("focus: {} (Esc to type)", zone.name))
+    }
+
+    /**
+     * The status bar text while the focused zone is scrolled back
+     */
+    pub fn scroll_status(&self) -> Option<String> {
+        self.zones.get(self.focused).and_then(|zone| zone.scrolled_back()).map(|back| format!("scrolled back {} (Ctrl+End to follow)", back))
+    }
+
+    /**
+     * The status bar text for the active search, if there is one
+     */
+    pub fn search_status(&self) -> Option<String> {
+        self.zones.get(self.focused).and_then(|zone| zone.search()).map(|search| search.status())
+    }
+
+    /**
+     * The status bar text while a window is viewed in the first zone's place, or while split
+     */
+    pub fn view_status(&self) -> Option<String> {
+        if self.split.is_some() {
+            let panes: Vec<&str> = self.zones.iter().map(|zone| zone.name.as_str()).collect();
+            return Some(format!("split: {} (/split off to end)", panes.join(" | ")));
+        }
+        let tab = self.tab_bar.as_ref().is_some_and(|bar| bar.contains(self.shown()));
+        self.viewing.filter(|_| !tab).and(self.zones.first()).map(|zone| format!("viewing: {} (/view to go back)", zone.name))
+    }
+
+    /**
+     * The status bar text while paused, with how many messages arrived since
+     */
+    pub fn pause_status(&self) -> Option<String> {
+        self.paused.map(|count| format!("PAUSED ({} new, Ctrl+Q to resume)", count))
+    }
+}
@@ -0,0 +1,679 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::path::Path;
+use crate::user_input::structs::{Console, PriorityStatus};
+use crate::user_input::config::{ConfigError, ConsoleConfig};
+use crate::user_input::keymap::Keymap;
+use crate::user_input::vi::EditMode;
+use crate::user_input::input::EofBehavior;
+use crate::output::zone::{Arrangement, ZoneSpec};
+use crate::broker::memory::MemoryLimits;
+use crate::broker::mailbox::Scheduling;
+use crate::broker::filter::FilterRule;
+use crate::broker::alerts::AlertRule;
+use crate::output::highlight::HighlightRule;
+use crate::output::fields::FieldColor;
+use crate::output::theme::{ColorDepth, Theme};
+use crate::broker::message::Message;
+use crate::broker::hooks::Flow;
+use crate::broker::auth::SessionAuth;
+use crate::broker::session::TlsListen;
+use crate::broker::mqtt::{MqttBridge, MqttSubscription};
+use crate::broker::relay::{ChatRelay, RelayService};
+use crate::broker::distribute::NatsTarget;
+use crate::broker::remote::RemoteListen;
+use crate::broker::syslog::{self, SyslogTarget};
+use crate::broker::shell::ShellPolicy;
+use crate::broker::pager::PagerPolicy;
+use crate::broker::tail::TailSource;
+use crate::broker::schedule::parse_duration;
+use crate::broker::webhook::{WebhookFormat, WebhookSink};
+use crate::output::notify::Alert;
+use crate::user_input::highlighter::{CommandHighlighter, Highlighter};
+
+//External
+use tokio::time::Duration;
+
+/**
+ * Builds a Console with its display settings in one expression, eg.
+ *
+ *     Console::builder().status_bar(true).expiry(PriorityStatus::Verbose, Some(Duration::from_secs(5))).build()
+ *
+ * A config file can be applied at any point, settings after it override the file.
+ * Console::event_builder() (or ConsoleBuilder::with_events) builds a Console<T> for an application with its own event type.
+ */
+#[derive(Debug)]
+pub struct ConsoleBuilder<T: Send + 'static = ()> {
+    console: Console<T>,
+}
+
+impl<T: Send + 'static> Default for ConsoleBuilder<T> {
+    fn default() -> Self {
+        ConsoleBuilder { console: Console::default() }
+    }
+}
+
+impl ConsoleBuilder<> {
+    pub fn new() -> Self {
+        ConsoleBuilder::default()
+    }
+}
+
+impl<T: Send + 'static> ConsoleBuilder<T> {
+    pub fn with_events() -> Self {
+        ConsoleBuilder::default()
+    }
+
+    pub fn status_bar(mut self, enabled: bool) -> Self {
+        self.console.enable_status_bar(enabled);
+        self
+    }
+
+    pub fn tabs(mut self, enabled: bool) -> Self {
+        self.console.enable_tabs(enabled);
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.console.set_theme(theme);
+        self
+    }
+
+    pub fn color_depth(mut self, depth: ColorDepth) -> Self {
+        self.console.set_color_depth(Some(depth));
+        self
+    }
+
+    /**
+     * See Console::enable_accessibility
+     */
+    pub fn accessible(mut self, enabled: bool) -> Self {
+        self.console.enable_accessibility(enabled);
+        self
+    }
+
+    pub fn filter_level(mut self, level: PriorityStatus) -> Self {
+        self.console.set_filter_level(level);
+        self
+    }
+
+    pub fn dedup(mut self, enabled: bool) -> Self {
+        self.console.set_default_dedup(enabled);
+        self
+    }
+
+    /**
+     * See Console::set_fold
+     */
+    pub fn fold(mut self, enabled: bool) -> Self {
+        self.console.set_fold(enabled);
+        self
+    }
+
+    pub fn filter(mut self, rule: FilterRule) -> Self {
+        self.console.add_filter(rule);
+        self
+    }
+
+    /**
+     * See Console::add_alert_rule
+     */
+    pub fn alert(mut self, rule: AlertRule) -> Self {
+        self.console.add_alert_rule(rule);
+        self
+    }
+
+    pub fn highlight(mut self, rule: HighlightRule) -> Self {
+        self.console.add_highlight(rule);
+        self
+    }
+
+    /**
+     * See Console::show_fields
+     */
+    pub fn fields(mut self, shown: bool) -> Self {
+        self.console.show_fields(shown);
+        self
+    }
+
+    /**
+     * See Console::add_field_color
+     */
+    pub fn field_color(mut self, color: FieldColor) -> Self {
+        self.console.add_field_color(color);
+        self
+    }
+
+    pub fn on_message(mut self, hook: impl FnMut(&mut Message) -> Flow + Send + 'static) -> Self {
+        self.console.on_message(hook);
+        self
+    }
+
+    pub fn on_input(mut self, hook: impl FnMut(&mut String) -> Flow + Send + 'static) -> Self {
+        self.console.on_input(hook);
+        self
+    }
+
+    pub fn highlighter(mut self, highlighter: impl Highlighter + 'static) -> Self {
+        self.console.set_highlighter(Some(std::sync::Arc::new(highlighter)));
+        self
+    }
+
+    pub fn validator(mut self, validator: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static) -> Self {
+        self.console.add_validator(validator);
+        self
+    }
+
+    /**
+     * How long messages of one priority stay in the output zone, None for never
+     */
+    pub fn expiry(mut self, priority: PriorityStatus, ttl: Option<Duration>) -> Self {
+        self.console.set_expiry(priority, ttl);
+        self
+    }
+
+    /**
+     * How long messages of any priority without a time of its own stay in the output zone
+     */
+    pub fn default_expiry(mut self, ttl: Option<Duration>) -> Self {
+        self.console.set_default_expiry(ttl);
+        self
+    }
+
+    /**
+     * How a message of one priority calls the user back while they are away, see output::notify::NotifyPolicy
+     */
+    pub fn notify(mut self, priority: PriorityStatus, alert: Alert) -> Self {
+        self.console.set_notify(priority, alert);
+        self
+    }
+
+    pub fn zone(mut self, spec: ZoneSpec) -> Self {
+        self.console.add_zone(spec);
+        self
+    }
+
+    pub fn arrangement(mut self, arrangement: Arrangement) -> Self {
+        self.console.set_zone_arrangement(arrangement);
+        self
+    }
+
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.console.set_keymap(keymap);
+        self
+    }
+
+    pub fn edit_mode(mut self, mode: EditMode) -> Self {
+        self.console.set_edit_mode(mode);
+        self
+    }
+
+    pub fn on_eof(mut self, eof: EofBehavior) -> Self {
+        self.console.set_eof_behavior(eof);
+        self
+    }
+
+    pub fn mouse(mut self, enabled: bool) -> Self {
+        self.console.enable_mouse(enabled);
+        self
+    }
+
+    pub fn alternate_screen(mut self, enabled: bool) -> Self {
+        self.console.enable_alternate_screen(enabled);
+        self
+    }
+
+    /**
+     * See Console::ingest_stdin
+     */
+    pub fn ingest(mut self, enabled: bool) -> Self {
+        self.console.ingest_stdin(enabled);
+        self
+    }
+
+    /**
+     * See Console::enable_display_only
+     */
+    pub fn display_only(mut self, enabled: bool) -> Self {
+        self.console.enable_display_only(enabled);
+        self
+    }
+
+    /**
+     * See Console::serve_session
+     */
+    pub fn session(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.console.serve_session(Some(path.into()));
+        self
+    }
+
+    /**
+     * See Console::serve_session_tls, cert and key are PEM files
+     */
+    pub fn session_tls(mut self, addr: std::net::SocketAddr, cert: impl Into<std::path::PathBuf>, key: impl Into<std::path::PathBuf>) -> Self {
+        self.console.serve_session_tls(Some(TlsListen { addr, cert: cert.into(), key: key.into() }));
+        self
+    }
+
+    /**
+     * See Console::set_mqtt_bridge
+     */
+    pub fn mqtt(mut self, bridge: MqttBridge) -> Self {
+        self.console.set_mqtt_bridge(Some(bridge));
+        self
+    }
+
+    /**
+     * See Console::set_chat_mode
+     */
+    pub fn chat(mut self, enabled: bool) -> Self {
+        self.console.set_chat_mode(enabled);
+        self
+    }
+
+    /**
+     * See Console::set_chat_relay
+     */
+    pub fn relay(mut self, relay: ChatRelay) -> Self {
+        self.console.set_chat_relay(Some(relay));
+        self
+    }
+
+    /**
+     * See Console::set_nats_bridge
+     */
+    pub fn nats(mut self, url: impl Into<String>, subject: impl Into<String>) -> Self {
+        self.console.set_nats_bridge(Some(NatsTarget { url: url.into(), subject: subject.into(), origin: None }));
+        self
+    }
+
+    /**
+     * See Console::serve_grpc, token is what every call has to send as "authorization: Bearer <token>"
+     */
+    pub fn grpc(mut self, addr: std::net::SocketAddr, token: Option<String>) -> Self {
+        self.console.serve_grpc(Some(RemoteListen::new(addr, token)));
+        self
+    }
+
+    /**
+     * See Console::serve_http, token is what every request has to send as "authorization: Bearer <token>"
+     */
+    pub fn http(mut self, addr: std::net::SocketAddr, token: Option<String>) -> Self {
+        self.console.serve_http(Some(RemoteListen::new(addr, token)));
+        self
+    }
+
+    /**
+     * See Console::forward_syslog
+     */
+    pub fn syslog(mut self, target: SyslogTarget) -> Self {
+        self.console.forward_syslog(Some(target));
+        self
+    }
+
+    /**
+     * See Console::serve_syslog
+     */
+    pub fn syslog_listen(mut self, addr: std::net::SocketAddr) -> Self {
+        self.console.serve_syslog(Some(addr));
+        self
+    }
+
+    /**
+     * See Console::export_telemetry, eg. http://localhost:4317
+     */
+    pub fn otel(mut self, endpoint: impl Into<String>) -> Self {
+        self.console.export_telemetry(Some(endpoint.into()));
+        self
+    }
+
+    /**
+     * See Console::follow_files
+     */
+    pub fn tail(mut self, source: TailSource) -> Self {
+        self.console.follow_files(Some(source));
+        self
+    }
+
+    /**
+     * See Console::set_pager_policy
+     */
+    pub fn pager(mut self, policy: PagerPolicy) -> Self {
+        self.console.set_pager_policy(policy);
+        self
+    }
+
+    /**
+     * See Console::set_shell_policy
+     */
+    pub fn shell(mut self, policy: ShellPolicy) -> Self {
+        self.console.set_shell_policy(policy);
+        self
+    }
+
+    /**
+     * See Console::forward_to_journal
+     */
+    pub fn journal(mut self, enabled: bool) -> Self {
+        self.console.forward_to_journal(enabled);
+        self
+    }
+
+    /**
+     * See Console::forward_to_webhook
+     */
+    pub fn webhook(mut self, sink: WebhookSink) -> Self {
+        self.console.forward_to_webhook(sink);
+        self
+    }
+
+    /**
+     * See Console::set_session_auth, every client answers with the same token
+     */
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.console.set_session_auth(Some(SessionAuth::Token(token.into())));
+        self
+    }
+
+    /**
+     * See Console::set_session_auth, adds a user to the ones that may attach. Replaces a token
+     */
+    pub fn session_user(mut self, user: impl Into<String>, password: impl Into<String>) -> Self {
+        let mut users = match self.console.session_auth.take() {
+            Some(SessionAuth::Users(users)) => users,
+            _ => HashMap::new(),
+        };
+        users.insert(user.into(), password.into());
+        self.console.set_session_auth(Some(SessionAuth::Users(users)));
+        self
+    }
+
+    /**
+     * See Console::enable_input_only
+     */
+    pub fn input_only(mut self, enabled: bool) -> Self {
+        self.console.enable_input_only(enabled);
+        self
+    }
+
+    pub fn scheduling(mut self, scheduling: Scheduling) -> Self {
+        self.console.set_scheduling(scheduling);
+        self
+    }
+
+    pub fn memory_limits(mut self, limits: MemoryLimits) -> Self {
+        self.console.set_memory_limits(limits);
+        self
+    }
+
+    pub fn record(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.console.record_session(Some(path.into()));
+        self
+    }
+
+    /**
+     * Apply a parsed config, only the settings present in it are changed
+     */
+    pub fn config(mut self, config: &ConsoleConfig) -> Result<Self, ConfigError> {
+        if let Some(enabled) = config.status_bar {
+            self.console.enable_status_bar(enabled);
+        }
+        if let Some(enabled) = config.tabs {
+            self.console.enable_tabs(enabled);
+        }
+        if let Some(enabled) = config.mouse {
+            self.console.enable_mouse(enabled);
+        }
+        if let Some(enabled) = config.alternate_screen {
+            self.console.enable_alternate_screen(enabled);
+        }
+        if let Some(enabled) = config.highlight_input {
+            let highlighter: Option<std::sync::Arc<dyn Highlighter>> = match enabled {
+                true => Some(std::sync::Arc::new(CommandHighlighter::default())),
+                false => None,
+            };
+            self.console.set_highlighter(highlighter);
+        }
+        if let Some(enabled) = config.autosuggest {
+            self.console.enable_autosuggest(enabled);
+        }
+        if let Some(enabled) = config.dedup {
+            self.console.set_default_dedup(enabled);
+        }
+        if let Some(enabled) = config.fold {
+            self.console.set_fold(enabled);
+        }
+        if let Some(enabled) = config.chat {
+            self.console.set_chat_mode(enabled);
+        }
+        if let Some(path) = config.record.as_deref() {
+            self.console.record_session(Some(path.into()));
+        }
+        if let Some(path) = config.audit.as_deref() {
+            self.console.set_audit_file(Some(path.into())).map_err(ConfigError::Io)?;
+        }
+        if let Some(path) = config.phonebook.as_deref() {
+            self.console.set_phonebook_file(Some(path.into())).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        }
+        if let Some(level) = config.level.as_deref() {
+            match PriorityStatus::parse(level) {
+                Some(level) => self.console.set_filter_level(level),
+                None => return Err(ConfigError::Invalid(format!("unknown level: {}", level))),
+            }
+        }
+        for rule in &config.filters {
+            let parsed = FilterRule::parse(rule).map_err(|e| ConfigError::Invalid(format!("filter \"{}\": {}", rule, e)))?;
+            self.console.add_filter(parsed);
+        }
+        for rule in &config.alerts {
+            let parsed = AlertRule::parse(rule).map_err(|e| ConfigError::Invalid(format!("alert \"{}\": {}", rule, e)))?;
+            self.console.add_alert_rule(parsed);
+        }
+        for plugin in &config.plugins {
+            self.console.load_plugin(Path::new(plugin)).map_err(|e| ConfigError::Invalid(format!("plugin \"{}\": {}", plugin, e)))?;
+        }
+        if let Some(directory) = config.scripts.as_deref() {
+            self.console.set_scripts_dir(directory.into()).map_err(|e| ConfigError::Invalid(format!("scripts: {}", e)))?;
+        }
+        if let Some(path) = config.macros.as_deref() {
+            self.console.set_macros_file(Some(path.into()))?;
+        }
+        for highlight in &config.highlights {
+            let rule = HighlightRule::new(&highlight.pattern, &highlight.style)
+                .map_err(|e| ConfigError::Invalid(format!("highlight \"{}\": {}", highlight.pattern, e)))?;
+            self.console.add_highlight(rule);
+        }
+        if let Some(fields) = &config.fields {
+            if let Some(shown) = fields.shown {
+                self.console.show_fields(shown);
+            }
+            let mut colors: Vec<(&String, &String)> = fields.colors.iter().collect();
+            colors.sort();
+            for (key, style) in colors {
+                let color = FieldColor::new(key, style).map_err(|e| ConfigError::Invalid(format!("[fields.colors] {}: {}", key, e)))?;
+                self.console.add_field_color(color);
+            }
+        }
+        for webhook in &config.webhooks {
+            let invalid = |e: String| ConfigError::Invalid(format!("[[webhooks]] {}: {}", webhook.url, e));
+            let mut sink = WebhookSink::new(&webhook.url).map_err(invalid)?;
+            if let Some(when) = webhook.when.as_deref() {
+                sink = sink.when(when).map_err(invalid)?;
+            }
+            if let Some(format) = webhook.format.as_deref() {
+                let format = WebhookFormat::parse(format).ok_or_else(|| invalid(format!("unknown format: {}, json, slack or discord", format)))?;
+                sink = sink.format(format);
+            }
+            if let Some(retries) = webhook.retries {
+                sink = sink.retries(retries);
+            }
+            self.console.forward_to_webhook(sink);
+        }
+        for (priority, ttl) in config.expiry_times()? {
+            match priority {
+                Some(priority) => self.console.set_expiry(priority, ttl),
+                None => self.console.set_default_expiry(ttl),
+            }
+        }
+        for (priority, alert) in config.notify_alerts()? {
+            match priority {
+                Some(priority) => self.console.set_notify(priority, alert),
+                None => self.console.set_default_notify(alert),
+            }
+        }
+        if let Some(idle) = config.away_after()? {
+            self.console.set_away_after(idle);
+        }
+        if let Some(theme) = config.resolve_theme()? {
+            self.console.set_theme(theme);
+        }
+        if let Some(depth) = config.color_depth.as_deref() {
+            match ColorDepth::parse(depth) {
+                Some(depth) => self.console.set_color_depth(Some(depth)),
+                None => return Err(ConfigError::Invalid(format!("unknown color_depth: {}, use 16, 256, truecolor or none", depth))),
+            }
+        }
+        if let Some(accessible) = config.accessible {
+            self.console.enable_accessibility(accessible);
+        }
+        match (config.session.token.clone(), config.session.users.is_empty()) {
+            (Some(_), false) => return Err(ConfigError::Invalid("[session] has a token and users, use one of them".to_string())),
+            (Some(token), true) => self.console.set_session_auth(Some(SessionAuth::Token(token))),
+            (None, false) => self.console.set_session_auth(Some(SessionAuth::Users(config.session.users.clone()))),
+            (None, true) => {},
+        }
+        match (config.session.listen.as_deref(), config.session.cert.as_deref(), config.session.key.as_deref()) {
+            (Some(listen), Some(cert), Some(key)) => {
+                let addr = listen.parse().map_err(|_| ConfigError::Invalid(format!("invalid [session] listen address: {}", listen)))?;
+                self.console.serve_session_tls(Some(TlsListen { addr, cert: cert.into(), key: key.into() }));
+            },
+            (Some(_), _, _) => return Err(ConfigError::Invalid("[session] listen needs a cert and a key".to_string())),
+            _ => {},
+        }
+        if let Some(mqtt) = &config.mqtt {
+            if mqtt.topics.is_empty() {
+                return Err(ConfigError::Invalid("[mqtt] needs a [mqtt.topics] table of topic = sender".to_string()));
+            }
+            let mut bridge = MqttBridge::new(mqtt.host.clone(), mqtt.port.unwrap_or(1883));
+            bridge.client_id = mqtt.client_id.clone().unwrap_or(bridge.client_id);
+            bridge.username = mqtt.username.clone();
+            bridge.password = mqtt.password.clone();
+            //the most specific filters first, a topic goes to the first that matches
+            let mut topics: Vec<(&String, &String)> = mqtt.topics.iter().collect();
+            topics.sort_by_key(|(filter, _)| std::cmp::Reverse(filter.len()));
+            for (filter, sender) in topics {
+                bridge = bridge.subscribe(filter.clone(), sender.clone());
+            }
+            self.console.set_mqtt_bridge(Some(bridge));
+        }
+        if let Some(relay) = &config.relay {
+            let service = RelayService::parse(&relay.service)
+                .ok_or_else(|| ConfigError::Invalid(format!("unknown [relay] service: {}, discord or slack", relay.service)))?;
+            let token = relay.token.clone().or_else(|| std::env::var("DUALZONE_RELAY_TOKEN").ok())
+                .ok_or_else(|| ConfigError::Invalid("[relay] needs a token, or one in DUALZONE_RELAY_TOKEN".to_string()))?;
+            if relay.channel.is_empty() {
+                return Err(ConfigError::Invalid("[relay] needs the channel to relay".to_string()));
+            }
+            let mut chat = ChatRelay::new(service, relay.channel.clone(), token).allow_commands(relay.commands.unwrap_or(false));
+            for topic in &relay.topics {
+                chat = chat.mirror(topic.clone());
+            }
+            if let Some(poll) = relay.poll.as_deref() {
+                let poll = parse_duration(poll).filter(|poll| !poll.is_zero())
+                    .ok_or_else(|| ConfigError::Invalid(format!("invalid [relay] poll: {}, eg. \"2s\"", poll)))?;
+                chat = chat.poll(poll);
+            }
+            self.console.set_chat_relay(Some(chat));
+        }
+        if let Some(nats) = &config.nats {
+            let subject = nats.subject.clone().unwrap_or_else(|| "dualzone".to_string());
+            if subject.is_empty() || subject.contains(['*', '>', ' ']) {
+                return Err(ConfigError::Invalid(format!("[nats] subject has to be a plain subject: {}", subject)));
+            }
+            self.console.set_nats_bridge(Some(NatsTarget { url: nats.url.clone(), subject, origin: nats.origin.clone() }));
+        }
+        if let Some(grpc) = &config.grpc {
+            let addr = grpc.listen.parse().map_err(|_| ConfigError::Invalid(format!("invalid [grpc] listen address: {}", grpc.listen)))?;
+            self.console.serve_grpc(Some(RemoteListen { addr, token: grpc.token.clone(), senders: grpc.senders.clone() }));
+        }
+        if let Some(http) = &config.http {
+            let addr = http.listen.parse().map_err(|_| ConfigError::Invalid(format!("invalid [http] listen address: {}", http.listen)))?;
+            self.console.serve_http(Some(RemoteListen { addr, token: http.token.clone(), senders: http.senders.clone() }));
+        }
+        if let Some(forward) = config.syslog.forward.as_deref() {
+            let mut target = SyslogTarget::new(forward);
+            if let Some(facility) = config.syslog.facility.as_deref() {
+                target.facility = syslog::parse_facility(facility)
+                    .ok_or_else(|| ConfigError::Invalid(format!("unknown [syslog] facility: {}, use eg. user, daemon or local0", facility)))?;
+            }
+            target.app_name = config.syslog.app_name.clone().unwrap_or(target.app_name);
+            self.console.forward_syslog(Some(target));
+        }
+        if let Some(otel) = &config.otel {
+            //the standard variable says where the collector is when the table doesn't
+            let endpoint = otel.endpoint.clone()
+                .or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok())
+                .unwrap_or_else(|| "http://localhost:4317".to_string());
+            self.console.export_telemetry(Some(endpoint));
+        }
+        if !config.tail.is_empty() {
+            self.console.follow_files(Some(TailSource::new(config.tail.iter())));
+        }
+        if let Some(pager) = &config.pager {
+            let commands = pager.commands.iter().map(|(name, pages)| (name.trim_start_matches('/').to_ascii_lowercase(), *pages)).collect();
+            self.console.set_pager_policy(PagerPolicy { enabled: pager.enabled.unwrap_or(true), commands });
+        }
+        if let Some(shell) = &config.shell {
+            let mut policy = ShellPolicy { allow: shell.allow.clone(), ..ShellPolicy::default() };
+            policy.confirm = shell.confirm.clone().unwrap_or(policy.confirm);
+            self.console.set_shell_policy(policy);
+        }
+        if let Some(journal) = config.journal {
+            self.console.forward_to_journal(journal);
+        }
+        if let Some(listen) = config.syslog.listen.as_deref() {
+            let addr = listen.parse().map_err(|_| ConfigError::Invalid(format!("invalid [syslog] listen address: {}", listen)))?;
+            self.console.serve_syslog(Some(addr));
+        }
+        if let Some(scheduling) = config.scheduling.as_deref() {
+            match Scheduling::parse(scheduling) {
+                Some(scheduling) => self.console.set_scheduling(scheduling),
+                None => return Err(ConfigError::Invalid(format!("unknown scheduling: {}", scheduling))),
+            }
+        }
+        if let Some(eof) = config.on_eof.as_deref() {
+            match EofBehavior::parse(eof) {
+                Some(eof) => self.console.set_eof_behavior(eof),
+                None => return Err(ConfigError::Invalid(format!("unknown on_eof: {}, use shutdown, display or reattach", eof))),
+            }
+        }
+        if let Some(mode) = config.edit_mode.as_deref() {
+            match EditMode::parse(mode) {
+                Some(mode) => self.console.set_edit_mode(mode),
+                None => return Err(ConfigError::Invalid(format!("unknown edit_mode: {}", mode))),
+            }
+        }
+        let limits = config.memory_limits(&self.console.memory_limits)?;
+        self.console.set_memory_limits(limits);
+        for (chord, action) in config.keys.iter() {
+            self.console.keymap.bind_str(chord, action).map_err(ConfigError::Invalid)?;
+        }
+        Ok(self)
+    }
+
+    /**
+     * Read and apply a TOML config file, see ConsoleConfig for the format
+     */
+    pub fn config_file(self, path: &Path) -> Result<Self, ConfigError> {
+        let config = ConsoleConfig::load(path)?;
+        self.config(&config)
+    }
+
+    pub fn build(self) -> Console<T> {
+        self.console
+    }
+}
@@ -0,0 +1,324 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::broker::schedule::parse_duration;
+use crate::broker::split::ALL_SOURCE;
+use crate::broker::threads::ThreadCommand;
+use crate::broker::chat::ChatCommand;
+
+//External
+use tokio::time::Duration;
+
+/**
+ * What /filter, /highlight and /alert do to their list of rules
+ *  ~ List shows the rules, the command on its own.
+ *  ~ Add appends a rule, eg. /filter add exclude sender heartbeat*, /highlight add red,bold ERR-\d+
+ *    or /alert add bell,pin when priority Critical+.
+ *  ~ Remove takes out the rule with the number List shows, Clear all of them.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleCommand {
+    List,
+    Add(String),
+    Remove(Option<usize>),
+    Clear,
+    Unknown(String),
+}
+
+/**
+ * What /macro does
+ *  ~ Record starts recording the typed lines under a name, Stop ends it and keeps the macro.
+ *  ~ Play sends the lines again, with the recorded delays when timed (/macro play name timed).
+ *  ~ List shows the macros, the command on its own, Delete forgets one.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroCommand {
+    List,
+    Record(Option<String>),
+    Stop,
+    Play { name: Option<String>, timed: bool },
+    Delete(Option<String>),
+    Unknown(String),
+}
+
+/**
+ * What /schedule does
+ *  ~ Add schedules a notice, once after a delay or every interval, eg. /schedule "backup starting" every 1h
+ *    or /schedule "stand-up" in 10m. The quotes are only needed when the text itself ends in "every ..." or "in ...".
+ *  ~ List shows what is scheduled, the command on its own, Cancel takes out the one with the id List shows.
+ *  ~ Invalid is a line that could not be parsed, with the reason.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleCommand {
+    List,
+    Add { text: String, delay: Duration, repeat: bool },
+    Cancel(Option<u64>),
+    Invalid(String),
+}
+
+/**
+ * What /split does
+ *  ~ Start follows two sources in a split output area, eg. /split errors all, the second one is all when left out.
+ *  ~ Off ends the split, Status says what is followed, the command on its own.
+ *  ~ Filter changes the filter rules of pane 1 or 2 the way /filter does the Console's, eg. /split 2 add include priority Warning+.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SplitCommand {
+    Status,
+    Start(String, String),
+    Off,
+    Filter { pane: usize, command: RuleCommand },
+}
+
+/**
+ * What /proc does with the processes started with Console::spawn_process
+ *  ~ List shows them, the command on its own.
+ *  ~ Send writes a line to a process's stdin, eg. /proc send server reload.
+ *  ~ Focus sends every typed line that isn't a /command to a process's stdin, until /proc focus on its own.
+ *  ~ Close closes a process's stdin, Kill ends it.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessCommand {
+    List,
+    Send { name: Option<String>, line: String },
+    Focus(Option<String>),
+    Close(Option<String>),
+    Kill(Option<String>),
+    Unknown(String),
+}
+
+/**
+ * Slash commands typed into the input zone, handled by the Console broker instead of being echoed as output.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Stats,
+    Who,
+    Senders,
+    Plugins,
+    Scripts(Option<String>),
+    Macro(MacroCommand),
+    Schedule(ScheduleCommand),
+    SenderStatus(Option<String>),
+    Ban(Option<String>),
+    Unban(Option<String>),
+    Audit(usize),
+    DeadLetters(usize),
+    Level(Option<String>),
+    Unpin(Option<String>),
+    Search(Option<String>),
+    Pause(bool),
+    Detach,
+    EditMode(Option<String>),
+    Copy(Option<usize>),
+    View(Option<String>),
+    Tab(Option<String>),
+    Split(SplitCommand),
+    Filter(RuleCommand),
+    Highlight(RuleCommand),
+    Alert(RuleCommand),
+    Process(ProcessCommand),
+    Shell(String),
+    Thread(ThreadCommand),
+    Fields(Option<String>),
+    Chat(ChatCommand),
+    Export {
+        path: Option<String>,
+        format: Option<String>,
+    },
+    Unknown(String),
+}
+
+impl Command {
+    /**
+     * Whether the command reaches past the console into the host it runs on: a shell, a file, a process, the clipboard,
+     * the scripts or the senders. Only the user at the terminal runs these, see broker::message::Origin.
+     * A macro is the terminal's own lines played back as typed there, and its file is on the host, so /macro is one of them
+     */
+    pub fn is_host_only(&self) -> bool {
+        match self {
+            Command::Shell(_) | Command::Export { .. } | Command::Copy(_) | Command::Ban(_) | Command::Unban(_) => true,
+            Command::Process(command) => *command != ProcessCommand::List,
+            Command::Macro(command) => *command != MacroCommand::List,
+            Command::Scripts(subcommand) => subcommand.is_some(),
+            _ => false,
+        }
+    }
+
+    /**
+     * Parse a line of user input, returns None when the line is not a command
+     */
+    pub fn parse(line: &str) -> Option<Command> {
+        let line = line.trim();
+        if !line.starts_with('/') {
+            return None;
+        }
+        let mut parts = line[1..].split_whitespace();
+        let name = match parts.next() {
+            Some(n) => n.to_ascii_lowercase(),
+            None => return Some(Command::Unknown(line.to_string())),
+        };
+        match name.as_str() {
+            "stats" => Some(Command::Stats),
+            "who" => Some(Command::Who),
+            "senders" => Some(Command::Senders),
+            "plugins" => Some(Command::Plugins),
+            "macro" => Some(Command::Macro(MacroCommand::parse(parts))),
+            "schedule" => Some(Command::Schedule(ScheduleCommand::parse(line))),
+            "scripts" => Some(Command::Scripts(parts.next().map(|p| p.to_ascii_lowercase()))),
+            "status" => Some(Command::SenderStatus(parts.next().map(|p| p.to_string()))),
+            "ban" => Some(Command::Ban(parts.next().map(|p| p.to_string()))),
+            "unban" => Some(Command::Unban(parts.next().map(|p| p.to_string()))),
+            "pause" => Some(Command::Pause(true)),
+            "resume" => Some(Command::Pause(false)),
+            "detach" => Some(Command::Detach),
+            //everything after /search is the term, spaces included
+            "search" => {
+                let term = line[1..].split_once(char::is_whitespace).map(|(_, t)| t).map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+                Some(Command::Search(term))
+            },
+            "tab" => Some(Command::Tab(parts.next().map(|p| p.to_string()))),
+            "view" => Some(Command::View(parts.next().map(|p| p.to_string()))),
+            "split" => Some(Command::Split(SplitCommand::parse(line, parts))),
+            "copy" => Some(Command::Copy(parts.next().and_then(|n| n.parse::<usize>().ok()).filter(|n| *n > 0))),
+            "export" => Some(Command::Export { path: parts.next().map(|p| p.to_string()), format: parts.next().map(|f| f.to_string()) }),
+            "mode" => Some(Command::EditMode(parts.next().map(|p| p.to_string()))),
+            "unpin" => Some(Command::Unpin(parts.next().map(|p| p.to_string()))),
+            "level" => Some(Command::Level(parts.next().map(|p| p.to_string()))),
+            "filter" => Some(Command::Filter(RuleCommand::parse(line, parts))),
+            "highlight" => Some(Command::Highlight(RuleCommand::parse(line, parts))),
+            "alert" => Some(Command::Alert(RuleCommand::parse(line, parts))),
+            "proc" => Some(Command::Process(ProcessCommand::parse(line, parts))),
+            "sh" => Some(Command::Shell(line[1..].split_once(char::is_whitespace).map_or("", |(_, rest)| rest).trim().to_string())),
+            "fields" => Some(Command::Fields(parts.next().map(|p| p.to_ascii_lowercase()))),
+            "expand" => Some(Command::Thread(ThreadCommand::Expand(parts.next().map(|p| p.to_string())))),
+            "collapse" => Some(Command::Thread(ThreadCommand::Collapse(parts.next().map(|p| p.to_string())))),
+            //what is said keeps its spaces
+            "nick" | "me" | "msg" => ChatCommand::parse(&name, line[1..].split_once(char::is_whitespace).map_or("", |(_, rest)| rest)).map(Command::Chat),
+            "audit" => {
+                let count = parts.next().and_then(|n| n.parse::<usize>().ok()).unwrap_or(10);
+                Some(Command::Audit(count))
+            },
+            "dlq" => {
+                let count = parts.next().and_then(|n| n.parse::<usize>().ok()).unwrap_or(5);
+                Some(Command::DeadLetters(count))
+            },
+            _ => Some(Command::Unknown(line.to_string())),
+        }
+    }
+}
+
+impl ProcessCommand {
+    /**
+     * The part of a /proc line after the command name, the line /proc send writes is everything after the process name
+     */
+    fn parse<'a>(line: &str, mut parts: impl Iterator<Item = &'a str>) -> ProcessCommand {
+        let name = |parts: &mut dyn Iterator<Item = &'a str>| parts.next().map(|p| p.to_string());
+        match parts.next().map(|p| p.to_ascii_lowercase()).as_deref() {
+            None | Some("list") => ProcessCommand::List,
+            Some("send") => {
+                let text = line.splitn(4, char::is_whitespace).nth(3).unwrap_or("").to_string();
+                ProcessCommand::Send { name: name(&mut parts), line: text }
+            },
+            Some("focus") => ProcessCommand::Focus(name(&mut parts)),
+            Some("close") => ProcessCommand::Close(name(&mut parts)),
+            Some("kill") => ProcessCommand::Kill(name(&mut parts)),
+            Some(other) => ProcessCommand::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl MacroCommand {
+    /**
+     * The part of a /macro line after the command name
+     */
+    fn parse<'a>(mut parts: impl Iterator<Item = &'a str>) -> MacroCommand {
+        match parts.next().map(|p| p.to_ascii_lowercase()).as_deref() {
+            None | Some("list") => MacroCommand::List,
+            Some("record") => MacroCommand::Record(parts.next().map(|p| p.to_string())),
+            Some("stop") => MacroCommand::Stop,
+            Some("play") => {
+                let name = parts.next().map(|p| p.to_string());
+                let timed = parts.next().is_some_and(|p| p.eq_ignore_ascii_case("timed"));
+                MacroCommand::Play { name, timed }
+            },
+            Some("delete") => MacroCommand::Delete(parts.next().map(|p| p.to_string())),
+            Some(other) => MacroCommand::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl ScheduleCommand {
+    /**
+     * The whole /schedule line, the text of a notice may contain spaces
+     */
+    fn parse(line: &str) -> ScheduleCommand {
+        let rest = line[1..].split_once(char::is_whitespace).map_or("", |(_, rest)| rest).trim();
+        let mut words = rest.split_whitespace();
+        match words.next().map(|w| w.to_ascii_lowercase()).as_deref() {
+            None | Some("list") => return ScheduleCommand::List,
+            Some("cancel") => return ScheduleCommand::Cancel(words.next().and_then(|id| id.parse::<u64>().ok())),
+            _ => {},
+        }
+
+        //the last two words say when, everything before them is the text
+        let mut tail = rest.rsplitn(3, char::is_whitespace);
+        let (duration, mode, text) = match (tail.next(), tail.next(), tail.next()) {
+            (Some(duration), Some(mode), Some(text)) => (duration, mode.to_ascii_lowercase(), text.trim()),
+            _ => return ScheduleCommand::Invalid("usage: /schedule \"text\" every <interval> | in <delay>".to_string()),
+        };
+        let repeat = match mode.as_str() {
+            "every" => true,
+            "in" => false,
+            _ => return ScheduleCommand::Invalid(format!("expected every or in before {}", duration)),
+        };
+        let delay = match parse_duration(duration) {
+            Some(delay) if !(repeat && delay.is_zero()) => delay,
+            _ => return ScheduleCommand::Invalid(format!("{} is not a duration, eg. 90s, 10m, 1h30m", duration)),
+        };
+        let text = text.strip_prefix('"').and_then(|text| text.strip_suffix('"')).unwrap_or(text).to_string();
+        if text.is_empty() {
+            return ScheduleCommand::Invalid("nothing to send".to_string());
+        }
+        ScheduleCommand::Add { text, delay, repeat }
+    }
+}
+
+impl SplitCommand {
+    /**
+     * The part of a /split line after the command name
+     */
+    fn parse<'a>(line: &str, mut parts: impl Iterator<Item = &'a str>) -> SplitCommand {
+        let first = match parts.next() {
+            None => return SplitCommand::Status,
+            Some(first) if first.eq_ignore_ascii_case("off") => return SplitCommand::Off,
+            Some(first) => first,
+        };
+        match first.parse::<usize>() {
+            Ok(pane @ 1..=2) => {
+                //what follows the pane number reads like a /filter line
+                let rest = line[1..].split_once(char::is_whitespace).map_or("", |(_, rest)| rest).trim_start();
+                SplitCommand::Filter { pane: pane - 1, command: RuleCommand::parse(&format!("/{}", rest), parts) }
+            },
+            _ => SplitCommand::Start(first.to_string(), parts.next().unwrap_or(ALL_SOURCE).to_string()),
+        }
+    }
+}
+
+impl RuleCommand {
+    /**
+     * The part of a /filter or /highlight line after the command name
+     */
+    fn parse<'a>(line: &str, mut parts: impl Iterator<Item = &'a str>) -> RuleCommand {
+        match parts.next().map(|p| p.to_ascii_lowercase()).as_deref() {
+            None | Some("list") => RuleCommand::List,
+            //the rule is the rest of the line, a pattern may contain spaces
+            Some("add") => RuleCommand::Add(line[1..].splitn(3, char::is_whitespace).nth(2).unwrap_or("").trim().to_string()),
+            Some("remove") => RuleCommand::Remove(parts.next().and_then(|n| n.parse::<usize>().ok()).filter(|n| *n > 0)),
+            Some("clear") => RuleCommand::Clear,
+            Some(other) => RuleCommand::Unknown(other.to_string()),
+        }
+    }
+}
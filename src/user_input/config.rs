@@ -0,0 +1,577 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use crate::broker::memory::{EvictionPolicy, MemoryLimit, MemoryLimits};
+use crate::user_input::structs::PriorityStatus;
+use crate::output::notify::Alert;
+use crate::output::theme::Theme;
+use crate::broker::schedule::parse_duration;
+
+//External
+use serde::{Serialize, Deserialize};
+use tokio::time::Duration;
+
+/**
+ * Where the binary looks for its config when DUALZONE_CONFIG is not set
+ */
+pub const DEFAULT_CONFIG_FILE: &str = "dualzone.toml";
+
+/**
+ * A time to live from the [expiry] table, None for the priority is the "default" entry and None for the ttl "never"
+ */
+pub type ExpiryTime = (Option<PriorityStatus>, Option<Duration>);
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read config: {}", e),
+            ConfigError::Parse(e) => write!(f, "could not parse config: {}", e),
+            ConfigError::Invalid(e) => write!(f, "invalid config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/**
+ * A message time to live, a number of seconds or "never"
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Ttl {
+    Seconds(f64),
+    Keyword(String),
+}
+
+impl Ttl {
+    pub fn to_duration(&self) -> Result<Option<Duration>, ConfigError> {
+        match self {
+            Ttl::Seconds(secs) if *secs >= 0.0 => Ok(Some(Duration::from_secs_f64(*secs))),
+            Ttl::Keyword(word) if word.eq_ignore_ascii_case("never") => Ok(None),
+            other => Err(ConfigError::Invalid(format!("expiry must be seconds or \"never\", got {:?}", other))),
+        }
+    }
+}
+
+/**
+ * A [memory.*] table, the limits of one buffer. Missing keys keep the current limit
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitConfig {
+    pub entries: Option<usize>,
+    pub bytes: Option<usize>,
+    pub eviction: Option<String>,
+}
+
+impl LimitConfig {
+    fn apply(&self, limit: &mut MemoryLimit, buffer: &str) -> Result<(), ConfigError> {
+        if let Some(entries) = self.entries {
+            limit.max_entries = Some(entries);
+        }
+        if let Some(bytes) = self.bytes {
+            limit.max_bytes = Some(bytes);
+        }
+        if let Some(eviction) = self.eviction.as_deref() {
+            limit.eviction = EvictionPolicy::parse(eviction)
+                .ok_or_else(|| ConfigError::Invalid(format!("unknown eviction in [memory.{}]: {}", buffer, eviction)))?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * One [[highlights]] table
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighlightConfig {
+    pub pattern: String,
+    pub style: String,
+}
+
+/**
+ * One [[webhooks]] table, the URL the matching rendered lines are posted to
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub when: Option<String>,
+    pub format: Option<String>,
+    pub retries: Option<u32>,
+}
+
+/**
+ * The [session] table, who may attach to the session: one token for everyone or a password per user, not both.
+ * listen serves it on the network too, over TLS with cert and key
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub token: Option<String>,
+    pub users: HashMap<String, String>,
+    pub listen: Option<String>,
+    pub cert: Option<String>,
+    pub key: Option<String>,
+}
+
+/**
+ * The [mqtt] table, the broker to bridge and [mqtt.topics], topic filters to the sender their messages come from
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topics: HashMap<String, String>,
+}
+
+/**
+ * The [relay] table, the Discord or Slack channel the topics are mirrored to and the input comes from
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayConfig {
+    pub service: String,
+    pub channel: String,
+    pub token: Option<String>,
+    pub topics: Vec<String>,
+    pub commands: Option<bool>,
+    pub poll: Option<String>,
+}
+
+/**
+ * The [nats] table, the server the SubConsoles are distributed over, the subject they go out under and this console's name there
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NatsConfig {
+    pub url: String,
+    pub subject: Option<String>,
+    pub origin: Option<String>,
+}
+
+/**
+ * The [grpc] and [http] tables, the address the broker is served on that way and the token calls have to send,
+ * and the token of each sender a call may send as
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    pub listen: String,
+    pub token: Option<String>,
+    pub senders: HashMap<String, String>,
+}
+
+/**
+ * The [syslog] table, the daemon the rendered lines are forwarded to (as facility and app_name) and the UDP port it listens on
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyslogConfig {
+    pub forward: Option<String>,
+    pub facility: Option<String>,
+    pub app_name: Option<String>,
+    pub listen: Option<String>,
+}
+
+/**
+ * The [otel] table, the OTLP collector the broker's telemetry goes to
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtelConfig {
+    pub endpoint: Option<String>,
+}
+
+/**
+ * The [pager] table, whether long command results are paged and which commands say otherwise
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PagerConfig {
+    pub enabled: Option<bool>,
+    pub commands: HashMap<String, bool>,
+}
+
+/**
+ * The [fields] table, whether the fields of messages are drawn and the style of those whose key matches
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldsConfig {
+    pub shown: Option<bool>,
+    pub colors: HashMap<String, String>,
+}
+
+/**
+ * The [shell] table, what /sh runs and what it asks about first
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellConfig {
+    pub allow: Vec<String>,
+    pub confirm: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    pub scrollback: Option<LimitConfig>,
+    pub history: Option<LimitConfig>,
+    pub dead_letters: Option<LimitConfig>,
+}
+
+/**
+ * The config file, TOML. Everything is optional and falls back to the Console defaults.
+ *
+ *     status_bar = true
+ *     tabs = true
+ *     mouse = false
+ *     alternate_screen = true
+ *     highlight_input = true
+ *     autosuggest = false
+ *     dedup = false
+ *     fold = false
+ *     chat = true
+ *     record = "session.jsonl"
+ *     audit = "audit.jsonl"
+ *     phonebook = "phonebook.json"
+ *     level = "Notice"
+ *     edit_mode = "vi"
+ *     on_eof = "display"
+ *     scheduling = "fifo"
+ *     theme = "mine"
+ *     color_depth = "256"
+ *     accessible = false
+ *     filters = ["exclude sender heartbeat*", "include priority Warning+"]
+ *     alerts = ["bell,flash when priority Critical+", "pin,webhook:https://hooks.example.com/ops when sender db* and payload (?i)timeout"]
+ *     plugins = ["plugins/libhello.so"]
+ *     scripts = "scripts"
+ *     macros = "macros.toml"
+ *     journal = true
+ *     tail = ["/var/log/syslog", "app.log"]
+ *
+ *     [expiry]
+ *     default = 2
+ *     Verbose = 5
+ *     Critical = "never"
+ *
+ *     [notify]
+ *     Urgent = "bell,desktop"
+ *     Warning = "bell"
+ *     away_after = "2m"
+ *
+ *     [keys]
+ *     "ctrl+r" = "search"
+ *     "f5" = "action:refresh"
+ *     "ctrl+s" = "none"
+ *
+ *     [[highlights]]
+ *     pattern = "ERR-\\d+"
+ *     style = "red,bold"
+ *
+ *     [[webhooks]]
+ *     url = "https://hooks.slack.com/services/T000/B000/XXXX"
+ *     when = "priority Critical+ and sender db*"
+ *     format = "slack"
+ *     retries = 3
+ *
+ *     [fields]
+ *     shown = true
+ *
+ *     [fields.colors]
+ *     "http.status" = "green"
+ *     "*_ms" = "dim"
+ *
+ *     [memory.scrollback]
+ *     entries = 20000
+ *     bytes = 16777216
+ *     eviction = "lowest-priority"
+ *
+ *     [themes.mine]
+ *     base = "dark"
+ *     Critical = "#ff5f5f,bold"
+ *     status_bar = "black,on_cyan"
+ *
+ *     [session]
+ *     listen = "0.0.0.0:7443"
+ *     cert = "console.pem"
+ *     key = "console.key"
+ *
+ *     [session.users]
+ *     alice = "correct horse"
+ *
+ *     [mqtt]
+ *     host = "broker.local"
+ *     port = 1883
+ *
+ *     [mqtt.topics]
+ *     "home/+/temperature" = "thermostat"
+ *     "alerts/#" = "alerts"
+ *
+ *     [relay]
+ *     service = "discord"
+ *     channel = "1094738291038472210"
+ *     topics = ["game-*", "alerts"]
+ *     commands = false
+ *
+ *     [nats]
+ *     url = "nats://localhost:4222"
+ *     subject = "dualzone"
+ *     origin = "build-box"
+ *
+ *     [grpc]
+ *     listen = "127.0.0.1:50051"
+ *     token = "s3cret"
+ *
+ *     [grpc.senders]
+ *     db = "db-s3cret"
+ *
+ *     [http]
+ *     listen = "127.0.0.1:8080"
+ *
+ *     [syslog]
+ *     forward = "127.0.0.1:514"
+ *     facility = "local0"
+ *     listen = "0.0.0.0:5514"
+ *
+ *     [otel]
+ *     endpoint = "http://localhost:4317"
+ *
+ *     [pager]
+ *     enabled = true
+ *
+ *     [pager.commands]
+ *     audit = false
+ *
+ *     [shell]
+ *     allow = ["git status", "git log", "df", "systemctl status *", "systemctl restart *"]
+ *     confirm = ["systemctl restart *"]
+ *
+ * highlight_input styles /commands in the input line as they are typed, see user_input::highlighter::CommandHighlighter.
+ * tabs puts a tab bar over the main zone with a tab per topic and module, see output::tabs::TabBar.
+ * autosuggest suggests lines from the input history as they are typed, on unless set to false.
+ * audit appends every administrative action (authorizations, blacklisting, filter changes, commands) to that file, see /audit.
+ * phonebook keeps the known senders and their statuses in that file across restarts, bans included.
+ * dedup collapses a module's repeated lines into one "text (×N)" line, on unless set to false.
+ * fold draws a module's payload of several lines (a panic, a stack trace) as its first line until /expand, on unless set to false.
+ * chat draws what the users of the session say as "<nick> text", for a terminal chat server with /nick, /me and /msg,
+ * see Console::set_chat_mode.
+ * on_eof is what the end of stdin does: "shutdown" (the default), "display" to keep showing output or "reattach" to wait
+ * for more, see user_input::input::EofBehavior.
+ * scheduling is "priority" (the default), higher priority messages are taken from the queue first, or "fifo".
+ * filters are filter rules in the /filter add syntax, added after any the builder already has.
+ * alerts are alert rules in the /alert add syntax: bell, flash, desktop, pin, script:<name> or webhook:<url> when the message
+ * matches every condition, see broker::alerts::AlertRule. webhook needs the webhook feature.
+ * plugins are libraries loaded with Console::load_plugin, in order, needs the plugins feature.
+ * scripts is a directory of Rhai scripts, see broker::scripts::Scripts, needs the scripting feature.
+ * macros is the file /macro saves the recorded macros to and loads them from, see user_input::macros::Macros.
+ * journal forwards every rendered line to journald, see Console::forward_to_journal. It needs the systemd feature and Linux,
+ * which also tells systemd when the broker is ready (sd_notify, for Type=notify units).
+ * tail follows those files like tail -F, each one a sender named after it, see broker::tail::TailSource.
+ * [notify] sets the alert of a priority ("bell", "desktop", "flash", "bell,desktop" or "none", "default" for the rest) and how long
+ * without input before the user counts as away, see output::notify::NotifyPolicy. desktop needs the desktop-notify feature.
+ * [[highlights]] adds a highlight rule each, see output::highlight::HighlightStyle for the style words.
+ * [[webhooks]] posts the rendered lines that match every condition of when (those of an alert rule, every line when left out)
+ * to url, as format "json" (the default), "slack" or "discord". A line that could not be posted is tried again with backoff,
+ * 5 times unless retries says, see broker::webhook::WebhookSink. It needs the webhook feature.
+ * [fields] draws the key=value fields of messages after their payload unless shown = false, [fields.colors] styles the fields
+ * whose key matches the glob, see output::fields::FieldView.
+ * [keys] changes single bindings of the default keymap, see keymap::Action for the names.
+ * [memory.scrollback], [memory.history] and [memory.dead_letters] limit those buffers, eviction is "oldest" or "lowest-priority".
+ * theme is plain (the default), dark, light or one of the [themes.*] tables, which start from their base theme (plain unless
+ * they say) and change its styles by name, see output::theme::Theme. color_depth is "16", "256", "truecolor" or "none", detected when left out
+ * (a NO_COLOR environment variable makes it none). accessible labels messages by priority and keeps the screen still,
+ * see Console::enable_accessibility.
+ * [session] makes the clients of the session authenticate, with token = "..." for one secret or a [session.users] table
+ * of passwords, see Console::set_session_auth. Clients give theirs in DUALZONE_TOKEN or DUALZONE_PASSWORD.
+ * listen serves the session over TLS at that address with the cert and key PEM files, see Console::serve_session_tls.
+ * It needs the tls feature, and clients trust the certificate in DUALZONE_CA.
+ * [mqtt] shows what is published on the [mqtt.topics] in the output zone, as messages from the sender each filter names
+ * (client_id, username and password are optional), see broker::mqtt::MqttBridge. It needs the mqtt feature.
+ * [relay] mirrors the lines of the topics (globs) to a "discord" or "slack" channel and takes what people say there as their input,
+ * /commands only when commands = true. The bot token is token or DUALZONE_RELAY_TOKEN, the channel is asked every poll ("2s"
+ * unless set), see broker::relay::ChatRelay. It needs the relay feature.
+ * [nats] shares the SubConsoles with the consoles on the same NATS subject (dualzone unless set), see Console::distribute_subconsoles.
+ * It needs the nats feature.
+ * [grpc] serves the broker over gRPC (proto/dualzone.proto) for services in any language, calls send the token as
+ * "authorization: Bearer <token>", see Console::serve_grpc. Only loopback is served without a token, it needs the grpc feature.
+ * A call sends lines as a sender of [grpc.senders] when it names it in x-dualzone-client and sends its token in x-dualzone-token.
+ * [http] is the same over HTTP, POST /messages and GET /stream (Server-Sent Events), see Console::serve_http. It needs the http feature.
+ * [syslog] forwards every rendered line to the daemon at forward (RFC 5424 over UDP, local0 and dualzone unless facility and
+ * app_name say otherwise) and shows what is sent to listen, each app@host its own sender once it is in the phonebook, see broker::syslog.
+ * [otel] exports spans and counters over OTLP to endpoint (OTEL_EXPORTER_OTLP_ENDPOINT or localhost:4317 when left out),
+ * see Console::export_telemetry. It needs the otel feature.
+ * [pager] pages the results of /commands that are taller than the output zone, on unless enabled = false,
+ * [pager.commands] turns it on or off for a command by name, see broker::pager::PagerPolicy.
+ * [shell] lets /sh run the commands allow lists, only for the user at the terminal, and asks first about those confirm lists
+ * (destructive ones like rm, kill or git push unless set), see broker::shell::ShellPolicy.
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsoleConfig {
+    pub status_bar: Option<bool>,
+    pub tabs: Option<bool>,
+    pub mouse: Option<bool>,
+    pub alternate_screen: Option<bool>,
+    pub highlight_input: Option<bool>,
+    pub autosuggest: Option<bool>,
+    pub dedup: Option<bool>,
+    pub fold: Option<bool>,
+    pub chat: Option<bool>,
+    pub record: Option<String>,
+    pub audit: Option<String>,
+    pub phonebook: Option<String>,
+    pub level: Option<String>,
+    pub edit_mode: Option<String>,
+    pub on_eof: Option<String>,
+    pub scheduling: Option<String>,
+    pub theme: Option<String>,
+    pub color_depth: Option<String>,
+    pub accessible: Option<bool>,
+    pub filters: Vec<String>,
+    pub alerts: Vec<String>,
+    pub plugins: Vec<String>,
+    pub scripts: Option<String>,
+    pub macros: Option<String>,
+    pub journal: Option<bool>,
+    pub tail: Vec<String>,
+    pub highlights: Vec<HighlightConfig>,
+    pub webhooks: Vec<WebhookConfig>,
+    pub fields: Option<FieldsConfig>,
+    pub expiry: HashMap<String, Ttl>,
+    pub notify: HashMap<String, String>,
+    pub keys: HashMap<String, String>,
+    pub memory: MemoryConfig,
+    pub themes: HashMap<String, HashMap<String, String>>,
+    pub session: SessionConfig,
+    pub mqtt: Option<MqttConfig>,
+    pub relay: Option<RelayConfig>,
+    pub nats: Option<NatsConfig>,
+    pub grpc: Option<RemoteConfig>,
+    pub http: Option<RemoteConfig>,
+    pub syslog: SyslogConfig,
+    pub otel: Option<OtelConfig>,
+    pub pager: Option<PagerConfig>,
+    pub shell: Option<ShellConfig>,
+}
+
+impl ConsoleConfig {
+    pub fn load(path: &Path) -> Result<ConsoleConfig, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        ConsoleConfig::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<ConsoleConfig, ConfigError> {
+        toml::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /**
+     * current with the [memory] tables applied on top
+     */
+    pub fn memory_limits(&self, current: &MemoryLimits) -> Result<MemoryLimits, ConfigError> {
+        let mut limits = current.clone();
+        if let Some(scrollback) = &self.memory.scrollback {
+            scrollback.apply(&mut limits.scrollback, "scrollback")?;
+        }
+        if let Some(history) = &self.memory.history {
+            history.apply(&mut limits.history, "history")?;
+        }
+        if let Some(dead_letters) = &self.memory.dead_letters {
+            dead_letters.apply(&mut limits.dead_letters, "dead_letters")?;
+        }
+        Ok(limits)
+    }
+
+    /**
+     * The alerts of the notify table as (priority, alert) pairs, "default" comes back as None for the priority
+     */
+    pub fn notify_alerts(&self) -> Result<Vec<(Option<PriorityStatus>, Alert)>, ConfigError> {
+        let mut alerts = Vec::new();
+        for (name, value) in self.notify.iter() {
+            if name.eq_ignore_ascii_case("away_after") {
+                continue;
+            }
+            let priority = if name.eq_ignore_ascii_case("default") {
+                None
+            } else {
+                match PriorityStatus::parse(name) {
+                    Some(priority) => Some(priority),
+                    None => return Err(ConfigError::Invalid(format!("unknown priority in [notify]: {}", name))),
+                }
+            };
+            let alert = Alert::parse(value).ok_or_else(|| ConfigError::Invalid(format!("unknown alert in [notify]: {}", value)))?;
+            if alert.desktop && !cfg!(feature = "desktop-notify") {
+                return Err(ConfigError::Invalid("desktop alerts need the desktop-notify feature".to_string()));
+            }
+            alerts.push((priority, alert));
+        }
+        Ok(alerts)
+    }
+
+    /**
+     * The away_after of the notify table, if set
+     */
+    pub fn away_after(&self) -> Result<Option<Duration>, ConfigError> {
+        match self.notify.iter().find(|(name, _)| name.eq_ignore_ascii_case("away_after")) {
+            Some((_, value)) => parse_duration(value).map(Some).ok_or_else(|| ConfigError::Invalid(format!("away_after is not a duration: {}", value))),
+            None => Ok(None),
+        }
+    }
+
+    /**
+     * The theme named by theme, a [themes.*] table before a built-in theme of the same name
+     */
+    pub fn resolve_theme(&self) -> Result<Option<Theme>, ConfigError> {
+        let name = match self.theme.as_deref() {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let table = match self.themes.get(name) {
+            Some(table) => table,
+            None => return Theme::builtin(name).map(Some).ok_or_else(|| ConfigError::Invalid(format!("unknown theme: {}", name))),
+        };
+        let base = table.get("base").map_or("plain", |base| base.as_str());
+        let mut theme = Theme::builtin(base).ok_or_else(|| ConfigError::Invalid(format!("unknown base theme in [themes.{}]: {}", name, base)))?;
+        theme.name = name.to_string();
+        for (key, style) in table.iter().filter(|(key, _)| key.as_str() != "base") {
+            theme.set(key, style).map_err(|e| ConfigError::Invalid(format!("[themes.{}] {}: {}", name, key, e)))?;
+        }
+        Ok(Some(theme))
+    }
+
+    /**
+     * The expiry table as (priority, ttl) pairs, "default" comes back as None for the priority
+     */
+    pub fn expiry_times(&self) -> Result<Vec<ExpiryTime>, ConfigError> {
+        let mut times = Vec::new();
+        for (name, ttl) in self.expiry.iter() {
+            let priority = if name.eq_ignore_ascii_case("default") {
+                None
+            } else {
+                match PriorityStatus::parse(name) {
+                    Some(priority) => Some(priority),
+                    None => return Err(ConfigError::Invalid(format!("unknown priority in [expiry]: {}", name))),
+                }
+            };
+            times.push((priority, ttl.to_duration()?));
+        }
+        Ok(times)
+    }
+}
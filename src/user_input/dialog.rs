@@ -0,0 +1,239 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::user_input::editor::LineEditor;
+
+//External
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tokio::sync::oneshot;
+
+/**
+ * An active confirm() dialog, see Console::confirm
+ */
+#[derive(Debug)]
+pub struct ConfirmDialog {
+    pub prompt: String,
+    pub default: bool,
+    pub reply: oneshot::Sender<bool>,
+}
+
+impl ConfirmDialog {
+    /**
+     * Send the answer, the asker may have stopped waiting for it
+     */
+    pub fn answer(self, yes: bool) {
+        let _ = self.reply.send(yes);
+    }
+}
+
+/**
+ * The answer taken when the user just presses Enter, yes when the prompt offers [Y/n] and no otherwise
+ */
+pub fn default_answer(prompt: &str) -> bool {
+    prompt.contains("[Y/n]") || prompt.contains("(Y/n)")
+}
+
+/**
+ * The answer a key gives, None for keys that don't answer
+ *  ~ y and n answer, Enter takes the default.
+ *  ~ Esc and Ctrl+C cancel, which is no whatever the default.
+ */
+pub fn answer_for_key(key: &KeyEvent, default: bool) -> Option<bool> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') if !ctrl => Some(true),
+        KeyCode::Char('n') | KeyCode::Char('N') if !ctrl => Some(false),
+        KeyCode::Char('c') if ctrl => Some(false),
+        KeyCode::Esc => Some(false),
+        KeyCode::Enter => Some(default),
+        _ => None,
+    }
+}
+
+/**
+ * The answer a typed line gives when stdin is not a terminal, None for lines that don't answer
+ */
+pub fn answer_for_line(line: &str, default: bool) -> Option<bool> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        "" => Some(default),
+        _ => None,
+    }
+}
+
+/**
+ * What the user does with the rest of a long result the pager holds, see broker::pager::Pager
+ *  ~ Page shows the next screenful, Line the next line.
+ *  ~ Quit drops the rest.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageStep {
+    Page,
+    Line,
+    Quit,
+}
+
+/**
+ * The pager's prompt while it has the input zone, see broker::pager::Pager
+ */
+#[derive(Debug)]
+pub struct PageDialog {
+    pub prompt: String,
+    pub reply: oneshot::Sender<PageStep>,
+}
+
+impl PageDialog {
+    pub fn answer(self, step: PageStep) {
+        let _ = self.reply.send(step);
+    }
+}
+
+/**
+ * The step a key takes the pager, None for keys that don't page, like more and less:
+ * space or PageDown a page, Enter or Down a line, q, Esc and Ctrl+C stop
+ */
+pub fn page_step_for_key(key: &KeyEvent) -> Option<PageStep> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char(' ') | KeyCode::Char('f') if !ctrl => Some(PageStep::Page),
+        KeyCode::PageDown => Some(PageStep::Page),
+        KeyCode::Enter | KeyCode::Down => Some(PageStep::Line),
+        KeyCode::Char('j') if !ctrl => Some(PageStep::Line),
+        KeyCode::Char('q') | KeyCode::Char('Q') if !ctrl => Some(PageStep::Quit),
+        KeyCode::Char('c') if ctrl => Some(PageStep::Quit),
+        KeyCode::Esc => Some(PageStep::Quit),
+        _ => None,
+    }
+}
+
+/**
+ * The step a typed line takes the pager when stdin is not a terminal, Enter is a page there as a line can't be shorter
+ */
+pub fn page_step_for_line(line: &str) -> Option<PageStep> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "" | "f" => Some(PageStep::Page),
+        "j" => Some(PageStep::Line),
+        "q" | "quit" => Some(PageStep::Quit),
+        _ => None,
+    }
+}
+
+/**
+ * Most rows the list of a select() picker takes above the prompt, fewer when the screen is short
+ */
+pub const MAX_SELECT_ROWS: usize = 10;
+
+/**
+ * What a key did to a select() picker
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectStep {
+    Open,
+    Chosen(usize),
+    Cancel,
+}
+
+/**
+ * An active select() picker, see Console::select.
+ *  ~ Typing filters the items (case insensitive, anywhere in the item), Up/Down, Ctrl+P/N, Tab and PageUp/PageDown move the selection.
+ *  ~ Enter chooses the selected item, Esc, Ctrl+C and Ctrl+G cancel.
+ *  ~ reply gets the index into items, not into the filtered list.
+ */
+#[derive(Debug)]
+pub struct SelectDialog {
+    pub title: String,
+    pub items: Vec<String>,
+    pub filter: LineEditor,
+    //index into matches()
+    pub selected: usize,
+    pub reply: oneshot::Sender<usize>,
+}
+
+impl SelectDialog {
+    pub fn new(title: String, items: Vec<String>, reply: oneshot::Sender<usize>) -> Self {
+        SelectDialog { title, items, filter: LineEditor::new(), selected: 0, reply }
+    }
+
+    /**
+     * Indexes of the items that match the filter, in their order
+     */
+    pub fn matches(&self) -> Vec<usize> {
+        let filter = self.filter.buffer().to_lowercase();
+        self.items.iter().enumerate().filter(|(_, item)| item.to_lowercase().contains(&filter)).map(|(index, _)| index).collect()
+    }
+
+    /**
+     * The prompt under the list, with how many items match
+     */
+    pub fn prompt(&self) -> String {
+        format!("{} ({}/{}): ", self.title, self.matches().len(), self.items.len())
+    }
+
+    fn move_by(&mut self, step: isize) {
+        let count = self.matches().len();
+        if count > 0 {
+            self.selected = (self.selected as isize + step).clamp(0, count as isize - 1) as usize;
+        }
+    }
+
+    fn filtered(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn key(&mut self, key: &KeyEvent) -> SelectStep {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let page = MAX_SELECT_ROWS as isize;
+        match key.code {
+            KeyCode::Esc => return SelectStep::Cancel,
+            KeyCode::Char('c') | KeyCode::Char('g') if ctrl => return SelectStep::Cancel,
+            KeyCode::Enter => return match self.matches().get(self.selected) {
+                Some(index) => SelectStep::Chosen(*index),
+                None => SelectStep::Open,
+            },
+            KeyCode::Up | KeyCode::BackTab => self.move_by(-1),
+            KeyCode::Char('p') if ctrl => self.move_by(-1),
+            KeyCode::Down | KeyCode::Tab => self.move_by(1),
+            KeyCode::Char('n') if ctrl => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-page),
+            KeyCode::PageDown => self.move_by(page),
+            KeyCode::Char('u') if ctrl => {
+                self.filter.kill_to_start();
+                self.filtered();
+            },
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.filter.insert(c);
+                self.filtered();
+            },
+            KeyCode::Backspace => {
+                self.filter.backspace();
+                self.filtered();
+            },
+            KeyCode::Left => self.filter.left(),
+            KeyCode::Right => self.filter.right(),
+            _ => {},
+        }
+        SelectStep::Open
+    }
+
+    /**
+     * Add pasted text to the filter, its first line only
+     */
+    pub fn paste(&mut self, text: &str) {
+        self.filter.insert_str(text.lines().next().unwrap_or(""));
+        self.filtered();
+    }
+}
+
+/**
+ * The item a typed line picks when stdin is not a terminal: its number as listed (from 1) or its text, None for neither
+ */
+pub fn select_for_line(line: &str, items: &[String]) -> Option<usize> {
+    let line = line.trim();
+    match line.parse::<usize>() {
+        Ok(number) if (1..=items.len()).contains(&number) => Some(number - 1),
+        _ => items.iter().position(|item| item.eq_ignore_ascii_case(line)),
+    }
+}
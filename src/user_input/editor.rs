@@ -0,0 +1,774 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::sync::Arc;
+use crate::broker::memory::{self, MemoryLimit, MemoryMeter};
+use crate::output::text;
+
+//External
+use unicode_segmentation::UnicodeSegmentation;
+
+/**
+ * How many submitted lines the editor remembers for Up/Down, unless set_history_limit says otherwise
+ */
+pub const MAX_HISTORY: usize = 500;
+
+/**
+ * How many edits of one line can be undone
+ */
+pub const MAX_UNDO: usize = 200;
+
+/**
+ * How many killed texts the kill ring keeps for Ctrl+Y and Alt+Y
+ */
+pub const MAX_KILLS: usize = 32;
+
+/**
+ * Kinds of edit for undo, a run of typed characters (or of single deletions, or of kills) at the cursor is undone as one
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditKind {
+    Insert,
+    Backspace,
+    Delete,
+    Kill,
+    Other,
+}
+
+/**
+ * Text killed from the line for yanking back, newest last, like readline's kill ring.
+ *  ~ Kills right after each other go into one entry, so Ctrl+W Ctrl+W Ctrl+Y brings both words back.
+ *  ~ It belongs to the editor of the input line and lives as long as the session, it is not cleared when a line is submitted.
+ *    read_secret() prompts have an editor of their own, so a secret never ends up in it.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct KillRing {
+    entries: std::collections::VecDeque<String>,
+}
+
+impl KillRing {
+    /**
+     * Add killed text, joined to the newest entry when continuing a kill. backward kills go in front of it
+     */
+    pub fn push(&mut self, text: &str, continuing: bool, backward: bool) {
+        match (continuing, self.entries.back_mut()) {
+            (true, Some(last)) if backward => last.insert_str(0, text),
+            (true, Some(last)) => last.push_str(text),
+            _ => {
+                self.entries.push_back(text.to_string());
+                if self.entries.len() > MAX_KILLS {
+                    self.entries.pop_front();
+                }
+            },
+        }
+    }
+
+    /**
+     * The entry back steps from the newest, wrapping around
+     */
+    pub fn get(&self, back: usize) -> Option<&str> {
+        match self.entries.len() {
+            0 => None,
+            len => self.entries.get(len - 1 - back % len).map(|text| text.as_str()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/**
+ * The last yank, for Alt+Y to swap for an older kill: where it went and how far back in the ring it came from
+ */
+#[derive(Debug, Clone, Copy)]
+struct Yank {
+    start: usize,
+    end: usize,
+    back: usize,
+}
+
+/**
+ * The editable line in the input zone.
+ *  ~ cursor is a byte offset into buffer and always sits on a grapheme boundary, so a wide character or an emoji moves as one.
+ *  ~ history holds submitted lines oldest first, browsing it keeps what was being typed in stash.
+ *  ~ buffer may hold newlines while a multi-line input is composed, it is still submitted as one line of input.
+ *  ~ With autosuggest on the newest history entry that starts with the line is offered as its rest, see suggestion.
+ *  ~ undo holds the line as it was before each edit, like readline every line has its own: submitting or moving through
+ *    the history starts a fresh one.
+ *  ~ kills keeps what the kill commands cut out of the line, see KillRing.
+ */
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    buffer: String,
+    cursor: usize,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    history_bytes: usize,
+    history_limit: Option<MemoryLimit>,
+    meter: Option<Arc<MemoryMeter>>,
+    stash: String,
+    autosuggest: bool,
+    undo: Vec<(String, usize)>,
+    redo: Vec<(String, usize)>,
+    //the kind of the last edit and where it left the cursor, the next one of the same kind from there joins it
+    last_edit: Option<(EditKind, usize)>,
+    kills: KillRing,
+    yank: Option<Yank>,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        LineEditor::default()
+    }
+
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /**
+     * Byte offset of the grapheme boundary before the cursor
+     */
+    fn previous_boundary(&self) -> usize {
+        self.buffer[..self.cursor].grapheme_indices(true).next_back().map(|(i, _)| i).unwrap_or(0)
+    }
+
+    /**
+     * Byte offset of the grapheme boundary after the cursor
+     */
+    fn next_boundary(&self) -> usize {
+        match self.buffer[self.cursor..].graphemes(true).next() {
+            Some(grapheme) => self.cursor + grapheme.len(),
+            None => self.buffer.len(),
+        }
+    }
+
+    /**
+     * Remember the line before an edit, unless the edit continues the last one
+     */
+    fn checkpoint(&mut self, kind: EditKind) {
+        let joins = kind != EditKind::Other && self.last_edit == Some((kind, self.cursor));
+        if !joins {
+            self.undo.push((self.buffer.clone(), self.cursor));
+            if self.undo.len() > MAX_UNDO {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.last_edit = None;
+        self.yank = None;
+    }
+
+    fn edited(&mut self, kind: EditKind) {
+        self.last_edit = Some((kind, self.cursor));
+    }
+
+    /**
+     * Forget the edits of the line, for when it is replaced as a whole
+     */
+    fn reset_undo(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_edit = None;
+    }
+
+    /**
+     * Put the line back as it was before the last edit, false when there is nothing to undo
+     */
+    pub fn undo(&mut self) -> bool {
+        //edits that changed nothing, eg. a backspace at the start, are skipped
+        while let Some((buffer, cursor)) = self.undo.pop() {
+            if buffer != self.buffer {
+                let current = (std::mem::replace(&mut self.buffer, buffer), self.cursor);
+                self.redo.push(current);
+                self.cursor = cursor;
+                self.last_edit = None;
+                return true;
+            }
+        }
+        false
+    }
+
+    /**
+     * Make the last undone edit again, false when there is nothing to redo
+     */
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some((buffer, cursor)) => {
+                let current = (std::mem::replace(&mut self.buffer, buffer), self.cursor);
+                self.undo.push(current);
+                self.cursor = cursor;
+                self.last_edit = None;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.checkpoint(EditKind::Insert);
+        self.buffer.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        self.edited(EditKind::Insert);
+    }
+
+    /**
+     * Insert text at the cursor, undone as a whole like a paste
+     */
+    pub fn insert_str(&mut self, s: &str) {
+        self.checkpoint(EditKind::Other);
+        self.buffer.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    pub fn backspace(&mut self) {
+        self.checkpoint(EditKind::Backspace);
+        let start = self.previous_boundary();
+        self.buffer.replace_range(start..self.cursor, "");
+        self.cursor = start;
+        self.edited(EditKind::Backspace);
+    }
+
+    pub fn delete(&mut self) {
+        self.checkpoint(EditKind::Delete);
+        let end = self.next_boundary();
+        self.buffer.replace_range(self.cursor..end, "");
+        self.edited(EditKind::Delete);
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.previous_boundary();
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.buffer.len();
+    }
+
+    /**
+     * Cut a range next to the cursor into the kill ring, backward for a range that ends at the cursor
+     */
+    fn kill(&mut self, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        let continuing = self.last_edit == Some((EditKind::Kill, self.cursor));
+        let backward = end == self.cursor;
+        self.checkpoint(EditKind::Kill);
+        let killed: String = self.buffer.drain(start..end).collect();
+        self.kills.push(&killed, continuing, backward);
+        self.cursor = start;
+        self.edited(EditKind::Kill);
+    }
+
+    /**
+     * Kill from the cursor back to the start of the line
+     */
+    pub fn kill_to_start(&mut self) {
+        self.kill(0, self.cursor);
+    }
+
+    /**
+     * Kill from the cursor to the end of the line
+     */
+    pub fn kill_to_end(&mut self) {
+        self.kill(self.cursor, self.buffer.len());
+    }
+
+    /**
+     * Kill back to the previous whitespace, like readline's unix-word-rubout (Ctrl+W)
+     */
+    pub fn kill_word_backward(&mut self) {
+        let before = self.buffer[..self.cursor].trim_end_matches(char::is_whitespace);
+        let start = before.char_indices().rev().find(|(_, c)| c.is_whitespace()).map_or(0, |(at, c)| at + c.len_utf8());
+        self.kill(start, self.cursor);
+    }
+
+    /**
+     * Kill to the end of the next word, like readline's kill-word (Alt+D)
+     */
+    pub fn kill_word_forward(&mut self) {
+        let end = self.forward_word();
+        self.kill(self.cursor, end);
+    }
+
+    /**
+     * Insert the newest kill at the cursor
+     */
+    pub fn yank(&mut self) {
+        let text = match self.kills.get(0) {
+            Some(text) => text.to_string(),
+            None => return,
+        };
+        let start = self.cursor;
+        self.insert_str(&text);
+        self.yank = Some(Yank { start, end: self.cursor, back: 0 });
+    }
+
+    /**
+     * Right after a yank, swap the yanked text for the kill before it, round the ring (Alt+Y)
+     */
+    pub fn yank_pop(&mut self) {
+        let yank = match self.yank {
+            Some(yank) if yank.end == self.cursor => yank,
+            _ => return,
+        };
+        let text = match self.kills.get(yank.back + 1) {
+            Some(text) => text.to_string(),
+            None => return,
+        };
+        self.replace(yank.start, yank.end, &text);
+        self.cursor = yank.start + text.len();
+        self.yank = Some(Yank { start: yank.start, end: self.cursor, back: yank.back + 1 });
+    }
+
+    pub fn kill_ring(&self) -> &KillRing {
+        &self.kills
+    }
+
+    /**
+     * End of the current or next word, words being letters, digits and _ like emacs' forward-word (Alt+F)
+     */
+    pub fn forward_word(&self) -> usize {
+        let graphemes: Vec<(usize, &str)> = self.buffer[self.cursor..].grapheme_indices(true).collect();
+        let mut index = 0;
+        while index < graphemes.len() && word_class(graphemes[index].1) != WordClass::Word {
+            index += 1;
+        }
+        while index < graphemes.len() && word_class(graphemes[index].1) == WordClass::Word {
+            index += 1;
+        }
+        graphemes.get(index).map(|(i, _)| self.cursor + i).unwrap_or(self.buffer.len())
+    }
+
+    /**
+     * Start of the current or previous word, like emacs' backward-word (Alt+B)
+     */
+    pub fn backward_word(&self) -> usize {
+        let graphemes: Vec<(usize, &str)> = self.buffer[..self.cursor].grapheme_indices(true).collect();
+        let mut index = graphemes.len();
+        while index > 0 && word_class(graphemes[index - 1].1) != WordClass::Word {
+            index -= 1;
+        }
+        while index > 0 && word_class(graphemes[index - 1].1) == WordClass::Word {
+            index -= 1;
+        }
+        graphemes.get(index).map(|(i, _)| *i).unwrap_or(0)
+    }
+
+    pub fn word_left(&mut self) {
+        self.cursor = self.backward_word();
+    }
+
+    pub fn word_right(&mut self) {
+        self.cursor = self.forward_word();
+    }
+
+    pub fn clear(&mut self) {
+        self.checkpoint(EditKind::Other);
+        self.buffer.clear();
+        self.cursor = 0;
+        self.history_index = None;
+    }
+
+    /**
+     * Take the line for submission and remember it in the history, blank lines and repeats are not remembered
+     */
+    pub fn submit(&mut self) -> String {
+        let line = std::mem::take(&mut self.buffer);
+        self.cursor = 0;
+        self.history_index = None;
+        self.reset_undo();
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            let size = memory::text_size(&line);
+            self.history.push(line.clone());
+            self.history_bytes += size;
+            if let Some(meter) = self.meter.as_ref() {
+                meter.history.added(size);
+            }
+            self.trim_history();
+        }
+        line
+    }
+
+    /**
+     * Limit the history and report its usage to meter. Lines have no priority, so they always go oldest first
+     */
+    pub fn set_history_limit(&mut self, limit: MemoryLimit, meter: Arc<MemoryMeter>) {
+        for line in self.history.iter() {
+            meter.history.added(memory::text_size(line));
+        }
+        self.history_limit = Some(limit);
+        self.meter = Some(meter);
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        let limit = self.history_limit.clone().unwrap_or_else(|| MemoryLimit::entries(MAX_HISTORY));
+        while !self.history.is_empty() && limit.exceeded(self.history.len(), self.history_bytes) {
+            let line = self.history.remove(0);
+            let size = memory::text_size(&line);
+            self.history_bytes -= size;
+            if let Some(meter) = self.meter.as_ref() {
+                meter.history.evicted(size);
+            }
+            //browsing stays on the same line, or the oldest one left
+            self.history_index = self.history_index.map(|index| index.saturating_sub(1));
+        }
+    }
+
+    /**
+     * Take the line without remembering it, for answers that must not end up in the history
+     */
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        self.history_index = None;
+        self.reset_undo();
+        std::mem::take(&mut self.buffer)
+    }
+
+    /**
+     * A copy to draw instead of this line, every grapheme replaced by mask or the line hidden entirely when None
+     */
+    pub fn masked(&self, mask: Option<char>) -> LineEditor {
+        let (buffer, cursor) = match mask {
+            Some(mask) => {
+                let count = |text: &str| text.graphemes(true).count();
+                let masked: String = std::iter::repeat_n(mask, count(&self.buffer)).collect();
+                let cursor = std::iter::repeat_n(mask, count(&self.buffer[..self.cursor])).map(|c| c.len_utf8()).sum();
+                (masked, cursor)
+            },
+            None => (String::new(), 0),
+        };
+        LineEditor { buffer, cursor, ..LineEditor::default() }
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn history_entry(&self, index: usize) -> Option<&str> {
+        self.history.get(index).map(|line| line.as_str())
+    }
+
+    /**
+     * The newest history entry before index before that contains query
+     */
+    pub fn find_history(&self, query: &str, before: usize) -> Option<usize> {
+        self.history[..before.min(self.history.len())].iter().rposition(|line| line.contains(query))
+    }
+
+    /**
+     * The oldest history entry from index after on that contains query
+     */
+    pub fn find_history_newer(&self, query: &str, after: usize) -> Option<usize> {
+        self.history.iter().enumerate().skip(after).find(|(_, line)| line.contains(query)).map(|(index, _)| index)
+    }
+
+    /**
+     * Replace the whole line, eg. with a history entry found by a search, as one edit that can be undone
+     */
+    pub fn set_line(&mut self, line: &str) {
+        self.checkpoint(EditKind::Other);
+        self.buffer = line.to_string();
+        self.cursor = self.buffer.len();
+        self.history_index = None;
+    }
+
+    pub fn set_autosuggest(&mut self, enabled: bool) {
+        self.autosuggest = enabled;
+    }
+
+    /**
+     * The rest of the newest history entry that starts with the line, like fish's autosuggestions.
+     * Only offered with the cursor at the end of a line that isn't empty and isn't a history entry being browsed
+     */
+    pub fn suggestion(&self) -> Option<&str> {
+        if !self.autosuggest || self.buffer.is_empty() || self.cursor != self.buffer.len() || self.history_index.is_some() {
+            return None;
+        }
+        self.history.iter().rev()
+            .find(|line| line.len() > self.buffer.len() && line.starts_with(self.buffer.as_str()))
+            .map(|line| &line[self.buffer.len()..])
+    }
+
+    /**
+     * Complete the line with the suggestion, false when there is none
+     */
+    pub fn accept_suggestion(&mut self) -> bool {
+        match self.suggestion().map(|rest| rest.to_string()) {
+            Some(rest) => {
+                self.insert_str(&rest);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /**
+     * Show the previous history entry, the line being typed is kept and comes back past the newest entry
+     */
+    pub fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.stash = self.buffer.clone();
+                self.history.len() - 1
+            },
+        };
+        self.history_index = Some(index);
+        self.buffer = self.history[index].clone();
+        self.cursor = self.buffer.len();
+        self.reset_undo();
+    }
+
+    pub fn history_next(&mut self) {
+        match self.history_index {
+            None => return,
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.buffer = self.history[index + 1].clone();
+            },
+            Some(_) => {
+                self.history_index = None;
+                self.buffer = std::mem::take(&mut self.stash);
+            },
+        }
+        self.cursor = self.buffer.len();
+        self.reset_undo();
+    }
+
+    /**
+     * Start a new line inside the input instead of submitting it
+     */
+    pub fn newline(&mut self) {
+        self.insert('\n');
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.buffer.split('\n').count()
+    }
+
+    /**
+     * Byte offsets where each line of the buffer starts
+     */
+    fn line_starts(&self) -> Vec<usize> {
+        std::iter::once(0).chain(self.buffer.match_indices('\n').map(|(i, _)| i + 1)).collect()
+    }
+
+    /**
+     * Which line the cursor is on and its byte offset within that line
+     */
+    pub fn cursor_line(&self) -> (usize, usize) {
+        let starts = self.line_starts();
+        let line = starts.iter().rposition(|start| *start <= self.cursor).unwrap_or(0);
+        (line, self.cursor - starts[line])
+    }
+
+    /**
+     * Move the cursor to the same grapheme column of another line, false if there is no such line
+     */
+    fn move_to_line(&mut self, target: usize) -> bool {
+        let starts = self.line_starts();
+        if target >= starts.len() {
+            return false;
+        }
+        let (line, offset) = self.cursor_line();
+        let column = self.buffer[starts[line]..starts[line] + offset].graphemes(true).count();
+        let end = starts.get(target + 1).map(|next| next - 1).unwrap_or(self.buffer.len());
+        let target_text = &self.buffer[starts[target]..end];
+        let within: usize = target_text.graphemes(true).take(column).map(|g| g.len()).sum();
+        self.cursor = starts[target] + within;
+        true
+    }
+
+    /**
+     * Move up a line in a multi-line input, false when already on the first line
+     */
+    pub fn up(&mut self) -> bool {
+        match self.cursor_line() {
+            (0, _) => false,
+            (line, _) => self.move_to_line(line - 1),
+        }
+    }
+
+    /**
+     * Move down a line in a multi-line input, false when already on the last line
+     */
+    pub fn down(&mut self) -> bool {
+        let (line, _) = self.cursor_line();
+        self.move_to_line(line + 1)
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /**
+     * Put the cursor at a byte offset, moved back to the grapheme boundary at or before it
+     */
+    pub fn set_cursor(&mut self, at: usize) {
+        let at = at.min(self.buffer.len());
+        let boundaries = self.buffer.grapheme_indices(true).map(|(i, _)| i).chain(std::iter::once(self.buffer.len()));
+        self.cursor = boundaries.take_while(|i| *i <= at).last().unwrap_or(0);
+    }
+
+    /**
+     * Cut a byte range out of the buffer and return it, the cursor ends up at its start
+     */
+    pub fn remove(&mut self, start: usize, end: usize) -> String {
+        self.checkpoint(EditKind::Other);
+        let (start, end) = (start.min(end), end.max(start).min(self.buffer.len()));
+        let removed: String = self.buffer.drain(start..end).collect();
+        self.cursor = start;
+        removed
+    }
+
+    /**
+     * Replace a byte range with text as one edit, the cursor ends up at the start of it
+     */
+    pub fn replace(&mut self, start: usize, end: usize, text: &str) {
+        self.checkpoint(EditKind::Other);
+        let (start, end) = (start.min(end), end.max(start).min(self.buffer.len()));
+        self.buffer.replace_range(start..end, text);
+        self.cursor = start;
+    }
+
+    pub fn slice(&self, start: usize, end: usize) -> &str {
+        let (start, end) = (start.min(end), end.max(start).min(self.buffer.len()));
+        &self.buffer[start..end]
+    }
+
+    /**
+     * Start of the next word after the cursor, like vi's w
+     */
+    pub fn next_word_start(&self) -> usize {
+        let graphemes: Vec<(usize, &str)> = self.buffer[self.cursor..].grapheme_indices(true).collect();
+        let mut index = 0;
+        if let Some((_, first)) = graphemes.first() {
+            let class = word_class(first);
+            if class != WordClass::Space {
+                while index < graphemes.len() && word_class(graphemes[index].1) == class {
+                    index += 1;
+                }
+            }
+        }
+        while index < graphemes.len() && word_class(graphemes[index].1) == WordClass::Space {
+            index += 1;
+        }
+        graphemes.get(index).map(|(i, _)| self.cursor + i).unwrap_or(self.buffer.len())
+    }
+
+    /**
+     * Start of the word before the cursor, or of the word the cursor is in, like vi's b
+     */
+    pub fn previous_word_start(&self) -> usize {
+        let graphemes: Vec<(usize, &str)> = self.buffer[..self.cursor].grapheme_indices(true).collect();
+        let mut index = graphemes.len();
+        while index > 0 && word_class(graphemes[index - 1].1) == WordClass::Space {
+            index -= 1;
+        }
+        if index > 0 {
+            let class = word_class(graphemes[index - 1].1);
+            while index > 0 && word_class(graphemes[index - 1].1) == class {
+                index -= 1;
+            }
+        }
+        graphemes.get(index).map(|(i, _)| *i).unwrap_or(0)
+    }
+
+    /**
+     * Byte offset just past the end of the current or next word, like vi's e but exclusive
+     */
+    pub fn word_end(&self) -> usize {
+        let graphemes: Vec<(usize, &str)> = self.buffer[self.cursor..].grapheme_indices(true).collect();
+        //e always moves, so start looking one grapheme ahead when already at the end of a word
+        let mut index = 0;
+        let at_end = graphemes.len() > 1 && word_class(graphemes[0].1) != word_class(graphemes[1].1);
+        if at_end {
+            index = 1;
+        }
+        while index < graphemes.len() && word_class(graphemes[index].1) == WordClass::Space {
+            index += 1;
+        }
+        if let Some((_, first)) = graphemes.get(index) {
+            let class = word_class(first);
+            while index < graphemes.len() && word_class(graphemes[index].1) == class {
+                index += 1;
+            }
+        }
+        graphemes.get(index).map(|(i, _)| self.cursor + i).unwrap_or(self.buffer.len())
+    }
+
+    /**
+     * The part of the line that fits in width columns and the cursor column within it.
+     * The view scrolls horizontally so the cursor is always on screen, one column is kept free for it at the end.
+     */
+    pub fn view(&self, width: usize) -> (String, usize) {
+        view_line(&self.buffer, self.cursor, width)
+    }
+}
+
+/**
+ * What a grapheme counts as when moving by words, a word is a run of graphemes of one class
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WordClass {
+    Space,
+    Word,
+    Punctuation,
+}
+
+fn word_class(grapheme: &str) -> WordClass {
+    match grapheme.chars().next() {
+        Some(c) if c.is_whitespace() => WordClass::Space,
+        Some(c) if c.is_alphanumeric() || c == '_' => WordClass::Word,
+        _ => WordClass::Punctuation,
+    }
+}
+
+/**
+ * The part of one line that fits in width columns with the cursor (a byte offset) on screen, and the cursor column
+ */
+pub fn view_line(line: &str, cursor: usize, width: usize) -> (String, usize) {
+    let (range, cursor_column) = view_range(line, cursor, width);
+    (line[range].to_string(), cursor_column)
+}
+
+/**
+ * Like view_line, the bytes of the line that are shown instead of the text
+ */
+pub fn view_range(line: &str, cursor: usize, width: usize) -> (std::ops::Range<usize>, usize) {
+    if width == 0 {
+        return (0..0, 0);
+    }
+    let before = text::graphemes(&line[..cursor]);
+    let mut cursor_column: usize = before.iter().map(|(_, w)| w).sum();
+    //drop graphemes off the left until the cursor fits
+    let mut skip = 0;
+    while cursor_column >= width && skip < before.len() {
+        cursor_column -= before[skip].1;
+        skip += 1;
+    }
+    let skipped: usize = before.iter().take(skip).map(|(g, _)| g.len()).sum();
+    (skipped..skipped + text::take_width(&line[skipped..], width).len(), cursor_column)
+}
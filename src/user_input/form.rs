@@ -0,0 +1,383 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use crate::user_input::editor::LineEditor;
+use crate::user_input::validate::Validator;
+
+//External
+use tokio::sync::oneshot;
+
+/**
+ * What a form field takes and what its answer is turned into
+ *  ~ Text and Secret keep the line as typed, Secret is drawn as mask (or not at all when None) and kept out of the history.
+ *  ~ Integer and Number must parse, Flag takes y, yes, n or no.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldKind {
+    Text,
+    Secret(Option<char>),
+    Integer,
+    Number,
+    Flag,
+}
+
+/**
+ * An answer of a form, typed by the kind of its field
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Integer(i64),
+    Number(f64),
+    Flag(bool),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Text(text) => write!(f, "{}", text),
+            FieldValue::Integer(value) => write!(f, "{}", value),
+            FieldValue::Number(value) => write!(f, "{}", value),
+            FieldValue::Flag(value) => write!(f, "{}", if *value { "yes" } else { "no" }),
+        }
+    }
+}
+
+/**
+ * One labeled field of a form.
+ *  ~ A field with a default takes it when the answer is left empty, the default is shown in the prompt (unless it is a secret).
+ *  ~ An optional field left empty without a default has no answer, a required one asks again.
+ *  ~ validator runs on the answer as typed, after it parsed for its kind.
+ */
+#[derive(Clone)]
+pub struct Field {
+    pub name: String,
+    pub label: String,
+    pub kind: FieldKind,
+    pub default: Option<String>,
+    pub required: bool,
+    pub validator: Option<Validator>,
+}
+
+impl fmt::Debug for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field").field("name", &self.name).field("label", &self.label).field("kind", &self.kind)
+            .field("default", &self.default).field("required", &self.required).finish()
+    }
+}
+
+impl Field {
+    pub fn new(name: &str, label: &str, kind: FieldKind) -> Self {
+        Field { name: name.to_string(), label: label.to_string(), kind, default: None, required: true, validator: None }
+    }
+
+    pub fn text(name: &str, label: &str) -> Self {
+        Field::new(name, label, FieldKind::Text)
+    }
+
+    pub fn secret(name: &str, label: &str) -> Self {
+        Field::new(name, label, FieldKind::Secret(Some('*')))
+    }
+
+    pub fn integer(name: &str, label: &str) -> Self {
+        Field::new(name, label, FieldKind::Integer)
+    }
+
+    pub fn number(name: &str, label: &str) -> Self {
+        Field::new(name, label, FieldKind::Number)
+    }
+
+    pub fn flag(name: &str, label: &str) -> Self {
+        Field::new(name, label, FieldKind::Flag)
+    }
+
+    pub fn default(mut self, value: impl ToString) -> Self {
+        self.default = Some(value.to_string());
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    /**
+     * Draw the answer as mask instead of the default *, or draw nothing with None. The field becomes a Secret one
+     */
+    pub fn masked(mut self, mask: Option<char>) -> Self {
+        self.kind = FieldKind::Secret(mask);
+        self
+    }
+
+    pub fn validate(mut self, validator: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    pub fn is_secret(&self) -> bool {
+        matches!(self.kind, FieldKind::Secret(_))
+    }
+
+    /**
+     * The prompt of the field, with its default in brackets
+     */
+    pub fn prompt(&self) -> String {
+        match (&self.default, self.is_secret()) {
+            (Some(default), false) => format!("{} [{}]: ", self.label, default),
+            _ => format!("{}: ", self.label),
+        }
+    }
+
+    /**
+     * Turn an answer into its value, Ok(None) for an optional field left empty
+     */
+    pub fn parse(&self, input: &str) -> Result<Option<FieldValue>, String> {
+        let input = match (input.trim(), &self.kind) {
+            (_, FieldKind::Secret(_)) if !input.is_empty() => input,
+            ("", _) => match self.default.as_deref() {
+                Some(default) => default,
+                None if self.required => return Err("an answer is required".to_string()),
+                None => return Ok(None),
+            },
+            (trimmed, _) => trimmed,
+        };
+        let value = match self.kind {
+            FieldKind::Text | FieldKind::Secret(_) => FieldValue::Text(input.to_string()),
+            FieldKind::Integer => FieldValue::Integer(input.parse().map_err(|_| format!("not a whole number: {}", input))?),
+            FieldKind::Number => FieldValue::Number(input.parse().map_err(|_| format!("not a number: {}", input))?),
+            FieldKind::Flag => match input.to_ascii_lowercase().as_str() {
+                "y" | "yes" | "true" => FieldValue::Flag(true),
+                "n" | "no" | "false" => FieldValue::Flag(false),
+                _ => return Err("answer yes or no".to_string()),
+            },
+        };
+        if let Some(validator) = self.validator.as_ref() {
+            validator(input)?;
+        }
+        Ok(Some(value))
+    }
+}
+
+/**
+ * A sequence of fields asked one at a time in the input zone, see Console::form
+ *
+ *     let form = Form::new("Setup")
+ *         .field(Field::text("host", "Host").default("localhost"))
+ *         .field(Field::integer("port", "Port").default(8080).validate(|port| ...))
+ *         .field(Field::secret("token", "API token"));
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Form {
+    pub title: String,
+    pub fields: Vec<Field>,
+}
+
+impl Form {
+    pub fn new(title: &str) -> Self {
+        Form { title: title.to_string(), fields: Vec::new() }
+    }
+
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/**
+ * The answers of a form by field name, optional fields left empty are missing
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormAnswers {
+    values: HashMap<String, FieldValue>,
+}
+
+impl FormAnswers {
+    pub fn get(&self, name: &str) -> Option<&FieldValue> {
+        self.values.get(name)
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.values.get(name) {
+            Some(FieldValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> Option<i64> {
+        match self.values.get(name) {
+            Some(FieldValue::Integer(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /**
+     * A Number field, or an Integer one as a float
+     */
+    pub fn number(&self, name: &str) -> Option<f64> {
+        match self.values.get(name) {
+            Some(FieldValue::Number(value)) => Some(*value),
+            Some(FieldValue::Integer(value)) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self, name: &str) -> Option<bool> {
+        match self.values.get(name) {
+            Some(FieldValue::Flag(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn into_map(self) -> HashMap<String, FieldValue> {
+        self.values
+    }
+}
+
+/**
+ * What submitting a field did
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormStep {
+    Next,
+    Invalid(String),
+    Done,
+}
+
+/**
+ * A form being filled in, in the raw mode input zone or line by line from stdin.
+ *  ~ Enter submits the field, an invalid answer stays with the error under it. Up (or Shift+Tab) goes back a field.
+ *  ~ Going back and forth keeps what was typed, the answer of a field is only taken when it is submitted.
+ *  ~ The reply is dropped when the user cancels with Esc or Ctrl+C, the asker sees a closed oneshot.
+ */
+#[derive(Debug)]
+pub struct FormDialog {
+    pub form: Form,
+    pub index: usize,
+    //what is typed in each field, kept when moving between them
+    pub inputs: Vec<String>,
+    pub editor: LineEditor,
+    pub error: Option<String>,
+    answers: FormAnswers,
+    reply: oneshot::Sender<FormAnswers>,
+}
+
+impl FormDialog {
+    pub fn new(form: Form, reply: oneshot::Sender<FormAnswers>) -> Self {
+        let inputs = vec![String::new(); form.fields.len()];
+        FormDialog { form, index: 0, inputs, editor: LineEditor::new(), error: None, answers: FormAnswers::default(), reply }
+    }
+
+    pub fn field(&self) -> Option<&Field> {
+        self.form.fields.get(self.index)
+    }
+
+    /**
+     * The prompt of the current field, with the title and how far along the form is
+     */
+    pub fn prompt(&self) -> String {
+        match self.field() {
+            Some(field) => format!("{} [{}/{}] {}", self.form.title, self.index + 1, self.form.fields.len(), field.prompt()),
+            None => String::new(),
+        }
+    }
+
+    /**
+     * The mask of the current field when it is a secret, see LineEditor::masked
+     */
+    pub fn mask(&self) -> Option<Option<char>> {
+        match self.field().map(|field| &field.kind) {
+            Some(FieldKind::Secret(mask)) => Some(*mask),
+            _ => None,
+        }
+    }
+
+    /**
+     * Move to another field, what was typed in it before comes back
+     */
+    fn go_to(&mut self, index: usize) {
+        if let Some(input) = self.inputs.get_mut(self.index) {
+            *input = self.editor.take();
+        }
+        self.index = index;
+        self.editor.insert_str(self.inputs.get(index).map_or("", |input| input.as_str()));
+        self.error = None;
+    }
+
+    /**
+     * Submit an answer to the current field, a valid one moves on to the next
+     */
+    pub fn submit(&mut self, input: &str) -> FormStep {
+        let field = match self.field() {
+            Some(field) => field,
+            None => return FormStep::Done,
+        };
+        let name = field.name.clone();
+        match field.parse(input) {
+            Ok(value) => {
+                match value {
+                    Some(value) => self.answers.values.insert(name, value),
+                    None => self.answers.values.remove(&name),
+                };
+                self.error = None;
+                if self.index + 1 >= self.form.fields.len() {
+                    return FormStep::Done;
+                }
+                self.go_to(self.index + 1);
+                FormStep::Next
+            },
+            Err(error) => {
+                self.error = Some(error.clone());
+                FormStep::Invalid(error)
+            },
+        }
+    }
+
+    /**
+     * Submit what is in the editor
+     */
+    pub fn submit_line(&mut self) -> FormStep {
+        let input = self.editor.buffer().to_string();
+        self.submit(&input)
+    }
+
+    /**
+     * Back to the field before, false on the first one
+     */
+    pub fn previous(&mut self) -> bool {
+        match self.index {
+            0 => false,
+            index => {
+                self.go_to(index - 1);
+                true
+            },
+        }
+    }
+
+    /**
+     * Send the answers, once submit returned Done
+     */
+    pub fn finish(self) {
+        let _ = self.reply.send(self.answers);
+    }
+}
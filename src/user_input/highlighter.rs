@@ -0,0 +1,114 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::ops::Range;
+use crate::output::highlight::HighlightStyle;
+use crate::user_input::commands::Command;
+
+/**
+ * A styled part of the input, range is in bytes of the whole input (every line of it)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub style: HighlightStyle,
+}
+
+/**
+ * Styles the input line as it is typed, like rustyline's Highlighter, see Console::set_highlighter.
+ *  ~ highlight is called on every redraw of the input line with the whole input and the cursor (a byte offset into it).
+ *  ~ It returns spans instead of styled text, the input zone has to slice the line to fit the screen and keep the
+ *    cursor where it is, which escape sequences in the text would get in the way of.
+ *  ~ Spans may come in any order, where two overlap the first one wins. A range that is not on character boundaries is skipped.
+ *  ~ Only the input line is highlighted, not searches or read_secret() prompts.
+ */
+pub trait Highlighter: Send + Sync {
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Span>;
+}
+
+impl fmt::Debug for dyn Highlighter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Highlighter")
+    }
+}
+
+/**
+ * The part of line from start to end, with the spans of the whole input that fall in it drawn in their style.
+ * offset is where line starts in the input the spans were made for
+ */
+pub fn styled(line: &str, offset: usize, start: usize, end: usize, spans: &[Span]) -> String {
+    let mut inside: Vec<(usize, usize, &HighlightStyle)> = Vec::new();
+    for span in spans {
+        let from = span.range.start.saturating_sub(offset).max(start);
+        let to = span.range.end.saturating_sub(offset).min(end);
+        let valid = from < to && line.is_char_boundary(from) && line.is_char_boundary(to);
+        if valid && inside.iter().all(|(taken_from, taken_to, _)| to <= *taken_from || from >= *taken_to) {
+            inside.push((from, to, &span.style));
+        }
+    }
+    inside.sort_by_key(|(from, _, _)| *from);
+    let mut out = String::with_capacity(end - start);
+    let mut last = start;
+    for (from, to, style) in inside {
+        out.push_str(&line[last..from]);
+        out.push_str(&style.open());
+        out.push_str(&line[from..to]);
+        out.push_str("\u{1b}[0m");
+        last = to;
+    }
+    out.push_str(&line[last..end]);
+    out
+}
+
+/**
+ * Highlights /commands: a known command name in one style, its arguments in another, an unknown name underlined.
+ * Lines that are not commands are left alone. Plugin and script commands are only known to the broker,
+ * name the ones in use in extra so they are not marked.
+ */
+#[derive(Debug, Clone)]
+pub struct CommandHighlighter {
+    pub command: HighlightStyle,
+    pub argument: HighlightStyle,
+    pub invalid: HighlightStyle,
+    pub extra: Vec<String>,
+}
+
+impl Default for CommandHighlighter {
+    fn default() -> Self {
+        CommandHighlighter {
+            command: HighlightStyle::parse("cyan,bold").unwrap(),
+            argument: HighlightStyle::parse("yellow").unwrap(),
+            invalid: HighlightStyle::parse("red,underline").unwrap(),
+            extra: Vec::new(),
+        }
+    }
+}
+
+impl Highlighter for CommandHighlighter {
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Span> {
+        let start = line.len() - line.trim_start().len();
+        if !line[start..].starts_with('/') {
+            return Vec::new();
+        }
+        let name_end = line[start..].find(char::is_whitespace).map_or(line.len(), |at| start + at);
+        let name = line[start + 1..name_end].to_ascii_lowercase();
+        let known = !matches!(Command::parse(&line[start..name_end]), Some(Command::Unknown(_))) || self.extra.contains(&name);
+        //a name still being typed is not wrong yet
+        let typing = cursor == name_end && name_end == line.len();
+        let style = match (known, typing) {
+            (true, _) => self.command.clone(),
+            (false, true) => return Vec::new(),
+            (false, false) => self.invalid.clone(),
+        };
+        let mut spans = vec![Span { range: start..name_end, style }];
+        let arguments = line[name_end..].trim();
+        if known && !arguments.is_empty() {
+            let from = name_end + line[name_end..].find(|c: char| !c.is_whitespace()).unwrap_or(0);
+            spans.push(Span { range: from..line.trim_end().len(), style: self.argument.clone() });
+        }
+        spans
+    }
+}
@@ -0,0 +1,1227 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use crate::broker::mailbox::MailboxSender;
+use crate::broker::memory::{MemoryLimit, MemoryMeter};
+use crate::broker::message::ConsoleEvent;
+use crate::broker::threads::ThreadCommand;
+use crate::output::tabs::TabCommand;
+use crate::output::renderer::Renderer;
+use crate::output::theme::Theme;
+use crate::output::terminal as capabilities;
+use crate::output::writer::TerminalWriter;
+use crate::output::search::SearchCommand;
+use crate::user_input::editor::LineEditor;
+use crate::user_input::prompt::PromptSource;
+use crate::user_input::keymap::{Action, Keymap};
+use crate::user_input::vi::{EditMode, ViState};
+use crate::user_input::reverse_search::{ReverseSearch, SearchStep};
+use crate::user_input::dialog::{self, ConfirmDialog, PageDialog, PageStep, SelectDialog, SelectStep};
+use crate::user_input::form::{FormDialog, FormStep};
+use crate::user_input::validate::Validators;
+use crate::user_input::highlighter::Highlighter;
+use crate::output::zone::{Arrangement, FocusCommand, ScrollCommand};
+use crate::output::layout::Layout;
+use crate::output::selection::SelectCommand;
+
+//External
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::execute;
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+
+const SEARCH_PROMPT: &str = "search: ";
+/**
+ * Keys that follow each other closer than this are a paste, nobody types (or key-repeats) this fast
+ */
+const PASTE_GAP: std::time::Duration = std::time::Duration::from_millis(5);
+
+/**
+ * Where keystrokes go
+ *  ~ Line edits the line that is submitted as a message or /command.
+ *  ~ Search edits the scrollback search term, every change re-runs the search so it is incremental.
+ *  ~ Secret edits the answer to a read_secret() prompt, masked and kept out of the history.
+ *  ~ HistorySearch edits the query of a Ctrl+R search through the input history, see ReverseSearch.
+ *  ~ Confirm waits for the yes or no of a confirm() dialog, other keys are held until it is answered.
+ *  ~ Select moves through and filters the list of a select() picker.
+ *  ~ Form edits the field of a form that is being asked, see FormDialog.
+ *  ~ Page waits for the step the pager of a long result takes, see PageDialog, other keys are held like Confirm's.
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Line,
+    Search,
+    Secret,
+    HistorySearch,
+    Confirm,
+    Select,
+    Form,
+    Page,
+}
+
+/**
+ * What the event reader thread hands the input task
+ *  ~ Terminal is a crossterm event as read.
+ *  ~ Paste is text that arrived in one burst, it is inserted into the line as a whole instead of replayed key by key,
+ *    so a pasted newline doesn't submit (or run a /command) halfway through.
+ */
+#[derive(Debug)]
+enum InputEvent {
+    Terminal(Event),
+    Paste(String),
+}
+
+/**
+ * Something a module or the Console wants from the user, answered through the input zone
+ *  ~ Secret asks for a value without showing it, mask is drawn once per character or nothing is drawn when None.
+ *    The reply is dropped, which the asker sees as a closed oneshot, when the user cancels with Esc or Ctrl+C.
+ *  ~ Confirm asks a yes or no question, Enter takes default. Cancelling answers no.
+ *  ~ Select asks to pick one of items, the reply is its index. It is dropped when the user cancels, like Secret's.
+ *  ~ Form asks the fields of a form one after the other, see Console::form.
+ *  ~ Page asks what the pager of a long command result does next, see broker::pager::Pager. Cancelling stops it.
+ *  ~ EditMode switches the input line between emacs and vi editing, it applies right away.
+ *  ~ Detach stops the input zone and gives the terminal back, the console goes on without it (/detach).
+ */
+#[derive(Debug)]
+pub enum InputRequest {
+    Secret {
+        prompt: String,
+        mask: Option<char>,
+        reply: oneshot::Sender<String>,
+    },
+    Confirm {
+        prompt: String,
+        default: bool,
+        reply: oneshot::Sender<bool>,
+    },
+    Select {
+        title: String,
+        items: Vec<String>,
+        reply: oneshot::Sender<usize>,
+    },
+    Form(Box<FormDialog>),
+    Page {
+        prompt: String,
+        reply: oneshot::Sender<PageStep>,
+    },
+    EditMode(EditMode),
+    Detach,
+}
+
+/**
+ * What happens when stdin ends (EOF), eg. the pipe feeding the console closed or Ctrl+D on a line mode terminal
+ *  ~ Shutdown stops the console like exit does, the default.
+ *  ~ Display keeps the output zones running without input, for a console that only shows what its modules send.
+ *  ~ Reattach waits for stdin to have more to read, eg. the next writer of a named pipe, and takes input again from there.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EofBehavior {
+    Shutdown,
+    Display,
+    Reattach,
+}
+
+impl EofBehavior {
+    pub fn parse(name: &str) -> Option<EofBehavior> {
+        match name.to_ascii_lowercase().as_str() {
+            "shutdown" | "exit" => Some(EofBehavior::Shutdown),
+            "display" => Some(EofBehavior::Display),
+            "reattach" => Some(EofBehavior::Reattach),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * How often stdin is read again while it is at its end with EofBehavior::Reattach
+ */
+const REATTACH_POLL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/**
+ * What the input zone needs to know about the console, taken when it is started (see Console::input_options)
+ *  ~ zone_count, arrangement and status_bar tell which output zone is under the mouse pointer.
+ *  ~ mouse turns mouse capture on, without it the terminal keeps its own selection and wheel scrolling.
+ *  ~ autosuggest draws the history entry the line is the start of after the cursor, see LineEditor::suggestion.
+ *  ~ theme styles the prompt, the suggestion, errors and pickers, already fitted to the terminal.
+ *  ~ eof is what the end of stdin does when it is read line by line.
+ *  ~ ingest says stdin is piped lines to show (see Console::ingest_stdin), keys are read from the terminal instead.
+ */
+#[derive(Debug, Clone)]
+pub struct InputOptions {
+    pub keymap: Keymap,
+    pub zone_count: usize,
+    pub arrangement: Arrangement,
+    pub status_bar: bool,
+    pub mouse: bool,
+    pub autosuggest: bool,
+    pub history_limit: MemoryLimit,
+    pub memory: Arc<MemoryMeter>,
+    pub validators: Validators,
+    pub highlighter: Option<Arc<dyn Highlighter>>,
+    pub theme: Theme,
+    pub eof: EofBehavior,
+    pub ingest: bool,
+}
+
+impl InputOptions {
+    /**
+     * The output zone drawn at a screen cell, None for the status bar and the input rows
+     */
+    pub fn zone_at(&self, column: u16, row: u16) -> Option<usize> {
+        Layout::from_terminal(self.status_bar).split_output(self.zone_count, &self.arrangement)
+            .iter().position(|rect| rect.contains(column, row))
+    }
+}
+
+/**
+ * An active read_secret() prompt
+ */
+#[derive(Debug)]
+struct SecretPrompt {
+    prompt: String,
+    mask: Option<char>,
+    reply: oneshot::Sender<String>,
+    editor: LineEditor,
+}
+
+/**
+ * Everything the raw mode input zone keeps between keys
+ */
+#[derive(Debug)]
+struct InputZone {
+    tx: MailboxSender,
+    options: InputOptions,
+    vi: Option<ViState>,
+    focus: Option<usize>,
+    selecting: bool,
+    mode: Mode,
+    editor: LineEditor,
+    search: LineEditor,
+    reverse: ReverseSearch,
+    secret: Option<SecretPrompt>,
+    confirm: Option<ConfirmDialog>,
+    select: Option<SelectDialog>,
+    form: Option<FormDialog>,
+    page: Option<PageDialog>,
+    //keys and pastes that came while a dialog had the input zone, replayed into the line once it is answered
+    held: VecDeque<InputEvent>,
+    pending: VecDeque<InputRequest>,
+    //why the last submitted line was rejected, drawn under it until the next key
+    error: Option<String>,
+}
+
+impl InputZone {
+    fn new(tx: MailboxSender, options: InputOptions) -> Self {
+        let mut editor = LineEditor::new();
+        editor.set_history_limit(options.history_limit.clone(), options.memory.clone());
+        editor.set_autosuggest(options.autosuggest);
+        InputZone { tx, options, vi: None, focus: None, selecting: false, mode: Mode::Line, editor, search: LineEditor::new(), reverse: ReverseSearch::new(), secret: None, confirm: None, select: None, form: None, page: None, held: VecDeque::new(), pending: VecDeque::new(), error: None }
+    }
+
+    /**
+     * Queue a request, it takes over the input zone as soon as the user isn't in the middle of a search
+     */
+    fn request(&mut self, request: InputRequest) {
+        match request {
+            InputRequest::EditMode(mode) => self.set_edit_mode(mode),
+            InputRequest::Detach => {},
+            request => {
+                self.pending.push_back(request);
+                self.next_request();
+            },
+        }
+    }
+
+    fn set_edit_mode(&mut self, mode: EditMode) {
+        self.vi = match mode {
+            EditMode::Vi => Some(self.vi.take().unwrap_or_default()),
+            EditMode::Emacs => None,
+        };
+    }
+
+    fn next_request(&mut self) {
+        if self.mode != Mode::Line {
+            return;
+        }
+        match self.pending.pop_front() {
+            Some(InputRequest::Secret { prompt, mask, reply }) => {
+                self.secret = Some(SecretPrompt { prompt, mask, reply, editor: LineEditor::new() });
+                self.mode = Mode::Secret;
+            },
+            Some(InputRequest::Confirm { prompt, default, reply }) => {
+                self.confirm = Some(ConfirmDialog { prompt, default, reply });
+                self.mode = Mode::Confirm;
+            },
+            Some(InputRequest::Select { title, items, reply }) => {
+                self.select = Some(SelectDialog::new(title, items, reply));
+                self.mode = Mode::Select;
+            },
+            Some(InputRequest::Form(form)) => {
+                self.form = Some(*form);
+                self.mode = Mode::Form;
+            },
+            Some(InputRequest::Page { prompt, reply }) => {
+                self.page = Some(PageDialog { prompt, reply });
+                self.mode = Mode::Page;
+            },
+            Some(InputRequest::EditMode(mode)) => self.set_edit_mode(mode),
+            Some(InputRequest::Detach) | None => {},
+        }
+    }
+
+    /**
+     * Draw the input zone, when it changed height the output zones are asked to redraw around it
+     */
+    fn redraw(&self, stdout: &mut TerminalWriter, prompt: &PromptSource) {
+        let resized = match (&self.mode, &self.secret) {
+            (Mode::Secret, Some(secret)) => Renderer::draw_input(stdout, &secret.prompt, &secret.editor.masked(secret.mask), None, None, &self.options.theme),
+            (Mode::Search, _) => Renderer::draw_input(stdout, SEARCH_PROMPT, &self.search, None, None, &self.options.theme),
+            (Mode::Confirm, _) if self.confirm.is_some() => {
+                let prompt = self.confirm.as_ref().map_or(String::new(), |confirm| format!("{} ", confirm.prompt));
+                Renderer::draw_input(stdout, &prompt, &LineEditor::new(), None, None, &self.options.theme)
+            },
+            (Mode::Page, _) if self.page.is_some() => {
+                let prompt = self.page.as_ref().map_or(String::new(), |page| page.prompt.clone());
+                Renderer::draw_input(stdout, &prompt, &LineEditor::new(), None, None, &self.options.theme)
+            },
+            (Mode::Select, _) => match &self.select {
+                Some(select) => Renderer::draw_select(stdout, select, &self.options.theme),
+                None => Ok(false),
+            },
+            (Mode::Form, _) => match &self.form {
+                Some(form) => {
+                    let masked = form.mask().map(|mask| form.editor.masked(mask));
+                    Renderer::draw_input(stdout, &form.prompt(), masked.as_ref().unwrap_or(&form.editor), form.error.as_deref(), None, &self.options.theme)
+                },
+                None => Ok(false),
+            },
+            (Mode::HistorySearch, _) => Renderer::draw_input(stdout, &self.reverse.prompt(), &self.reverse.preview(&self.editor), None, None, &self.options.theme),
+            _ => {
+                let highlighter = self.options.highlighter.as_deref();
+                match &self.vi {
+                    Some(vi) => Renderer::draw_input(stdout, &format!("{}{}", vi.indicator(), prompt.render()), &self.editor, self.error.as_deref(), highlighter, &self.options.theme),
+                    None => Renderer::draw_input(stdout, &prompt.render(), &self.editor, self.error.as_deref(), highlighter, &self.options.theme),
+                }
+            },
+        };
+        if let Ok(true) = resized {
+            let _ = self.tx.try_send(ConsoleEvent::Redraw);
+        }
+        let _ = Renderer::show_cursor(stdout, self.focus.is_none());
+    }
+
+    /**
+     * Move the keyboard focus, the output zones are told so they can draw the focused one
+     */
+    fn move_focus(&mut self, command: FocusCommand) -> Option<ConsoleEvent> {
+        let focus = command.apply(self.focus, self.options.zone_count);
+        self.set_focus(focus)
+    }
+
+    fn set_focus(&mut self, focus: Option<usize>) -> Option<ConsoleEvent> {
+        if focus == self.focus {
+            return None;
+        }
+        self.focus = focus;
+        Some(ConsoleEvent::Focus(focus))
+    }
+
+    /**
+     * Keys while an output zone has the focus: j/k and the arrows scroll, g/G jump to the oldest message and back to live output,
+     * Esc or Enter go back to the input line. Keys the keymap binds to console actions work as usual,
+     * anything that edits the line (typing included) moves the focus back to the input line first.
+     */
+    fn output_key(&mut self, key: KeyEvent) -> Option<ConsoleEvent> {
+        let plain = !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let scroll = |command| Some(ConsoleEvent::Scroll(command));
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') if plain => return scroll(ScrollCommand::LineUp),
+            KeyCode::Down | KeyCode::Char('j') if plain => return scroll(ScrollCommand::LineDown),
+            KeyCode::Home | KeyCode::Char('g') if plain => return scroll(ScrollCommand::Top),
+            KeyCode::End | KeyCode::Char('G') if plain => return scroll(ScrollCommand::Bottom),
+            KeyCode::Char('/') if plain => {
+                self.mode = Mode::Search;
+                return None;
+            },
+            KeyCode::Esc | KeyCode::Enter => return self.move_focus(FocusCommand::Input),
+            _ => {},
+        }
+        match self.options.keymap.lookup(&key) {
+            Some(action) if !action.edits_line() => line_key(key, &self.options.keymap, &mut self.editor, &mut self.mode),
+            _ => {
+                if let Some(event) = self.move_focus(FocusCommand::Input) {
+                    let _ = self.tx.try_send(event);
+                }
+                self.line_key(key)
+            },
+        }
+    }
+
+    /**
+     * The wheel scrolls the zone under the pointer, a click focuses it (or the input line) and a drag selects text for /copy.
+     * Only the input line takes the mouse, a search or read_secret() prompt ignores it.
+     */
+    fn mouse(&mut self, mouse: MouseEvent) -> Option<ConsoleEvent> {
+        if self.mode != Mode::Line {
+            return None;
+        }
+        let zone = self.options.zone_at(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                //scrolling acts on the focused zone, so the zone under the pointer takes the focus first
+                if let Some(event) = zone.and_then(|zone| self.set_focus(Some(zone))) {
+                    let _ = self.tx.try_send(event);
+                }
+                let command = if mouse.kind == MouseEventKind::ScrollUp { ScrollCommand::LineUp } else { ScrollCommand::LineDown };
+                zone.map(|_| ConsoleEvent::Scroll(command))
+            },
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(event) = self.set_focus(zone) {
+                    let _ = self.tx.try_send(event);
+                }
+                self.selecting = zone.is_some();
+                Some(ConsoleEvent::Select(match zone {
+                    Some(zone) => SelectCommand::Start { zone, column: mouse.column, row: mouse.row },
+                    None => SelectCommand::Clear,
+                }))
+            },
+            MouseEventKind::Drag(MouseButton::Left) if self.selecting => {
+                Some(ConsoleEvent::Select(SelectCommand::Extend { column: mouse.column, row: mouse.row }))
+            },
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.selecting = false;
+                None
+            },
+            _ => None,
+        }
+    }
+
+    /**
+     * A key for the input line, through the vi layer when vi mode is on
+     */
+    fn line_key(&mut self, key: KeyEvent) -> Option<ConsoleEvent> {
+        match self.options.keymap.lookup(&key) {
+            Some(Action::FocusNext) => return self.move_focus(FocusCommand::Next),
+            Some(Action::FocusPrevious) => return self.move_focus(FocusCommand::Previous),
+            Some(Action::HistorySearch) => {
+                self.reverse = ReverseSearch::new();
+                self.mode = Mode::HistorySearch;
+                return None;
+            },
+            _ => {},
+        }
+        let consumed = match self.vi.as_mut() {
+            Some(vi) => vi.key(&key, &mut self.editor),
+            None => false,
+        };
+        if consumed {
+            return None;
+        }
+        //a rejected line is not submitted, it stays for the user to fix
+        if self.options.keymap.lookup(&key) == Some(&Action::Submit) && !self.editor.buffer().ends_with('\\') {
+            if let Err(error) = self.options.validators.check(self.editor.buffer().trim()) {
+                self.error = Some(error);
+                return None;
+            }
+        }
+        line_key(key, &self.options.keymap, &mut self.editor, &mut self.mode)
+    }
+
+    /**
+     * Apply a key in whichever mode is active, returns the event to send to the broker if the key produced one
+     */
+    fn key(&mut self, key: KeyEvent) -> Option<ConsoleEvent> {
+        self.error = None;
+        let event = match self.mode {
+            Mode::Line if self.focus.is_some() => match self.options.keymap.lookup(&key) {
+                Some(Action::FocusNext) => self.move_focus(FocusCommand::Next),
+                Some(Action::FocusPrevious) => self.move_focus(FocusCommand::Previous),
+                _ => self.output_key(key),
+            },
+            Mode::Line => self.line_key(key),
+            Mode::Search => search_key(key, &mut self.search, &mut self.mode),
+            Mode::HistorySearch => self.history_search_key(key),
+            Mode::Secret => {
+                self.secret_key(key);
+                None
+            },
+            Mode::Confirm => {
+                self.confirm_key(key);
+                None
+            },
+            Mode::Page => {
+                self.page_key(key);
+                None
+            },
+            Mode::Select => {
+                self.select_key(key);
+                None
+            },
+            Mode::Form => {
+                self.form_key(key);
+                None
+            },
+        };
+        self.next_request();
+        if self.mode == Mode::Line && !self.held.is_empty() {
+            self.replay_held();
+        }
+        event
+    }
+
+    /**
+     * A key while a confirm() dialog is up, keys that don't answer it are held for the line
+     */
+    fn confirm_key(&mut self, key: KeyEvent) {
+        let default = self.confirm.as_ref().is_some_and(|confirm| confirm.default);
+        match dialog::answer_for_key(&key, default) {
+            Some(yes) => {
+                if let Some(confirm) = self.confirm.take() {
+                    confirm.answer(yes);
+                }
+                self.mode = Mode::Line;
+            },
+            None => self.held.push_back(InputEvent::Terminal(Event::Key(key))),
+        }
+    }
+
+    /**
+     * A key while the pager waits, keys that don't page are held for the line
+     */
+    fn page_key(&mut self, key: KeyEvent) {
+        match dialog::page_step_for_key(&key) {
+            Some(step) => {
+                if let Some(page) = self.page.take() {
+                    page.answer(step);
+                }
+                self.mode = Mode::Line;
+            },
+            None => self.held.push_back(InputEvent::Terminal(Event::Key(key))),
+        }
+    }
+
+    /**
+     * A key while a select() picker is up
+     */
+    fn select_key(&mut self, key: KeyEvent) {
+        let step = match self.select.as_mut() {
+            Some(select) => select.key(&key),
+            None => SelectStep::Cancel,
+        };
+        match step {
+            SelectStep::Open => {},
+            SelectStep::Chosen(index) => {
+                if let Some(select) = self.select.take() {
+                    let _ = select.reply.send(index);
+                }
+                self.mode = Mode::Line;
+            },
+            SelectStep::Cancel => {
+                self.select = None;
+                self.mode = Mode::Line;
+            },
+        }
+    }
+
+    /**
+     * A key while a form is being filled in: Enter submits the field, Up and Shift+Tab go back one, Esc and Ctrl+C cancel
+     */
+    fn form_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let form = match self.form.as_mut() {
+            Some(form) => form,
+            None => {
+                self.mode = Mode::Line;
+                return;
+            },
+        };
+        form.error = None;
+        match key.code {
+            KeyCode::Enter if form.submit_line() == FormStep::Done => {
+                if let Some(form) = self.form.take() {
+                    form.finish();
+                }
+                self.mode = Mode::Line;
+            },
+            KeyCode::Enter => {},
+            KeyCode::Esc | KeyCode::Char('c') if key.code == KeyCode::Esc || ctrl => {
+                self.form = None;
+                self.mode = Mode::Line;
+            },
+            KeyCode::Up | KeyCode::BackTab => {
+                form.previous();
+            },
+            KeyCode::Char('u') if ctrl => form.editor.kill_to_start(),
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => form.editor.insert(c),
+            KeyCode::Backspace => form.editor.backspace(),
+            KeyCode::Delete => form.editor.delete(),
+            KeyCode::Left => form.editor.left(),
+            KeyCode::Right => form.editor.right(),
+            KeyCode::Home => form.editor.home(),
+            KeyCode::End => form.editor.end(),
+            _ => {},
+        }
+    }
+
+    /**
+     * Apply what was typed while a dialog was up, as if it was typed now. The events go out right away,
+     * a key that opens the next dialog holds the rest again
+     */
+    fn replay_held(&mut self) {
+        for event in std::mem::take(&mut self.held) {
+            let event = match event {
+                InputEvent::Terminal(Event::Key(key)) => self.key(key),
+                InputEvent::Paste(text) => self.paste(&text),
+                InputEvent::Terminal(_) => None,
+            };
+            if let Some(event) = event {
+                let _ = self.tx.try_send(event);
+            }
+        }
+    }
+
+    /**
+     * A key of a Ctrl+R search, an accepted match goes into the line before the key that accepted it is applied there
+     */
+    fn history_search_key(&mut self, key: KeyEvent) -> Option<ConsoleEvent> {
+        match self.reverse.key(&key, &self.editor) {
+            SearchStep::Searching => None,
+            SearchStep::Cancel => {
+                self.mode = Mode::Line;
+                None
+            },
+            SearchStep::Accept(line, key) => {
+                self.mode = Mode::Line;
+                if let Some(line) = line {
+                    self.editor.set_line(&line);
+                    self.editor.set_cursor(line.find(self.reverse.query()).unwrap_or(line.len()));
+                }
+                self.line_key(key)
+            },
+        }
+    }
+
+    /**
+     * Insert pasted text in whichever mode is active, only the line editor keeps newlines
+     */
+    fn paste(&mut self, text: &str) -> Option<ConsoleEvent> {
+        self.error = None;
+        match self.mode {
+            Mode::Line => {
+                self.editor.insert_str(text);
+                self.move_focus(FocusCommand::Input)
+            },
+            Mode::Search => {
+                self.search.insert_str(text.lines().next().unwrap_or(""));
+                Some(ConsoleEvent::Search(SearchCommand::Query(self.search.buffer().to_string())))
+            },
+            Mode::Secret => {
+                if let Some(secret) = self.secret.as_mut() {
+                    secret.editor.insert_str(&text.replace('\n', ""));
+                }
+                None
+            },
+            Mode::Confirm | Mode::Page => {
+                self.held.push_back(InputEvent::Paste(text.to_string()));
+                None
+            },
+            Mode::Select => {
+                if let Some(select) = self.select.as_mut() {
+                    select.paste(text);
+                }
+                None
+            },
+            Mode::Form => {
+                if let Some(form) = self.form.as_mut() {
+                    form.editor.insert_str(text.lines().next().unwrap_or(""));
+                }
+                None
+            },
+            Mode::HistorySearch => {
+                for c in text.lines().next().unwrap_or("").chars() {
+                    self.reverse.key(&KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), &self.editor);
+                }
+                None
+            },
+        }
+    }
+
+    fn secret_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let secret = match self.secret.as_mut() {
+            Some(secret) => secret,
+            None => {
+                self.mode = Mode::Line;
+                return;
+            }
+        };
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(secret) = self.secret.take() {
+                    let mut editor = secret.editor;
+                    let _ = secret.reply.send(editor.take());
+                }
+                self.mode = Mode::Line;
+            },
+            KeyCode::Esc => {
+                self.secret = None;
+                self.mode = Mode::Line;
+            },
+            KeyCode::Char('c') if ctrl => {
+                self.secret = None;
+                self.mode = Mode::Line;
+            },
+            KeyCode::Char('u') if ctrl => secret.editor.kill_to_start(),
+            KeyCode::Char(c) if !ctrl => secret.editor.insert(c),
+            KeyCode::Backspace => secret.editor.backspace(),
+            KeyCode::Delete => secret.editor.delete(),
+            KeyCode::Left => secret.editor.left(),
+            KeyCode::Right => secret.editor.right(),
+            KeyCode::Home => secret.editor.home(),
+            KeyCode::End => secret.editor.end(),
+            _ => {},
+        }
+    }
+}
+
+/**
+ * Whether there is a terminal to read keys from besides stdin, the controlling terminal (/dev/tty) on Unix.
+ * Windows reads them from the console whatever stdin is
+ */
+pub fn has_terminal() -> bool {
+    #[cfg(unix)]
+    return std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty").is_ok();
+    #[cfg(not(unix))]
+    true
+}
+
+/**
+ * Run the input zone until the user quits.
+ * On a terminal the line is edited in raw mode with keybinds, when stdin is a pipe or file it is read line by line,
+ * and so it is on a terminal that can't move the cursor (the Append strategy, see output::terminal::RenderStrategy).
+ * With ingest stdin isn't input at all, the keys come from the terminal (crossterm opens /dev/tty when stdin is not one)
+ */
+pub async fn run(tx: MailboxSender, stdout: TerminalWriter, prompt: PromptSource, requests: mpsc::UnboundedReceiver<InputRequest>, options: InputOptions) {
+    if (std::io::stdin().is_tty() || options.ingest) && capabilities::strategy().moves_cursor() {
+        raw_input(tx, stdout, prompt, requests, options).await;
+    } else {
+        line_input(tx, stdout, prompt, requests, options.validators, options.theme, options.eof).await;
+    }
+}
+
+/**
+ * Lines typed straight into stdin, no editing beyond what the terminal gives.
+ * A read_secret() request takes the next line as its answer, it can't be masked here.
+ * A confirm() question takes the next y, yes, n, no or empty line, other lines wait until it is answered.
+ * A select() lists its items numbered and takes the number or the text of one the same way.
+ * A form takes a line for each field, a field that is not valid is asked again. Secret fields can't be masked here.
+ * A line a validator rejects is dropped with the error printed, there is no editor to keep it in.
+ * The end of stdin does what eof says, see EofBehavior.
+ */
+async fn line_input(tx: MailboxSender, mut stdout: TerminalWriter, prompt: PromptSource, mut requests: mpsc::UnboundedReceiver<InputRequest>, validators: Validators, theme: Theme, eof: EofBehavior) {
+    let mut reader = BufReader::new(tokio::io::stdin());
+    let mut pending: VecDeque<InputRequest> = VecDeque::new();
+    //lines typed while a confirm() question was waiting for its answer, they are read again after it
+    let mut held: VecDeque<String> = VecDeque::new();
+    //lines ending in a backslash are joined with the next one
+    let mut block = String::new();
+    //stdin ended and is read again every REATTACH_POLL until there is more
+    let mut at_end = false;
+    loop {
+        let mut input = String::new();
+        let read = match held.pop_front() {
+            Some(line) if pending.is_empty() => {
+                input = line;
+                Ok(input.len())
+            },
+            line => {
+                held.extend(line);
+                tokio::select! {
+                    read = reader.read_line(&mut input) => read,
+                    request = requests.recv() => {
+                        //there is no editing to switch without raw mode
+                        match request {
+                            Some(InputRequest::EditMode(_)) | None => {},
+                            Some(InputRequest::Detach) => break,
+                            Some(request) => {
+                                if pending.is_empty() {
+                                    draw_question(&mut stdout, &request, &theme);
+                                }
+                                pending.push_back(request);
+                            },
+                        }
+                        continue;
+                    },
+                }
+            },
+        };
+        match read {
+        //stdin ended
+            Ok(0) => match eof {
+                EofBehavior::Shutdown => {
+                    let _ = tx.send(ConsoleEvent::Shutdown).await;
+                    break;
+                },
+                EofBehavior::Display => {
+                    println!("[console] stdin closed, showing output only");
+                    break;
+                },
+                EofBehavior::Reattach => {
+                    if !at_end {
+                        println!("[console] stdin closed, waiting for more input");
+                        at_end = true;
+                    }
+                    tokio::time::sleep(REATTACH_POLL).await;
+                },
+            },
+        //a module is waiting for this line
+            Ok(n) if n > 0 && !pending.is_empty() => {
+                at_end = false;
+                match pending.pop_front().map(|request| answer_line(request, &input)) {
+                    Some(LineAnswer::Continues(request)) => pending.push_front(request),
+                    //not an answer, the question stays and the line waits for after it
+                    Some(LineAnswer::Unanswered(request)) => {
+                        held.push_back(input);
+                        pending.push_front(request);
+                    },
+                    _ => {},
+                }
+                if !pending.front().is_some_and(|request| draw_question(&mut stdout, request, &theme)) {
+                    Renderer::draw_prompt(&mut stdout, &prompt.render(), &theme).unwrap();
+                }
+            },
+        //user ends input
+            Ok(_) if input.trim().eq_ignore_ascii_case("exit") || input.trim().eq_ignore_ascii_case("quit") => {
+                let _ = tx.send(ConsoleEvent::Shutdown).await;
+                break;
+            },
+        //continued on the next line
+            Ok(_) if input.trim_end().ends_with('\\') => {
+                at_end = false;
+                block.push_str(input.trim_end().trim_end_matches('\\'));
+                block.push('\n');
+            },
+        //message ok
+            Ok(_) => {
+                at_end = false;
+                block.push_str(input.trim_end());
+                let line = std::mem::take(&mut block);
+                match validators.check(line.trim()) {
+                    //the broker has shut down, nothing is left to type to
+                    Ok(()) => if tx.send(ConsoleEvent::UserInput(line.trim().to_string())).await.is_err() {
+                        break;
+                    },
+                    Err(error) => println!("! {}", error),
+                }
+                //the newline scrolled the screen, put the prompt back on the input row
+                Renderer::draw_prompt(&mut stdout, &prompt.render(), &theme).unwrap();
+            },
+        //User pressed Ctrl+C
+            Err(ref e) if e.kind() == tokio::io::ErrorKind::Interrupted => {
+                break;
+            },
+        //other errors
+            Err(e) => {
+                println!("Error reading from stdin: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/**
+ * Show a confirm() question, the numbered items of a select() or the field of a form in place of the prompt, there is no input zone
+ * to take over without raw mode. False for requests that don't show anything
+ */
+fn draw_question(stdout: &mut TerminalWriter, request: &InputRequest, theme: &Theme) -> bool {
+    match request {
+        InputRequest::Confirm { prompt, .. } => {
+            let _ = Renderer::draw_prompt(stdout, &format!("{} ", prompt), theme);
+            true
+        },
+        InputRequest::Select { title, items, .. } => {
+            for (number, item) in items.iter().enumerate() {
+                println!("{:>3}) {}", number + 1, item);
+            }
+            let _ = Renderer::draw_prompt(stdout, &format!("{} [1-{}]: ", title, items.len()), theme);
+            true
+        },
+        InputRequest::Form(form) => {
+            let _ = Renderer::draw_prompt(stdout, &form.prompt(), theme);
+            true
+        },
+        InputRequest::Page { prompt, .. } => {
+            let _ = Renderer::draw_prompt(stdout, &format!("{} ", prompt), theme);
+            true
+        },
+        _ => false,
+    }
+}
+
+/**
+ * What a line typed into stdin did to the request waiting for it
+ *  ~ Continues: the request took the line and wants more, eg. a form with fields left.
+ *  ~ Unanswered: the line is not an answer, it waits until the request is answered.
+ */
+enum LineAnswer {
+    Answered,
+    Continues(InputRequest),
+    Unanswered(InputRequest),
+}
+
+/**
+ * Answer a request with a line typed into stdin, the request comes back when it isn't done with
+ */
+fn answer_line(request: InputRequest, input: &str) -> LineAnswer {
+    let line = input.trim_end_matches(&['\r', '\n'][..]);
+    match request {
+        InputRequest::Secret { reply, .. } => {
+            let _ = reply.send(line.to_string());
+        },
+        InputRequest::Confirm { prompt, default, reply } => match dialog::answer_for_line(input, default) {
+            Some(yes) => {
+                let _ = reply.send(yes);
+            },
+            None => return LineAnswer::Unanswered(InputRequest::Confirm { prompt, default, reply }),
+        },
+        InputRequest::Select { title, items, reply } => match dialog::select_for_line(input, &items) {
+            Some(index) => {
+                let _ = reply.send(index);
+            },
+            None => return LineAnswer::Unanswered(InputRequest::Select { title, items, reply }),
+        },
+        InputRequest::Form(mut form) => match form.submit(line) {
+            FormStep::Done => form.finish(),
+            FormStep::Next => return LineAnswer::Continues(InputRequest::Form(form)),
+            FormStep::Invalid(error) => {
+                println!("! {}", error);
+                return LineAnswer::Continues(InputRequest::Form(form));
+            },
+        },
+        InputRequest::Page { prompt, reply } => match dialog::page_step_for_line(input) {
+            Some(step) => {
+                let _ = reply.send(step);
+            },
+            None => return LineAnswer::Unanswered(InputRequest::Page { prompt, reply }),
+        },
+        InputRequest::EditMode(_) | InputRequest::Detach => {},
+    }
+    LineAnswer::Answered
+}
+
+/**
+ * The text a key stands for when it is part of a paste, None for keys that can't be pasted
+ */
+fn pasted_char(event: &Event) -> Option<char> {
+    let key = match event {
+        Event::Key(key) => key,
+        _ => return None,
+    };
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('j') if ctrl => Some('\n'),
+        KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => Some(c),
+        KeyCode::Enter => Some('\r'),
+        KeyCode::Tab => Some(' '),
+        _ => None,
+    }
+}
+
+/**
+ * Line ends as the terminal sent them turned into Enter keys.
+ *  ~ A \r or \n that comes through as a plain character is Enter.
+ *  ~ Windows consoles can follow Enter with the \n (or Ctrl+J) of a CRLF pair, within PASTE_GAP it is dropped
+ *    so one keypress doesn't turn into a two key "paste".
+ */
+fn line_end(event: Event, last_enter: &mut Option<Instant>) -> Option<Event> {
+    let key = match event {
+        Event::Key(key) => key,
+        other => {
+            *last_enter = None;
+            return Some(other);
+        },
+    };
+    let plain = !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+    let line_feed = key.code == KeyCode::Char('\n') || key.code == KeyCode::Char('j') && key.modifiers == KeyModifiers::CONTROL;
+    if line_feed && last_enter.take().is_some_and(|at| at.elapsed() <= PASTE_GAP) {
+        return None;
+    }
+    let key = match key.code {
+        KeyCode::Char('\r') | KeyCode::Char('\n') if plain => KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        _ => key,
+    };
+    *last_enter = if key.code == KeyCode::Enter { Some(Instant::now()) } else { None };
+    Some(Event::Key(key))
+}
+
+/**
+ * crossterm's event read blocks, so it gets a thread of its own and forwards events to the input task.
+ * crossterm 0.22 swallows the bracketed paste markers, so a paste is told apart by timing instead:
+ * text keys that keep arriving within PASTE_GAP of each other are gathered and sent as one Paste.
+ */
+fn spawn_event_reader() -> mpsc::Receiver<InputEvent> {
+    let (tx, rx) = mpsc::channel(64);
+    std::thread::spawn(move || {
+        let mut last_enter = None;
+        let mut read = move || -> Option<Event> {
+            loop {
+                if let Some(event) = line_end(event::read().ok()?, &mut last_enter) {
+                    return Some(event);
+                }
+            }
+        };
+        while let Some(first) = read() {
+            let mut burst: Vec<Event> = Vec::new();
+            let mut next = Some(first);
+            while let Some(event) = next.take() {
+                if pasted_char(&event).is_none() {
+                    next = Some(event);
+                    break;
+                }
+                burst.push(event);
+                next = match event::poll(PASTE_GAP) {
+                    Ok(true) => read(),
+                    _ => None,
+                };
+            }
+            let mut out: Vec<InputEvent> = Vec::new();
+            if burst.len() > 1 {
+                let text: String = burst.iter().filter_map(pasted_char).collect();
+                //terminals paste line ends as \r, some as \r\n, and a trailing one would only leave an empty line
+                let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                out.push(InputEvent::Paste(text.trim_end_matches('\n').to_string()));
+            } else {
+                out.extend(burst.into_iter().map(InputEvent::Terminal));
+            }
+            out.extend(next.map(InputEvent::Terminal));
+            if out.into_iter().any(|event| tx.blocking_send(event).is_err()) {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/**
+ * The raw mode line editor, the keys of the input line come from the keymap. With the default one:
+ *  ~ Enter submits, Up/Down browse history, Ctrl+A/E, Ctrl+U/K and the arrow keys edit.
+ *  ~ Ctrl+U/K, Ctrl+W and Alt+D kill into the kill ring, Ctrl+Y yanks and Alt+Y cycles older kills, Alt+B/F move by words.
+ *  ~ Ctrl+_ (or Ctrl+Z) undoes the last edit and Alt+/ redoes it.
+ *  ~ The newest history entry that starts with the line is suggested in dim text after the cursor, Right or End takes it.
+ *  ~ PageUp/PageDown scroll the focused output zone, Ctrl+Home/Ctrl+End jump to the oldest message and back to live output.
+ *  ~ Tab and Shift+Tab move the focus from the input line through the output zones, a focused zone scrolls with j/k and the arrows.
+ *    The cursor is hidden while an output zone has the focus and typing moves it back to the input line.
+ *  ~ In vi mode Esc enters normal mode on top of all this, see ViState.
+ *  ~ Alt+Enter (or Shift+Enter) or a trailing backslash starts a new line, the whole block is submitted as one input.
+ *  ~ Pasted text is inserted as a whole, newlines included, and waits for Enter like typed text.
+ *  ~ With the mouse on, the wheel scrolls the zone under the pointer, a click focuses a zone and a drag selects text that /copy copies.
+ *  ~ Ctrl+S pauses the output zones and Ctrl+Q resumes them.
+ *  ~ Ctrl+R searches back through the input history, again for older matches, Enter runs the match and Esc gives up.
+ *  ~ Ctrl+F searches the scrollback, Up/Ctrl+P and Down/Ctrl+N jump to older and newer matches, Enter keeps the view, Esc ends the search.
+ *  ~ Ctrl+C, Ctrl+D on an empty line, exit and quit shut the console down.
+ *  ~ A read_secret() request takes over the input line until it is answered with Enter or cancelled with Esc.
+ *  ~ A confirm() question takes over the input line until y, n or Enter answers it, what else is typed meanwhile goes into the line after.
+ *  ~ A select() picker lists its items above the prompt line until one is chosen with Enter or Esc cancels, typing filters them.
+ *  ~ A form asks its fields one at a time with the error of an invalid answer under it, Up goes back a field and Esc cancels.
+ */
+async fn raw_input(tx: MailboxSender, mut stdout: TerminalWriter, mut prompt: PromptSource, mut requests: mpsc::UnboundedReceiver<InputRequest>, options: InputOptions) {
+    if let Err(e) = terminal::enable_raw_mode() {
+        println!("Could not switch the terminal to raw mode: {}", e);
+        return line_input(tx, stdout, prompt, requests, options.validators, options.theme, options.eof).await;
+    }
+    let mouse = options.mouse;
+    if mouse {
+        let _ = execute!(stdout, event::EnableMouseCapture);
+    }
+    let mut events = spawn_event_reader();
+    let mut zone = InputZone::new(tx.clone(), options);
+    //time and unread segments change on their own, redraw them once a second
+    let mut prompt_tick = tokio::time::interval(tokio::time::Duration::from_secs(1));
+
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Some(event) => event,
+                None => break,
+            },
+            Some(request) = requests.recv() => {
+                if matches!(request, InputRequest::Detach) {
+                    break;
+                }
+                zone.request(request);
+                zone.redraw(&mut stdout, &prompt);
+                continue;
+            },
+            changed = prompt.spec.changed() => {
+                if changed.is_ok() {
+                    zone.redraw(&mut stdout, &prompt);
+                }
+                continue;
+            },
+            _ = prompt_tick.tick(), if prompt.is_dynamic() => {
+                zone.redraw(&mut stdout, &prompt);
+                continue;
+            },
+        };
+        let key = match event {
+            InputEvent::Paste(text) => {
+                let event = zone.paste(&text);
+                zone.redraw(&mut stdout, &prompt);
+                if let Some(event) = event {
+                    let _ = tx.send(event).await;
+                }
+                continue;
+            },
+            InputEvent::Terminal(Event::Key(key)) => key,
+            InputEvent::Terminal(Event::Mouse(mouse)) => {
+                if let Some(event) = zone.mouse(mouse) {
+                    let _ = tx.send(event).await;
+                }
+                zone.redraw(&mut stdout, &prompt);
+                continue;
+            },
+            InputEvent::Terminal(Event::Resize(_, _)) => {
+                zone.redraw(&mut stdout, &prompt);
+                let _ = tx.try_send(ConsoleEvent::Redraw);
+                continue;
+            },
+            _ => continue,
+        };
+        let event = zone.key(key);
+        zone.redraw(&mut stdout, &prompt);
+        if let Some(event) = event {
+            let shutdown = matches!(event, ConsoleEvent::Shutdown);
+            if tx.send(event).await.is_err() || shutdown {
+                break;
+            }
+        }
+    }
+    if mouse {
+        let _ = execute!(stdout, event::DisableMouseCapture);
+    }
+    let _ = Renderer::show_cursor(&mut stdout, true);
+    let _ = terminal::disable_raw_mode();
+}
+
+/**
+ * Apply a key to the input line through the keymap, returns the event to send to the broker if the key produced one
+ */
+fn line_key(key: KeyEvent, keymap: &Keymap, editor: &mut LineEditor, mode: &mut Mode) -> Option<ConsoleEvent> {
+    let action = match keymap.lookup(&key) {
+        Some(action) => action,
+        None => {
+            if let KeyCode::Char(c) = key.code {
+                if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                    editor.insert(c);
+                }
+            }
+            return None;
+        },
+    };
+    match action {
+        //a trailing backslash continues the input on a new line
+        Action::Submit if editor.buffer().ends_with('\\') => {
+            editor.end();
+            editor.backspace();
+            editor.newline();
+        },
+        Action::Submit => {
+            let line = editor.submit();
+            let line = line.trim();
+            if line.eq_ignore_ascii_case("exit") || line.eq_ignore_ascii_case("quit") {
+                return Some(ConsoleEvent::Shutdown);
+            }
+            return Some(ConsoleEvent::UserInput(line.to_string()));
+        },
+        Action::Newline => editor.newline(),
+        Action::Quit => return Some(ConsoleEvent::Shutdown),
+        Action::Eof if editor.is_empty() => return Some(ConsoleEvent::Shutdown),
+        Action::Eof => editor.delete(),
+        Action::Left => editor.left(),
+        //at the end of the line both take the suggestion, if there is one
+        Action::Right | Action::End if editor.accept_suggestion() => {},
+        Action::Right => editor.right(),
+        Action::Home => editor.home(),
+        Action::End => editor.end(),
+        Action::Backspace => editor.backspace(),
+        Action::Delete => editor.delete(),
+        Action::KillToStart => editor.kill_to_start(),
+        Action::KillToEnd => editor.kill_to_end(),
+        Action::KillWordBackward => editor.kill_word_backward(),
+        Action::KillWordForward => editor.kill_word_forward(),
+        Action::Yank => editor.yank(),
+        Action::YankPop => editor.yank_pop(),
+        Action::WordLeft => editor.word_left(),
+        Action::WordRight => editor.word_right(),
+        Action::Undo => { editor.undo(); },
+        Action::Redo => { editor.redo(); },
+        Action::Up => if !editor.up() { editor.history_previous() },
+        Action::Down => if !editor.down() { editor.history_next() },
+        Action::HistoryPrevious => editor.history_previous(),
+        Action::HistoryNext => editor.history_next(),
+        //InputZone starts it, the search has state of its own
+        Action::HistorySearch => {},
+        Action::Search => *mode = Mode::Search,
+        Action::ClearSearch => return Some(ConsoleEvent::Search(SearchCommand::Clear)),
+        Action::Pause => return Some(ConsoleEvent::Pause(true)),
+        Action::Resume => return Some(ConsoleEvent::Pause(false)),
+        Action::ScrollUp => return Some(ConsoleEvent::Scroll(ScrollCommand::Up)),
+        Action::ScrollDown => return Some(ConsoleEvent::Scroll(ScrollCommand::Down)),
+        Action::ScrollTop => return Some(ConsoleEvent::Scroll(ScrollCommand::Top)),
+        Action::ScrollBottom => return Some(ConsoleEvent::Scroll(ScrollCommand::Bottom)),
+        Action::NextTab => return Some(ConsoleEvent::Tab(TabCommand::Next)),
+        Action::PreviousTab => return Some(ConsoleEvent::Tab(TabCommand::Previous)),
+        Action::ToggleThread => return Some(ConsoleEvent::Thread(ThreadCommand::Toggle(None))),
+        //InputZone moves the focus, it knows where it is
+        Action::FocusNext | Action::FocusPrevious => {},
+        Action::Custom(name) => return Some(ConsoleEvent::Action(name.clone())),
+    }
+    None
+}
+
+/**
+ * Apply a key to the search term, every edit sends the new term so the search follows the typing
+ */
+fn search_key(key: KeyEvent, search: &mut LineEditor, mode: &mut Mode) -> Option<ConsoleEvent> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let command = match key.code {
+        KeyCode::Enter => {
+            *mode = Mode::Line;
+            return None;
+        },
+        KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('g') if key.code == KeyCode::Esc || ctrl => {
+            *mode = Mode::Line;
+            search.clear();
+            SearchCommand::Clear
+        },
+        KeyCode::Up => SearchCommand::Older,
+        KeyCode::Char('p') if ctrl => SearchCommand::Older,
+        KeyCode::Down => SearchCommand::Newer,
+        KeyCode::Char('n') if ctrl => SearchCommand::Newer,
+        KeyCode::Char(c) if !ctrl => {
+            search.insert(c);
+            SearchCommand::Query(search.buffer().to_string())
+        },
+        KeyCode::Backspace => {
+            search.backspace();
+            SearchCommand::Query(search.buffer().to_string())
+        },
+        KeyCode::Left => {
+            search.left();
+            return None;
+        },
+        KeyCode::Right => {
+            search.right();
+            return None;
+        },
+        _ => return None,
+    };
+    Some(ConsoleEvent::Search(command))
+}
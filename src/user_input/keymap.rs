@@ -0,0 +1,347 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::HashMap;
+use std::fmt;
+
+//External
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/**
+ * Topic of the message a custom action delivers to every module inbox, the payload is the action name
+ */
+pub const ACTION_TOPIC: &str = "action";
+
+/**
+ * What a key does in the input line.
+ *  ~ Up and Down move between the lines of a multi-line input and browse the history past the first and last line.
+ *  ~ Kill actions cut text into the kill ring, Yank puts the newest kill back and YankPop right after it swaps it for an older one.
+ *  ~ HistorySearch searches back through the input history as the query is typed, see reverse_search::ReverseSearch.
+ *  ~ Undo puts the line back as it was before the last edit (a run of typing counts as one), Redo makes it again.
+ *  ~ Eof deletes the character under the cursor, or shuts the console down when the line is empty (Ctrl+D).
+ *  ~ Scroll actions move the focused output zone back through its scrollback, Focus actions move the keyboard focus
+ *    from the input line through the output zones.
+ *  ~ Tab actions switch to the tab beside the one shown when the tab bar is on.
+ *  ~ ToggleThread expands the newest thread of correlated messages or folded payload, or collapses it again (see broker::threads).
+ *  ~ Custom is delivered to every module inbox as a message with the ACTION_TOPIC topic.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    Submit,
+    Newline,
+    Quit,
+    Eof,
+    Left,
+    Right,
+    Home,
+    End,
+    Backspace,
+    Delete,
+    KillToStart,
+    KillToEnd,
+    KillWordBackward,
+    KillWordForward,
+    Yank,
+    YankPop,
+    WordLeft,
+    WordRight,
+    Undo,
+    Redo,
+    Up,
+    Down,
+    HistoryPrevious,
+    HistoryNext,
+    HistorySearch,
+    Search,
+    ClearSearch,
+    Pause,
+    Resume,
+    ScrollUp,
+    ScrollDown,
+    ScrollTop,
+    ScrollBottom,
+    FocusNext,
+    FocusPrevious,
+    NextTab,
+    PreviousTab,
+    ToggleThread,
+    Custom(String),
+}
+
+impl Action {
+    /**
+     * True for actions that change the line being edited, as opposed to ones that act on the console
+     */
+    pub fn edits_line(&self) -> bool {
+        matches!(self,
+            Action::Submit | Action::Newline | Action::Eof | Action::Left | Action::Right | Action::Home | Action::End
+            | Action::Backspace | Action::Delete | Action::KillToStart | Action::KillToEnd | Action::Undo | Action::Redo
+            | Action::KillWordBackward | Action::KillWordForward | Action::Yank | Action::YankPop | Action::WordLeft | Action::WordRight
+            | Action::Up | Action::Down | Action::HistoryPrevious | Action::HistoryNext | Action::HistorySearch)
+    }
+
+    /**
+     * Action names as used in the [keys] table of the config file, custom actions are written "action:name"
+     */
+    pub fn parse(name: &str) -> Option<Action> {
+        if let Some(custom) = name.strip_prefix("action:") {
+            return if custom.is_empty() { None } else { Some(Action::Custom(custom.to_string())) };
+        }
+        let action = match name.to_ascii_lowercase().as_str() {
+            "submit" => Action::Submit,
+            "newline" => Action::Newline,
+            "quit" => Action::Quit,
+            "eof" => Action::Eof,
+            "left" => Action::Left,
+            "right" => Action::Right,
+            "home" => Action::Home,
+            "end" => Action::End,
+            "backspace" => Action::Backspace,
+            "delete" => Action::Delete,
+            "kill-to-start" => Action::KillToStart,
+            "kill-to-end" => Action::KillToEnd,
+            "kill-word-backward" => Action::KillWordBackward,
+            "kill-word-forward" => Action::KillWordForward,
+            "yank" => Action::Yank,
+            "yank-pop" => Action::YankPop,
+            "word-left" => Action::WordLeft,
+            "word-right" => Action::WordRight,
+            "undo" => Action::Undo,
+            "redo" => Action::Redo,
+            "up" => Action::Up,
+            "down" => Action::Down,
+            "history-previous" => Action::HistoryPrevious,
+            "history-next" => Action::HistoryNext,
+            "history-search" => Action::HistorySearch,
+            "search" => Action::Search,
+            "clear-search" => Action::ClearSearch,
+            "pause" => Action::Pause,
+            "resume" => Action::Resume,
+            "scroll-up" => Action::ScrollUp,
+            "scroll-down" => Action::ScrollDown,
+            "scroll-top" => Action::ScrollTop,
+            "scroll-bottom" => Action::ScrollBottom,
+            "focus-next" => Action::FocusNext,
+            "focus-previous" => Action::FocusPrevious,
+            "next-tab" => Action::NextTab,
+            "previous-tab" => Action::PreviousTab,
+            "toggle-thread" => Action::ToggleThread,
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+/**
+ * A key with its modifiers, written like "ctrl+a", "alt+enter", "pageup" or "f5".
+ * Shift is part of the character for printable keys, so "A" is shift+a and "shift+a" is not a chord of its own.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Chord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Chord { code, modifiers }
+    }
+
+    pub fn plain(code: KeyCode) -> Self {
+        Chord::new(code, KeyModifiers::NONE)
+    }
+
+    pub fn ctrl(c: char) -> Self {
+        Chord::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    pub fn from_key(key: &KeyEvent) -> Self {
+        Chord::new(key.code, key.modifiers)
+    }
+
+    pub fn parse(text: &str) -> Option<Chord> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = text.split('+').collect();
+        //"ctrl++" binds the plus key
+        let key = match parts.pop() {
+            Some("") if text.ends_with("++") => { parts.pop(); "+" },
+            Some(key) if !key.is_empty() => key,
+            _ => return None,
+        };
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" | "meta" => modifiers |= KeyModifiers::ALT,
+                "shift" => modifiers |= KeyModifiers::SHIFT,
+                _ => return None,
+            }
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(if modifiers.contains(KeyModifiers::CONTROL) { c.to_ascii_lowercase() } else { c }),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                function => match function.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                    _ => return None,
+                },
+            },
+        };
+        Some(Chord::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{}", format!("{:?}", code).to_ascii_lowercase()),
+        }
+    }
+}
+
+/**
+ * Which action each key runs in the input line, emacs style by default.
+ * Printable keys that aren't bound insert themselves, anything else that isn't bound is ignored.
+ * The search prompt and read_secret() prompts keep their own fixed keys.
+ */
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<Chord, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Keymap::empty();
+        let defaults = [
+            (Chord::plain(KeyCode::Enter), Action::Submit),
+            (Chord::new(KeyCode::Enter, KeyModifiers::ALT), Action::Newline),
+            (Chord::new(KeyCode::Enter, KeyModifiers::SHIFT), Action::Newline),
+            (Chord::ctrl('c'), Action::Quit),
+            (Chord::ctrl('d'), Action::Eof),
+            (Chord::ctrl('f'), Action::Search),
+            (Chord::ctrl('r'), Action::HistorySearch),
+            (Chord::ctrl('s'), Action::Pause),
+            (Chord::ctrl('q'), Action::Resume),
+            (Chord::ctrl('a'), Action::Home),
+            (Chord::ctrl('e'), Action::End),
+            (Chord::ctrl('b'), Action::Left),
+            (Chord::ctrl('u'), Action::KillToStart),
+            (Chord::ctrl('k'), Action::KillToEnd),
+            (Chord::ctrl('w'), Action::KillWordBackward),
+            (Chord::new(KeyCode::Char('d'), KeyModifiers::ALT), Action::KillWordForward),
+            (Chord::ctrl('y'), Action::Yank),
+            (Chord::new(KeyCode::Char('y'), KeyModifiers::ALT), Action::YankPop),
+            (Chord::new(KeyCode::Char('b'), KeyModifiers::ALT), Action::WordLeft),
+            (Chord::new(KeyCode::Char('f'), KeyModifiers::ALT), Action::WordRight),
+            (Chord::new(KeyCode::Left, KeyModifiers::CONTROL), Action::WordLeft),
+            (Chord::new(KeyCode::Right, KeyModifiers::CONTROL), Action::WordRight),
+            (Chord::ctrl('p'), Action::HistoryPrevious),
+            (Chord::ctrl('n'), Action::HistoryNext),
+            (Chord::ctrl('h'), Action::Backspace),
+            //terminals send Ctrl+_ as the same byte as Ctrl+7, crossterm reports it as the latter
+            (Chord::ctrl('_'), Action::Undo),
+            (Chord::ctrl('7'), Action::Undo),
+            (Chord::ctrl('z'), Action::Undo),
+            (Chord::new(KeyCode::Char('/'), KeyModifiers::ALT), Action::Redo),
+            (Chord::plain(KeyCode::Esc), Action::ClearSearch),
+            (Chord::plain(KeyCode::Backspace), Action::Backspace),
+            (Chord::plain(KeyCode::Delete), Action::Delete),
+            (Chord::plain(KeyCode::Left), Action::Left),
+            (Chord::plain(KeyCode::Right), Action::Right),
+            (Chord::plain(KeyCode::Home), Action::Home),
+            (Chord::plain(KeyCode::End), Action::End),
+            (Chord::plain(KeyCode::Up), Action::Up),
+            (Chord::plain(KeyCode::Down), Action::Down),
+            (Chord::plain(KeyCode::PageUp), Action::ScrollUp),
+            (Chord::plain(KeyCode::PageDown), Action::ScrollDown),
+            (Chord::new(KeyCode::Home, KeyModifiers::CONTROL), Action::ScrollTop),
+            (Chord::new(KeyCode::End, KeyModifiers::CONTROL), Action::ScrollBottom),
+            (Chord::plain(KeyCode::Tab), Action::FocusNext),
+            (Chord::new(KeyCode::BackTab, KeyModifiers::SHIFT), Action::FocusPrevious),
+            (Chord::plain(KeyCode::BackTab), Action::FocusPrevious),
+            (Chord::new(KeyCode::Right, KeyModifiers::ALT), Action::NextTab),
+            (Chord::new(KeyCode::Left, KeyModifiers::ALT), Action::PreviousTab),
+            (Chord::new(KeyCode::Char('t'), KeyModifiers::ALT), Action::ToggleThread),
+        ];
+        for (chord, action) in defaults {
+            keymap.bind(chord, action);
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /**
+     * A keymap with nothing bound, every printable key still inserts itself
+     */
+    pub fn empty() -> Self {
+        Keymap { bindings: HashMap::new() }
+    }
+
+    pub fn bind(&mut self, chord: Chord, action: Action) {
+        self.bindings.insert(chord, action);
+    }
+
+    pub fn unbind(&mut self, chord: &Chord) {
+        self.bindings.remove(chord);
+    }
+
+    /**
+     * Bind from the text form used in the config file, action "none" unbinds the key
+     */
+    pub fn bind_str(&mut self, chord: &str, action: &str) -> Result<(), String> {
+        let chord = Chord::parse(chord).ok_or_else(|| format!("unknown key: {}", chord))?;
+        if action.eq_ignore_ascii_case("none") {
+            self.unbind(&chord);
+            return Ok(());
+        }
+        let action = Action::parse(action).ok_or_else(|| format!("unknown action for {}: {}", chord, action))?;
+        self.bind(chord, action);
+        Ok(())
+    }
+
+    pub fn lookup(&self, key: &KeyEvent) -> Option<&Action> {
+        self.bindings.get(&Chord::from_key(key))
+    }
+
+    /**
+     * Every key bound to an action, eg. to list them in a help screen
+     */
+    pub fn keys_for(&self, action: &Action) -> Vec<Chord> {
+        self.bindings.iter().filter(|(_, bound)| *bound == action).map(|(chord, _)| *chord).collect()
+    }
+}
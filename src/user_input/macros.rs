@@ -0,0 +1,150 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::user_input::config::ConfigError;
+
+//External
+use serde::{Serialize, Deserialize};
+use tokio::time::Instant;
+
+/**
+ * One recorded input line and how long after the previous one it was typed
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub line: String,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/**
+ * A macro being recorded, the lines so far and when the last one came
+ */
+#[derive(Debug, Clone)]
+struct Recording {
+    name: String,
+    steps: Vec<MacroStep>,
+    last: Instant,
+}
+
+/**
+ * The macros file, TOML, one table per macro, eg.
+ *
+ *     [[rotate.steps]]
+ *     line = "/ban flaky"
+ *     delay_ms = 0
+ *
+ *     [[rotate.steps]]
+ *     line = "/unban flaky"
+ *     delay_ms = 1500
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct MacroTable {
+    steps: Vec<MacroStep>,
+}
+
+/**
+ * Input macros, sequences of typed lines replayed as if typed again (see /macro).
+ *  ~ Recording keeps every line typed after /macro record, commands and plain lines alike, except /macro commands,
+ *    so a macro never records or plays another one.
+ *  ~ Playing sends the lines one after the other, at once or with the recorded delays between them.
+ *  ~ With a macros file every change is saved to it, the file is owned by the Console and rewritten whole.
+ */
+#[derive(Debug, Default)]
+pub struct Macros {
+    saved: BTreeMap<String, Vec<MacroStep>>,
+    recording: Option<Recording>,
+    file: Option<PathBuf>,
+}
+
+impl Macros {
+    /**
+     * Read the macros file if it exists, and save to it from now on. Returns how many macros were loaded
+     */
+    pub fn set_file(&mut self, path: Option<PathBuf>) -> Result<usize, ConfigError> {
+        let mut loaded = 0;
+        if let Some(path) = path.as_deref().filter(|path| path.exists()) {
+            let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+            let tables: BTreeMap<String, MacroTable> = toml::from_str(&text).map_err(|e| ConfigError::Parse(e.to_string()))?;
+            loaded = tables.len();
+            self.saved.extend(tables.into_iter().map(|(name, table)| (name, table.steps)));
+        }
+        self.file = path;
+        Ok(loaded)
+    }
+
+    fn save(&self) -> Result<(), ConfigError> {
+        let path = match self.file.as_deref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tables: BTreeMap<&String, MacroTable> = self.saved.iter().map(|(name, steps)| (name, MacroTable { steps: steps.clone() })).collect();
+        let text = toml::to_string(&tables).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        std::fs::write(path, text).map_err(ConfigError::Io)
+    }
+
+    /**
+     * Add or replace a macro, saved to the macros file if there is one
+     */
+    pub fn insert(&mut self, name: String, steps: Vec<MacroStep>) -> Result<(), ConfigError> {
+        self.saved.insert(name, steps);
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<bool, ConfigError> {
+        let removed = self.saved.remove(name).is_some();
+        self.save()?;
+        Ok(removed)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Vec<MacroStep>> {
+        self.saved.get(name)
+    }
+
+    /**
+     * Every macro name with its number of lines
+     */
+    pub fn list(&self) -> Vec<(String, usize)> {
+        self.saved.iter().map(|(name, steps)| (name.clone(), steps.len())).collect()
+    }
+
+    pub fn start(&mut self, name: String) {
+        self.recording = Some(Recording { name, steps: Vec::new(), last: Instant::now() });
+    }
+
+    /**
+     * The name of the macro being recorded, if any
+     */
+    pub fn recording(&self) -> Option<&str> {
+        self.recording.as_ref().map(|recording| recording.name.as_str())
+    }
+
+    /**
+     * Called with every input line, kept when a macro is being recorded
+     */
+    pub fn record(&mut self, line: &str) {
+        if let Some(recording) = self.recording.as_mut() {
+            let now = Instant::now();
+            //the first line plays right away, the wait before it was the user getting ready
+            let delay_ms = match recording.steps.is_empty() {
+                true => 0,
+                false => now.duration_since(recording.last).as_millis() as u64,
+            };
+            recording.last = now;
+            recording.steps.push(MacroStep { line: line.to_string(), delay_ms });
+        }
+    }
+
+    /**
+     * Stop recording and keep the macro, returns its name and length, None when nothing was being recorded
+     */
+    pub fn stop(&mut self) -> Option<Result<(String, usize), ConfigError>> {
+        let recording = self.recording.take()?;
+        let count = recording.steps.len();
+        Some(self.insert(recording.name.clone(), recording.steps).map(|_| (recording.name, count)))
+    }
+}
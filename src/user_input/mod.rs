@@ -1,5 +1,15 @@
-
-
-
-
-pub mod structs;
\ No newline at end of file
+pub mod structs;
+pub mod commands;
+pub mod config;
+pub mod builder;
+pub mod prompt;
+pub mod keymap;
+pub mod vi;
+pub mod editor;
+pub mod reverse_search;
+pub mod dialog;
+pub mod form;
+pub mod input;
+pub mod macros;
+pub mod validate;
+pub mod highlighter;
\ No newline at end of file
@@ -0,0 +1,168 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+//External
+use chrono::Local;
+use crossterm::style::{self, Attribute, Color, Stylize};
+use tokio::sync::watch;
+
+/**
+ * The prompt every Console starts with
+ */
+pub const DEFAULT_PROMPT: &str = "> ";
+
+/**
+ * One piece of the prompt
+ *  ~ Text is drawn as is.
+ *  ~ Name is the name set with PromptSpec::with_name, usually the module or app name.
+ *  ~ Time is the local time in the given chrono format, eg. "%H:%M".
+ *  ~ Unread is the number of messages drawn since the user last submitted a line, hidden while it is zero.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Name,
+    Time(String),
+    Unread,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptPart {
+    pub segment: Segment,
+    pub color: Option<Color>,
+    pub bold: bool,
+}
+
+/**
+ * What the input zone shows in front of the line being edited, set with Console::set_prompt.
+ * Built from parts, color() and bold() style the part added last, eg.
+ *
+ *     PromptSpec::new().name().color(Color::Cyan).text(" ").unread().color(Color::Yellow).text("> ".to_string())
+ *
+ * Dynamic parts are recomputed every time the prompt is drawn.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptSpec {
+    pub parts: Vec<PromptPart>,
+    pub name: String,
+}
+
+impl Default for PromptSpec {
+    fn default() -> Self {
+        PromptSpec::plain(DEFAULT_PROMPT.to_string())
+    }
+}
+
+impl PromptSpec {
+    pub fn new() -> Self {
+        PromptSpec { parts: Vec::new(), name: String::new() }
+    }
+
+    /**
+     * A prompt that is just text, like the old hardcoded "> "
+     */
+    pub fn plain(text: String) -> Self {
+        PromptSpec::new().text(text)
+    }
+
+    fn part(mut self, segment: Segment) -> Self {
+        self.parts.push(PromptPart { segment, color: None, bold: false });
+        self
+    }
+
+    pub fn text(self, text: String) -> Self {
+        self.part(Segment::Text(text))
+    }
+
+    pub fn name(self) -> Self {
+        self.part(Segment::Name)
+    }
+
+    pub fn time(self, format: String) -> Self {
+        self.part(Segment::Time(format))
+    }
+
+    pub fn unread(self) -> Self {
+        self.part(Segment::Unread)
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        if let Some(part) = self.parts.last_mut() {
+            part.color = Some(color);
+        }
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        if let Some(part) = self.parts.last_mut() {
+            part.bold = true;
+        }
+        self
+    }
+
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
+    /**
+     * True if the prompt shows something that changes without the user typing
+     */
+    pub fn is_dynamic(&self) -> bool {
+        self.parts.iter().any(|part| matches!(part.segment, Segment::Time(_) | Segment::Unread))
+    }
+
+    /**
+     * The prompt as it should be drawn now, colors are included as escape sequences
+     */
+    pub fn render(&self, unread: u64) -> String {
+        let mut out = String::new();
+        for part in self.parts.iter() {
+            let text = match &part.segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Name => self.name.clone(),
+                Segment::Time(format) => Local::now().format(format).to_string(),
+                Segment::Unread if unread == 0 => continue,
+                Segment::Unread => format!("({} new)", unread),
+            };
+            if part.color.is_none() && !part.bold {
+                out.push_str(&text);
+                continue;
+            }
+            let mut styled = style::style(text);
+            if let Some(color) = part.color {
+                styled = styled.with(color);
+            }
+            if part.bold {
+                styled = styled.attribute(Attribute::Bold);
+            }
+            out.push_str(&styled.to_string());
+        }
+        out
+    }
+}
+
+/**
+ * The input zone's view of the prompt, handed out by Console::prompt_source().
+ * The spec follows Console::set_prompt and the unread count follows the broker.
+ */
+#[derive(Debug, Clone)]
+pub struct PromptSource {
+    pub spec: watch::Receiver<PromptSpec>,
+    pub unread: Arc<AtomicU64>,
+}
+
+impl PromptSource {
+    pub fn render(&self) -> String {
+        self.spec.borrow().render(self.unread.load(Ordering::Relaxed))
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.spec.borrow().is_dynamic()
+    }
+}
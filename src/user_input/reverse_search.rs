@@ -0,0 +1,126 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::user_input::editor::LineEditor;
+
+//External
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/**
+ * What a key did to a reverse search
+ *  ~ Searching: the search goes on with the new query or match.
+ *  ~ Cancel: back to the line as it was before the search.
+ *  ~ Accept: the match becomes the line, then the key (Enter, an arrow...) is applied to it as usual.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchStep {
+    Searching,
+    Cancel,
+    Accept(Option<String>, KeyEvent),
+}
+
+/**
+ * Ctrl+R incremental search back through the input history, like bash and zsh.
+ *  ~ Typing narrows the query and the newest entry containing it is shown in place of the line, Backspace widens it again.
+ *  ~ Ctrl+R again goes to the next older match, Ctrl+S back to a newer one.
+ *  ~ Enter runs the match, any other key that isn't text takes it into the line and then does what it usually does.
+ *  ~ Esc, Ctrl+G and Ctrl+C give up and leave the line as it was.
+ *  ~ Matching is by substring and case sensitive, as in bash.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ReverseSearch {
+    query: String,
+    //index of the history entry shown
+    found: Option<usize>,
+    failed: bool,
+}
+
+impl ReverseSearch {
+    pub fn new() -> Self {
+        ReverseSearch::default()
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /**
+     * The prompt drawn in place of the normal one
+     */
+    pub fn prompt(&self) -> String {
+        match self.failed {
+            true => format!("(failed reverse-i-search)`{}': ", self.query),
+            false => format!("(reverse-i-search)`{}': ", self.query),
+        }
+    }
+
+    /**
+     * The history entry found so far, None before anything matched
+     */
+    pub fn matched<'a>(&self, editor: &'a LineEditor) -> Option<&'a str> {
+        self.found.and_then(|index| editor.history_entry(index))
+    }
+
+    /**
+     * A copy of the match to draw in place of the line, the cursor on where the query is in it
+     */
+    pub fn preview(&self, editor: &LineEditor) -> LineEditor {
+        let mut preview = LineEditor::new();
+        if let Some(line) = self.matched(editor) {
+            preview.insert_str(line);
+            preview.set_cursor(line.find(self.query.as_str()).unwrap_or(line.len()));
+        }
+        preview
+    }
+
+    /**
+     * Look for the query, from before (exclusive) back to the oldest entry. A miss keeps the last match on screen
+     */
+    fn search_back(&mut self, editor: &LineEditor, before: usize) {
+        match editor.find_history(&self.query, before) {
+            Some(index) => {
+                self.found = Some(index);
+                self.failed = false;
+            },
+            None => self.failed = true,
+        }
+    }
+
+    pub fn key(&mut self, key: &KeyEvent, editor: &LineEditor) -> SearchStep {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let newest = editor.history_len();
+        match key.code {
+            KeyCode::Esc => return SearchStep::Cancel,
+            KeyCode::Char('g') | KeyCode::Char('c') if ctrl => return SearchStep::Cancel,
+            KeyCode::Char('r') if ctrl => {
+                let before = self.found.unwrap_or(newest);
+                self.search_back(editor, before);
+            },
+            KeyCode::Char('s') if ctrl => {
+                let after = self.found.map_or(newest, |index| index + 1);
+                if let Some(index) = editor.find_history_newer(&self.query, after) {
+                    self.found = Some(index);
+                    self.failed = false;
+                }
+            },
+            KeyCode::Char(c) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) => {
+                self.query.push(c);
+                //the match shown may still contain the longer query
+                let before = self.found.map_or(newest, |index| index + 1);
+                self.search_back(editor, before);
+            },
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.found = None;
+                self.failed = false;
+                if !self.query.is_empty() {
+                    self.search_back(editor, newest);
+                }
+            },
+            _ => return SearchStep::Accept(self.matched(editor).map(|line| line.to_string()), *key),
+        }
+        SearchStep::Searching
+    }
+}
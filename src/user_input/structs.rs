@@ -3,10 +3,63 @@
 #![allow(unused)]
 
 //Internal
-use std::collections::HashMap;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc};
+use crate::broker::ids::IdSigner;
+use crate::broker::handle::{HandleError, SenderHandle, HandleState};
+use crate::broker::mailbox::SendOutcome;
+use crate::broker::mailbox::{self, MailboxSender, MailboxReceiver, OverflowPolicy, Scheduling};
+use crate::broker::message::{AppEvent, ConsoleEvent, Message, Origin, Output, RenderedLine};
+use crate::broker::rate_limit::{RateLimit, TokenBucket};
+use crate::broker::dead_letter::{DeadLetter, DeadLetterQueue};
+use crate::broker::filter::{FilterRule, FilterRules};
+use crate::broker::split::Split;
+use crate::broker::dedup::Repeats;
+use crate::broker::phonebook::PhonebookEntry;
+use crate::broker::capability::Capabilities;
+use crate::broker::audit::{AuditAction, AuditLog};
+use crate::broker::hooks::{Flow, Hooks};
+use crate::broker::plugin::Plugins;
+use crate::broker::scripts::Scripts;
+use crate::broker::schedule::TimerWheel;
+use crate::broker::message::CONSOLE_SENDER;
+use crate::broker::memory::{MemoryLimits, MemoryMeter, MemoryUsage};
+use crate::broker::routing::{self, Delivery, DeliveryError, RetryPolicy, DEFAULT_INBOX_CAPACITY};
+use crate::broker::metrics::{BrokerCounters, Metrics};
+use crate::broker::auth::SessionAuth;
+use crate::broker::session::TlsListen;
+use crate::broker::remote::RemoteListen;
+use crate::broker::syslog::SyslogTarget;
+use crate::broker::webhook::WebhookSink;
+use crate::broker::process::{self, Process, ProcessInput};
+use crate::broker::shell::ShellPolicy;
+use crate::broker::pager::{Pager, PagerPolicy};
+use crate::broker::threads::Threads;
+use crate::broker::alerts::{AlertRule, AlertRules};
+use crate::broker::mqtt::MqttBridge;
+use crate::broker::relay::ChatRelay;
+use crate::broker::chat::Chat;
+use crate::broker::tail::TailSource;
+use crate::broker::distribute::{self, BridgedMessage, NatsTarget, SubConsoleLink};
+use crate::output::zone::{Arrangement, ZoneSet, ZoneSpec, MAIN_ZONE};
+use crate::output::ansi::AnsiPolicy;
+use crate::output::highlight::{HighlightRule, Highlights};
+use crate::output::fields::FieldColor;
+use crate::output::theme::{ColorDepth, Theme};
+use crate::output::expiry::ExpiryPolicy;
+use crate::output::notify::{Alert, NotifyPolicy};
+use crate::user_input::validate::Validators;
+use crate::user_input::highlighter::Highlighter;
+use crate::output::export::ExportFormat;
+use crate::user_input::builder::ConsoleBuilder;
+use crate::user_input::prompt::{PromptSource, PromptSpec};
+use crate::user_input::input::{EofBehavior, InputOptions, InputRequest};
+use crate::user_input::dialog;
+use crate::user_input::form::{Form, FormAnswers, FormDialog};
+use crate::user_input::keymap::Keymap;
+use crate::user_input::macros::Macros;
+use crate::user_input::config::ConfigError;
+use crate::user_input::vi::EditMode;
 
 //External
 use derive_getters::Getters;
@@ -18,97 +71,351 @@ use tokio::io::stdout;
  * The struct defining the Console Object, which is designed to be a broker for all other modules to communicate with the main thread and output to the console without collisions
  *  ~ The Console struct is designed to be a singleton, and is therefore not clonable.
  *  ~ init() creates the receiver which is not clonable. 
- *  ~ new_sender() creates clones of the sender, which can be passed to other modules.
+ *  ~ new_sender() creates a SenderHandle wrapping a clone of the sender, which can be passed to other modules.
  *  ~ Each Sender must be in the Authorized list and NOT in the BlackListed list to be able to send messages to the console or communicate with the main thread.
- *  ~ Senders and receivers are currently only enabled to handle strings, but this will be updated to handle a more complex data structures in the future.
- *  ~ Modules that need their own Console Broker should call SubConsole to have their output configured to the module workspace via rabbitMQ(WIP).
+ *  ~ Senders and receivers carry ConsoleEvents, module output is wrapped in a Message envelope tagged with the sender id and priority.
+ *  ~ next_output() is the broker step, it enforces the phonebook and rate limits and hands back the next message to render.
+ *  ~ T is the application's own event type, routed by the broker as is (SenderHandle::emit) and taken with next_event(). Console alone is Console<()>.
+ *  ~ Modules that want their output apart call new_subconsole() for a SubConsole with a window of its own, see SubWindow.
  * !  Console `may` be called multiple times if called from a different thread when a failover occurs, see major failover documentation.
  */
 
 #[derive(Debug, Getters)]
-pub struct Console<> {
-    pub tx: Sender<String>,
-    pub rx: Receiver<String>,
+pub struct Console<T: Send + 'static = ()> {
+    pub tx: MailboxSender<T>,
+    pub rx: MailboxReceiver<T>,
     pub stdout: Arc<Mutex<tokio::io::Stdout>>,
-    pub phonebook: HashMap<String, (String, SenderStatus)>,
-    pub Authorized: HashMap<String, Sender<String>>,
-    pub BlackListed: HashMap<String, Sender<String>>
+    pub phonebook: HashMap<String, PhonebookEntry>,
+    pub Authorized: HashMap<String, MailboxSender<T>>,
+    pub BlackListed: HashMap<String, MailboxSender<T>>,
+    id_signer: IdSigner,
+    pub(crate) handles: HashMap<String, Arc<std::sync::Mutex<HandleState>>>,
+    pub(crate) default_overflow: OverflowPolicy,
+    pub(crate) default_capabilities: Capabilities,
+    pub(crate) default_rate_limit: Option<RateLimit>,
+    pub(crate) rate_limits: HashMap<String, TokenBucket>,
+    pub(crate) rate_limited: HashMap<String, u64>,
+    pub(crate) delayed: VecDeque<(tokio::time::Instant, Message)>,
+    pub(crate) timers: TimerWheel,
+    pub(crate) ready: VecDeque<Output>,
+    pub(crate) pinned_by: HashMap<u64, String>,
+    pub(crate) dead_letters: Arc<std::sync::Mutex<DeadLetterQueue>>,
+    pub(crate) inboxes: HashMap<String, Sender<Delivery>>,
+    pub(crate) event_inboxes: HashMap<String, Sender<AppEvent<T>>>,
+    //application events waiting for next_event()
+    pub(crate) app_events: VecDeque<AppEvent<T>>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) counters: BrokerCounters,
+    pub(crate) metrics_publisher: tokio::sync::watch::Sender<Metrics>,
+    pub(crate) metrics_published: tokio::time::Instant,
+    pub(crate) filter_level: PriorityStatus,
+    pub(crate) filter_rules: FilterRules,
+    pub(crate) highlights: Highlights,
+    pub(crate) theme: Theme,
+    pub(crate) color_depth: Option<ColorDepth>,
+    pub(crate) accessible: bool,
+    pub(crate) status_bar: bool,
+    pub(crate) tabs: bool,
+    pub(crate) zones: Vec<ZoneSpec>,
+    //SubConsole windows kept off screen, see SubWindow::Tab
+    pub(crate) windows: Vec<ZoneSpec>,
+    //the ids of this console's SubConsoles with their shared inbox, their lines go over the bridge
+    pub(crate) subconsoles: HashMap<String, Arc<Mutex<Receiver<Delivery>>>>,
+    pub(crate) bridge: Option<(String, tokio::sync::mpsc::UnboundedSender<BridgedMessage>)>,
+    pub(crate) zone_arrangement: Arrangement,
+    pub(crate) split: Option<Split>,
+    //copies of the last line for the split panes, handed out before the broker takes another step
+    pub(crate) split_copies: VecDeque<Message>,
+    pub(crate) ansi_policies: HashMap<String, AnsiPolicy>,
+    pub(crate) default_ansi_policy: AnsiPolicy,
+    pub(crate) dedup: HashMap<String, bool>,
+    pub(crate) default_dedup: bool,
+    pub(crate) repeats: Repeats,
+    pub(crate) threads: Threads,
+    pub(crate) fold: bool,
+    pub(crate) alert_rules: AlertRules,
+    pub(crate) redrawn: HashSet<u64>,
+    pub(crate) audit_log: AuditLog,
+    pub(crate) hooks: Hooks,
+    pub(crate) validators: Validators,
+    pub(crate) highlighter: Option<Arc<dyn Highlighter>>,
+    pub(crate) plugins: Plugins<T>,
+    pub(crate) scripts: Scripts,
+    pub(crate) macros: Macros,
+    //who the administrative calls are made for while a command runs, see record_audit
+    pub(crate) acting: Option<String>,
+    //where that command came from, only ever Terminal for a line from the input zone
+    pub(crate) origin: Option<Origin>,
+    pub(crate) expiry: ExpiryPolicy,
+    pub(crate) notify: NotifyPolicy,
+    //whether the user is watching, for NotifyPolicy
+    pub(crate) paused: bool,
+    pub(crate) last_input: tokio::time::Instant,
+    pub(crate) prompt_publisher: tokio::sync::watch::Sender<PromptSpec>,
+    pub(crate) unread: Arc<std::sync::atomic::AtomicU64>,
+    pub(crate) input_requests: tokio::sync::mpsc::UnboundedSender<InputRequest>,
+    pub(crate) input_receiver: Option<tokio::sync::mpsc::UnboundedReceiver<InputRequest>>,
+    pub(crate) keymap: Keymap,
+    pub(crate) edit_mode: EditMode,
+    pub(crate) mouse: bool,
+    pub(crate) autosuggest: bool,
+    pub(crate) eof: EofBehavior,
+    pub(crate) alternate_screen: bool,
+    pub(crate) display_only: bool,
+    pub(crate) input_only: bool,
+    pub(crate) ingest: bool,
+    pub(crate) output_redirect: Option<tokio::sync::mpsc::UnboundedSender<Output>>,
+    pub(crate) session: Option<std::path::PathBuf>,
+    pub(crate) session_auth: Option<SessionAuth>,
+    pub(crate) session_tls: Option<TlsListen>,
+    pub(crate) mqtt: Option<MqttBridge>,
+    pub(crate) relay: Option<ChatRelay>,
+    pub(crate) chat: Chat,
+    pub(crate) tail: Option<TailSource>,
+    pub(crate) nats: Option<NatsTarget>,
+    pub(crate) grpc: Option<RemoteListen>,
+    pub(crate) http: Option<RemoteListen>,
+    pub(crate) syslog_forward: Option<SyslogTarget>,
+    pub(crate) syslog_listen: Option<std::net::SocketAddr>,
+    pub(crate) journal: bool,
+    pub(crate) otel: Option<String>,
+    pub(crate) webhooks: Vec<WebhookSink>,
+    pub(crate) processes: Vec<Process>,
+    pub(crate) process_focus: Option<String>,
+    pub(crate) shell: ShellPolicy,
+    pub(crate) pager_policy: PagerPolicy,
+    pub(crate) paging: Option<Pager>,
+    pub(crate) line_sinks: Vec<tokio::sync::mpsc::UnboundedSender<RenderedLine>>,
+    pub(crate) detached: bool,
+    pub(crate) memory_limits: MemoryLimits,
+    pub(crate) memory: Arc<MemoryMeter>,
+    pub(crate) recording: Option<std::path::PathBuf>,
+    pub(crate) phonebook_file: Option<std::path::PathBuf>,
 }
 
 /**
  * Default impl for the Console struct mirrors the init() function with one exception, it returns the Console struct instead of the Sender, Receiver tuple.
  */
-impl Default for Console {
+impl<T: Send + 'static> Default for Console<T> {
     fn default() -> Self {
-        //call init to create the default impl
-        let (tx, rx): (Sender<String>, Receiver<String>) = channel(100);
+        let (tx, rx): (MailboxSender<T>, MailboxReceiver<T>) = mailbox::channel(100);
         let stdout = Arc::new(Mutex::new(stdout()));
-        let Authorized: HashMap<String, Sender<String>> = HashMap::new();
-        let phonebook: HashMap<String, (String, SenderStatus)> = HashMap::new();
-        let BlackListed: HashMap<String, Sender<String>> = HashMap::new();
-        let console = Console {
+        let Authorized: HashMap<String, MailboxSender<T>> = HashMap::new();
+        let phonebook: HashMap<String, PhonebookEntry> = HashMap::new();
+        let BlackListed: HashMap<String, MailboxSender<T>> = HashMap::new();
+        let id_signer = IdSigner::new();
+        let (input_requests, input_receiver) = tokio::sync::mpsc::unbounded_channel();
+        Console {
             tx,
             rx,
             stdout,
             phonebook,
             Authorized,
             BlackListed,
-        };
-        console
+            id_signer,
+            handles: HashMap::new(),
+            default_overflow: OverflowPolicy::Block,
+            default_capabilities: Capabilities::default(),
+            default_rate_limit: None,
+            rate_limits: HashMap::new(),
+            rate_limited: HashMap::new(),
+            delayed: VecDeque::new(),
+            timers: TimerWheel::default(),
+            ready: VecDeque::new(),
+            pinned_by: HashMap::new(),
+            dead_letters: Arc::new(std::sync::Mutex::new(DeadLetterQueue::default())),
+            inboxes: HashMap::new(),
+            event_inboxes: HashMap::new(),
+            app_events: VecDeque::new(),
+            retry_policy: RetryPolicy::default(),
+            counters: BrokerCounters::default(),
+            metrics_publisher: tokio::sync::watch::channel(Metrics::default()).0,
+            metrics_published: tokio::time::Instant::now(),
+            filter_level: PriorityStatus::Ignore,
+            filter_rules: FilterRules::default(),
+            highlights: Highlights::default(),
+            theme: Theme::default(),
+            color_depth: None,
+            accessible: false,
+            status_bar: false,
+            tabs: false,
+            zones: vec![ZoneSpec::new(MAIN_ZONE.to_string())],
+            windows: Vec::new(),
+            subconsoles: HashMap::new(),
+            bridge: None,
+            zone_arrangement: Arrangement::Stacked,
+            split: None,
+            split_copies: VecDeque::new(),
+            ansi_policies: HashMap::new(),
+            default_ansi_policy: AnsiPolicy::default(),
+            dedup: HashMap::new(),
+            default_dedup: true,
+            repeats: Repeats::default(),
+            threads: Threads::default(),
+            fold: true,
+            alert_rules: AlertRules::default(),
+            redrawn: HashSet::new(),
+            audit_log: AuditLog::default(),
+            hooks: Hooks::default(),
+            validators: Validators::default(),
+            highlighter: None,
+            plugins: Plugins::default(),
+            scripts: Scripts::default(),
+            macros: Macros::default(),
+            acting: None,
+            origin: None,
+            expiry: ExpiryPolicy::default(),
+            notify: NotifyPolicy::default(),
+            paused: false,
+            last_input: tokio::time::Instant::now(),
+            prompt_publisher: tokio::sync::watch::channel(PromptSpec::default()).0,
+            unread: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            input_requests,
+            input_receiver: Some(input_receiver),
+            keymap: Keymap::default(),
+            edit_mode: EditMode::Emacs,
+            mouse: true,
+            autosuggest: true,
+            eof: EofBehavior::Shutdown,
+            alternate_screen: false,
+            display_only: false,
+            input_only: false,
+            ingest: false,
+            output_redirect: None,
+            session: None,
+            session_auth: None,
+            session_tls: None,
+            mqtt: None,
+            relay: None,
+            chat: Chat::default(),
+            tail: None,
+            nats: None,
+            grpc: None,
+            http: None,
+            syslog_forward: None,
+            syslog_listen: None,
+            journal: false,
+            otel: None,
+            webhooks: Vec::new(),
+            processes: Vec::new(),
+            process_focus: None,
+            shell: ShellPolicy::default(),
+            pager_policy: PagerPolicy::default(),
+            paging: None,
+            line_sinks: Vec::new(),
+            detached: false,
+            memory_limits: MemoryLimits::default(),
+            memory: Arc::new(MemoryMeter::default()),
+            recording: None,
+            phonebook_file: None,
+        }
     }
 }
 
 impl Console<> {
     /**
-     * Start the Console Broker and return a Sender<String> to the caller.
+     * Start building a Console, see ConsoleBuilder
      */
-    pub fn init() -> (Sender<String>, Receiver<String>) {
-        let (tx, rx): (Sender<String>, Receiver<String>) = channel(100);
-        let stdout = Arc::new(Mutex::new(stdout()));
-        let Authorized: HashMap<String, Sender<String>> = HashMap::new();
-        let phonebook: HashMap<String, (String, SenderStatus)> = HashMap::new();
-        let BlackListed: HashMap<String, Sender<String>> = HashMap::new();
-        let console = Console {
-            tx,
-            rx,
-            stdout,
-            phonebook,
-            Authorized,
-            BlackListed,
-        };
+    pub fn builder() -> ConsoleBuilder {
+        ConsoleBuilder::new()
+    }
+
+    /**
+     * Start the Console Broker and return a MailboxSender to the caller.
+     */
+    pub fn init() -> (MailboxSender, MailboxReceiver) {
+        let console: Console = Console::default();
         (console.tx.clone(), console.rx)
     }
+}
+
+impl<T: Send + 'static> Console<T> {
+    /**
+     * Start building a Console carrying application events of type T, see ConsoleBuilder::with_events
+     */
+    pub fn event_builder() -> ConsoleBuilder<T> {
+        ConsoleBuilder::with_events()
+    }
 
     /**
-     * Create a new Sender, add it to the Authorized list and return it.
+     * Create a new Sender, add it to the Authorized list and return its handle.
+     * Only a name new to the phonebook is authorized. One it already knows, asked again or loaded from a saved phonebook,
+     * keeps its entry and status: a blacklisted or NotAuthorized sender stays that way until reauthorize() is called.
      */
-    pub fn new_sender(&mut self, name:String) -> Sender<String> {
+    pub fn new_sender(&mut self, name:String) -> SenderHandle<T> {
         let sender = self.tx.clone();
-        let signed_name = Console::generate_id(name.clone());
-        self.Authorized.insert(signed_name.clone(), sender.clone());
-        self.phonebook.insert(signed_name, (name, SenderStatus::Authorized));
-        sender
+        let signed_name = self.generate_id(name.clone());
+        let status = match self.phonebook.get(&signed_name) {
+            Some(entry) => entry.status.clone(),
+            None => {
+                self.Authorized.insert(signed_name.clone(), sender.clone());
+                self.phonebook.insert(signed_name.clone(), PhonebookEntry::new(name.clone(), SenderStatus::Authorized));
+                self.record_audit(AuditAction::Authorized, name.clone());
+                self.persist_phonebook();
+                SenderStatus::Authorized
+            }
+        };
+        //every handle of a name shares one state, so a change to the sender reaches the handles given out before too
+        let shared_state = match self.handles.get(&signed_name) {
+            Some(shared_state) => shared_state.clone(),
+            None => {
+                let state = HandleState { status, overflow: self.default_overflow.clone(), capabilities: self.default_capabilities };
+                let shared_state = Arc::new(std::sync::Mutex::new(state));
+                self.handles.insert(signed_name.clone(), shared_state.clone());
+                shared_state
+            }
+        };
+        SenderHandle::new(signed_name, name, sender, shared_state, self.dead_letters.clone())
+    }
+
+    /**
+     * Create a SubConsole for a module, a sender of its own with its output shown as window says.
+     * The window is added to the zones right away when they are already drawn, a Zone one after the zones there are.
+     * A name asked for again shares the inbox of its first SubConsole. Fails when the name has an inbox of open_inbox().
+     */
+    pub fn new_subconsole(&mut self, name: String, window: SubWindow) -> Result<SubConsole<T>, String> {
+        let id = self.generate_id(name.clone());
+        let inbox = match self.subconsoles.get(&id) {
+            Some(inbox) => inbox.clone(),
+            None => Arc::new(Mutex::new(self.open_inbox(name.clone())?)),
+        };
+        let handle = self.new_sender(name.clone());
+        self.subconsoles.insert(id, inbox.clone());
+        let spec = ZoneSpec::new(name.clone()).with_sender(name);
+        match window {
+            SubWindow::Shared => {},
+            SubWindow::Zone => {
+                self.add_zone(spec.clone());
+                self.ready.push_back(Output::Window { spec, tab: false });
+            },
+            SubWindow::Tab => {
+                match self.windows.iter().position(|window| window.name == spec.name) {
+                    Some(index) => self.windows[index] = spec.clone(),
+                    None => self.windows.push(spec.clone()),
+                }
+                self.ready.push_back(Output::Window { spec, tab: true });
+            },
+        }
+        Ok(SubConsole { handle, window, inbox })
     }
 
     /**
-     * Added security for the identifiers
+     * Added security for the identifiers, ids are HMAC signed with the key generated when this Console was created.
+     * See IdSigner for the id format.
      */
-    fn generate_id(identifier: String) -> String {
-        let mut hasher = DefaultHasher::new();
-        identifier.hash(&mut hasher);
-        let signed_identifier = hasher.finish();
-        signed_identifier.to_string()
+    pub(crate) fn generate_id(&self, identifier: String) -> String {
+        self.id_signer.sign(&identifier)
     }
 
     /**
      * Get the Plaintext name fom the generated id
      */
-    fn get_plaintext_name(&self, search_name: String) -> String {
+    pub(crate) fn get_plaintext_name(&self, search_name: String) -> String {
         
         let name = self.phonebook.get(&search_name);
         match name {
             Some(n) => {
-                n.0.to_string()
+                n.name.to_string()
             },
             None => {
                 "Name not found".to_string()
@@ -119,11 +426,11 @@ impl Console<> {
     /**
      * Get the SenderStatus from the generated id
      */
-    fn get_sender_status(&self, search_name: String) -> SenderStatus {
+    pub(crate) fn get_sender_status(&self, search_name: String) -> SenderStatus {
         let status = self.phonebook.get(&search_name);
         match status {
             Some(s) => {
-                s.1.clone()
+                s.status.clone()
             },
             None => {
                 SenderStatus::NotInPhonebook
@@ -135,11 +442,11 @@ impl Console<> {
      * Get the SenderStatus from the plaintext name
      */
     fn get_sender_status_by_name(&self, search_name: String) -> SenderStatus {
-        let id = Console::generate_id(search_name);
+        let id = self.generate_id(search_name);
         let status = self.phonebook.get(&id);
         match status {
             Some(s) => {
-                s.1.clone()
+                s.status.clone()
             },
             None => {
                 SenderStatus::NotInPhonebook
@@ -148,128 +455,1288 @@ impl Console<> {
     }
 
     /**
-     * Update the Senderstatus by either identifier or plaintext name
+     * Update the Senderstatus by either identifier or plaintext name, false (and a note in the output zone) when it is neither
      */
-    fn change_sender_status(&mut self, search_name: String, new_status: SenderStatus) {
-        let id = Console::generate_id(search_name.clone());
-        let status = self.phonebook.get(&id);
-        match status {
-            Some(s) => {
-                self.phonebook.insert(id, (s.0.clone(), new_status));
-            },
-            None => {
-                //check the phonebook for the search_name directly
-                let status = self.phonebook.get(&search_name);
-                match status {
-                    Some(s) => {
-                        self.phonebook.insert(id, (s.0.clone(), new_status));
-                    },
-                    None => {
-                        println!("Name not found using both plaintext and id");
-                    }
-                }
-                println!("Name not found using id");
-            }
+    fn change_sender_status(&mut self, search_name: String, new_status: SenderStatus) -> bool {
+        let id = self.generate_id(search_name.clone());
+        if self.phonebook.contains_key(&id) {
+            self.set_status(&id, new_status);
+        } else if self.phonebook.contains_key(&search_name) {
+            //the search_name was already an id
+            self.set_status(&search_name, new_status);
+        } else {
+            self.emit_event(format!("{} is not in the phonebook by name or id", search_name));
+            return false;
+        }
+        true
+    }
+
+    /**
+     * Update the phonebook entry and the shared handle status for an id in one place
+     */
+    fn set_status(&mut self, id: &str, new_status: SenderStatus) {
+        if let Some(entry) = self.phonebook.get_mut(id) {
+            entry.status = new_status.clone();
         }
+        if let Some(handle_state) = self.handles.get(id) {
+            handle_state.lock().unwrap().status = new_status;
+        }
+    }
+
+    /**
+     * Add an entry to the audit log, acted by whoever runs the current command or else the program owning the Console
+     */
+    pub(crate) fn record_audit(&mut self, action: AuditAction, subject: String) {
+        let actor = self.acting.clone().unwrap_or_else(|| CONSOLE_SENDER.to_string());
+        self.audit_log.record(&actor, action, subject);
+    }
+
+    /**
+     * Append every audit entry from now on to a file as JSON lines, None stops. See broker::audit::AuditLog
+     */
+    pub fn set_audit_file(&mut self, path: Option<std::path::PathBuf>) -> std::io::Result<()> {
+        self.audit_log.set_file(path.as_deref())
+    }
+
+    /**
+     * Let the output zone know the membership of a sender changed, or why it could not be
+     */
+    pub(crate) fn emit_event(&self, event: String) {
+        let _ = self.tx.try_send(ConsoleEvent::Message(Message::from_console(format!("[console] {}", event))));
     }
 
-        /**
+    /**
      * Adds a sender to the blacklist
      */
     pub fn add_to_blacklist(&mut self, identifier: String) {
-        let id = Console::generate_id(identifier);
+        let id = self.generate_id(identifier.clone());
         let sender = self.Authorized.remove(&id);
         match sender {
             Some(s) => {
-                self.BlackListed.insert(id, s);
+                self.BlackListed.insert(id.clone(), s);
+                self.set_status(&id, SenderStatus::BlackListed);
+                self.record_audit(AuditAction::Blacklisted, identifier.clone());
+                self.persist_phonebook();
+                self.emit_event(format!("{} was blacklisted", identifier));
             },
             None => {
-                println!("Sender not found");
+                self.emit_event(format!("{} is not an authorized sender", identifier));
             }
         }
     }
 
     /**
-     * Get just the names of everyone on the blacklist
+     * Removes a sender from the blacklist, the sender is left NotAuthorized until reauthorize() is called.
+     * Returns false for a sender that isn't on it, the output zone says so either way
      */
-    pub fn get_blacklist_names(&self) -> Vec<String> {
-        let mut names: Vec<String> = Vec::new();
-        for (name, _) in self.BlackListed.iter() {
-            names.push(name.to_string());
+    pub fn remove_from_blacklist(&mut self, identifier: String) -> bool {
+        let id = self.generate_id(identifier.clone());
+        let sender = self.BlackListed.remove(&id);
+        match sender {
+            Some(_) => {
+                self.set_status(&id, SenderStatus::NotAuthorized);
+                self.record_audit(AuditAction::Unblacklisted, identifier.clone());
+                self.persist_phonebook();
+                self.emit_event(format!("{} was removed from the blacklist", identifier));
+                true
+            },
+            None => {
+                self.emit_event(format!("{} is not on the blacklist", identifier));
+                false
+            }
         }
-        names
     }
 
     /**
-     * show all names in the blacklist in plaintext
+     * Puts a known sender back on the Authorized list, from either the blacklist or NotAuthorized.
+     * Returns false for a sender the phonebook doesn't have, the output zone says so either way
      */
-    pub fn show_blacklist(&self) {
-        println!("Blacklisted Names: ");
-        for (name, _) in self.BlackListed.iter() {
-            println!("{}", name);
+    pub fn reauthorize(&mut self, identifier: String) -> bool {
+        let id = self.generate_id(identifier.clone());
+        if !self.phonebook.contains_key(&id) {
+            self.emit_event(format!("{} is not in the phonebook", identifier));
+            return false;
         }
+        self.BlackListed.remove(&id);
+        self.Authorized.insert(id.clone(), self.tx.clone());
+        self.set_status(&id, SenderStatus::Authorized);
+        self.record_audit(AuditAction::Reauthorized, identifier.clone());
+        self.persist_phonebook();
+        self.emit_event(format!("{} was reauthorized", identifier));
+        true
     }
 
     /**
-     * Get just the names of everyone on the authorized list
+     * Drops a sender entirely, every clone of its handle stops working and cannot be reauthorized.
+     * Returns false for a sender the phonebook doesn't have, the output zone says so either way
      */
-    pub fn get_authorized_names(&self) -> Vec<String> {
-        let mut names: Vec<String> = Vec::new();
-        for (name, _) in self.Authorized.iter() {
-            names.push(name.to_string());
+    pub fn revoke_sender(&mut self, identifier: String) -> bool {
+        let id = self.generate_id(identifier.clone());
+        if self.phonebook.remove(&id).is_none() {
+            self.emit_event(format!("{} is not in the phonebook", identifier));
+            return false;
         }
-        names
+        self.Authorized.remove(&id);
+        self.BlackListed.remove(&id);
+        if let Some(handle_state) = self.handles.remove(&id) {
+            handle_state.lock().unwrap().status = SenderStatus::NotInPhonebook;
+        }
+        self.record_audit(AuditAction::Revoked, identifier.clone());
+        self.persist_phonebook();
+        self.emit_event(format!("{} was revoked", identifier));
+        true
     }
+
     /**
-     * show all names in the authorized list in plaintext
+     * Set the token bucket limit applied to one sender, replaces any bucket it already had
      */
-    pub fn show_authorized(&self) {
-        println!("Authorized Names: ");
-        for (name, _) in self.Authorized.iter() {
-            println!("{}", name);
+    pub fn set_rate_limit(&mut self, identifier: String, limit: RateLimit) {
+        let id = self.generate_id(identifier);
+        self.rate_limits.insert(id, TokenBucket::new(limit));
+    }
+
+    /**
+     * Remove the limit for one sender, it falls back to the default limit (if any)
+     */
+    pub fn clear_rate_limit(&mut self, identifier: String) {
+        let id = self.generate_id(identifier);
+        self.rate_limits.remove(&id);
+    }
+
+    /**
+     * Set the limit given to every sender that has no limit of its own
+     */
+    pub fn set_default_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.default_rate_limit = limit;
+    }
+
+    /**
+     * Choose what happens to ANSI escape sequences in one sender's messages
+     */
+    pub fn set_ansi_policy(&mut self, identifier: String, policy: AnsiPolicy) {
+        let id = self.generate_id(identifier);
+        self.ansi_policies.insert(id, policy);
+    }
+
+    /**
+     * Collapse one sender's repeated lines into a single "text (×N)" line or not, see dedup::Repeats
+     */
+    pub fn set_dedup(&mut self, identifier: String, enabled: bool) {
+        let id = self.generate_id(identifier);
+        self.dedup.insert(id, enabled);
+    }
+
+    /**
+     * Collapse repeated lines for every sender without a setting of its own, on unless changed
+     */
+    pub fn set_default_dedup(&mut self, enabled: bool) {
+        self.default_dedup = enabled;
+    }
+
+    /**
+     * Fold a module's payload of several lines (eg. a panic or a stack trace) to its first line until it is expanded with /expand
+     * or Alt+T, so one noisy error doesn't take the whole zone. On unless changed, see threads::Threads::fold
+     */
+    pub fn set_fold(&mut self, enabled: bool) {
+        self.fold = enabled;
+    }
+
+    /**
+     * Set the ANSI policy for every sender without one of its own, Sanitize unless changed
+     */
+    pub fn set_default_ansi_policy(&mut self, policy: AnsiPolicy) {
+        self.default_ansi_policy = policy;
+    }
+
+    /**
+     * Choose what a sender's handle does when the channel is full, applies to handles already given out
+     */
+    pub fn set_overflow_policy(&mut self, identifier: String, policy: OverflowPolicy) {
+        let id = self.generate_id(identifier.clone());
+        match self.handles.get(&id) {
+            Some(handle_state) => {
+                handle_state.lock().unwrap().overflow = policy;
+            },
+            None => {
+                self.emit_event(format!("{} has no handle", identifier));
+            }
         }
     }
-}
 
-#[derive(Debug, Clone)]
-pub enum SenderStatus {
-    Authorized,
-    BlackListed,
-    NotAuthorized,
-    NotInPhonebook,
-}
+    /**
+     * Overflow policy given to senders created after this call
+     */
+    pub fn set_default_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.default_overflow = policy;
+    }
 
-#[derive(Debug, Clone)]
-pub enum PriorityStatus {
-    Urgent,
-    Critical,
-    Notice,
-    Warning,
-    Exception,
-    Delay,
-    Verbose, 
-    Normal, 
-    Ignore, 
-    Informational
-}
+    /**
+     * Open an inbox so other modules can route messages to this name. A name has one inbox at a time, opening another fails
+     * until the receiver of the first one was dropped
+     */
+    pub fn open_inbox(&mut self, name: String) -> Result<Receiver<Delivery>, String> {
+        let id = self.generate_id(name.clone());
+        if self.inboxes.get(&id).is_some_and(|inbox| !inbox.is_closed()) {
+            return Err(format!("{} already has an inbox", name));
+        }
+        let (tx, rx) = channel(DEFAULT_INBOX_CAPACITY);
+        self.inboxes.insert(id, tx);
+        Ok(rx)
+    }
 
-/******************************************************************************************************************************************************************************/
-/**
- * ! WIP
- * This Struct is to essentially Mirror what the Console struct does, but for other modules to use.
- * Instead of the shared output being used to communicate with the main thread, it will be used to communicate with the module's thread.
- * The idea is to have this set up to implement RabbitMQ for easier system distribution.
- * Each module that calls a subConsole should have a workspace loop, that is to say, its using the established async runtime to run its own loop independent of other processes.
- * This really shouldnt be used for synchronous modules as it could block and will likely cause a deadlock.
- */
-#[derive(Debug, Getters)]
-struct SubConsole<> {
-    tx: Sender<String>,
-    rx: Receiver<String>,
-    // stdout: Arc<Mutex<tokio::io::Stdout>>, //Rabbit MQ will handle this
-    phonebook: HashMap<String, (String, SenderStatus)>,
-    Authorized: HashMap<String, Sender<String>>,
-    BlackListed: HashMap<String, Sender<String>>
+    /**
+     * Open an event inbox so modules can emit application events to this name (SenderHandle::emit_to). Like open_inbox a name has
+     * one at a time, opening another fails until the receiver of the first one was dropped.
+     * Events that don't fit are dropped with a console note, the inbox holds DEFAULT_INBOX_CAPACITY of them
+     */
+    pub fn open_event_inbox(&mut self, name: String) -> Result<Receiver<AppEvent<T>>, String> {
+        let id = self.generate_id(name.clone());
+        if self.event_inboxes.get(&id).is_some_and(|inbox| !inbox.is_closed()) {
+            return Err(format!("{} already has an event inbox", name));
+        }
+        let (tx, rx) = channel(DEFAULT_INBOX_CAPACITY);
+        self.event_inboxes.insert(id, tx);
+        Ok(rx)
+    }
+
+    /**
+     * Send a message to one module alone, eg. console.send_to("worker", Message::from_console("reload".to_string())).
+     * It goes to the module's SubConsole (see SubConsole::recv), not to the output zone or any other module.
+     *  ~ The target is looked up in the phonebook by name. One it doesn't know, or that has no SubConsole, is UnknownTarget.
+     *  ~ A Stale target (see Console::require_heartbeat) is TargetStale and a blacklisted one TargetBlacklisted,
+     *    nothing is sent to a module that may be gone or was shut out.
+     *  ~ Delivery is best effort like SenderHandle::route, a full or closed SubConsole puts the message in the dead-letter queue.
+     */
+    pub fn send_to(&mut self, target: &str, message: Message) -> Result<(), DeliveryError> {
+        let id = self.resolve_target(target)?;
+        if !self.subconsoles.contains_key(&id) {
+            return Err(DeliveryError::UnknownTarget);
+        }
+        let inbox = self.inboxes.get(&id).ok_or(DeliveryError::UnknownTarget)?;
+        routing::deliver_once(inbox, message, &self.dead_letters)
+    }
+
+    /**
+     * The signed id of a module the phonebook knows by name and that may be sent to
+     */
+    pub(crate) fn resolve_target(&self, target: &str) -> Result<String, DeliveryError> {
+        let id = self.generate_id(target.to_string());
+        match self.phonebook.get(&id).map(|entry| &entry.status) {
+            Some(SenderStatus::Authorized) => Ok(id),
+            Some(SenderStatus::Stale) => Err(DeliveryError::TargetStale),
+            Some(SenderStatus::BlackListed) => Err(DeliveryError::TargetBlacklisted),
+            _ => Err(DeliveryError::UnknownTarget),
+        }
+    }
+
+    /**
+     * Set the retry and backoff used for messages routed with an ack request
+     */
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /**
+     * Snapshot of the broker counters, queue depth and render times
+     */
+    pub fn metrics(&self) -> Metrics {
+        let channel = self.rx.stats();
+        let named = |counts: &HashMap<String, u64>| -> HashMap<String, u64> {
+            counts.iter().map(|(id, count)| (self.display_name(id), *count)).collect()
+        };
+        let avg = if self.counters.rendered == 0 { 0 } else { (self.counters.render_total.as_micros() / self.counters.rendered as u128) as u64 };
+        Metrics {
+            received: self.counters.received,
+            rendered: self.counters.rendered,
+            dropped: self.dead_letters.lock().unwrap().total(),
+            per_sender: named(&self.counters.per_sender),
+            rate_limited: named(&self.rate_limited),
+            delayed: self.delayed.len(),
+            channel_depth: channel.depth,
+            channel_capacity: channel.capacity,
+            overflow_depth: channel.overflow_depth,
+            dropped_oldest: channel.dropped_oldest,
+            dropped_newest: channel.dropped_newest,
+            spilled: channel.spilled,
+            render_avg_us: avg,
+            render_max_us: self.counters.render_max.as_micros() as u64,
+            render_last_us: self.counters.render_last.as_micros() as u64,
+        }
+    }
+
+    /**
+     * Hide module messages less severe than the given level, Ignore shows everything
+     */
+    pub fn set_filter_level(&mut self, level: PriorityStatus) {
+        self.filter_level = level;
+    }
+
+    /**
+     * Add a rule at the end of the filter rules, see FilterRules for how they decide
+     */
+    pub fn add_filter(&mut self, rule: FilterRule) {
+        self.record_audit(AuditAction::FilterAdded, rule.to_string());
+        self.filter_rules.add(rule);
+    }
+
+    /**
+     * Remove the filter rule at index (0 based), returns it if there was one
+     */
+    pub fn remove_filter(&mut self, index: usize) -> Option<FilterRule> {
+        let removed = self.filter_rules.remove(index);
+        if let Some(rule) = &removed {
+            self.record_audit(AuditAction::FilterRemoved, rule.to_string());
+        }
+        removed
+    }
+
+    pub fn clear_filters(&mut self) {
+        self.record_audit(AuditAction::FiltersCleared, format!("{} rules", self.filter_rules.rules().len()));
+        self.filter_rules.clear();
+    }
+
+    /**
+     * Add an alert rule, see AlertRules for when they fire
+     */
+    pub fn add_alert_rule(&mut self, rule: AlertRule) {
+        self.record_audit(AuditAction::AlertAdded, rule.to_string());
+        self.alert_rules.add(rule);
+    }
+
+    /**
+     * Remove the alert rule at index (0 based), returns it if there was one
+     */
+    pub fn remove_alert_rule(&mut self, index: usize) -> Option<AlertRule> {
+        let removed = self.alert_rules.remove(index);
+        if let Some(rule) = &removed {
+            self.record_audit(AuditAction::AlertRemoved, rule.to_string());
+        }
+        removed
+    }
+
+    pub fn clear_alert_rules(&mut self) {
+        self.record_audit(AuditAction::AlertsCleared, format!("{} rules", self.alert_rules.rules().len()));
+        self.alert_rules.clear();
+    }
+
+    /**
+     * Load the recorded macros from a file and save every change to /macro there, None keeps them for this session only
+     */
+    pub fn set_macros_file(&mut self, path: Option<std::path::PathBuf>) -> Result<usize, ConfigError> {
+        self.macros.set_file(path)
+    }
+
+    /**
+     * Add a hook that sees every message about to be rendered, it may change it or return Flow::Veto to drop it, eg.
+     *
+     *     console.on_message(|message| if message.payload.contains("password") { Flow::Veto } else { Flow::Continue });
+     *
+     * See broker::hooks::Hooks for the order hooks run in.
+     */
+    pub fn on_message(&mut self, hook: impl FnMut(&mut Message) -> Flow + Send + 'static) {
+        self.hooks.add_message_hook(Box::new(hook));
+    }
+
+    /**
+     * Add a hook that sees every line typed into the input zone before it is dispatched, it may rewrite it or veto it
+     */
+    pub fn on_input(&mut self, hook: impl FnMut(&mut String) -> Flow + Send + 'static) {
+        self.hooks.add_input_hook(Box::new(hook));
+    }
+
+    /**
+     * Add a check of the input line, a line it rejects stays in the editor with the error under it (see user_input::validate).
+     * Takes effect when the input zone is started
+     */
+    pub fn add_validator(&mut self, validator: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static) {
+        self.validators.add(std::sync::Arc::new(validator));
+    }
+
+    /**
+     * Style the input line as it is typed, eg. with user_input::highlighter::CommandHighlighter. None draws it plain.
+     * Takes effect when the input zone is started
+     */
+    pub fn set_highlighter(&mut self, highlighter: Option<Arc<dyn Highlighter>>) {
+        self.highlighter = highlighter;
+    }
+
+    /**
+     * Add a highlight rule, the output zones redraw with it right away, scrollback included
+     */
+    pub fn add_highlight(&mut self, rule: HighlightRule) {
+        self.highlights.add(rule);
+        self.ready.push_back(Output::Highlights(self.highlights.fitted(&self.screen_depth())));
+    }
+
+    /**
+     * Remove the highlight rule at index (0 based), returns it if there was one
+     */
+    pub fn remove_highlight(&mut self, index: usize) -> Option<HighlightRule> {
+        let removed = self.highlights.remove(index);
+        self.ready.push_back(Output::Highlights(self.highlights.fitted(&self.screen_depth())));
+        removed
+    }
+
+    pub fn clear_highlights(&mut self) {
+        self.highlights.clear();
+        self.ready.push_back(Output::Highlights(self.highlights.fitted(&self.screen_depth())));
+    }
+
+    /**
+     * Draw the key=value fields of messages after their payload or not (/fields on|off), on unless changed, see fields::FieldView
+     */
+    pub fn show_fields(&mut self, shown: bool) {
+        self.highlights.fields.shown = shown;
+        self.ready.push_back(Output::Highlights(self.highlights.fitted(&self.screen_depth())));
+    }
+
+    /**
+     * Draw the fields whose key matches in a style of their own, the rule added first wins
+     */
+    pub fn add_field_color(&mut self, color: FieldColor) {
+        self.highlights.fields.colors.push(color);
+        self.ready.push_back(Output::Highlights(self.highlights.fitted(&self.screen_depth())));
+    }
+
+    /**
+     * Change the prompt in front of the input line, the input zone redraws it right away
+     */
+    pub fn set_prompt(&mut self, spec: PromptSpec) {
+        let _ = self.prompt_publisher.send(spec);
+    }
+
+    /**
+     * Handle the input zone draws the prompt from, it follows set_prompt and the unread count
+     */
+    pub fn prompt_source(&self) -> PromptSource {
+        PromptSource { spec: self.prompt_publisher.subscribe(), unread: self.unread.clone() }
+    }
+
+    /**
+     * Ask the user for a value without echoing it, eg. a password. The input zone shows prompt and draws * for every character.
+     * The value never goes through the broker or into the input history.
+     * The receiver errors if the user cancels with Esc or Ctrl+C.
+     */
+    pub fn read_secret(&self, prompt: String) -> tokio::sync::oneshot::Receiver<String> {
+        let (reply, answer) = tokio::sync::oneshot::channel();
+        let _ = self.input_requests.send(InputRequest::Secret { prompt, mask: Some('*'), reply });
+        answer
+    }
+
+    /**
+     * Ask the user a yes or no question, eg. before a dangerous admin command. The question takes over the input zone
+     * until it is answered, keys typed meanwhile are held and go into the input line after it, nothing is dropped.
+     * Enter takes the default, yes when the prompt offers [Y/n] and no otherwise. Esc and Ctrl+C answer no.
+     *
+     *     if console.confirm("Really shut down? [y/N]").await.unwrap_or(false) { ... }
+     */
+    pub fn confirm(&self, prompt: impl Into<String>) -> tokio::sync::oneshot::Receiver<bool> {
+        let prompt = prompt.into();
+        let (reply, answer) = tokio::sync::oneshot::channel();
+        let default = dialog::default_answer(&prompt);
+        let _ = self.input_requests.send(InputRequest::Confirm { prompt, default, reply });
+        answer
+    }
+
+    /**
+     * Let the user pick one of items from a list drawn above the prompt, filtered by typing and moved through with the arrows,
+     * see user_input::dialog::SelectDialog. Resolves to the chosen item, None if the user cancels with Esc or Ctrl+C.
+     *
+     *     let region = console.select("Choose region", vec!["eu-west", "us-east"]).await;
+     */
+    pub fn select<I: std::fmt::Display + Send + 'static>(&self, title: impl Into<String>, items: Vec<I>) -> impl std::future::Future<Output = Option<I>> {
+        let (reply, answer) = tokio::sync::oneshot::channel();
+        let labels = items.iter().map(|item| item.to_string()).collect();
+        let _ = self.input_requests.send(InputRequest::Select { title: title.into(), items: labels, reply });
+        async move {
+            let index = answer.await.ok()?;
+            items.into_iter().nth(index)
+        }
+    }
+
+    /**
+     * Ask the fields of a form one at a time in the input zone, eg. for a setup wizard, see user_input::form.
+     * The receiver gets the answers by field name once the last field is submitted, it errors if the user cancels with Esc or Ctrl+C.
+     */
+    pub fn form(&self, form: Form) -> tokio::sync::oneshot::Receiver<FormAnswers> {
+        let (reply, answer) = tokio::sync::oneshot::channel();
+        let dialog = Box::new(FormDialog::new(form, reply));
+        match dialog.form.is_empty() {
+            true => dialog.finish(),
+            false => {
+                let _ = self.input_requests.send(InputRequest::Form(dialog));
+            },
+        }
+        answer
+    }
+
+    /**
+     * The receiving end of read_secret() and friends, taken once by whatever runs the input zone
+     */
+    pub fn take_input_requests(&mut self) -> Option<tokio::sync::mpsc::UnboundedReceiver<InputRequest>> {
+        self.input_receiver.take()
+    }
+
+    /**
+     * Replace the keys of the input line, see Keymap for the defaults. Takes effect when the input zone is started
+     */
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /**
+     * Switch the input line between emacs (the default) and vi editing, also at runtime and with /mode
+     */
+    pub fn set_edit_mode(&mut self, mode: EditMode) {
+        self.edit_mode = mode.clone();
+        let _ = self.input_requests.send(InputRequest::EditMode(mode));
+    }
+
+    /**
+     * Capture the mouse so the wheel scrolls the output zones, a click focuses one and a drag selects text for /copy.
+     * On by default. With it off the terminal keeps its own selection and scrolling. Takes effect when the input zone is started
+     */
+    pub fn enable_mouse(&mut self, enabled: bool) {
+        self.mouse = enabled;
+    }
+
+    /**
+     * Suggest the newest history entry that starts with the line being typed, drawn dim after the cursor and taken with Right or End.
+     * On by default. Takes effect when the input zone is started
+     */
+    pub fn enable_autosuggest(&mut self, enabled: bool) {
+        self.autosuggest = enabled;
+    }
+
+    /**
+     * What the end of stdin does when input is read line by line (a pipe, a file or a dumb terminal), see EofBehavior.
+     * Shutdown by default. Takes effect when the input zone is started
+     */
+    pub fn set_eof_behavior(&mut self, eof: EofBehavior) {
+        self.eof = eof;
+    }
+
+    /**
+     * The order the broker takes queued events in, Priority (the default) lets higher priority messages overtake a backlog
+     */
+    pub fn set_scheduling(&mut self, scheduling: Scheduling) {
+        self.tx.set_scheduling(scheduling);
+    }
+
+    /**
+     * Run the zones in the terminal's alternate screen, like less or vim do, so the shell's screen and scrollback
+     * are left as they were and come back when the console exits. Off by default. Takes effect when the console is started
+     */
+    pub fn enable_alternate_screen(&mut self, enabled: bool) {
+        self.alternate_screen = enabled;
+    }
+
+    /**
+     * Run without an input zone, as the display of a background service. The input task is never started,
+     * the output zones (and the status bar) take the input row too and Ctrl+C shuts the console down.
+     * Modules still send and /commands still come from SenderHandle::run_command. Off by default. Takes effect when the console is started
+     */
+    pub fn enable_display_only(&mut self, enabled: bool) {
+        self.display_only = enabled;
+    }
+
+    /**
+     * Show the lines piped into stdin as messages of the stdin sender, other-program | dualZoneCli --ingest, see broker::ingest.
+     * The input zone reads the keys from the terminal then, without one to type on (or on a terminal that can only append)
+     * the console is display only. Off by default. Takes effect when the console is started
+     */
+    pub fn ingest_stdin(&mut self, enabled: bool) {
+        self.ingest = enabled;
+    }
+
+    /**
+     * Run with the input zone alone, for a console whose display is somewhere else (eg. a remote client).
+     * Input goes to the broker as always but nothing is drawn locally, the outputs go to redirect_output's receiver
+     * or are dropped, see relay_output. The mouse is not captured. Off by default. Takes effect when the console is started
+     */
+    pub fn enable_input_only(&mut self, enabled: bool) {
+        self.input_only = enabled;
+    }
+
+    /**
+     * Serve the console as a session on a Unix domain socket at path, see broker::session.
+     * Other terminals attach to it with dualZoneCli attach, and the console's own terminal can detach (/detach or dualZoneCli detach)
+     * while the console keeps running with its scrollback. Off (None) by default. Takes effect when the console is started
+     */
+    pub fn serve_session(&mut self, path: Option<std::path::PathBuf>) {
+        self.session = path;
+    }
+
+    /**
+     * Make clients authenticate before they attach to the session or detach the console, with a pre-shared token or a password per user.
+     * None (the default) lets anyone who can open the socket in, that is only the user running the console. See broker::auth::SessionAuth
+     */
+    pub fn set_session_auth(&mut self, auth: Option<SessionAuth>) {
+        self.session_auth = auth;
+    }
+
+    /**
+     * Serve the session on the network too, over TLS, for remote admin consoles (dualZoneCli attach --tls host:port).
+     * Needs the tls feature and session auth, without either the console says why it isn't serving. None (the default) stays local
+     */
+    pub fn serve_session_tls(&mut self, listen: Option<TlsListen>) {
+        self.session_tls = listen;
+    }
+
+    /**
+     * Show what is published on MQTT topics in the output zone, see broker::mqtt::MqttBridge. Needs the mqtt feature.
+     * None (the default) bridges nothing. Takes effect when the console is started
+     */
+    pub fn set_mqtt_bridge(&mut self, bridge: Option<MqttBridge>) {
+        self.mqtt = bridge;
+    }
+
+    /**
+     * Chat mode, for a terminal chat server of the clients attached to the session: what a user says is drawn as "<nick> text"
+     * instead of "[client] > text", see broker::chat::Chat. /nick, /me and /msg work either way. Off by default
+     */
+    pub fn set_chat_mode(&mut self, enabled: bool) {
+        self.chat.enabled = enabled;
+    }
+
+    /**
+     * Mirror topics to a Discord or Slack channel and take what is said there as input of its users, see broker::relay::ChatRelay.
+     * Needs the relay feature. None (the default) relays nothing. Takes effect when the console is started
+     */
+    pub fn set_chat_relay(&mut self, relay: Option<ChatRelay>) {
+        self.relay = relay;
+    }
+
+    /**
+     * Follow log files like tail -F, each line a message of the file it was appended to, see broker::tail::TailSource.
+     * None (the default) follows nothing. Takes effect when the console is started
+     */
+    pub fn follow_files(&mut self, source: Option<TailSource>) {
+        self.tail = source;
+    }
+
+    /**
+     * Share the SubConsoles with other consoles over a message bus, see broker::distribute::SubConsoleBridge.
+     * Hand the link to broker::distribute::run with a bridge, eg. tokio::spawn(run(NatsBridge::connect(url, subject).await?, link)).
+     *  ~ Every line of this console's SubConsoles is published, as coming from origin (default_origin() when None).
+     *  ~ The lines of the other consoles' SubConsoles come in from senders named subconsole@origin, so they can be
+     *    given zones and blacklisted like any module.
+     */
+    pub fn distribute_subconsoles(&mut self, origin: Option<String>) -> SubConsoleLink<T> {
+        let origin = origin.unwrap_or_else(distribute::default_origin);
+        let (tx, outgoing) = tokio::sync::mpsc::unbounded_channel();
+        self.bridge = Some((origin.clone(), tx));
+        SubConsoleLink { origin, outgoing, console: self.tx.clone() }
+    }
+
+    /**
+     * Distribute the SubConsoles over NATS when the console is started, see broker::distribute::NatsTarget. Needs the nats feature
+     */
+    pub fn set_nats_bridge(&mut self, target: Option<NatsTarget>) {
+        self.nats = target;
+    }
+
+    /**
+     * Serve the broker over gRPC when the console is started, for services outside the process to send messages, follow
+     * the output and list or ban senders (see proto/dualzone.proto and broker::grpc). Needs the grpc feature.
+     * None (the default) serves nothing
+     */
+    pub fn serve_grpc(&mut self, listen: Option<RemoteListen>) {
+        self.grpc = listen;
+    }
+
+    /**
+     * Serve the broker over HTTP when the console is started, POST /messages to send and GET /stream to follow the output
+     * as Server-Sent Events, for web dashboards (see broker::http). Needs the http feature.
+     * None (the default) serves nothing
+     */
+    pub fn serve_http(&mut self, listen: Option<RemoteListen>) {
+        self.http = listen;
+    }
+
+    /**
+     * Forward every rendered line to a syslog daemon when the console is started, as RFC 5424 over UDP (see broker::syslog::forward).
+     * Don't point it at a daemon that sends its lines on to the console's own syslog port, they would go round for ever
+     */
+    pub fn forward_syslog(&mut self, target: Option<SyslogTarget>) {
+        self.syslog_forward = target;
+    }
+
+    /**
+     * Every line the zones get from now on, for a sink like broker::syslog::forward,
+     * eg. tokio::spawn(forward(target, console.rendered_lines(), console.tx.clone())). Any number of sinks may take them
+     */
+    pub fn rendered_lines(&mut self) -> tokio::sync::mpsc::UnboundedReceiver<RenderedLine> {
+        let (tx, lines) = tokio::sync::mpsc::unbounded_channel();
+        self.line_sinks.push(tx);
+        lines
+    }
+
+    /**
+     * Show what is sent to a UDP syslog port in the output zone when the console is started, see broker::syslog::listen.
+     * None (the default) listens on nothing
+     */
+    pub fn serve_syslog(&mut self, addr: Option<std::net::SocketAddr>) {
+        self.syslog_listen = addr;
+    }
+
+    /**
+     * Forward every rendered line to journald when the console is started, with PRIORITY, SENDER and TOPIC fields
+     * (see broker::journald::entry). Needs the systemd feature and Linux, off by default
+     */
+    pub fn forward_to_journal(&mut self, enabled: bool) {
+        self.journal = enabled;
+    }
+
+    /**
+     * Export the broker's telemetry over OTLP to an endpoint when the console is started, a span per message from send to
+     * render and counters of what was dropped (see broker::otel::export). Needs the otel feature, None (the default) exports nothing
+     */
+    pub fn export_telemetry(&mut self, endpoint: Option<String>) {
+        self.otel = endpoint;
+    }
+
+    /**
+     * Post the rendered lines a sink matches to its URL when the console is started, with retry and backoff
+     * (see broker::webhook::forward). Any number of sinks may be added, needs the webhook feature
+     */
+    pub fn forward_to_webhook(&mut self, sink: WebhookSink) {
+        self.webhooks.push(sink);
+    }
+
+    /**
+     * Run a program and show what it prints, eg. console.spawn_process("cargo watch -x check"). Returns the name it goes by.
+     *  ~ The command line is split at whitespace, quotes keep words together (see broker::process::split_command), no shell is involved.
+     *  ~ Its stdout lines come from a sender named after the program (numbered while another one of that name runs), its stderr
+     *    lines are Warning and the exit status is the last line.
+     *  ~ /proc send, or /proc focus and then plain lines, write to its stdin, /proc kill ends it. It is killed with the console.
+     * Has to be called from within the tokio runtime
+     */
+    pub fn spawn_process(&mut self, command: &str) -> std::io::Result<String> {
+        let words = process::split_command(command);
+        let base = words.first().map_or(String::new(), |program| process::program_name(program));
+        self.spawn_words(base, command, &words)
+    }
+
+    /**
+     * Start a process that goes by base (or base-2, ... while one of that name runs), command is what /proc shows of it
+     */
+    pub(crate) fn spawn_words(&mut self, base: String, command: &str, words: &[String]) -> std::io::Result<String> {
+        let child = process::start(words)?;
+        self.processes.retain(|process| process.is_running());
+        let mut name = base.clone();
+        let mut number = 1;
+        while self.processes.iter().any(|process| process.name == name) {
+            number += 1;
+            name = format!("{}-{}", base, number);
+        }
+        let (input, requests) = tokio::sync::mpsc::unbounded_channel();
+        let pid = child.id();
+        tokio::spawn(process::supervise(child, self.new_sender(name.clone()), requests));
+        self.processes.push(Process { name: name.clone(), command: command.to_string(), pid, input });
+        Ok(name)
+    }
+
+    /**
+     * Let /sh run the commands policy allows, it runs nothing until this is called (or the [shell] table is configured).
+     * It is only ever run for the user at the terminal, never for a module, script or client of the session
+     */
+    pub fn set_shell_policy(&mut self, policy: ShellPolicy) {
+        self.shell = policy;
+    }
+
+    /**
+     * Which /commands page a result taller than the output zone, space shows the next page, Enter the next line and q drops the rest.
+     * Every command does (see broker::pager::PagerPolicy), a display only or input only console never pages
+     */
+    pub fn set_pager_policy(&mut self, policy: PagerPolicy) {
+        self.pager_policy = policy;
+    }
+
+    /**
+     * The processes started with spawn_process that still run
+     */
+    pub fn running_processes(&self) -> Vec<Process> {
+        self.processes.iter().filter(|process| process.is_running()).cloned().collect()
+    }
+
+    /**
+     * Hand something to a running process by name, false when there is none of that name
+     */
+    pub fn send_to_process(&self, name: &str, input: ProcessInput) -> bool {
+        self.processes.iter().any(|process| process.name == name && process.input.send(input.clone()).is_ok())
+    }
+
+    /**
+     * Send every output to the receiver instead of drawing it, in input only mode
+     */
+    pub fn redirect_output(&mut self) -> tokio::sync::mpsc::UnboundedReceiver<Output> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.output_redirect = Some(sender);
+        receiver
+    }
+
+    /**
+     * What the input zone needs to know about the console when it is started
+     */
+    pub fn input_options(&self) -> InputOptions {
+        InputOptions {
+            keymap: self.keymap.clone(),
+            zone_count: self.zones.len(),
+            arrangement: self.zone_arrangement.clone(),
+            status_bar: self.status_bar,
+            mouse: self.mouse && !self.input_only,
+            autosuggest: self.autosuggest,
+            history_limit: self.memory_limits.history.clone(),
+            memory: self.memory.clone(),
+            validators: self.validators.clone(),
+            highlighter: self.highlighter.clone(),
+            theme: self.screen_theme(),
+            eof: self.eof,
+            ingest: self.ingest,
+        }
+    }
+
+    /**
+     * Save the scrollback of every output zone to a file, eg. to attach to a bug report.
+     * The output zone writes it once it gets to it and reports how it went as a console message, same as /export
+     */
+    pub fn export_scrollback(&mut self, path: impl Into<std::path::PathBuf>, format: ExportFormat) {
+        self.ready.push_back(Output::Export { path: path.into(), format });
+    }
+
+    /**
+     * Record the session to a file that `dualZoneCli replay <file>` plays back, None stops recording.
+     * Takes effect when the output zone is started
+     */
+    pub fn record_session(&mut self, path: Option<std::path::PathBuf>) {
+        self.recording = path;
+    }
+
+    /**
+     * Put a tab bar over the main output zone, with a tab for every topic and module that sends to it and how many
+     * of their messages are unread. Switch with Alt+Left/Alt+Right or /tab <name>, see output::tabs::TabBar. Off by default
+     */
+    pub fn enable_tabs(&mut self, enabled: bool) {
+        self.tabs = enabled;
+    }
+
+    /**
+     * Draw with theme, see output::theme::Theme for the built-in ones. Takes effect when the zones are started
+     */
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /**
+     * The colors the terminal shows, None (the default) to go by COLORTERM and TERM, see ColorDepth::detect
+     */
+    pub fn set_color_depth(&mut self, depth: Option<ColorDepth>) {
+        self.color_depth = depth;
+    }
+
+    pub(crate) fn screen_depth(&self) -> ColorDepth {
+        self.color_depth.unwrap_or_else(ColorDepth::detect)
+    }
+
+    /**
+     * The theme with its colors fitted to the terminal, what the renderer and the input zone draw with
+     */
+    pub fn screen_theme(&self) -> Theme {
+        let mut theme = self.theme.fitted(&self.screen_depth());
+        theme.labels |= self.accessible;
+        theme
+    }
+
+    /**
+     * Accessibility mode, for screen readers and users who can't tell the colors apart or are bothered by movement.
+     *  ~ Messages get their priority's label, eg. [CRIT] or [WARN], instead of going by the theme's colors alone.
+     *  ~ Messages don't expire, they stay until newer lines push them up, so the output zone doesn't keep clearing.
+     *  ~ Spinners stand still instead of animating.
+     * Takes effect when the zones are started
+     */
+    pub fn enable_accessibility(&mut self, enabled: bool) {
+        self.accessible = enabled;
+    }
+
+    /**
+     * Show the one line status bar between the output and input zones
+     */
+    pub fn enable_status_bar(&mut self, enabled: bool) {
+        self.status_bar = enabled;
+    }
+
+    /**
+     * Set how long messages of one priority stay in the output zone, None keeps them until newer lines push them out
+     */
+    pub fn set_expiry(&mut self, priority: PriorityStatus, ttl: Option<tokio::time::Duration>) {
+        self.expiry.set(priority, ttl);
+    }
+
+    /**
+     * Set how long messages stay for every priority without a time of its own
+     */
+    pub fn set_default_expiry(&mut self, ttl: Option<tokio::time::Duration>) {
+        self.expiry.default = ttl;
+    }
+
+    /**
+     * Set how a message of one priority calls the user back while they are away, see output::notify::NotifyPolicy.
+     * A desktop alert needs the desktop-notify feature, without it only the bell rings
+     */
+    pub fn set_notify(&mut self, priority: PriorityStatus, alert: Alert) {
+        self.notify.set(priority, alert);
+    }
+
+    /**
+     * Set how every priority without an alert of its own calls the user back
+     */
+    pub fn set_default_notify(&mut self, alert: Alert) {
+        self.notify.default = alert;
+    }
+
+    /**
+     * Set how long without input before the user counts as away
+     */
+    pub fn set_away_after(&mut self, idle: tokio::time::Duration) {
+        self.notify.away_after = idle;
+    }
+
+    /**
+     * Whether the user is not watching the output, it is paused or nothing was typed for a while
+     */
+    pub(crate) fn is_away(&self) -> bool {
+        self.paused || self.last_input.elapsed() >= self.notify.away_after
+    }
+
+    /**
+     * Add a named output zone after the existing ones, a zone with the same name is replaced in place
+     */
+    pub fn add_zone(&mut self, spec: ZoneSpec) {
+        match self.zones.iter().position(|zone| zone.name == spec.name) {
+            Some(index) => self.zones[index] = spec,
+            None => self.zones.push(spec),
+        }
+    }
+
+    /**
+     * Remove a named output zone, the last zone can't be removed
+     */
+    pub fn remove_zone(&mut self, name: String) {
+        if self.zones.len() > 1 {
+            self.zones.retain(|zone| zone.name != name);
+        }
+    }
+
+    /**
+     * Choose whether the zones are stacked or side by side
+     */
+    pub fn set_zone_arrangement(&mut self, arrangement: Arrangement) {
+        self.zone_arrangement = arrangement;
+    }
+
+    /**
+     * Empty output zones matching the current zone definitions, for the renderer
+     */
+    pub fn zone_set(&self) -> ZoneSet {
+        let expiry = match self.accessible {
+            true => ExpiryPolicy::uniform(None),
+            false => self.expiry.clone(),
+        };
+        let mut zones = ZoneSet::from_specs(&self.zones, self.zone_arrangement.clone(), &expiry);
+        zones.set_memory_limit(&self.memory_limits.scrollback, &self.memory);
+        for window in self.windows.iter() {
+            zones.add_window(window, true);
+        }
+        if self.tabs {
+            zones.enable_tabs();
+        }
+        zones.highlights = self.highlights.fitted(&self.screen_depth());
+        zones
+    }
+
+    /**
+     * Dismiss a pinned message by id, or every pinned message when None
+     */
+    pub fn dismiss_pinned(&mut self, id: Option<u64>) {
+        self.ready.push_back(Output::Dismiss(id));
+    }
+
+    /**
+     * Subscribe to the metrics snapshots the broker publishes as it works, used by the Prometheus exporter
+     */
+    pub fn watch_metrics(&self) -> tokio::sync::watch::Receiver<Metrics> {
+        self.metrics_publisher.subscribe()
+    }
+
+    /**
+     * Push a fresh snapshot to the watchers, at most every 250ms so a busy broker is not slowed down by it
+     */
+    pub(crate) fn publish_metrics(&mut self) {
+        let now = tokio::time::Instant::now();
+        if now.duration_since(self.metrics_published) < tokio::time::Duration::from_millis(250) {
+            return;
+        }
+        self.metrics_published = now;
+        let _ = self.metrics_publisher.send(self.metrics());
+    }
+
+    /**
+     * Called by the output zone once a message has been drawn, with how long the draw took
+     */
+    pub fn record_render(&mut self, took: tokio::time::Duration) {
+        self.counters.record_render(took);
+        self.publish_metrics();
+    }
+
+    /**
+     * Plaintext name for an id, the built in senders are returned as they are
+     */
+    pub(crate) fn display_name(&self, id: &str) -> String {
+        match self.phonebook.get(id) {
+            Some(entry) => entry.name.clone(),
+            None => id.to_string(),
+        }
+    }
+
+    /**
+     * Copy of every message currently in the dead-letter queue, oldest first
+     */
+    pub fn inspect_dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.lock().unwrap().snapshot()
+    }
+
+    /**
+     * Send every dead letter back through the broker, they are checked again so a sender that is still blacklisted is rejected again.
+     * Returns how many were requeued. Once the console's queue is full the rest stay in the dead-letter queue, as they were,
+     * and the output zone says how many.
+     */
+    pub fn replay_dead_letters(&mut self) -> usize {
+        let mut replayed = 0;
+        loop {
+            let letter = match self.dead_letters.lock().unwrap().pop() {
+                Some(letter) => letter,
+                None => break,
+            };
+            let event = ConsoleEvent::Message(letter.message.clone());
+            if self.tx.try_send(event).is_err() {
+                let left = {
+                    let mut queue = self.dead_letters.lock().unwrap();
+                    queue.requeue(letter);
+                    queue.len()
+                };
+                let note = format!("[dlq] the console's queue is full, replayed {} and kept {} for later", replayed, left);
+                self.ready.push_back(Output::Line(Message::from_console(note)));
+                break;
+            }
+            replayed += 1;
+        }
+        replayed
+    }
+
+    /**
+     * Write the dead-letter queue to a file as JSON lines
+     */
+    pub fn export_dead_letters(&self, path: String) -> std::io::Result<usize> {
+        self.dead_letters.lock().unwrap().export(&path)
+    }
+
+    /**
+     * Change how many rejected messages are kept
+     */
+    pub fn set_dead_letter_capacity(&mut self, capacity: usize) {
+        self.memory_limits.dead_letters.max_entries = Some(capacity);
+        self.dead_letters.lock().unwrap().set_capacity(capacity);
+    }
+
+    /**
+     * Bound the buffers that grow with the session, for consoles that run for days.
+     * The dead-letter limit applies right away, the scrollback and history ones when the zones are started.
+     */
+    pub fn set_memory_limits(&mut self, limits: MemoryLimits) {
+        self.dead_letters.lock().unwrap().set_limit(limits.dead_letters.clone());
+        self.memory_limits = limits;
+    }
+
+    /**
+     * What the scrollback of every zone, the input history and the dead-letter queue hold right now, and how much was evicted
+     */
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            scrollback: self.memory.scrollback.usage(),
+            history: self.memory.history.usage(),
+            dead_letters: self.dead_letters.lock().unwrap().usage(),
+        }
+    }
+
+    /**
+     * Get just the names of everyone on the blacklist, in plaintext and sorted
+     */
+    pub fn get_blacklist_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.BlackListed.keys().map(|id| self.display_name(id)).collect();
+        names.sort();
+        names
+    }
+
+    /**
+     * show all names in the blacklist in plaintext
+     */
+    pub fn show_blacklist(&self) {
+        println!("Blacklisted Names: ");
+        for name in self.get_blacklist_names() {
+            println!("{}", name);
+        }
+    }
+
+    /**
+     * Get just the names of everyone on the authorized list, in plaintext and sorted
+     */
+    pub fn get_authorized_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.Authorized.keys().map(|id| self.display_name(id)).collect();
+        names.sort();
+        names
+    }
+    /**
+     * show all names in the authorized list in plaintext
+     */
+    pub fn show_authorized(&self) {
+        println!("Authorized Names: ");
+        for name in self.get_authorized_names() {
+            println!("{}", name);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SenderStatus {
+    Authorized,
+    BlackListed,
+    NotAuthorized,
+    NotInPhonebook,
+    //registered with a heartbeat and silent for longer than it, only ever in the phonebook
+    Stale,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PriorityStatus {
+    Urgent,
+    Critical,
+    Notice,
+    Warning,
+    Exception,
+    Delay,
+    Verbose, 
+    Normal, 
+    Ignore, 
+    Informational
+}
+
+impl PriorityStatus {
+    /**
+     * Ordering used by the filter level, higher is more severe
+     */
+    pub fn severity(&self) -> u8 {
+        match self {
+            PriorityStatus::Urgent => 9,
+            PriorityStatus::Critical => 8,
+            PriorityStatus::Exception => 7,
+            PriorityStatus::Warning => 6,
+            PriorityStatus::Notice => 5,
+            PriorityStatus::Delay => 4,
+            PriorityStatus::Normal => 3,
+            PriorityStatus::Informational => 2,
+            PriorityStatus::Verbose => 1,
+            PriorityStatus::Ignore => 0,
+        }
+    }
+
+    /**
+     * Parse a priority name, case insensitive
+     */
+    pub fn parse(name: &str) -> Option<PriorityStatus> {
+        match name.to_ascii_lowercase().as_str() {
+            "urgent" => Some(PriorityStatus::Urgent),
+            "critical" => Some(PriorityStatus::Critical),
+            "notice" => Some(PriorityStatus::Notice),
+            "warning" => Some(PriorityStatus::Warning),
+            "exception" => Some(PriorityStatus::Exception),
+            "delay" => Some(PriorityStatus::Delay),
+            "verbose" => Some(PriorityStatus::Verbose),
+            "normal" => Some(PriorityStatus::Normal),
+            "ignore" => Some(PriorityStatus::Ignore),
+            "informational" => Some(PriorityStatus::Informational),
+            _ => None,
+        }
+    }
+}
+
+/******************************************************************************************************************************************************************************/
+/**
+ * Where the output of a SubConsole is shown
+ *  ~ Shared: with everything else, wherever the zone bindings put it.
+ *  ~ Zone: in an output zone of its own, laid out after the other zones.
+ *  ~ Tab: in a window of its own kept off screen, /view <module> shows it in the main output zone and /view goes back.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubWindow {
+    Shared,
+    Zone,
+    Tab,
+}
+
+impl SubWindow {
+    pub fn parse(name: &str) -> Option<SubWindow> {
+        match name.to_ascii_lowercase().as_str() {
+            "shared" | "none" => Some(SubWindow::Shared),
+            "zone" => Some(SubWindow::Zone),
+            "tab" => Some(SubWindow::Tab),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * A module's own console, see Console::new_subconsole and SenderHandle::spawn_subconsole.
+ *  ~ Its output goes through the broker like any sender's, under the module's name, and can be given a window of its own
+ *    so it can be read apart from everything else.
+ *  ~ handle is a SenderHandle like new_sender() gives out, revoking or blacklisting the name applies to it.
+ *  ~ Its lines reach the SubConsoles of other consoles when they are distributed, see Console::distribute_subconsoles.
+ *  ~ inbox is where what is sent to the module alone arrives (Console::send_to, and SenderHandle::route to its name), see recv.
+ * ! Running a SubConsole as a broker of its own for the module's workspace is still WIP.
+ */
+#[derive(Debug, Getters)]
+pub struct SubConsole<T = ()> {
+    handle: SenderHandle<T>,
+    window: SubWindow,
+    inbox: Arc<Mutex<Receiver<Delivery>>>,
+}
+
+//not derived, that would want T: Clone
+impl<T> Clone for SubConsole<T> {
+    fn clone(&self) -> Self {
+        SubConsole { handle: self.handle.clone(), window: self.window.clone(), inbox: self.inbox.clone() }
+    }
+}
+
+impl<T> SubConsole<T> {
+    pub fn name(&self) -> &str {
+        self.handle.name()
+    }
+
+    pub async fn send(&self, message: String) -> Result<SendOutcome<T>, HandleError> {
+        self.handle.send(message).await
+    }
+
+    /**
+     * The next message sent to this SubConsole alone, None once the console is gone
+     */
+    pub async fn recv(&self) -> Option<Delivery> {
+        self.inbox.lock().await.recv().await
+    }
+
+    pub fn into_handle(self) -> SenderHandle<T> {
+        self.handle
+    }
 }
 
 /*******************************************************************************Processes**************************************************************************************/
\ No newline at end of file
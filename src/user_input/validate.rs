@@ -0,0 +1,84 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use std::fmt;
+use std::sync::Arc;
+use crate::user_input::commands::Command;
+
+/**
+ * A check of a submitted input line, Err is the reason shown under the prompt
+ */
+pub type Validator = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/**
+ * The validators of the input line, see Console::add_validator.
+ *  ~ They run in the input zone when Enter is pressed, in the order they were added, the first error wins.
+ *  ~ A rejected line is not sent anywhere, it stays in the editor with the error drawn under it until the next key.
+ *  ~ exit and quit are never checked, a validator can't lock the user in.
+ */
+#[derive(Clone, Default)]
+pub struct Validators {
+    list: Vec<Validator>,
+}
+
+impl Validators {
+    pub fn add(&mut self, validator: Validator) {
+        self.list.push(validator);
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn check(&self, line: &str) -> Result<(), String> {
+        if line.eq_ignore_ascii_case("exit") || line.eq_ignore_ascii_case("quit") {
+            return Ok(());
+        }
+        self.list.iter().try_for_each(|validator| validator(line))
+    }
+}
+
+impl fmt::Debug for Validators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Validators({})", self.list.len())
+    }
+}
+
+/**
+ * Lines must be JSON, /commands pass
+ */
+pub fn json() -> Validator {
+    Arc::new(|line: &str| {
+        if line.starts_with('/') {
+            return Ok(());
+        }
+        serde_json::from_str::<serde_json::Value>(line).map(|_| ()).map_err(|e| format!("must be valid JSON: {}", e))
+    })
+}
+
+/**
+ * /commands must be built in ones or one of extra. Plugin and script commands are only known to the broker,
+ * name the ones in use in extra (without the slash)
+ */
+pub fn known_command(extra: Vec<String>) -> Validator {
+    Arc::new(move |line: &str| match Command::parse(line) {
+        Some(Command::Unknown(_)) => {
+            let name = line.trim().trim_start_matches('/').split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+            match extra.iter().any(|known| known.eq_ignore_ascii_case(&name)) {
+                true => Ok(()),
+                false => Err(format!("unknown command: /{}", name)),
+            }
+        },
+        _ => Ok(()),
+    })
+}
@@ -0,0 +1,335 @@
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(unused)]
+
+//Internal
+use crate::user_input::editor::LineEditor;
+
+//External
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/**
+ * Shown in front of the prompt while vi mode is on, like readline's show-mode-in-prompt
+ */
+pub const VI_INSERT_INDICATOR: &str = "(ins) ";
+pub const VI_COMMAND_INDICATOR: &str = "(cmd) ";
+
+/**
+ * How the input line is edited
+ *  ~ Emacs is the keymap alone, the default.
+ *  ~ Vi adds a normal (command) mode on top of the keymap, Esc enters it and i, a, A, I, o, c... go back to inserting.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditMode {
+    Emacs,
+    Vi,
+}
+
+impl EditMode {
+    pub fn parse(name: &str) -> Option<EditMode> {
+        match name.to_ascii_lowercase().as_str() {
+            "emacs" => Some(EditMode::Emacs),
+            "vi" | "vim" => Some(EditMode::Vi),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ViMode {
+    Insert,
+    Normal,
+}
+
+/**
+ * The vi layer of the input line.
+ *  ~ Insert mode leaves every key to the keymap except Esc.
+ *  ~ Normal mode takes every printable key: motions h l 0 ^ $ w b e, counts, the operators d c y with a motion or doubled,
+ *    x X D C s S r p P, u to undo, and j k for the lines of a multi-line input and the history.
+ *    Keys with Ctrl or Alt, Enter and the arrows still go to the keymap.
+ *  ~ register holds what was last deleted or yanked, for p and P.
+ */
+#[derive(Debug, Clone)]
+pub struct ViState {
+    mode: ViMode,
+    count: Option<usize>,
+    operator: Option<char>,
+    register: String,
+}
+
+impl Default for ViState {
+    fn default() -> Self {
+        ViState { mode: ViMode::Insert, count: None, operator: None, register: String::new() }
+    }
+}
+
+impl ViState {
+    pub fn new() -> Self {
+        ViState::default()
+    }
+
+    pub fn is_normal(&self) -> bool {
+        self.mode == ViMode::Normal
+    }
+
+    pub fn indicator(&self) -> &'static str {
+        match self.mode {
+            ViMode::Insert => VI_INSERT_INDICATOR,
+            ViMode::Normal => VI_COMMAND_INDICATOR,
+        }
+    }
+
+    /**
+     * Back to insert mode with nothing pending, eg. after a line was submitted
+     */
+    pub fn reset(&mut self) {
+        self.mode = ViMode::Insert;
+        self.count = None;
+        self.operator = None;
+    }
+
+    /**
+     * Apply a key, returns false when the key is left to the keymap
+     */
+    pub fn key(&mut self, key: &KeyEvent, editor: &mut LineEditor) -> bool {
+        let plain = !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match (&self.mode, key.code) {
+            (ViMode::Insert, KeyCode::Esc) if plain => {
+                self.mode = ViMode::Normal;
+                editor.left();
+                true
+            },
+            (ViMode::Insert, _) => false,
+            (ViMode::Normal, KeyCode::Esc) => {
+                self.count = None;
+                self.operator = None;
+                true
+            },
+            (ViMode::Normal, KeyCode::Char(c)) if plain => {
+                self.command(c, editor);
+                if self.mode == ViMode::Normal {
+                    stay_on_line(editor);
+                }
+                true
+            },
+            //a submitted line starts the next one in insert mode
+            (ViMode::Normal, KeyCode::Enter) => {
+                self.reset();
+                false
+            },
+            (ViMode::Normal, _) => false,
+        }
+    }
+
+    fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1).max(1)
+    }
+
+    /**
+     * One key of a normal mode command
+     */
+    fn command(&mut self, c: char, editor: &mut LineEditor) {
+        //r takes the next key as the replacement
+        if self.operator == Some('r') {
+            self.operator = None;
+            let at = editor.cursor();
+            editor.right();
+            let end = editor.cursor();
+            if end > at {
+                editor.replace(at, end, &c.to_string());
+            }
+            return;
+        }
+        match c {
+            '1'..='9' => {
+                self.count = Some(self.count.unwrap_or(0) * 10 + c.to_digit(10).unwrap() as usize);
+                return;
+            },
+            '0' if self.count.is_some() => {
+                self.count = self.count.map(|count| count * 10);
+                return;
+            },
+            _ => {},
+        }
+        if let Some(operator) = self.operator.take() {
+            return self.operate(operator, c, editor);
+        }
+        let count = self.take_count();
+        if is_motion(c) {
+            for _ in 0..count {
+                motion(c, editor);
+            }
+            return;
+        }
+        match c {
+            'd' | 'c' | 'y' | 'r' => {
+                //the count waits for the motion
+                self.count = if count > 1 { Some(count) } else { None };
+                self.operator = Some(c);
+            },
+            'i' => self.mode = ViMode::Insert,
+            'a' => {
+                editor.right();
+                self.mode = ViMode::Insert;
+            },
+            'I' => {
+                editor.home();
+                self.mode = ViMode::Insert;
+            },
+            'A' => {
+                editor.end();
+                self.mode = ViMode::Insert;
+            },
+            'o' => {
+                editor.end();
+                editor.newline();
+                self.mode = ViMode::Insert;
+            },
+            'x' | 's' => {
+                let start = editor.cursor();
+                for _ in 0..count {
+                    editor.right();
+                }
+                let end = editor.cursor();
+                self.register = editor.remove(start, end);
+                if c == 's' {
+                    self.mode = ViMode::Insert;
+                }
+            },
+            'X' => {
+                let end = editor.cursor();
+                for _ in 0..count {
+                    editor.left();
+                }
+                let start = editor.cursor();
+                self.register = editor.remove(start, end);
+            },
+            'D' | 'C' => {
+                let start = editor.cursor();
+                let end = editor.buffer().len();
+                self.register = editor.remove(start, end);
+                if c == 'C' {
+                    self.mode = ViMode::Insert;
+                }
+            },
+            'S' => {
+                self.register = editor.buffer().to_string();
+                editor.clear();
+                self.mode = ViMode::Insert;
+            },
+            'p' | 'P' => {
+                if self.register.is_empty() {
+                    return;
+                }
+                if c == 'p' {
+                    editor.right();
+                }
+                let text = self.register.repeat(count);
+                editor.insert_str(&text);
+                editor.left();
+            },
+            'u' => {
+                for _ in 0..count {
+                    editor.undo();
+                }
+            },
+            'j' => {
+                for _ in 0..count {
+                    if !editor.down() {
+                        editor.history_next();
+                    }
+                }
+            },
+            'k' => {
+                for _ in 0..count {
+                    if !editor.up() {
+                        editor.history_previous();
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /**
+     * An operator followed by its motion, or doubled (dd, cc, yy) for the whole input
+     */
+    fn operate(&mut self, operator: char, c: char, editor: &mut LineEditor) {
+        let count = self.take_count();
+        let (start, end) = if c == operator {
+            (0, editor.buffer().len())
+        } else if is_motion(c) {
+            let start = editor.cursor();
+            //cw changes to the end of the word, like vi
+            let c = if operator == 'c' && c == 'w' && !at_space(editor) { 'e' } else { c };
+            for _ in 0..count {
+                //e lands on the last character of the word, as an operator target it includes it
+                if c == 'e' {
+                    let end = editor.word_end();
+                    editor.set_cursor(end);
+                } else {
+                    motion(c, editor);
+                }
+            }
+            let end = editor.cursor();
+            (start.min(end), start.max(end))
+        } else {
+            return;
+        };
+        match operator {
+            'y' => {
+                self.register = editor.slice(start, end).to_string();
+                editor.set_cursor(start);
+            },
+            _ => {
+                self.register = editor.remove(start, end);
+                if operator == 'c' {
+                    self.mode = ViMode::Insert;
+                }
+            },
+        }
+    }
+}
+
+fn is_motion(c: char) -> bool {
+    matches!(c, 'h' | 'l' | '0' | '^' | '$' | 'w' | 'b' | 'e')
+}
+
+/**
+ * Move the cursor by one motion
+ */
+fn motion(c: char, editor: &mut LineEditor) {
+    match c {
+        'h' => editor.left(),
+        'l' => editor.right(),
+        '0' | '^' => editor.home(),
+        '$' => editor.end(),
+        'w' => {
+            let next = editor.next_word_start();
+            editor.set_cursor(next);
+        },
+        'b' => {
+            let previous = editor.previous_word_start();
+            editor.set_cursor(previous);
+        },
+        //on the last character of the word, not past it
+        'e' => {
+            let end = editor.word_end();
+            editor.set_cursor(end.saturating_sub(1));
+        },
+        _ => {},
+    }
+}
+
+fn at_space(editor: &LineEditor) -> bool {
+    editor.buffer()[editor.cursor()..].chars().next().map(|c| c.is_whitespace()).unwrap_or(true)
+}
+
+/**
+ * In normal mode the cursor sits on a character, never past the last one
+ */
+fn stay_on_line(editor: &mut LineEditor) {
+    if !editor.is_empty() && editor.cursor() >= editor.buffer().len() {
+        editor.left();
+    }
+}